@@ -0,0 +1,101 @@
+use clap::Parser;
+use std::{fs::File, io::BufReader, path::PathBuf};
+use trace_recorder_parser::extract::{ExtractionRule, Extractor};
+use trace_recorder_parser::streaming::RecorderData;
+use trace_recorder_parser::types::UserEventChannel;
+use tracing::error;
+
+#[derive(Parser, Debug, Clone)]
+#[clap(name = "grep example", version, about = "Extract fields from the user events of streaming data with a regex", long_about = None)]
+pub struct Opts {
+    /// Only match the user events of this channel
+    #[clap(long)]
+    pub channel: Option<String>,
+
+    /// Event ID of the custom printf events
+    #[clap(long, value_parser=clap_num::maybe_hex::<u16>)]
+    pub custom_printf_event_id: Option<u16>,
+
+    /// Regex applied to the formatted user events, named capture groups become fields
+    #[clap(value_parser)]
+    pub pattern: String,
+
+    /// Path to streaming data file
+    #[clap(value_parser)]
+    pub path: PathBuf,
+}
+
+fn main() {
+    match do_main() {
+        Ok(()) => (),
+        Err(e) => {
+            eprintln!("{e}");
+            let mut cause = e.source();
+            while let Some(err) = cause {
+                eprintln!("Caused by: {err}");
+                cause = err.source();
+            }
+            std::process::exit(exitcode::SOFTWARE);
+        }
+    }
+}
+
+fn do_main() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::parse();
+
+    reset_signal_pipe_handler()?;
+
+    tracing_subscriber::fmt::init();
+
+    let mut rule = ExtractionRule::new("grep", &opts.pattern)?;
+    if let Some(channel) = &opts.channel {
+        rule = rule.with_channel(UserEventChannel::Custom(channel.clone()));
+    }
+    let extractor = Extractor::new().with_rule(rule);
+
+    let f = File::open(&opts.path)?;
+    let mut r = BufReader::new(f);
+
+    let mut rd = RecorderData::find(&mut r)?;
+
+    if let Some(custom_printf_event_id) = opts.custom_printf_event_id {
+        rd.set_custom_printf_event_id(custom_printf_event_id.into());
+    }
+
+    loop {
+        let event = match rd.read_event_auto_restart(&mut r) {
+            Ok(Some((_, ev))) => ev,
+            Ok(None) => break,
+            Err(e) => {
+                error!("{e}");
+                continue;
+            }
+        };
+
+        for record in extractor.extract_streaming_event(&event) {
+            print!("[{}]:[{}]", record.timestamp, record.channel);
+            for (name, value) in record.fields.iter() {
+                print!(" {name}={value}");
+            }
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+// Used to prevent panics on broken pipes.
+// See:
+//   https://github.com/rust-lang/rust/issues/46016#issuecomment-605624865
+fn reset_signal_pipe_handler() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(target_family = "unix")]
+    {
+        use nix::sys::signal;
+
+        unsafe {
+            signal::signal(signal::Signal::SIGPIPE, signal::SigHandler::SigDfl)?;
+        }
+    }
+
+    Ok(())
+}
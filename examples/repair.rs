@@ -0,0 +1,64 @@
+use clap::Parser;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use trace_recorder_parser::streaming::{repair, RepairOptions};
+
+#[derive(Parser, Debug, Clone)]
+#[clap(name = "repair example", version, about = "Copy the valid part of streaming data to a new file", long_about = None)]
+pub struct Opts {
+    /// Only keep the first session, dropping everything after a trace restart
+    #[clap(long)]
+    pub strip_restarts: bool,
+
+    /// Event ID of the custom printf events
+    #[clap(long, value_parser=clap_num::maybe_hex::<u16>)]
+    pub custom_printf_event_id: Option<u16>,
+
+    /// Path to streaming data file
+    #[clap(value_parser)]
+    pub path: PathBuf,
+
+    /// Path of the repaired file
+    #[clap(value_parser)]
+    pub output: PathBuf,
+}
+
+fn main() {
+    match do_main() {
+        Ok(()) => (),
+        Err(e) => {
+            eprintln!("{e}");
+            let mut cause = e.source();
+            while let Some(err) = cause {
+                eprintln!("Caused by: {err}");
+                cause = err.source();
+            }
+            std::process::exit(exitcode::SOFTWARE);
+        }
+    }
+}
+
+fn do_main() -> Result<(), Box<dyn std::error::Error>> {
+    let opts = Opts::parse();
+
+    tracing_subscriber::fmt::init();
+
+    let r = BufReader::new(File::open(&opts.path)?);
+    let w = BufWriter::new(File::create(&opts.output)?);
+    let options = RepairOptions {
+        strip_restarts: opts.strip_restarts,
+        custom_printf_event_id: opts.custom_printf_event_id.map(Into::into),
+    };
+    let stats = repair(r, w, options)?;
+
+    println!("Sessions: {}", stats.sessions);
+    println!("Events: {}", stats.events);
+    println!("Bytes written: {}", stats.bytes_written);
+    println!("Bytes dropped: {}", stats.bytes_dropped);
+    if let Some(reason) = &stats.stop_reason {
+        println!("Stopped: {reason}");
+    }
+
+    Ok(())
+}
@@ -2,7 +2,7 @@ use clap::Parser;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::PathBuf;
-use trace_recorder_parser::snapshot::RecorderData;
+use trace_recorder_parser::snapshot::{RecorderData, WindowedEvent};
 
 #[derive(Parser, Debug, Clone)]
 #[clap(name = "snapshot example", version, about = "Parse snapshot data from memory dump file", long_about = None)]
@@ -42,11 +42,20 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
     let desc = RecorderData::locate_and_parse(&mut f)?;
     println!("{desc:#?}");
 
+    println!("{}", desc.read_status(&mut f)?);
+
     if !opts.no_events {
         let mut observed_type_counters = BTreeMap::new();
 
-        for event in desc.events(&mut f)? {
-            let (event_type, event) = event?;
+        for event in desc.windowed_events(&mut f)? {
+            let WindowedEvent {
+                event_type,
+                event,
+                earliest_surviving,
+            } = event?;
+            if earliest_surviving {
+                println!("---- earliest surviving event ----");
+            }
             println!("{event_type} : {event}");
             *observed_type_counters.entry(event_type).or_insert(0) += 1_u64;
         }
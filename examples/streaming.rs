@@ -2,7 +2,10 @@ use clap::Parser;
 use std::collections::BTreeMap;
 use std::{fs::File, io::BufReader, path::PathBuf};
 use tabular::{Row, Table};
-use trace_recorder_parser::streaming::{event::TrackingEventCounter, Error, RecorderData};
+use trace_recorder_parser::streaming::{
+    event::{Event, TrackingEventCounter},
+    Query, RecorderData,
+};
 use tracing::{error, warn};
 
 #[derive(Parser, Debug, Clone)]
@@ -16,6 +19,10 @@ pub struct Opts {
     #[clap(long, value_parser=clap_num::maybe_hex::<u16>)]
     pub custom_printf_event_id: Option<u16>,
 
+    /// Only print events matching the query, e.g. 'type == QueueSend && ticks > 1000'
+    #[clap(long)]
+    pub filter: Option<Query>,
+
     /// Path to streaming data file
     #[clap(value_parser)]
     pub path: PathBuf,
@@ -62,24 +69,18 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
         let mut total_dropped_events = 0_u64;
 
         loop {
-            let (event_code, event) = match rd.read_event(&mut r) {
+            let (event_code, event) = match rd.read_event_auto_restart(&mut r) {
+                Ok(Some((_, Event::SessionBoundary(_)))) => {
+                    warn!("Detected a restarted trace stream");
+                    first_event_observed = false;
+                    continue;
+                }
                 Ok(Some((ec, ev))) => (ec, ev),
                 Ok(None) => break,
-                Err(e) => match e {
-                    Error::TraceRestarted(psf_start_word_endianness) => {
-                        warn!("Detected a restarted trace stream");
-                        first_event_observed = false;
-                        rd = RecorderData::read_with_endianness(psf_start_word_endianness, &mut r)?;
-                        if let Some(custom_printf_event_id) = opts.custom_printf_event_id {
-                            rd.set_custom_printf_event_id(custom_printf_event_id.into());
-                        }
-                        continue;
-                    }
-                    _ => {
-                        error!("{e}");
-                        continue;
-                    }
-                },
+                Err(e) => {
+                    error!("{e}");
+                    continue;
+                }
             };
 
             let dropped_events = if !first_event_observed {
@@ -91,7 +92,14 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
             };
 
             let event_type = event_code.event_type();
-            println!("{event_type} : {event} : {}", event.event_count());
+            if opts
+                .filter
+                .as_ref()
+                .map(|q| q.matches(event_code, &event))
+                .unwrap_or(true)
+            {
+                println!("{event_type} : {event} : {}", event.event_count());
+            }
             *observed_type_counters.entry(event_type).or_insert(0) += 1_u64;
             total_count += 1;
 
@@ -105,27 +113,7 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         println!("--------------------------------------------------------");
-        let mut table = Table::new("{:>}    {:>}    {:<}");
-        for (handle, entry) in rd.entry_table.entries().iter() {
-            let entry_class = if let Some(c) = entry.class {
-                c.to_string()
-            } else {
-                "NA".to_owned()
-            };
-            let entry_sym = if let Some(s) = &entry.symbol {
-                s.as_ref()
-            } else {
-                "NA"
-            };
-
-            table.add_row(
-                Row::new()
-                    .with_cell(handle)
-                    .with_cell(entry_class)
-                    .with_cell(entry_sym),
-            );
-        }
-        print!("{table}");
+        print!("{}", rd.entry_table.to_table_string());
 
         println!("--------------------------------------------------------");
         let mut table = Table::new("{:>}    {:>}    {:<}");
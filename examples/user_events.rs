@@ -1,7 +1,8 @@
 use clap::Parser;
 use std::{fs::File, io::BufReader, path::PathBuf};
-use trace_recorder_parser::streaming::{event::Event, Error, RecorderData};
-use tracing::{error, warn};
+use trace_recorder_parser::severity::{Severity, SeverityMap};
+use trace_recorder_parser::streaming::{event::Event, RecorderData};
+use tracing::error;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(name = "streaming example", version, about = "Parse streaming data from file", long_about = None)]
@@ -14,6 +15,19 @@ pub struct Opts {
     #[clap(long, value_parser=clap_num::maybe_hex::<u16>)]
     pub custom_printf_event_id: Option<u16>,
 
+    /// Declare the level of a channel, i.e. `--severity gpio=debug`.
+    /// The level of other channels is guessed from their name.
+    #[clap(long, value_parser = parse_severity)]
+    pub severity: Vec<(String, Severity)>,
+
+    /// Only print the events of channels at or above this level
+    #[clap(long)]
+    pub min_severity: Option<Severity>,
+
+    /// Color the events by the level of their channel
+    #[clap(long)]
+    pub color: bool,
+
     /// Path to streaming data file
     #[clap(value_parser)]
     pub path: PathBuf,
@@ -50,31 +64,39 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
         rd.set_custom_printf_event_id(custom_printf_event_id.into());
     }
 
+    let severities = opts
+        .severity
+        .iter()
+        .cloned()
+        .fold(SeverityMap::new().with_guessing(), |map, (ch, sev)| {
+            map.with_channel(ch, sev)
+        });
+
     loop {
-        let (_event_code, event) = match rd.read_event(&mut r) {
+        let (_event_code, event) = match rd.read_event_auto_restart(&mut r) {
             Ok(Some((ec, ev))) => (ec, ev),
             Ok(None) => break,
-            Err(e) => match e {
-                Error::TraceRestarted(psf_start_word_endianness) => {
-                    warn!("Detected a restarted trace stream");
-                    rd = RecorderData::read_with_endianness(psf_start_word_endianness, &mut r)?;
-                    if let Some(custom_printf_event_id) = opts.custom_printf_event_id {
-                        rd.set_custom_printf_event_id(custom_printf_event_id.into());
-                    }
-                    continue;
-                }
-                _ => {
-                    error!("{e}");
-                    continue;
-                }
-            },
+            Err(e) => {
+                error!("{e}");
+                continue;
+            }
         };
 
+        if let Some(min) = opts.min_severity {
+            if !severities.keep_streaming_event(&event, min) {
+                continue;
+            }
+        }
+
         if let Event::User(ev) = event {
-            if opts.debug {
-                println!("{ev:#?}");
+            let text = if opts.debug {
+                format!("{ev:#?}")
             } else {
-                println!("{ev}");
+                ev.to_string()
+            };
+            match severities.severity(&ev.channel) {
+                Some(severity) if opts.color => println!("{}", severity.paint(&text)),
+                _ => println!("{text}"),
             }
         }
     }
@@ -82,6 +104,14 @@ fn do_main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+fn parse_severity(s: &str) -> Result<(String, Severity), String> {
+    let (channel, severity) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Expected CHANNEL=LEVEL, got '{s}'"))?;
+    let severity = severity.parse().map_err(|e| format!("{e}"))?;
+    Ok((channel.to_owned(), severity))
+}
+
 // Used to prevent panics on broken pipes.
 // See:
 //   https://github.com/rust-lang/rust/issues/46016#issuecomment-605624865
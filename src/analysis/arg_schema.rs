@@ -0,0 +1,207 @@
+//! Inference of the argument signature of each user event channel
+//!
+//! Events logged to a channel usually share a single format string, or a few
+//! with the same arguments. The signature seen most often on a channel is
+//! taken as its schema and every event with different argument types or count
+//! is a deviation, which is often a format string that doesn't match the
+//! arguments passed by the firmware.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use crate::types::{Argument, ArgumentKind, FormatString, UserEventChannel};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The argument types of a user event, in order
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ArgumentSignature(pub Vec<ArgumentKind>);
+
+impl ArgumentSignature {
+    pub fn of(args: &[Argument]) -> Self {
+        Self(args.iter().map(Argument::kind).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ArgumentSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(")?;
+        for (i, kind) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{kind}")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// The events of a channel observed with one signature
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SignatureObservation {
+    pub signature: ArgumentSignature,
+    pub count: u64,
+    /// Timestamp and format string of the first event with this signature
+    pub first_timestamp: Timestamp,
+    pub first_format_string: FormatString,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ChannelSchema {
+    pub channel: UserEventChannel,
+    /// The signature observed most often, ties go to the one seen first
+    pub schema: SignatureObservation,
+    /// The other signatures observed, most frequent first
+    pub deviations: Vec<SignatureObservation>,
+}
+
+impl ChannelSchema {
+    pub fn is_stable(&self) -> bool {
+        self.deviations.is_empty()
+    }
+
+    /// Number of events that don't match the schema
+    pub fn deviating_events(&self) -> u64 {
+        self.deviations.iter().map(|d| d.count).sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArgumentSchemaAnalyzer {
+    /// Signatures of each channel, in order of first appearance
+    channels: BTreeMap<UserEventChannel, Vec<SignatureObservation>>,
+}
+
+impl ArgumentSchemaAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        if let streaming::event::Event::User(ev) = event {
+            self.observe(&ev.channel, &ev.format_string, &ev.args, ev.timestamp);
+        }
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        if let snapshot::event::Event::User(ev) = event {
+            self.observe(&ev.channel, &ev.format_string, &ev.args, ev.timestamp);
+        }
+    }
+
+    /// Whether the event matches the schema inferred so far for its channel,
+    /// events of a channel not seen yet always match
+    pub fn matches(&self, channel: &UserEventChannel, args: &[Argument]) -> bool {
+        match self.schema(channel) {
+            Some(schema) => schema.schema.signature == ArgumentSignature::of(args),
+            None => true,
+        }
+    }
+
+    pub fn schema(&self, channel: &UserEventChannel) -> Option<ChannelSchema> {
+        let observations = self.channels.get(channel)?;
+        Some(Self::infer(channel, observations))
+    }
+
+    /// The schema of every channel, in channel order
+    pub fn schemas(&self) -> Vec<ChannelSchema> {
+        self.channels
+            .iter()
+            .map(|(channel, observations)| Self::infer(channel, observations))
+            .collect()
+    }
+
+    fn observe(
+        &mut self,
+        channel: &UserEventChannel,
+        format_string: &FormatString,
+        args: &[Argument],
+        timestamp: Timestamp,
+    ) {
+        let signature = ArgumentSignature::of(args);
+        let observations = self.channels.entry(channel.clone()).or_default();
+        match observations.iter_mut().find(|o| o.signature == signature) {
+            Some(o) => o.count += 1,
+            None => observations.push(SignatureObservation {
+                signature,
+                count: 1,
+                first_timestamp: timestamp,
+                first_format_string: format_string.clone(),
+            }),
+        }
+    }
+
+    fn infer(channel: &UserEventChannel, observations: &[SignatureObservation]) -> ChannelSchema {
+        let mut sorted = observations.to_vec();
+        // Stable, so ties keep their order of first appearance
+        sorted.sort_by_key(|o| std::cmp::Reverse(o.count));
+        let schema = sorted.remove(0);
+        ChannelSchema {
+            channel: channel.clone(),
+            schema,
+            deviations: sorted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, UserEvent};
+    use crate::types::FormattedString;
+
+    fn user(channel: &str, format_string: &str, args: Vec<Argument>, t: u64) -> Event {
+        Event::User(UserEvent {
+            timestamp: Timestamp(t),
+            channel: UserEventChannel::Custom(channel.to_owned()),
+            format_string: FormatString(format_string.into()),
+            formatted_string: FormattedString(String::new()),
+            args: args.into(),
+        })
+    }
+
+    #[test]
+    fn flags_deviating_signatures() {
+        let events = [
+            user("speed", "rpm=%u", vec![Argument::U32(1)], 0),
+            user("speed", "rpm=%u", vec![Argument::U32(2)], 10),
+            user("speed", "rpm=%d", vec![Argument::I32(3)], 20),
+            user("speed", "rpm=%u", vec![Argument::U32(4)], 30),
+            user(
+                "temp",
+                "t=%d h=%d",
+                vec![Argument::I32(5), Argument::I32(6)],
+                40,
+            ),
+        ];
+        let mut a = ArgumentSchemaAnalyzer::new();
+        events.iter().for_each(|ev| a.process_snapshot_event(ev));
+
+        let speed = UserEventChannel::Custom("speed".to_owned());
+        let schema = a.schema(&speed).unwrap();
+        assert_eq!(
+            schema.schema.signature,
+            ArgumentSignature(vec![ArgumentKind::U32])
+        );
+        assert_eq!(schema.schema.count, 3);
+        assert_eq!(schema.deviating_events(), 1);
+        assert_eq!(schema.deviations[0].signature.to_string(), "(i32)");
+        assert_eq!(schema.deviations[0].first_timestamp, Timestamp(20));
+        assert_eq!(schema.deviations[0].first_format_string.as_str(), "rpm=%d");
+        assert!(a.matches(&speed, &[Argument::U32(7)]));
+        assert!(!a.matches(&speed, &[]));
+
+        let schemas = a.schemas();
+        assert_eq!(schemas.len(), 2);
+        assert!(schemas[1].is_stable());
+        assert_eq!(schemas[1].schema.signature.to_string(), "(i32, i32)");
+    }
+}
@@ -0,0 +1,231 @@
+//! Detection of operations performed in a context they don't belong to
+//!
+//! Blocking calls made while an ISR is running can't block and usually point to
+//! misinstrumented or buggy firmware. The `FromISR` API variants made by a task
+//! are suspicious as well, they don't trigger a context switch on their own.
+//!
+//! The running context is tracked from the task switch and ISR events, operations
+//! occurring before the first of them aren't checked.
+
+use crate::analysis::sched::{ContextStack, SchedEvent};
+use crate::analysis::StreamingClock;
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use derive_more::Display;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum ContextAnomalyKind {
+    /// A blocking call made from an ISR
+    #[display(fmt = "blocking call in ISR")]
+    BlockingInIsr,
+    /// An ISR-only call made from a task
+    #[display(fmt = "ISR call in task")]
+    IsrCallInTask,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]: {kind}: {operation} in {context}")]
+pub struct ContextAnomaly {
+    pub kind: ContextAnomalyKind,
+    /// Ticks since the first event for streaming data
+    pub timestamp: Timestamp,
+    /// The event type of the operation
+    pub operation: String,
+    /// The ISR or task the operation occurred in
+    pub context: ObjectHandle,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ContextAnalyzer {
+    context: ContextStack,
+    streaming_clock: StreamingClock,
+    anomalies: Vec<ContextAnomaly>,
+}
+
+impl ContextAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn anomalies(&self) -> &[ContextAnomaly] {
+        &self.anomalies
+    }
+
+    pub fn into_anomalies(self) -> Vec<ContextAnomaly> {
+        self.anomalies
+    }
+
+    pub fn process_streaming_event(
+        &mut self,
+        event_code: streaming::event::EventCode,
+        event: &streaming::event::Event,
+    ) {
+        use streaming::event::EventType::*;
+
+        let t = self.streaming_clock.time(event.timestamp());
+        if let Some(sched_event) = SchedEvent::from_streaming(event) {
+            self.context.apply(&sched_event);
+        }
+        let event_type = event_code.event_type();
+        let kind = match event_type {
+            TaskNotifyWaitBlock
+            | QueueSendBlock
+            | QueueReceiveBlock
+            | QueuePeekBlock
+            | QueueSendFrontBlock
+            | MutexGiveBlock
+            | MutexTakeBlock
+            | MutexTakeRecursiveBlock
+            | SemaphoreGiveBlock
+            | SemaphoreTakeBlock
+            | SemaphorePeekBlock
+            | EventGroupSyncBlock
+            | EventGroupWaitBitsBlock
+            | StreamBufferSendBlock
+            | StreamBufferReceiveBlock
+            | MessageBufferSendBlock
+            | MessageBufferReceiveBlock
+            | TaskDelay
+            | TaskDelayUntil => ContextAnomalyKind::BlockingInIsr,
+            TaskResumeFromIsr
+            | TaskNotifyFromIsr
+            | QueueSendFromIsr
+            | QueueSendFromIsrFailed
+            | QueueReceiveFromIsr
+            | QueueReceiveFromIsrFailed
+            | QueueSendFrontFromIsr
+            | SemaphoreGiveFromIsr
+            | SemaphoreGiveFromIsrFailed
+            | SemaphoreTakeFromIsr
+            | SemaphoreTakeFromIsrFailed
+            | EventGroupClearBitsFromIsr
+            | EventGroupSetBitsFromIsr
+            | StreamBufferSendFromIsr
+            | StreamBufferSendFromIsrFailed
+            | StreamBufferReceiveFromIsr
+            | StreamBufferReceiveFromIsrFailed
+            | MessageBufferSendFromIsr
+            | MessageBufferSendFromIsrFailed
+            | MessageBufferReceiveFromIsr
+            | MessageBufferReceiveFromIsrFailed => ContextAnomalyKind::IsrCallInTask,
+            _ => return,
+        };
+        self.check(kind, t, event_type.to_string());
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        use snapshot::event::{Event, EventType};
+
+        if let Some(sched_event) = SchedEvent::from_snapshot(event) {
+            self.context.apply(&sched_event);
+        }
+        let event_type = match event {
+            Event::PendFuncCallFromIsr(_) => EventType::PendFuncCallFromIsr,
+            Event::PendFuncCallFromIsrFailed(_) => EventType::PendFuncCallFromIsrFailed,
+            Event::Unknown(_, record) => EventType::from(record.event_code()),
+            _ => return,
+        };
+        let kind = match event_type {
+            EventType::ReceiveBlock(_)
+            | EventType::SendBlock(_)
+            | EventType::TaskDelay
+            | EventType::TaskDelayUntil
+            | EventType::EventGroupSyncBlock
+            | EventType::EventGroupWaitBitsBlock
+            | EventType::TaskNotifyTakeBlock
+            | EventType::TaskNotifyWaitBlock
+            | EventType::QueuePeekBlock
+            | EventType::SemaphortPeekBlock
+            | EventType::MutexPeekBlock => ContextAnomalyKind::BlockingInIsr,
+            EventType::SendFromIsr(_)
+            | EventType::ReceiveFromIsr(_)
+            | EventType::SendFromIsrFailed(_)
+            | EventType::ReceiveFromIsrFailed(_)
+            | EventType::TaskResumeFromIsr
+            | EventType::PendFuncCallFromIsr
+            | EventType::PendFuncCallFromIsrFailed => ContextAnomalyKind::IsrCallInTask,
+            _ => return,
+        };
+        self.check(kind, event.timestamp(), event_type.to_string());
+    }
+
+    fn check(&mut self, kind: ContextAnomalyKind, timestamp: Timestamp, operation: String) {
+        let context = match kind {
+            ContextAnomalyKind::BlockingInIsr => self.context.isrs.last().copied(),
+            ContextAnomalyKind::IsrCallInTask if self.context.isrs.is_empty() => self.context.task,
+            ContextAnomalyKind::IsrCallInTask => None,
+        };
+        if let Some(context) = context {
+            self.anomalies.push(ContextAnomaly {
+                kind,
+                timestamp,
+                operation,
+                context,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, EventCode, EventRecord, EventType, IsrEvent, TaskEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{ObjectName, Priority};
+
+    fn unknown(event_type: EventType, t: u64) -> Event {
+        Event::Unknown(
+            Timestamp(t),
+            EventRecord::new([u8::from(EventCode::from(event_type)), 0, 0, 0]),
+        )
+    }
+
+    #[test]
+    fn snapshot_anomalies() {
+        let task = ObjectHandle::new(1).unwrap();
+        let isr = ObjectHandle::new(2).unwrap();
+        let events = [
+            // Unknown context
+            unknown(EventType::TaskDelay, 5),
+            Event::TaskBegin(TaskEvent {
+                handle: task,
+                name: ObjectName("t".into()),
+                state: TaskState::Active,
+                priority: Priority(1),
+                timestamp: Timestamp(10),
+            }),
+            unknown(EventType::TaskDelay, 15),
+            unknown(EventType::TaskResumeFromIsr, 20),
+            Event::IsrBegin(IsrEvent {
+                handle: isr,
+                name: ObjectName("isr".into()),
+                priority: Priority(5),
+                timestamp: Timestamp(30),
+            }),
+            unknown(EventType::TaskResumeFromIsr, 35),
+            unknown(EventType::TaskDelay, 40),
+        ];
+        let mut a = ContextAnalyzer::new();
+        events.iter().for_each(|ev| a.process_snapshot_event(ev));
+
+        assert_eq!(
+            a.anomalies(),
+            &[
+                ContextAnomaly {
+                    kind: ContextAnomalyKind::IsrCallInTask,
+                    timestamp: Timestamp(20),
+                    operation: EventType::TaskResumeFromIsr.to_string(),
+                    context: task,
+                },
+                ContextAnomaly {
+                    kind: ContextAnomalyKind::BlockingInIsr,
+                    timestamp: Timestamp(40),
+                    operation: EventType::TaskDelay.to_string(),
+                    context: isr,
+                },
+            ]
+        );
+    }
+}
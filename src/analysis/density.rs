@@ -0,0 +1,136 @@
+//! Event counts per type bucketed into fixed tick windows, e.g. to draw activity
+//! sparklines, computed in a single pass over the trace
+//!
+//! Streaming buckets start at the first event processed, snapshot buckets at the
+//! snapshot's zero timestamp.
+
+use crate::analysis::StreamingClock;
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug)]
+pub struct EventDensity<T> {
+    bucket_ticks: u64,
+    num_buckets: usize,
+    counts: BTreeMap<T, Vec<u64>>,
+    clock: StreamingClock,
+}
+
+impl<T: Copy + Ord> EventDensity<T> {
+    /// Count events in windows of `bucket_ticks` ticks, at least one
+    pub fn new(bucket_ticks: u64) -> Self {
+        Self {
+            bucket_ticks: bucket_ticks.max(1),
+            num_buckets: 0,
+            counts: BTreeMap::new(),
+            clock: StreamingClock::default(),
+        }
+    }
+
+    pub fn bucket_ticks(&self) -> u64 {
+        self.bucket_ticks
+    }
+
+    /// Number of buckets up to the one of the latest event
+    pub fn num_buckets(&self) -> usize {
+        self.num_buckets
+    }
+
+    /// Start of the bucket, in ticks
+    pub fn bucket_start(&self, bucket: usize) -> u64 {
+        bucket as u64 * self.bucket_ticks
+    }
+
+    /// The event types seen so far
+    pub fn types(&self) -> impl Iterator<Item = T> + '_ {
+        self.counts.keys().copied()
+    }
+
+    /// Event counts of the type for each bucket, `num_buckets` long
+    pub fn counts(&self, event_type: T) -> Vec<u64> {
+        let mut counts = self.counts.get(&event_type).cloned().unwrap_or_default();
+        counts.resize(self.num_buckets, 0);
+        counts
+    }
+
+    /// Event counts of all the types for each bucket, `num_buckets` long
+    pub fn totals(&self) -> Vec<u64> {
+        let mut totals = vec![0; self.num_buckets];
+        for counts in self.counts.values() {
+            totals.iter_mut().zip(counts).for_each(|(t, c)| *t += c);
+        }
+        totals
+    }
+
+    pub fn record(&mut self, event_type: T, timestamp: Timestamp) {
+        let bucket = usize::try_from(timestamp.ticks() / self.bucket_ticks).unwrap_or(usize::MAX);
+        let counts = self.counts.entry(event_type).or_default();
+        if counts.len() <= bucket {
+            counts.resize(bucket + 1, 0);
+        }
+        counts[bucket] += 1;
+        self.num_buckets = self.num_buckets.max(bucket + 1);
+    }
+}
+
+impl EventDensity<streaming::event::EventType> {
+    pub fn process_streaming_event(
+        &mut self,
+        event_code: streaming::event::EventCode,
+        event: &streaming::event::Event,
+    ) {
+        let t = self.clock.time(event.timestamp());
+        self.record(event_code.event_type(), t);
+    }
+}
+
+impl EventDensity<snapshot::event::EventType> {
+    pub fn process_snapshot_event(
+        &mut self,
+        event_type: snapshot::event::EventType,
+        event: &snapshot::event::Event,
+    ) {
+        self.record(event_type, event.timestamp());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, Event, EventCode, EventCount, EventId, EventType};
+
+    fn event(et: EventType, t: u64) -> (EventCode, Event) {
+        let code = EventCode(u16::from(EventId::from(et)).into());
+        let ev = BaseEvent {
+            code,
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(t),
+            parameters: [0; 15],
+        };
+        (code, Event::Unknown(ev))
+    }
+
+    #[test]
+    fn streaming_buckets() {
+        let events = [
+            event(EventType::TaskDelay, 1000),
+            event(EventType::TaskDelay, 1005),
+            event(EventType::TaskSuspend, 1012),
+            event(EventType::TaskDelay, 1045),
+        ];
+        let mut density = EventDensity::new(10);
+        events
+            .iter()
+            .for_each(|(code, ev)| density.process_streaming_event(*code, ev));
+
+        assert_eq!(density.num_buckets(), 5);
+        assert_eq!(density.counts(EventType::TaskDelay), vec![2, 0, 0, 0, 1]);
+        assert_eq!(density.counts(EventType::TaskSuspend), vec![0, 1, 0, 0, 0]);
+        assert_eq!(density.counts(EventType::TaskResume), vec![0; 5]);
+        assert_eq!(density.totals(), vec![2, 1, 0, 0, 1]);
+        assert_eq!(density.bucket_start(4), 40);
+    }
+}
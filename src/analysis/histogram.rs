@@ -0,0 +1,216 @@
+//! Log-bucketed histograms of durations or other unsigned quantities
+//!
+//! Bucket 0 holds zeros and bucket `i` holds the values in `[2^(i-1), 2^i)`,
+//! so the relative error of a quantile is at most a factor of two, while
+//! the exact count, sum, minimum and maximum are kept alongside.
+
+use std::fmt;
+
+const NUM_BUCKETS: usize = u64::BITS as usize + 1;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Bucket {
+    /// Inclusive
+    pub lower: u64,
+    /// Inclusive
+    pub upper: u64,
+    pub count: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Histogram {
+    buckets: [u64; NUM_BUCKETS],
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; NUM_BUCKETS],
+            count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1)
+    }
+
+    /// Record `n` occurrences of `value`
+    pub fn record_n(&mut self, value: u64, n: u64) {
+        if n == 0 {
+            return;
+        }
+        self.buckets[bucket_index(value)] += n;
+        self.count += n;
+        self.sum += u128::from(value) * u128::from(n);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Add the values recorded by `other` to this histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.is_empty() {
+            return;
+        }
+        for (b, o) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *b += o;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn sum(&self) -> u128 {
+        self.sum
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        (!self.is_empty()).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        (!self.is_empty()).then_some(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.sum as f64 / self.count as f64)
+    }
+
+    /// An upper bound of the value below which the fraction `q` (clamped to `[0, 1]`)
+    /// of the recorded values fall, never more than the maximum
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(bucket_bounds(i).1.min(self.max).max(self.min));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// The non-empty buckets, in increasing order
+    pub fn buckets(&self) -> impl Iterator<Item = Bucket> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count != 0)
+            .map(|(i, count)| {
+                let (lower, upper) = bucket_bounds(i);
+                Bucket {
+                    lower,
+                    upper,
+                    count: *count,
+                }
+            })
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<u64> for Histogram {
+    fn extend<T: IntoIterator<Item = u64>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|v| self.record(v))
+    }
+}
+
+impl FromIterator<u64> for Histogram {
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        let mut h = Self::new();
+        h.extend(iter);
+        h
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min(), self.max(), self.mean()) {
+            (Some(min), Some(max), Some(mean)) => write!(
+                f,
+                "count={}, min={min}, mean={mean:.1}, max={max}",
+                self.count
+            ),
+            _ => write!(f, "count=0"),
+        }
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    (u64::BITS - value.leading_zeros()) as usize
+}
+
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    match index {
+        0 => (0, 0),
+        _ => {
+            let lower = 1_u64 << (index - 1);
+            (lower, lower.wrapping_shl(1).wrapping_sub(1).max(lower))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets_and_quantiles() {
+        let h: Histogram = [0, 1, 2, 3, 4, 100, 1000].into_iter().collect();
+        assert_eq!(h.count(), 7);
+        assert_eq!(h.min(), Some(0));
+        assert_eq!(h.max(), Some(1000));
+        assert_eq!(h.sum(), 1110);
+        let buckets: Vec<_> = h.buckets().map(|b| (b.lower, b.upper, b.count)).collect();
+        assert_eq!(
+            buckets,
+            vec![
+                (0, 0, 1),
+                (1, 1, 1),
+                (2, 3, 2),
+                (4, 7, 1),
+                (64, 127, 1),
+                (512, 1023, 1)
+            ]
+        );
+        assert_eq!(h.quantile(0.5), Some(3));
+        assert_eq!(h.quantile(0.0), Some(0));
+        assert_eq!(h.quantile(1.0), Some(1000));
+        assert_eq!(bucket_bounds(bucket_index(u64::MAX)), (1 << 63, u64::MAX));
+        assert_eq!(Histogram::new().quantile(0.5), None);
+    }
+
+    #[test]
+    fn merge() {
+        let mut a: Histogram = [5, 6].into_iter().collect();
+        let b: Histogram = [1, 200].into_iter().collect();
+        a.merge(&b);
+        a.merge(&Histogram::new());
+        assert_eq!(a, [1, 5, 6, 200].into_iter().collect());
+        assert_eq!(a.min(), Some(1));
+        assert_eq!(a.max(), Some(200));
+        assert_eq!(a.mean(), Some(53.0));
+    }
+}
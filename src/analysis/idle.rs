@@ -0,0 +1,253 @@
+//! Idle and low-power (tickless idle) accounting
+//!
+//! Idle time is the time the idle task spends running, recognized by name.
+//! Low-power time spans the snapshot `LowPowerBegin`/`LowPowerEnd` events, usually
+//! emitted by the tickless idle hooks while the idle task runs.
+//! The streaming protocol has no standard low-power events, their event IDs can be
+//! provided when the recorder port defines them.
+
+use crate::analysis::{Histogram, Interval, StreamingClock};
+use crate::snapshot;
+use crate::streaming;
+use crate::streaming::event::EventId;
+use crate::time::Timestamp;
+use crate::types::{ObjectHandle, IDLE_TASK_NAME};
+use std::collections::BTreeSet;
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct IdleStats {
+    /// Ticks between the first and the last event processed
+    pub observed: u64,
+    /// Ticks spent running the idle task, including low-power periods
+    pub idle: u64,
+    /// Ticks spent in low-power mode
+    pub low_power: u64,
+    pub longest_low_power: Option<Interval>,
+    /// Durations of the completed low-power periods, in ticks
+    pub low_power_periods: Histogram,
+}
+
+impl IdleStats {
+    pub fn idle_percentage(&self) -> Option<f64> {
+        self.percentage(self.idle)
+    }
+
+    pub fn low_power_percentage(&self) -> Option<f64> {
+        self.percentage(self.low_power)
+    }
+
+    fn percentage(&self, ticks: u64) -> Option<f64> {
+        (self.observed != 0).then(|| 100.0 * ticks as f64 / self.observed as f64)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IdleAnalyzer {
+    idle_task_name: String,
+    streaming_low_power_ids: Option<(EventId, EventId)>,
+    idle_tasks: BTreeSet<ObjectHandle>,
+    idle_since: Option<Timestamp>,
+    low_power_since: Option<Timestamp>,
+    first: Option<Timestamp>,
+    last: Timestamp,
+    streaming_clock: StreamingClock,
+    stats: IdleStats,
+}
+
+impl IdleAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            idle_task_name: IDLE_TASK_NAME.to_owned(),
+            streaming_low_power_ids: None,
+            idle_tasks: BTreeSet::new(),
+            idle_since: None,
+            low_power_since: None,
+            first: None,
+            last: Timestamp::zero(),
+            streaming_clock: StreamingClock::default(),
+            stats: IdleStats::default(),
+        }
+    }
+
+    /// Use a different `configIDLE_TASK_NAME`
+    pub fn with_idle_task_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.idle_task_name = name.into();
+        self
+    }
+
+    /// Recognize streaming events with the given IDs as the beginning and end
+    /// of low-power periods
+    pub fn with_streaming_low_power_event_ids(mut self, begin: EventId, end: EventId) -> Self {
+        self.streaming_low_power_ids = Some((begin, end));
+        self
+    }
+
+    /// The statistics so far, periods still open count up to the last event
+    /// but aren't part of the low-power histogram
+    pub fn stats(&self) -> IdleStats {
+        let mut stats = self.stats.clone();
+        if let Some(start) = self.idle_since {
+            stats.idle += self.last.ticks().saturating_sub(start.ticks());
+        }
+        if let Some(start) = self.low_power_since {
+            stats.low_power += self.last.ticks().saturating_sub(start.ticks());
+        }
+        stats
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        use streaming::event::{Event, EventType};
+
+        let t = self.streaming_clock.time(event.timestamp());
+        self.advance(t);
+        match event {
+            Event::TraceStart(ev) => self.switch(ev.current_task_handle, Some(&ev.current_task), t),
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.switch(ev.handle, Some(&ev.name), t)
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.switch_out(t),
+            Event::Unknown(ev) => {
+                let id = ev.code.event_id();
+                match self.streaming_low_power_ids {
+                    Some((begin, _)) if id == begin => self.low_power_begin(t),
+                    Some((_, end)) if id == end => self.low_power_end(t),
+                    // Task begin events are returned as unknown events
+                    _ if ev.code.event_type() == EventType::TaskSwitchTaskBegin => {
+                        match ObjectHandle::new(ev.parameters[0]) {
+                            Some(handle) => self.switch(handle, None, t),
+                            None => self.switch_out(t),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        use snapshot::event::Event;
+
+        let t = event.timestamp();
+        self.advance(t);
+        match event {
+            Event::TaskBegin(ev) | Event::TaskResume(ev) => {
+                self.switch(ev.handle, Some(&ev.name), t)
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.switch_out(t),
+            Event::LowPowerBegin(_) => self.low_power_begin(t),
+            Event::LowPowerEnd(_) => self.low_power_end(t),
+            _ => (),
+        }
+    }
+
+    fn advance(&mut self, t: Timestamp) {
+        let first = *self.first.get_or_insert(t);
+        self.last = self.last.max(t);
+        self.stats.observed = self.last.ticks().saturating_sub(first.ticks());
+    }
+
+    fn switch(&mut self, handle: ObjectHandle, name: Option<&str>, t: Timestamp) {
+        if name == Some(self.idle_task_name.as_str()) {
+            self.idle_tasks.insert(handle);
+        }
+        if self.idle_tasks.contains(&handle) {
+            self.idle_since.get_or_insert(t);
+        } else {
+            self.switch_out(t);
+        }
+    }
+
+    fn switch_out(&mut self, t: Timestamp) {
+        if let Some(start) = self.idle_since.take() {
+            self.stats.idle += t.ticks().saturating_sub(start.ticks());
+        }
+    }
+
+    fn low_power_begin(&mut self, t: Timestamp) {
+        self.low_power_since.get_or_insert(t);
+    }
+
+    fn low_power_end(&mut self, t: Timestamp) {
+        let Some(start) = self.low_power_since.take() else {
+            return;
+        };
+        let period = Interval { start, end: t };
+        let stats = &mut self.stats;
+        stats.low_power += period.duration();
+        stats.low_power_periods.record(period.duration());
+        if stats
+            .longest_low_power
+            .is_none_or(|p| period.duration() > p.duration())
+        {
+            stats.longest_low_power = Some(period);
+        }
+    }
+}
+
+impl Default for IdleAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, LowPowerEvent, TaskEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{ObjectName, Priority};
+
+    fn begin(handle: u16, name: &str, t: u64) -> Event {
+        Event::TaskBegin(TaskEvent {
+            handle: ObjectHandle::new(handle.into()).unwrap(),
+            name: ObjectName(name.into()),
+            state: TaskState::Active,
+            priority: Priority(0),
+            timestamp: Timestamp(t),
+        })
+    }
+
+    fn low_power(begin: bool, t: u64) -> Event {
+        let ev = LowPowerEvent {
+            timestamp: Timestamp(t),
+        };
+        if begin {
+            Event::LowPowerBegin(ev)
+        } else {
+            Event::LowPowerEnd(ev)
+        }
+    }
+
+    #[test]
+    fn snapshot_idle_and_low_power() {
+        let events = vec![
+            begin(1, "worker", 0),
+            begin(2, "IDLE", 100),
+            low_power(true, 120),
+            low_power(false, 170),
+            begin(1, "worker", 200),
+            begin(2, "IDLE", 300),
+            low_power(true, 310),
+            low_power(false, 330),
+            begin(1, "worker", 400),
+        ];
+        let mut a = IdleAnalyzer::new();
+        events.iter().for_each(|ev| a.process_snapshot_event(ev));
+
+        let stats = a.stats();
+        assert_eq!(stats.observed, 400);
+        assert_eq!(stats.idle, 200);
+        assert_eq!(stats.low_power, 70);
+        assert_eq!(stats.idle_percentage(), Some(50.0));
+        assert_eq!(stats.low_power_percentage(), Some(17.5));
+        assert_eq!(
+            stats.longest_low_power,
+            Some(Interval {
+                start: Timestamp(120),
+                end: Timestamp(170)
+            })
+        );
+        assert_eq!(stats.low_power_periods.count(), 2);
+    }
+}
@@ -0,0 +1,260 @@
+//! Lock-order analysis of the streaming mutex events
+//!
+//! Every mutex a task takes, or blocks on, while holding other mutexes adds an
+//! edge from each held mutex to the new one in the lock-order graph.
+//! A cycle in the graph means the mutexes were taken in conflicting orders, which
+//! can deadlock when the tasks involved interleave badly, even if the trace didn't
+//! deadlock. The first occurrence of every edge is kept as its witness.
+
+use crate::analysis::sched::{ContextStack, SchedEvent};
+use crate::analysis::StreamingClock;
+use crate::streaming::event::{Event, EventCount};
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A mutex taken while holding another one
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct LockOrderEdge {
+    /// The mutex held
+    pub held: ObjectHandle,
+    /// The mutex taken, or blocked on
+    pub taken: ObjectHandle,
+    pub task: ObjectHandle,
+    /// When `held` was taken, ticks since the first event
+    pub held_since: Timestamp,
+    pub timestamp: Timestamp,
+    pub event_count: EventCount,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PotentialDeadlock {
+    /// The mutexes of the cycle, each taken while holding the previous one,
+    /// the first one while holding the last one
+    pub mutexes: Vec<ObjectHandle>,
+    /// One witness per edge of the cycle
+    pub witnesses: Vec<LockOrderEdge>,
+}
+
+impl PotentialDeadlock {
+    /// Whether the conflicting orders were all seen in the same task, which can't
+    /// deadlock by itself
+    pub fn is_single_task(&self) -> bool {
+        self.witnesses.windows(2).all(|w| w[0].task == w[1].task)
+    }
+}
+
+/// A mutex held by a task
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Held {
+    mutex: ObjectHandle,
+    since: Timestamp,
+    /// Takes not given back yet, recursive mutexes are released by the last give
+    depth: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LockOrderAnalyzer {
+    context: ContextStack,
+    clock: StreamingClock,
+    /// The mutexes each task holds, in the order they were taken
+    held: BTreeMap<ObjectHandle, Vec<Held>>,
+    edges: BTreeMap<(ObjectHandle, ObjectHandle), LockOrderEdge>,
+}
+
+impl LockOrderAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &Event) {
+        let t = self.clock.time(event.timestamp());
+        if let Some(sched_event) = SchedEvent::from_streaming(event) {
+            self.context.apply(&sched_event);
+        }
+        // Mutexes are only used by tasks
+        let Some(task) = self.context.task.filter(|_| self.context.isrs.is_empty()) else {
+            return;
+        };
+        match event {
+            Event::MutexTake(ev) | Event::MutexTakeRecursive(ev) => {
+                self.add_edges(task, ev.handle, t, ev.event_count);
+                let held = self.held.entry(task).or_default();
+                match held.iter_mut().find(|h| h.mutex == ev.handle) {
+                    Some(h) => h.depth += 1,
+                    None => held.push(Held {
+                        mutex: ev.handle,
+                        since: t,
+                        depth: 1,
+                    }),
+                }
+            }
+            Event::MutexTakeBlock(ev) | Event::MutexTakeRecursiveBlock(ev) => {
+                self.add_edges(task, ev.handle, t, ev.event_count)
+            }
+            Event::MutexGive(ev) | Event::MutexGiveRecursive(ev) => {
+                if let Some(held) = self.held.get_mut(&task) {
+                    if let Some(h) = held.iter_mut().find(|h| h.mutex == ev.handle) {
+                        h.depth -= 1;
+                    }
+                    held.retain(|h| h.depth != 0);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The mutexes each task holds
+    pub fn held(&self) -> impl Iterator<Item = (ObjectHandle, Vec<ObjectHandle>)> + '_ {
+        self.held
+            .iter()
+            .filter(|(_, held)| !held.is_empty())
+            .map(|(task, held)| (*task, held.iter().map(|h| h.mutex).collect()))
+    }
+
+    /// The edges of the lock-order graph
+    pub fn edges(&self) -> impl Iterator<Item = &LockOrderEdge> {
+        self.edges.values()
+    }
+
+    /// The elementary cycles of the lock-order graph
+    pub fn potential_deadlocks(&self) -> Vec<PotentialDeadlock> {
+        let mut graph: BTreeMap<ObjectHandle, Vec<ObjectHandle>> = BTreeMap::new();
+        for (from, to) in self.edges.keys() {
+            graph.entry(*from).or_default().push(*to);
+        }
+
+        // Find the cycles through each node, visiting only greater nodes so
+        // every cycle is found once, starting from its smallest node
+        let mut cycles = Vec::new();
+        for start in graph.keys() {
+            let mut path = vec![*start];
+            let mut on_path = BTreeSet::from([*start]);
+            self.find_cycles(&graph, *start, &mut path, &mut on_path, &mut cycles);
+        }
+        cycles
+    }
+
+    fn find_cycles(
+        &self,
+        graph: &BTreeMap<ObjectHandle, Vec<ObjectHandle>>,
+        start: ObjectHandle,
+        path: &mut Vec<ObjectHandle>,
+        on_path: &mut BTreeSet<ObjectHandle>,
+        cycles: &mut Vec<PotentialDeadlock>,
+    ) {
+        let node = path[path.len() - 1];
+        for next in graph.get(&node).into_iter().flatten() {
+            if *next == start {
+                let witnesses = path
+                    .iter()
+                    .zip(path.iter().skip(1).chain(Some(&start)))
+                    .map(|(from, to)| self.edges[&(*from, *to)])
+                    .collect();
+                cycles.push(PotentialDeadlock {
+                    mutexes: path.clone(),
+                    witnesses,
+                });
+            } else if *next > start && on_path.insert(*next) {
+                path.push(*next);
+                self.find_cycles(graph, start, path, on_path, cycles);
+                path.pop();
+                on_path.remove(next);
+            }
+        }
+    }
+
+    fn add_edges(
+        &mut self,
+        task: ObjectHandle,
+        mutex: ObjectHandle,
+        t: Timestamp,
+        event_count: EventCount,
+    ) {
+        for held in self.held.get(&task).into_iter().flatten() {
+            if held.mutex == mutex {
+                continue;
+            }
+            self.edges
+                .entry((held.mutex, mutex))
+                .or_insert_with(|| LockOrderEdge {
+                    held: held.mutex,
+                    taken: mutex,
+                    task,
+                    held_since: held.since,
+                    timestamp: t,
+                    event_count,
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{MutexEvent, TaskEvent};
+    use crate::types::{ObjectName, Priority};
+
+    fn switch(task: ObjectHandle) -> Event {
+        Event::TaskActivate(TaskEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            handle: task,
+            name: ObjectName("t".into()),
+            priority: Priority(1),
+        })
+    }
+
+    fn mutex(count: u16, handle: ObjectHandle) -> MutexEvent {
+        MutexEvent {
+            event_count: EventCount(count),
+            sequence: count.into(),
+            timestamp: Timestamp(count.into()),
+            handle,
+            name: None,
+            ticks_to_wait: None,
+        }
+    }
+
+    #[test]
+    fn conflicting_orders() {
+        let a = ObjectHandle::new(1).unwrap();
+        let b = ObjectHandle::new(2).unwrap();
+        let m1 = ObjectHandle::new(0x10).unwrap();
+        let m2 = ObjectHandle::new(0x20).unwrap();
+        let m3 = ObjectHandle::new(0x30).unwrap();
+        let events = [
+            switch(a),
+            Event::MutexTake(mutex(1, m1)),
+            Event::MutexTake(mutex(2, m2)),
+            Event::MutexGive(mutex(3, m2)),
+            Event::MutexGive(mutex(4, m1)),
+            // Consistent order
+            Event::MutexTake(mutex(5, m1)),
+            Event::MutexTake(mutex(6, m3)),
+            Event::MutexGive(mutex(7, m3)),
+            Event::MutexGive(mutex(8, m1)),
+            switch(b),
+            Event::MutexTake(mutex(9, m2)),
+            Event::MutexTakeBlock(mutex(10, m1)),
+        ];
+        let mut analyzer = LockOrderAnalyzer::new();
+        events
+            .iter()
+            .for_each(|ev| analyzer.process_streaming_event(ev));
+
+        assert_eq!(analyzer.edges().count(), 3);
+        assert_eq!(analyzer.held().collect::<Vec<_>>(), vec![(b, vec![m2])]);
+        let deadlocks = analyzer.potential_deadlocks();
+        assert_eq!(deadlocks.len(), 1);
+        assert_eq!(deadlocks[0].mutexes, vec![m1, m2]);
+        let witnesses: Vec<_> = deadlocks[0]
+            .witnesses
+            .iter()
+            .map(|w| (w.task, w.event_count))
+            .collect();
+        assert_eq!(witnesses, vec![(a, EventCount(2)), (b, EventCount(10))]);
+        assert!(!deadlocks[0].is_single_task());
+    }
+}
@@ -0,0 +1,32 @@
+//! Analyses built on top of the parsed snapshot and streaming events
+
+pub(crate) use crate::time::StreamingClock;
+
+pub mod arg_schema;
+pub mod context;
+pub mod density;
+pub mod histogram;
+pub mod idle;
+pub mod lock_order;
+pub mod notify;
+pub(crate) mod sched;
+pub mod semaphore;
+pub mod service_call;
+pub mod task_state;
+pub mod timer;
+pub mod wcet;
+
+pub use arg_schema::{
+    ArgumentSchemaAnalyzer, ArgumentSignature, ChannelSchema, SignatureObservation,
+};
+pub use context::{ContextAnalyzer, ContextAnomaly, ContextAnomalyKind};
+pub use density::EventDensity;
+pub use histogram::{Bucket, Histogram};
+pub use idle::{IdleAnalyzer, IdleStats};
+pub use lock_order::{LockOrderAnalyzer, LockOrderEdge, PotentialDeadlock};
+pub use notify::{NotifyAnalyzer, TaskNotifyStats};
+pub use semaphore::{SemaphoreAnalyzer, SemaphoreStats};
+pub use service_call::{ServiceCall, ServiceCallAnalyzer, ServiceCallStats};
+pub use task_state::{SchedulingState, TaskStateAnalyzer, TaskStateTransition, TimeInState};
+pub use timer::{ExpirationStats, TimerAnalyzer, TimerCallback, TimerStats};
+pub use wcet::{Interval, TaskWcet, WcetAnalyzer};
@@ -0,0 +1,180 @@
+//! Per-task summary of the streaming task notification events
+//!
+//! A wait that blocks is paired with the wait (notified) or failed wait (timed out)
+//! event the task records once unblocked, giving the time spent blocked.
+//! Waits that don't block at all (zero ticks to wait) are counted as polls, many of
+//! them hint at a busy-wait, many timeouts at a timeout-driven design.
+
+use crate::analysis::{Histogram, StreamingClock};
+use crate::streaming::event::{Event, EventType};
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TaskNotifyStats {
+    pub handle: ObjectHandle,
+    pub name: Option<String>,
+    /// Notifications received from tasks
+    pub notifies: u64,
+    /// Notifications received from ISRs
+    pub notifies_from_isr: u64,
+    /// Successful waits, blocking or not
+    pub waits: u64,
+    /// Successful waits that didn't block, with zero ticks to wait
+    pub polls: u64,
+    pub wait_blocks: u64,
+    /// Waits that failed, timing out or polling without a pending notification
+    pub wait_timeouts: u64,
+    /// Ticks spent blocked in a wait, until notified or timed out
+    pub block_durations: Histogram,
+}
+
+impl TaskNotifyStats {
+    fn new(handle: ObjectHandle) -> Self {
+        Self {
+            handle,
+            name: None,
+            notifies: 0,
+            notifies_from_isr: 0,
+            waits: 0,
+            polls: 0,
+            wait_blocks: 0,
+            wait_timeouts: 0,
+            block_durations: Histogram::new(),
+        }
+    }
+
+    /// Fraction of the waits that failed
+    pub fn timeout_ratio(&self) -> Option<f64> {
+        let attempts = self.waits + self.wait_timeouts;
+        (attempts != 0).then(|| self.wait_timeouts as f64 / attempts as f64)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NotifyAnalyzer {
+    clock: StreamingClock,
+    tasks: BTreeMap<ObjectHandle, TaskNotifyStats>,
+    blocked_since: BTreeMap<ObjectHandle, Timestamp>,
+}
+
+impl NotifyAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn task(&self, handle: ObjectHandle) -> Option<&TaskNotifyStats> {
+        self.tasks.get(&handle)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &TaskNotifyStats> {
+        self.tasks.values()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &Event) {
+        let t = self.clock.time(event.timestamp());
+        match event {
+            Event::TaskNotify(ev) | Event::TaskNotifyFromIsr(ev) => {
+                let task = self.entry(ev.handle, ev.task_name.as_deref());
+                if matches!(event, Event::TaskNotify(_)) {
+                    task.notifies += 1;
+                } else {
+                    task.notifies_from_isr += 1;
+                }
+            }
+            Event::TaskNotifyWaitBlock(ev) => {
+                self.entry(ev.handle, ev.task_name.as_deref()).wait_blocks += 1;
+                self.blocked_since.insert(ev.handle, t);
+            }
+            Event::TaskNotifyWait(ev) => {
+                let blocked_since = self.blocked_since.remove(&ev.handle);
+                let task = self.entry(ev.handle, ev.task_name.as_deref());
+                task.waits += 1;
+                match blocked_since {
+                    Some(start) => task.block_durations.record(t.ticks() - start.ticks()),
+                    None if ev.ticks_to_wait.is_some_and(|t| t.get_raw() == 0) => task.polls += 1,
+                    None => (),
+                }
+            }
+            // Failed waits are returned as unknown events
+            Event::Unknown(ev) if ev.code.event_type() == EventType::TaskNotifyWaitFailed => {
+                let Some(handle) = ev.parameters().first().and_then(|h| ObjectHandle::new(*h))
+                else {
+                    return;
+                };
+                let blocked_since = self.blocked_since.remove(&handle);
+                let task = self.entry(handle, None);
+                task.wait_timeouts += 1;
+                if let Some(start) = blocked_since {
+                    task.block_durations.record(t.ticks() - start.ticks());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn entry(&mut self, handle: ObjectHandle, name: Option<&str>) -> &mut TaskNotifyStats {
+        let task = self
+            .tasks
+            .entry(handle)
+            .or_insert_with(|| TaskNotifyStats::new(handle));
+        if let Some(name) = name {
+            if task.name.as_deref() != Some(name) {
+                task.name = Some(name.to_owned());
+            }
+        }
+        task
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCode, EventCount, EventId, TaskNotifyEvent};
+    use crate::time::Ticks;
+
+    fn notify(t: u64, ticks_to_wait: Option<u32>) -> TaskNotifyEvent {
+        TaskNotifyEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(t),
+            handle: ObjectHandle::new(1).unwrap(),
+            task_name: None,
+            ticks_to_wait: ticks_to_wait.map(Ticks),
+            index: None,
+            value: None,
+        }
+    }
+
+    #[test]
+    fn waits_and_timeouts() {
+        let failed = BaseEvent {
+            code: EventCode(
+                (u16::from(EventId::from(EventType::TaskNotifyWaitFailed)) | (2 << 12)).into(),
+            ),
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(300),
+            parameters: [1, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        let events = [
+            Event::TaskNotifyWait(notify(0, Some(0))),
+            Event::TaskNotifyWaitBlock(notify(10, Some(100))),
+            Event::TaskNotifyFromIsr(notify(50, None)),
+            Event::TaskNotifyWait(notify(60, Some(100))),
+            Event::TaskNotifyWaitBlock(notify(200, Some(100))),
+            Event::Unknown(failed),
+        ];
+        let mut a = NotifyAnalyzer::new();
+        events.iter().for_each(|ev| a.process_streaming_event(ev));
+
+        let task = a.task(ObjectHandle::new(1).unwrap()).unwrap();
+        assert_eq!((task.waits, task.polls, task.wait_blocks), (2, 1, 2));
+        assert_eq!((task.notifies, task.notifies_from_isr), (0, 1));
+        assert_eq!(task.wait_timeouts, 1);
+        assert_eq!(task.block_durations.count(), 2);
+        assert_eq!(task.block_durations.max(), Some(100));
+        assert_eq!(task.timeout_ratio(), Some(1.0 / 3.0));
+    }
+}
@@ -0,0 +1,221 @@
+//! Protocol-independent view of the scheduling events used by the exporters
+
+use crate::snapshot;
+use crate::streaming;
+use crate::types::{Argument, ObjectHandle};
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum SchedEvent<'a> {
+    /// A task was switched in
+    TaskSwitch(ObjectHandle, Option<&'a str>),
+    /// An ISR started, preempting the running task or ISR
+    IsrBegin(ObjectHandle, &'a str),
+    /// A nested ISR returned to the given ISR
+    IsrResume(ObjectHandle, &'a str),
+    TaskReady(ObjectHandle, &'a str),
+    /// The running task blocked, delayed or suspended itself
+    Block,
+    /// A user event with its first numeric argument, if any
+    User(String, Option<f64>),
+}
+
+impl SchedEvent<'_> {
+    pub(crate) fn from_streaming(event: &streaming::event::Event) -> Option<SchedEvent<'_>> {
+        use streaming::event::{Event, EventType};
+        Some(match event {
+            Event::TraceStart(ev) => {
+                SchedEvent::TaskSwitch(ev.current_task_handle, Some(&ev.current_task))
+            }
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                SchedEvent::TaskSwitch(ev.handle, Some(&ev.name))
+            }
+            // Task begin events are returned as unknown events
+            Event::Unknown(ev) if ev.code.event_type() == EventType::TaskSwitchTaskBegin => {
+                SchedEvent::TaskSwitch(ObjectHandle::new(ev.parameters[0])?, None)
+            }
+            Event::IsrBegin(ev) => SchedEvent::IsrBegin(ev.handle, &ev.name),
+            Event::IsrResume(ev) => SchedEvent::IsrResume(ev.handle, &ev.name),
+            Event::TaskReady(ev) => SchedEvent::TaskReady(ev.handle, &ev.name),
+            Event::TaskNotifyWaitBlock(_)
+            | Event::QueueSendBlock(_)
+            | Event::QueueReceiveBlock(_)
+            | Event::QueuePeekBlock(_)
+            | Event::QueueSendFrontBlock(_)
+            | Event::MutexGiveBlock(_)
+            | Event::MutexTakeBlock(_)
+            | Event::MutexTakeRecursiveBlock(_)
+            | Event::SemaphoreGiveBlock(_)
+            | Event::SemaphoreTakeBlock(_)
+            | Event::SemaphorePeekBlock(_)
+            | Event::EventGroupSyncBlock(_)
+            | Event::EventGroupWaitBitsBlock(_)
+            | Event::StreamBufferSendBlock(_)
+            | Event::StreamBufferReceiveBlock(_)
+            | Event::MessageBufferSendBlock(_)
+            | Event::MessageBufferReceiveBlock(_) => SchedEvent::Block,
+            Event::Unknown(ev)
+                if matches!(
+                    ev.code.event_type(),
+                    EventType::TaskDelay | EventType::TaskDelayUntil | EventType::TaskSuspend
+                ) =>
+            {
+                SchedEvent::Block
+            }
+            Event::User(ev) => SchedEvent::User(
+                ev.channel.to_string(),
+                ev.args.iter().find_map(Argument::as_f64),
+            ),
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn from_snapshot(event: &snapshot::event::Event) -> Option<SchedEvent<'_>> {
+        use snapshot::event::{Event, EventType};
+        Some(match event {
+            Event::TaskBegin(ev) | Event::TaskResume(ev) => {
+                SchedEvent::TaskSwitch(ev.handle, Some(&ev.name))
+            }
+            Event::IsrBegin(ev) => SchedEvent::IsrBegin(ev.handle, &ev.name),
+            Event::IsrResume(ev) => SchedEvent::IsrResume(ev.handle, &ev.name),
+            Event::TaskReady(ev) => SchedEvent::TaskReady(ev.handle, &ev.name),
+            Event::Unknown(_, record)
+                if matches!(
+                    EventType::from(record.event_code()),
+                    EventType::ReceiveBlock(_)
+                        | EventType::SendBlock(_)
+                        | EventType::TaskDelay
+                        | EventType::TaskDelayUntil
+                        | EventType::TaskSuspend
+                        | EventType::EventGroupSyncBlock
+                        | EventType::EventGroupWaitBitsBlock
+                        | EventType::TaskNotifyTakeBlock
+                        | EventType::TaskNotifyWaitBlock
+                        | EventType::QueuePeekBlock
+                        | EventType::SemaphortPeekBlock
+                        | EventType::MutexPeekBlock
+                ) =>
+            {
+                SchedEvent::Block
+            }
+            Event::User(ev) => SchedEvent::User(
+                ev.channel.to_string(),
+                ev.args.iter().find_map(Argument::as_f64),
+            ),
+            _ => return None,
+        })
+    }
+}
+
+/// What's running, a task with the ISRs nested on top of it
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct ContextStack {
+    pub(crate) task: Option<ObjectHandle>,
+    pub(crate) isrs: Vec<ObjectHandle>,
+}
+
+impl ContextStack {
+    /// Apply a context switch, returning the ISRs that ended
+    pub(crate) fn apply(&mut self, event: &SchedEvent<'_>) -> Vec<ObjectHandle> {
+        match event {
+            SchedEvent::TaskSwitch(handle, _) => {
+                self.task = Some(*handle);
+                std::mem::take(&mut self.isrs)
+            }
+            SchedEvent::IsrBegin(handle, _) => {
+                self.isrs.push(*handle);
+                Vec::new()
+            }
+            SchedEvent::IsrResume(handle, _) => match self.isrs.iter().rposition(|h| h == handle) {
+                Some(idx) => self.isrs.split_off(idx + 1),
+                // Resuming an ISR that began before the trace did
+                None => {
+                    let ended = std::mem::take(&mut self.isrs);
+                    self.isrs.push(*handle);
+                    ended
+                }
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCode, EventCount, EventId, EventType};
+    use crate::time::Timestamp;
+
+    fn unknown(event_type: EventType, param: u32) -> streaming::event::Event {
+        let mut parameters = [0; 15];
+        parameters[0] = param;
+        streaming::event::Event::Unknown(BaseEvent {
+            code: EventCode(EventId::from(event_type).0.into()),
+            event_count: EventCount(1),
+            sequence: 1,
+            timestamp: Timestamp(1),
+            parameters,
+        })
+    }
+
+    #[test]
+    fn streaming_unknown_events() {
+        let task = ObjectHandle::new(3).unwrap();
+        assert_eq!(
+            SchedEvent::from_streaming(&unknown(EventType::TaskSwitchTaskBegin, 3)),
+            Some(SchedEvent::TaskSwitch(task, None))
+        );
+        // A task begin on the null handle isn't a context switch
+        assert_eq!(
+            SchedEvent::from_streaming(&unknown(EventType::TaskSwitchTaskBegin, 0)),
+            None
+        );
+        assert_eq!(
+            SchedEvent::from_streaming(&unknown(EventType::TaskDelayUntil, 0)),
+            Some(SchedEvent::Block)
+        );
+        assert_eq!(
+            SchedEvent::from_streaming(&unknown(EventType::MemoryAlloc, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn nested_isrs() {
+        let task = ObjectHandle::new(1).unwrap();
+        let isr1 = ObjectHandle::new(2).unwrap();
+        let isr2 = ObjectHandle::new(3).unwrap();
+        let isr3 = ObjectHandle::new(4).unwrap();
+        let mut ctx = ContextStack::default();
+
+        assert!(ctx.apply(&SchedEvent::TaskSwitch(task, None)).is_empty());
+        assert!(ctx.apply(&SchedEvent::IsrBegin(isr1, "a")).is_empty());
+        assert!(ctx.apply(&SchedEvent::IsrBegin(isr2, "b")).is_empty());
+        assert!(ctx.apply(&SchedEvent::IsrBegin(isr3, "c")).is_empty());
+        assert!(ctx.apply(&SchedEvent::Block).is_empty());
+
+        // Returning to the outermost ISR ends the nested ones
+        assert_eq!(
+            ctx.apply(&SchedEvent::IsrResume(isr1, "a")),
+            vec![isr2, isr3]
+        );
+        assert_eq!(ctx.isrs, vec![isr1]);
+
+        // Switching tasks ends all ISRs
+        assert_eq!(ctx.apply(&SchedEvent::TaskSwitch(task, None)), vec![isr1]);
+        assert_eq!(ctx.task, Some(task));
+        assert!(ctx.isrs.is_empty());
+    }
+
+    #[test]
+    fn resuming_an_isr_that_began_before_the_trace() {
+        let isr1 = ObjectHandle::new(2).unwrap();
+        let isr2 = ObjectHandle::new(3).unwrap();
+        let mut ctx = ContextStack::default();
+
+        assert!(ctx.apply(&SchedEvent::IsrResume(isr1, "a")).is_empty());
+        assert_eq!(ctx.isrs, vec![isr1]);
+        assert!(ctx.apply(&SchedEvent::IsrBegin(isr2, "b")).is_empty());
+        assert_eq!(ctx.apply(&SchedEvent::IsrResume(isr1, "a")), vec![isr2]);
+        assert_eq!(ctx.task, None);
+    }
+}
@@ -0,0 +1,204 @@
+//! Give/take balance of the streaming semaphore events
+//!
+//! Semaphores that are chronically exhausted show up as takes that fail or block,
+//! and a count stuck at zero. Leaked semaphores, taken and never given back,
+//! show up as more takes than gives.
+//! The failed give and take events are only counted, their count isn't traced.
+
+use crate::streaming::event::{Event, EventType};
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SemaphoreStats {
+    pub handle: ObjectHandle,
+    pub name: Option<String>,
+    /// Count given at creation, counting semaphores only
+    pub initial_count: Option<u32>,
+    /// Successful gives, from tasks or ISRs
+    pub gives: u64,
+    /// Successful takes, from tasks or ISRs
+    pub takes: u64,
+    pub failed_gives: u64,
+    pub failed_takes: u64,
+    /// Gives that blocked, the give itself is counted when it succeeds
+    pub blocked_gives: u64,
+    /// Takes that blocked, the take itself is counted when it succeeds
+    pub blocked_takes: u64,
+    /// Gives and takes (successful or not) that saw a zero count
+    pub seen_empty: u64,
+    pub min_count: Option<u32>,
+    pub max_count: Option<u32>,
+    /// Count of the last give or take
+    pub final_count: Option<u32>,
+}
+
+impl SemaphoreStats {
+    fn new(handle: ObjectHandle) -> Self {
+        Self {
+            handle,
+            name: None,
+            initial_count: None,
+            gives: 0,
+            takes: 0,
+            failed_gives: 0,
+            failed_takes: 0,
+            blocked_gives: 0,
+            blocked_takes: 0,
+            seen_empty: 0,
+            min_count: None,
+            max_count: None,
+            final_count: None,
+        }
+    }
+
+    /// Successful takes minus successful gives, positive when the semaphore was
+    /// taken more than given back
+    pub fn net_takes(&self) -> i64 {
+        self.takes as i64 - self.gives as i64
+    }
+
+    /// Fraction of the take attempts that failed or had to block, a take that
+    /// blocks then times out is seen as both
+    pub fn exhaustion_ratio(&self) -> Option<f64> {
+        let attempts = self.takes + self.failed_takes;
+        let exhausted = (self.failed_takes + self.blocked_takes).min(attempts);
+        (attempts != 0).then(|| exhausted as f64 / attempts as f64)
+    }
+
+    fn record_count(&mut self, count: u32) {
+        if count == 0 {
+            self.seen_empty += 1;
+        }
+        self.min_count = Some(self.min_count.map_or(count, |c| c.min(count)));
+        self.max_count = Some(self.max_count.map_or(count, |c| c.max(count)));
+        self.final_count = Some(count);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SemaphoreAnalyzer {
+    semaphores: BTreeMap<ObjectHandle, SemaphoreStats>,
+}
+
+impl SemaphoreAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn semaphore(&self, handle: ObjectHandle) -> Option<&SemaphoreStats> {
+        self.semaphores.get(&handle)
+    }
+
+    pub fn semaphores(&self) -> impl Iterator<Item = &SemaphoreStats> {
+        self.semaphores.values()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &Event) {
+        match event {
+            Event::SemaphoreBinaryCreate(ev) | Event::SemaphoreCountingCreate(ev) => {
+                let s = self.entry(ev.handle);
+                s.name = ev.name.as_ref().map(|n| n.to_string());
+                s.initial_count = ev.count;
+            }
+            Event::SemaphoreGive(ev) | Event::SemaphoreGiveFromIsr(ev) => {
+                let s = self.entry(ev.handle);
+                s.gives += 1;
+                s.record_count(ev.count);
+            }
+            Event::SemaphoreTake(ev) | Event::SemaphoreTakeFromIsr(ev) => {
+                let s = self.entry(ev.handle);
+                s.takes += 1;
+                s.record_count(ev.count);
+            }
+            Event::SemaphoreGiveBlock(ev) => {
+                let s = self.entry(ev.handle);
+                s.blocked_gives += 1;
+                s.record_count(ev.count);
+            }
+            Event::SemaphoreTakeBlock(ev) => {
+                let s = self.entry(ev.handle);
+                s.blocked_takes += 1;
+                s.record_count(ev.count);
+            }
+            // Failed events are returned as unknown events
+            Event::Unknown(ev) => {
+                let Some(handle) = ev.parameters().first().and_then(|h| ObjectHandle::new(*h))
+                else {
+                    return;
+                };
+                match ev.code.event_type() {
+                    EventType::SemaphoreGiveFailed | EventType::SemaphoreGiveFromIsrFailed => {
+                        self.entry(handle).failed_gives += 1
+                    }
+                    EventType::SemaphoreTakeFailed | EventType::SemaphoreTakeFromIsrFailed => {
+                        self.entry(handle).failed_takes += 1
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn entry(&mut self, handle: ObjectHandle) -> &mut SemaphoreStats {
+        self.semaphores
+            .entry(handle)
+            .or_insert_with(|| SemaphoreStats::new(handle))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCode, EventCount, EventId, SemaphoreEvent};
+    use crate::time::Timestamp;
+
+    fn sem(handle: ObjectHandle, count: u32) -> SemaphoreEvent {
+        SemaphoreEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            handle,
+            name: None,
+            ticks_to_wait: None,
+            count,
+        }
+    }
+
+    #[test]
+    fn give_take_balance() {
+        let h = ObjectHandle::new(0x10).unwrap();
+        let failed_take = BaseEvent {
+            code: EventCode(
+                (u16::from(EventId::from(EventType::SemaphoreTakeFailed)) | (2 << 12)).into(),
+            ),
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            parameters: [0x10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        let events = [
+            Event::SemaphoreTake(sem(h, 1)),
+            Event::SemaphoreTakeBlock(sem(h, 0)),
+            Event::SemaphoreGive(sem(h, 0)),
+            Event::SemaphoreTake(sem(h, 1)),
+            Event::Unknown(failed_take),
+        ];
+        let mut a = SemaphoreAnalyzer::new();
+        events.iter().for_each(|ev| a.process_streaming_event(ev));
+
+        let s = a.semaphore(h).unwrap();
+        assert_eq!(
+            (s.takes, s.gives, s.failed_takes, s.blocked_takes),
+            (2, 1, 1, 1)
+        );
+        assert_eq!(s.net_takes(), 1);
+        assert_eq!(s.seen_empty, 2);
+        assert_eq!(
+            (s.min_count, s.max_count, s.final_count),
+            (Some(0), Some(1), Some(1))
+        );
+        assert_eq!(s.exhaustion_ratio(), Some(2.0 / 3.0));
+    }
+}
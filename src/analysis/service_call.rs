@@ -0,0 +1,329 @@
+//! Latency of the blocking kernel service calls, from the streaming events
+//!
+//! The recorder traces a call when it returns, and when it blocks. A blocked call
+//! is paired with the successful or failed event the same task records once it's
+//! unblocked, the time in between is the call's latency. Calls that return without
+//! blocking have a zero latency, the time spent in the call itself isn't traced.
+//! Calls from ISRs never block and aren't included.
+
+use crate::analysis::sched::{ContextStack, SchedEvent};
+use crate::analysis::{Histogram, StreamingClock};
+use crate::streaming::event::{Event, EventType};
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use derive_more::Display;
+use std::collections::BTreeMap;
+
+/// A kernel API that can block, named after its FreeRTOS function
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum ServiceCall {
+    #[display(fmt = "xQueueSend")]
+    QueueSend,
+    #[display(fmt = "xQueueSendToFront")]
+    QueueSendFront,
+    #[display(fmt = "xQueueReceive")]
+    QueueReceive,
+    #[display(fmt = "xQueuePeek")]
+    QueuePeek,
+    #[display(fmt = "xSemaphoreGive")]
+    SemaphoreGive,
+    #[display(fmt = "xSemaphoreTake")]
+    SemaphoreTake,
+    #[display(fmt = "xSemaphorePeek")]
+    SemaphorePeek,
+    #[display(fmt = "xSemaphoreGive(mutex)")]
+    MutexGive,
+    #[display(fmt = "xSemaphoreTake(mutex)")]
+    MutexTake,
+    #[display(fmt = "xSemaphoreTakeRecursive")]
+    MutexTakeRecursive,
+    #[display(fmt = "xEventGroupSync")]
+    EventGroupSync,
+    #[display(fmt = "xEventGroupWaitBits")]
+    EventGroupWaitBits,
+    #[display(fmt = "xStreamBufferSend")]
+    StreamBufferSend,
+    #[display(fmt = "xStreamBufferReceive")]
+    StreamBufferReceive,
+    #[display(fmt = "xMessageBufferSend")]
+    MessageBufferSend,
+    #[display(fmt = "xMessageBufferReceive")]
+    MessageBufferReceive,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ServiceCallStats {
+    pub call: ServiceCall,
+    /// Calls that returned, successfully or not
+    pub calls: u64,
+    /// Calls that blocked
+    pub blocked: u64,
+    /// Calls that failed, blocking or not
+    pub failed: u64,
+    /// Calls that blocked then failed
+    pub timeouts: u64,
+    /// Ticks from blocking to returning, zero for the calls that didn't block
+    pub latency: Histogram,
+}
+
+impl ServiceCallStats {
+    fn new(call: ServiceCall) -> Self {
+        Self {
+            call,
+            calls: 0,
+            blocked: 0,
+            failed: 0,
+            timeouts: 0,
+            latency: Histogram::new(),
+        }
+    }
+
+    /// Fraction of the calls that blocked
+    pub fn blocked_ratio(&self) -> Option<f64> {
+        (self.calls != 0).then(|| self.blocked as f64 / self.calls as f64)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Outcome {
+    Block,
+    Return,
+    Fail,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct BlockedCall {
+    call: ServiceCall,
+    handle: ObjectHandle,
+    since: Timestamp,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ServiceCallAnalyzer {
+    clock: StreamingClock,
+    context: ContextStack,
+    /// The call each task is blocked in, keyed by task, `None` before the first task switch
+    blocked: BTreeMap<Option<ObjectHandle>, BlockedCall>,
+    calls: BTreeMap<ServiceCall, ServiceCallStats>,
+}
+
+impl ServiceCallAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn call(&self, call: ServiceCall) -> Option<&ServiceCallStats> {
+        self.calls.get(&call)
+    }
+
+    pub fn calls(&self) -> impl Iterator<Item = &ServiceCallStats> {
+        self.calls.values()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &Event) {
+        let t = self.clock.time(event.timestamp());
+        if let Some(sched) = SchedEvent::from_streaming(event) {
+            self.context.apply(&sched);
+        }
+        let Some((call, outcome, handle)) = classify(event) else {
+            return;
+        };
+        if !self.context.isrs.is_empty() {
+            return;
+        }
+        let task = self.context.task;
+        if outcome == Outcome::Block {
+            self.entry(call).blocked += 1;
+            self.blocked.insert(
+                task,
+                BlockedCall {
+                    call,
+                    handle,
+                    since: t,
+                },
+            );
+            return;
+        }
+
+        // The call the task blocked in takes precedence over the event's, some
+        // calls return with the event of a related call, i.e. a failed send to
+        // front is reported as a failed send
+        let blocked = self
+            .blocked
+            .get(&task)
+            .filter(|b| b.handle == handle)
+            .copied();
+        let (call, latency) = match blocked {
+            Some(b) => {
+                self.blocked.remove(&task);
+                (b.call, t.ticks().saturating_sub(b.since.ticks()))
+            }
+            None => (call, 0),
+        };
+        let stats = self.entry(call);
+        stats.calls += 1;
+        stats.latency.record(latency);
+        if outcome == Outcome::Fail {
+            stats.failed += 1;
+            if blocked.is_some() {
+                stats.timeouts += 1;
+            }
+        }
+    }
+
+    fn entry(&mut self, call: ServiceCall) -> &mut ServiceCallStats {
+        self.calls
+            .entry(call)
+            .or_insert_with(|| ServiceCallStats::new(call))
+    }
+}
+
+fn classify(event: &Event) -> Option<(ServiceCall, Outcome, ObjectHandle)> {
+    use Outcome::*;
+    use ServiceCall::*;
+    Some(match event {
+        Event::QueueSend(ev) => (QueueSend, Return, ev.handle),
+        Event::QueueSendBlock(ev) => (QueueSend, Block, ev.handle),
+        Event::QueueSendFront(ev) => (QueueSendFront, Return, ev.handle),
+        Event::QueueSendFrontBlock(ev) => (QueueSendFront, Block, ev.handle),
+        Event::QueueReceive(ev) => (QueueReceive, Return, ev.handle),
+        Event::QueueReceiveBlock(ev) => (QueueReceive, Block, ev.handle),
+        Event::QueuePeek(ev) => (QueuePeek, Return, ev.handle),
+        Event::QueuePeekBlock(ev) => (QueuePeek, Block, ev.handle),
+        Event::SemaphoreGive(ev) => (SemaphoreGive, Return, ev.handle),
+        Event::SemaphoreGiveBlock(ev) => (SemaphoreGive, Block, ev.handle),
+        Event::SemaphoreTake(ev) => (SemaphoreTake, Return, ev.handle),
+        Event::SemaphoreTakeBlock(ev) => (SemaphoreTake, Block, ev.handle),
+        Event::SemaphorePeek(ev) => (SemaphorePeek, Return, ev.handle),
+        Event::SemaphorePeekBlock(ev) => (SemaphorePeek, Block, ev.handle),
+        Event::MutexGive(ev) => (MutexGive, Return, ev.handle),
+        Event::MutexGiveBlock(ev) => (MutexGive, Block, ev.handle),
+        Event::MutexTake(ev) => (MutexTake, Return, ev.handle),
+        Event::MutexTakeBlock(ev) => (MutexTake, Block, ev.handle),
+        Event::MutexTakeRecursive(ev) => (MutexTakeRecursive, Return, ev.handle),
+        Event::MutexTakeRecursiveBlock(ev) => (MutexTakeRecursive, Block, ev.handle),
+        Event::EventGroupSync(ev) => (EventGroupSync, Return, ev.handle),
+        Event::EventGroupSyncBlock(ev) => (EventGroupSync, Block, ev.handle),
+        Event::EventGroupWaitBits(ev) => (EventGroupWaitBits, Return, ev.handle),
+        Event::EventGroupWaitBitsBlock(ev) => (EventGroupWaitBits, Block, ev.handle),
+        Event::StreamBufferSend(ev) => (StreamBufferSend, Return, ev.handle),
+        Event::StreamBufferSendBlock(ev) => (StreamBufferSend, Block, ev.handle),
+        Event::StreamBufferReceive(ev) => (StreamBufferReceive, Return, ev.handle),
+        Event::StreamBufferReceiveBlock(ev) => (StreamBufferReceive, Block, ev.handle),
+        Event::MessageBufferSend(ev) => (MessageBufferSend, Return, ev.handle),
+        Event::MessageBufferSendBlock(ev) => (MessageBufferSend, Block, ev.handle),
+        Event::MessageBufferReceive(ev) => (MessageBufferReceive, Return, ev.handle),
+        Event::MessageBufferReceiveBlock(ev) => (MessageBufferReceive, Block, ev.handle),
+        // Failed calls are returned as unknown events
+        Event::Unknown(ev) => {
+            let call = match ev.code.event_type() {
+                EventType::QueueSendFailed => QueueSend,
+                EventType::QueueReceiveFailed => QueueReceive,
+                EventType::QueuePeekFailed => QueuePeek,
+                EventType::SemaphoreGiveFailed => SemaphoreGive,
+                EventType::SemaphoreTakeFailed => SemaphoreTake,
+                EventType::SemaphorePeekFailed => SemaphorePeek,
+                EventType::MutexGiveFailed => MutexGive,
+                EventType::MutexTakeFailed => MutexTake,
+                EventType::EventGroupSyncFailed => EventGroupSync,
+                EventType::EventGroupWaitBitsFailed => EventGroupWaitBits,
+                EventType::StreamBufferSendFailed => StreamBufferSend,
+                EventType::StreamBufferReceiveFailed => StreamBufferReceive,
+                EventType::MessageBufferSendFailed => MessageBufferSend,
+                EventType::MessageBufferReceiveFailed => MessageBufferReceive,
+                _ => return None,
+            };
+            let handle = ev
+                .parameters()
+                .first()
+                .and_then(|h| ObjectHandle::new(*h))?;
+            (call, Fail, handle)
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{
+        BaseEvent, EventCode, EventCount, EventId, QueueEvent, TaskEvent,
+    };
+    use crate::types::{ObjectName, Priority};
+
+    fn queue(t: u64, handle: ObjectHandle) -> QueueEvent {
+        QueueEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(t),
+            handle,
+            name: None,
+            ticks_to_wait: None,
+            messages_waiting: 0,
+        }
+    }
+
+    fn task(t: u64, handle: ObjectHandle) -> TaskEvent {
+        TaskEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(t),
+            handle,
+            name: ObjectName("t".into()),
+            priority: Priority(1),
+        }
+    }
+
+    #[test]
+    fn blocked_calls_latency() {
+        let [t1, t2, q] = [1, 2, 0x10].map(|h| ObjectHandle::new(h).unwrap());
+        let failed_receive = BaseEvent {
+            code: EventCode(
+                (u16::from(EventId::from(EventType::QueueReceiveFailed)) | (2 << 12)).into(),
+            ),
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(500),
+            parameters: [0x10, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        let events = [
+            Event::TaskResume(task(0, t1)),
+            Event::QueueReceiveBlock(queue(10, q)),
+            Event::TaskResume(task(20, t2)),
+            Event::QueueSend(queue(30, q)),
+            Event::QueueSendFrontBlock(queue(40, q)),
+            Event::TaskResume(task(50, t1)),
+            Event::QueueReceive(queue(60, q)),
+            Event::QueueReceiveBlock(queue(70, q)),
+            Event::TaskResume(task(80, t2)),
+            Event::QueueSend(queue(90, q)),
+            Event::TaskResume(task(400, t1)),
+            Event::Unknown(failed_receive),
+        ];
+        let mut a = ServiceCallAnalyzer::new();
+        events.iter().for_each(|ev| a.process_streaming_event(ev));
+
+        let receive = a.call(ServiceCall::QueueReceive).unwrap();
+        assert_eq!(
+            (
+                receive.calls,
+                receive.blocked,
+                receive.failed,
+                receive.timeouts
+            ),
+            (2, 2, 1, 1)
+        );
+        assert_eq!(receive.latency.min(), Some(50));
+        assert_eq!(receive.latency.max(), Some(430));
+
+        // The send to front blocked and returned with a send event
+        let send_front = a.call(ServiceCall::QueueSendFront).unwrap();
+        assert_eq!((send_front.calls, send_front.blocked), (1, 1));
+        assert_eq!(send_front.latency.max(), Some(50));
+        let send = a.call(ServiceCall::QueueSend).unwrap();
+        assert_eq!((send.calls, send.blocked), (1, 0));
+        assert_eq!(send.latency.max(), Some(0));
+        assert_eq!(send.blocked_ratio(), Some(0.0));
+        assert_eq!(ServiceCall::QueueSendFront.to_string(), "xQueueSendToFront");
+    }
+}
@@ -0,0 +1,320 @@
+//! Per-task scheduling state model, the transitions between the states and the
+//! time spent in each of them
+//!
+//! States are reconstructed from the kernel events:
+//! * switched in: running, the task switched out is ready again (preempted)
+//! * ready events, task creation and resumption: ready
+//! * blocking calls and delays of the running task: blocked
+//! * suspension: suspended
+//!
+//! ISRs don't change the state of the task they preempt.
+//! A task's state is unknown until its first event, that time isn't accounted for.
+
+use crate::analysis::sched::SchedEvent;
+use crate::analysis::StreamingClock;
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use derive_more::Display;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum SchedulingState {
+    Running,
+    Ready,
+    Blocked,
+    Suspended,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{task}:{from:?}->{to}")]
+pub struct TaskStateTransition {
+    /// Ticks since the first event for streaming data
+    pub timestamp: Timestamp,
+    pub task: ObjectHandle,
+    /// `None` for the first state of the task
+    pub from: Option<SchedulingState>,
+    pub to: SchedulingState,
+}
+
+/// Ticks spent in each state
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct TimeInState {
+    pub running: u64,
+    pub ready: u64,
+    pub blocked: u64,
+    pub suspended: u64,
+}
+
+impl TimeInState {
+    pub fn get(&self, state: SchedulingState) -> u64 {
+        match state {
+            SchedulingState::Running => self.running,
+            SchedulingState::Ready => self.ready,
+            SchedulingState::Blocked => self.blocked,
+            SchedulingState::Suspended => self.suspended,
+        }
+    }
+
+    fn add(&mut self, state: SchedulingState, ticks: u64) {
+        match state {
+            SchedulingState::Running => self.running += ticks,
+            SchedulingState::Ready => self.ready += ticks,
+            SchedulingState::Blocked => self.blocked += ticks,
+            SchedulingState::Suspended => self.suspended += ticks,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Change {
+    /// A task was switched in
+    Switch(ObjectHandle),
+    /// The running task blocked
+    Block,
+    Set(ObjectHandle, SchedulingState),
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskStateAnalyzer {
+    clock: StreamingClock,
+    record_transitions: bool,
+    running: Option<ObjectHandle>,
+    states: BTreeMap<ObjectHandle, (SchedulingState, Timestamp)>,
+    times: BTreeMap<ObjectHandle, TimeInState>,
+    transitions: Vec<TaskStateTransition>,
+    last: Timestamp,
+}
+
+impl TaskStateAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            clock: StreamingClock::default(),
+            record_transitions: false,
+            running: None,
+            states: BTreeMap::new(),
+            times: BTreeMap::new(),
+            transitions: Vec::new(),
+            last: Timestamp::zero(),
+        }
+    }
+
+    /// Keep every transition, see `transitions`, only the time in each state
+    /// is kept otherwise
+    pub fn with_transitions(mut self) -> Self {
+        self.record_transitions = true;
+        self
+    }
+
+    pub fn transitions(&self) -> &[TaskStateTransition] {
+        &self.transitions
+    }
+
+    /// The current state of the task
+    pub fn state(&self, task: ObjectHandle) -> Option<SchedulingState> {
+        self.states.get(&task).map(|(state, _)| *state)
+    }
+
+    /// The time the task spent in each state, up to the last event processed
+    pub fn time_in_state(&self, task: ObjectHandle) -> Option<TimeInState> {
+        let mut times = self.times.get(&task).copied();
+        if let Some((state, since)) = self.states.get(&task) {
+            times
+                .get_or_insert_with(TimeInState::default)
+                .add(*state, self.last.ticks().saturating_sub(since.ticks()));
+        }
+        times
+    }
+
+    /// The tasks seen so far
+    pub fn tasks(&self) -> impl Iterator<Item = ObjectHandle> + '_ {
+        self.states.keys().copied()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        use streaming::event::{Event, EventType};
+
+        let t = self.clock.time(event.timestamp());
+        let change = match event {
+            Event::TaskCreate(ev) => Some(Change::Set(ev.handle, SchedulingState::Ready)),
+            // Suspension and resumption are returned as unknown events
+            Event::Unknown(ev)
+                if matches!(
+                    ev.code.event_type(),
+                    EventType::TaskSuspend | EventType::TaskResume | EventType::TaskResumeFromIsr
+                ) =>
+            {
+                let state = if ev.code.event_type() == EventType::TaskSuspend {
+                    SchedulingState::Suspended
+                } else {
+                    SchedulingState::Ready
+                };
+                ev.parameters()
+                    .first()
+                    .and_then(|h| ObjectHandle::new(*h))
+                    .map(|h| Change::Set(h, state))
+            }
+            _ => SchedEvent::from_streaming(event).and_then(Self::sched_change),
+        };
+        self.apply(change, t);
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        use snapshot::event::{Event, EventType};
+
+        let t = event.timestamp();
+        let change = match event {
+            Event::TaskCreate(ev) => Some(Change::Set(ev.handle, SchedulingState::Ready)),
+            Event::Unknown(_, record)
+                if matches!(
+                    EventType::from(record.event_code()),
+                    EventType::TaskSuspend | EventType::TaskResume | EventType::TaskResumeFromIsr
+                ) =>
+            {
+                let state = if EventType::from(record.event_code()) == EventType::TaskSuspend {
+                    SchedulingState::Suspended
+                } else {
+                    SchedulingState::Ready
+                };
+                ObjectHandle::new(record.as_slice()[1].into()).map(|h| Change::Set(h, state))
+            }
+            _ => SchedEvent::from_snapshot(event).and_then(Self::sched_change),
+        };
+        self.apply(change, t);
+    }
+
+    fn sched_change(event: SchedEvent<'_>) -> Option<Change> {
+        Some(match event {
+            SchedEvent::TaskSwitch(handle, _) => Change::Switch(handle),
+            SchedEvent::TaskReady(handle, _) => Change::Set(handle, SchedulingState::Ready),
+            SchedEvent::Block => Change::Block,
+            _ => return None,
+        })
+    }
+
+    fn apply(&mut self, change: Option<Change>, t: Timestamp) {
+        self.last = t;
+        match change {
+            Some(Change::Switch(task)) => {
+                if let Some(previous) = self.running.filter(|p| *p != task) {
+                    if self.state(previous) == Some(SchedulingState::Running) {
+                        self.set(previous, SchedulingState::Ready, t);
+                    }
+                }
+                self.running = Some(task);
+                self.set(task, SchedulingState::Running, t);
+            }
+            Some(Change::Block) => {
+                if let Some(task) = self.running {
+                    self.set(task, SchedulingState::Blocked, t);
+                }
+            }
+            // A running task readied again, i.e. a yield, stays running
+            Some(Change::Set(task, SchedulingState::Ready))
+                if self.state(task) == Some(SchedulingState::Running) => {}
+            Some(Change::Set(task, state)) => self.set(task, state, t),
+            None => (),
+        }
+    }
+
+    fn set(&mut self, task: ObjectHandle, to: SchedulingState, t: Timestamp) {
+        let from = self.states.insert(task, (to, t));
+        if let Some((from, since)) = from {
+            if from == to {
+                // Keep the time the state was entered
+                self.states.insert(task, (from, since));
+                return;
+            }
+            self.times
+                .entry(task)
+                .or_default()
+                .add(from, t.ticks().saturating_sub(since.ticks()));
+        }
+        if self.record_transitions {
+            self.transitions.push(TaskStateTransition {
+                timestamp: t,
+                task,
+                from: from.map(|(s, _)| s),
+                to,
+            });
+        }
+    }
+}
+
+impl Default for TaskStateAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, EventCode, EventRecord, EventType, TaskEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{ObjectName, Priority};
+
+    fn task_event(handle: ObjectHandle, t: u64) -> TaskEvent {
+        TaskEvent {
+            handle,
+            name: ObjectName("t".into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(t),
+        }
+    }
+
+    #[test]
+    fn snapshot_transitions() {
+        let a = ObjectHandle::new(1).unwrap();
+        let b = ObjectHandle::new(2).unwrap();
+        let record = |et: EventType, handle: u8| {
+            EventRecord::new([u8::from(EventCode::from(et)), handle, 0, 0])
+        };
+        let events = [
+            Event::TaskReady(task_event(a, 0)),
+            Event::TaskBegin(task_event(a, 10)),
+            Event::TaskReady(task_event(b, 20)),
+            Event::TaskBegin(task_event(b, 30)),
+            Event::Unknown(Timestamp(50), record(EventType::TaskDelay, 0)),
+            Event::TaskResume(task_event(a, 55)),
+            Event::Unknown(Timestamp(60), record(EventType::TaskSuspend, 2)),
+            Event::Unknown(Timestamp(90), record(EventType::TaskResume, 2)),
+            Event::TaskBegin(task_event(b, 100)),
+        ];
+        let mut analyzer = TaskStateAnalyzer::new().with_transitions();
+        events
+            .iter()
+            .for_each(|ev| analyzer.process_snapshot_event(ev));
+
+        use SchedulingState::*;
+        let b_transitions: Vec<_> = analyzer
+            .transitions()
+            .iter()
+            .filter(|tr| tr.task == b)
+            .map(|tr| (tr.timestamp.ticks(), tr.from, tr.to))
+            .collect();
+        assert_eq!(
+            b_transitions,
+            vec![
+                (20, None, Ready),
+                (30, Some(Ready), Running),
+                (50, Some(Running), Blocked),
+                (60, Some(Blocked), Suspended),
+                (90, Some(Suspended), Ready),
+                (100, Some(Ready), Running),
+            ]
+        );
+        assert_eq!(
+            analyzer.time_in_state(a),
+            Some(TimeInState {
+                running: 20 + 45,
+                ready: 10 + 25,
+                blocked: 0,
+                suspended: 0,
+            })
+        );
+        assert_eq!(analyzer.state(a), Some(Ready));
+    }
+}
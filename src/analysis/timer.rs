@@ -0,0 +1,233 @@
+//! Software timer statistics from the streaming timer events
+//!
+//! Commands (start, reset, stop) are counted per timer handle. Expirations are
+//! recorded by the callback function and timer ID, that's all the expiration
+//! events carry, so the period jitter of a timer is only available once its
+//! callback is known, see `TimerAnalyzer::with_timer_callback`.
+//!
+//! Timer periods are in OS ticks while event timestamps are in timer counter ticks,
+//! they're converted using the `TsConfig` event, or `with_tick_rates` when the
+//! trace doesn't have one.
+
+use crate::analysis::{Histogram, StreamingClock};
+use crate::streaming::event::Event;
+use crate::time::{Frequency, Ticks, Timestamp};
+use crate::types::ObjectHandle;
+use derive_more::Display;
+use std::collections::BTreeMap;
+
+/// The callback function address and timer ID of a timer
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "{callback:X}:{timer_id:X}")]
+pub struct TimerCallback {
+    pub callback: u32,
+    pub timer_id: u32,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TimerStats {
+    pub handle: ObjectHandle,
+    pub name: Option<String>,
+    /// Period given at creation, in OS ticks
+    pub period: Option<Ticks>,
+    pub starts: u64,
+    pub resets: u64,
+    pub stops: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ExpirationStats {
+    pub callback: TimerCallback,
+    pub expirations: u64,
+    /// Ticks between consecutive expirations
+    pub intervals: Histogram,
+    last: Option<Timestamp>,
+}
+
+impl ExpirationStats {
+    /// Largest deviation of the intervals between expirations from `period`,
+    /// in timer counter ticks
+    pub fn max_jitter(&self, period: u64) -> Option<u64> {
+        let min = self.intervals.min()?;
+        let max = self.intervals.max()?;
+        Some(period.abs_diff(min).max(period.abs_diff(max)))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TimerAnalyzer {
+    clock: StreamingClock,
+    /// Timer counter ticks per OS tick
+    ticks_per_os_tick: Option<f64>,
+    callbacks: BTreeMap<ObjectHandle, TimerCallback>,
+    timers: BTreeMap<ObjectHandle, TimerStats>,
+    expirations: BTreeMap<TimerCallback, ExpirationStats>,
+}
+
+impl TimerAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given timer counter frequency and OS tick rate (`configTICK_RATE_HZ`)
+    /// to convert timer periods, a `TsConfig` event overrides them
+    pub fn with_tick_rates(mut self, frequency: Frequency, os_tick_rate_hz: u32) -> Self {
+        self.set_tick_rates(frequency, os_tick_rate_hz);
+        self
+    }
+
+    /// Associate the expirations of `callback` to the timer `handle`
+    pub fn with_timer_callback(mut self, handle: ObjectHandle, callback: TimerCallback) -> Self {
+        self.callbacks.insert(handle, callback);
+        self
+    }
+
+    pub fn timer(&self, handle: ObjectHandle) -> Option<&TimerStats> {
+        self.timers.get(&handle)
+    }
+
+    pub fn timers(&self) -> impl Iterator<Item = &TimerStats> {
+        self.timers.values()
+    }
+
+    pub fn expirations(&self, callback: TimerCallback) -> Option<&ExpirationStats> {
+        self.expirations.get(&callback)
+    }
+
+    pub fn all_expirations(&self) -> impl Iterator<Item = &ExpirationStats> {
+        self.expirations.values()
+    }
+
+    /// The expirations of the timer, when its callback is known
+    pub fn timer_expirations(&self, handle: ObjectHandle) -> Option<&ExpirationStats> {
+        self.expirations.get(self.callbacks.get(&handle)?)
+    }
+
+    /// Period of the timer in timer counter ticks
+    pub fn period_ticks(&self, handle: ObjectHandle) -> Option<u64> {
+        let period = self.timers.get(&handle)?.period?;
+        let ticks_per_os_tick = self.ticks_per_os_tick?;
+        Some((f64::from(period.get_raw()) * ticks_per_os_tick).round() as u64)
+    }
+
+    /// Largest deviation of the intervals between the timer's expirations from its
+    /// period, in timer counter ticks
+    pub fn period_jitter(&self, handle: ObjectHandle) -> Option<u64> {
+        self.timer_expirations(handle)?
+            .max_jitter(self.period_ticks(handle)?)
+    }
+
+    pub fn process_streaming_event(&mut self, event: &Event) {
+        let t = self.clock.time(event.timestamp());
+        match event {
+            Event::TsConfig(ev) => self.set_tick_rates(ev.frequency, ev.tick_rate_hz),
+            Event::TimerCreate(ev) => {
+                let timer = self.entry(ev.handle);
+                timer.name = ev.name.as_ref().map(|n| n.to_string());
+                timer.period = Some(ev.period);
+            }
+            Event::TimerStart(ev) => self.entry(ev.handle).starts += 1,
+            Event::TimerReset(ev) => self.entry(ev.handle).resets += 1,
+            Event::TimerStop(ev) => self.entry(ev.handle).stops += 1,
+            Event::TimerExpired(ev) => {
+                let callback = TimerCallback {
+                    callback: ev.callback,
+                    timer_id: ev.timer_id,
+                };
+                let stats = self
+                    .expirations
+                    .entry(callback)
+                    .or_insert_with(|| ExpirationStats {
+                        callback,
+                        expirations: 0,
+                        intervals: Histogram::new(),
+                        last: None,
+                    });
+                stats.expirations += 1;
+                if let Some(last) = stats.last {
+                    stats.intervals.record(t.ticks() - last.ticks());
+                }
+                stats.last = Some(t);
+            }
+            _ => (),
+        }
+    }
+
+    fn set_tick_rates(&mut self, frequency: Frequency, os_tick_rate_hz: u32) {
+        if !frequency.is_unitless() && os_tick_rate_hz != 0 {
+            self.ticks_per_os_tick =
+                Some(f64::from(frequency.get_raw()) / f64::from(os_tick_rate_hz));
+        }
+    }
+
+    fn entry(&mut self, handle: ObjectHandle) -> &mut TimerStats {
+        self.timers.entry(handle).or_insert_with(|| TimerStats {
+            handle,
+            name: None,
+            period: None,
+            starts: 0,
+            resets: 0,
+            stops: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, TimerCreateEvent, TimerEvent, TimerExpiredEvent};
+
+    fn expired(t: u64) -> Event {
+        Event::TimerExpired(TimerExpiredEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(t),
+            callback: 0x0800_1000,
+            timer_id: 1,
+        })
+    }
+
+    #[test]
+    fn period_jitter() {
+        let h = ObjectHandle::new(0x10).unwrap();
+        let callback = TimerCallback {
+            callback: 0x0800_1000,
+            timer_id: 1,
+        };
+        let start = TimerEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            handle: h,
+            name: None,
+            value: 0,
+        };
+        let events = [
+            Event::TimerCreate(TimerCreateEvent {
+                event_count: EventCount(0),
+                sequence: 0,
+                timestamp: Timestamp(0),
+                handle: h,
+                name: None,
+                period: Ticks(10),
+            }),
+            Event::TimerStart(start.clone()),
+            expired(1000),
+            expired(2000),
+            expired(3050),
+            expired(4000),
+            Event::TimerStop(start),
+        ];
+        // 100 timer counter ticks per OS tick
+        let mut a = TimerAnalyzer::new()
+            .with_tick_rates(Frequency(100_000), 1000)
+            .with_timer_callback(h, callback);
+        events.iter().for_each(|ev| a.process_streaming_event(ev));
+
+        let timer = a.timer(h).unwrap();
+        assert_eq!((timer.starts, timer.resets, timer.stops), (1, 0, 1));
+        assert_eq!(a.timer_expirations(h).unwrap().expirations, 4);
+        assert_eq!(a.period_ticks(h), Some(1000));
+        assert_eq!(a.period_jitter(h), Some(50));
+    }
+}
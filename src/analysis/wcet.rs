@@ -0,0 +1,296 @@
+//! Worst-case execution segments and activations of tasks
+//!
+//! An execution segment spans from a task being switched in until the next
+//! context switch (to another task or an ISR).
+//! An activation spans from the task becoming ready until it completes,
+//! i.e. blocks, delays or suspends itself, or (snapshot only) marks its
+//! instance as finished.
+
+use crate::analysis::{Histogram, StreamingClock};
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Interval {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl Interval {
+    pub fn duration(&self) -> u64 {
+        self.end.ticks().saturating_sub(self.start.ticks())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TaskWcet {
+    pub handle: ObjectHandle,
+    pub name: Option<String>,
+    pub longest_segment: Option<Interval>,
+    pub longest_activation: Option<Interval>,
+    /// Durations of all the execution segments, in ticks
+    pub segments: Histogram,
+    /// Durations of all the activations, in ticks
+    pub activations: Histogram,
+}
+
+impl TaskWcet {
+    fn new(handle: ObjectHandle) -> Self {
+        Self {
+            handle,
+            name: None,
+            longest_segment: None,
+            longest_activation: None,
+            segments: Histogram::new(),
+            activations: Histogram::new(),
+        }
+    }
+}
+
+/// Collects the execution segments and activations of each task.
+///
+/// Streaming timestamps are extended to 64 bits to account for timer rollovers,
+/// so the reported intervals are relative to the first streaming event processed.
+/// Segments and activations still open at the end of the trace aren't reported.
+#[derive(Clone, Debug, Default)]
+pub struct WcetAnalyzer {
+    tasks: BTreeMap<ObjectHandle, TaskWcet>,
+    running: Option<(ObjectHandle, Timestamp)>,
+    ready_since: BTreeMap<ObjectHandle, Timestamp>,
+    streaming_clock: StreamingClock,
+}
+
+impl WcetAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn task(&self, handle: ObjectHandle) -> Option<&TaskWcet> {
+        self.tasks.get(&handle)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &TaskWcet> {
+        self.tasks.values()
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        use streaming::event::{Event, EventType};
+
+        let t = self.streaming_clock.time(event.timestamp());
+
+        match event {
+            Event::TraceStart(ev) => {
+                self.switch_in(ev.current_task_handle, Some(&ev.current_task), t)
+            }
+            Event::TaskReady(ev) => self.ready(ev.handle, Some(&ev.name), t),
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.switch_in(ev.handle, Some(&ev.name), t)
+            }
+            // Task begin events are returned as unknown events
+            Event::Unknown(ev) if ev.code.event_type() == EventType::TaskSwitchTaskBegin => {
+                if let Some(handle) = ObjectHandle::new(ev.parameters[0]) {
+                    self.switch_in(handle, None, t)
+                }
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.switch_out(t),
+            Event::TaskNotifyWaitBlock(_)
+            | Event::QueueSendBlock(_)
+            | Event::QueueReceiveBlock(_)
+            | Event::QueuePeekBlock(_)
+            | Event::QueueSendFrontBlock(_)
+            | Event::MutexGiveBlock(_)
+            | Event::MutexTakeBlock(_)
+            | Event::MutexTakeRecursiveBlock(_)
+            | Event::SemaphoreGiveBlock(_)
+            | Event::SemaphoreTakeBlock(_)
+            | Event::SemaphorePeekBlock(_)
+            | Event::EventGroupSyncBlock(_)
+            | Event::EventGroupWaitBitsBlock(_)
+            | Event::StreamBufferSendBlock(_)
+            | Event::StreamBufferReceiveBlock(_)
+            | Event::MessageBufferSendBlock(_)
+            | Event::MessageBufferReceiveBlock(_) => self.complete_running(t),
+            Event::Unknown(ev)
+                if matches!(
+                    ev.code.event_type(),
+                    EventType::TaskDelay | EventType::TaskDelayUntil | EventType::TaskSuspend
+                ) =>
+            {
+                self.complete_running(t)
+            }
+            _ => (),
+        }
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        use snapshot::event::{Event, EventType};
+
+        let t = event.timestamp();
+        match event {
+            Event::TaskReady(ev) => self.ready(ev.handle, Some(&ev.name), t),
+            Event::TaskBegin(ev) | Event::TaskResume(ev) => {
+                self.switch_in(ev.handle, Some(&ev.name), t)
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.switch_out(t),
+            Event::TaskInstanceFinishedDirect(ev) => self.complete(ev.handle, t),
+            Event::Unknown(_, record) => {
+                let blocks = matches!(
+                    EventType::from(record.event_code()),
+                    EventType::ReceiveBlock(_)
+                        | EventType::SendBlock(_)
+                        | EventType::TaskDelay
+                        | EventType::TaskDelayUntil
+                        | EventType::TaskSuspend
+                        | EventType::EventGroupSyncBlock
+                        | EventType::EventGroupWaitBitsBlock
+                        | EventType::TaskNotifyTakeBlock
+                        | EventType::TaskNotifyWaitBlock
+                        | EventType::QueuePeekBlock
+                        | EventType::SemaphortPeekBlock
+                        | EventType::MutexPeekBlock
+                );
+                if blocks {
+                    self.complete_running(t);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn entry(&mut self, handle: ObjectHandle, name: Option<&str>) -> &mut TaskWcet {
+        let task = self
+            .tasks
+            .entry(handle)
+            .or_insert_with(|| TaskWcet::new(handle));
+        if let Some(name) = name {
+            if task.name.as_deref() != Some(name) {
+                task.name = Some(name.to_owned());
+            }
+        }
+        task
+    }
+
+    fn ready(&mut self, handle: ObjectHandle, name: Option<&str>, t: Timestamp) {
+        self.entry(handle, name);
+        // A task already in an activation stays in it
+        self.ready_since.entry(handle).or_insert(t);
+    }
+
+    fn switch_in(&mut self, handle: ObjectHandle, name: Option<&str>, t: Timestamp) {
+        if self.running.map(|(h, _)| h) == Some(handle) {
+            return;
+        }
+        self.switch_out(t);
+        self.entry(handle, name);
+        self.running = Some((handle, t));
+    }
+
+    fn switch_out(&mut self, t: Timestamp) {
+        let Some((handle, start)) = self.running.take() else {
+            return;
+        };
+        let segment = Interval { start, end: t };
+        let task = self.entry(handle, None);
+        task.segments.record(segment.duration());
+        if task
+            .longest_segment
+            .is_none_or(|s| segment.duration() > s.duration())
+        {
+            task.longest_segment = Some(segment);
+        }
+    }
+
+    fn complete_running(&mut self, t: Timestamp) {
+        if let Some((handle, _)) = self.running {
+            self.complete(handle, t);
+        }
+    }
+
+    fn complete(&mut self, handle: ObjectHandle, t: Timestamp) {
+        let Some(start) = self.ready_since.remove(&handle) else {
+            return;
+        };
+        let activation = Interval { start, end: t };
+        let task = self.entry(handle, None);
+        task.activations.record(activation.duration());
+        if task
+            .longest_activation
+            .is_none_or(|a| activation.duration() > a.duration())
+        {
+            task.longest_activation = Some(activation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{
+        Event, EventCode, EventRecord, EventType, IsrEvent, TaskEvent, TaskInstanceFinishedEvent,
+    };
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{ObjectName, Priority};
+
+    fn task_event(handle: ObjectHandle, t: u64) -> TaskEvent {
+        TaskEvent {
+            handle,
+            name: ObjectName("t".into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(t),
+        }
+    }
+
+    #[test]
+    fn snapshot_segments_and_activations() {
+        let task = ObjectHandle::new(1).unwrap();
+        let other = ObjectHandle::new(2).unwrap();
+        let isr = IsrEvent {
+            handle: ObjectHandle::new(3).unwrap(),
+            name: ObjectName("isr".into()),
+            priority: Priority(5),
+            timestamp: Timestamp(30),
+        };
+        let delay = EventRecord::new([u8::from(EventCode::from(EventType::TaskDelay)), 0, 0, 0]);
+        let events = vec![
+            Event::TaskReady(task_event(task, 10)),
+            Event::TaskBegin(task_event(task, 20)),
+            Event::IsrBegin(isr),
+            Event::TaskResume(task_event(task, 35)),
+            Event::Unknown(Timestamp(80), delay),
+            Event::TaskBegin(task_event(other, 90)),
+            Event::TaskReady(task_event(task, 100)),
+            Event::TaskResume(task_event(task, 110)),
+            Event::TaskInstanceFinishedDirect(TaskInstanceFinishedEvent {
+                handle: task,
+                name: ObjectName("t".into()),
+                timestamp: Timestamp(115),
+            }),
+            Event::TaskBegin(task_event(other, 120)),
+        ];
+        let mut a = WcetAnalyzer::new();
+        events.iter().for_each(|ev| a.process_snapshot_event(ev));
+
+        let wcet = a.task(task).unwrap();
+        assert_eq!(
+            wcet.longest_segment,
+            Some(Interval {
+                start: Timestamp(35),
+                end: Timestamp(90)
+            })
+        );
+        assert_eq!(wcet.segments.count(), 3);
+        assert_eq!(
+            wcet.longest_activation,
+            Some(Interval {
+                start: Timestamp(10),
+                end: Timestamp(80)
+            })
+        );
+        assert_eq!(wcet.activations.count(), 2);
+        assert_eq!(a.task(other).unwrap().segments.count(), 1);
+    }
+}
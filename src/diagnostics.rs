@@ -0,0 +1,132 @@
+//! Structured reports of recoverable issues found while parsing
+//!
+//! The parsers tolerate a number of issues in the trace data (i.e. bad format strings,
+//! unsupported object classes or versions), logging them and carrying on.
+//! They're also collected as `Diagnostic`s, available from the `RecorderData`
+//! types, so they can be presented to users.
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, derive_more::Display)]
+pub enum Severity {
+    #[display(fmt = "warning")]
+    Warning,
+    #[display(fmt = "error")]
+    Error,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Byte offset of the data the diagnostic refers to, if known.
+    ///
+    /// Offsets are relative to the start of the input for snapshot data and
+    /// relative to the first event (after the header and entry table) for streaming data.
+    pub offset: Option<u64>,
+    pub message: String,
+    /// Event count of the event the diagnostic refers to, if any
+    pub event_count: Option<u64>,
+}
+
+impl Diagnostic {
+    pub fn new<S: Into<String>>(severity: Severity, message: S) -> Self {
+        Self {
+            severity,
+            offset: None,
+            message: message.into(),
+            event_count: None,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn with_event_count(mut self, event_count: u64) -> Self {
+        self.event_count = Some(event_count);
+        self
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.severity)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        if let Some(event_count) = self.event_count {
+            write!(f, " (event {event_count})")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Log a warning and record it as a diagnostic
+macro_rules! warn_diagnostic {
+    ($diagnostics:expr, $offset:expr, $($arg:tt)+) => {{
+        let message = format!($($arg)+);
+        warn!("{message}");
+        let diagnostic =
+            $crate::diagnostics::Diagnostic::new($crate::diagnostics::Severity::Warning, message);
+        $diagnostics.push(match $offset {
+            Some(offset) => diagnostic.with_offset(offset),
+            None => diagnostic,
+        });
+    }};
+}
+
+/// Log an error and record it as a diagnostic
+macro_rules! error_diagnostic {
+    ($diagnostics:expr, $offset:expr, $($arg:tt)+) => {{
+        let message = format!($($arg)+);
+        error!("{message}");
+        let diagnostic =
+            $crate::diagnostics::Diagnostic::new($crate::diagnostics::Severity::Error, message);
+        $diagnostics.push(match $offset {
+            Some(offset) => diagnostic.with_offset(offset),
+            None => diagnostic,
+        });
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let d = Diagnostic::new(Severity::Warning, "bad format string");
+        assert_eq!(d.to_string(), "warning: bad format string");
+        let d = d.with_offset(16);
+        assert_eq!(d.to_string(), "warning at offset 16: bad format string");
+        let d = Diagnostic::new(Severity::Error, "truncated").with_event_count(3);
+        assert_eq!(d.to_string(), "error (event 3): truncated");
+        let d = d.with_offset(16);
+        assert_eq!(d.to_string(), "error at offset 16 (event 3): truncated");
+    }
+
+    #[test]
+    fn macros_record_the_offset_when_known() {
+        let mut diagnostics = Vec::new();
+        let offset: Option<u64> = None;
+        warn_diagnostic!(diagnostics, offset, "unsupported class {}", 9);
+        error_diagnostic!(diagnostics, Some(32), "invalid handle {:#X}", 0xFF);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::new(Severity::Warning, "unsupported class 9"),
+                Diagnostic::new(Severity::Error, "invalid handle 0xFF").with_offset(32),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_sort_after_warnings() {
+        assert!(Severity::Warning < Severity::Error);
+        let mut diagnostics = [
+            Diagnostic::new(Severity::Error, "a"),
+            Diagnostic::new(Severity::Warning, "b"),
+        ];
+        diagnostics.sort();
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+}
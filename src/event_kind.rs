@@ -0,0 +1,845 @@
+//! Stable, protocol independent identifiers of the event types
+//!
+//! The `Display` strings of the protocol specific `EventType`s follow the recorder's
+//! naming and may change between releases. `EventKind` is meant for persistence and
+//! for interfacing with other tools: its snake case names (also used by the serde
+//! representation) and its numeric codes never change, new kinds get new codes.
+//!
+//! The snapshot events that apply to any object class (i.e. `SEND(Queue)`) map to the
+//! kind of the matching streaming event (i.e. `queue_send`) when there is one.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::types::ObjectClass;
+use enum_iterator::Sequence;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Sequence)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[repr(u16)]
+pub enum EventKind {
+    Null = 1,
+    TraceStart = 2,
+    TsConfig = 3,
+    ObjectName = 4,
+    TaskPriority = 5,
+    TaskPriorityInherit = 6,
+    TaskPriorityDisinherit = 7,
+    DefineIsr = 8,
+    TaskCreate = 9,
+    TaskCreateFailed = 10,
+    TaskReady = 11,
+    TaskSwitchIsrBegin = 12,
+    TaskSwitchIsrResume = 13,
+    TaskSwitchTaskBegin = 14,
+    TaskSwitchTaskResume = 15,
+    TaskActivate = 16,
+    TaskDelayUntil = 17,
+    TaskDelay = 18,
+    TaskSuspend = 19,
+    TaskResume = 20,
+    TaskResumeFromIsr = 21,
+    TaskNotify = 22,
+    TaskNotifyWait = 23,
+    TaskNotifyWaitFailed = 24,
+    TaskNotifyWaitBlock = 25,
+    TaskNotifyFromIsr = 26,
+    MemoryAlloc = 27,
+    MemoryFree = 28,
+    QueueCreate = 29,
+    QueueCreateFailed = 30,
+    QueueSend = 31,
+    QueueSendFailed = 32,
+    QueueSendBlock = 33,
+    QueueSendFromIsr = 34,
+    QueueSendFromIsrFailed = 35,
+    QueueReceive = 36,
+    QueueReceiveFailed = 37,
+    QueueReceiveBlock = 38,
+    QueueReceiveFromIsr = 39,
+    QueueReceiveFromIsrFailed = 40,
+    QueuePeek = 41,
+    QueuePeekFailed = 42,
+    QueuePeekBlock = 43,
+    QueueSendFront = 44,
+    QueueSendFrontBlock = 45,
+    QueueSendFrontFromIsr = 46,
+    MutexCreate = 47,
+    MutexCreateFailed = 48,
+    MutexGive = 49,
+    MutexGiveFailed = 50,
+    MutexGiveBlock = 51,
+    MutexGiveRecursive = 52,
+    MutexTake = 53,
+    MutexTakeFailed = 54,
+    MutexTakeBlock = 55,
+    MutexTakeRecursive = 56,
+    MutexTakeRecursiveBlock = 57,
+    SemaphoreBinaryCreate = 58,
+    SemaphoreBinaryCreateFailed = 59,
+    SemaphoreCountingCreate = 60,
+    SemaphoreCountingCreateFailed = 61,
+    SemaphoreGive = 62,
+    SemaphoreGiveFailed = 63,
+    SemaphoreGiveBlock = 64,
+    SemaphoreGiveFromIsr = 65,
+    SemaphoreGiveFromIsrFailed = 66,
+    SemaphoreTake = 67,
+    SemaphoreTakeFailed = 68,
+    SemaphoreTakeBlock = 69,
+    SemaphoreTakeFromIsr = 70,
+    SemaphoreTakeFromIsrFailed = 71,
+    SemaphorePeek = 72,
+    SemaphorePeekFailed = 73,
+    SemaphorePeekBlock = 74,
+    TimerCreate = 75,
+    TimerStart = 76,
+    TimerReset = 77,
+    TimerStop = 78,
+    TimerExpired = 79,
+    EventGroupCreate = 80,
+    EventGroupCreateFailed = 81,
+    EventGroupSync = 82,
+    EventGroupWaitBits = 83,
+    EventGroupClearBits = 84,
+    EventGroupClearBitsFromIsr = 85,
+    EventGroupSetBits = 86,
+    EventGroupSetBitsFromIsr = 87,
+    EventGroupSyncBlock = 88,
+    EventGroupWaitBitsBlock = 89,
+    EventGroupSyncFailed = 90,
+    EventGroupWaitBitsFailed = 91,
+    StreamBufferCreate = 92,
+    StreamBufferCreateFailed = 93,
+    StreamBufferSend = 94,
+    StreamBufferSendBlock = 95,
+    StreamBufferSendFailed = 96,
+    StreamBufferReceive = 97,
+    StreamBufferReceiveBlock = 98,
+    StreamBufferReceiveFailed = 99,
+    StreamBufferSendFromIsr = 100,
+    StreamBufferSendFromIsrFailed = 101,
+    StreamBufferReceiveFromIsr = 102,
+    StreamBufferReceiveFromIsrFailed = 103,
+    StreamBufferReset = 104,
+    MessageBufferCreate = 105,
+    MessageBufferCreateFailed = 106,
+    MessageBufferSend = 107,
+    MessageBufferSendBlock = 108,
+    MessageBufferSendFailed = 109,
+    MessageBufferReceive = 110,
+    MessageBufferReceiveBlock = 111,
+    MessageBufferReceiveFailed = 112,
+    MessageBufferSendFromIsr = 113,
+    MessageBufferSendFromIsrFailed = 114,
+    MessageBufferReceiveFromIsr = 115,
+    MessageBufferReceiveFromIsrFailed = 116,
+    MessageBufferReset = 117,
+    TaskDelete = 118,
+    QueueDelete = 119,
+    SemaphoreDelete = 120,
+    MutexDelete = 121,
+    TimerDelete = 122,
+    EventGroupDelete = 123,
+    StreamBufferDelete = 124,
+    MessageBufferDelete = 125,
+    StateMachineStateCreate = 126,
+    StateMachineCreate = 127,
+    StateMachineStateChange = 128,
+    User = 129,
+    UnusedStack = 130,
+    Xps = 131,
+    NewTime = 132,
+    ObjectCloseName = 133,
+    ObjectCloseProperty = 134,
+    CreateObject = 135,
+    Send = 136,
+    Receive = 137,
+    SendFromIsr = 138,
+    ReceiveFromIsr = 139,
+    CreateObjectFailed = 140,
+    SendFailed = 141,
+    ReceiveFailed = 142,
+    SendFromIsrFailed = 143,
+    ReceiveFromIsrFailed = 144,
+    ReceiveBlock = 145,
+    SendBlock = 146,
+    Peek = 147,
+    DeleteObject = 148,
+    PendFuncCall = 149,
+    PendFuncCallFromIsr = 150,
+    PendFuncCallFailed = 151,
+    PendFuncCallFromIsrFailed = 152,
+    MemoryMallocSize = 153,
+    MemoryMallocAddress = 154,
+    MemoryFreeSize = 155,
+    MemoryFreeAddress = 156,
+    Xts8 = 157,
+    Xts16 = 158,
+    EventBeingWritten = 159,
+    ReservedDummyCode = 160,
+    LowPowerBegin = 161,
+    LowPowerEnd = 162,
+    Xid = 163,
+    Xts16l = 164,
+    TimerChangePeriod = 165,
+    TimerDeleteObject = 166,
+    TimerStartFromIsr = 167,
+    TimerResetFromIsr = 168,
+    TimerStopFromIsr = 169,
+    TimerCreateFailed = 170,
+    TimerStartFailed = 171,
+    TimerResetFailed = 172,
+    TimerStopFailed = 173,
+    TimerChangePeriodFailed = 174,
+    TimerDeleteFailed = 175,
+    TimerStartFromIsrFailed = 176,
+    TimerResetFromIsrFailed = 177,
+    TimerStopFromIsrFailed = 178,
+    EventGroupSyncEnd = 179,
+    EventGroupWaitBitsEnd = 180,
+    EventGroupDeleteObject = 181,
+    EventGroupSyncEndFailed = 182,
+    EventGroupWaitBitsEndFailed = 183,
+    EventGroupSetBitsFromIsrFailed = 184,
+    TaskInstanceFinishedNextKse = 185,
+    TaskInstanceFinishedDirect = 186,
+    TaskNotifyTake = 187,
+    TaskNotifyTakeBlock = 188,
+    TaskNotifyTakeFailed = 189,
+    TaskNotifyGiveFromIsr = 190,
+    MutexPeekBlock = 191,
+    MutexPeekFailed = 192,
+    StreamBufferObjectCloseName = 193,
+    MessageBufferObjectCloseName = 194,
+    StreamBufferObjectCloseProperty = 195,
+    MessageBufferObjectCloseProperty = 196,
+    MemoryMallocSizeFailed = 197,
+    MemoryFreeAddressFailed = 198,
+    /// The streaming `Event::Dropped` pseudo-event
+    Dropped = 199,
+    /// The streaming `Event::Annotation` pseudo-event
+    Annotation = 200,
+    /// The streaming `Event::SessionBoundary` pseudo-event
+    SessionBoundary = 201,
+    TaskCoreAffinitySet = 202,
+    TaskMigrate = 203,
+    /// Events the parsers don't know
+    Unknown = 0xFFFF,
+}
+
+impl EventKind {
+    /// The stable numeric code
+    pub const fn code(self) -> u16 {
+        self as u16
+    }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        enum_iterator::all::<Self>().find(|kind| kind.code() == code)
+    }
+
+    /// The stable snake case name
+    pub const fn name(self) -> &'static str {
+        use EventKind::*;
+        match self {
+            Null => "null",
+            TraceStart => "trace_start",
+            TsConfig => "ts_config",
+            ObjectName => "object_name",
+            TaskPriority => "task_priority",
+            TaskPriorityInherit => "task_priority_inherit",
+            TaskPriorityDisinherit => "task_priority_disinherit",
+            DefineIsr => "define_isr",
+            TaskCreate => "task_create",
+            TaskCreateFailed => "task_create_failed",
+            TaskReady => "task_ready",
+            TaskSwitchIsrBegin => "task_switch_isr_begin",
+            TaskSwitchIsrResume => "task_switch_isr_resume",
+            TaskSwitchTaskBegin => "task_switch_task_begin",
+            TaskSwitchTaskResume => "task_switch_task_resume",
+            TaskActivate => "task_activate",
+            TaskDelayUntil => "task_delay_until",
+            TaskDelay => "task_delay",
+            TaskSuspend => "task_suspend",
+            TaskResume => "task_resume",
+            TaskResumeFromIsr => "task_resume_from_isr",
+            TaskNotify => "task_notify",
+            TaskNotifyWait => "task_notify_wait",
+            TaskNotifyWaitFailed => "task_notify_wait_failed",
+            TaskNotifyWaitBlock => "task_notify_wait_block",
+            TaskNotifyFromIsr => "task_notify_from_isr",
+            MemoryAlloc => "memory_alloc",
+            MemoryFree => "memory_free",
+            QueueCreate => "queue_create",
+            QueueCreateFailed => "queue_create_failed",
+            QueueSend => "queue_send",
+            QueueSendFailed => "queue_send_failed",
+            QueueSendBlock => "queue_send_block",
+            QueueSendFromIsr => "queue_send_from_isr",
+            QueueSendFromIsrFailed => "queue_send_from_isr_failed",
+            QueueReceive => "queue_receive",
+            QueueReceiveFailed => "queue_receive_failed",
+            QueueReceiveBlock => "queue_receive_block",
+            QueueReceiveFromIsr => "queue_receive_from_isr",
+            QueueReceiveFromIsrFailed => "queue_receive_from_isr_failed",
+            QueuePeek => "queue_peek",
+            QueuePeekFailed => "queue_peek_failed",
+            QueuePeekBlock => "queue_peek_block",
+            QueueSendFront => "queue_send_front",
+            QueueSendFrontBlock => "queue_send_front_block",
+            QueueSendFrontFromIsr => "queue_send_front_from_isr",
+            MutexCreate => "mutex_create",
+            MutexCreateFailed => "mutex_create_failed",
+            MutexGive => "mutex_give",
+            MutexGiveFailed => "mutex_give_failed",
+            MutexGiveBlock => "mutex_give_block",
+            MutexGiveRecursive => "mutex_give_recursive",
+            MutexTake => "mutex_take",
+            MutexTakeFailed => "mutex_take_failed",
+            MutexTakeBlock => "mutex_take_block",
+            MutexTakeRecursive => "mutex_take_recursive",
+            MutexTakeRecursiveBlock => "mutex_take_recursive_block",
+            SemaphoreBinaryCreate => "semaphore_binary_create",
+            SemaphoreBinaryCreateFailed => "semaphore_binary_create_failed",
+            SemaphoreCountingCreate => "semaphore_counting_create",
+            SemaphoreCountingCreateFailed => "semaphore_counting_create_failed",
+            SemaphoreGive => "semaphore_give",
+            SemaphoreGiveFailed => "semaphore_give_failed",
+            SemaphoreGiveBlock => "semaphore_give_block",
+            SemaphoreGiveFromIsr => "semaphore_give_from_isr",
+            SemaphoreGiveFromIsrFailed => "semaphore_give_from_isr_failed",
+            SemaphoreTake => "semaphore_take",
+            SemaphoreTakeFailed => "semaphore_take_failed",
+            SemaphoreTakeBlock => "semaphore_take_block",
+            SemaphoreTakeFromIsr => "semaphore_take_from_isr",
+            SemaphoreTakeFromIsrFailed => "semaphore_take_from_isr_failed",
+            SemaphorePeek => "semaphore_peek",
+            SemaphorePeekFailed => "semaphore_peek_failed",
+            SemaphorePeekBlock => "semaphore_peek_block",
+            TimerCreate => "timer_create",
+            TimerStart => "timer_start",
+            TimerReset => "timer_reset",
+            TimerStop => "timer_stop",
+            TimerExpired => "timer_expired",
+            EventGroupCreate => "event_group_create",
+            EventGroupCreateFailed => "event_group_create_failed",
+            EventGroupSync => "event_group_sync",
+            EventGroupWaitBits => "event_group_wait_bits",
+            EventGroupClearBits => "event_group_clear_bits",
+            EventGroupClearBitsFromIsr => "event_group_clear_bits_from_isr",
+            EventGroupSetBits => "event_group_set_bits",
+            EventGroupSetBitsFromIsr => "event_group_set_bits_from_isr",
+            EventGroupSyncBlock => "event_group_sync_block",
+            EventGroupWaitBitsBlock => "event_group_wait_bits_block",
+            EventGroupSyncFailed => "event_group_sync_failed",
+            EventGroupWaitBitsFailed => "event_group_wait_bits_failed",
+            StreamBufferCreate => "stream_buffer_create",
+            StreamBufferCreateFailed => "stream_buffer_create_failed",
+            StreamBufferSend => "stream_buffer_send",
+            StreamBufferSendBlock => "stream_buffer_send_block",
+            StreamBufferSendFailed => "stream_buffer_send_failed",
+            StreamBufferReceive => "stream_buffer_receive",
+            StreamBufferReceiveBlock => "stream_buffer_receive_block",
+            StreamBufferReceiveFailed => "stream_buffer_receive_failed",
+            StreamBufferSendFromIsr => "stream_buffer_send_from_isr",
+            StreamBufferSendFromIsrFailed => "stream_buffer_send_from_isr_failed",
+            StreamBufferReceiveFromIsr => "stream_buffer_receive_from_isr",
+            StreamBufferReceiveFromIsrFailed => "stream_buffer_receive_from_isr_failed",
+            StreamBufferReset => "stream_buffer_reset",
+            MessageBufferCreate => "message_buffer_create",
+            MessageBufferCreateFailed => "message_buffer_create_failed",
+            MessageBufferSend => "message_buffer_send",
+            MessageBufferSendBlock => "message_buffer_send_block",
+            MessageBufferSendFailed => "message_buffer_send_failed",
+            MessageBufferReceive => "message_buffer_receive",
+            MessageBufferReceiveBlock => "message_buffer_receive_block",
+            MessageBufferReceiveFailed => "message_buffer_receive_failed",
+            MessageBufferSendFromIsr => "message_buffer_send_from_isr",
+            MessageBufferSendFromIsrFailed => "message_buffer_send_from_isr_failed",
+            MessageBufferReceiveFromIsr => "message_buffer_receive_from_isr",
+            MessageBufferReceiveFromIsrFailed => "message_buffer_receive_from_isr_failed",
+            MessageBufferReset => "message_buffer_reset",
+            TaskDelete => "task_delete",
+            QueueDelete => "queue_delete",
+            SemaphoreDelete => "semaphore_delete",
+            MutexDelete => "mutex_delete",
+            TimerDelete => "timer_delete",
+            EventGroupDelete => "event_group_delete",
+            StreamBufferDelete => "stream_buffer_delete",
+            MessageBufferDelete => "message_buffer_delete",
+            StateMachineStateCreate => "state_machine_state_create",
+            StateMachineCreate => "state_machine_create",
+            StateMachineStateChange => "state_machine_state_change",
+            User => "user",
+            UnusedStack => "unused_stack",
+            Xps => "xps",
+            NewTime => "new_time",
+            ObjectCloseName => "object_close_name",
+            ObjectCloseProperty => "object_close_property",
+            CreateObject => "create_object",
+            Send => "send",
+            Receive => "receive",
+            SendFromIsr => "send_from_isr",
+            ReceiveFromIsr => "receive_from_isr",
+            CreateObjectFailed => "create_object_failed",
+            SendFailed => "send_failed",
+            ReceiveFailed => "receive_failed",
+            SendFromIsrFailed => "send_from_isr_failed",
+            ReceiveFromIsrFailed => "receive_from_isr_failed",
+            ReceiveBlock => "receive_block",
+            SendBlock => "send_block",
+            Peek => "peek",
+            DeleteObject => "delete_object",
+            PendFuncCall => "pend_func_call",
+            PendFuncCallFromIsr => "pend_func_call_from_isr",
+            PendFuncCallFailed => "pend_func_call_failed",
+            PendFuncCallFromIsrFailed => "pend_func_call_from_isr_failed",
+            MemoryMallocSize => "memory_malloc_size",
+            MemoryMallocAddress => "memory_malloc_address",
+            MemoryFreeSize => "memory_free_size",
+            MemoryFreeAddress => "memory_free_address",
+            Xts8 => "xts8",
+            Xts16 => "xts16",
+            EventBeingWritten => "event_being_written",
+            ReservedDummyCode => "reserved_dummy_code",
+            LowPowerBegin => "low_power_begin",
+            LowPowerEnd => "low_power_end",
+            Xid => "xid",
+            Xts16l => "xts16l",
+            TimerChangePeriod => "timer_change_period",
+            TimerDeleteObject => "timer_delete_object",
+            TimerStartFromIsr => "timer_start_from_isr",
+            TimerResetFromIsr => "timer_reset_from_isr",
+            TimerStopFromIsr => "timer_stop_from_isr",
+            TimerCreateFailed => "timer_create_failed",
+            TimerStartFailed => "timer_start_failed",
+            TimerResetFailed => "timer_reset_failed",
+            TimerStopFailed => "timer_stop_failed",
+            TimerChangePeriodFailed => "timer_change_period_failed",
+            TimerDeleteFailed => "timer_delete_failed",
+            TimerStartFromIsrFailed => "timer_start_from_isr_failed",
+            TimerResetFromIsrFailed => "timer_reset_from_isr_failed",
+            TimerStopFromIsrFailed => "timer_stop_from_isr_failed",
+            EventGroupSyncEnd => "event_group_sync_end",
+            EventGroupWaitBitsEnd => "event_group_wait_bits_end",
+            EventGroupDeleteObject => "event_group_delete_object",
+            EventGroupSyncEndFailed => "event_group_sync_end_failed",
+            EventGroupWaitBitsEndFailed => "event_group_wait_bits_end_failed",
+            EventGroupSetBitsFromIsrFailed => "event_group_set_bits_from_isr_failed",
+            TaskInstanceFinishedNextKse => "task_instance_finished_next_kse",
+            TaskInstanceFinishedDirect => "task_instance_finished_direct",
+            TaskNotifyTake => "task_notify_take",
+            TaskNotifyTakeBlock => "task_notify_take_block",
+            TaskNotifyTakeFailed => "task_notify_take_failed",
+            TaskNotifyGiveFromIsr => "task_notify_give_from_isr",
+            MutexPeekBlock => "mutex_peek_block",
+            MutexPeekFailed => "mutex_peek_failed",
+            StreamBufferObjectCloseName => "stream_buffer_object_close_name",
+            MessageBufferObjectCloseName => "message_buffer_object_close_name",
+            StreamBufferObjectCloseProperty => "stream_buffer_object_close_property",
+            MessageBufferObjectCloseProperty => "message_buffer_object_close_property",
+            MemoryMallocSizeFailed => "memory_malloc_size_failed",
+            MemoryFreeAddressFailed => "memory_free_address_failed",
+            Dropped => "dropped",
+            Annotation => "annotation",
+            SessionBoundary => "session_boundary",
+            TaskCoreAffinitySet => "task_core_affinity_set",
+            TaskMigrate => "task_migrate",
+            Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown event kind '{0}'")]
+pub struct UnknownEventKind(pub String);
+
+impl FromStr for EventKind {
+    type Err = UnknownEventKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        enum_iterator::all::<Self>()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| UnknownEventKind(s.to_owned()))
+    }
+}
+
+impl From<streaming::event::EventType> for EventKind {
+    fn from(et: streaming::event::EventType) -> Self {
+        use streaming::event::EventType as T;
+        use EventKind::*;
+        match et {
+            T::Null => Null,
+            T::TraceStart => TraceStart,
+            T::TsConfig => TsConfig,
+            T::ObjectName => ObjectName,
+            T::TaskPriority => TaskPriority,
+            T::TaskPriorityInherit => TaskPriorityInherit,
+            T::TaskPriorityDisinherit => TaskPriorityDisinherit,
+            T::DefineIsr => DefineIsr,
+            T::TaskCreate => TaskCreate,
+            T::TaskCreateFailed => TaskCreateFailed,
+            T::TaskReady => TaskReady,
+            T::TaskSwitchIsrBegin => TaskSwitchIsrBegin,
+            T::TaskSwitchIsrResume => TaskSwitchIsrResume,
+            T::TaskSwitchTaskBegin => TaskSwitchTaskBegin,
+            T::TaskSwitchTaskResume => TaskSwitchTaskResume,
+            T::TaskActivate => TaskActivate,
+            T::TaskCoreAffinitySet => TaskCoreAffinitySet,
+            T::TaskMigrate => TaskMigrate,
+            T::TaskDelayUntil => TaskDelayUntil,
+            T::TaskDelay => TaskDelay,
+            T::TaskSuspend => TaskSuspend,
+            T::TaskResume => TaskResume,
+            T::TaskResumeFromIsr => TaskResumeFromIsr,
+            T::TaskNotify => TaskNotify,
+            T::TaskNotifyWait => TaskNotifyWait,
+            T::TaskNotifyWaitFailed => TaskNotifyWaitFailed,
+            T::TaskNotifyWaitBlock => TaskNotifyWaitBlock,
+            T::TaskNotifyFromIsr => TaskNotifyFromIsr,
+            T::MemoryAlloc => MemoryAlloc,
+            T::MemoryFree => MemoryFree,
+            T::QueueCreate => QueueCreate,
+            T::QueueCreateFailed => QueueCreateFailed,
+            T::QueueSend => QueueSend,
+            T::QueueSendFailed => QueueSendFailed,
+            T::QueueSendBlock => QueueSendBlock,
+            T::QueueSendFromIsr => QueueSendFromIsr,
+            T::QueueSendFromIsrFailed => QueueSendFromIsrFailed,
+            T::QueueReceive => QueueReceive,
+            T::QueueReceiveFailed => QueueReceiveFailed,
+            T::QueueReceiveBlock => QueueReceiveBlock,
+            T::QueueReceiveFromIsr => QueueReceiveFromIsr,
+            T::QueueReceiveFromIsrFailed => QueueReceiveFromIsrFailed,
+            T::QueuePeek => QueuePeek,
+            T::QueuePeekFailed => QueuePeekFailed,
+            T::QueuePeekBlock => QueuePeekBlock,
+            T::QueueSendFront => QueueSendFront,
+            T::QueueSendFrontBlock => QueueSendFrontBlock,
+            T::QueueSendFrontFromIsr => QueueSendFrontFromIsr,
+            T::MutexCreate => MutexCreate,
+            T::MutexCreateFailed => MutexCreateFailed,
+            T::MutexGive => MutexGive,
+            T::MutexGiveFailed => MutexGiveFailed,
+            T::MutexGiveBlock => MutexGiveBlock,
+            T::MutexGiveRecursive => MutexGiveRecursive,
+            T::MutexTake => MutexTake,
+            T::MutexTakeFailed => MutexTakeFailed,
+            T::MutexTakeBlock => MutexTakeBlock,
+            T::MutexTakeRecursive => MutexTakeRecursive,
+            T::MutexTakeRecursiveBlock => MutexTakeRecursiveBlock,
+            T::SemaphoreBinaryCreate => SemaphoreBinaryCreate,
+            T::SemaphoreBinaryCreateFailed => SemaphoreBinaryCreateFailed,
+            T::SemaphoreCountingCreate => SemaphoreCountingCreate,
+            T::SemaphoreCountingCreateFailed => SemaphoreCountingCreateFailed,
+            T::SemaphoreGive => SemaphoreGive,
+            T::SemaphoreGiveFailed => SemaphoreGiveFailed,
+            T::SemaphoreGiveBlock => SemaphoreGiveBlock,
+            T::SemaphoreGiveFromIsr => SemaphoreGiveFromIsr,
+            T::SemaphoreGiveFromIsrFailed => SemaphoreGiveFromIsrFailed,
+            T::SemaphoreTake => SemaphoreTake,
+            T::SemaphoreTakeFailed => SemaphoreTakeFailed,
+            T::SemaphoreTakeBlock => SemaphoreTakeBlock,
+            T::SemaphoreTakeFromIsr => SemaphoreTakeFromIsr,
+            T::SemaphoreTakeFromIsrFailed => SemaphoreTakeFromIsrFailed,
+            T::SemaphorePeek => SemaphorePeek,
+            T::SemaphorePeekFailed => SemaphorePeekFailed,
+            T::SemaphorePeekBlock => SemaphorePeekBlock,
+            T::TimerCreate => TimerCreate,
+            T::TimerStart => TimerStart,
+            T::TimerReset => TimerReset,
+            T::TimerStop => TimerStop,
+            T::TimerExpired => TimerExpired,
+            T::EventGroupCreate => EventGroupCreate,
+            T::EventGroupCreateFailed => EventGroupCreateFailed,
+            T::EventGroupSync => EventGroupSync,
+            T::EventGroupWaitBits => EventGroupWaitBits,
+            T::EventGroupClearBits => EventGroupClearBits,
+            T::EventGroupClearBitsFromIsr => EventGroupClearBitsFromIsr,
+            T::EventGroupSetBits => EventGroupSetBits,
+            T::EventGroupSetBitsFromIsr => EventGroupSetBitsFromIsr,
+            T::EventGroupSyncBlock => EventGroupSyncBlock,
+            T::EventGroupWaitBitsBlock => EventGroupWaitBitsBlock,
+            T::EventGroupSyncFailed => EventGroupSyncFailed,
+            T::EventGroupWaitBitsFailed => EventGroupWaitBitsFailed,
+            T::StreamBufferCreate => StreamBufferCreate,
+            T::StreamBufferCreateFailed => StreamBufferCreateFailed,
+            T::StreamBufferSend => StreamBufferSend,
+            T::StreamBufferSendBlock => StreamBufferSendBlock,
+            T::StreamBufferSendFailed => StreamBufferSendFailed,
+            T::StreamBufferReceive => StreamBufferReceive,
+            T::StreamBufferReceiveBlock => StreamBufferReceiveBlock,
+            T::StreamBufferReceiveFailed => StreamBufferReceiveFailed,
+            T::StreamBufferSendFromIsr => StreamBufferSendFromIsr,
+            T::StreamBufferSendFromIsrFailed => StreamBufferSendFromIsrFailed,
+            T::StreamBufferReceiveFromIsr => StreamBufferReceiveFromIsr,
+            T::StreamBufferReceiveFromIsrFailed => StreamBufferReceiveFromIsrFailed,
+            T::StreamBufferReset => StreamBufferReset,
+            T::MessageBufferCreate => MessageBufferCreate,
+            T::MessageBufferCreateFailed => MessageBufferCreateFailed,
+            T::MessageBufferSend => MessageBufferSend,
+            T::MessageBufferSendBlock => MessageBufferSendBlock,
+            T::MessageBufferSendFailed => MessageBufferSendFailed,
+            T::MessageBufferReceive => MessageBufferReceive,
+            T::MessageBufferReceiveBlock => MessageBufferReceiveBlock,
+            T::MessageBufferReceiveFailed => MessageBufferReceiveFailed,
+            T::MessageBufferSendFromIsr => MessageBufferSendFromIsr,
+            T::MessageBufferSendFromIsrFailed => MessageBufferSendFromIsrFailed,
+            T::MessageBufferReceiveFromIsr => MessageBufferReceiveFromIsr,
+            T::MessageBufferReceiveFromIsrFailed => MessageBufferReceiveFromIsrFailed,
+            T::MessageBufferReset => MessageBufferReset,
+            T::TaskDelete => TaskDelete,
+            T::QueueDelete => QueueDelete,
+            T::SemaphoreDelete => SemaphoreDelete,
+            T::MutexDelete => MutexDelete,
+            T::TimerDelete => TimerDelete,
+            T::EventGroupDelete => EventGroupDelete,
+            T::StreamBufferDelete => StreamBufferDelete,
+            T::MessageBufferDelete => MessageBufferDelete,
+            T::StateMachineStateCreate => StateMachineStateCreate,
+            T::StateMachineCreate => StateMachineCreate,
+            T::StateMachineStateChange => StateMachineStateChange,
+            T::UserEvent(_) => User,
+            T::UnusedStack => UnusedStack,
+            T::Dropped => Dropped,
+            T::Annotation => Annotation,
+            T::SessionBoundary => SessionBoundary,
+            T::Unknown(_) => Unknown,
+        }
+    }
+}
+
+impl From<snapshot::event::EventType> for EventKind {
+    fn from(et: snapshot::event::EventType) -> Self {
+        use snapshot::event::EventType as T;
+        use EventKind::*;
+        match et {
+            T::Null => Null,
+            T::Xps => Xps,
+            T::TaskReady => TaskReady,
+            T::NewTime => NewTime,
+            T::TaskSwitchIsrBegin => TaskSwitchIsrBegin,
+            T::TaskSwitchIsrResume => TaskSwitchIsrResume,
+            T::TaskSwitchTaskBegin => TaskSwitchTaskBegin,
+            T::TaskSwitchTaskResume => TaskSwitchTaskResume,
+            T::ObjectCloseName(class) => object_kind(ObjectCloseName, class.into_class()),
+            T::ObjectCloseProperty(class) => object_kind(ObjectCloseProperty, class.into_class()),
+            T::CreateObject(class) => object_kind(CreateObject, class.into_class()),
+            T::Send(class) => object_kind(Send, class.into_class()),
+            T::Receive(class) => object_kind(Receive, class.into_class()),
+            T::SendFromIsr(class) => object_kind(SendFromIsr, class.into_class()),
+            T::ReceiveFromIsr(class) => object_kind(ReceiveFromIsr, class.into_class()),
+            T::CreateObjectFailed(class) => object_kind(CreateObjectFailed, class.into_class()),
+            T::SendFailed(class) => object_kind(SendFailed, class.into_class()),
+            T::ReceiveFailed(class) => object_kind(ReceiveFailed, class.into_class()),
+            T::SendFromIsrFailed(class) => object_kind(SendFromIsrFailed, class.into_class()),
+            T::ReceiveFromIsrFailed(class) => object_kind(ReceiveFromIsrFailed, class.into_class()),
+            T::ReceiveBlock(class) => object_kind(ReceiveBlock, class.into_class()),
+            T::SendBlock(class) => object_kind(SendBlock, class.into_class()),
+            T::Peek(class) => object_kind(Peek, class.into_class()),
+            T::DeleteObject(class) => object_kind(DeleteObject, class.into_class()),
+            T::TaskDelayUntil => TaskDelayUntil,
+            T::TaskDelay => TaskDelay,
+            T::TaskSuspend => TaskSuspend,
+            T::TaskResume => TaskResume,
+            T::TaskResumeFromIsr => TaskResumeFromIsr,
+            T::TaskPrioritySet => TaskPriority,
+            T::TaskPriorityInherit => TaskPriorityInherit,
+            T::TaskPriorityDisinherit => TaskPriorityDisinherit,
+            T::PendFuncCall => PendFuncCall,
+            T::PendFuncCallFromIsr => PendFuncCallFromIsr,
+            T::PendFuncCallFailed => PendFuncCallFailed,
+            T::PendFuncCallFromIsrFailed => PendFuncCallFromIsrFailed,
+            T::MemoryMallocSize => MemoryMallocSize,
+            T::MemoryMallocAddress => MemoryMallocAddress,
+            T::MemoryFreeSize => MemoryFreeSize,
+            T::MemoryFreeAddress => MemoryFreeAddress,
+            T::UserEvent(_) => User,
+            T::Xts8 => Xts8,
+            T::Xts16 => Xts16,
+            T::EventBeingWritten => EventBeingWritten,
+            T::ReservedDummyCode => ReservedDummyCode,
+            T::LowPowerBegin => LowPowerBegin,
+            T::LowPowerEnd => LowPowerEnd,
+            T::Xid => Xid,
+            T::Xts16l => Xts16l,
+            T::TimerCreate => TimerCreate,
+            T::TimerStart => TimerStart,
+            T::TimerReset => TimerReset,
+            T::TimerStop => TimerStop,
+            T::TimerChangePeriod => TimerChangePeriod,
+            T::TimerDeleteObject => TimerDeleteObject,
+            T::TimerStartFromIsr => TimerStartFromIsr,
+            T::TimerResetFromIsr => TimerResetFromIsr,
+            T::TimerStopFromIsr => TimerStopFromIsr,
+            T::TimerCreateFailed => TimerCreateFailed,
+            T::TimerStartFailed => TimerStartFailed,
+            T::TimerResetFailed => TimerResetFailed,
+            T::TimerStopFailed => TimerStopFailed,
+            T::TimerChangePeriodFailed => TimerChangePeriodFailed,
+            T::TimerDeleteFailed => TimerDeleteFailed,
+            T::TimerStartFromIsrFailed => TimerStartFromIsrFailed,
+            T::TimerResetFromIsrFailed => TimerResetFromIsrFailed,
+            T::TimerStopFromIsrFailed => TimerStopFromIsrFailed,
+            T::EventGroupCreate => EventGroupCreate,
+            T::EventGroupCreateFailed => EventGroupCreateFailed,
+            T::EventGroupSyncBlock => EventGroupSyncBlock,
+            T::EventGroupSyncEnd => EventGroupSyncEnd,
+            T::EventGroupWaitBitsBlock => EventGroupWaitBitsBlock,
+            T::EventGroupWaitBitsEnd => EventGroupWaitBitsEnd,
+            T::EventGroupClearBits => EventGroupClearBits,
+            T::EventGroupClearBitsFromIsr => EventGroupClearBitsFromIsr,
+            T::EventGroupSetBits => EventGroupSetBits,
+            T::EventGroupDeleteObject => EventGroupDeleteObject,
+            T::EventGroupSyncEndFailed => EventGroupSyncEndFailed,
+            T::EventGroupWaitBitsEndFailed => EventGroupWaitBitsEndFailed,
+            T::EventGroupSetBitsFromIsr => EventGroupSetBitsFromIsr,
+            T::EventGroupSetBitsFromIsrFailed => EventGroupSetBitsFromIsrFailed,
+            T::TaskInstanceFinishedNextKse => TaskInstanceFinishedNextKse,
+            T::TaskInstanceFinishedDirect => TaskInstanceFinishedDirect,
+            T::TaskNotify => TaskNotify,
+            T::TaskNotifyTake => TaskNotifyTake,
+            T::TaskNotifyTakeBlock => TaskNotifyTakeBlock,
+            T::TaskNotifyTakeFailed => TaskNotifyTakeFailed,
+            T::TaskNotifyWait => TaskNotifyWait,
+            T::TaskNotifyWaitBlock => TaskNotifyWaitBlock,
+            T::TaskNotifyWaitFailed => TaskNotifyWaitFailed,
+            T::TaskNotifyFromIsr => TaskNotifyFromIsr,
+            T::TaskNotifyGiveFromIsr => TaskNotifyGiveFromIsr,
+            T::TimerExpired => TimerExpired,
+            T::QueuePeekBlock => QueuePeekBlock,
+            T::SemaphortPeekBlock => SemaphorePeekBlock,
+            T::MutexPeekBlock => MutexPeekBlock,
+            T::QueuePeekFailed => QueuePeekFailed,
+            T::SemaphortPeekFailed => SemaphorePeekFailed,
+            T::MutexPeekFailed => MutexPeekFailed,
+            T::StreambufferReset => StreamBufferReset,
+            T::MessagebufferReset => MessageBufferReset,
+            T::StreambufferObjectCloseName => StreamBufferObjectCloseName,
+            T::MessagebufferObjectCloseName => MessageBufferObjectCloseName,
+            T::StreambufferObjectCloseProperty => StreamBufferObjectCloseProperty,
+            T::MessagebufferObjectCloseProperty => MessageBufferObjectCloseProperty,
+            T::MemoryMallocSizeFailed => MemoryMallocSizeFailed,
+            T::MemoryFreeAddressFailed => MemoryFreeAddressFailed,
+            T::UnusedStack => UnusedStack,
+            T::Unknown(_) => Unknown,
+        }
+    }
+}
+
+/// The class specific kind of a snapshot event that applies to any object class
+fn object_kind(kind: EventKind, class: ObjectClass) -> EventKind {
+    use EventKind::*;
+    use ObjectClass as C;
+    match (kind, class) {
+        (CreateObject, C::Queue) => QueueCreate,
+        (CreateObject, C::Mutex) => MutexCreate,
+        (CreateObject, C::Task) => TaskCreate,
+        (CreateObject, C::Timer) => TimerCreate,
+        (CreateObject, C::EventGroup) => EventGroupCreate,
+        (CreateObject, C::StreamBuffer) => StreamBufferCreate,
+        (CreateObjectFailed, C::Queue) => QueueCreateFailed,
+        (CreateObjectFailed, C::Mutex) => MutexCreateFailed,
+        (CreateObjectFailed, C::Task) => TaskCreateFailed,
+        (CreateObjectFailed, C::EventGroup) => EventGroupCreateFailed,
+        (CreateObjectFailed, C::StreamBuffer) => StreamBufferCreateFailed,
+        (Send, C::Queue) => QueueSend,
+        (Send, C::Semaphore) => SemaphoreGive,
+        (Send, C::Mutex) => MutexGive,
+        (Send, C::StreamBuffer) => StreamBufferSend,
+        (SendFailed, C::Queue) => QueueSendFailed,
+        (SendFailed, C::Semaphore) => SemaphoreGiveFailed,
+        (SendFailed, C::Mutex) => MutexGiveFailed,
+        (SendFailed, C::StreamBuffer) => StreamBufferSendFailed,
+        (SendBlock, C::Queue) => QueueSendBlock,
+        (SendBlock, C::Semaphore) => SemaphoreGiveBlock,
+        (SendBlock, C::Mutex) => MutexGiveBlock,
+        (SendBlock, C::StreamBuffer) => StreamBufferSendBlock,
+        (SendFromIsr, C::Queue) => QueueSendFromIsr,
+        (SendFromIsr, C::Semaphore) => SemaphoreGiveFromIsr,
+        (SendFromIsr, C::StreamBuffer) => StreamBufferSendFromIsr,
+        (SendFromIsrFailed, C::Queue) => QueueSendFromIsrFailed,
+        (SendFromIsrFailed, C::Semaphore) => SemaphoreGiveFromIsrFailed,
+        (SendFromIsrFailed, C::StreamBuffer) => StreamBufferSendFromIsrFailed,
+        (Receive, C::Queue) => QueueReceive,
+        (Receive, C::Semaphore) => SemaphoreTake,
+        (Receive, C::Mutex) => MutexTake,
+        (Receive, C::StreamBuffer) => StreamBufferReceive,
+        (ReceiveFailed, C::Queue) => QueueReceiveFailed,
+        (ReceiveFailed, C::Semaphore) => SemaphoreTakeFailed,
+        (ReceiveFailed, C::Mutex) => MutexTakeFailed,
+        (ReceiveFailed, C::StreamBuffer) => StreamBufferReceiveFailed,
+        (ReceiveBlock, C::Queue) => QueueReceiveBlock,
+        (ReceiveBlock, C::Semaphore) => SemaphoreTakeBlock,
+        (ReceiveBlock, C::Mutex) => MutexTakeBlock,
+        (ReceiveBlock, C::StreamBuffer) => StreamBufferReceiveBlock,
+        (ReceiveFromIsr, C::Queue) => QueueReceiveFromIsr,
+        (ReceiveFromIsr, C::Semaphore) => SemaphoreTakeFromIsr,
+        (ReceiveFromIsr, C::StreamBuffer) => StreamBufferReceiveFromIsr,
+        (ReceiveFromIsrFailed, C::Queue) => QueueReceiveFromIsrFailed,
+        (ReceiveFromIsrFailed, C::Semaphore) => SemaphoreTakeFromIsrFailed,
+        (ReceiveFromIsrFailed, C::StreamBuffer) => StreamBufferReceiveFromIsrFailed,
+        (Peek, C::Queue) => QueuePeek,
+        (Peek, C::Semaphore) => SemaphorePeek,
+        (DeleteObject, C::Queue) => QueueDelete,
+        (DeleteObject, C::Semaphore) => SemaphoreDelete,
+        (DeleteObject, C::Mutex) => MutexDelete,
+        (DeleteObject, C::Task) => TaskDelete,
+        (DeleteObject, C::Timer) => TimerDelete,
+        (DeleteObject, C::EventGroup) => EventGroupDelete,
+        (DeleteObject, C::StreamBuffer) => StreamBufferDelete,
+        _ => kind,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn stable_codes_and_names() {
+        let kinds: Vec<_> = enum_iterator::all::<EventKind>().collect();
+        let codes: BTreeSet<_> = kinds.iter().map(|k| k.code()).collect();
+        let names: BTreeSet<_> = kinds.iter().map(|k| k.name()).collect();
+        assert_eq!(codes.len(), kinds.len());
+        assert_eq!(names.len(), kinds.len());
+        for kind in kinds {
+            assert_eq!(EventKind::from_code(kind.code()), Some(kind));
+            assert_eq!(kind.name().parse::<EventKind>().unwrap(), kind);
+        }
+
+        // Persisted values, these must never change
+        assert_eq!(EventKind::TraceStart.code(), 2);
+        assert_eq!(EventKind::QueueSendFromIsr.name(), "queue_send_from_isr");
+        assert_eq!(EventKind::Unknown.code(), 0xFFFF);
+    }
+
+    #[test]
+    fn protocol_event_types() {
+        use snapshot::event::EventType as SnapshotEventType;
+        use streaming::event::EventType as StreamingEventType;
+
+        assert_eq!(
+            EventKind::from(StreamingEventType::QueueSendFromIsr),
+            EventKind::QueueSendFromIsr
+        );
+        let send = SnapshotEventType::Send(snapshot::event::ObjectClassCode(0));
+        assert_eq!(send.to_string(), "SEND(Queue)");
+        assert_eq!(EventKind::from(send), EventKind::QueueSend);
+        assert_eq!(
+            EventKind::from(SnapshotEventType::TaskPrioritySet),
+            EventKind::TaskPriority
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Extraction of typed records from formatted user events with regular expressions
+//!
+//! Each rule is a regex with named capture groups, applied to the formatted
+//! string of the user events, optionally only those of one channel.
+//! Every match yields a record with a field per named group that matched,
+//! the captured text is typed as an integer, a float or a string.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::time::Timestamp;
+use crate::types::{Argument, UserEventChannel};
+use regex::Regex;
+
+#[derive(Clone, Debug)]
+pub struct ExtractionRule {
+    name: String,
+    channel: Option<UserEventChannel>,
+    regex: Regex,
+}
+
+impl ExtractionRule {
+    pub fn new<S: Into<String>>(name: S, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            name: name.into(),
+            channel: None,
+            regex: Regex::new(pattern)?,
+        })
+    }
+
+    /// Only apply the rule to the events of `channel`
+    pub fn with_channel(mut self, channel: UserEventChannel) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn channel(&self) -> Option<&UserEventChannel> {
+        self.channel.as_ref()
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ExtractedRecord {
+    /// Name of the rule that matched
+    pub rule: String,
+    pub timestamp: Timestamp,
+    pub channel: UserEventChannel,
+    /// The named groups that matched, in the order of the pattern
+    pub fields: Vec<(String, Argument)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Extractor {
+    rules: Vec<ExtractionRule>,
+}
+
+impl Extractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: ExtractionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn add_rule(&mut self, rule: ExtractionRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[ExtractionRule] {
+        &self.rules
+    }
+
+    pub fn extract_streaming_event(&self, event: &streaming::event::Event) -> Vec<ExtractedRecord> {
+        match event {
+            streaming::event::Event::User(ev) => {
+                self.extract(ev.timestamp, &ev.channel, &ev.formatted_string)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn extract_snapshot_event(&self, event: &snapshot::event::Event) -> Vec<ExtractedRecord> {
+        match event {
+            snapshot::event::Event::User(ev) => {
+                self.extract(ev.timestamp, &ev.channel, &ev.formatted_string)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Apply every rule to a formatted user event, a rule yields a record
+    /// per non-overlapping match
+    pub fn extract(
+        &self,
+        timestamp: Timestamp,
+        channel: &UserEventChannel,
+        formatted_string: &str,
+    ) -> Vec<ExtractedRecord> {
+        let mut records = Vec::new();
+        for rule in self.rules.iter() {
+            if rule.channel.as_ref().is_some_and(|c| c != channel) {
+                continue;
+            }
+            for caps in rule.regex.captures_iter(formatted_string) {
+                let fields = rule
+                    .regex
+                    .capture_names()
+                    .flatten()
+                    .filter_map(|name| {
+                        let m = caps.name(name)?;
+                        Some((name.to_owned(), typed_value(m.as_str())))
+                    })
+                    .collect();
+                records.push(ExtractedRecord {
+                    rule: rule.name.clone(),
+                    timestamp,
+                    channel: channel.clone(),
+                    fields,
+                });
+            }
+        }
+        records
+    }
+}
+
+/// Type captured text like the user event arguments: 32-bit integers,
+/// including `0x` prefixed hexadecimal, then floats, then strings
+pub fn typed_value(s: &str) -> Argument {
+    if let Ok(v) = s.parse::<u32>() {
+        Argument::U32(v)
+    } else if let Ok(v) = s.parse::<i32>() {
+        Argument::I32(v)
+    } else if let Some(Ok(v)) = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .map(|hex| u32::from_str_radix(hex, 16))
+    {
+        Argument::U32(v)
+    } else if let Ok(v) = s.parse::<f64>() {
+        Argument::F64(v.into())
+    } else {
+        Argument::String(s.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn named_groups() {
+        let extractor = Extractor::new()
+            .with_rule(
+                ExtractionRule::new("motor", r"rpm=(?P<rpm>\S+)(?: (?P<state>[a-z]+))?")
+                    .unwrap()
+                    .with_channel(UserEventChannel::Custom("motor".to_owned())),
+            )
+            .with_rule(ExtractionRule::new("temp", r"t=(?P<t>-?[\d.]+)").unwrap());
+        let motor = UserEventChannel::Custom("motor".to_owned());
+
+        let records = extractor.extract(Timestamp(5), &motor, "rpm=1200 ok, rpm=0x10");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].rule, "motor");
+        assert_eq!(
+            records[0].fields,
+            vec![
+                ("rpm".to_owned(), Argument::U32(1200)),
+                ("state".to_owned(), Argument::String("ok".to_owned())),
+            ]
+        );
+        assert_eq!(
+            records[1].fields,
+            vec![("rpm".to_owned(), Argument::U32(16))]
+        );
+
+        let records = extractor.extract(Timestamp(6), &UserEventChannel::Default, "t=-2 rpm=1");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].fields, vec![("t".to_owned(), Argument::I32(-2))]);
+        assert_eq!(
+            extractor.extract(Timestamp(7), &motor, "t=1.5")[0].fields,
+            vec![("t".to_owned(), Argument::F64(1.5.into()))]
+        );
+    }
+}
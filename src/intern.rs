@@ -0,0 +1,117 @@
+//! A string interning pool for the names and format strings found in the events
+//! of either protocol
+//!
+//! Interned strings are `Arc<str>`, cheap to clone and to keep around in analyses,
+//! and equal strings interned in the same pool share the same allocation, so they
+//! can be compared with `Arc::ptr_eq`.
+//!
+//! A pool set with `streaming::RecorderData::set_intern_pool` or
+//! `snapshot::RecorderData::set_intern_pool` interns the symbols, object names and
+//! format strings the parsers create, the names and format strings of the events
+//! share them. Sharing one pool between several parsers, i.e. a snapshot and a
+//! streaming capture of the same system, makes their equal strings share too.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A thread-safe pool of interned strings, clones share the same pool.
+///
+/// Pools are compared by identity, a pool is only equal to its clones.
+#[derive(Clone, Debug, Default)]
+pub struct InternPool(Arc<Mutex<HashSet<Arc<str>>>>);
+
+impl InternPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared handle of the string, added to the pool if it isn't already in it
+    pub fn intern<S: AsRef<str>>(&self, s: S) -> Arc<str> {
+        let s = s.as_ref();
+        let mut strings = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(s);
+                strings.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// The shared handle of the string, if it was interned before
+    pub fn get<S: AsRef<str>>(&self, s: S) -> Option<Arc<str>> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(s.as_ref())
+            .cloned()
+    }
+
+    /// Number of distinct strings in the pool
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove the strings no longer referenced outside of the pool
+    pub fn shrink(&self) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|s| Arc::strong_count(s) > 1);
+    }
+}
+
+impl PartialEq for InternPool {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for InternPool {}
+
+impl PartialOrd for InternPool {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InternPool {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Arc::as_ptr(&self.0).cmp(&Arc::as_ptr(&other.0))
+    }
+}
+
+impl Hash for InternPool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::ObjectName;
+
+    #[test]
+    fn shared_handles() {
+        let pool = InternPool::new();
+        let other = pool.clone();
+        let a = pool.intern(ObjectName::from("TaskA".to_owned()));
+        let b = other.intern("TaskA");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &pool.intern("TaskB")));
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.get("TaskC"), None);
+
+        drop((a, b));
+        pool.shrink();
+        assert!(pool.is_empty());
+    }
+}
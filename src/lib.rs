@@ -1,4 +1,26 @@
+#[macro_use]
+pub mod logging;
+#[macro_use]
+pub mod diagnostics;
+#[cfg(feature = "analysis")]
+pub mod analysis;
+pub mod event_kind;
+#[cfg(feature = "regex")]
+pub mod extract;
+pub mod intern;
+pub mod lifetime;
+pub mod manifest;
+pub mod session;
+pub mod severity;
 pub mod snapshot;
+#[cfg(all(feature = "json", feature = "export"))]
+pub mod speedscope;
 pub mod streaming;
+pub mod symbolizer;
+pub mod table_cache;
 pub mod time;
+#[cfg(feature = "export")]
+pub mod tracealyzer_csv;
 pub mod types;
+#[cfg(feature = "export")]
+pub mod vcd;
@@ -0,0 +1,372 @@
+//! Kernel object lifetimes
+//!
+//! Combines the object create and delete (and snapshot close) events of either
+//! protocol into a table of object lifetimes with per-object event counts.
+
+use crate::snapshot::{self, ObjectPropertyTable};
+use crate::streaming::{self, EntryTable};
+use crate::time::Timestamp;
+use crate::types::{ObjectClass, ObjectHandle};
+use byteordered::ByteOrdered;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ObjectLifetime {
+    pub handle: ObjectHandle,
+    pub class: Option<ObjectClass>,
+    pub name: Option<String>,
+    /// `None` when the object was created before the trace started
+    pub created_at: Option<Timestamp>,
+    /// `None` when the object was still alive at the end of the trace
+    pub deleted_at: Option<Timestamp>,
+    /// Number of events referencing the object, including the create and delete events
+    pub num_events: u64,
+}
+
+/// Object lifetimes, in the order the objects were first seen.
+///
+/// Handles can be reused once an object is deleted, in which case each
+/// object gets its own lifetime.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ObjectLifetimeTable {
+    lifetimes: Vec<ObjectLifetime>,
+    /// Index of the lifetime of the live objects.
+    /// Snapshot handles are per-class indices so the class is part of the key,
+    /// it's always `None` for streaming handles.
+    live: BTreeMap<(Option<ObjectClass>, ObjectHandle), usize>,
+}
+
+impl ObjectLifetimeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with the objects of a streaming entry table, i.e. the objects
+    /// created before the trace started
+    pub fn from_entry_table(entry_table: &EntryTable) -> Self {
+        let mut t = Self::new();
+        for (handle, entry) in entry_table.entries().iter() {
+            if entry.symbol.is_none() && entry.class.is_none() {
+                continue;
+            }
+            let idx = t.live_lifetime(None, *handle);
+            let lt = &mut t.lifetimes[idx];
+            lt.class = entry.class;
+            lt.name = entry.symbol.as_ref().map(|s| s.to_string());
+        }
+        t
+    }
+
+    /// Build the lifetime table of a snapshot
+    pub fn from_snapshot<R: Read + Seek + Send>(
+        rd: &snapshot::RecorderData,
+        r: &mut R,
+    ) -> Result<Self, snapshot::Error> {
+        let mut t = Self::new();
+        for item in rd.events(r)? {
+            let (event_type, event) = item?;
+            t.process_snapshot_event(rd, event_type, &event);
+        }
+        Ok(t)
+    }
+
+    pub fn lifetimes(&self) -> &[ObjectLifetime] {
+        &self.lifetimes
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ObjectLifetime> {
+        self.lifetimes.iter()
+    }
+
+    /// The lifetime of the live streaming object with the given handle
+    pub fn live(&self, handle: ObjectHandle) -> Option<&ObjectLifetime> {
+        self.live
+            .get(&(None, handle))
+            .map(|idx| &self.lifetimes[*idx])
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        use streaming::event::Event;
+
+        let Some(handle) = event.object_handle() else {
+            return;
+        };
+        let timestamp = event.timestamp();
+
+        if let Event::ObjectDelete(ev) = event {
+            let idx = self.live_lifetime(None, handle);
+            self.live.remove(&(None, handle));
+            let lt = &mut self.lifetimes[idx];
+            lt.num_events += 1;
+            lt.deleted_at = Some(timestamp);
+            lt.class = Some(ev.class);
+            if let Some(name) = ev.name.as_ref() {
+                lt.name = Some(name.to_string());
+            }
+            return;
+        }
+
+        let created_class = match event {
+            Event::TaskCreate(_) => Some(ObjectClass::Task),
+            Event::QueueCreate(_) => Some(ObjectClass::Queue),
+            Event::MutexCreate(_) => Some(ObjectClass::Mutex),
+            Event::SemaphoreBinaryCreate(_) | Event::SemaphoreCountingCreate(_) => {
+                Some(ObjectClass::Semaphore)
+            }
+            Event::EventGroupCreate(_) => Some(ObjectClass::EventGroup),
+            Event::StreamBufferCreate(_) => Some(ObjectClass::StreamBuffer),
+            Event::MessageBufferCreate(_) => Some(ObjectClass::MessageBuffer),
+            Event::StateMachineCreate(_) => Some(ObjectClass::StateMachine),
+            _ => None,
+        };
+
+        let idx = match created_class {
+            Some(class) => {
+                let idx = self.new_lifetime(None, handle);
+                let lt = &mut self.lifetimes[idx];
+                lt.class = Some(class);
+                lt.created_at = Some(timestamp);
+                idx
+            }
+            None => self.live_lifetime(None, handle),
+        };
+        let lt = &mut self.lifetimes[idx];
+        lt.num_events += 1;
+        if lt.name.is_none() {
+            lt.name = event.object_name().map(str::to_owned);
+        }
+    }
+
+    pub fn process_snapshot_event(
+        &mut self,
+        rd: &snapshot::RecorderData,
+        event_type: snapshot::event::EventType,
+        event: &snapshot::event::Event,
+    ) {
+        use snapshot::event::{Event, EventType};
+
+        let timestamp = event.timestamp();
+        match (event_type, event) {
+            (EventType::CreateObject(occ), _) => {
+                let class = occ.into_class();
+                if let Some(handle) = snapshot_handle(event) {
+                    let idx = self.new_lifetime(Some(class), handle);
+                    let lt = &mut self.lifetimes[idx];
+                    lt.class = Some(class);
+                    lt.created_at = Some(timestamp);
+                    lt.name = snapshot_object_name(&rd.object_property_table, class, handle);
+                    lt.num_events += 1;
+                }
+            }
+            (EventType::DeleteObject(occ), _) => {
+                let class = occ.into_class();
+                if let Some(handle) = snapshot_handle(event) {
+                    let idx = self.live_lifetime(Some(class), handle);
+                    self.live.remove(&(Some(class), handle));
+                    let lt = &mut self.lifetimes[idx];
+                    lt.class = Some(class);
+                    lt.deleted_at = Some(timestamp);
+                    lt.num_events += 1;
+                }
+            }
+            // Close events follow the delete event, the object's name is only
+            // available from here since its property table entry may have been reused
+            (EventType::ObjectCloseName(occ), Event::Unknown(_, record)) => {
+                let class = occ.into_class();
+                let bytes = record.as_slice();
+                let mut r = ByteOrdered::runtime(&bytes[2..], rd.endianness.into());
+                let name = r
+                    .read_u16()
+                    .ok()
+                    .and_then(|idx| ObjectHandle::new(idx.into()))
+                    .and_then(|idx| rd.symbol_table.get(idx))
+                    .map(|sym| sym.symbol.to_string());
+                let key = ObjectHandle::new(bytes[1].into());
+                let lt = key.and_then(|handle| {
+                    self.lifetimes.iter_mut().rev().find(|lt| {
+                        lt.handle == handle && lt.class == Some(class) && lt.deleted_at.is_some()
+                    })
+                });
+                if let (Some(lt), Some(name)) = (lt, name) {
+                    lt.name = Some(name);
+                }
+            }
+            _ => {
+                if let Some((class, handle)) = snapshot_object(event_type, event) {
+                    let idx = self.live_lifetime(Some(class), handle);
+                    let lt = &mut self.lifetimes[idx];
+                    if lt.name.is_none() {
+                        lt.name = snapshot_object_name(&rd.object_property_table, class, handle);
+                    }
+                    lt.num_events += 1;
+                }
+            }
+        }
+    }
+
+    /// Start a new lifetime for a created object, replacing any live object with the same handle
+    fn new_lifetime(&mut self, class: Option<ObjectClass>, handle: ObjectHandle) -> usize {
+        let idx = self.lifetimes.len();
+        self.lifetimes.push(ObjectLifetime {
+            handle,
+            class,
+            name: None,
+            created_at: None,
+            deleted_at: None,
+            num_events: 0,
+        });
+        self.live.insert((class, handle), idx);
+        idx
+    }
+
+    /// The lifetime of a live object, starting one if the object hasn't been seen yet
+    fn live_lifetime(&mut self, class: Option<ObjectClass>, handle: ObjectHandle) -> usize {
+        match self.live.get(&(class, handle)) {
+            Some(idx) => *idx,
+            None => self.new_lifetime(class, handle),
+        }
+    }
+}
+
+/// The object handle of a snapshot kernel call event record
+fn snapshot_handle(event: &snapshot::event::Event) -> Option<ObjectHandle> {
+    use snapshot::event::Event;
+    match event {
+        Event::Unknown(_, record) => ObjectHandle::new(record.as_slice()[1].into()),
+        Event::TaskCreate(ev) => Some(ev.handle),
+        _ => None,
+    }
+}
+
+/// The class and handle of the object a snapshot event references, if any
+fn snapshot_object(
+    event_type: snapshot::event::EventType,
+    event: &snapshot::event::Event,
+) -> Option<(ObjectClass, ObjectHandle)> {
+    use snapshot::event::{Event, EventType};
+    match event {
+        Event::TaskBegin(ev) | Event::TaskReady(ev) | Event::TaskResume(ev) => {
+            Some((ObjectClass::Task, ev.handle))
+        }
+        Event::IsrBegin(ev) | Event::IsrResume(ev) => Some((ObjectClass::Isr, ev.handle)),
+        Event::PendFuncCall(ev)
+        | Event::PendFuncCallFromIsr(ev)
+        | Event::PendFuncCallFailed(ev)
+        | Event::PendFuncCallFromIsrFailed(ev) => Some((ObjectClass::Task, ev.daemon_task_handle)),
+        Event::TaskInstanceFinishedNextKse(ev) | Event::TaskInstanceFinishedDirect(ev) => {
+            Some((ObjectClass::Task, ev.handle))
+        }
+        Event::Unknown(_, record) => {
+            let class = match event_type {
+                EventType::Send(occ)
+                | EventType::Receive(occ)
+                | EventType::SendFromIsr(occ)
+                | EventType::ReceiveFromIsr(occ)
+                | EventType::SendFailed(occ)
+                | EventType::ReceiveFailed(occ)
+                | EventType::SendFromIsrFailed(occ)
+                | EventType::ReceiveFromIsrFailed(occ)
+                | EventType::ReceiveBlock(occ)
+                | EventType::SendBlock(occ)
+                | EventType::Peek(occ) => occ.into_class(),
+                _ => return None,
+            };
+            ObjectHandle::new(record.as_slice()[1].into()).map(|h| (class, h))
+        }
+        _ => None,
+    }
+}
+
+fn snapshot_object_name(
+    table: &ObjectPropertyTable,
+    class: ObjectClass,
+    handle: ObjectHandle,
+) -> Option<String> {
+    let name = match class {
+        ObjectClass::Queue => table
+            .queue_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::Semaphore => table
+            .semaphore_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::Mutex => table
+            .mutex_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::Task => table
+            .task_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::Isr => table
+            .isr_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::Timer => table
+            .timer_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::EventGroup => table
+            .event_group_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::StreamBuffer => table
+            .stream_buffer_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::MessageBuffer => table
+            .message_buffer_object_properties
+            .get(&handle)
+            .map(|p| p.display_name()),
+        ObjectClass::StateMachine => None,
+    };
+    name.map(str::to_owned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{Event, EventCount, ObjectDeleteEvent, QueueCreateEvent};
+    use crate::types::ObjectName;
+
+    fn queue_create(handle: ObjectHandle, ts: u64, name: &str) -> Event {
+        Event::QueueCreate(QueueCreateEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(ts),
+            handle,
+            name: Some(ObjectName(name.into())),
+            queue_length: 1,
+        })
+    }
+
+    #[test]
+    fn streaming_handle_reuse() {
+        let h = ObjectHandle::new(0x2000_0100).unwrap();
+        let mut t = ObjectLifetimeTable::new();
+        t.process_streaming_event(&queue_create(h, 10, "q0"));
+        t.process_streaming_event(&Event::ObjectDelete(ObjectDeleteEvent {
+            event_count: EventCount(1),
+            sequence: 1,
+            timestamp: Timestamp(20),
+            handle: h,
+            class: ObjectClass::Queue,
+            name: None,
+            state: None,
+        }));
+        assert!(t.live(h).is_none());
+        t.process_streaming_event(&queue_create(h, 30, "q1"));
+
+        let lts = t.lifetimes();
+        assert_eq!(lts.len(), 2);
+        assert_eq!(lts[0].name.as_deref(), Some("q0"));
+        assert_eq!(lts[0].created_at, Some(Timestamp(10)));
+        assert_eq!(lts[0].deleted_at, Some(Timestamp(20)));
+        assert_eq!(lts[0].num_events, 2);
+        assert_eq!(lts[1].name.as_deref(), Some("q1"));
+        assert_eq!(lts[1].deleted_at, None);
+        assert_eq!(t.live(h), Some(&lts[1]));
+    }
+}
@@ -0,0 +1,216 @@
+//! Routing of the parsers' log messages
+//!
+//! By default, and when the `tracing` feature is enabled, warnings and errors about
+//! recoverable issues (i.e. malformed format strings or unsupported object classes)
+//! are emitted with `tracing`. A `LogSink` can be installed with `set_log_sink` to
+//! take over the warnings and errors, for example a `RateLimitedSink` to prevent
+//! noisy traces from flooding the logs.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, derive_more::Display)]
+pub enum Level {
+    #[display(fmt = "warn")]
+    Warn,
+    #[display(fmt = "error")]
+    Error,
+}
+
+/// A destination for the parsers' warnings and errors
+pub trait LogSink: Send + Sync {
+    fn log(&self, level: Level, message: &str);
+}
+
+impl<F> LogSink for F
+where
+    F: Fn(Level, &str) + Send + Sync,
+{
+    fn log(&self, level: Level, message: &str) {
+        self(level, message)
+    }
+}
+
+static SINK_INSTALLED: AtomicBool = AtomicBool::new(false);
+static SINK: RwLock<Option<Arc<dyn LogSink>>> = RwLock::new(None);
+
+/// Route warnings and errors to `sink` instead of `tracing`
+pub fn set_log_sink<S: LogSink + 'static>(sink: S) {
+    let mut s = SINK.write().unwrap_or_else(|e| e.into_inner());
+    *s = Some(Arc::new(sink));
+    SINK_INSTALLED.store(true, Ordering::Release);
+}
+
+/// Remove the installed sink, reverting to `tracing`
+pub fn clear_log_sink() {
+    let mut s = SINK.write().unwrap_or_else(|e| e.into_inner());
+    *s = None;
+    SINK_INSTALLED.store(false, Ordering::Release);
+}
+
+/// Hand a message to the installed sink, returns false if there isn't one
+pub(crate) fn dispatch<F: FnOnce() -> String>(level: Level, message: F) -> bool {
+    if !SINK_INSTALLED.load(Ordering::Acquire) {
+        return false;
+    }
+    let sink = SINK.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match sink {
+        Some(sink) => {
+            sink.log(level, &message());
+            true
+        }
+        None => false,
+    }
+}
+
+/// Forwards at most `max_messages` messages per `window` to the inner sink.
+///
+/// When a window with suppressed messages has elapsed, the next forwarded
+/// message is preceded by a warning with the number of suppressed messages.
+pub struct RateLimitedSink<S> {
+    inner: S,
+    max_messages: u32,
+    window: Duration,
+    state: Mutex<RateLimitState>,
+}
+
+struct RateLimitState {
+    window_start: Instant,
+    num_messages: u32,
+    num_suppressed_in_window: u64,
+    num_suppressed: u64,
+}
+
+impl<S: LogSink> RateLimitedSink<S> {
+    pub fn new(inner: S, max_messages: u32, window: Duration) -> Self {
+        Self {
+            inner,
+            max_messages,
+            window,
+            state: Mutex::new(RateLimitState {
+                window_start: Instant::now(),
+                num_messages: 0,
+                num_suppressed_in_window: 0,
+                num_suppressed: 0,
+            }),
+        }
+    }
+
+    /// Total number of messages that have been suppressed
+    pub fn num_suppressed(&self) -> u64 {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .num_suppressed
+    }
+}
+
+impl<S: LogSink> LogSink for RateLimitedSink<S> {
+    fn log(&self, level: Level, message: &str) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.window_start.elapsed() >= self.window {
+            if state.num_suppressed_in_window != 0 {
+                self.inner.log(
+                    Level::Warn,
+                    &format!("Suppressed {} log messages", state.num_suppressed_in_window),
+                );
+            }
+            state.window_start = Instant::now();
+            state.num_messages = 0;
+            state.num_suppressed_in_window = 0;
+        }
+        if state.num_messages < self.max_messages {
+            state.num_messages += 1;
+            self.inner.log(level, message);
+        } else {
+            state.num_suppressed_in_window += 1;
+            state.num_suppressed += 1;
+        }
+    }
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        debug_args!($($arg)*);
+    }};
+}
+
+/// Evaluates the fields and message arguments of a `debug!` without `tracing`,
+/// so the values that are only logged are still used
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug_args {
+    () => {};
+    ($fmt:literal $($args:tt)*) => {
+        let _ = format_args!($fmt $($args)*);
+    };
+    ($name:ident = % $value:expr $(, $($rest:tt)*)?) => {
+        let _ = &$value;
+        debug_args!($($($rest)*)?);
+    };
+    ($name:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+        let _ = &$value;
+        debug_args!($($($rest)*)?);
+    };
+    ($name:ident = $value:expr $(, $($rest:tt)*)?) => {
+        let _ = &$value;
+        debug_args!($($($rest)*)?);
+    };
+    (% $name:ident $(, $($rest:tt)*)?) => {
+        let _ = &$name;
+        debug_args!($($($rest)*)?);
+    };
+    (? $name:ident $(, $($rest:tt)*)?) => {
+        let _ = &$name;
+        debug_args!($($($rest)*)?);
+    };
+    ($name:ident $(, $($rest:tt)*)?) => {
+        let _ = &$name;
+        debug_args!($($($rest)*)?);
+    };
+}
+
+macro_rules! warn {
+    ($($arg:tt)+) => {{
+        if !$crate::logging::dispatch($crate::logging::Level::Warn, || format!($($arg)+)) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! error {
+    ($($arg:tt)+) => {{
+        if !$crate::logging::dispatch($crate::logging::Level::Error, || format!($($arg)+)) {
+            #[cfg(feature = "tracing")]
+            tracing::error!($($arg)+);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn rate_limited_sink() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        let sink = RateLimitedSink::new(
+            move |_: Level, _: &str| {
+                c.fetch_add(1, Ordering::SeqCst);
+            },
+            2,
+            Duration::from_secs(3600),
+        );
+        for _ in 0..5 {
+            sink.log(Level::Warn, "msg");
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        assert_eq!(sink.num_suppressed(), 3);
+    }
+}
@@ -0,0 +1,731 @@
+//! A machine readable manifest of the event kinds and the fields of their events,
+//! for the version of the crate in use, to generate decoders or columns from
+//!
+//! Field types are one of:
+//! * `u16`, `u32`, `u64`
+//! * `string`
+//! * `handle`: a non-zero object handle (u32)
+//! * `timestamp`: timer counter ticks (u64)
+//! * `ticks`: OS ticks (u32)
+//! * `priority`: u32
+//! * `frequency`: Hz (u32), zero when unknown
+//! * `object_class`, `timer_counter`, `task_state`: names
+//! * `heap`: the `current`, `high_water_mark` and `max` heap usage (u32)
+//! * `arguments`: the arguments of a user event
+//! * `parameters`: the raw parameters of a streaming event (u32)
+//! * `record`: the raw bytes of a snapshot event record
+//!
+//! Kinds without a typed event have the fields of the protocol's untyped event.
+
+use crate::event_kind::EventKind;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldSchema {
+    pub name: &'static str,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub ty: &'static str,
+    pub optional: bool,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EventSchema {
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub kind: EventKind,
+    /// See `EventKind::code`
+    pub code: u16,
+    /// See `EventKind::name`
+    pub name: &'static str,
+    /// The fields of the streaming event, `None` if the protocol doesn't have the kind
+    pub streaming: Option<&'static [FieldSchema]>,
+    /// The fields of the snapshot event, `None` if the protocol doesn't have the kind
+    pub snapshot: Option<&'static [FieldSchema]>,
+}
+
+impl EventSchema {
+    pub fn new(kind: EventKind) -> Self {
+        let (streaming, snapshot) = fields(kind);
+        Self {
+            kind,
+            code: kind.code(),
+            name: kind.name(),
+            streaming,
+            snapshot,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Manifest {
+    /// Version of the crate
+    pub version: &'static str,
+    pub events: Vec<EventSchema>,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            events: enum_iterator::all::<EventKind>()
+                .map(EventSchema::new)
+                .collect(),
+        }
+    }
+
+    pub fn event(&self, kind: EventKind) -> Option<&EventSchema> {
+        self.events.iter().find(|schema| schema.kind == kind)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn write_json<W: std::io::Write>(&self, w: W) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(w, self)
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const fn field(name: &'static str, ty: &'static str, optional: bool) -> FieldSchema {
+    FieldSchema { name, ty, optional }
+}
+
+fn fields(
+    kind: EventKind,
+) -> (
+    Option<&'static [FieldSchema]>,
+    Option<&'static [FieldSchema]>,
+) {
+    use EventKind::*;
+    match kind {
+        Null => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TraceStart => (Some(TRACE_START_EVENT), None),
+        TsConfig => (Some(TS_CONFIG_EVENT), None),
+        ObjectName => (Some(OBJECT_NAME_EVENT), None),
+        TaskPriority => (Some(TASK_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskPriorityInherit => (Some(TASK_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskPriorityDisinherit => (Some(TASK_EVENT), Some(SNAPSHOT_RECORD)),
+        DefineIsr => (Some(ISR_EVENT), None),
+        TaskCreate => (Some(TASK_EVENT), Some(SNAPSHOT_TASK_EVENT)),
+        TaskCreateFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskReady => (Some(TASK_EVENT), Some(SNAPSHOT_TASK_EVENT)),
+        TaskSwitchIsrBegin => (Some(ISR_EVENT), Some(SNAPSHOT_ISR_EVENT)),
+        TaskSwitchIsrResume => (Some(ISR_EVENT), Some(SNAPSHOT_ISR_EVENT)),
+        TaskSwitchTaskBegin => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_TASK_EVENT)),
+        TaskSwitchTaskResume => (Some(TASK_EVENT), Some(SNAPSHOT_TASK_EVENT)),
+        TaskActivate => (Some(TASK_EVENT), None),
+        TaskDelayUntil => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskDelay => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskSuspend => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskResume => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskResumeFromIsr => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskNotify => (Some(TASK_NOTIFY_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskNotifyWait => (Some(TASK_NOTIFY_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskNotifyWaitFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskNotifyWaitBlock => (Some(TASK_NOTIFY_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskNotifyFromIsr => (Some(TASK_NOTIFY_EVENT), Some(SNAPSHOT_RECORD)),
+        MemoryAlloc => (Some(MEMORY_EVENT), None),
+        MemoryFree => (Some(MEMORY_EVENT), None),
+        QueueCreate => (Some(QUEUE_CREATE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueCreateFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSend => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSendFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSendBlock => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSendFromIsr => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSendFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueReceive => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueReceiveFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueReceiveBlock => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueReceiveFromIsr => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueReceiveFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueuePeek => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueuePeekFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueuePeekBlock => (Some(QUEUE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueSendFront => (Some(QUEUE_EVENT), None),
+        QueueSendFrontBlock => (Some(QUEUE_EVENT), None),
+        QueueSendFrontFromIsr => (Some(QUEUE_EVENT), None),
+        MutexCreate => (Some(MUTEX_CREATE_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexCreateFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexGive => (Some(MUTEX_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexGiveFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexGiveBlock => (Some(MUTEX_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexGiveRecursive => (Some(MUTEX_EVENT), None),
+        MutexTake => (Some(MUTEX_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexTakeFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexTakeBlock => (Some(MUTEX_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexTakeRecursive => (Some(MUTEX_EVENT), None),
+        MutexTakeRecursiveBlock => (Some(MUTEX_EVENT), None),
+        SemaphoreBinaryCreate => (Some(SEMAPHORE_CREATE_EVENT), None),
+        SemaphoreBinaryCreateFailed => (Some(STREAMING_BASE_EVENT), None),
+        SemaphoreCountingCreate => (Some(SEMAPHORE_CREATE_EVENT), None),
+        SemaphoreCountingCreateFailed => (Some(STREAMING_BASE_EVENT), None),
+        SemaphoreGive => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreGiveFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreGiveBlock => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreGiveFromIsr => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreGiveFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreTake => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreTakeFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreTakeBlock => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreTakeFromIsr => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreTakeFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphorePeek => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphorePeekFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphorePeekBlock => (Some(SEMAPHORE_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerCreate => (Some(TIMER_CREATE_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerStart => (Some(TIMER_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerReset => (Some(TIMER_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerStop => (Some(TIMER_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerExpired => (Some(TIMER_EXPIRED_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupCreate => (Some(EVENT_GROUP_CREATE_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupCreateFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupSync => (Some(EVENT_GROUP_EVENT), None),
+        EventGroupWaitBits => (Some(EVENT_GROUP_EVENT), None),
+        EventGroupClearBits => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupClearBitsFromIsr => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupSetBits => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupSetBitsFromIsr => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupSyncBlock => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupWaitBitsBlock => (Some(EVENT_GROUP_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupSyncFailed => (Some(STREAMING_BASE_EVENT), None),
+        EventGroupWaitBitsFailed => (Some(STREAMING_BASE_EVENT), None),
+        StreamBufferCreate => (Some(STREAM_BUFFER_CREATE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferCreateFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferSend => (Some(STREAM_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferSendBlock => (Some(STREAM_BUFFER_BLOCK_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferSendFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReceive => (Some(STREAM_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReceiveBlock => (Some(STREAM_BUFFER_BLOCK_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReceiveFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferSendFromIsr => (Some(STREAM_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferSendFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReceiveFromIsr => (Some(STREAM_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReceiveFromIsrFailed => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferReset => (Some(STREAM_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        MessageBufferCreate => (Some(MESSAGE_BUFFER_CREATE_EVENT), None),
+        MessageBufferCreateFailed => (Some(STREAMING_BASE_EVENT), None),
+        MessageBufferSend => (Some(MESSAGE_BUFFER_EVENT), None),
+        MessageBufferSendBlock => (Some(MESSAGE_BUFFER_BLOCK_EVENT), None),
+        MessageBufferSendFailed => (Some(STREAMING_BASE_EVENT), None),
+        MessageBufferReceive => (Some(MESSAGE_BUFFER_EVENT), None),
+        MessageBufferReceiveBlock => (Some(MESSAGE_BUFFER_BLOCK_EVENT), None),
+        MessageBufferReceiveFailed => (Some(STREAMING_BASE_EVENT), None),
+        MessageBufferSendFromIsr => (Some(MESSAGE_BUFFER_EVENT), None),
+        MessageBufferSendFromIsrFailed => (Some(STREAMING_BASE_EVENT), None),
+        MessageBufferReceiveFromIsr => (Some(MESSAGE_BUFFER_EVENT), None),
+        MessageBufferReceiveFromIsrFailed => (Some(STREAMING_BASE_EVENT), None),
+        MessageBufferReset => (Some(MESSAGE_BUFFER_EVENT), Some(SNAPSHOT_RECORD)),
+        TaskDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        QueueDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        SemaphoreDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        MutexDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        TimerDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        EventGroupDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        StreamBufferDelete => (Some(OBJECT_DELETE_EVENT), Some(SNAPSHOT_RECORD)),
+        MessageBufferDelete => (Some(OBJECT_DELETE_EVENT), None),
+        StateMachineStateCreate => (Some(STATE_MACHINE_STATE_EVENT), None),
+        StateMachineCreate => (Some(STATE_MACHINE_CREATE_EVENT), None),
+        StateMachineStateChange => (Some(STATE_MACHINE_STATE_EVENT), None),
+        User => (Some(USER_EVENT), Some(SNAPSHOT_USER_EVENT)),
+        UnusedStack => (Some(UNUSED_STACK_EVENT), Some(SNAPSHOT_RECORD)),
+        Xps => (None, Some(SNAPSHOT_RECORD)),
+        NewTime => (None, Some(SNAPSHOT_RECORD)),
+        ObjectCloseName => (None, Some(SNAPSHOT_RECORD)),
+        ObjectCloseProperty => (None, Some(SNAPSHOT_RECORD)),
+        CreateObject => (None, Some(SNAPSHOT_RECORD)),
+        Send => (None, Some(SNAPSHOT_RECORD)),
+        Receive => (None, Some(SNAPSHOT_RECORD)),
+        SendFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        ReceiveFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        CreateObjectFailed => (None, Some(SNAPSHOT_RECORD)),
+        SendFailed => (None, Some(SNAPSHOT_RECORD)),
+        ReceiveFailed => (None, Some(SNAPSHOT_RECORD)),
+        SendFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        ReceiveFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        ReceiveBlock => (None, Some(SNAPSHOT_RECORD)),
+        SendBlock => (None, Some(SNAPSHOT_RECORD)),
+        Peek => (None, Some(SNAPSHOT_RECORD)),
+        DeleteObject => (None, Some(SNAPSHOT_RECORD)),
+        PendFuncCall => (None, Some(SNAPSHOT_PEND_FUNC_CALL_EVENT)),
+        PendFuncCallFromIsr => (None, Some(SNAPSHOT_PEND_FUNC_CALL_EVENT)),
+        PendFuncCallFailed => (None, Some(SNAPSHOT_PEND_FUNC_CALL_EVENT)),
+        PendFuncCallFromIsrFailed => (None, Some(SNAPSHOT_PEND_FUNC_CALL_EVENT)),
+        MemoryMallocSize => (None, Some(SNAPSHOT_RECORD)),
+        MemoryMallocAddress => (None, Some(SNAPSHOT_RECORD)),
+        MemoryFreeSize => (None, Some(SNAPSHOT_RECORD)),
+        MemoryFreeAddress => (None, Some(SNAPSHOT_RECORD)),
+        Xts8 => (None, Some(SNAPSHOT_RECORD)),
+        Xts16 => (None, Some(SNAPSHOT_RECORD)),
+        EventBeingWritten => (None, Some(SNAPSHOT_RECORD)),
+        ReservedDummyCode => (None, Some(SNAPSHOT_RECORD)),
+        LowPowerBegin => (None, Some(SNAPSHOT_LOW_POWER_EVENT)),
+        LowPowerEnd => (None, Some(SNAPSHOT_LOW_POWER_EVENT)),
+        Xid => (None, Some(SNAPSHOT_RECORD)),
+        Xts16l => (None, Some(SNAPSHOT_RECORD)),
+        TimerChangePeriod => (None, Some(SNAPSHOT_RECORD)),
+        TimerDeleteObject => (None, Some(SNAPSHOT_RECORD)),
+        TimerStartFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        TimerResetFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        TimerStopFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        TimerCreateFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerStartFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerResetFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerStopFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerChangePeriodFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerDeleteFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerStartFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerResetFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        TimerStopFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupSyncEnd => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupWaitBitsEnd => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupDeleteObject => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupSyncEndFailed => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupWaitBitsEndFailed => (None, Some(SNAPSHOT_RECORD)),
+        EventGroupSetBitsFromIsrFailed => (None, Some(SNAPSHOT_RECORD)),
+        TaskInstanceFinishedNextKse => (None, Some(SNAPSHOT_TASK_INSTANCE_FINISHED_EVENT)),
+        TaskInstanceFinishedDirect => (None, Some(SNAPSHOT_TASK_INSTANCE_FINISHED_EVENT)),
+        TaskNotifyTake => (None, Some(SNAPSHOT_RECORD)),
+        TaskNotifyTakeBlock => (None, Some(SNAPSHOT_RECORD)),
+        TaskNotifyTakeFailed => (None, Some(SNAPSHOT_RECORD)),
+        TaskNotifyGiveFromIsr => (None, Some(SNAPSHOT_RECORD)),
+        MutexPeekBlock => (None, Some(SNAPSHOT_RECORD)),
+        MutexPeekFailed => (None, Some(SNAPSHOT_RECORD)),
+        StreamBufferObjectCloseName => (None, Some(SNAPSHOT_RECORD)),
+        MessageBufferObjectCloseName => (None, Some(SNAPSHOT_RECORD)),
+        StreamBufferObjectCloseProperty => (None, Some(SNAPSHOT_RECORD)),
+        MessageBufferObjectCloseProperty => (None, Some(SNAPSHOT_RECORD)),
+        MemoryMallocSizeFailed => (None, Some(SNAPSHOT_RECORD)),
+        MemoryFreeAddressFailed => (None, Some(SNAPSHOT_RECORD)),
+        Dropped => (Some(DROPPED_EVENT), None),
+        Annotation => (Some(ANNOTATION_EVENT), None),
+        SessionBoundary => (Some(SESSION_BOUNDARY_EVENT), None),
+        TaskCoreAffinitySet => (Some(TASK_CORE_AFFINITY_EVENT), None),
+        TaskMigrate => (Some(TASK_MIGRATE_EVENT), None),
+        Unknown => (Some(STREAMING_BASE_EVENT), Some(SNAPSHOT_RECORD)),
+    }
+}
+
+const ANNOTATION_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("label", "string", false),
+    field("note", "string", true),
+];
+
+const DROPPED_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("around_timestamp", "timestamp", false),
+    field("count", "u64", false),
+];
+
+const EVENT_GROUP_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("event_bits", "u32", false),
+];
+
+const EVENT_GROUP_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("bits", "u32", false),
+];
+
+const ISR_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("priority", "priority", false),
+    field("symbolized", "string", true),
+];
+
+const MEMORY_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("address", "u32", false),
+    field("size", "u32", false),
+    field("heap", "heap", false),
+    field("symbolized", "string", true),
+    field("task", "handle", true),
+    field("task_name", "string", true),
+];
+
+const MESSAGE_BUFFER_BLOCK_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+];
+
+const MESSAGE_BUFFER_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("buffer_size", "u32", false),
+];
+
+const MESSAGE_BUFFER_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("bytes_in_buffer", "u32", false),
+];
+
+const MUTEX_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+];
+
+const MUTEX_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("ticks_to_wait", "ticks", true),
+];
+
+const OBJECT_DELETE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("class", "object_class", false),
+    field("name", "string", true),
+    field("state", "u32", true),
+];
+
+const OBJECT_NAME_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+];
+
+const QUEUE_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("queue_length", "u32", false),
+];
+
+const QUEUE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("ticks_to_wait", "ticks", true),
+    field("messages_waiting", "u32", false),
+];
+
+const SEMAPHORE_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("count", "u32", true),
+];
+
+const SEMAPHORE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("ticks_to_wait", "ticks", true),
+    field("count", "u32", false),
+];
+
+const SESSION_BOUNDARY_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("session", "u32", false),
+];
+
+const SNAPSHOT_ISR_EVENT: &[FieldSchema] = &[
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("priority", "priority", false),
+    field("timestamp", "timestamp", false),
+];
+
+const SNAPSHOT_LOW_POWER_EVENT: &[FieldSchema] = &[field("timestamp", "timestamp", false)];
+
+const SNAPSHOT_PEND_FUNC_CALL_EVENT: &[FieldSchema] = &[
+    field("daemon_task_handle", "handle", false),
+    field("daemon_task_name", "string", false),
+    field("timestamp", "timestamp", false),
+];
+
+const SNAPSHOT_RECORD: &[FieldSchema] = &[
+    field("timestamp", "timestamp", false),
+    field("record", "record", false),
+];
+
+const SNAPSHOT_TASK_EVENT: &[FieldSchema] = &[
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("state", "task_state", false),
+    field("priority", "priority", false),
+    field("timestamp", "timestamp", false),
+];
+
+const SNAPSHOT_TASK_INSTANCE_FINISHED_EVENT: &[FieldSchema] = &[
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("timestamp", "timestamp", false),
+];
+
+const SNAPSHOT_USER_EVENT: &[FieldSchema] = &[
+    field("timestamp", "timestamp", false),
+    field("channel", "string", false),
+    field("format_string", "string", false),
+    field("formatted_string", "string", false),
+    field("args", "arguments", false),
+];
+
+const STATE_MACHINE_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+];
+
+const STATE_MACHINE_STATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("state_handle", "handle", false),
+    field("state", "string", false),
+];
+
+const STREAMING_BASE_EVENT: &[FieldSchema] = &[
+    field("code", "u16", false),
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("parameters", "parameters", false),
+];
+
+const STREAM_BUFFER_BLOCK_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+];
+
+const STREAM_BUFFER_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("buffer_size", "u32", false),
+];
+
+const STREAM_BUFFER_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("bytes_in_buffer", "u32", false),
+];
+
+const TASK_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("priority", "priority", false),
+];
+
+const TASK_CORE_AFFINITY_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("affinity_mask", "u32", false),
+];
+
+const TASK_MIGRATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", false),
+    field("from_core", "u32", false),
+    field("to_core", "u32", false),
+];
+
+const TASK_NOTIFY_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("task_name", "string", true),
+    field("ticks_to_wait", "ticks", true),
+    field("index", "u32", true),
+    field("value", "u32", true),
+];
+
+const TIMER_CREATE_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("period", "ticks", false),
+];
+
+const TIMER_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("name", "string", true),
+    field("value", "u32", false),
+];
+
+const TIMER_EXPIRED_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("callback", "u32", false),
+    field("timer_id", "u32", false),
+];
+
+const TRACE_START_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("current_task_handle", "handle", false),
+    field("current_task", "string", false),
+];
+
+const TS_CONFIG_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("frequency", "frequency", false),
+    field("tick_rate_hz", "u32", false),
+    field("hwtc_type", "timer_counter", false),
+    field("isr_chaining_threshold", "u32", false),
+    field("htc_period", "u32", true),
+];
+
+const UNUSED_STACK_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("handle", "handle", false),
+    field("task", "string", false),
+    field("low_mark", "u32", false),
+];
+
+const USER_EVENT: &[FieldSchema] = &[
+    field("event_count", "u16", false),
+    field("sequence", "u64", false),
+    field("timestamp", "timestamp", false),
+    field("channel", "string", false),
+    field("format_string", "string", false),
+    field("formatted_string", "string", false),
+    field("args", "arguments", false),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn typed_and_untyped_fields() {
+        let manifest = Manifest::new();
+        assert_eq!(
+            manifest.events.len(),
+            enum_iterator::cardinality::<EventKind>()
+        );
+
+        let queue_send = manifest.event(EventKind::QueueSend).unwrap();
+        let names: Vec<_> = queue_send
+            .streaming
+            .unwrap()
+            .iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "event_count",
+                "sequence",
+                "timestamp",
+                "handle",
+                "name",
+                "ticks_to_wait",
+                "messages_waiting"
+            ]
+        );
+        assert_eq!(queue_send.snapshot, Some(SNAPSHOT_RECORD));
+
+        let low_power = manifest.event(EventKind::LowPowerBegin).unwrap();
+        assert_eq!(low_power.streaming, None);
+        assert_eq!(low_power.snapshot.unwrap()[0].ty, "timestamp");
+
+        assert_eq!(
+            manifest.event(EventKind::TaskDelay).unwrap().streaming,
+            Some(STREAMING_BASE_EVENT)
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let mut out = Vec::new();
+        Manifest::new().write_json(&mut out).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let user = json["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|ev| ev["name"] == "user")
+            .unwrap();
+        assert_eq!(user["code"], EventKind::User.code());
+        assert_eq!(
+            user["snapshot"][4],
+            serde_json::json!({"name": "args", "type": "arguments", "optional": false})
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Trace-wide metadata of a recording, for either protocol
+//!
+//! The recorder data provides most of it, the trace start task and session
+//! duration are filled in as the events are processed.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::time::StreamingClock;
+use crate::time::{ConvertedTime, Frequency, Timestamp};
+use crate::types::{KernelPortIdentity, KernelVersion, PlatformCfgVersion, Protocol};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SessionInfo {
+    pub protocol: Protocol,
+    /// Only provided by the streaming protocol
+    pub platform_cfg: Option<String>,
+    pub platform_cfg_version: Option<PlatformCfgVersion>,
+    pub kernel_port: KernelPortIdentity,
+    pub kernel_version: KernelVersion,
+    pub timer_frequency: Frequency,
+    /// Only provided by the streaming protocol
+    pub os_tick_rate: Option<Frequency>,
+    pub num_cores: u32,
+    /// The task running when the trace started, or the first task switched in
+    /// when the protocol doesn't record it
+    pub start_task: Option<String>,
+    /// Ticks between the first and last event processed
+    pub duration: Timestamp,
+
+    clock: StreamingClock,
+    first_timestamp: Option<Timestamp>,
+}
+
+impl SessionInfo {
+    pub fn from_streaming(rd: &streaming::RecorderData) -> Self {
+        Self {
+            protocol: rd.protocol,
+            platform_cfg: Some(rd.header.platform_cfg.clone()),
+            platform_cfg_version: Some(rd.header.platform_cfg_version),
+            kernel_port: rd.header.kernel_port,
+            kernel_version: rd.header.kernel_version,
+            timer_frequency: rd.timestamp_info.timer_frequency,
+            os_tick_rate: Some(rd.timestamp_info.os_tick_rate_hz),
+            num_cores: rd.header.num_cores,
+            start_task: None,
+            duration: Timestamp::zero(),
+            clock: StreamingClock::default(),
+            first_timestamp: None,
+        }
+    }
+
+    pub fn from_snapshot(rd: &snapshot::RecorderData) -> Self {
+        Self {
+            protocol: rd.protocol,
+            platform_cfg: None,
+            platform_cfg_version: None,
+            kernel_port: rd.kernel_port,
+            kernel_version: rd.kernel_version,
+            timer_frequency: rd.frequency,
+            os_tick_rate: None,
+            num_cores: 1,
+            start_task: None,
+            duration: Timestamp::zero(),
+            clock: StreamingClock::default(),
+            first_timestamp: None,
+        }
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        use streaming::event::Event;
+
+        self.duration = self.clock.time(event.timestamp());
+        if self.start_task.is_none() {
+            match event {
+                Event::TraceStart(ev) => self.start_task = Some(ev.current_task.to_string()),
+                Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                    self.start_task = Some(ev.name.to_string())
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        use snapshot::event::Event;
+
+        let t = event.timestamp();
+        let first = *self.first_timestamp.get_or_insert(t);
+        self.duration = Timestamp(t.ticks().saturating_sub(first.ticks()));
+        if self.start_task.is_none() {
+            if let Event::TaskBegin(ev) | Event::TaskResume(ev) = event {
+                self.start_task = Some(ev.name.to_string());
+            }
+        }
+    }
+
+    /// The session duration, `None` when the timer frequency is unknown
+    pub fn wall_duration(&self) -> Option<Duration> {
+        self.duration.to_time(self.timer_frequency).duration()
+    }
+}
+
+/// One `key: value` line per field, suitable as a report header
+impl fmt::Display for SessionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "protocol: {}", self.protocol)?;
+        if let Some(platform_cfg) = &self.platform_cfg {
+            writeln!(f, "platform_cfg: {platform_cfg}")?;
+        }
+        if let Some(version) = &self.platform_cfg_version {
+            writeln!(f, "platform_cfg_version: {version}")?;
+        }
+        writeln!(f, "kernel_port: {}", self.kernel_port)?;
+        writeln!(f, "kernel_version: {}", self.kernel_version)?;
+        writeln!(f, "timer_frequency: {}", self.timer_frequency)?;
+        if let Some(rate) = &self.os_tick_rate {
+            writeln!(f, "os_tick_rate: {rate}")?;
+        }
+        writeln!(f, "num_cores: {}", self.num_cores)?;
+        if let Some(task) = &self.start_task {
+            writeln!(f, "start_task: {task}")?;
+        }
+        match self.duration.to_time(self.timer_frequency) {
+            ConvertedTime::Duration(d) => write!(f, "duration: {d:?}"),
+            ConvertedTime::TickOnly(ticks) => write!(f, "duration: {ticks} ticks"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, EventRecord, TaskEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::snapshot::recorder_data::test::recorder_data;
+    use crate::types::{ObjectHandle, ObjectName, Priority};
+
+    fn task_begin(name: &str, t: u64) -> Event {
+        Event::TaskBegin(TaskEvent {
+            handle: ObjectHandle::new(1).unwrap(),
+            name: ObjectName(name.into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(t),
+        })
+    }
+
+    fn unknown(t: u64) -> Event {
+        Event::Unknown(Timestamp(t), EventRecord::new([0xAC, 0, 0, 0]))
+    }
+
+    #[test]
+    fn snapshot_start_task_and_duration() {
+        let mut info = SessionInfo::from_snapshot(&recorder_data(8, 5, 5));
+        assert_eq!(info.num_cores, 1);
+        assert_eq!(info.platform_cfg, None);
+        assert_eq!(info.start_task, None);
+
+        info.process_snapshot_event(&unknown(100));
+        info.process_snapshot_event(&task_begin("first", 110));
+        info.process_snapshot_event(&task_begin("second", 150));
+        assert_eq!(info.start_task.as_deref(), Some("first"));
+        assert_eq!(info.duration, Timestamp(50));
+
+        // Out of order timestamps don't underflow
+        info.process_snapshot_event(&unknown(90));
+        assert_eq!(info.duration, Timestamp::zero());
+    }
+
+    #[test]
+    fn unknown_frequency_reports_ticks() {
+        let mut info = SessionInfo::from_snapshot(&recorder_data(8, 5, 5));
+        info.process_snapshot_event(&unknown(10));
+        info.process_snapshot_event(&unknown(15));
+        assert_eq!(info.wall_duration(), None);
+        let s = info.to_string();
+        assert!(s.ends_with("duration: 5 ticks"), "{s}");
+        assert!(!s.contains("start_task"), "{s}");
+        assert!(!s.contains("os_tick_rate"), "{s}");
+    }
+}
@@ -0,0 +1,196 @@
+//! Log levels of user event channels
+//!
+//! Firmware commonly logs through one channel per level ("err", "warn", "info", ...).
+//! A `SeverityMap` declares which channels map to which level, so user events
+//! can be filtered by, or colored by, their severity.
+//! Channels without a declared level can have one guessed from their name,
+//! see `Severity::guess`.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::types::UserEventChannel;
+use derive_more::Display;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Ordered from the least to the most severe
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Severity {
+    #[display(fmt = "trace")]
+    Trace,
+    #[display(fmt = "debug")]
+    Debug,
+    #[display(fmt = "info")]
+    Info,
+    #[display(fmt = "warn")]
+    Warn,
+    #[display(fmt = "error")]
+    Error,
+}
+
+impl Severity {
+    /// Guess the level from common channel naming conventions, i.e. "err",
+    /// "net_warn" or "DEBUG". Each word of the name is tried, the most severe
+    /// match wins.
+    pub fn guess(channel: &str) -> Option<Self> {
+        channel
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(|word| word.parse().ok())
+            .max()
+    }
+
+    /// The ANSI SGR escape sequence coloring terminal output of this level
+    pub fn ansi_color(self) -> &'static str {
+        match self {
+            Severity::Trace => "\x1b[2m",
+            Severity::Debug => "\x1b[34m",
+            Severity::Info => "\x1b[32m",
+            Severity::Warn => "\x1b[33m",
+            Severity::Error => "\x1b[31m",
+        }
+    }
+
+    /// `text` wrapped in the ANSI color of this level
+    pub fn paint(self, text: &str) -> String {
+        format!("{}{text}\x1b[0m", self.ansi_color())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown severity '{0}'")]
+pub struct UnknownSeverity(pub String);
+
+/// Accepts the level names and their usual abbreviations and aliases,
+/// case insensitive
+impl FromStr for Severity {
+    type Err = UnknownSeverity;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Severity::*;
+        Ok(match s.to_lowercase().trim() {
+            "trace" | "trc" | "verbose" | "vrb" => Trace,
+            "debug" | "dbg" => Debug,
+            "info" | "inf" | "notice" | "log" => Info,
+            "warn" | "wrn" | "warning" => Warn,
+            "error" | "err" | "fatal" | "crit" | "critical" | "panic" | "assert" => Error,
+            _ => return Err(UnknownSeverity(s.to_owned())),
+        })
+    }
+}
+
+/// Levels of user event channels
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SeverityMap {
+    channels: BTreeMap<String, Severity>,
+    guess: bool,
+    default: Option<Severity>,
+}
+
+impl SeverityMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_channel<S: Into<String>>(mut self, channel: S, severity: Severity) -> Self {
+        self.channels.insert(channel.into(), severity);
+        self
+    }
+
+    pub fn add_channel<S: Into<String>>(&mut self, channel: S, severity: Severity) {
+        self.channels.insert(channel.into(), severity);
+    }
+
+    /// Guess the level of channels that weren't declared, see `Severity::guess`
+    pub fn with_guessing(mut self) -> Self {
+        self.guess = true;
+        self
+    }
+
+    /// The level of channels that weren't declared and couldn't be guessed
+    pub fn with_default(mut self, severity: Severity) -> Self {
+        self.default = Some(severity);
+        self
+    }
+
+    pub fn severity(&self, channel: &UserEventChannel) -> Option<Severity> {
+        let channel = channel.as_str();
+        self.channels
+            .get(channel)
+            .copied()
+            .or_else(|| self.guess.then(|| Severity::guess(channel)).flatten())
+            .or(self.default)
+    }
+
+    /// The level of a user event, `None` for other events
+    pub fn streaming_event_severity(&self, event: &streaming::event::Event) -> Option<Severity> {
+        match event {
+            streaming::event::Event::User(ev) => self.severity(&ev.channel),
+            _ => None,
+        }
+    }
+
+    /// The level of a user event, `None` for other events
+    pub fn snapshot_event_severity(&self, event: &snapshot::event::Event) -> Option<Severity> {
+        match event {
+            snapshot::event::Event::User(ev) => self.severity(&ev.channel),
+            _ => None,
+        }
+    }
+
+    /// Whether a log-level filter of `min` keeps the event.
+    /// Only user events are filtered, those of a channel without a level are
+    /// dropped.
+    pub fn keep_streaming_event(&self, event: &streaming::event::Event, min: Severity) -> bool {
+        match event {
+            streaming::event::Event::User(ev) => self.severity(&ev.channel) >= Some(min),
+            _ => true,
+        }
+    }
+
+    /// Whether a log-level filter of `min` keeps the event, see `keep_streaming_event`
+    pub fn keep_snapshot_event(&self, event: &snapshot::event::Event, min: Severity) -> bool {
+        match event {
+            snapshot::event::Event::User(ev) => self.severity(&ev.channel) >= Some(min),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn channel(s: &str) -> UserEventChannel {
+        UserEventChannel::Custom(s.to_owned())
+    }
+
+    #[test]
+    fn guess_levels() {
+        assert_eq!(Severity::guess("err"), Some(Severity::Error));
+        assert_eq!(Severity::guess("WARNING"), Some(Severity::Warn));
+        assert_eq!(Severity::guess("net_dbg"), Some(Severity::Debug));
+        assert_eq!(Severity::guess("app.info"), Some(Severity::Info));
+        assert_eq!(Severity::guess("debug/error"), Some(Severity::Error));
+        assert_eq!(Severity::guess("errors"), None);
+        assert_eq!(Severity::guess("gpio"), None);
+    }
+
+    #[test]
+    fn declared_guessed_and_default_levels() {
+        let map = SeverityMap::new()
+            .with_channel("gpio", Severity::Debug)
+            .with_channel("warn", Severity::Error);
+        assert_eq!(map.severity(&channel("gpio")), Some(Severity::Debug));
+        assert_eq!(map.severity(&channel("warn")), Some(Severity::Error));
+        assert_eq!(map.severity(&channel("info")), None);
+
+        let map = map.with_guessing().with_default(Severity::Info);
+        assert_eq!(map.severity(&channel("warn")), Some(Severity::Error));
+        assert_eq!(map.severity(&channel("fatal")), Some(Severity::Error));
+        assert_eq!(
+            map.severity(&UserEventChannel::Default),
+            Some(Severity::Info)
+        );
+        assert!(Severity::Warn > Severity::Info);
+    }
+}
@@ -5,13 +5,22 @@ use derive_more::{Binary, Deref, Display, Into, LowerHex, Octal, UpperHex};
 pub use isr::{IsrBeginEvent, IsrEvent, IsrResumeEvent};
 pub use low_power::{LowPowerBeginEvent, LowPowerEndEvent, LowPowerEvent};
 pub use parser::EventParser;
+pub use pend_func_call::{
+    PendFuncCallEvent, PendFuncCallFailedEvent, PendFuncCallFromIsrEvent,
+    PendFuncCallFromIsrFailedEvent,
+};
 pub use task::{TaskBeginEvent, TaskCreateEvent, TaskEvent, TaskReadyEvent, TaskResumeEvent};
+pub use task_instance::{
+    TaskInstanceFinishedDirectEvent, TaskInstanceFinishedEvent, TaskInstanceFinishedNextKseEvent,
+};
 pub use user::UserEvent;
 
 pub mod isr;
 pub mod low_power;
 pub mod parser;
+pub mod pend_func_call;
 pub mod task;
+pub mod task_instance;
 pub mod user;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
@@ -709,11 +718,25 @@ pub enum Event {
     #[display(fmt = "TaskCreate({_0})")]
     TaskCreate(TaskCreateEvent),
 
+    #[display(fmt = "TaskInstanceFinishedNextKse({_0})")]
+    TaskInstanceFinishedNextKse(TaskInstanceFinishedNextKseEvent),
+    #[display(fmt = "TaskInstanceFinishedDirect({_0})")]
+    TaskInstanceFinishedDirect(TaskInstanceFinishedDirectEvent),
+
     #[display(fmt = "LowPowerBegin({_0})")]
     LowPowerBegin(LowPowerBeginEvent),
     #[display(fmt = "LowPowerEnd({_0})")]
     LowPowerEnd(LowPowerEndEvent),
 
+    #[display(fmt = "PendFuncCall({_0})")]
+    PendFuncCall(PendFuncCallEvent),
+    #[display(fmt = "PendFuncCallFromIsr({_0})")]
+    PendFuncCallFromIsr(PendFuncCallFromIsrEvent),
+    #[display(fmt = "PendFuncCallFailed({_0})")]
+    PendFuncCallFailed(PendFuncCallFailedEvent),
+    #[display(fmt = "PendFuncCallFromIsrFailed({_0})")]
+    PendFuncCallFromIsrFailed(PendFuncCallFromIsrFailedEvent),
+
     #[display(fmt = "User({_0})")]
     User(UserEvent),
 
@@ -731,8 +754,14 @@ impl Event {
             TaskReady(e) => e.timestamp,
             TaskResume(e) => e.timestamp,
             TaskCreate(e) => e.timestamp,
+            TaskInstanceFinishedNextKse(e) => e.timestamp,
+            TaskInstanceFinishedDirect(e) => e.timestamp,
             LowPowerBegin(e) => e.timestamp,
             LowPowerEnd(e) => e.timestamp,
+            PendFuncCall(e) => e.timestamp,
+            PendFuncCallFromIsr(e) => e.timestamp,
+            PendFuncCallFailed(e) => e.timestamp,
+            PendFuncCallFromIsrFailed(e) => e.timestamp,
             User(e) => e.timestamp,
             Unknown(t, _e) => *t,
         }
@@ -1,16 +1,17 @@
+use crate::intern::InternPool;
 use crate::snapshot::event::*;
 use crate::snapshot::object_properties::ObjectPropertyTable;
 use crate::snapshot::symbol_table::SymbolTable;
 use crate::time::{DifferentialTimestamp, Dts16, Dts8};
 use crate::types::{
-    format_symbol_string, FormatString, FormattedString, FormattedStringError, ObjectHandle,
+    FormatCache, FormatString, FormattedString, FormattedStringError, Heap, ObjectHandle,
     ObjectName, Protocol, UserEventChannel,
 };
 use byteordered::{ByteOrdered, Endianness};
 use derive_more::From;
+use std::collections::BTreeSet;
 use std::io;
 use thiserror::Error;
-use tracing::error;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -45,7 +46,7 @@ pub enum Error {
     Io(#[from] io::Error),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct EventParser {
     /// Endianness of the data
     endianness: Endianness,
@@ -57,11 +58,30 @@ pub struct EventParser {
     /// an event to form a complete differential timestamp for the event
     dts_for_next_event: DifferentialTimestamp,
 
+    /// Parameter upper bytes from an XPS event that precedes an event to form
+    /// the complete parameter of the event
+    xps_for_next_event: u32,
+
+    /// The task that was most recently switched in
+    current_task: Option<ObjectHandle>,
+
     /// Number of user event argument records that follow the base user event record
     user_arg_record_count: usize,
 
     /// User event record buffer, all other events are single records
     user_event_records: Vec<EventRecord>,
+
+    /// Only user events on these channels are produced, when set
+    user_event_channels: Option<BTreeSet<UserEventChannel>>,
+
+    /// User event format strings compiled so far
+    format_cache: FormatCache,
+
+    /// Heap usage maintained from the memory events, `max` is unknown (zero)
+    heap: Heap,
+
+    /// See `set_intern_pool`
+    intern_pool: Option<InternPool>,
 }
 
 impl EventParser {
@@ -70,11 +90,67 @@ impl EventParser {
             endianness,
             accumulated_time: Timestamp::zero(),
             dts_for_next_event: DifferentialTimestamp::zero(),
+            xps_for_next_event: 0,
+            current_task: None,
             user_arg_record_count: 0,
             user_event_records: Vec::with_capacity(UserEventArgRecordCount::MAX),
+            user_event_channels: None,
+            format_cache: FormatCache::default(),
+            heap: Heap::default(),
+            intern_pool: None,
+        }
+    }
+
+    /// Intern the object names from here on in the given pool, user event format
+    /// strings share the symbols of the symbol table
+    pub fn set_intern_pool(&mut self, pool: InternPool) {
+        self.intern_pool = Some(pool);
+    }
+
+    /// Only produce user events on the given channels, the others are skipped
+    /// before their format string is rendered. `None` produces all user events.
+    pub fn set_user_event_channel_filter(&mut self, channels: Option<BTreeSet<UserEventChannel>>) {
+        self.user_event_channels = channels;
+    }
+
+    fn object_name(&self, name: &str) -> ObjectName {
+        match &self.intern_pool {
+            Some(pool) => ObjectName(pool.intern(name)),
+            None => ObjectName(name.into()),
+        }
+    }
+
+    /// The heap usage from the memory events parsed so far
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// The timestamp accumulated from the differential timestamps so far
+    pub(crate) fn accumulated_time(&self) -> Timestamp {
+        self.accumulated_time
+    }
+
+    /// The state carried from one event to the next, see `restore_replay_state`
+    pub(crate) fn replay_state(&self) -> ReplayState {
+        ReplayState {
+            accumulated_time: self.accumulated_time,
+            dts_for_next_event: self.dts_for_next_event,
+            xps_for_next_event: self.xps_for_next_event,
+            current_task: self.current_task,
+            heap: self.heap,
         }
     }
 
+    /// Resume parsing at an event boundary from a previously captured state
+    pub(crate) fn restore_replay_state(&mut self, state: ReplayState) {
+        self.accumulated_time = state.accumulated_time;
+        self.dts_for_next_event = state.dts_for_next_event;
+        self.xps_for_next_event = state.xps_for_next_event;
+        self.current_task = state.current_task;
+        self.heap = state.heap;
+        self.end_user_event();
+    }
+
     pub fn parse(
         &mut self,
         obj_props: &ObjectPropertyTable,
@@ -106,7 +182,7 @@ impl EventParser {
                     .ok_or(Error::ObjectLookup(handle))?;
                 let event = IsrEvent {
                     handle,
-                    name: ObjectName(obj.display_name().to_string()),
+                    name: self.object_name(obj.display_name()),
                     priority: obj.priority(),
                     timestamp: self.get_timestamp(dts.into()),
                 };
@@ -134,11 +210,14 @@ impl EventParser {
                     .ok_or(Error::ObjectLookup(handle))?;
                 let event = TaskEvent {
                     handle,
-                    name: ObjectName(obj.display_name().to_string()),
+                    name: self.object_name(obj.display_name()),
                     state: obj.state(),
                     priority: obj.current_priority(),
                     timestamp: self.get_timestamp(dts.into()),
                 };
+                if event_type != EventType::TaskReady {
+                    self.current_task = Some(handle);
+                }
                 Some((
                     event_type,
                     match event_type {
@@ -161,7 +240,7 @@ impl EventParser {
                             event_type,
                             Event::TaskCreate(TaskEvent {
                                 handle,
-                                name: ObjectName(obj.display_name().to_string()),
+                                name: self.object_name(obj.display_name()),
                                 state: obj.state(),
                                 priority: obj.current_priority(),
                                 timestamp: self.accumulated_time,
@@ -198,6 +277,17 @@ impl EventParser {
                 None
             }
 
+            // XPS events hold the upper bytes of the parameter of the following event,
+            // when it doesn't fit in its record. The raw record is still passed back.
+            EventType::Xps => {
+                let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
+                let _event_code = r.read_u8()?;
+                let xps_8 = r.read_u8()?;
+                let xps_16 = r.read_u16()?;
+                self.xps_for_next_event = u32::from(xps_16) << 16 | u32::from(xps_8) << 8;
+                Some((event_type, Event::Unknown(self.accumulated_time, record)))
+            }
+
             EventType::LowPowerBegin | EventType::LowPowerEnd => {
                 let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
                 let _event_code = r.read_u8()?;
@@ -272,12 +362,37 @@ impl EventParser {
             | EventType::PendFuncCallFromIsr
             | EventType::PendFuncCallFailed
             | EventType::PendFuncCallFromIsrFailed => {
-                self.parse_generic_kernel_call(&record)?;
-                Some((event_type, Event::Unknown(self.accumulated_time, record)))
+                // The object is the timer daemon task
+                let handle = self.parse_generic_kernel_call(&record)?;
+                let obj = obj_props
+                    .task_object_properties
+                    .get(&handle)
+                    .ok_or(Error::ObjectLookup(handle))?;
+                let event = PendFuncCallEvent {
+                    daemon_task_handle: handle,
+                    daemon_task_name: self.object_name(obj.display_name()),
+                    timestamp: self.accumulated_time,
+                };
+                Some((
+                    event_type,
+                    match event_type {
+                        EventType::PendFuncCall => Event::PendFuncCall(event),
+                        EventType::PendFuncCallFromIsr => Event::PendFuncCallFromIsr(event),
+                        EventType::PendFuncCallFailed => Event::PendFuncCallFailed(event),
+                        _ /*EventType::PendFuncCallFromIsrFailed*/ => {
+                            Event::PendFuncCallFromIsrFailed(event)
+                        }
+                    },
+                ))
             }
 
             EventType::MemoryMallocSize | EventType::MemoryFreeSize => {
-                self.parse_generic_mem_size(&record)?;
+                let size = self.parse_generic_mem_size(&record)?;
+                if event_type == EventType::MemoryMallocSize {
+                    self.heap.handle_alloc(size);
+                } else {
+                    self.heap.handle_free(size);
+                }
                 Some((event_type, Event::Unknown(self.accumulated_time, record)))
             }
 
@@ -341,7 +456,29 @@ impl EventParser {
 
             EventType::TaskInstanceFinishedNextKse | EventType::TaskInstanceFinishedDirect => {
                 self.parse_generic_task_instance_status(&record)?;
-                Some((event_type, Event::Unknown(self.accumulated_time, record)))
+                // The running task isn't known until the first task switch
+                match self.current_task {
+                    Some(handle) => {
+                        let obj = obj_props
+                            .task_object_properties
+                            .get(&handle)
+                            .ok_or(Error::ObjectLookup(handle))?;
+                        let event = TaskInstanceFinishedEvent {
+                            handle,
+                            name: self.object_name(obj.display_name()),
+                            timestamp: self.accumulated_time,
+                        };
+                        Some((
+                            event_type,
+                            if event_type == EventType::TaskInstanceFinishedNextKse {
+                                Event::TaskInstanceFinishedNextKse(event)
+                            } else {
+                                Event::TaskInstanceFinishedDirect(event)
+                            },
+                        ))
+                    }
+                    None => Some((event_type, Event::Unknown(self.accumulated_time, record))),
+                }
             }
 
             EventType::TaskNotify
@@ -418,12 +555,22 @@ impl EventParser {
         // Add it to the accumulated time
         self.accumulated_time += self.dts_for_next_event;
 
-        // Done with the DTS
+        // Done with the DTS, and the XPS if the event didn't have a parameter
         self.dts_for_next_event.clear();
+        self.xps_for_next_event = 0;
 
         self.accumulated_time
     }
 
+    /// Combines an event's parameter (lower 8 or 16 bits) with the possibly
+    /// existing XPS parameter bytes.
+    /// Must be called before `get_timestamp`, which clears the XPS bytes.
+    fn get_param(&mut self, param: u32) -> u32 {
+        let param = self.xps_for_next_event | param;
+        self.xps_for_next_event = 0;
+        param
+    }
+
     /// Process the DTS portion of a record containing a `struct KernelCall`
     fn parse_generic_kernel_call(&mut self, record: &EventRecord) -> Result<ObjectHandle, Error> {
         let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
@@ -435,37 +582,44 @@ impl EventParser {
         Ok(obj_handle)
     }
 
-    /// Process the DTS portion of a record containing a `struct KernelCallWithParamAndHandle`
-    fn parse_generic_kernel_call_with_param(&mut self, record: &EventRecord) -> Result<(), Error> {
+    /// Process the DTS portion of a record containing a `struct KernelCallWithParamAndHandle`,
+    /// returns the object handle, if any, and the parameter
+    fn parse_generic_kernel_call_with_param(
+        &mut self,
+        record: &EventRecord,
+    ) -> Result<(Option<ObjectHandle>, u32), Error> {
         let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
         let _event_code = r.read_u8()?;
-        let _obj_handle = r.read_u8()?;
-        let _param = r.read_u8()?;
+        let obj_handle = ObjectHandle::new(r.read_u8()?.into());
+        let param = self.get_param(r.read_u8()?.into());
         let dts = Dts8(r.read_u8()?);
         let _timestamp = self.get_timestamp(dts.into());
-        Ok(())
+        Ok((obj_handle, param))
     }
 
-    /// Process the DTS portion of a record containing a `struct KernelCallWithParam16`
+    /// Process the DTS portion of a record containing a `struct KernelCallWithParam16`,
+    /// returns the parameter
     fn parse_generic_kernel_call_with_numeric_param(
         &mut self,
         record: &EventRecord,
-    ) -> Result<(), Error> {
+    ) -> Result<u32, Error> {
         let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
         let _event_code = r.read_u8()?;
         let dts = Dts8(r.read_u8()?);
+        let param = self.get_param(r.read_u16()?.into());
         let _timestamp = self.get_timestamp(dts.into());
-        Ok(())
+        Ok(param)
     }
 
-    /// Process the DTS portion of a record containing a `struct MemEventSize`
-    fn parse_generic_mem_size(&mut self, record: &EventRecord) -> Result<(), Error> {
+    /// Process the DTS portion of a record containing a `struct MemEventSize`,
+    /// returns the size
+    fn parse_generic_mem_size(&mut self, record: &EventRecord) -> Result<u32, Error> {
         let mut r = ByteOrdered::runtime(record.as_slice(), self.endianness);
         let _event_code = r.read_u8()?;
         let dts = Dts8(r.read_u8()?);
-        let _size = r.read_u16()?;
+        let size = self.get_param(r.read_u16()?.into());
         let _timestamp = self.get_timestamp(dts.into());
-        Ok(())
+        Ok(size)
     }
 
     /// Process the DTS portion of a record containing a `struct TaskInstanceStatusEvent`
@@ -528,6 +682,17 @@ impl EventParser {
                 })
                 .unwrap_or(UserEventChannel::Default);
 
+            if self
+                .user_event_channels
+                .as_ref()
+                .is_some_and(|channels| !channels.contains(&channel))
+            {
+                // Still accounts for the event's time
+                self.get_timestamp(dts.into());
+                self.end_user_event();
+                return Ok(None);
+            }
+
             let arg_bytes: Vec<u8> = self
                 .user_event_records
                 .iter()
@@ -535,13 +700,15 @@ impl EventParser {
                 .flat_map(|r| r.as_slice().iter())
                 .cloned()
                 .collect();
-            let (formatted_string, args) = match format_symbol_string(
-                symbol_table,
-                Protocol::Snapshot,
-                self.endianness.into(),
-                &sym_entry.symbol,
-                &arg_bytes,
-            ) {
+            let (formatted_string, args) = match self
+                .format_cache
+                .get(Some(format_string_index), &sym_entry.symbol)
+                .render(
+                    symbol_table,
+                    Protocol::Snapshot,
+                    self.endianness.into(),
+                    &arg_bytes,
+                ) {
                 Ok((fs, args)) => (fs, args),
                 Err(e) => {
                     error!("Failed to parse user event format string arguments, using the raw symbol instead. {e}");
@@ -567,8 +734,184 @@ impl EventParser {
     }
 }
 
+/// The parser state needed to decode an event on its own, i.e. from an index.
+/// Everything else (configuration, caches) doesn't depend on the preceding events.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct ReplayState {
+    accumulated_time: Timestamp,
+    dts_for_next_event: DifferentialTimestamp,
+    xps_for_next_event: u32,
+    current_task: Option<ObjectHandle>,
+    heap: Heap,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, From)]
 enum Dts {
     Dts8(Dts8),
     Dts16(Dts16),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::object_properties::ObjectProperties;
+    use std::sync::Arc;
+
+    #[test]
+    fn xps_extends_the_next_param() {
+        let obj_props = ObjectPropertyTable::default();
+        let symbol_table = SymbolTable::default();
+        let mut p = EventParser::new(Endianness::Little);
+
+        // XPS with the upper 16 bits of the following 16-bit malloc size
+        let xps = EventRecord::new([0x01, 0x00, 0x01, 0x00]);
+        let (et, ev) = p.parse(&obj_props, &symbol_table, xps).unwrap().unwrap();
+        assert_eq!(et, EventType::Xps);
+        assert_eq!(ev, Event::Unknown(Timestamp::zero(), xps));
+
+        let malloc = EventRecord::new([0x94, 0x05, 0x34, 0x12]);
+        let (et, _) = p.parse(&obj_props, &symbol_table, malloc).unwrap().unwrap();
+        assert_eq!(et, EventType::MemoryMallocSize);
+        assert_eq!(p.heap().current, 0x0001_1234);
+        assert_eq!(p.accumulated_time(), Timestamp(5));
+
+        // Only applies to the following event
+        p.parse(&obj_props, &symbol_table, malloc).unwrap().unwrap();
+        assert_eq!(p.heap().current, 0x0001_1234 + 0x1234);
+    }
+
+    #[test]
+    fn object_names_interned_in_the_shared_pool() {
+        let mut obj_props = ObjectPropertyTable::default();
+        let handle = ObjectHandle::new(3).unwrap();
+        obj_props.task_object_properties.insert(
+            handle,
+            ObjectProperties::new(Some("worker".to_owned()), [1, 1, 0, 0]),
+        );
+        let symbol_table = SymbolTable::default();
+        let pool = InternPool::new();
+        let begin = EventRecord::new([0x06, 0x03, 0x0A, 0x00]);
+
+        let names = (0..2)
+            .map(|_| {
+                let mut p = EventParser::new(Endianness::Little);
+                p.set_intern_pool(pool.clone());
+                match p.parse(&obj_props, &symbol_table, begin).unwrap() {
+                    Some((_, Event::TaskBegin(ev))) => ev.name,
+                    ev => panic!("unexpected event {ev:?}"),
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names[0].as_ref(), "worker");
+        assert!(Arc::ptr_eq(&names[0].0, &names[1].0));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn task_instance_finished_attached_to_running_task() {
+        let mut obj_props = ObjectPropertyTable::default();
+        let handle = ObjectHandle::new(3).unwrap();
+        obj_props.task_object_properties.insert(
+            handle,
+            ObjectProperties::new(Some("worker".to_owned()), [1, 1, 0, 0]),
+        );
+        let symbol_table = SymbolTable::default();
+        let mut p = EventParser::new(Endianness::Little);
+
+        // Before any task switch the running task isn't known
+        let finished = EventRecord::new([0xD1, 0x00, 0x00, 0x02]);
+        let (_, ev) = p
+            .parse(&obj_props, &symbol_table, finished)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(ev, Event::Unknown(..)));
+
+        let begin = EventRecord::new([0x06, 0x03, 0x0A, 0x00]);
+        p.parse(&obj_props, &symbol_table, begin).unwrap().unwrap();
+        let (_, ev) = p
+            .parse(&obj_props, &symbol_table, finished)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ev,
+            Event::TaskInstanceFinishedDirect(TaskInstanceFinishedEvent {
+                handle,
+                name: ObjectName("worker".into()),
+                timestamp: Timestamp(14),
+            })
+        );
+    }
+
+    #[test]
+    fn pend_func_call_attached_to_daemon_task() {
+        let mut obj_props = ObjectPropertyTable::default();
+        let daemon = ObjectHandle::new(2).unwrap();
+        obj_props.task_object_properties.insert(
+            daemon,
+            ObjectProperties::new(Some("Tmr Svc".to_owned()), [1, 1, 0, 0]),
+        );
+        let symbol_table = SymbolTable::default();
+        let mut p = EventParser::new(Endianness::Little);
+
+        let pend = EventRecord::new([0x90, 0x02, 0x05, 0x00]);
+        let (event_type, ev) = p.parse(&obj_props, &symbol_table, pend).unwrap().unwrap();
+        assert_eq!(event_type, EventType::PendFuncCall);
+        assert_eq!(
+            ev,
+            Event::PendFuncCall(PendFuncCallEvent {
+                daemon_task_handle: daemon,
+                daemon_task_name: ObjectName("Tmr Svc".into()),
+                timestamp: Timestamp(5),
+            })
+        );
+
+        let failed = EventRecord::new([0x93, 0x02, 0x03, 0x00]);
+        let (_, ev) = p.parse(&obj_props, &symbol_table, failed).unwrap().unwrap();
+        assert_eq!(
+            ev,
+            Event::PendFuncCallFromIsrFailed(PendFuncCallEvent {
+                daemon_task_handle: daemon,
+                daemon_task_name: ObjectName("Tmr Svc".into()),
+                timestamp: Timestamp(8),
+            })
+        );
+
+        // The handle isn't in the task object property table
+        let unknown = EventRecord::new([0x91, 0x04, 0x01, 0x00]);
+        let res = p.parse(&obj_props, &symbol_table, unknown);
+        assert!(matches!(res, Err(Error::ObjectLookup(h)) if h == ObjectHandle::new(4).unwrap()));
+    }
+
+    #[test]
+    fn user_event_channel_filter() {
+        use crate::snapshot::symbol_table::SymbolCrc6;
+        use crate::types::SymbolString;
+
+        let obj_props = ObjectPropertyTable::default();
+        let mut symbol_table = SymbolTable::default();
+        let channel = ObjectHandle::new(1).unwrap();
+        let fmt = ObjectHandle::new(8).unwrap();
+        for (handle, channel_index, symbol) in [(channel, None, "ch"), (fmt, Some(channel), "hi")] {
+            symbol_table.insert(
+                handle,
+                channel_index,
+                SymbolCrc6::new(symbol.as_bytes()),
+                SymbolString(symbol.into()),
+            );
+        }
+        let mut p = EventParser::new(Endianness::Little);
+        p.set_user_event_channel_filter(Some(
+            [UserEventChannel::Custom("other".to_owned())].into(),
+        ));
+
+        // User event without arguments, format string at 8
+        let user = EventRecord::new([0x98, 0x03, 0x08, 0x00]);
+        assert!(p.parse(&obj_props, &symbol_table, user).unwrap().is_none());
+        assert_eq!(p.accumulated_time(), Timestamp(3));
+
+        p.set_user_event_channel_filter(Some([UserEventChannel::Custom("ch".to_owned())].into()));
+        let (_, ev) = p.parse(&obj_props, &symbol_table, user).unwrap().unwrap();
+        assert_eq!(ev.timestamp(), Timestamp(6));
+        assert!(matches!(ev, Event::User(ue) if ue.formatted_string.0 == "hi"));
+    }
+}
@@ -0,0 +1,19 @@
+use crate::time::Timestamp;
+use crate::types::{ObjectHandle, TaskName};
+use derive_more::Display;
+
+/// A function call pended to the timer daemon task (`xTimerPendFunctionCall`).
+///
+/// Snapshot records don't carry the pended function itself, the event
+/// references the timer daemon task that will execute it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:'{daemon_task_name}'")]
+pub struct PendFuncCallEvent {
+    pub daemon_task_handle: ObjectHandle,
+    pub daemon_task_name: TaskName,
+    pub timestamp: Timestamp,
+}
+
+pub type PendFuncCallFromIsrEvent = PendFuncCallEvent;
+pub type PendFuncCallFailedEvent = PendFuncCallEvent;
+pub type PendFuncCallFromIsrFailedEvent = PendFuncCallEvent;
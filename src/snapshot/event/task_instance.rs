@@ -0,0 +1,18 @@
+use crate::time::Timestamp;
+use crate::types::{ObjectHandle, TaskName};
+use derive_more::Display;
+
+/// The end of a task instance (job), from `vTraceInstanceFinishedNow` or
+/// `vTraceInstanceFinishedNext`, attached to the task that was running
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:'{name}'")]
+pub struct TaskInstanceFinishedEvent {
+    pub handle: ObjectHandle,
+    pub name: TaskName,
+    pub timestamp: Timestamp,
+}
+
+/// The instance finishes at the task's next kernel service call (i.e. when it blocks)
+pub type TaskInstanceFinishedNextKseEvent = TaskInstanceFinishedEvent;
+/// The instance finished at the event's timestamp
+pub type TaskInstanceFinishedDirectEvent = TaskInstanceFinishedEvent;
@@ -1,5 +1,7 @@
 use crate::time::Timestamp;
-use crate::types::{Argument, FormatString, FormattedString, UserEventChannel};
+use crate::types::{
+    key_value_fields, Argument, Arguments, FormatString, FormattedString, UserEventChannel,
+};
 use derive_more::Display;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
@@ -9,5 +11,12 @@ pub struct UserEvent {
     pub channel: UserEventChannel,
     pub format_string: FormatString,
     pub formatted_string: FormattedString,
-    pub args: Vec<Argument>,
+    pub args: Arguments,
+}
+
+impl UserEvent {
+    /// The `key=value` fields of the event, see `key_value_fields`
+    pub fn key_values(&self) -> Vec<(String, Argument)> {
+        key_value_fields(&self.format_string, &self.args)
+    }
 }
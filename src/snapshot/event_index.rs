@@ -0,0 +1,211 @@
+use crate::snapshot::event::parser::ReplayState;
+use crate::snapshot::event::{Event, EventRecord, EventType};
+use crate::snapshot::{Error, RecorderData};
+use crate::time::Timestamp;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::io::{Read, Seek};
+use std::ops::Range;
+
+/// An index of the events in a snapshot's event buffer with their absolute timestamps.
+///
+/// Building the index reads the event records into memory and accumulates the
+/// differential timestamps (including XTS records) once. Events can then be
+/// decoded in any order, see `RecorderData::event_index`.
+#[derive(Clone, Debug)]
+pub struct EventIndex {
+    records: Vec<EventRecord>,
+    /// Absolute time after each record was processed
+    record_timestamps: Vec<Timestamp>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    /// The records making up the event, including any preceding XTS records
+    records: Range<usize>,
+    event_type: EventType,
+    timestamp: Timestamp,
+    /// Parser state at the start of the event's records
+    state: ReplayState,
+}
+
+impl EventIndex {
+    pub(crate) fn build<R: Read + Seek + Send>(
+        rd: &RecorderData,
+        r: &mut R,
+    ) -> Result<Self, Error> {
+        let records = rd.event_records(r)?.collect::<Result<Vec<_>, Error>>()?;
+        let mut record_timestamps = Vec::with_capacity(records.len());
+        let mut entries = Vec::new();
+
+        let mut parser = rd.event_parser();
+        let mut start_index = 0;
+        let mut start_state = parser.replay_state();
+        for (index, record) in records.iter().enumerate() {
+            let res = parser.parse(&rd.object_property_table, &rd.symbol_table, *record);
+            record_timestamps.push(parser.accumulated_time());
+            let (event_type, timestamp) = match res {
+                Ok(None) => continue,
+                Ok(Some((event_type, event))) => (event_type, event.timestamp()),
+                Err(_) => (
+                    EventType::from(records[start_index].event_code()),
+                    parser.accumulated_time(),
+                ),
+            };
+            entries.push(Entry {
+                records: start_index..index + 1,
+                event_type,
+                timestamp,
+                state: start_state,
+            });
+            start_index = index + 1;
+            start_state = parser.replay_state();
+        }
+
+        Ok(Self {
+            records,
+            record_timestamps,
+            entries,
+        })
+    }
+
+    /// Number of events
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn event_type(&self, index: usize) -> Option<EventType> {
+        self.entries.get(index).map(|e| e.event_type)
+    }
+
+    pub fn timestamp(&self, index: usize) -> Option<Timestamp> {
+        self.entries.get(index).map(|e| e.timestamp)
+    }
+
+    /// The event records, oldest first
+    pub fn records(&self) -> &[EventRecord] {
+        &self.records
+    }
+
+    /// The absolute timestamp of each event record, XTS records carry the
+    /// timestamp of the preceding record
+    pub fn record_timestamps(&self) -> &[Timestamp] {
+        &self.record_timestamps
+    }
+
+    /// Index of the first event at or after `timestamp`
+    pub fn find(&self, timestamp: Timestamp) -> usize {
+        self.entries.partition_point(|e| e.timestamp < timestamp)
+    }
+
+    /// Decode the event at `index`
+    pub fn event(
+        &self,
+        rd: &RecorderData,
+        index: usize,
+    ) -> Option<Result<(EventType, Event), Error>> {
+        let entry = self.entries.get(index)?;
+        let mut parser = rd.event_parser();
+        parser.restore_replay_state(entry.state);
+        let mut res = Ok(None);
+        for record in self.records[entry.records.clone()].iter() {
+            res = parser.parse(&rd.object_property_table, &rd.symbol_table, *record);
+        }
+        res.map_err(Error::from).transpose()
+    }
+
+    /// Decode the events in the given range of indices
+    pub fn events<'a>(
+        &'a self,
+        rd: &'a RecorderData,
+        range: Range<usize>,
+    ) -> impl DoubleEndedIterator<Item = Result<(EventType, Event), Error>> + 'a {
+        range.filter_map(move |index| self.event(rd, index))
+    }
+
+    /// Decode the events in the given range of indices on the rayon thread pool.
+    ///
+    /// Each event is decoded from the parser state recorded while building the
+    /// index, collecting the iterator keeps the events in order.
+    #[cfg(feature = "rayon")]
+    pub fn par_events<'a>(
+        &'a self,
+        rd: &'a RecorderData,
+        range: Range<usize>,
+    ) -> impl ParallelIterator<Item = Result<(EventType, Event), Error>> + 'a {
+        range
+            .into_par_iter()
+            .filter_map(move |index| self.event(rd, index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::recorder_data::test::recorder_data;
+    use std::io::Cursor;
+
+    #[test]
+    fn xts_merges_and_undecodable_events() {
+        let mut data = Vec::new();
+        for record in [
+            // Low power begin, dts 2
+            [0xAC, 0, 2, 0],
+            // XTS16, upper bytes of the next event's dts
+            [0xA9, 0, 1, 0],
+            [0xAC, 0, 3, 0],
+            // ISR begin on an ISR missing from the object property table
+            [0x04, 9, 1, 0],
+            [0xAC, 0, 1, 0],
+        ] {
+            data.extend_from_slice(&record);
+        }
+        let rd = recorder_data(8, 5, 5);
+        let index = rd.event_index(&mut Cursor::new(data)).unwrap();
+
+        assert_eq!(index.len(), 4);
+        assert!(!index.is_empty());
+        assert_eq!(index.records().len(), 5);
+        // The XTS record carries the timestamp of the preceding record
+        assert_eq!(index.record_timestamps()[1], Timestamp(2));
+        assert_eq!(index.timestamp(1), Some(Timestamp(0x10005)));
+
+        // The undecodable event still gets an entry
+        assert_eq!(index.event_type(2), Some(EventType::TaskSwitchIsrBegin));
+        assert_eq!(index.timestamp(2), Some(Timestamp(0x10005)));
+        assert!(matches!(index.event(&rd, 2), Some(Err(_))));
+        // and the following events decode from their own state
+        assert_eq!(
+            index.event(&rd, 3).unwrap().unwrap().1.timestamp(),
+            index.timestamp(3).unwrap()
+        );
+        assert_eq!(index.events(&rd, 0..4).filter(Result::is_err).count(), 1);
+    }
+
+    #[test]
+    fn out_of_range_lookups() {
+        let rd = recorder_data(8, 0, 0);
+        let index = rd.event_index(&mut Cursor::new(Vec::new())).unwrap();
+        assert!(index.is_empty());
+        assert!(index.event(&rd, 0).is_none());
+        assert_eq!(index.event_type(0), None);
+        assert_eq!(index.find(Timestamp(1)), 0);
+
+        let mut data = Vec::new();
+        for dts in [1, 2] {
+            data.extend_from_slice(&[0xAC, 0, dts, 0]);
+        }
+        let rd = recorder_data(8, 2, 2);
+        let index = rd.event_index(&mut Cursor::new(data)).unwrap();
+        assert!(index.event(&rd, 2).is_none());
+        assert_eq!(index.timestamp(2), None);
+        // Past the last event
+        assert_eq!(index.find(Timestamp(100)), index.len());
+        assert_eq!(index.events(&rd, 1..5).count(), 1);
+    }
+}
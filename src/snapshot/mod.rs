@@ -1,11 +1,17 @@
 pub use error::Error;
-pub use object_properties::ObjectPropertyTable;
-pub use recorder_data::RecorderData;
+pub use event_index::EventIndex;
+pub use object_properties::{GenericObjectProperties, ObjectPropertyTable};
+pub use recorder_data::{HeapReconciliation, RecorderData, WindowedEvent};
+pub use status::RecorderStatus;
+pub use streaming_converter::{ConversionStats, StreamingConverter};
 pub use symbol_table::{SymbolTable, SymbolTableEntry};
 
 pub mod error;
 pub mod event;
+pub mod event_index;
 pub mod markers;
 pub mod object_properties;
 pub mod recorder_data;
+pub mod status;
+pub mod streaming_converter;
 pub mod symbol_table;
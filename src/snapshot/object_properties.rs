@@ -1,11 +1,13 @@
+use crate::table_cache::{self, CacheReader, CacheWriter, TableCacheError, TableKind};
 use crate::types::{
-    IsrPriority, ObjectClass, ObjectHandle, Priority, TaskPriority, UNNAMED_OBJECT,
+    Endianness, IsrPriority, ObjectClass, ObjectHandle, Priority, TaskPriority, UNNAMED_OBJECT,
 };
 use derive_more::{Display, Into};
 use std::collections::BTreeMap;
+use std::io::{Read, Write};
 use std::marker::PhantomData;
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct ObjectPropertyTable {
     pub queue_object_properties: BTreeMap<ObjectHandle, ObjectProperties<QueueObjectClass>>,
     pub semaphore_object_properties: BTreeMap<ObjectHandle, ObjectProperties<SemaphoreObjectClass>>,
@@ -21,6 +23,227 @@ pub struct ObjectPropertyTable {
         BTreeMap<ObjectHandle, ObjectProperties<MessageBufferObjectClass>>,
 }
 
+impl ObjectPropertyTable {
+    /// Handle of the first object of the given class with the given name
+    pub fn find_by_name(&self, class: ObjectClass, name: &str) -> Option<ObjectHandle> {
+        fn find<C: ObjectClassExt>(
+            props: &BTreeMap<ObjectHandle, ObjectProperties<C>>,
+            name: &str,
+        ) -> Option<ObjectHandle> {
+            props
+                .iter()
+                .find_map(|(h, p)| (p.name() == Some(name)).then_some(*h))
+        }
+
+        match class {
+            ObjectClass::Queue => find(&self.queue_object_properties, name),
+            ObjectClass::Semaphore => find(&self.semaphore_object_properties, name),
+            ObjectClass::Mutex => find(&self.mutex_object_properties, name),
+            ObjectClass::Task => find(&self.task_object_properties, name),
+            ObjectClass::Isr => find(&self.isr_object_properties, name),
+            ObjectClass::Timer => find(&self.timer_object_properties, name),
+            ObjectClass::EventGroup => find(&self.event_group_object_properties, name),
+            ObjectClass::StreamBuffer => find(&self.stream_buffer_object_properties, name),
+            ObjectClass::MessageBuffer => find(&self.message_buffer_object_properties, name),
+            ObjectClass::StateMachine => None,
+        }
+    }
+
+    /// Properties of the object with the given class and handle
+    pub fn get(
+        &self,
+        class: ObjectClass,
+        handle: ObjectHandle,
+    ) -> Option<GenericObjectProperties<'_>> {
+        use GenericObjectProperties as G;
+        Some(match class {
+            ObjectClass::Queue => G::Queue(self.queue_object_properties.get(&handle)?),
+            ObjectClass::Semaphore => G::Semaphore(self.semaphore_object_properties.get(&handle)?),
+            ObjectClass::Mutex => G::Mutex(self.mutex_object_properties.get(&handle)?),
+            ObjectClass::Task => G::Task(self.task_object_properties.get(&handle)?),
+            ObjectClass::Isr => G::Isr(self.isr_object_properties.get(&handle)?),
+            ObjectClass::Timer => G::Timer(self.timer_object_properties.get(&handle)?),
+            ObjectClass::EventGroup => {
+                G::EventGroup(self.event_group_object_properties.get(&handle)?)
+            }
+            ObjectClass::StreamBuffer => {
+                G::StreamBuffer(self.stream_buffer_object_properties.get(&handle)?)
+            }
+            ObjectClass::MessageBuffer => {
+                G::MessageBuffer(self.message_buffer_object_properties.get(&handle)?)
+            }
+            ObjectClass::StateMachine => return None,
+        })
+    }
+
+    /// Iterate over all of the objects, by class then handle
+    pub fn iter(&self) -> impl Iterator<Item = ObjectEntry<'_>> + '_ {
+        fn class_iter<'a, C: ObjectClassExt + 'a>(
+            props: &'a BTreeMap<ObjectHandle, ObjectProperties<C>>,
+            f: fn(&'a ObjectProperties<C>) -> GenericObjectProperties<'a>,
+        ) -> impl Iterator<Item = ObjectEntry<'a>> + 'a {
+            props
+                .iter()
+                .map(move |(h, p)| (C::class(), *h, p.name(), f(p)))
+        }
+
+        use GenericObjectProperties as G;
+        class_iter(&self.queue_object_properties, G::Queue)
+            .chain(class_iter(&self.semaphore_object_properties, G::Semaphore))
+            .chain(class_iter(&self.mutex_object_properties, G::Mutex))
+            .chain(class_iter(&self.task_object_properties, G::Task))
+            .chain(class_iter(&self.isr_object_properties, G::Isr))
+            .chain(class_iter(&self.timer_object_properties, G::Timer))
+            .chain(class_iter(
+                &self.event_group_object_properties,
+                G::EventGroup,
+            ))
+            .chain(class_iter(
+                &self.stream_buffer_object_properties,
+                G::StreamBuffer,
+            ))
+            .chain(class_iter(
+                &self.message_buffer_object_properties,
+                G::MessageBuffer,
+            ))
+    }
+
+    /// Write the table to the compact table cache format, see `table_cache`
+    pub fn save<W: Write>(&self, w: W) -> Result<(), TableCacheError> {
+        let mut w = table_cache::write_header(w, TableKind::ObjectPropertyTable)?;
+        save_class(&mut w, &self.queue_object_properties)?;
+        save_class(&mut w, &self.semaphore_object_properties)?;
+        save_class(&mut w, &self.mutex_object_properties)?;
+        save_class(&mut w, &self.task_object_properties)?;
+        save_class(&mut w, &self.isr_object_properties)?;
+        save_class(&mut w, &self.timer_object_properties)?;
+        save_class(&mut w, &self.event_group_object_properties)?;
+        save_class(&mut w, &self.stream_buffer_object_properties)?;
+        save_class(&mut w, &self.message_buffer_object_properties)?;
+        Ok(())
+    }
+
+    /// Read a table previously written with `ObjectPropertyTable::save`
+    pub fn load<R: Read>(r: R) -> Result<Self, TableCacheError> {
+        let mut r = table_cache::read_header(r, TableKind::ObjectPropertyTable)?;
+        Ok(ObjectPropertyTable {
+            queue_object_properties: load_class(&mut r)?,
+            semaphore_object_properties: load_class(&mut r)?,
+            mutex_object_properties: load_class(&mut r)?,
+            task_object_properties: load_class(&mut r)?,
+            isr_object_properties: load_class(&mut r)?,
+            timer_object_properties: load_class(&mut r)?,
+            event_group_object_properties: load_class(&mut r)?,
+            stream_buffer_object_properties: load_class(&mut r)?,
+            message_buffer_object_properties: load_class(&mut r)?,
+        })
+    }
+}
+
+/// An object's class, handle, name and properties, see `ObjectPropertyTable::iter`
+pub type ObjectEntry<'a> = (
+    ObjectClass,
+    ObjectHandle,
+    Option<&'a str>,
+    GenericObjectProperties<'a>,
+);
+
+/// A reference to the properties of an object of any class, see `ObjectPropertyTable::get`.
+///
+/// Match on the variant to get at the class-specific accessors.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum GenericObjectProperties<'a> {
+    Queue(&'a ObjectProperties<QueueObjectClass>),
+    Semaphore(&'a ObjectProperties<SemaphoreObjectClass>),
+    Mutex(&'a ObjectProperties<MutexObjectClass>),
+    Task(&'a ObjectProperties<TaskObjectClass>),
+    Isr(&'a ObjectProperties<IsrObjectClass>),
+    Timer(&'a ObjectProperties<TimerObjectClass>),
+    EventGroup(&'a ObjectProperties<EventGroupObjectClass>),
+    StreamBuffer(&'a ObjectProperties<StreamBufferObjectClass>),
+    MessageBuffer(&'a ObjectProperties<MessageBufferObjectClass>),
+}
+
+impl<'a> GenericObjectProperties<'a> {
+    pub fn class(&self) -> ObjectClass {
+        use GenericObjectProperties::*;
+        match self {
+            Queue(p) => p.class(),
+            Semaphore(p) => p.class(),
+            Mutex(p) => p.class(),
+            Task(p) => p.class(),
+            Isr(p) => p.class(),
+            Timer(p) => p.class(),
+            EventGroup(p) => p.class(),
+            StreamBuffer(p) => p.class(),
+            MessageBuffer(p) => p.class(),
+        }
+    }
+
+    pub fn name(&self) -> Option<&'a str> {
+        use GenericObjectProperties::*;
+        match self {
+            Queue(p) => p.name(),
+            Semaphore(p) => p.name(),
+            Mutex(p) => p.name(),
+            Task(p) => p.name(),
+            Isr(p) => p.name(),
+            Timer(p) => p.name(),
+            EventGroup(p) => p.name(),
+            StreamBuffer(p) => p.name(),
+            MessageBuffer(p) => p.name(),
+        }
+    }
+
+    pub fn display_name(&self) -> &'a str {
+        self.name().unwrap_or(UNNAMED_OBJECT)
+    }
+
+    /// The raw property bytes, their meaning depends on the class
+    pub fn raw_properties(&self) -> [u8; 4] {
+        use GenericObjectProperties::*;
+        match self {
+            Queue(p) => p.properties,
+            Semaphore(p) => p.properties,
+            Mutex(p) => p.properties,
+            Task(p) => p.properties,
+            Isr(p) => p.properties,
+            Timer(p) => p.properties,
+            EventGroup(p) => p.properties,
+            StreamBuffer(p) => p.properties,
+            MessageBuffer(p) => p.properties,
+        }
+    }
+}
+
+fn save_class<W: Write, C: ObjectClassExt>(
+    w: &mut CacheWriter<W>,
+    props: &BTreeMap<ObjectHandle, ObjectProperties<C>>,
+) -> Result<(), TableCacheError> {
+    table_cache::write_len(w, props.len())?;
+    for (handle, p) in props.iter() {
+        w.write_u32(u32::from(*handle))?;
+        table_cache::write_opt_str(w, p.name())?;
+        w.write_all(&p.properties)?;
+    }
+    Ok(())
+}
+
+fn load_class<R: Read, C: ObjectClassExt>(
+    r: &mut CacheReader<R>,
+) -> Result<BTreeMap<ObjectHandle, ObjectProperties<C>>, TableCacheError> {
+    let len = table_cache::read_len(r)?;
+    let mut props = BTreeMap::new();
+    for _ in 0..len {
+        let handle = table_cache::read_handle(r)?;
+        let name = table_cache::read_opt_string(r)?;
+        let mut properties = [0; 4];
+        r.read_exact(&mut properties)?;
+        props.insert(handle, ObjectProperties::new(name, properties));
+    }
+    Ok(props)
+}
+
 pub trait ObjectClassExt {
     fn class() -> ObjectClass;
 }
@@ -184,6 +407,33 @@ impl ObjectClassExt for TimerObjectClass {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum TimerState {
+    #[display(fmt = "Dormant")]
+    Dormant,
+    #[display(fmt = "Active")]
+    Active,
+}
+
+/// Timers have a single property byte, the object state.
+///
+/// The period isn't part of the snapshot properties, it's only recorded as the
+/// parameter of `TimerChangePeriod` events.
+impl ObjectProperties<TimerObjectClass> {
+    pub fn state(&self) -> TimerState {
+        if self.properties[0] == 0 {
+            TimerState::Dormant
+        } else {
+            TimerState::Active
+        }
+    }
+
+    /// The raw object state byte
+    pub fn raw_state(&self) -> u8 {
+        self.properties[0]
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct EventGroupObjectClass;
 impl ObjectClassExt for EventGroupObjectClass {
@@ -192,6 +442,14 @@ impl ObjectClassExt for EventGroupObjectClass {
     }
 }
 
+/// Event groups have 4 property bytes, the event bits in the target's byte order
+impl ObjectProperties<EventGroupObjectClass> {
+    /// The event bits
+    pub fn bits(&self, endianness: Endianness) -> u32 {
+        u32_from_bytes(self.properties, endianness)
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct StreamBufferObjectClass;
 impl ObjectClassExt for StreamBufferObjectClass {
@@ -200,6 +458,14 @@ impl ObjectClassExt for StreamBufferObjectClass {
     }
 }
 
+/// Stream buffers have 4 property bytes, the number of bytes in the buffer
+/// in the target's byte order
+impl ObjectProperties<StreamBufferObjectClass> {
+    pub fn bytes_in_buffer(&self, endianness: Endianness) -> u32 {
+        u32_from_bytes(self.properties, endianness)
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct MessageBufferObjectClass;
 impl ObjectClassExt for MessageBufferObjectClass {
@@ -207,3 +473,94 @@ impl ObjectClassExt for MessageBufferObjectClass {
         ObjectClass::MessageBuffer
     }
 }
+
+/// Message buffers have the same properties as stream buffers, the number of
+/// bytes in the buffer (including the message length prefixes) in the target's byte order
+impl ObjectProperties<MessageBufferObjectClass> {
+    pub fn bytes_in_buffer(&self, endianness: Endianness) -> u32 {
+        u32_from_bytes(self.properties, endianness)
+    }
+}
+
+fn u32_from_bytes(bytes: [u8; 4], endianness: Endianness) -> u32 {
+    match endianness {
+        Endianness::Little => u32::from_le_bytes(bytes),
+        Endianness::Big => u32::from_be_bytes(bytes),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table() -> ObjectPropertyTable {
+        let mut t = ObjectPropertyTable::default();
+        let h = |h| ObjectHandle::new(h).unwrap();
+        t.task_object_properties.insert(
+            h(3),
+            ObjectProperties::new(Some("worker".to_owned()), [1, 1, 0, 0]),
+        );
+        t.queue_object_properties.insert(
+            h(1),
+            ObjectProperties::new(Some("worker".to_owned()), [0; 4]),
+        );
+        t.isr_object_properties
+            .insert(h(2), ObjectProperties::new(None, [0, 4, 0, 0]));
+        t
+    }
+
+    #[test]
+    fn find_by_name_and_iter() {
+        let t = table();
+        let h = |h| ObjectHandle::new(h).unwrap();
+        assert_eq!(t.find_by_name(ObjectClass::Task, "worker"), Some(h(3)));
+        assert_eq!(t.find_by_name(ObjectClass::Queue, "worker"), Some(h(1)));
+        assert_eq!(t.find_by_name(ObjectClass::Mutex, "worker"), None);
+        assert_eq!(
+            t.iter()
+                .map(|(class, handle, name, _)| (class, handle, name))
+                .collect::<Vec<_>>(),
+            vec![
+                (ObjectClass::Queue, h(1), Some("worker")),
+                (ObjectClass::Task, h(3), Some("worker")),
+                (ObjectClass::Isr, h(2), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_access() {
+        let t = table();
+        let h = |h| ObjectHandle::new(h).unwrap();
+        let p = t.get(ObjectClass::Isr, h(2)).unwrap();
+        assert_eq!(p.class(), ObjectClass::Isr);
+        assert_eq!(p.display_name(), UNNAMED_OBJECT);
+        assert_eq!(p.raw_properties(), [0, 4, 0, 0]);
+        match p {
+            GenericObjectProperties::Isr(isr) => assert_eq!(isr.priority(), Priority(4)),
+            _ => panic!("Expected an ISR"),
+        }
+        assert!(t.get(ObjectClass::Task, h(2)).is_none());
+        assert!(t.iter().all(|(class, _, _, props)| props.class() == class));
+    }
+
+    #[test]
+    fn timer_state() {
+        let timer = ObjectProperties::<TimerObjectClass>::new(None, [1, 0, 0, 0]);
+        assert_eq!(timer.state(), TimerState::Active);
+        assert_eq!(timer.raw_state(), 1);
+        let timer = ObjectProperties::<TimerObjectClass>::new(None, [0; 4]);
+        assert_eq!(timer.state(), TimerState::Dormant);
+    }
+
+    #[test]
+    fn event_group_and_buffer_properties() {
+        let eg = ObjectProperties::<EventGroupObjectClass>::new(None, [0x05, 0, 0, 0x80]);
+        assert_eq!(eg.bits(Endianness::Little), 0x8000_0005);
+        assert_eq!(eg.bits(Endianness::Big), 0x0500_0080);
+        let sb = ObjectProperties::<StreamBufferObjectClass>::new(None, [0x10, 0x01, 0, 0]);
+        assert_eq!(sb.bytes_in_buffer(Endianness::Little), 0x110);
+        let mb = ObjectProperties::<MessageBufferObjectClass>::new(None, [0, 0, 0, 0x08]);
+        assert_eq!(mb.bytes_in_buffer(Endianness::Big), 8);
+    }
+}
@@ -1,17 +1,59 @@
+use crate::diagnostics::Diagnostic;
+use crate::intern::InternPool;
 use crate::snapshot::event::{Event, EventParser, EventRecord, EventType};
+use crate::snapshot::event_index::EventIndex;
 use crate::snapshot::markers::{DebugMarker, MarkerBytes};
 use crate::snapshot::object_properties::{ObjectProperties, ObjectPropertyTable};
+use crate::snapshot::status::RecorderStatus;
+use crate::snapshot::streaming_converter::{ConversionStats, StreamingConverter};
 use crate::snapshot::symbol_table::{SymbolCrc6, SymbolTable};
 use crate::snapshot::Error;
 use crate::time::Frequency;
 use crate::types::{
-    Endianness, FloatEncoding, KernelPortIdentity, KernelVersion, ObjectClass, ObjectHandle,
-    OffsetBytes, Protocol, TrimmedString,
+    Endianness, FloatEncoding, Heap, KernelPortIdentity, KernelVersion, ObjectClass, ObjectHandle,
+    OffsetBytes, Protocol, StringDecoder, TrimmedString, UserEventChannel,
 };
 use byteordered::ByteOrdered;
-use std::collections::{BTreeMap, VecDeque};
-use std::io::{Read, Seek, SeekFrom};
-use tracing::{debug, error, warn};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// An event from `RecorderData::windowed_events`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct WindowedEvent {
+    pub event_type: EventType,
+    pub event: Event,
+    /// Set on the first event when earlier events were overwritten
+    pub earliest_surviving: bool,
+}
+
+/// The heap usage maintained from the memory events versus the header's,
+/// see `RecorderData::reconcile_heap`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct HeapReconciliation {
+    /// The heap usage from the events, `max` is unknown (zero)
+    pub events: Heap,
+    /// See `RecorderData::heap_mem_usage`
+    pub header_usage: u32,
+    /// See `RecorderData::heap_mem_max_usage`
+    pub header_max_usage: u32,
+}
+
+impl HeapReconciliation {
+    /// Header usage minus the events' usage, i.e. allocations whose events were
+    /// overwritten or made before tracing started
+    pub fn usage_discrepancy(&self) -> i64 {
+        i64::from(self.header_usage) - i64::from(self.events.current)
+    }
+
+    /// Header max usage minus the events' high water mark
+    pub fn max_usage_discrepancy(&self) -> i64 {
+        i64::from(self.header_max_usage) - i64::from(self.events.high_water_mark)
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.usage_discrepancy() == 0 && self.max_usage_discrepancy() == 0
+    }
+}
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct RecorderData {
@@ -40,16 +82,32 @@ pub struct RecorderData {
     pub float_encoding: FloatEncoding,
     pub internal_error_occured: bool,
     pub system_info: String,
+    /// Recoverable issues found while parsing the recorder data
+    pub diagnostics: Vec<Diagnostic>,
 
     /// Offset of the recorder data start markers
     start_offset: OffsetBytes,
     /// Offset of the recorder data event data
     event_data_offset: OffsetBytes,
     // TODO - add user event buffer offset here when supported
+    /// See `set_user_event_channel_filter`
+    user_event_channels: Option<BTreeSet<UserEventChannel>>,
+    /// See `set_intern_pool`
+    intern_pool: Option<InternPool>,
 }
 
 impl RecorderData {
     pub fn locate_and_parse<R: Read + Seek>(r: &mut R) -> Result<Self, Error> {
+        Self::locate_and_parse_with_string_decoder(r, StringDecoder::default())
+    }
+
+    /// Like `locate_and_parse`, but decodes the object names, symbol table and
+    /// system info with `string_decoder`
+    pub fn locate_and_parse_with_string_decoder<R: Read + Seek>(
+        r: &mut R,
+        string_decoder: StringDecoder,
+    ) -> Result<Self, Error> {
+        let mut diagnostics = Vec::new();
         let mut tmp_buffer = VecDeque::with_capacity(1024);
         let mut r = ByteOrdered::native(r);
 
@@ -87,11 +145,19 @@ impl RecorderData {
         debug!(minor_version = minor_version, "Found minor version");
 
         if kernel_port != KernelPortIdentity::FreeRtos {
-            warn!("Kernel port {kernel_port} is not officially supported");
+            warn_diagnostic!(
+                diagnostics,
+                Some(r.stream_position()?),
+                "Kernel port {kernel_port} is not officially supported"
+            );
         }
 
         if minor_version != 7 {
-            warn!("Version {minor_version} is not officially supported");
+            warn_diagnostic!(
+                diagnostics,
+                Some(r.stream_position()?),
+                "Version {minor_version} is not officially supported"
+            );
         }
 
         let irq_priority_order = r.read_u8()?;
@@ -120,7 +186,11 @@ impl RecorderData {
         }
 
         if frequency.is_unitless() {
-            warn!("Time base frequency is zero, units will be in ticks only");
+            warn_diagnostic!(
+                diagnostics,
+                Some(r.stream_position()?),
+                "Time base frequency is zero, units will be in ticks only"
+            );
         }
 
         // Object property table starts here
@@ -178,13 +248,21 @@ impl RecorderData {
             let start_index = start_index_of_class[obj_class_index];
 
             if total_bytes_per_obj == 0 {
-                error!("Skipping empty object class {obj_class} property table entry");
+                error_diagnostic!(
+                    diagnostics,
+                    Some(r.stream_position()?),
+                    "Skipping empty object class {obj_class} property table entry"
+                );
                 // Keep on trying
                 continue;
             }
 
             if obj_class_index as u32 >= num_object_classes {
-                warn!("Skipping unsupported object class {obj_class} property table entry");
+                warn_diagnostic!(
+                    diagnostics,
+                    Some(r.stream_position()?),
+                    "Skipping unsupported object class {obj_class} property table entry"
+                );
                 r.seek(SeekFrom::Current(i64::from(
                     total_bytes_per_obj as u32 * num_objects as u32,
                 )))?;
@@ -193,7 +271,7 @@ impl RecorderData {
 
             let class_offset = r.stream_position()?;
             if (class_offset - pos_at_prop_table) != u64::from(start_index) {
-                warn!("Offset of object class {obj_class} {class_offset}, relative to the property table {} doesn't match the reported start index {start_index}", class_offset - pos_at_prop_table);
+                warn_diagnostic!(diagnostics, Some(r.stream_position()?), "Offset of object class {obj_class} {class_offset}, relative to the property table {} doesn't match the reported start index {start_index}", class_offset - pos_at_prop_table);
             }
             let end_of_class =
                 class_offset + u64::from(num_objects as u32 * total_bytes_per_obj as u32);
@@ -207,7 +285,11 @@ impl RecorderData {
 
                 // Zero length name is invalid (pretty sure), but try and tolerate it
                 if name_len == 0 {
-                    warn!("Skipping object class {obj_class} entry because name length is zero");
+                    warn_diagnostic!(
+                        diagnostics,
+                        Some(r.stream_position()?),
+                        "Skipping object class {obj_class} entry because name length is zero"
+                    );
                     r.seek(SeekFrom::Current(i64::from(total_bytes_per_obj)))?;
                     continue;
                 }
@@ -227,7 +309,10 @@ impl RecorderData {
                 let name = if tmp_buffer[0] == 0x01 {
                     None
                 } else {
-                    Some(TrimmedString::from_raw(tmp_buffer.make_contiguous()).into())
+                    Some(
+                        TrimmedString::from_raw_with(tmp_buffer.make_contiguous(), &string_decoder)
+                            .into(),
+                    )
                 };
 
                 // Read properties
@@ -320,7 +405,7 @@ impl RecorderData {
         // Entry 0 is reserved. Any reference to entry 0 implies NULL
         let next_free_symbol_index = r.read_u32()?;
         if next_free_symbol_index > symbol_table_size {
-            warn!("Next free symbol index {next_free_symbol_index} exceeds symbol table size {symbol_table_size}");
+            warn_diagnostic!(diagnostics, Some(r.stream_position()?), "Next free symbol index {next_free_symbol_index} exceeds symbol table size {symbol_table_size}");
         }
         let end_of_symbol_table_region =
             r.stream_position()? + u64::from(round_up_nearest_4(symbol_table_size));
@@ -329,7 +414,9 @@ impl RecorderData {
 
         let unused_index_slot = r.read_u8()?;
         if unused_index_slot != 0 {
-            warn!(
+            warn_diagnostic!(
+                diagnostics,
+                Some(r.stream_position()?),
                 "Reserved symbol table entry 0 contains an invalid value 0x{unused_index_slot:X}"
             );
         }
@@ -350,9 +437,11 @@ impl RecorderData {
                     // They double null-terminate for some reason, I think it's a bug and a waste :/
                     let extra_null = r.read_u8()?;
                     if extra_null != 0 {
-                        warn!(
-                            "Found non-zero NULL terminated symbol table entry at offeset {}",
-                            r.stream_position()?
+                        let offset = r.stream_position()?;
+                        warn_diagnostic!(
+                            diagnostics,
+                            Some(offset),
+                            "Found non-zero NULL terminated symbol table entry at offeset {offset}"
                         );
                     }
                     break;
@@ -368,7 +457,7 @@ impl RecorderData {
                 .ok_or(Error::InvalidSymbolTableIndex(start_of_symbol_table_entry))?,
                 ObjectHandle::new(channel.into()),
                 crc,
-                TrimmedString::from_raw(tmp_buffer.make_contiguous()).into(),
+                TrimmedString::from_raw_with(tmp_buffer.make_contiguous(), &string_decoder).into(),
             );
         }
 
@@ -391,7 +480,11 @@ impl RecorderData {
 
         let internal_error_occured = r.read_u32()?;
         if internal_error_occured != 0 {
-            warn!("The 'internal_error_occured' field is set to {internal_error_occured}");
+            warn_diagnostic!(
+                diagnostics,
+                Some(r.stream_position()?),
+                "The 'internal_error_occured' field is set to {internal_error_occured}"
+            );
         }
 
         DebugMarker::Marker2.read(&mut r)?;
@@ -400,7 +493,8 @@ impl RecorderData {
         tmp_buffer.clear();
         tmp_buffer.resize(NUM_SYSTEM_INFO_BYTES, 0);
         r.read_exact(tmp_buffer.make_contiguous())?;
-        let system_info = TrimmedString::from_raw(tmp_buffer.make_contiguous()).0;
+        let system_info =
+            TrimmedString::from_raw_with(tmp_buffer.make_contiguous(), &string_decoder).0;
         if !system_info.is_empty() {
             debug!(system_info = %system_info, "Found system info");
         }
@@ -419,7 +513,11 @@ impl RecorderData {
             // Read the rest of endOfSecondaryBlocks (always zero)
             let end_of_secondary_blocks = r.read_u16()?;
             if end_of_secondary_blocks != 0 {
-                warn!("End of secondary blocks field ({end_of_secondary_blocks}) should be zero");
+                warn_diagnostic!(
+                    diagnostics,
+                    Some(r.stream_position()?),
+                    "End of secondary blocks field ({end_of_secondary_blocks}) should be zero"
+                );
             }
         } else {
             // TODO - add support for this and put info in the data
@@ -463,18 +561,48 @@ impl RecorderData {
             float_encoding,
             internal_error_occured: internal_error_occured != 0,
             system_info,
+            diagnostics,
 
             // Internal stuff
             start_offset,
             event_data_offset,
+            user_event_channels: None,
+            intern_pool: None,
         })
     }
 
+    /// Only produce user events on the given channels from `events` and the
+    /// other event iterators, see `EventParser::set_user_event_channel_filter`
+    pub fn set_user_event_channel_filter(&mut self, channels: Option<BTreeSet<UserEventChannel>>) {
+        self.user_event_channels = channels;
+    }
+
+    /// Intern the symbols of the symbol table, and the object names of the events
+    /// from `events` and the other event iterators, in the given pool
+    pub fn set_intern_pool(&mut self, pool: InternPool) {
+        self.symbol_table.intern_symbols(&pool);
+        self.intern_pool = Some(pool);
+    }
+
+    pub(crate) fn event_parser(&self) -> EventParser {
+        let mut parser = EventParser::new(self.endianness.into());
+        parser.set_user_event_channel_filter(self.user_event_channels.clone());
+        if let Some(pool) = &self.intern_pool {
+            parser.set_intern_pool(pool.clone());
+        }
+        parser
+    }
+
+    /// Number of events lost to the ring buffer wrapping
+    pub fn overwritten_event_count(&self) -> u32 {
+        self.num_events.saturating_sub(self.max_events)
+    }
+
     pub fn event_records<'r, R: Read + Seek + Send>(
         &'r self,
         r: &'r mut R,
     ) -> Result<Box<dyn Iterator<Item = Result<EventRecord, Error>> + Send + 'r>, Error> {
-        if (self.num_events < self.max_events) || ((self.num_events % self.max_events) == 0) {
+        if (self.num_events < self.max_events) || self.num_events.is_multiple_of(self.max_events) {
             // Buffer is still still contiguous, can iterate from start of memory
             let num_events_clamped = std::cmp::min(self.num_events, self.max_events);
             r.seek(SeekFrom::Start(self.event_data_offset))?;
@@ -515,7 +643,7 @@ impl RecorderData {
         &'r self,
         r: &'r mut R,
     ) -> Result<impl Iterator<Item = Result<(EventType, Event), Error>> + 'r, Error> {
-        let mut parser = EventParser::new(self.endianness.into());
+        let mut parser = self.event_parser();
         let iter = self.event_records(r)?.filter_map(move |item| match item {
             Ok(er) => match parser
                 .parse(&self.object_property_table, &self.symbol_table, er)
@@ -528,6 +656,108 @@ impl RecorderData {
         });
         Ok(iter)
     }
+
+    /// Write the recorder data and events as a streaming PSF file, see `StreamingConverter`
+    pub fn write_streaming<R: Read + Seek + Send, W: Write>(
+        &self,
+        r: &mut R,
+        w: W,
+    ) -> Result<ConversionStats, Error> {
+        let mut converter = StreamingConverter::new(self);
+        for item in self.events(r)? {
+            let (_, event) = item?;
+            converter.push(&event);
+        }
+        Ok(converter.finish(w)?)
+    }
+
+    /// Build an index of the events and their absolute timestamps,
+    /// for random access and reverse iteration
+    pub fn event_index<R: Read + Seek + Send>(&self, r: &mut R) -> Result<EventIndex, Error> {
+        EventIndex::build(self, r)
+    }
+
+    /// Decode all the events on the rayon thread pool, oldest first.
+    ///
+    /// The event index is built first, accumulating the timestamps sequentially,
+    /// see `EventIndex::par_events`. Fails with the first event that can't be decoded.
+    #[cfg(feature = "rayon")]
+    pub fn par_events<R: Read + Seek + Send>(
+        &self,
+        r: &mut R,
+    ) -> Result<Vec<(EventType, Event)>, Error> {
+        use rayon::iter::ParallelIterator;
+
+        let index = self.event_index(r)?;
+        index.par_events(self, 0..index.len()).collect()
+    }
+
+    /// Iterate the events newest to oldest, see `event_index`
+    pub fn events_rev<'r, R: Read + Seek + Send>(
+        &'r self,
+        r: &'r mut R,
+    ) -> Result<impl Iterator<Item = Result<(EventType, Event), Error>> + 'r, Error> {
+        let index = self.event_index(r)?;
+        Ok((0..index.len())
+            .rev()
+            .filter_map(move |i| index.event(self, i)))
+    }
+
+    /// Like `events`, but marks the earliest event that survived the ring buffer wrapping,
+    /// see `overwritten_event_count`
+    pub fn windowed_events<'r, R: Read + Seek + Send>(
+        &'r self,
+        r: &'r mut R,
+    ) -> Result<impl Iterator<Item = Result<WindowedEvent, Error>> + 'r, Error> {
+        let mut first = self.overwritten_event_count() != 0;
+        let iter = self.events(r)?.map(move |item| {
+            item.map(|(event_type, event)| {
+                let earliest_surviving = first;
+                first = false;
+                WindowedEvent {
+                    event_type,
+                    event,
+                    earliest_surviving,
+                }
+            })
+        });
+        Ok(iter)
+    }
+
+    /// The recorder's status flags and their interpretation
+    pub fn status(&self) -> RecorderStatus {
+        RecorderStatus::new(self)
+    }
+
+    /// Like `status`, with the timestamp of the earliest surviving event
+    /// when the buffer wrapped
+    pub fn read_status<R: Read + Seek + Send>(&self, r: &mut R) -> Result<RecorderStatus, Error> {
+        let status = self.status();
+        if !status.wrapped() {
+            return Ok(status);
+        }
+        match self.events(r)?.next().transpose()? {
+            Some((_, event)) => Ok(status.with_earliest_surviving(event.timestamp())),
+            None => Ok(status),
+        }
+    }
+
+    /// Replay the memory events to compare the heap usage they add up to with
+    /// the header's `heap_mem_usage` and `heap_mem_max_usage`
+    pub fn reconcile_heap<R: Read + Seek + Send>(
+        &self,
+        r: &mut R,
+    ) -> Result<HeapReconciliation, Error> {
+        let mut parser = self.event_parser();
+        for record in self.event_records(r)? {
+            parser.parse(&self.object_property_table, &self.symbol_table, record?)?;
+        }
+        Ok(HeapReconciliation {
+            events: *parser.heap(),
+            header_usage: self.heap_mem_usage,
+            header_max_usage: self.heap_mem_max_usage,
+        })
+    }
 }
 
 /// Max size of the system info string
@@ -536,11 +766,183 @@ const NUM_SYSTEM_INFO_BYTES: usize = 80;
 // Rounded up to the closest multiple of 2
 // Used in the data struct allocation to avoid alignment issues
 fn round_up_nearest_2(n: u32) -> u32 {
-    2 * ((n + 1) / 2)
+    2 * n.div_ceil(2)
 }
 
 // Rounded up to the closest multiple of 4
 // Used in the data struct allocation to avoid alignment issues
 fn round_up_nearest_4(n: u32) -> u32 {
-    4 * ((n + 3) / 4)
+    4 * n.div_ceil(4)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::snapshot::event::LowPowerEvent;
+    use crate::time::{Ticks, Timestamp};
+    use std::io::Cursor;
+
+    fn low_power_record(dts: u16) -> [u8; 4] {
+        let dts = dts.to_le_bytes();
+        [0xAC, 0, dts[0], dts[1]]
+    }
+
+    pub(crate) fn recorder_data(
+        max_events: u32,
+        num_events: u32,
+        next_free_index: u32,
+    ) -> RecorderData {
+        RecorderData {
+            protocol: Protocol::Snapshot,
+            kernel_version: KernelVersion([0xA1, 0x1A]),
+            kernel_port: KernelPortIdentity::FreeRtos,
+            endianness: Endianness::Little,
+            minor_version: 7,
+            irq_priority_order: 0,
+            filesize: 0,
+            num_events,
+            max_events,
+            next_free_index,
+            buffer_is_full: num_events >= max_events,
+            frequency: Frequency(0),
+            abs_time_last_event: 0,
+            abs_time_last_event_second: 0,
+            recorder_active: true,
+            isr_tail_chaining_threshold: 0,
+            heap_mem_usage: 0,
+            heap_mem_max_usage: 0,
+            is_using_16bit_handles: false,
+            object_property_table: ObjectPropertyTable::default(),
+            symbol_table: SymbolTable::default(),
+            float_encoding: FloatEncoding::Unsupported,
+            internal_error_occured: false,
+            system_info: String::new(),
+            diagnostics: Vec::new(),
+            start_offset: 0,
+            event_data_offset: 0,
+            user_event_channels: None,
+            intern_pool: None,
+        }
+    }
+
+    #[test]
+    fn heap_reconciliation() {
+        // Malloc 100 + 0x10000 (XPS), free 100, malloc 8
+        let mut data = Vec::new();
+        for record in [
+            [0x94, 1, 100, 0],
+            [0x01, 0, 1, 0],
+            [0x94, 1, 0, 0],
+            [0x96, 1, 100, 0],
+            [0x94, 1, 8, 0],
+        ] {
+            data.extend_from_slice(&record);
+        }
+        let mut rd = recorder_data(8, 5, 5);
+        rd.heap_mem_usage = 0x10008;
+        rd.heap_mem_max_usage = 0x10100;
+
+        let heap = rd.reconcile_heap(&mut Cursor::new(data)).unwrap();
+        assert_eq!(heap.events.current, 0x10008);
+        assert_eq!(heap.events.high_water_mark, 0x10064);
+        assert_eq!(heap.usage_discrepancy(), 0);
+        assert_eq!(heap.max_usage_discrepancy(), 0x9C);
+        assert!(!heap.is_consistent());
+    }
+
+    #[test]
+    fn wrapped_buffer_forward_and_reverse() {
+        // Ring buffer of 4 records, 6 events written, so the oldest surviving
+        // event is at index 2 (dts 3) and the newest at index 1 (dts 6)
+        let mut data = Vec::new();
+        for dts in [5, 6, 3, 4] {
+            data.extend_from_slice(&low_power_record(dts));
+        }
+        let rd = recorder_data(4, 6, 2);
+        assert_eq!(rd.overwritten_event_count(), 2);
+
+        let timestamps = |events: Vec<Event>| {
+            events
+                .into_iter()
+                .map(|e| e.timestamp().ticks())
+                .collect::<Vec<_>>()
+        };
+
+        let mut r = Cursor::new(data.clone());
+        let fwd: Vec<Event> = rd.events(&mut r).unwrap().map(|e| e.unwrap().1).collect();
+        assert_eq!(timestamps(fwd.clone()), vec![3, 7, 12, 18]);
+        let fwd_events = fwd.clone();
+
+        let mut r = Cursor::new(data.clone());
+        let rev: Vec<Event> = rd
+            .events_rev(&mut r)
+            .unwrap()
+            .map(|e| e.unwrap().1)
+            .collect();
+        assert_eq!(rev, fwd.into_iter().rev().collect::<Vec<_>>());
+
+        let mut r = Cursor::new(data.clone());
+        let index = rd.event_index(&mut r).unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.timestamp(2), Some(Timestamp::from(Ticks::new(12))));
+        assert_eq!(index.find(Timestamp::from(Ticks::new(8))), 2);
+        assert_eq!(index.event(&rd, 2).unwrap().unwrap().1, fwd_events[2]);
+
+        #[cfg(feature = "rayon")]
+        {
+            let mut r = Cursor::new(data.clone());
+            let par: Vec<Event> = rd
+                .par_events(&mut r)
+                .unwrap()
+                .into_iter()
+                .map(|(_, e)| e)
+                .collect();
+            assert_eq!(par, fwd_events);
+        }
+
+        let mut r = Cursor::new(data);
+        let windowed: Vec<WindowedEvent> = rd
+            .windowed_events(&mut r)
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+        assert!(windowed[0].earliest_surviving);
+        assert!(windowed.iter().skip(1).all(|e| !e.earliest_surviving));
+        assert_eq!(
+            windowed[0].event,
+            Event::LowPowerBegin(LowPowerEvent {
+                timestamp: Timestamp::from(Ticks::new(3)),
+            })
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_events_match_sequential_decoding() {
+        use rayon::iter::ParallelIterator;
+
+        let mut data = Vec::new();
+        for i in 0..200 {
+            data.extend_from_slice(&low_power_record(i % 7 + 1));
+        }
+        let rd = recorder_data(256, 200, 200);
+
+        let seq: Vec<(EventType, Event)> = rd
+            .events(&mut Cursor::new(data.clone()))
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect();
+        let par = rd.par_events(&mut Cursor::new(data.clone())).unwrap();
+        assert_eq!(par.len(), 200);
+        assert_eq!(par, seq);
+
+        let index = rd.event_index(&mut Cursor::new(data.clone())).unwrap();
+        let window: Vec<(EventType, Event)> =
+            index.par_events(&rd, 50..60).map(|e| e.unwrap()).collect();
+        assert_eq!(window, seq[50..60]);
+
+        // A PendFuncCall on a task missing from the object property table
+        data[400..404].copy_from_slice(&[0x90, 0x02, 0x01, 0x00]);
+        assert!(rd.par_events(&mut Cursor::new(data)).is_err());
+    }
 }
@@ -0,0 +1,145 @@
+use crate::snapshot::RecorderData;
+use crate::time::Timestamp;
+use std::fmt;
+
+/// The state of the recorder when the snapshot was taken, see `RecorderData::status`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct RecorderStatus {
+    /// The recorder was still recording
+    pub active: bool,
+    /// The event buffer filled up at least once
+    pub buffer_full: bool,
+    /// Events written, including the overwritten ones
+    pub num_events: u32,
+    /// Capacity of the event buffer
+    pub max_events: u32,
+    /// Events lost to the ring buffer wrapping
+    pub overwritten_events: u32,
+    /// The message of the recorder's internal error, if one occurred.
+    /// The recorder stores it in the system info string.
+    pub internal_error: Option<String>,
+    /// Timestamp of the earliest event that survived the ring buffer wrapping,
+    /// see `RecorderData::read_status`
+    pub earliest_surviving: Option<Timestamp>,
+}
+
+impl RecorderStatus {
+    pub fn new(rd: &RecorderData) -> Self {
+        Self {
+            active: rd.recorder_active,
+            buffer_full: rd.buffer_is_full,
+            num_events: rd.num_events,
+            max_events: rd.max_events,
+            overwritten_events: rd.overwritten_event_count(),
+            internal_error: rd.internal_error_occured.then(|| rd.system_info.clone()),
+            earliest_surviving: None,
+        }
+    }
+
+    pub fn with_earliest_surviving(mut self, timestamp: Timestamp) -> Self {
+        self.earliest_surviving = Some(timestamp);
+        self
+    }
+
+    /// Events from the start of the trace are missing
+    pub fn wrapped(&self) -> bool {
+        self.overwritten_events != 0
+    }
+
+    /// The buffer filled up and the recorder stopped, events after that are missing.
+    /// The recorder stops when full in its "stop when full" buffer mode.
+    pub fn stopped_when_full(&self) -> bool {
+        self.buffer_full && !self.active && !self.wrapped()
+    }
+
+    /// Whether the events are a complete record of the trace up to the snapshot
+    pub fn is_complete(&self) -> bool {
+        !self.wrapped() && !self.stopped_when_full() && self.internal_error.is_none()
+    }
+
+    /// Human readable interpretations of the status, one per issue
+    pub fn notes(&self) -> Vec<String> {
+        let mut notes = Vec::new();
+        if self.wrapped() {
+            let at = self
+                .earliest_surviving
+                .map(|t| format!(" at tick {}", t.ticks()))
+                .unwrap_or_default();
+            notes.push(format!(
+                "{} events are likely missing because the buffer wrapped{at}",
+                self.overwritten_events
+            ));
+        }
+        if self.stopped_when_full() {
+            notes.push(format!(
+                "The recorder stopped when the buffer filled up after {} events, later events are missing",
+                self.num_events
+            ));
+        } else if !self.active {
+            notes.push("The recorder was stopped".to_owned());
+        }
+        if let Some(msg) = &self.internal_error {
+            if msg.is_empty() {
+                notes.push("The recorder reported an internal error".to_owned());
+            } else {
+                notes.push(format!("The recorder reported an internal error: {msg}"));
+            }
+        }
+        notes
+    }
+}
+
+/// One `key: value` line per field, followed by the notes
+impl fmt::Display for RecorderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "active: {}", self.active)?;
+        writeln!(f, "buffer_full: {}", self.buffer_full)?;
+        writeln!(f, "events: {}/{}", self.num_events, self.max_events)?;
+        write!(f, "overwritten_events: {}", self.overwritten_events)?;
+        if let Some(t) = self.earliest_surviving {
+            write!(f, "\nearliest_surviving: {t}")?;
+        }
+        for note in self.notes() {
+            write!(f, "\nnote: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::recorder_data::test::recorder_data;
+
+    #[test]
+    fn interpretations() {
+        let mut rd = recorder_data(4, 6, 2);
+        rd.recorder_active = true;
+        let status = RecorderStatus::new(&rd).with_earliest_surviving(Timestamp(1200));
+        assert!(status.wrapped());
+        assert!(!status.is_complete());
+        assert_eq!(
+            status.notes(),
+            ["2 events are likely missing because the buffer wrapped at tick 1200"]
+        );
+
+        let mut rd = recorder_data(4, 4, 0);
+        rd.recorder_active = false;
+        rd.internal_error_occured = true;
+        rd.system_info = "Invalid object handle".to_owned();
+        let status = rd.status();
+        assert!(!status.wrapped());
+        assert!(status.stopped_when_full());
+        assert_eq!(
+            status.notes(),
+            [
+                "The recorder stopped when the buffer filled up after 4 events, later events are missing",
+                "The recorder reported an internal error: Invalid object handle"
+            ]
+        );
+
+        let rd = recorder_data(4, 3, 3);
+        assert!(rd.status().is_complete());
+        assert!(rd.status().notes().is_empty());
+    }
+}
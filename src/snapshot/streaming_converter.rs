@@ -0,0 +1,388 @@
+//! Conversion of snapshot data to the streaming format
+//!
+//! The output is a little-endian format version 14 PSF file: the header and
+//! timestamp info from the recorder data, an entry table built from the
+//! object property table and the strings the events reference, then the
+//! events.
+//!
+//! Snapshot object handles are only unique within a class, each object is
+//! given a new address in the entry table. Only the events with a streaming
+//! equivalent are converted, the others are counted in `ConversionStats::skipped`.
+
+use crate::snapshot::event::Event;
+use crate::snapshot::object_properties::GenericObjectProperties;
+use crate::snapshot::RecorderData;
+use crate::streaming::event::{EventId, EventType};
+use crate::streaming::HeaderInfo;
+use crate::time::Timestamp;
+use crate::types::{
+    Argument, CompiledFormat, ObjectClass, ObjectHandle, Priority, TimerCounter,
+    UserEventArgRecordCount, UserEventChannel,
+};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// The streaming format version written
+pub const FORMAT_VERSION: u16 = 14;
+
+/// Handle of the default user event channel, never in the entry table
+const DEFAULT_CHANNEL_HANDLE: u32 = 1;
+const FIRST_ENTRY_ADDRESS: u32 = 0x1000;
+const MAX_PARAMS: usize = 15;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ConversionStats {
+    /// Events written to the streaming output
+    pub converted: u64,
+    /// Events without a streaming equivalent, or user events with too many
+    /// parameters
+    pub skipped: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+enum EntryKey {
+    Object(ObjectClass, ObjectHandle),
+    Symbol(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+struct Entry {
+    address: u32,
+    symbol: String,
+    priority: Priority,
+}
+
+/// Converts the events of a snapshot to a streaming PSF file.
+///
+/// The events are encoded as they're pushed, the entry table needs all of them
+/// so the output is only written by `finish`.
+#[derive(Clone, Debug)]
+pub struct StreamingConverter<'a> {
+    rd: &'a RecorderData,
+    entries: BTreeMap<EntryKey, Entry>,
+    next_address: u32,
+    events: Vec<u8>,
+    event_count: u16,
+    last_timestamp: Timestamp,
+    stats: ConversionStats,
+}
+
+impl<'a> StreamingConverter<'a> {
+    pub fn new(rd: &'a RecorderData) -> Self {
+        let mut c = Self {
+            rd,
+            entries: BTreeMap::new(),
+            next_address: FIRST_ENTRY_ADDRESS,
+            events: Vec::new(),
+            event_count: 0,
+            last_timestamp: Timestamp::zero(),
+            stats: ConversionStats::default(),
+        };
+        for (class, handle, _, props) in rd.object_property_table.iter() {
+            let priority = match props {
+                GenericObjectProperties::Task(p) => p.current_priority(),
+                GenericObjectProperties::Isr(p) => p.priority(),
+                _ => Priority(0),
+            };
+            c.object(class, handle, props.display_name(), priority);
+        }
+        c
+    }
+
+    pub fn stats(&self) -> ConversionStats {
+        self.stats
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let t = event.timestamp();
+        match event {
+            Event::TaskCreate(ev) => {
+                let h = self.object(ObjectClass::Task, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskCreate, t, &[h, ev.priority.0]);
+            }
+            Event::TaskReady(ev) => {
+                let h = self.object(ObjectClass::Task, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskReady, t, &[h]);
+            }
+            Event::TaskBegin(ev) => {
+                let h = self.object(ObjectClass::Task, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskActivate, t, &[h, ev.priority.0]);
+            }
+            Event::TaskResume(ev) => {
+                let h = self.object(ObjectClass::Task, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskSwitchTaskResume, t, &[h]);
+            }
+            Event::IsrBegin(ev) => {
+                let h = self.object(ObjectClass::Isr, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskSwitchIsrBegin, t, &[h]);
+            }
+            Event::IsrResume(ev) => {
+                let h = self.object(ObjectClass::Isr, ev.handle, &ev.name, ev.priority);
+                self.write_event(EventType::TaskSwitchIsrResume, t, &[h]);
+            }
+            Event::User(ev) => self.user_event(
+                t,
+                &ev.channel,
+                &ev.format_string,
+                &ev.formatted_string,
+                &ev.args,
+            ),
+            _ => self.stats.skipped += 1,
+        }
+    }
+
+    /// Write the streaming data
+    pub fn finish<W: Write>(self, mut w: W) -> io::Result<ConversionStats> {
+        let rd = self.rd;
+        let u32_le = |w: &mut W, v: u32| w.write_all(&v.to_le_bytes());
+
+        // Header, the platform config layout is the one of v13 and later
+        u32_le(&mut w, HeaderInfo::PSF_LITTLE_ENDIAN)?;
+        w.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        w.write_all(&rd.kernel_version.0)?;
+        u32_le(&mut w, u32::from(rd.irq_priority_order & 0x01))?;
+        u32_le(&mut w, 1)?;
+        u32_le(&mut w, rd.isr_tail_chaining_threshold)?;
+        w.write_all(&[0, 0, 0, 0])?;
+        let mut platform_cfg = [0; 8];
+        let name = rd.kernel_port.to_string();
+        let len = name.len().min(platform_cfg.len());
+        platform_cfg[..len].copy_from_slice(&name.as_bytes()[..len]);
+        w.write_all(&platform_cfg)?;
+
+        // Timestamp info, the snapshot timer type isn't known
+        let last_timestamp = self.last_timestamp;
+        u32_le(&mut w, TimerCounter::FreeRunning32Incr.hwtc_type())?;
+        u32_le(&mut w, 0)?;
+        u32_le(&mut w, rd.frequency.get_raw())?;
+        u32_le(&mut w, (last_timestamp.ticks() >> 32) as u32)?;
+        u32_le(&mut w, 0)?;
+        u32_le(&mut w, last_timestamp.ticks() as u32)?;
+        u32_le(&mut w, 0)?;
+
+        // Entry table
+        let symbol_size = self
+            .entries
+            .values()
+            .map(|e| e.symbol.len() + 1)
+            .max()
+            .unwrap_or(1)
+            .next_multiple_of(4);
+        u32_le(&mut w, self.entries.len() as u32)?;
+        u32_le(&mut w, symbol_size as u32)?;
+        u32_le(&mut w, 3)?;
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by_key(|e| e.address);
+        let mut symbol = vec![0; symbol_size];
+        for e in entries {
+            u32_le(&mut w, e.address)?;
+            u32_le(&mut w, e.priority.0)?;
+            u32_le(&mut w, 0)?;
+            u32_le(&mut w, 0)?;
+            u32_le(&mut w, 0)?;
+            symbol.fill(0);
+            symbol[..e.symbol.len()].copy_from_slice(e.symbol.as_bytes());
+            w.write_all(&symbol)?;
+        }
+
+        w.write_all(&self.events)?;
+        Ok(self.stats)
+    }
+
+    fn object(
+        &mut self,
+        class: ObjectClass,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) -> u32 {
+        let address = self.allocate(EntryKey::Object(class, handle), name);
+        if let Some(e) = self.entries.get_mut(&EntryKey::Object(class, handle)) {
+            e.priority = priority;
+        }
+        address
+    }
+
+    fn symbol(&mut self, symbol: &str) -> u32 {
+        self.allocate(EntryKey::Symbol(symbol.to_owned()), symbol)
+    }
+
+    fn allocate(&mut self, key: EntryKey, symbol: &str) -> u32 {
+        if let Some(e) = self.entries.get(&key) {
+            return e.address;
+        }
+        let address = self.next_address;
+        self.next_address += 4;
+        self.entries.insert(
+            key,
+            Entry {
+                address,
+                symbol: symbol.to_owned(),
+                priority: Priority(0),
+            },
+        );
+        address
+    }
+
+    fn user_event(
+        &mut self,
+        t: Timestamp,
+        channel: &UserEventChannel,
+        format_string: &str,
+        formatted_string: &str,
+        args: &[Argument],
+    ) {
+        let channel = match channel {
+            UserEventChannel::Default => DEFAULT_CHANNEL_HANDLE,
+            UserEventChannel::Custom(c) => self.symbol(c),
+        };
+
+        // Events that couldn't be rendered keep their text, without arguments
+        let (format_string, args) =
+            if CompiledFormat::new(format_string).conversions() == args.len() {
+                (format_string.to_owned(), args)
+            } else {
+                (formatted_string.replace('%', "%%"), &[][..])
+            };
+
+        let mut payload = channel.to_le_bytes().to_vec();
+        for arg in args {
+            match arg {
+                Argument::Char(v) => payload.extend_from_slice(&u32::from(*v).to_le_bytes()),
+                Argument::I8(v) => payload.extend_from_slice(&i32::from(*v).to_le_bytes()),
+                Argument::U8(v) => payload.extend_from_slice(&u32::from(*v).to_le_bytes()),
+                Argument::I16(v) => payload.extend_from_slice(&i32::from(*v).to_le_bytes()),
+                Argument::U16(v) => payload.extend_from_slice(&u32::from(*v).to_le_bytes()),
+                Argument::I32(v) => payload.extend_from_slice(&v.to_le_bytes()),
+                Argument::U32(v) => payload.extend_from_slice(&v.to_le_bytes()),
+                Argument::F32(v) => payload.extend_from_slice(&v.0.to_le_bytes()),
+                Argument::F64(v) => payload.extend_from_slice(&v.0.to_le_bytes()),
+                Argument::String(s) => {
+                    let handle = self.symbol(s);
+                    payload.extend_from_slice(&handle.to_le_bytes());
+                }
+            }
+        }
+        let arg_words = payload.len() / 4 - 1;
+        // Null terminated, padded to whole parameters
+        let mut fmt = format_string.into_bytes();
+        fmt.resize((fmt.len() + 4) & !3, 0);
+        payload.extend_from_slice(&fmt);
+
+        if payload.len() / 4 > MAX_PARAMS {
+            self.stats.skipped += 1;
+            return;
+        }
+        // The argument count includes the format string
+        let arg_count = arg_words as u8 + 1;
+        self.write_payload(
+            EventType::UserEvent(UserEventArgRecordCount(arg_count)),
+            t,
+            &payload,
+        );
+    }
+
+    fn write_event(&mut self, event_type: EventType, t: Timestamp, params: &[u32]) {
+        let payload: Vec<u8> = params.iter().flat_map(|p| p.to_le_bytes()).collect();
+        self.write_payload(event_type, t, &payload);
+    }
+
+    /// Write an event made of whole parameters
+    fn write_payload(&mut self, event_type: EventType, t: Timestamp, payload: &[u8]) {
+        let id = EventId::from(event_type).0;
+        let code = id | (((payload.len() / 4) as u16) << 12);
+        self.events.extend_from_slice(&code.to_le_bytes());
+        self.events
+            .extend_from_slice(&self.event_count.to_le_bytes());
+        self.events
+            .extend_from_slice(&(t.ticks() as u32).to_le_bytes());
+        self.events.extend_from_slice(payload);
+        self.event_count = self.event_count.wrapping_add(1);
+        self.last_timestamp = t;
+        self.stats.converted += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{IsrEvent, LowPowerEvent, TaskEvent, UserEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::snapshot::recorder_data::test::recorder_data;
+    use crate::streaming;
+    use crate::types::{Arguments, FormatString, FormattedString, ObjectName};
+
+    fn task(handle: u32, t: u64) -> TaskEvent {
+        TaskEvent {
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName("worker".into()),
+            state: TaskState::Active,
+            priority: Priority(3),
+            timestamp: Timestamp(t),
+        }
+    }
+
+    #[test]
+    fn snapshot_events_to_streaming() {
+        let rd = recorder_data(0, 0, 0);
+        let mut c = StreamingConverter::new(&rd);
+        c.push(&Event::TaskCreate(task(1, 10)));
+        c.push(&Event::TaskBegin(task(1, 20)));
+        // Same handle, different class
+        c.push(&Event::IsrBegin(IsrEvent {
+            handle: ObjectHandle::new(1).unwrap(),
+            name: ObjectName("uart".into()),
+            priority: Priority(5),
+            timestamp: Timestamp(30),
+        }));
+        c.push(&Event::LowPowerBegin(LowPowerEvent {
+            timestamp: Timestamp(35),
+        }));
+        c.push(&Event::User(UserEvent {
+            timestamp: Timestamp(40),
+            channel: UserEventChannel::Custom("log".to_owned()),
+            format_string: FormatString("%s %d%%".into()),
+            formatted_string: FormattedString("ok -2%".to_owned()),
+            args: vec![Argument::String("ok".to_owned()), Argument::I16(-2)].into(),
+        }));
+        assert_eq!(
+            c.stats(),
+            ConversionStats {
+                converted: 4,
+                skipped: 1
+            }
+        );
+
+        let mut data = Vec::new();
+        c.finish(&mut data).unwrap();
+        let mut r = data.as_slice();
+        let mut srd = streaming::RecorderData::read(&mut r).unwrap();
+        assert_eq!(srd.header.format_version, FORMAT_VERSION);
+        assert_eq!(srd.timestamp_info.latest_timestamp, Timestamp(40));
+        let mut events = Vec::new();
+        while let Some((_, ev)) = srd.read_event(&mut r).unwrap() {
+            events.push(ev);
+        }
+        assert_eq!(events.len(), 4);
+        let streaming::event::Event::TaskActivate(begin) = &events[1] else {
+            panic!("{:?}", events[1]);
+        };
+        assert_eq!(begin.name.to_string(), "worker");
+        assert_eq!(begin.priority, Priority(3));
+        let streaming::event::Event::IsrBegin(isr) = &events[2] else {
+            panic!("{:?}", events[2]);
+        };
+        assert_eq!(isr.name.to_string(), "uart");
+        assert_ne!(isr.handle, begin.handle);
+        let streaming::event::Event::User(user) = &events[3] else {
+            panic!("{:?}", events[3]);
+        };
+        assert_eq!(user.timestamp, Timestamp(40));
+        assert_eq!(user.channel.as_str(), "log");
+        assert_eq!(user.formatted_string.as_str(), "ok -2%");
+        assert_eq!(
+            user.args,
+            Arguments::from(vec![Argument::String("ok".to_owned()), Argument::I32(-2)])
+        );
+    }
+}
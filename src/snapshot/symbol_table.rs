@@ -1,6 +1,9 @@
+use crate::intern::InternPool;
+use crate::table_cache::{self, TableCacheError, TableKind};
 use crate::types::{ObjectHandle, SymbolString, SymbolTableExt};
 use derive_more::{Binary, Display, Into, LowerHex, Octal, UpperHex};
 use std::collections::BTreeMap;
+use std::io::{Read, Write};
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct SymbolTable {
@@ -31,9 +34,76 @@ impl SymbolTable {
         );
     }
 
+    /// Replace the symbol of each entry with its handle in the pool
+    pub(crate) fn intern_symbols(&mut self, pool: &InternPool) {
+        for entry in self.symbols.values_mut() {
+            entry.symbol.0 = pool.intern(&entry.symbol.0);
+        }
+    }
+
     pub fn get(&self, handle: ObjectHandle) -> Option<&SymbolTableEntry> {
         self.symbols.get(&handle)
     }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Iterate over the entries by handle
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectHandle, &SymbolTableEntry)> + '_ {
+        self.symbols.iter().map(|(h, e)| (*h, e))
+    }
+
+    /// Entries with the given checksum, by handle
+    pub fn find_by_crc(
+        &self,
+        crc: SymbolCrc6,
+    ) -> impl Iterator<Item = (ObjectHandle, &SymbolTableEntry)> + '_ {
+        self.iter().filter(move |(_, e)| e.crc == crc)
+    }
+
+    /// Handle and entry of the first symbol matching `name`.
+    ///
+    /// Note that symbols are stored decoded, the checksum is
+    /// only used to skip entries when `name` is the same as the original bytes.
+    pub fn find_by_name(&self, name: &str) -> Option<(ObjectHandle, &SymbolTableEntry)> {
+        let crc = SymbolCrc6::new(name.as_bytes());
+        self.find_by_crc(crc)
+            .find(|(_, e)| &*e.symbol == name)
+            .or_else(|| self.iter().find(|(_, e)| &*e.symbol == name))
+    }
+
+    /// Write the table to the compact table cache format, see `table_cache`
+    pub fn save<W: Write>(&self, w: W) -> Result<(), TableCacheError> {
+        let mut w = table_cache::write_header(w, TableKind::SymbolTable)?;
+        table_cache::write_len(&mut w, self.symbols.len())?;
+        for (handle, entry) in self.symbols.iter() {
+            w.write_u32(u32::from(*handle))?;
+            w.write_u32(entry.channel_index.map(u32::from).unwrap_or(0))?;
+            w.write_u8(entry.crc.0)?;
+            table_cache::write_str(&mut w, &entry.symbol)?;
+        }
+        Ok(())
+    }
+
+    /// Read a table previously written with `SymbolTable::save`
+    pub fn load<R: Read>(r: R) -> Result<Self, TableCacheError> {
+        let mut r = table_cache::read_header(r, TableKind::SymbolTable)?;
+        let num_entries = table_cache::read_len(&mut r)?;
+        let mut table = SymbolTable::default();
+        for _ in 0..num_entries {
+            let handle = table_cache::read_handle(&mut r)?;
+            let channel_index = ObjectHandle::new(r.read_u32()?);
+            let crc = SymbolCrc6(r.read_u8()?);
+            let symbol = SymbolString(table_cache::read_string(&mut r)?.into());
+            table.insert(handle, channel_index, crc, symbol);
+        }
+        Ok(table)
+    }
 }
 
 impl SymbolTableExt for SymbolTable {
@@ -48,9 +118,9 @@ pub struct SymbolTableEntry {
     /// Reference to a symbol table entry, a label for vTracePrintF
     /// format strings only (the handle of the destination channel)
     pub channel_index: Option<ObjectHandle>,
-    /// 6-bit CRC of the binary symbol (before lossy UTF8 string conversion)
+    /// 6-bit CRC of the binary symbol (before string decoding)
     pub crc: SymbolCrc6,
-    /// The symbol (lossy converted to UTF8)
+    /// The symbol, decoded with the configured `StringDecoder`
     pub symbol: SymbolString,
 }
 
@@ -82,3 +152,31 @@ impl SymbolCrc6 {
         Self((crc & 0x3F) as u8)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn iter_and_find() {
+        let mut t = SymbolTable::default();
+        let h = |h| ObjectHandle::new(h).unwrap();
+        for (handle, sym) in [(4, "main"), (16, "IDLE"), (24, "Tmr Svc")] {
+            t.insert(
+                h(handle),
+                None,
+                SymbolCrc6::new(sym.as_bytes()),
+                SymbolString(sym.into()),
+            );
+        }
+        assert_eq!(t.len(), 3);
+        assert_eq!(
+            t.iter().map(|(h, _)| u32::from(h)).collect::<Vec<_>>(),
+            vec![4, 16, 24]
+        );
+        let (handle, entry) = t.find_by_name("IDLE").unwrap();
+        assert_eq!(handle, h(16));
+        assert_eq!(t.find_by_crc(entry.crc).next().unwrap().0, h(16));
+        assert!(t.find_by_name("idle").is_none());
+    }
+}
@@ -0,0 +1,320 @@
+//! Export of CPU usage as a [speedscope](https://www.speedscope.app) evented profile
+//!
+//! The stack is the running task at the bottom with the ISRs preempting it
+//! nested on top, so a flame graph or the "left heavy" view shows which
+//! tasks and ISRs dominate CPU time.
+
+use crate::analysis::sched::{ContextStack, SchedEvent};
+use crate::analysis::StreamingClock;
+use crate::snapshot;
+use crate::streaming;
+use crate::time::{Frequency, Timestamp};
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+pub const SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum FrameEvent {
+    Open,
+    Close,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpeedscopeExporter {
+    frequency: Frequency,
+    name: String,
+    clock: StreamingClock,
+    frames: Vec<String>,
+    task_frames: BTreeMap<ObjectHandle, usize>,
+    isr_frames: BTreeMap<ObjectHandle, usize>,
+    stack: ContextStack,
+    open: Vec<usize>,
+    events: Vec<(Timestamp, FrameEvent, usize)>,
+    start: Option<Timestamp>,
+    end: Timestamp,
+}
+
+impl SpeedscopeExporter {
+    /// Timestamps are converted to nanoseconds using the timer `frequency`,
+    /// the profile is unitless when the frequency is unknown
+    pub fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            name: env!("CARGO_PKG_NAME").to_owned(),
+            clock: StreamingClock::default(),
+            frames: Vec::new(),
+            task_frames: BTreeMap::new(),
+            isr_frames: BTreeMap::new(),
+            stack: ContextStack::default(),
+            open: Vec::new(),
+            events: Vec::new(),
+            start: None,
+            end: Timestamp::zero(),
+        }
+    }
+
+    /// Set the profile name shown by speedscope
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        let t = self.clock.time(event.timestamp());
+        self.process(t, SchedEvent::from_streaming(event));
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        self.process(event.timestamp(), SchedEvent::from_snapshot(event));
+    }
+
+    /// Write the profile as JSON, frames still open are closed at the time of
+    /// the last event
+    pub fn write<W: Write>(&self, w: W) -> Result<(), serde_json::Error> {
+        let unit = if self.frequency.is_unitless() {
+            "none"
+        } else {
+            "nanoseconds"
+        };
+        let events = self
+            .events
+            .iter()
+            .copied()
+            .chain(
+                self.open
+                    .iter()
+                    .rev()
+                    .map(|frame| (self.end, FrameEvent::Close, *frame)),
+            )
+            .map(|(t, ev, frame)| file::Event {
+                typ: match ev {
+                    FrameEvent::Open => "O",
+                    FrameEvent::Close => "C",
+                },
+                frame,
+                at: self.time(t),
+            })
+            .collect();
+        let file = file::File {
+            schema: SCHEMA,
+            shared: file::Shared {
+                frames: self
+                    .frames
+                    .iter()
+                    .map(|name| file::Frame { name })
+                    .collect(),
+            },
+            profiles: vec![file::Profile {
+                typ: "evented",
+                name: &self.name,
+                unit,
+                start_value: self.time(self.start.unwrap_or(self.end)),
+                end_value: self.time(self.end),
+                events,
+            }],
+            name: &self.name,
+            exporter: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")),
+        };
+        serde_json::to_writer(w, &file)
+    }
+
+    fn process(&mut self, t: Timestamp, event: Option<SchedEvent<'_>>) {
+        self.start.get_or_insert(t);
+        self.end = self.end.max(t);
+        let Some(event) = event else {
+            return;
+        };
+
+        match &event {
+            SchedEvent::TaskSwitch(handle, name) => {
+                let name = name
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| handle.to_string());
+                frame(&mut self.frames, &mut self.task_frames, *handle, &name);
+            }
+            SchedEvent::IsrBegin(handle, name) | SchedEvent::IsrResume(handle, name) => {
+                frame(&mut self.frames, &mut self.isr_frames, *handle, name);
+            }
+            _ => return,
+        }
+        self.stack.apply(&event);
+
+        let stack: Vec<usize> = self
+            .stack
+            .task
+            .and_then(|h| self.task_frames.get(&h))
+            .into_iter()
+            .chain(
+                self.stack
+                    .isrs
+                    .iter()
+                    .filter_map(|h| self.isr_frames.get(h)),
+            )
+            .copied()
+            .collect();
+        let common = self
+            .open
+            .iter()
+            .zip(stack.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while self.open.len() > common {
+            let frame = self.open.pop().unwrap();
+            self.events.push((t, FrameEvent::Close, frame));
+        }
+        for frame in &stack[common..] {
+            self.open.push(*frame);
+            self.events.push((t, FrameEvent::Open, *frame));
+        }
+    }
+
+    fn time(&self, t: Timestamp) -> u64 {
+        t.to_time(self.frequency).nanos_or_ticks()
+    }
+}
+
+fn frame(
+    frames: &mut Vec<String>,
+    map: &mut BTreeMap<ObjectHandle, usize>,
+    handle: ObjectHandle,
+    name: &str,
+) -> usize {
+    *map.entry(handle).or_insert_with(|| {
+        frames.push(name.to_owned());
+        frames.len() - 1
+    })
+}
+
+/// The subset of the speedscope file format used for evented profiles
+mod file {
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    pub(super) struct File<'a> {
+        #[serde(rename = "$schema")]
+        pub(super) schema: &'static str,
+        pub(super) shared: Shared<'a>,
+        pub(super) profiles: Vec<Profile<'a>>,
+        pub(super) name: &'a str,
+        pub(super) exporter: &'static str,
+    }
+
+    #[derive(Serialize)]
+    pub(super) struct Shared<'a> {
+        pub(super) frames: Vec<Frame<'a>>,
+    }
+
+    #[derive(Serialize)]
+    pub(super) struct Frame<'a> {
+        pub(super) name: &'a str,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub(super) struct Profile<'a> {
+        #[serde(rename = "type")]
+        pub(super) typ: &'static str,
+        pub(super) name: &'a str,
+        pub(super) unit: &'static str,
+        pub(super) start_value: u64,
+        pub(super) end_value: u64,
+        pub(super) events: Vec<Event>,
+    }
+
+    #[derive(Serialize)]
+    pub(super) struct Event {
+        #[serde(rename = "type")]
+        pub(super) typ: &'static str,
+        pub(super) frame: usize,
+        pub(super) at: u64,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, IsrEvent, TaskEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{ObjectName, Priority};
+
+    fn task(handle: u32, name: &str, t: u64) -> Event {
+        Event::TaskBegin(TaskEvent {
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName(name.into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(t),
+        })
+    }
+
+    fn isr(handle: u32, name: &str, t: u64, resume: bool) -> Event {
+        let ev = IsrEvent {
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName(name.into()),
+            priority: Priority(5),
+            timestamp: Timestamp(t),
+        };
+        if resume {
+            Event::IsrResume(ev)
+        } else {
+            Event::IsrBegin(ev)
+        }
+    }
+
+    #[test]
+    fn isr_over_task_nesting() {
+        let events = [
+            task(1, "a", 0),
+            isr(10, "uart", 10, false),
+            isr(11, "tick", 12, false),
+            isr(10, "uart", 14, true),
+            task(1, "a", 20),
+            task(2, "b", 30),
+            task(2, "b", 40),
+        ];
+        let mut exporter = SpeedscopeExporter::new(Frequency(0)).with_name("test");
+        events
+            .iter()
+            .for_each(|ev| exporter.process_snapshot_event(ev));
+        let mut out = Vec::new();
+        exporter.write(&mut out).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(json["$schema"], SCHEMA);
+        assert_eq!(
+            json["shared"]["frames"],
+            serde_json::json!([{"name": "a"}, {"name": "uart"}, {"name": "tick"}, {"name": "b"}])
+        );
+        let profile = &json["profiles"][0];
+        assert_eq!(profile["unit"], "none");
+        assert_eq!(profile["endValue"], 40);
+        let events: Vec<(String, u64, u64)> = profile["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|ev| {
+                (
+                    ev["type"].as_str().unwrap().to_owned(),
+                    ev["frame"].as_u64().unwrap(),
+                    ev["at"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        let expected: Vec<(String, u64, u64)> = [
+            ("O", 0, 0),
+            ("O", 1, 10),
+            ("O", 2, 12),
+            ("C", 2, 14),
+            ("C", 1, 20),
+            ("C", 0, 30),
+            ("O", 3, 30),
+            ("C", 3, 40),
+        ]
+        .into_iter()
+        .map(|(t, f, at)| (t.to_owned(), f, at))
+        .collect();
+        assert_eq!(events, expected);
+    }
+}
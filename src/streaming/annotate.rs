@@ -0,0 +1,111 @@
+use crate::streaming::event::{AnnotationEvent, Event, EventCode, EventCount};
+use crate::time::{StreamingInstant, Timestamp};
+use std::collections::VecDeque;
+
+/// An iterator adapter that inserts host-side annotations into the events,
+/// see `EventIteratorExt::annotate`.
+///
+/// Annotation timestamps are in the rollover-extended time of the events,
+/// the same as the events' timestamps until the first rollover. Annotations
+/// later than the last event are yielded after it.
+#[derive(Clone, Debug)]
+pub struct Annotate<I> {
+    iter: I,
+    /// Sorted by timestamp
+    annotations: VecDeque<AnnotationEvent>,
+    instant: StreamingInstant,
+    /// The next event, with its extended timestamp
+    pending: Option<(Timestamp, EventCode, Event)>,
+    /// Count and sequence of the last event yielded
+    event_count: EventCount,
+    sequence: u64,
+}
+
+impl<I> Annotate<I> {
+    pub(crate) fn new<A: IntoIterator<Item = AnnotationEvent>>(iter: I, annotations: A) -> Self {
+        let mut annotations: Vec<_> = annotations.into_iter().collect();
+        annotations.sort_by_key(|a| a.timestamp);
+        Self {
+            iter,
+            annotations: annotations.into(),
+            instant: StreamingInstant::zero(),
+            pending: None,
+            event_count: EventCount(0),
+            sequence: 0,
+        }
+    }
+}
+
+impl<I> Iterator for Annotate<I>
+where
+    I: Iterator<Item = (EventCode, Event)>,
+{
+    type Item = (EventCode, Event);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_none() {
+            if let Some((code, event)) = self.iter.next() {
+                let t = self.instant.elapsed(event.timestamp());
+                self.pending = Some((t, code, event));
+            }
+        }
+
+        let annotation_first = match (&self.pending, self.annotations.front()) {
+            (Some((t, _, _)), Some(annotation)) => annotation.timestamp <= *t,
+            (None, annotation) => annotation.is_some(),
+            (Some(_), None) => false,
+        };
+        if annotation_first {
+            let mut annotation = self.annotations.pop_front()?;
+            annotation.event_count = self.event_count;
+            annotation.sequence = self.sequence;
+            return Some((EventCode::ANNOTATION, Event::Annotation(annotation)));
+        }
+
+        let (_, code, event) = self.pending.take()?;
+        if !matches!(
+            event,
+            Event::Dropped(_) | Event::Annotation(_) | Event::SessionBoundary(_)
+        ) {
+            self.event_count = event.event_count();
+            self.sequence = event.sequence();
+        }
+        Some((code, event))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{test_event, EventType};
+    use crate::streaming::EventIteratorExt;
+
+    #[test]
+    fn annotations_in_time_order() {
+        // The timestamps roll over after the second event
+        let events = [
+            test_event(1, 10),
+            test_event(2, u64::from(u32::MAX) - 10),
+            test_event(3, 5),
+        ];
+        let rollover = Timestamp(u64::from(u32::MAX) + 1);
+        let annotations = [
+            AnnotationEvent::new(Timestamp(u64::from(u32::MAX) + 100), "end"),
+            AnnotationEvent::new(Timestamp(0), "start"),
+            AnnotationEvent::new(rollover, "reset").with_note("watchdog"),
+        ];
+        let annotated: Vec<_> = events
+            .into_iter()
+            .mark_gaps()
+            .annotate(annotations)
+            .map(|(code, ev)| match ev {
+                Event::Annotation(a) => {
+                    assert_eq!(code.event_type(), EventType::Annotation);
+                    format!("{}@{}", a.label, u16::from(a.event_count))
+                }
+                ev => u16::from(ev.event_count()).to_string(),
+            })
+            .collect();
+        assert_eq!(annotated, ["start@0", "1", "2", "reset@2", "3", "end@3"]);
+    }
+}
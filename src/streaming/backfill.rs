@@ -0,0 +1,100 @@
+//! Deferred name resolution for captures that begin mid-run: events referencing
+//! an object before its `ObjectName` event don't have a name, or have
+//! `UNNAMED_OBJECT` with `ObjectLookupPolicy::Substitute`.
+//!
+//! Once the events have been read, the names found later in the capture are
+//! back-filled onto the earlier events.
+
+use crate::streaming::event::{Event, EventCode};
+use crate::types::{ObjectHandle, ObjectName, UNNAMED_OBJECT};
+use std::collections::BTreeMap;
+
+/// Give the unnamed events the first name their object has later on,
+/// returns the number of events named
+pub fn backfill_names(events: &mut [(EventCode, Event)]) -> usize {
+    let mut next_names: BTreeMap<ObjectHandle, ObjectName> = BTreeMap::new();
+    let mut named = 0;
+    for (_, event) in events.iter_mut().rev() {
+        let Some(handle) = event.object_handle() else {
+            continue;
+        };
+        match event.object_name() {
+            Some(name) if name != UNNAMED_OBJECT => {
+                next_names.insert(handle, ObjectName(name.into()));
+            }
+            _ => {
+                if let Some(name) = next_names.get(&handle) {
+                    if event.set_object_name(name.clone()) {
+                        named += 1;
+                    }
+                }
+            }
+        }
+    }
+    named
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{
+        EventCount, EventId, EventType, ObjectNameEvent, QueueEvent, TaskEvent,
+    };
+    use crate::time::Timestamp;
+    use crate::types::{Priority, SymbolString};
+
+    fn code(et: EventType) -> EventCode {
+        EventCode(u16::from(EventId::from(et)).into())
+    }
+
+    #[test]
+    fn names_from_later_events() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let queue = ObjectHandle::new(0x2000_2000).unwrap();
+        let task_event = |name: &str, count| {
+            let ev = TaskEvent {
+                event_count: EventCount(count),
+                sequence: count.into(),
+                timestamp: Timestamp(count.into()),
+                handle: task,
+                name: ObjectName(name.into()),
+                priority: Priority(1),
+            };
+            (code(EventType::TaskReady), Event::TaskReady(ev))
+        };
+        let mut events = vec![
+            task_event(UNNAMED_OBJECT, 1),
+            (
+                code(EventType::QueueSend),
+                Event::QueueSend(QueueEvent {
+                    event_count: EventCount(2),
+                    sequence: 2,
+                    timestamp: Timestamp(2),
+                    handle: queue,
+                    name: None,
+                    ticks_to_wait: None,
+                    messages_waiting: 0,
+                }),
+            ),
+            (
+                code(EventType::ObjectName),
+                Event::ObjectName(ObjectNameEvent {
+                    event_count: EventCount(3),
+                    sequence: 3,
+                    timestamp: Timestamp(3),
+                    handle: task,
+                    name: SymbolString("t0".into()),
+                }),
+            ),
+            task_event("t0", 4),
+            task_event("t1", 5),
+        ];
+
+        assert_eq!(backfill_names(&mut events), 1);
+        let names: Vec<_> = events.iter().map(|(_, ev)| ev.object_name()).collect();
+        assert_eq!(
+            names,
+            [Some("t0"), None, Some("t0"), Some("t0"), Some("t1")]
+        );
+    }
+}
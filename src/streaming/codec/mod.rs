@@ -0,0 +1,93 @@
+//! Layouts of the version-specific parts of the streaming header and timestamp info
+//!
+//! The codec is selected once from the format version, adding support for a new
+//! layout means adding a module here rather than branching in the readers.
+
+use crate::streaming::Error;
+use crate::time::Frequency;
+use crate::types::{Endianness, PlatformCfgVersion};
+use std::io::Read;
+
+mod v10;
+mod v13;
+
+/// The platform configuration name and version from the header
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct PlatformCfg {
+    pub name: [u8; 8],
+    pub version: PlatformCfgVersion,
+}
+
+/// The timer fields from the timestamp info
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct TimerCfg {
+    pub frequency: Frequency,
+    pub period: u32,
+}
+
+pub(crate) trait Codec: Sync {
+    fn read_platform_cfg(
+        &self,
+        r: &mut dyn Read,
+        endianness: Endianness,
+    ) -> Result<PlatformCfg, Error>;
+
+    fn read_timer_cfg(&self, r: &mut dyn Read, endianness: Endianness) -> Result<TimerCfg, Error>;
+}
+
+/// The codec for the given format version.
+///
+/// Unknown versions use the latest layout.
+pub(crate) fn for_version(format_version: u16) -> &'static dyn Codec {
+    match format_version {
+        10 | 12 => &v10::V10,
+        _ => &v13::V13,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layouts_decode_the_same_values() {
+        let v10_bytes = [
+            b'F', b'r', b'e', b'e', b'R', b'T', b'O', b'S', 0x03, 0x00, 0x02, 0x01,
+        ];
+        let v13_bytes = [
+            0x03, 0x00, 0x02, 0x01, b'F', b'r', b'e', b'e', b'R', b'T', b'O', b'S',
+        ];
+        let expected = PlatformCfg {
+            name: *b"FreeRTOS",
+            version: PlatformCfgVersion {
+                major: 1,
+                minor: 2,
+                patch: 3,
+            },
+        };
+        for (version, bytes) in [
+            (10, v10_bytes),
+            (12, v10_bytes),
+            (13, v13_bytes),
+            (14, v13_bytes),
+        ] {
+            let cfg = for_version(version)
+                .read_platform_cfg(&mut bytes.as_slice(), Endianness::Little)
+                .unwrap();
+            assert_eq!(cfg, expected, "v{version}");
+        }
+
+        let expected = TimerCfg {
+            frequency: Frequency(1_000_000),
+            period: 0xFFFF,
+        };
+        let freq = 1_000_000_u32.to_be_bytes();
+        let period = 0xFFFF_u32.to_be_bytes();
+        for (version, bytes) in [(12, [freq, period].concat()), (14, [period, freq].concat())] {
+            let cfg = for_version(version)
+                .read_timer_cfg(&mut bytes.as_slice(), Endianness::Big)
+                .unwrap();
+            assert_eq!(cfg, expected, "v{version}");
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::{Codec, PlatformCfg, TimerCfg};
+use crate::streaming::Error;
+use crate::time::Frequency;
+use crate::types::{Endianness, PlatformCfgVersion};
+use byteordered::ByteOrdered;
+use std::io::Read;
+
+/// Formats v13 and later, the platform config version moved ahead of its name
+/// and the timer period ahead of the frequency
+pub(super) struct V13;
+
+impl Codec for V13 {
+    fn read_platform_cfg(
+        &self,
+        r: &mut dyn Read,
+        endianness: Endianness,
+    ) -> Result<PlatformCfg, Error> {
+        let mut r = ByteOrdered::new(r, byteordered::Endianness::from(endianness));
+        let patch = r.read_u16()?;
+        let minor = r.read_u8()?;
+        let major = r.read_u8()?;
+        let mut name = [0; 8];
+        r.read_exact(&mut name)?;
+        Ok(PlatformCfg {
+            name,
+            version: PlatformCfgVersion {
+                major,
+                minor,
+                patch,
+            },
+        })
+    }
+
+    fn read_timer_cfg(&self, r: &mut dyn Read, endianness: Endianness) -> Result<TimerCfg, Error> {
+        let mut r = ByteOrdered::new(r, byteordered::Endianness::from(endianness));
+        let period = r.read_u32()?;
+        // NOTE: we assume TRC_BASE_TYPE and TRC_UNSIGNED_BASE_TYPE are 32-bit
+        let frequency = Frequency(r.read_u32()?);
+        Ok(TimerCfg { frequency, period })
+    }
+}
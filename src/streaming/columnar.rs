@@ -0,0 +1,338 @@
+//! Columnar export of streaming events as Arrow record batches and, with the
+//! `parquet` feature, Parquet files
+//!
+//! Each event is a row, columns that don't apply to an event are null.
+
+use crate::streaming::event::{Event, EventCode};
+use crate::types::Argument;
+use arrow_array::builder::{
+    Float64Builder, ListBuilder, StringBuilder, UInt16Builder, UInt32Builder, UInt64Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use std::sync::{Arc, OnceLock};
+
+/// The schema of the record batches built by `RecordBatchBuilder`
+pub fn schema() -> SchemaRef {
+    static SCHEMA: OnceLock<SchemaRef> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| {
+            Arc::new(Schema::new(vec![
+                Field::new("event_count", DataType::UInt16, false),
+                // The event count extended across its rollovers, see `Event::sequence`
+                Field::new("sequence", DataType::UInt64, false),
+                Field::new("timestamp", DataType::UInt64, false),
+                Field::new("event_id", DataType::UInt16, false),
+                Field::new("event_type", DataType::Utf8, false),
+                Field::new("object_handle", DataType::UInt32, true),
+                Field::new("object_name", DataType::Utf8, true),
+                Field::new("priority", DataType::UInt32, true),
+                Field::new("address", DataType::UInt32, true),
+                Field::new("size", DataType::UInt32, true),
+                Field::new("heap_current", DataType::UInt32, true),
+                Field::new("channel", DataType::Utf8, true),
+                Field::new("formatted_string", DataType::Utf8, true),
+                // Numeric user event arguments, string arguments are skipped
+                Field::new(
+                    "args",
+                    DataType::List(Arc::new(Field::new_list_field(DataType::Float64, true))),
+                    true,
+                ),
+            ]))
+        })
+        .clone()
+}
+
+/// Accumulates events into an Arrow `RecordBatch`
+#[derive(Debug)]
+pub struct RecordBatchBuilder {
+    event_count: UInt16Builder,
+    sequence: UInt64Builder,
+    timestamp: UInt64Builder,
+    event_id: UInt16Builder,
+    event_type: StringBuilder,
+    object_handle: UInt32Builder,
+    object_name: StringBuilder,
+    priority: UInt32Builder,
+    address: UInt32Builder,
+    size: UInt32Builder,
+    heap_current: UInt32Builder,
+    channel: StringBuilder,
+    formatted_string: StringBuilder,
+    args: ListBuilder<Float64Builder>,
+    len: usize,
+}
+
+impl RecordBatchBuilder {
+    pub fn new() -> Self {
+        Self {
+            event_count: UInt16Builder::new(),
+            sequence: UInt64Builder::new(),
+            timestamp: UInt64Builder::new(),
+            event_id: UInt16Builder::new(),
+            event_type: StringBuilder::new(),
+            object_handle: UInt32Builder::new(),
+            object_name: StringBuilder::new(),
+            priority: UInt32Builder::new(),
+            address: UInt32Builder::new(),
+            size: UInt32Builder::new(),
+            heap_current: UInt32Builder::new(),
+            channel: StringBuilder::new(),
+            formatted_string: StringBuilder::new(),
+            args: ListBuilder::new(Float64Builder::new()),
+            len: 0,
+        }
+    }
+
+    /// Number of events appended since the last `finish`
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn append(&mut self, event_code: EventCode, event: &Event) {
+        self.len += 1;
+        self.event_count.append_value(event.event_count().into());
+        self.sequence.append_value(event.sequence());
+        self.timestamp.append_value(event.timestamp().ticks());
+        self.event_id.append_value(event_code.event_id().0);
+        self.event_type
+            .append_value(event_code.event_type().to_string());
+        self.object_handle
+            .append_option(event.object_handle().map(u32::from));
+        self.object_name.append_option(event.object_name());
+
+        let priority = match event {
+            Event::TaskCreate(ev)
+            | Event::TaskReady(ev)
+            | Event::TaskPriority(ev)
+            | Event::TaskPriorityInherit(ev)
+            | Event::TaskPriorityDisinherit(ev)
+            | Event::TaskBegin(ev)
+            | Event::TaskResume(ev)
+            | Event::TaskActivate(ev) => Some(ev.priority.0),
+            Event::IsrDefine(ev) | Event::IsrBegin(ev) | Event::IsrResume(ev) => {
+                Some(ev.priority.0)
+            }
+            _ => None,
+        };
+        self.priority.append_option(priority);
+
+        match event {
+            Event::MemoryAlloc(ev) | Event::MemoryFree(ev) => {
+                self.address.append_value(ev.address);
+                self.size.append_value(ev.size);
+                self.heap_current.append_value(ev.heap.current);
+            }
+            _ => {
+                self.address.append_null();
+                self.size.append_null();
+                self.heap_current.append_null();
+            }
+        }
+
+        match event {
+            Event::User(ev) => {
+                self.channel.append_value(ev.channel.to_string());
+                self.formatted_string.append_value(&ev.formatted_string.0);
+                self.args
+                    .append_value(ev.args.iter().filter_map(Argument::as_f64).map(Some));
+            }
+            _ => {
+                self.channel.append_null();
+                self.formatted_string.append_null();
+                self.args.append_null();
+            }
+        }
+    }
+
+    /// Build a batch of the events appended so far and reset the builder
+    pub fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        self.len = 0;
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.event_count.finish()),
+            Arc::new(self.sequence.finish()),
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.event_id.finish()),
+            Arc::new(self.event_type.finish()),
+            Arc::new(self.object_handle.finish()),
+            Arc::new(self.object_name.finish()),
+            Arc::new(self.priority.finish()),
+            Arc::new(self.address.finish()),
+            Arc::new(self.size.finish()),
+            Arc::new(self.heap_current.finish()),
+            Arc::new(self.channel.finish()),
+            Arc::new(self.formatted_string.finish()),
+            Arc::new(self.args.finish()),
+        ];
+        RecordBatch::try_new(schema(), columns)
+    }
+}
+
+impl Default for RecordBatchBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub use self::parquet_export::ParquetExporter;
+
+#[cfg(feature = "parquet")]
+mod parquet_export {
+    use super::{schema, RecordBatchBuilder};
+    use crate::streaming::event::{Event, EventCode};
+    use parquet::arrow::ArrowWriter;
+    use parquet::errors::ParquetError;
+    use parquet::file::properties::WriterProperties;
+    use std::io::Write;
+
+    /// Writes events to a Parquet file, one row group per `rows_per_batch` events
+    pub struct ParquetExporter<W: Write + Send> {
+        writer: ArrowWriter<W>,
+        builder: RecordBatchBuilder,
+        rows_per_batch: usize,
+    }
+
+    impl<W: Write + Send> ParquetExporter<W> {
+        pub const DEFAULT_ROWS_PER_BATCH: usize = 64 * 1024;
+
+        pub fn new(w: W) -> Result<Self, ParquetError> {
+            Self::with_properties(w, WriterProperties::default())
+        }
+
+        pub fn with_properties(w: W, props: WriterProperties) -> Result<Self, ParquetError> {
+            Ok(Self {
+                writer: ArrowWriter::try_new(w, schema(), Some(props))?,
+                builder: RecordBatchBuilder::new(),
+                rows_per_batch: Self::DEFAULT_ROWS_PER_BATCH,
+            })
+        }
+
+        pub fn with_rows_per_batch(mut self, rows_per_batch: usize) -> Self {
+            self.rows_per_batch = rows_per_batch.max(1);
+            self
+        }
+
+        pub fn write(&mut self, event_code: EventCode, event: &Event) -> Result<(), ParquetError> {
+            self.builder.append(event_code, event);
+            if self.builder.len() >= self.rows_per_batch {
+                self.flush_batch()?;
+            }
+            Ok(())
+        }
+
+        /// Write the remaining events and the file footer, returning the inner writer
+        pub fn finish(mut self) -> Result<W, ParquetError> {
+            self.flush_batch()?;
+            self.writer.into_inner()
+        }
+
+        fn flush_batch(&mut self) -> Result<(), ParquetError> {
+            if self.builder.is_empty() {
+                return Ok(());
+            }
+            let batch = self.builder.finish()?;
+            self.writer.write(&batch)?;
+            self.writer.flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, EventId, EventType, MemoryEvent, UserEvent};
+    use crate::time::Timestamp;
+    use crate::types::{Argument, FormatString, FormattedString, Heap, UserEventChannel};
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, UInt32Type};
+    use arrow_array::Array;
+
+    fn events() -> Vec<(EventCode, Event)> {
+        vec![
+            (
+                EventCode(EventId::from(EventType::MemoryAlloc).0.into()),
+                Event::MemoryAlloc(MemoryEvent {
+                    event_count: EventCount(1),
+                    sequence: 1,
+                    timestamp: Timestamp(10),
+                    address: 0x2000_0000,
+                    size: 64,
+                    heap: Heap {
+                        current: 64,
+                        high_water_mark: 64,
+                        max: 1024,
+                    },
+                    symbolized: None,
+                    task: None,
+                    task_name: None,
+                }),
+            ),
+            (
+                EventCode(0x90),
+                Event::User(UserEvent {
+                    event_count: EventCount(2),
+                    sequence: 2,
+                    timestamp: Timestamp(20),
+                    channel: UserEventChannel::Default,
+                    format_string: FormatString("x=%d %s".into()),
+                    formatted_string: FormattedString("x=3 hi".to_owned()),
+                    args: vec![Argument::I32(3), Argument::String("hi".to_owned())].into(),
+                }),
+            ),
+        ]
+    }
+
+    #[test]
+    fn record_batch_columns() {
+        let mut b = RecordBatchBuilder::new();
+        for (code, ev) in events() {
+            b.append(code, &ev);
+        }
+        let batch = b.finish().unwrap();
+        assert!(b.is_empty());
+        assert_eq!(batch.num_rows(), 2);
+
+        let size = batch
+            .column_by_name("size")
+            .unwrap()
+            .as_primitive::<UInt32Type>();
+        assert_eq!(size.value(0), 64);
+        assert!(size.is_null(1));
+        let strings = batch
+            .column_by_name("formatted_string")
+            .unwrap()
+            .as_string::<i32>();
+        assert!(strings.is_null(0));
+        assert_eq!(strings.value(1), "x=3 hi");
+        let args = batch.column_by_name("args").unwrap().as_list::<i32>();
+        let args = args.value(1);
+        assert_eq!(args.as_primitive::<Float64Type>().values(), &[3.0]);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_roundtrip() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut exporter = ParquetExporter::new(Vec::new())
+            .unwrap()
+            .with_rows_per_batch(1);
+        for (code, ev) in events() {
+            exporter.write(code, &ev).unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("trc-{}.parquet", std::process::id()));
+        std::fs::write(&path, exporter.finish().unwrap()).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let rows: usize = reader.map(|b| b.unwrap().num_rows()).sum();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rows, 2);
+    }
+}
@@ -1,48 +1,102 @@
-use crate::streaming::Error;
+use crate::intern::InternPool;
+use crate::streaming::event::EventCount;
+use crate::streaming::{Error, SymbolMap};
+use crate::symbolizer::Symbolizer;
+use crate::table_cache::{self, TableCacheError, TableKind};
+use crate::time::Timestamp;
 use crate::types::{
-    Endianness, Heap, ObjectClass, ObjectHandle, Priority, SymbolString, SymbolTableExt,
-    TrimmedString, STARTUP_TASK_NAME, TZ_CTRL_TASK_NAME,
+    Endianness, Heap, ObjectClass, ObjectHandle, Priority, StringDecoder, StringSanitizer,
+    SymbolString, SymbolTableExt, TrimmedString,
 };
 use byteordered::ByteOrdered;
-use std::collections::BTreeMap;
-use std::io::Read;
-use tracing::debug;
+use derive_more::Display;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Generations are unique across tables, two tables only have the same
+/// generation when one is a copy of the other
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
 
 /// The address field of an entry is the key.
 /// This is either an object address (task, queue, etc) or the address of the
 /// entry "slot" in memory (self-referential, i.e. user event strings).
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub struct EntryTable(BTreeMap<ObjectHandle, Entry>);
+///
+/// Multicore recorders share one table between all cores, objects are
+/// registered by whichever core creates them.
+#[derive(Clone, Debug)]
+pub struct EntryTable {
+    entries: BTreeMap<ObjectHandle, Entry>,
+    /// Handles inserted since the entries were last symbolized, see `symbolize_inserted`
+    unsymbolized: BTreeSet<ObjectHandle>,
+    /// See `generation`
+    generation: u64,
+}
+
+// Tables are compared by their entries only
+impl PartialEq for EntryTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for EntryTable {}
+
+impl PartialOrd for EntryTable {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EntryTable {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.entries.cmp(&other.entries)
+    }
+}
 
+impl std::hash::Hash for EntryTable {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.entries.hash(state)
+    }
+}
+
+// An empty table, the startup task is added by `WellKnownObjects`
 impl Default for EntryTable {
     fn default() -> Self {
-        let mut entries = BTreeMap::new();
-        let mut states = EntryStates::default();
-        states.set_priority(Priority(1));
-        entries.insert(
-            ObjectHandle::NO_TASK,
-            Entry {
-                symbol: SymbolString(STARTUP_TASK_NAME.to_owned()).into(),
-                options: 0,
-                states,
-                class: ObjectClass::Task.into(),
-            },
-        );
-        Self(entries)
+        Self {
+            entries: BTreeMap::new(),
+            unsymbolized: BTreeSet::new(),
+            generation: next_generation(),
+        }
     }
 }
 
 impl EntryTable {
     pub fn entries(&self) -> &BTreeMap<ObjectHandle, Entry> {
-        &self.0
+        &self.entries
+    }
+
+    /// Changes each time the entries change, i.e. to find out whether the table
+    /// changed without comparing it.
+    /// A copy of the table has the same generation until either one changes.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn changed(&mut self) {
+        self.generation = next_generation();
     }
 
     pub fn symbol(&self, handle: ObjectHandle) -> Option<&SymbolString> {
-        self.0.get(&handle).and_then(|e| e.symbol.as_ref())
+        self.entries.get(&handle).and_then(|e| e.symbol.as_ref())
     }
 
     pub fn class(&self, handle: ObjectHandle) -> Option<ObjectClass> {
-        self.0.get(&handle).and_then(|e| e.class)
+        self.entries.get(&handle).and_then(|e| e.class)
     }
 
     pub fn symbol_handle<S: AsRef<str>>(
@@ -50,7 +104,7 @@ impl EntryTable {
         symbol: S,
         class: Option<ObjectClass>,
     ) -> Option<ObjectHandle> {
-        self.0.iter().find_map(|(handle, entry)| {
+        self.entries.iter().find_map(|(handle, entry)| {
             let sym_match = entry.symbol.as_deref() == Some(symbol.as_ref());
             let class_match = match class {
                 None => true,
@@ -64,8 +118,108 @@ impl EntryTable {
         })
     }
 
+    /// The current priority of each task and ISR entry.
+    ///
+    /// Older recorders kept the object priorities in a separate object data table,
+    /// the supported formats (v10+) carry them in the entry table states.
+    pub fn priorities(&self) -> impl Iterator<Item = (ObjectHandle, Priority)> + '_ {
+        self.entries
+            .iter()
+            .filter_map(|(handle, entry)| entry.priority().map(|p| (*handle, p)))
+    }
+
+    /// Case-insensitive search of the entries' symbols.
+    ///
+    /// A pattern containing `*` (any sequence) or `?` (any character) is matched
+    /// against the whole symbol, otherwise symbols containing the pattern match.
+    pub fn search<'a>(
+        &'a self,
+        pattern: &str,
+    ) -> impl Iterator<Item = (ObjectHandle, &'a Entry)> + 'a {
+        let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+        let is_glob = pattern.iter().any(|c| matches!(c, '*' | '?'));
+        let substr: String = pattern.iter().collect();
+        self.entries.iter().filter_map(move |(handle, entry)| {
+            let symbol = entry.symbol.as_deref()?.to_lowercase();
+            let found = if is_glob {
+                glob_match(&pattern, &symbol.chars().collect::<Vec<_>>())
+            } else {
+                symbol.contains(&substr)
+            };
+            found.then_some((*handle, entry))
+        })
+    }
+
+    /// The entries as a table of aligned columns: handle, class and symbol
+    pub fn to_table_string(&self) -> String {
+        let rows: Vec<[String; 3]> = self
+            .entries
+            .iter()
+            .map(|(handle, entry)| {
+                [
+                    handle.to_string(),
+                    entry
+                        .class
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "NA".to_owned()),
+                    entry.symbol.as_deref().unwrap_or("NA").to_owned(),
+                ]
+            })
+            .collect();
+        let width = |col: usize| rows.iter().map(|r| r[col].len()).max().unwrap_or(0);
+        let (handle_width, class_width) = (width(0), width(1));
+        let mut table = String::new();
+        for [handle, class, symbol] in rows.iter() {
+            table.push_str(&format!(
+                "{handle:>handle_width$}    {class:>class_width$}    {symbol}\n"
+            ));
+        }
+        table
+    }
+
+    /// The differences between this table and `other`, ordered by handle
+    pub fn diff(&self, other: &EntryTable) -> Vec<EntryDifference> {
+        let mut diffs = Vec::new();
+        for (handle, entry) in self.entries.iter() {
+            let Some(other_entry) = other.entries.get(handle) else {
+                diffs.push(EntryDifference::Removed(*handle));
+                continue;
+            };
+            if entry.class != other_entry.class {
+                diffs.push(EntryDifference::ClassChanged {
+                    handle: *handle,
+                    previous: entry.class,
+                    class: other_entry.class,
+                });
+            }
+            if entry.symbol != other_entry.symbol {
+                diffs.push(EntryDifference::Renamed {
+                    handle: *handle,
+                    previous: entry.symbol.clone(),
+                    name: other_entry.symbol.clone(),
+                });
+            }
+            if let (Some(previous), Some(priority)) = (entry.priority(), other_entry.priority()) {
+                if previous != priority {
+                    diffs.push(EntryDifference::PriorityChanged {
+                        handle: *handle,
+                        previous,
+                        priority,
+                    });
+                }
+            }
+        }
+        for handle in other.entries.keys() {
+            if !self.entries.contains_key(handle) {
+                diffs.push(EntryDifference::Added(*handle));
+            }
+        }
+        diffs.sort_by_key(|d| d.handle());
+        diffs
+    }
+
     pub(crate) fn system_heap(&self) -> Option<Heap> {
-        self.0
+        self.entries
             .values()
             .find_map(|entry| {
                 if entry.symbol.as_deref() == Some(Entry::SYSTEM_HEAP_SYMBOL) {
@@ -81,9 +235,154 @@ impl EntryTable {
             })
     }
 
+    /// The entry of `handle`, inserted when missing.
+    /// Changes made through the returned entry don't change the generation,
+    /// see `set_symbol`, `set_class` and `set_priority`.
     pub(crate) fn entry(&mut self, handle: ObjectHandle) -> &mut Entry {
-        self.0.entry(handle).or_default()
+        if !self.entries.contains_key(&handle) {
+            self.unsymbolized.insert(handle);
+            self.changed();
+        }
+        self.entries.entry(handle).or_default()
+    }
+
+    /// Set the symbol of the entry of `handle`, inserted when missing
+    pub(crate) fn set_symbol(&mut self, handle: ObjectHandle, symbol: SymbolString) -> &mut Entry {
+        if self.entries.get(&handle).and_then(|e| e.symbol.as_ref()) != Some(&symbol) {
+            self.changed();
+        }
+        let entry = self.entry(handle);
+        entry.set_symbol(symbol);
+        entry
+    }
+
+    /// Set the class of the entry of `handle`, inserted when missing
+    pub(crate) fn set_class(&mut self, handle: ObjectHandle, class: ObjectClass) -> &mut Entry {
+        if self.class(handle) != Some(class) {
+            self.changed();
+        }
+        let entry = self.entry(handle);
+        entry.set_class(class);
+        entry
+    }
+
+    /// Clear the class of the entry of `handle`, if any
+    pub(crate) fn clear_class(&mut self, handle: ObjectHandle) {
+        if let Some(entry) = self.entries.get_mut(&handle).filter(|e| e.class.is_some()) {
+            entry.class = None;
+            self.changed();
+        }
+    }
+
+    /// Set the priority state of the entry of `handle`, inserted when missing
+    pub(crate) fn set_priority(&mut self, handle: ObjectHandle, priority: Priority) -> &mut Entry {
+        if self.entries.get(&handle).map(|e| e.states.priority()) != Some(priority) {
+            self.changed();
+        }
+        let entry = self.entry(handle);
+        entry.states.set_priority(priority);
+        entry
+    }
+
+    /// Pre-populate the table from a symbol map.
+    /// Symbols and classes already known to the table are left as is.
+    pub fn seed(&mut self, map: &SymbolMap) {
+        for (handle, map_entry) in map.iter() {
+            let entry = self.entry(*handle);
+            if entry.symbol.is_none() {
+                entry.set_symbol(map_entry.symbol.clone());
+            }
+            if entry.class.is_none() {
+                entry.class = map_entry.class;
+            }
+        }
+        self.changed();
+    }
+
+    /// Resolve the address of each entry that hasn't already been symbolized
+    pub fn symbolize(&mut self, symbolizer: &Symbolizer) {
+        for (handle, entry) in self.entries.iter_mut() {
+            if entry.symbolized.is_none() {
+                entry.symbolized = symbolizer.symbolize(u32::from(*handle).into());
+            }
+        }
+        self.unsymbolized.clear();
+        self.changed();
+    }
+
+    /// Resolve the address of the entries inserted since the last call, or since `symbolize`.
+    /// A handle that was removed and inserted again is resolved again.
+    pub(crate) fn symbolize_inserted(&mut self, symbolizer: &Symbolizer) {
+        for handle in std::mem::take(&mut self.unsymbolized) {
+            if let Some(entry) = self.entries.get_mut(&handle) {
+                if entry.symbolized.is_none() {
+                    entry.symbolized = symbolizer.symbolize(u32::from(handle).into());
+                    if entry.symbolized.is_some() {
+                        self.generation = next_generation();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sanitize the symbol of each entry
+    pub fn sanitize_symbols(&mut self, sanitizer: &StringSanitizer) {
+        for entry in self.entries.values_mut() {
+            if let Some(symbol) = &mut entry.symbol {
+                symbol.0 = sanitizer.sanitize(&symbol.0).into();
+            }
+        }
+        self.changed();
+    }
+
+    /// Replace the symbol of each entry with its handle in the pool, the
+    /// symbols are equal so the generation is left as is
+    pub(crate) fn intern_symbols(&mut self, pool: &InternPool) {
+        for entry in self.entries.values_mut() {
+            if let Some(symbol) = &mut entry.symbol {
+                symbol.0 = pool.intern(&symbol.0);
+            }
+        }
+    }
+
+    /// Remove an entry, i.e. when the object has been deleted and its handle
+    /// is free to be reused
+    pub(crate) fn remove(&mut self, handle: ObjectHandle) -> Option<Entry> {
+        self.unsymbolized.remove(&handle);
+        let entry = self.entries.remove(&handle);
+        if entry.is_some() {
+            self.changed();
+        }
+        entry
+    }
+}
+
+/// Match `text` against a pattern of literal characters, `*` and `?`
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it's matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
     }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 impl SymbolTableExt for EntryTable {
@@ -92,13 +391,119 @@ impl SymbolTableExt for EntryTable {
     }
 }
 
+/// A difference between two entry tables, see `EntryTable::diff`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum EntryDifference {
+    Added(ObjectHandle),
+    Removed(ObjectHandle),
+    Renamed {
+        handle: ObjectHandle,
+        previous: Option<SymbolString>,
+        name: Option<SymbolString>,
+    },
+    /// Task and ISR entries only
+    PriorityChanged {
+        handle: ObjectHandle,
+        previous: Priority,
+        priority: Priority,
+    },
+    ClassChanged {
+        handle: ObjectHandle,
+        previous: Option<ObjectClass>,
+        class: Option<ObjectClass>,
+    },
+}
+
+impl EntryDifference {
+    pub fn handle(&self) -> ObjectHandle {
+        match self {
+            EntryDifference::Added(handle) | EntryDifference::Removed(handle) => *handle,
+            EntryDifference::Renamed { handle, .. }
+            | EntryDifference::PriorityChanged { handle, .. }
+            | EntryDifference::ClassChanged { handle, .. } => *handle,
+        }
+    }
+}
+
+impl std::fmt::Display for EntryDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn or_na<T: std::fmt::Display>(v: &Option<T>) -> String {
+            v.as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_else(|| "NA".to_owned())
+        }
+        match self {
+            EntryDifference::Added(handle) => write!(f, "+ {handle}"),
+            EntryDifference::Removed(handle) => write!(f, "- {handle}"),
+            EntryDifference::Renamed {
+                handle,
+                previous,
+                name,
+            } => write!(f, "~ {handle} name: {} -> {}", or_na(previous), or_na(name)),
+            EntryDifference::PriorityChanged {
+                handle,
+                previous,
+                priority,
+            } => write!(f, "~ {handle} priority: {previous} -> {priority}"),
+            EntryDifference::ClassChanged {
+                handle,
+                previous,
+                class,
+            } => write!(
+                f,
+                "~ {handle} class: {} -> {}",
+                or_na(previous),
+                or_na(class)
+            ),
+        }
+    }
+}
+
+/// A change of an entry's symbol, see `EventParser::name_history`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct NameChange {
+    pub event_count: EventCount,
+    pub timestamp: Timestamp,
+    pub previous: SymbolString,
+    pub name: SymbolString,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum PriorityChangeKind {
+    /// `TaskPriority`
+    #[display(fmt = "Set")]
+    Set,
+    /// `TaskPriorityInherit`
+    #[display(fmt = "Inherit")]
+    Inherit,
+    /// `TaskPriorityDisinherit`
+    #[display(fmt = "Disinherit")]
+    Disinherit,
+    /// `TaskActivate` carrying a priority different from the known one
+    #[display(fmt = "Activate")]
+    Activate,
+}
+
+/// A change of a task's priority, see `EventParser::priority_history`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PriorityChange {
+    pub event_count: EventCount,
+    pub timestamp: Timestamp,
+    pub kind: PriorityChangeKind,
+    /// The priority from the entry table before the change
+    pub previous: Priority,
+    pub priority: Priority,
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct Entry {
-    /// The symbol (lossy converted to UTF8)                                                                           
+    /// The symbol, decoded with the configured `StringDecoder`
     pub symbol: Option<SymbolString>,
     pub options: u32,
     pub states: EntryStates,
     pub class: Option<ObjectClass>,
+    /// The symbol at the entry's address, if a symbolizer is provided
+    pub symbolized: Option<String>,
 }
 
 impl Entry {
@@ -114,6 +519,14 @@ impl Entry {
     pub(crate) fn set_class(&mut self, class: ObjectClass) {
         self.class = class.into()
     }
+
+    /// The priority of task and ISR entries
+    pub fn priority(&self) -> Option<Priority> {
+        match self.class? {
+            ObjectClass::Task | ObjectClass::Isr => Some(self.states.priority()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
@@ -127,7 +540,8 @@ impl EntryStates {
         Self([states[0], states[1], states[2]])
     }
 
-    pub(crate) const fn priority(&self) -> Priority {
+    /// The first state of task and ISR entries, maintained by priority change events
+    pub const fn priority(&self) -> Priority {
         Priority(self.0[0])
     }
 
@@ -149,7 +563,61 @@ impl EntryStates {
 }
 
 impl EntryTable {
-    pub(crate) fn read<R: Read>(r: &mut R, endianness: Endianness) -> Result<Self, Error> {
+    /// Write the table to the compact table cache format, see `table_cache`
+    pub fn save<W: Write>(&self, w: W) -> Result<(), TableCacheError> {
+        let mut w = table_cache::write_header(w, TableKind::EntryTable)?;
+        table_cache::write_len(&mut w, self.entries.len())?;
+        for (handle, entry) in self.entries.iter() {
+            w.write_u32(u32::from(*handle))?;
+            table_cache::write_opt_str(&mut w, entry.symbol.as_deref())?;
+            w.write_u32(entry.options)?;
+            for state in entry.states.0.iter() {
+                w.write_u32(*state)?;
+            }
+            table_cache::write_opt_class(&mut w, entry.class)?;
+            table_cache::write_opt_str(&mut w, entry.symbolized.as_deref())?;
+        }
+        Ok(())
+    }
+
+    /// Read a table previously written with `EntryTable::save`
+    pub fn load<R: Read>(r: R) -> Result<Self, TableCacheError> {
+        let mut r = table_cache::read_header(r, TableKind::EntryTable)?;
+        let num_entries = table_cache::read_len(&mut r)?;
+        let mut entries = BTreeMap::new();
+        for _ in 0..num_entries {
+            let handle = table_cache::read_handle(&mut r)?;
+            let symbol = table_cache::read_opt_string(&mut r)?.map(|s| SymbolString(s.into()));
+            let options = r.read_u32()?;
+            let mut states = EntryStates::default();
+            for state in states.0.iter_mut() {
+                *state = r.read_u32()?;
+            }
+            let class = table_cache::read_opt_class(&mut r)?;
+            let symbolized = table_cache::read_opt_string(&mut r)?;
+            entries.insert(
+                handle,
+                Entry {
+                    symbol,
+                    options,
+                    states,
+                    class,
+                    symbolized,
+                },
+            );
+        }
+        Ok(Self {
+            entries,
+            unsymbolized: BTreeSet::new(),
+            generation: next_generation(),
+        })
+    }
+
+    pub(crate) fn read<R: Read>(
+        r: &mut R,
+        endianness: Endianness,
+        string_decoder: &StringDecoder,
+    ) -> Result<Self, Error> {
         let mut r = ByteOrdered::new(r, byteordered::Endianness::from(endianness));
         let num_entries = r.read_u32()?;
         let symbol_size = r.read_u32()? as usize;
@@ -175,15 +643,9 @@ impl EntryTable {
                 let options = r.read_u32()?;
                 r.read_exact(&mut buf)?;
                 if let Some(oh) = ObjectHandle::new(address) {
-                    let symbol: SymbolString = TrimmedString::from_raw(&buf).into();
-
-                    let class = if symbol.0 == TZ_CTRL_TASK_NAME {
-                        Some(ObjectClass::Task)
-                    } else {
-                        None
-                    };
-
-                    table.0.insert(
+                    let symbol: SymbolString =
+                        TrimmedString::from_raw_with(&buf, string_decoder).into();
+                    table.entries.insert(
                         oh,
                         Entry {
                             symbol: if !symbol.0.is_empty() {
@@ -193,7 +655,8 @@ impl EntryTable {
                             },
                             options,
                             states,
-                            class,
+                            class: None,
+                            symbolized: None,
                         },
                     );
                 }
@@ -202,3 +665,170 @@ impl EntryTable {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::WellKnownObjects;
+
+    #[test]
+    fn read_with_string_decoder() {
+        let mut raw = Vec::new();
+        // 1 entry, 8 byte symbols, 3 states
+        for word in [1_u32, 8, 3, 0x10, 0, 0, 0, 0] {
+            raw.extend_from_slice(&word.to_le_bytes());
+        }
+        raw.extend_from_slice(b"caf\xE9\0\0\0\0");
+        let oh = ObjectHandle::new(0x10).unwrap();
+
+        // Each table is read with its own decoder
+        let latin1 = EntryTable::read(
+            &mut raw.as_slice(),
+            Endianness::Little,
+            &StringDecoder::Latin1,
+        )
+        .unwrap();
+        let utf8 = EntryTable::read(
+            &mut raw.as_slice(),
+            Endianness::Little,
+            &StringDecoder::Utf8Lossy,
+        )
+        .unwrap();
+        assert_eq!(latin1.symbol(oh).map(|s| s.as_ref()), Some("caf\u{E9}"));
+        assert_eq!(utf8.symbol(oh).map(|s| s.as_ref()), Some("caf\u{FFFD}"));
+    }
+
+    #[test]
+    fn symbolize_inserted_after_delete() {
+        let s = Symbolizer::from_symbols([(0x10, "rx_q", 4), (0x20, "tx_q", 4)]);
+        let (rx, tx) = (
+            ObjectHandle::new(0x10).unwrap(),
+            ObjectHandle::new(0x20).unwrap(),
+        );
+        let mut t = EntryTable::default();
+        t.entry(rx);
+        t.symbolize(&s);
+        assert_eq!(t.entries()[&rx].symbolized.as_deref(), Some("rx_q"));
+
+        // Deleted then created, the number of entries doesn't change
+        let len = t.entries().len();
+        t.remove(rx);
+        t.entry(tx);
+        assert_eq!(t.entries().len(), len);
+        t.symbolize_inserted(&s);
+        assert_eq!(t.entries()[&tx].symbolized.as_deref(), Some("tx_q"));
+
+        // The handle is reused
+        t.remove(tx);
+        t.entry(rx);
+        t.symbolize_inserted(&s);
+        assert_eq!(t.entries()[&rx].symbolized.as_deref(), Some("rx_q"));
+    }
+
+    #[test]
+    fn search_symbols() {
+        let mut t = EntryTable::default();
+        for (addr, sym) in [(0x10, "UART_rx_q"), (0x20, "uart_tx_q"), (0x30, "IDLE")] {
+            t.entry(ObjectHandle::new(addr).unwrap())
+                .set_symbol(SymbolString(sym.into()));
+        }
+        let found = |pattern| {
+            t.search(pattern)
+                .map(|(h, _)| u32::from(h))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("uart"), vec![0x10, 0x20]);
+        assert_eq!(found("uart_?x_q"), vec![0x10, 0x20]);
+        assert_eq!(found("*TX*"), vec![0x20]);
+        assert_eq!(found("idle*"), vec![0x30]);
+        assert_eq!(found("*_q"), vec![0x10, 0x20]);
+        assert!(found("q_*").is_empty());
+    }
+
+    #[test]
+    fn table_diff() {
+        let mut a = EntryTable::default();
+        let task = ObjectHandle::new(0x10).unwrap();
+        let queue = ObjectHandle::new(0x20).unwrap();
+        let entry = a.entry(task);
+        entry.set_class(ObjectClass::Task);
+        entry.set_symbol(SymbolString("worker".into()));
+        entry.states.set_priority(Priority(2));
+        a.entry(queue).set_class(ObjectClass::Queue);
+
+        let mut b = a.clone();
+        b.entries.remove(&queue);
+        let entry = b.entry(task);
+        entry.set_symbol(SymbolString("worker2".into()));
+        entry.states.set_priority(Priority(3));
+        b.entry(ObjectHandle::new(0x30).unwrap());
+
+        let diffs: Vec<String> = a.diff(&b).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diffs,
+            vec![
+                "~ 16 name: worker -> worker2",
+                "~ 16 priority: 2 -> 3",
+                "- 32",
+                "+ 48",
+            ]
+        );
+        assert!(a.diff(&a).is_empty());
+        assert_eq!(
+            a.to_table_string().lines().next(),
+            Some("16     Task    worker")
+        );
+    }
+
+    #[test]
+    fn priorities() {
+        let mut t = EntryTable::default();
+        WellKnownObjects::default().classify(&mut t);
+        let isr = ObjectHandle::new(0x10).unwrap();
+        let entry = t.entry(isr);
+        entry.set_class(ObjectClass::Isr);
+        entry.states.set_priority(Priority(5));
+        let queue = ObjectHandle::new(0x20).unwrap();
+        t.entry(queue).set_class(ObjectClass::Queue);
+        assert_eq!(
+            t.priorities().collect::<Vec<_>>(),
+            vec![(ObjectHandle::NO_TASK, Priority(1)), (isr, Priority(5))]
+        );
+    }
+
+    #[test]
+    fn generation_changes_with_the_entries() {
+        let mut t = EntryTable::default();
+        let copy = t.clone();
+        assert_eq!(copy.generation(), t.generation());
+        assert_ne!(EntryTable::default().generation(), t.generation());
+
+        let handle = ObjectHandle::new(0x10).unwrap();
+        let mut generation = t.generation();
+        let mut changed = |t: &EntryTable| {
+            let changed = t.generation() != generation;
+            generation = t.generation();
+            changed
+        };
+        t.entry(handle);
+        assert!(changed(&t));
+        t.entry(handle);
+        assert!(!changed(&t));
+        t.set_class(handle, ObjectClass::Task);
+        assert!(changed(&t));
+        t.set_class(handle, ObjectClass::Task);
+        assert!(!changed(&t));
+        t.set_priority(handle, Priority(3));
+        assert!(changed(&t));
+        t.set_priority(handle, Priority(3));
+        assert!(!changed(&t));
+        t.set_symbol(handle, SymbolString("t".into()));
+        assert!(changed(&t));
+        t.set_symbol(handle, SymbolString("t".into()));
+        assert!(!changed(&t));
+        t.remove(handle);
+        assert!(changed(&t));
+        t.remove(handle);
+        assert!(!changed(&t));
+    }
+}
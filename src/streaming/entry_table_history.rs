@@ -0,0 +1,134 @@
+use crate::streaming::event::{EventCount, TrackingEventCounter};
+use crate::streaming::EntryTable;
+use std::num::NonZeroU64;
+use std::sync::Arc;
+
+/// When `EntryTableHistory` takes a snapshot of the entry table
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum SnapshotPolicy {
+    /// After every N events
+    EveryNEvents(NonZeroU64),
+    /// After each event that changed the table
+    OnChange,
+}
+
+/// Snapshots of the entry table taken while parsing, see
+/// `RecorderData::enable_entry_table_history`.
+///
+/// Snapshots are keyed by the event count (with rollovers) of the event after
+/// which they were taken. Unchanged tables aren't copied with the `OnChange` policy,
+/// changes are found from the table's generation rather than comparing it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EntryTableHistory {
+    policy: SnapshotPolicy,
+    counter: Option<TrackingEventCounter>,
+    events_since_snapshot: u64,
+    snapshots: Vec<(u64, Arc<EntryTable>)>,
+}
+
+impl EntryTableHistory {
+    pub fn new(policy: SnapshotPolicy) -> Self {
+        Self {
+            policy,
+            counter: None,
+            events_since_snapshot: 0,
+            snapshots: Vec::new(),
+        }
+    }
+
+    pub fn policy(&self) -> SnapshotPolicy {
+        self.policy
+    }
+
+    /// Record the state of the table after the event with the given count
+    pub fn record(&mut self, event_count: EventCount, table: &EntryTable) {
+        let count = match self.counter.as_mut() {
+            Some(counter) => {
+                counter.update(event_count);
+                counter.count()
+            }
+            None => {
+                let mut counter = TrackingEventCounter::zero();
+                counter.set_initial_count(event_count);
+                self.counter = Some(counter);
+                self.snapshots
+                    .push((counter.count(), Arc::new(table.clone())));
+                return;
+            }
+        };
+
+        self.events_since_snapshot += 1;
+        let take = match self.policy {
+            SnapshotPolicy::EveryNEvents(n) => self.events_since_snapshot >= n.get(),
+            SnapshotPolicy::OnChange => self
+                .snapshots
+                .last()
+                .map(|(_, last)| last.generation() != table.generation())
+                .unwrap_or(true),
+        };
+        if take {
+            self.events_since_snapshot = 0;
+            self.snapshots.push((count, Arc::new(table.clone())));
+        }
+    }
+
+    /// The most recent snapshot taken at or before the given event count
+    pub fn at(&self, event_count: u64) -> Option<&Arc<EntryTable>> {
+        let idx = self.snapshots.partition_point(|(c, _)| *c <= event_count);
+        idx.checked_sub(1).map(|i| &self.snapshots[i].1)
+    }
+
+    /// The snapshots with the event count they were taken at, oldest first
+    pub fn snapshots(&self) -> &[(u64, Arc<EntryTable>)] {
+        &self.snapshots
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{ObjectHandle, SymbolString};
+
+    #[test]
+    fn snapshots_on_change() {
+        let mut table = EntryTable::default();
+        let mut history = EntryTableHistory::new(SnapshotPolicy::OnChange);
+        history.record(EventCount(5), &table);
+        history.record(EventCount(6), &table);
+        table
+            .entry(ObjectHandle::new(0x10).unwrap())
+            .set_symbol(SymbolString("q".into()));
+        history.record(EventCount(7), &table);
+        history.record(EventCount(8), &table);
+
+        assert_eq!(
+            history
+                .snapshots()
+                .iter()
+                .map(|(c, _)| *c)
+                .collect::<Vec<_>>(),
+            vec![5, 7]
+        );
+        assert!(history.at(4).is_none());
+        assert!(history.at(6).unwrap().entries().is_empty());
+        assert_eq!(history.at(100).unwrap().entries().len(), 1);
+    }
+
+    #[test]
+    fn snapshots_every_n_events() {
+        let table = EntryTable::default();
+        let mut history =
+            EntryTableHistory::new(SnapshotPolicy::EveryNEvents(NonZeroU64::new(2).unwrap()));
+        for count in 1..=5 {
+            history.record(EventCount(count), &table);
+        }
+        assert_eq!(
+            history
+                .snapshots()
+                .iter()
+                .map(|(c, _)| *c)
+                .collect::<Vec<_>>(),
+            vec![1, 3, 5]
+        );
+    }
+}
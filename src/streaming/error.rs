@@ -1,6 +1,6 @@
 use crate::streaming::entry_table::{Entry, EntryStates};
 use crate::streaming::event::{EventId, EventParameterCount};
-use crate::types::{Endianness, FormattedStringError, ObjectHandle};
+use crate::types::{Endianness, FormattedStringError, ObjectClass, ObjectHandle};
 use std::io;
 use thiserror::Error;
 
@@ -36,12 +36,18 @@ pub enum Error {
     #[error("Found an event with object handle {0} that doesn't exist in the entry table")]
     ObjectLookup(ObjectHandle),
 
+    #[error("Event ID {0} operates on a {2} but object handle {1} is a {3}")]
+    ObjectClassMismatch(EventId, ObjectHandle, ObjectClass, ObjectClass),
+
     #[error("Found a fixed user event with format string handle {0} that doesn't exist in the entry table")]
     FixedUserEventFmtStringLookup(ObjectHandle),
 
     #[error("Found an event ({0}) with an invalid zero value object handle")]
     InvalidObjectHandle(EventId),
 
+    #[error("The input stream ended part way through an event, {0} bytes of it were read")]
+    TruncatedEvent(usize),
+
     #[error(transparent)]
     FormattedString(#[from] FormattedStringError),
 
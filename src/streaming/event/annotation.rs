@@ -0,0 +1,35 @@
+use crate::streaming::event::EventCount;
+use crate::time::Timestamp;
+use derive_more::Display;
+
+/// A host-side pseudo-event, i.e. to mark where a reset happened, see
+/// `EventIteratorExt::annotate`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{label}")]
+pub struct AnnotationEvent {
+    /// Event count and sequence of the event preceding the annotation
+    pub event_count: EventCount,
+    pub sequence: u64,
+    /// In the rollover-extended time of the events, see `StreamingInstant`
+    pub timestamp: Timestamp,
+
+    pub label: String,
+    pub note: Option<String>,
+}
+
+impl AnnotationEvent {
+    pub fn new<S: Into<String>>(timestamp: Timestamp, label: S) -> Self {
+        Self {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp,
+            label: label.into(),
+            note: None,
+        }
+    }
+
+    pub fn with_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+}
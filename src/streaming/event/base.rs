@@ -11,6 +11,7 @@ use derive_more::Display;
 pub struct BaseEvent {
     pub code: EventCode,
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
     pub(crate) parameters: [u32; EventParameterCount::MAX],
 }
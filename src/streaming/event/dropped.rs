@@ -0,0 +1,18 @@
+use crate::streaming::event::{DroppedEventCount, EventCount};
+use crate::time::Timestamp;
+use derive_more::Display;
+
+/// A pseudo-event marking events the recorder dropped, detected from a gap in
+/// the event counter, see `EventIteratorExt::mark_gaps`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{around_timestamp}]:{count}")]
+pub struct DroppedEvent {
+    /// Event count and sequence of the first dropped event
+    pub event_count: EventCount,
+    pub sequence: u64,
+    /// Timestamp of the event that followed the gap
+    pub around_timestamp: Timestamp,
+
+    /// Number of dropped events
+    pub count: DroppedEventCount,
+}
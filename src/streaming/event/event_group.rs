@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}:0x{event_bits}")]
 pub struct EventGroupCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -18,6 +19,7 @@ pub struct EventGroupCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}:0x{bits}")]
 pub struct EventGroupEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -7,11 +7,14 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:'{name}':{priority}")]
 pub struct IsrEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
     pub name: IsrName,
     pub priority: IsrPriority,
+    /// The symbol at the ISR handle address, if a symbolizer is provided
+    pub symbolized: Option<String>,
 }
 
 pub type IsrDefineEvent = IsrEvent;
@@ -1,17 +1,24 @@
 use crate::streaming::event::EventCount;
 use crate::time::Timestamp;
-use crate::types::Heap;
+use crate::types::{Heap, ObjectHandle, TaskName};
 use derive_more::Display;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 #[display(fmt = "[{timestamp}]:0x{address:X}:{size}")]
 pub struct MemoryEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub address: u32,
     pub size: u32,
     pub heap: Heap,
+    /// The symbol containing the address, if a symbolizer is provided
+    pub symbolized: Option<String>,
+    /// The task running when the event occurred, `None` in ISR context or
+    /// before the first context switch
+    pub task: Option<ObjectHandle>,
+    pub task_name: Option<TaskName>,
 }
 
 pub type MemoryAllocEvent = MemoryEvent;
@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}:{buffer_size}")]
 pub struct MessageBufferCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -18,6 +19,7 @@ pub struct MessageBufferCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}:{bytes_in_buffer}")]
 pub struct MessageBufferEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -29,6 +31,7 @@ pub struct MessageBufferEvent {
 #[display(fmt = "[{timestamp}]:{handle}")]
 pub struct MessageBufferBlockEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
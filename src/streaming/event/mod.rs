@@ -1,16 +1,21 @@
 use crate::time::Timestamp;
-use crate::types::UserEventArgRecordCount;
+use crate::types::{ObjectClass, ObjectHandle, SymbolString, UserEventArgRecordCount};
 use derive_more::{Binary, Deref, Display, From, Into, LowerHex, Octal, UpperHex};
 use enum_iterator::Sequence;
 
+pub use annotation::AnnotationEvent;
 pub use base::BaseEvent;
+pub use dropped::DroppedEvent;
+pub use object_delete::ObjectDeleteEvent;
 pub use object_name::ObjectNameEvent;
-pub use parser::EventParser;
+pub use parser::{EventParser, ObjectLookupPolicy};
+pub use session_boundary::SessionBoundaryEvent;
 
 pub use trace_start::TraceStartEvent;
 pub use ts_config::TsConfigEvent;
 pub use unused_stack::UnusedStackEvent;
 pub use user::UserEvent;
+pub use visitor::{EventHeader, EventVisitor};
 
 pub use event_group::*;
 pub use isr::*;
@@ -20,26 +25,35 @@ pub use mutex::*;
 pub use queue::*;
 pub use semaphore::*;
 pub use state_machine::*;
+pub use stream_buffer::*;
 pub use task::*;
 pub use task_notify::*;
+pub use timer::*;
 
+pub mod annotation;
 pub mod base;
+pub mod dropped;
 pub mod event_group;
 pub mod isr;
 pub mod memory;
 pub mod message_buffer;
 pub mod mutex;
+pub mod object_delete;
 pub mod object_name;
 pub mod parser;
 pub mod queue;
 pub mod semaphore;
+pub mod session_boundary;
 pub mod state_machine;
+pub mod stream_buffer;
 pub mod task;
 pub mod task_notify;
+pub mod timer;
 pub mod trace_start;
 pub mod ts_config;
 pub mod unused_stack;
 pub mod user;
+pub mod visitor;
 
 pub(crate) const FIXED_USER_EVENT_ID: u16 = 0x98;
 
@@ -94,7 +108,8 @@ impl From<EventParameterCount> for usize {
 }
 
 /// Event codes for streaming mode
-/// Note that the upper 4 bits are the parameter count
+/// Note that the upper 4 bits of the recorded 16-bit codes are the parameter count,
+/// codes past 16 bits are reserved for the pseudo-events
 #[derive(
     Copy,
     Clone,
@@ -112,11 +127,33 @@ impl From<EventParameterCount> for usize {
     UpperHex,
 )]
 #[display(fmt = "{_0:X}")]
-pub struct EventCode(u16);
+pub struct EventCode(pub(crate) u32);
 
 impl EventCode {
+    /// Code paired with `Event::Dropped` pseudo-events
+    pub const DROPPED: EventCode = EventCode::pseudo(EventId::DROPPED);
+
+    /// Code paired with `Event::Annotation` pseudo-events
+    pub const ANNOTATION: EventCode = EventCode::pseudo(EventId::ANNOTATION);
+
+    /// Code paired with `Event::SessionBoundary` pseudo-events
+    pub const SESSION_BOUNDARY: EventCode = EventCode::pseudo(EventId::SESSION_BOUNDARY);
+
+    const fn pseudo(id: EventId) -> Self {
+        EventCode(0x1_0000 | id.0 as u32)
+    }
+
+    /// Whether the code is paired with a pseudo-event, i.e. not recorded by the target
+    pub fn is_pseudo(&self) -> bool {
+        self.0 > u16::MAX.into()
+    }
+
     pub fn event_id(&self) -> EventId {
-        EventId(self.0 & 0x0F_FF)
+        if self.is_pseudo() {
+            EventId(self.0 as u16)
+        } else {
+            EventId((self.0 & 0x0F_FF) as u16)
+        }
     }
 
     pub fn event_type(&self) -> EventType {
@@ -125,7 +162,11 @@ impl EventCode {
 
     /// Return the number of 32-bit parameters for the event
     pub fn parameter_count(&self) -> EventParameterCount {
-        EventParameterCount(((self.0 >> 12) & 0x0F) as u8)
+        if self.is_pseudo() {
+            EventParameterCount(0)
+        } else {
+            EventParameterCount(((self.0 >> 12) & 0x0F) as u8)
+        }
     }
 }
 
@@ -151,6 +192,17 @@ impl EventCode {
 #[display(fmt = "{_0:X}")]
 pub struct EventId(pub u16);
 
+impl EventId {
+    /// ID of `EventType::Dropped`, outside of the 12-bit IDs of the recorded events
+    pub const DROPPED: EventId = EventId(0x1000);
+
+    /// ID of `EventType::Annotation`
+    pub const ANNOTATION: EventId = EventId(0x1001);
+
+    /// ID of `EventType::SessionBoundary`
+    pub const SESSION_BOUNDARY: EventId = EventId(0x1002);
+}
+
 /// Event types for streaming mode
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display, Sequence)]
 pub enum EventType {
@@ -188,6 +240,14 @@ pub enum EventType {
     TaskSwitchTaskResume,
     #[display(fmt = "TASK_ACTIVATE")]
     TaskActivate,
+    /// Not in any verified recorder version, the ID and layout
+    /// (handle, affinity mask) are provisional
+    #[display(fmt = "TASK_CORE_AFFINITY_SET")]
+    TaskCoreAffinitySet,
+    /// Not in any verified recorder version, the ID and layout
+    /// (handle, from core, to core) are provisional
+    #[display(fmt = "TASK_MIGRATE")]
+    TaskMigrate,
     #[display(fmt = "TASK_DELAY_UNTIL")]
     TaskDelayUntil,
     #[display(fmt = "TASK_DELAY")]
@@ -345,6 +405,33 @@ pub enum EventType {
     #[display(fmt = "EVENTGROUP_WAITBITS_FAILED")]
     EventGroupWaitBitsFailed,
 
+    #[display(fmt = "STREAMBUFFER_CREATE")]
+    StreamBufferCreate,
+    #[display(fmt = "STREAMBUFFER_CREATE_FAILED")]
+    StreamBufferCreateFailed,
+    #[display(fmt = "STREAMBUFFER_SEND")]
+    StreamBufferSend,
+    #[display(fmt = "STREAMBUFFER_SEND_BLOCK")]
+    StreamBufferSendBlock,
+    #[display(fmt = "STREAMBUFFER_SEND_FAILED")]
+    StreamBufferSendFailed,
+    #[display(fmt = "STREAMBUFFER_RECEIVE")]
+    StreamBufferReceive,
+    #[display(fmt = "STREAMBUFFER_RECEIVE_BLOCK")]
+    StreamBufferReceiveBlock,
+    #[display(fmt = "STREAMBUFFER_RECEIVE_FAILED")]
+    StreamBufferReceiveFailed,
+    #[display(fmt = "STREAMBUFFER_SEND_FROM_ISR")]
+    StreamBufferSendFromIsr,
+    #[display(fmt = "STREAMBUFFER_SEND_FROM_ISR_FAILED")]
+    StreamBufferSendFromIsrFailed,
+    #[display(fmt = "STREAMBUFFER_RECEIVE_FROM_ISR")]
+    StreamBufferReceiveFromIsr,
+    #[display(fmt = "STREAMBUFFER_RECEIVE_FROM_ISR_FAILED")]
+    StreamBufferReceiveFromIsrFailed,
+    #[display(fmt = "STREAMBUFFER_RESET")]
+    StreamBufferReset,
+
     #[display(fmt = "MESSAGEBUFFER_CREATE")]
     MessageBufferCreate,
     #[display(fmt = "MESSAGEBUFFER_CREATE_FAILED")]
@@ -372,6 +459,23 @@ pub enum EventType {
     #[display(fmt = "MESSAGEBUFFER_RESET")]
     MessageBufferReset,
 
+    #[display(fmt = "TASK_DELETE")]
+    TaskDelete,
+    #[display(fmt = "QUEUE_DELETE")]
+    QueueDelete,
+    #[display(fmt = "SEMAPHORE_DELETE")]
+    SemaphoreDelete,
+    #[display(fmt = "MUTEX_DELETE")]
+    MutexDelete,
+    #[display(fmt = "TIMER_DELETE")]
+    TimerDelete,
+    #[display(fmt = "EVENTGROUP_DELETE")]
+    EventGroupDelete,
+    #[display(fmt = "STREAMBUFFER_DELETE")]
+    StreamBufferDelete,
+    #[display(fmt = "MESSAGEBUFFER_DELETE")]
+    MessageBufferDelete,
+
     #[display(fmt = "STATEMACHINE_STATE_CREATE")]
     StateMachineStateCreate,
     #[display(fmt = "STATEMACHINE_CREATE")]
@@ -391,6 +495,18 @@ pub enum EventType {
     #[display(fmt = "UNUSED_STACK")]
     UnusedStack,
 
+    /// Not recorded, see `EventCode::DROPPED`
+    #[display(fmt = "DROPPED")]
+    Dropped,
+
+    /// Not recorded, see `EventCode::ANNOTATION`
+    #[display(fmt = "ANNOTATION")]
+    Annotation,
+
+    /// Not recorded, see `EventCode::SESSION_BOUNDARY`
+    #[display(fmt = "SESSION_BOUNDARY")]
+    SessionBoundary,
+
     // Variant to handle unknown/unsupported event ID
     #[display(fmt = "UNKNOWN({_0})")]
     Unknown(EventId),
@@ -418,6 +534,8 @@ impl From<EventId> for EventType {
             0x35 => TaskSwitchTaskBegin,
             0x36 => TaskSwitchTaskResume,
             0x37 => TaskActivate,
+            0xE9 => TaskCoreAffinitySet,
+            0xEA => TaskMigrate,
             0x79 => TaskDelayUntil,
             0x7A => TaskDelay,
             0x7B => TaskSuspend,
@@ -500,6 +618,20 @@ impl From<EventId> for EventType {
             0xB8 => EventGroupSyncFailed,
             0xB9 => EventGroupWaitBitsFailed,
 
+            0x18 => StreamBufferCreate,
+            0x49 => StreamBufferCreateFailed,
+            0xD3 => StreamBufferSend,
+            0xD4 => StreamBufferSendBlock,
+            0xD5 => StreamBufferSendFailed,
+            0xD6 => StreamBufferReceive,
+            0xD7 => StreamBufferReceiveBlock,
+            0xD8 => StreamBufferReceiveFailed,
+            0xD9 => StreamBufferSendFromIsr,
+            0xDA => StreamBufferSendFromIsrFailed,
+            0xDB => StreamBufferReceiveFromIsr,
+            0xDC => StreamBufferReceiveFromIsrFailed,
+            0xDD => StreamBufferReset,
+
             0x19 => MessageBufferCreate,
             0x4A => MessageBufferCreateFailed,
             0xDE => MessageBufferSend,
@@ -514,6 +646,15 @@ impl From<EventId> for EventType {
             0xE7 => MessageBufferReceiveFromIsrFailed,
             0xE8 => MessageBufferReset,
 
+            0x20 => TaskDelete,
+            0x21 => QueueDelete,
+            0x22 => SemaphoreDelete,
+            0x23 => MutexDelete,
+            0x24 => TimerDelete,
+            0x25 => EventGroupDelete,
+            0x28 => StreamBufferDelete,
+            0x29 => MessageBufferDelete,
+
             0xEC => StateMachineStateCreate,
             0xED => StateMachineCreate,
             0xEE => StateMachineStateChange,
@@ -522,6 +663,10 @@ impl From<EventId> for EventType {
 
             0xEB => UnusedStack,
 
+            0x1000 => Dropped,
+            0x1001 => Annotation,
+            0x1002 => SessionBoundary,
+
             _ => Unknown(id),
         }
     }
@@ -549,6 +694,8 @@ impl From<EventType> for EventId {
             TaskSwitchTaskBegin => 0x35,
             TaskSwitchTaskResume => 0x36,
             TaskActivate => 0x37,
+            TaskCoreAffinitySet => 0xE9,
+            TaskMigrate => 0xEA,
             TaskDelayUntil => 0x79,
             TaskDelay => 0x7A,
             TaskSuspend => 0x7B,
@@ -631,6 +778,20 @@ impl From<EventType> for EventId {
             EventGroupSyncFailed => 0xB8,
             EventGroupWaitBitsFailed => 0xB9,
 
+            StreamBufferCreate => 0x18,
+            StreamBufferCreateFailed => 0x49,
+            StreamBufferSend => 0xD3,
+            StreamBufferSendBlock => 0xD4,
+            StreamBufferSendFailed => 0xD5,
+            StreamBufferReceive => 0xD6,
+            StreamBufferReceiveBlock => 0xD7,
+            StreamBufferReceiveFailed => 0xD8,
+            StreamBufferSendFromIsr => 0xD9,
+            StreamBufferSendFromIsrFailed => 0xDA,
+            StreamBufferReceiveFromIsr => 0xDB,
+            StreamBufferReceiveFromIsrFailed => 0xDC,
+            StreamBufferReset => 0xDD,
+
             MessageBufferCreate => 0x19,
             MessageBufferCreateFailed => 0x4A,
             MessageBufferSend => 0xDE,
@@ -645,6 +806,15 @@ impl From<EventType> for EventId {
             MessageBufferReceiveFromIsrFailed => 0xE7,
             MessageBufferReset => 0xE8,
 
+            TaskDelete => 0x20,
+            QueueDelete => 0x21,
+            SemaphoreDelete => 0x22,
+            MutexDelete => 0x23,
+            TimerDelete => 0x24,
+            EventGroupDelete => 0x25,
+            StreamBufferDelete => 0x28,
+            MessageBufferDelete => 0x29,
+
             StateMachineStateCreate => 0xEC,
             StateMachineCreate => 0xED,
             StateMachineStateChange => 0xEE,
@@ -653,6 +823,10 @@ impl From<EventType> for EventId {
 
             UnusedStack => 0xEB,
 
+            Dropped => EventId::DROPPED.0,
+            Annotation => EventId::ANNOTATION.0,
+            SessionBoundary => EventId::SESSION_BOUNDARY.0,
+
             Unknown(raw) => raw.0,
         };
         EventId(id)
@@ -660,6 +834,67 @@ impl From<EventType> for EventId {
 }
 
 impl EventType {
+    /// The class of object that the event type operates on, if any.
+    /// Object create and delete events are not included.
+    pub fn object_class(&self) -> Option<ObjectClass> {
+        use EventType::*;
+        Some(match self {
+            QueueSend
+            | QueueSendBlock
+            | QueueSendFromIsr
+            | QueueReceive
+            | QueueReceiveBlock
+            | QueueReceiveFromIsr
+            | QueuePeek
+            | QueuePeekBlock
+            | QueueSendFront
+            | QueueSendFrontBlock
+            | QueueSendFrontFromIsr => ObjectClass::Queue,
+
+            MutexGive
+            | MutexGiveBlock
+            | MutexGiveRecursive
+            | MutexTake
+            | MutexTakeBlock
+            | MutexTakeRecursive
+            | MutexTakeRecursiveBlock => ObjectClass::Mutex,
+
+            SemaphoreGive | SemaphoreGiveBlock | SemaphoreGiveFromIsr | SemaphoreTake
+            | SemaphoreTakeBlock | SemaphoreTakeFromIsr | SemaphorePeek | SemaphorePeekBlock => {
+                ObjectClass::Semaphore
+            }
+
+            TimerStart | TimerReset | TimerStop => ObjectClass::Timer,
+
+            EventGroupSync
+            | EventGroupWaitBits
+            | EventGroupClearBits
+            | EventGroupClearBitsFromIsr
+            | EventGroupSetBits
+            | EventGroupSetBitsFromIsr
+            | EventGroupSyncBlock
+            | EventGroupWaitBitsBlock => ObjectClass::EventGroup,
+
+            StreamBufferSend
+            | StreamBufferSendBlock
+            | StreamBufferReceive
+            | StreamBufferReceiveBlock
+            | StreamBufferSendFromIsr
+            | StreamBufferReceiveFromIsr
+            | StreamBufferReset => ObjectClass::StreamBuffer,
+
+            MessageBufferSend
+            | MessageBufferSendBlock
+            | MessageBufferReceive
+            | MessageBufferReceiveBlock
+            | MessageBufferSendFromIsr
+            | MessageBufferReceiveFromIsr
+            | MessageBufferReset => ObjectClass::MessageBuffer,
+
+            _ => return None,
+        })
+    }
+
     /// Return the number of expected parameters for the event type, otherwise
     /// return None for event types with variable parameters.
     pub(crate) fn expected_parameter_count(&self) -> Option<usize> {
@@ -674,6 +909,10 @@ impl EventType {
                 return None
             }
 
+            // The object state parameter is optional
+            TaskDelete | QueueDelete | SemaphoreDelete | MutexDelete | TimerDelete
+            | EventGroupDelete | StreamBufferDelete | MessageBufferDelete => return None,
+
             TaskCreate
             | QueueCreate
             | MutexCreate
@@ -683,28 +922,39 @@ impl EventType {
             TaskReady | TaskSwitchIsrBegin | TaskSwitchIsrResume | TaskSwitchTaskBegin
             | TaskSwitchTaskResume => 1,
 
-            TaskNotify | TaskNotifyFromIsr => 1,
-            TaskNotifyWait | TaskNotifyWaitBlock => 2,
+            TaskCoreAffinitySet => 2,
+            TaskMigrate => 3,
+
+            // Notification index and value parameters are optional
+            TaskNotify | TaskNotifyFromIsr | TaskNotifyWait | TaskNotifyWaitBlock => return None,
 
             MemoryAlloc | MemoryFree => 2,
 
+            // Blocking calls may also carry ticks-to-wait depending on the kernel port and trace version
             QueueSend
             | QueueSendBlock
-            | QueueSendFromIsr
-            | QueueReceiveFromIsr
             | QueueSendFront
             | QueueSendFrontBlock
-            | QueueSendFrontFromIsr => 2,
+            | MutexGive
+            | MutexGiveBlock
+            | MutexGiveRecursive
+            | SemaphoreGive
+            | SemaphoreGiveBlock => return None,
+
+            QueueSendFromIsr
+            | QueueReceiveFromIsr
+            | QueueSendFrontFromIsr
+            | SemaphoreGiveFromIsr
+            | SemaphoreTakeFromIsr => 2,
 
             QueueReceive | QueueReceiveBlock | QueuePeek | QueuePeekBlock => 3,
 
-            MutexGive | MutexGiveBlock | MutexGiveRecursive => 1,
             MutexTake | MutexTakeBlock | MutexTakeRecursive | MutexTakeRecursiveBlock => 2,
 
-            SemaphoreGive | SemaphoreGiveBlock | SemaphoreGiveFromIsr | SemaphoreTakeFromIsr => 2,
-
             SemaphoreTake | SemaphoreTakeBlock | SemaphorePeek | SemaphorePeekBlock => 3,
 
+            TimerCreate | TimerStart | TimerReset | TimerStop | TimerExpired => 2,
+
             UnusedStack => 2,
 
             EventGroupCreate |
@@ -717,6 +967,16 @@ impl EventType {
             EventGroupSyncBlock |
             EventGroupWaitBitsBlock => 2,
 
+            StreamBufferCreate |
+            StreamBufferSend |
+            StreamBufferReceive |
+            StreamBufferSendFromIsr |
+            StreamBufferReceiveFromIsr |
+            StreamBufferReset => 2,
+
+            StreamBufferSendBlock |
+            StreamBufferReceiveBlock => 1,
+
             MessageBufferCreate |
             MessageBufferSend |
             MessageBufferReceive |
@@ -774,6 +1034,10 @@ pub enum Event {
     TaskResume(TaskResumeEvent),
     #[display(fmt = "TaskActivate({_0})")]
     TaskActivate(TaskActivateEvent),
+    #[display(fmt = "TaskCoreAffinitySet({_0})")]
+    TaskCoreAffinitySet(TaskCoreAffinityEvent),
+    #[display(fmt = "TaskMigrate({_0})")]
+    TaskMigrate(TaskMigrateEvent),
 
     #[display(fmt = "TaskNotify({_0})")]
     TaskNotify(TaskNotifyEvent),
@@ -863,6 +1127,37 @@ pub enum Event {
     #[display(fmt = "EventGroupWaitBitsBlock({_0})")]
     EventGroupWaitBitsBlock(EventGroupWaitBitsBlockEvent),
 
+    #[display(fmt = "TimerCreate({_0})")]
+    TimerCreate(TimerCreateEvent),
+    #[display(fmt = "TimerStart({_0})")]
+    TimerStart(TimerStartEvent),
+    #[display(fmt = "TimerReset({_0})")]
+    TimerReset(TimerResetEvent),
+    #[display(fmt = "TimerStop({_0})")]
+    TimerStop(TimerStopEvent),
+    #[display(fmt = "TimerExpired({_0})")]
+    TimerExpired(TimerExpiredEvent),
+
+    #[display(fmt = "ObjectDelete({_0})")]
+    ObjectDelete(ObjectDeleteEvent),
+
+    #[display(fmt = "StreamBufferCreate({_0})")]
+    StreamBufferCreate(StreamBufferCreateEvent),
+    #[display(fmt = "StreamBufferSend({_0})")]
+    StreamBufferSend(StreamBufferSendEvent),
+    #[display(fmt = "StreamBufferReceive({_0})")]
+    StreamBufferReceive(StreamBufferReceiveEvent),
+    #[display(fmt = "StreamBufferSendFromIsr({_0})")]
+    StreamBufferSendFromIsr(StreamBufferSendFromIsrEvent),
+    #[display(fmt = "StreamBufferReceiveFromIsr({_0})")]
+    StreamBufferReceiveFromIsr(StreamBufferReceiveFromIsrEvent),
+    #[display(fmt = "StreamBufferReset({_0})")]
+    StreamBufferReset(StreamBufferResetEvent),
+    #[display(fmt = "StreamBufferSendBlock({_0})")]
+    StreamBufferSendBlock(StreamBufferSendBlockEvent),
+    #[display(fmt = "StreamBufferReceiveBlock({_0})")]
+    StreamBufferReceiveBlock(StreamBufferReceiveBlockEvent),
+
     #[display(fmt = "MessageBufferCreate({_0})")]
     MessageBufferCreate(MessageBufferCreateEvent),
     #[display(fmt = "MessageBufferSend({_0})")]
@@ -895,9 +1190,299 @@ pub enum Event {
 
     #[display(fmt = "BaseEvent({_0})")]
     Unknown(BaseEvent),
+
+    /// Not recorded, see `DroppedEvent`
+    #[display(fmt = "Dropped({_0})")]
+    Dropped(DroppedEvent),
+
+    /// Not recorded, see `AnnotationEvent`
+    #[display(fmt = "Annotation({_0})")]
+    Annotation(AnnotationEvent),
+
+    /// Not recorded, see `SessionBoundaryEvent`
+    #[display(fmt = "SessionBoundary({_0})")]
+    SessionBoundary(SessionBoundaryEvent),
 }
 
 impl Event {
+    /// The handle of the object (task, ISR, queue, etc) the event refers to, if any
+    pub fn object_handle(&self) -> Option<ObjectHandle> {
+        use Event::*;
+        Some(match self {
+            TraceStart(e) => e.current_task_handle,
+            ObjectName(e) => e.handle,
+            TaskPriority(e) => e.handle,
+            TaskPriorityInherit(e) => e.handle,
+            TaskPriorityDisinherit(e) => e.handle,
+            IsrDefine(e) => e.handle,
+            TaskCreate(e) => e.handle,
+            QueueCreate(e) => e.handle,
+            MutexCreate(e) => e.handle,
+            SemaphoreBinaryCreate(e) => e.handle,
+            SemaphoreCountingCreate(e) => e.handle,
+            TaskReady(e) => e.handle,
+            IsrBegin(e) => e.handle,
+            IsrResume(e) => e.handle,
+            TaskBegin(e) => e.handle,
+            TaskResume(e) => e.handle,
+            TaskActivate(e) => e.handle,
+            TaskCoreAffinitySet(e) => e.handle,
+            TaskMigrate(e) => e.handle,
+            TaskNotify(e) => e.handle,
+            TaskNotifyFromIsr(e) => e.handle,
+            TaskNotifyWait(e) => e.handle,
+            TaskNotifyWaitBlock(e) => e.handle,
+            QueueSend(e) => e.handle,
+            QueueSendBlock(e) => e.handle,
+            QueueSendFromIsr(e) => e.handle,
+            QueueReceive(e) => e.handle,
+            QueueReceiveBlock(e) => e.handle,
+            QueueReceiveFromIsr(e) => e.handle,
+            QueuePeek(e) => e.handle,
+            QueuePeekBlock(e) => e.handle,
+            QueueSendFront(e) => e.handle,
+            QueueSendFrontBlock(e) => e.handle,
+            QueueSendFrontFromIsr(e) => e.handle,
+            MutexGive(e) => e.handle,
+            MutexGiveBlock(e) => e.handle,
+            MutexGiveRecursive(e) => e.handle,
+            MutexTake(e) => e.handle,
+            MutexTakeBlock(e) => e.handle,
+            MutexTakeRecursive(e) => e.handle,
+            MutexTakeRecursiveBlock(e) => e.handle,
+            SemaphoreGive(e) => e.handle,
+            SemaphoreGiveBlock(e) => e.handle,
+            SemaphoreGiveFromIsr(e) => e.handle,
+            SemaphoreTake(e) => e.handle,
+            SemaphoreTakeBlock(e) => e.handle,
+            SemaphoreTakeFromIsr(e) => e.handle,
+            SemaphorePeek(e) => e.handle,
+            SemaphorePeekBlock(e) => e.handle,
+            EventGroupCreate(e) => e.handle,
+            EventGroupSync(e) => e.handle,
+            EventGroupWaitBits(e) => e.handle,
+            EventGroupClearBits(e) => e.handle,
+            EventGroupClearBitsFromIsr(e) => e.handle,
+            EventGroupSetBits(e) => e.handle,
+            EventGroupSetBitsFromIsr(e) => e.handle,
+            EventGroupSyncBlock(e) => e.handle,
+            EventGroupWaitBitsBlock(e) => e.handle,
+            TimerCreate(e) => e.handle,
+            TimerStart(e) => e.handle,
+            TimerReset(e) => e.handle,
+            TimerStop(e) => e.handle,
+            ObjectDelete(e) => e.handle,
+            StreamBufferCreate(e) => e.handle,
+            StreamBufferSend(e) => e.handle,
+            StreamBufferReceive(e) => e.handle,
+            StreamBufferSendFromIsr(e) => e.handle,
+            StreamBufferReceiveFromIsr(e) => e.handle,
+            StreamBufferReset(e) => e.handle,
+            StreamBufferSendBlock(e) => e.handle,
+            StreamBufferReceiveBlock(e) => e.handle,
+            MessageBufferCreate(e) => e.handle,
+            MessageBufferSend(e) => e.handle,
+            MessageBufferReceive(e) => e.handle,
+            MessageBufferSendFromIsr(e) => e.handle,
+            MessageBufferReceiveFromIsr(e) => e.handle,
+            MessageBufferReset(e) => e.handle,
+            MessageBufferSendBlock(e) => e.handle,
+            MessageBufferReceiveBlock(e) => e.handle,
+            StateMachineCreate(e) => e.handle,
+            StateMachineStateCreate(e) => e.handle,
+            StateMachineStateChange(e) => e.handle,
+            UnusedStack(e) => e.handle,
+            TsConfig(_) | MemoryAlloc(_) | MemoryFree(_) | TimerExpired(_) | User(_)
+            | Unknown(_) | Dropped(_) | Annotation(_) | SessionBoundary(_) => return None,
+        })
+    }
+
+    /// The name of the object (task, ISR, queue, etc) the event refers to, if known
+    pub fn object_name(&self) -> Option<&str> {
+        use Event::*;
+        match self {
+            TraceStart(e) => Some(e.current_task.as_ref()),
+            ObjectName(e) => Some(e.name.as_ref()),
+            TaskPriority(e) => Some(e.name.as_ref()),
+            TaskPriorityInherit(e) => Some(e.name.as_ref()),
+            TaskPriorityDisinherit(e) => Some(e.name.as_ref()),
+            IsrDefine(e) => Some(e.name.as_ref()),
+            TaskCreate(e) => Some(e.name.as_ref()),
+            QueueCreate(e) => e.name.as_deref(),
+            MutexCreate(e) => e.name.as_deref(),
+            SemaphoreBinaryCreate(e) => e.name.as_deref(),
+            SemaphoreCountingCreate(e) => e.name.as_deref(),
+            TaskReady(e) => Some(e.name.as_ref()),
+            IsrBegin(e) => Some(e.name.as_ref()),
+            IsrResume(e) => Some(e.name.as_ref()),
+            TaskBegin(e) => Some(e.name.as_ref()),
+            TaskResume(e) => Some(e.name.as_ref()),
+            TaskActivate(e) => Some(e.name.as_ref()),
+            TaskCoreAffinitySet(e) => Some(e.name.as_ref()),
+            TaskMigrate(e) => Some(e.name.as_ref()),
+            TaskNotify(e) => e.task_name.as_deref(),
+            TaskNotifyFromIsr(e) => e.task_name.as_deref(),
+            TaskNotifyWait(e) => e.task_name.as_deref(),
+            TaskNotifyWaitBlock(e) => e.task_name.as_deref(),
+            QueueSend(e) => e.name.as_deref(),
+            QueueSendBlock(e) => e.name.as_deref(),
+            QueueSendFromIsr(e) => e.name.as_deref(),
+            QueueReceive(e) => e.name.as_deref(),
+            QueueReceiveBlock(e) => e.name.as_deref(),
+            QueueReceiveFromIsr(e) => e.name.as_deref(),
+            QueuePeek(e) => e.name.as_deref(),
+            QueuePeekBlock(e) => e.name.as_deref(),
+            QueueSendFront(e) => e.name.as_deref(),
+            QueueSendFrontBlock(e) => e.name.as_deref(),
+            QueueSendFrontFromIsr(e) => e.name.as_deref(),
+            MutexGive(e) => e.name.as_deref(),
+            MutexGiveBlock(e) => e.name.as_deref(),
+            MutexGiveRecursive(e) => e.name.as_deref(),
+            MutexTake(e) => e.name.as_deref(),
+            MutexTakeBlock(e) => e.name.as_deref(),
+            MutexTakeRecursive(e) => e.name.as_deref(),
+            MutexTakeRecursiveBlock(e) => e.name.as_deref(),
+            SemaphoreGive(e) => e.name.as_deref(),
+            SemaphoreGiveBlock(e) => e.name.as_deref(),
+            SemaphoreGiveFromIsr(e) => e.name.as_deref(),
+            SemaphoreTake(e) => e.name.as_deref(),
+            SemaphoreTakeBlock(e) => e.name.as_deref(),
+            SemaphoreTakeFromIsr(e) => e.name.as_deref(),
+            SemaphorePeek(e) => e.name.as_deref(),
+            SemaphorePeekBlock(e) => e.name.as_deref(),
+            EventGroupCreate(e) => e.name.as_deref(),
+            EventGroupSync(e) => e.name.as_deref(),
+            EventGroupWaitBits(e) => e.name.as_deref(),
+            EventGroupClearBits(e) => e.name.as_deref(),
+            EventGroupClearBitsFromIsr(e) => e.name.as_deref(),
+            EventGroupSetBits(e) => e.name.as_deref(),
+            EventGroupSetBitsFromIsr(e) => e.name.as_deref(),
+            EventGroupSyncBlock(e) => e.name.as_deref(),
+            EventGroupWaitBitsBlock(e) => e.name.as_deref(),
+            TimerCreate(e) => e.name.as_deref(),
+            TimerStart(e) => e.name.as_deref(),
+            TimerReset(e) => e.name.as_deref(),
+            TimerStop(e) => e.name.as_deref(),
+            ObjectDelete(e) => e.name.as_deref(),
+            StreamBufferCreate(e) => e.name.as_deref(),
+            StreamBufferSend(e) => e.name.as_deref(),
+            StreamBufferReceive(e) => e.name.as_deref(),
+            StreamBufferSendFromIsr(e) => e.name.as_deref(),
+            StreamBufferReceiveFromIsr(e) => e.name.as_deref(),
+            StreamBufferReset(e) => e.name.as_deref(),
+            StreamBufferSendBlock(e) => e.name.as_deref(),
+            StreamBufferReceiveBlock(e) => e.name.as_deref(),
+            MessageBufferCreate(e) => e.name.as_deref(),
+            MessageBufferSend(e) => e.name.as_deref(),
+            MessageBufferReceive(e) => e.name.as_deref(),
+            MessageBufferSendFromIsr(e) => e.name.as_deref(),
+            MessageBufferReceiveFromIsr(e) => e.name.as_deref(),
+            MessageBufferReset(e) => e.name.as_deref(),
+            MessageBufferSendBlock(e) => e.name.as_deref(),
+            MessageBufferReceiveBlock(e) => e.name.as_deref(),
+            StateMachineCreate(e) => Some(e.name.as_ref()),
+            StateMachineStateCreate(e) => Some(e.name.as_ref()),
+            StateMachineStateChange(e) => Some(e.name.as_ref()),
+            UnusedStack(e) => Some(e.task.as_ref()),
+            TsConfig(_) | MemoryAlloc(_) | MemoryFree(_) | TimerExpired(_) | User(_)
+            | Unknown(_) | Dropped(_) | Annotation(_) | SessionBoundary(_) => None,
+        }
+    }
+
+    /// Set the name of the object the event refers to, see `object_name`.
+    /// Returns false for events that don't refer to a named object.
+    pub fn set_object_name(&mut self, name: crate::types::ObjectName) -> bool {
+        use Event::*;
+        match self {
+            TraceStart(e) => e.current_task = name,
+            ObjectName(e) => e.name = SymbolString(name.0),
+            TaskPriority(e) => e.name = name,
+            TaskPriorityInherit(e) => e.name = name,
+            TaskPriorityDisinherit(e) => e.name = name,
+            IsrDefine(e) => e.name = name,
+            TaskCreate(e) => e.name = name,
+            QueueCreate(e) => e.name = Some(name),
+            MutexCreate(e) => e.name = Some(name),
+            SemaphoreBinaryCreate(e) => e.name = Some(name),
+            SemaphoreCountingCreate(e) => e.name = Some(name),
+            TaskReady(e) => e.name = name,
+            IsrBegin(e) => e.name = name,
+            IsrResume(e) => e.name = name,
+            TaskBegin(e) => e.name = name,
+            TaskResume(e) => e.name = name,
+            TaskActivate(e) => e.name = name,
+            TaskCoreAffinitySet(e) => e.name = name,
+            TaskMigrate(e) => e.name = name,
+            TaskNotify(e) => e.task_name = Some(name),
+            TaskNotifyFromIsr(e) => e.task_name = Some(name),
+            TaskNotifyWait(e) => e.task_name = Some(name),
+            TaskNotifyWaitBlock(e) => e.task_name = Some(name),
+            QueueSend(e) => e.name = Some(name),
+            QueueSendBlock(e) => e.name = Some(name),
+            QueueSendFromIsr(e) => e.name = Some(name),
+            QueueReceive(e) => e.name = Some(name),
+            QueueReceiveBlock(e) => e.name = Some(name),
+            QueueReceiveFromIsr(e) => e.name = Some(name),
+            QueuePeek(e) => e.name = Some(name),
+            QueuePeekBlock(e) => e.name = Some(name),
+            QueueSendFront(e) => e.name = Some(name),
+            QueueSendFrontBlock(e) => e.name = Some(name),
+            QueueSendFrontFromIsr(e) => e.name = Some(name),
+            MutexGive(e) => e.name = Some(name),
+            MutexGiveBlock(e) => e.name = Some(name),
+            MutexGiveRecursive(e) => e.name = Some(name),
+            MutexTake(e) => e.name = Some(name),
+            MutexTakeBlock(e) => e.name = Some(name),
+            MutexTakeRecursive(e) => e.name = Some(name),
+            MutexTakeRecursiveBlock(e) => e.name = Some(name),
+            SemaphoreGive(e) => e.name = Some(name),
+            SemaphoreGiveBlock(e) => e.name = Some(name),
+            SemaphoreGiveFromIsr(e) => e.name = Some(name),
+            SemaphoreTake(e) => e.name = Some(name),
+            SemaphoreTakeBlock(e) => e.name = Some(name),
+            SemaphoreTakeFromIsr(e) => e.name = Some(name),
+            SemaphorePeek(e) => e.name = Some(name),
+            SemaphorePeekBlock(e) => e.name = Some(name),
+            EventGroupCreate(e) => e.name = Some(name),
+            EventGroupSync(e) => e.name = Some(name),
+            EventGroupWaitBits(e) => e.name = Some(name),
+            EventGroupClearBits(e) => e.name = Some(name),
+            EventGroupClearBitsFromIsr(e) => e.name = Some(name),
+            EventGroupSetBits(e) => e.name = Some(name),
+            EventGroupSetBitsFromIsr(e) => e.name = Some(name),
+            EventGroupSyncBlock(e) => e.name = Some(name),
+            EventGroupWaitBitsBlock(e) => e.name = Some(name),
+            TimerCreate(e) => e.name = Some(name),
+            TimerStart(e) => e.name = Some(name),
+            TimerReset(e) => e.name = Some(name),
+            TimerStop(e) => e.name = Some(name),
+            ObjectDelete(e) => e.name = Some(name),
+            StreamBufferCreate(e) => e.name = Some(name),
+            StreamBufferSend(e) => e.name = Some(name),
+            StreamBufferReceive(e) => e.name = Some(name),
+            StreamBufferSendFromIsr(e) => e.name = Some(name),
+            StreamBufferReceiveFromIsr(e) => e.name = Some(name),
+            StreamBufferReset(e) => e.name = Some(name),
+            StreamBufferSendBlock(e) => e.name = Some(name),
+            StreamBufferReceiveBlock(e) => e.name = Some(name),
+            MessageBufferCreate(e) => e.name = Some(name),
+            MessageBufferSend(e) => e.name = Some(name),
+            MessageBufferReceive(e) => e.name = Some(name),
+            MessageBufferSendFromIsr(e) => e.name = Some(name),
+            MessageBufferReceiveFromIsr(e) => e.name = Some(name),
+            MessageBufferReset(e) => e.name = Some(name),
+            MessageBufferSendBlock(e) => e.name = Some(name),
+            MessageBufferReceiveBlock(e) => e.name = Some(name),
+            StateMachineCreate(e) => e.name = name,
+            StateMachineStateCreate(e) => e.name = name,
+            StateMachineStateChange(e) => e.name = name,
+            UnusedStack(e) => e.task = name,
+            TsConfig(_) | MemoryAlloc(_) | MemoryFree(_) | TimerExpired(_) | User(_)
+            | Unknown(_) | Dropped(_) | Annotation(_) | SessionBoundary(_) => return false,
+        }
+        true
+    }
+
     /// Get the event count (sequence number).
     /// NOTE:
     /// * V10: TraceStart reports 1 (doesn't track the internal header/timestamp-info/etc)
@@ -923,6 +1508,8 @@ impl Event {
             TaskBegin(e) => e.event_count,
             TaskResume(e) => e.event_count,
             TaskActivate(e) => e.event_count,
+            TaskCoreAffinitySet(e) => e.event_count,
+            TaskMigrate(e) => e.event_count,
             TaskNotify(e) => e.event_count,
             TaskNotifyFromIsr(e) => e.event_count,
             TaskNotifyWait(e) => e.event_count,
@@ -964,6 +1551,20 @@ impl Event {
             EventGroupSetBitsFromIsr(e) => e.event_count,
             EventGroupSyncBlock(e) => e.event_count,
             EventGroupWaitBitsBlock(e) => e.event_count,
+            TimerCreate(e) => e.event_count,
+            TimerStart(e) => e.event_count,
+            TimerReset(e) => e.event_count,
+            TimerStop(e) => e.event_count,
+            TimerExpired(e) => e.event_count,
+            ObjectDelete(e) => e.event_count,
+            StreamBufferCreate(e) => e.event_count,
+            StreamBufferSend(e) => e.event_count,
+            StreamBufferReceive(e) => e.event_count,
+            StreamBufferSendFromIsr(e) => e.event_count,
+            StreamBufferReceiveFromIsr(e) => e.event_count,
+            StreamBufferReset(e) => e.event_count,
+            StreamBufferSendBlock(e) => e.event_count,
+            StreamBufferReceiveBlock(e) => e.event_count,
             MessageBufferCreate(e) => e.event_count,
             MessageBufferSend(e) => e.event_count,
             MessageBufferReceive(e) => e.event_count,
@@ -978,6 +1579,110 @@ impl Event {
             User(e) => e.event_count,
             UnusedStack(e) => e.event_count,
             Unknown(e) => e.event_count,
+            Dropped(e) => e.event_count,
+            Annotation(e) => e.event_count,
+            SessionBoundary(e) => e.event_count,
+        }
+    }
+
+    /// The event count extended to 64 bits by counting its rollovers since the
+    /// start of the session, see `TrackingEventCounter`.
+    /// Unlike the event count, it sorts the events across rollovers.
+    pub fn sequence(&self) -> u64 {
+        use Event::*;
+        match self {
+            TraceStart(e) => e.sequence,
+            TsConfig(e) => e.sequence,
+            ObjectName(e) => e.sequence,
+            TaskPriority(e) => e.sequence,
+            TaskPriorityInherit(e) => e.sequence,
+            TaskPriorityDisinherit(e) => e.sequence,
+            IsrDefine(e) => e.sequence,
+            TaskCreate(e) => e.sequence,
+            QueueCreate(e) => e.sequence,
+            MutexCreate(e) => e.sequence,
+            SemaphoreBinaryCreate(e) => e.sequence,
+            SemaphoreCountingCreate(e) => e.sequence,
+            TaskReady(e) => e.sequence,
+            IsrBegin(e) => e.sequence,
+            IsrResume(e) => e.sequence,
+            TaskBegin(e) => e.sequence,
+            TaskResume(e) => e.sequence,
+            TaskActivate(e) => e.sequence,
+            TaskCoreAffinitySet(e) => e.sequence,
+            TaskMigrate(e) => e.sequence,
+            TaskNotify(e) => e.sequence,
+            TaskNotifyFromIsr(e) => e.sequence,
+            TaskNotifyWait(e) => e.sequence,
+            TaskNotifyWaitBlock(e) => e.sequence,
+            MemoryAlloc(e) => e.sequence,
+            MemoryFree(e) => e.sequence,
+            QueueSend(e) => e.sequence,
+            QueueSendBlock(e) => e.sequence,
+            QueueSendFromIsr(e) => e.sequence,
+            QueueReceive(e) => e.sequence,
+            QueueReceiveBlock(e) => e.sequence,
+            QueueReceiveFromIsr(e) => e.sequence,
+            QueuePeek(e) => e.sequence,
+            QueuePeekBlock(e) => e.sequence,
+            QueueSendFront(e) => e.sequence,
+            QueueSendFrontBlock(e) => e.sequence,
+            QueueSendFrontFromIsr(e) => e.sequence,
+            MutexGive(e) => e.sequence,
+            MutexGiveBlock(e) => e.sequence,
+            MutexGiveRecursive(e) => e.sequence,
+            MutexTake(e) => e.sequence,
+            MutexTakeBlock(e) => e.sequence,
+            MutexTakeRecursive(e) => e.sequence,
+            MutexTakeRecursiveBlock(e) => e.sequence,
+            SemaphoreGive(e) => e.sequence,
+            SemaphoreGiveBlock(e) => e.sequence,
+            SemaphoreGiveFromIsr(e) => e.sequence,
+            SemaphoreTake(e) => e.sequence,
+            SemaphoreTakeBlock(e) => e.sequence,
+            SemaphoreTakeFromIsr(e) => e.sequence,
+            SemaphorePeek(e) => e.sequence,
+            SemaphorePeekBlock(e) => e.sequence,
+            EventGroupCreate(e) => e.sequence,
+            EventGroupSync(e) => e.sequence,
+            EventGroupWaitBits(e) => e.sequence,
+            EventGroupClearBits(e) => e.sequence,
+            EventGroupClearBitsFromIsr(e) => e.sequence,
+            EventGroupSetBits(e) => e.sequence,
+            EventGroupSetBitsFromIsr(e) => e.sequence,
+            EventGroupSyncBlock(e) => e.sequence,
+            EventGroupWaitBitsBlock(e) => e.sequence,
+            TimerCreate(e) => e.sequence,
+            TimerStart(e) => e.sequence,
+            TimerReset(e) => e.sequence,
+            TimerStop(e) => e.sequence,
+            TimerExpired(e) => e.sequence,
+            ObjectDelete(e) => e.sequence,
+            StreamBufferCreate(e) => e.sequence,
+            StreamBufferSend(e) => e.sequence,
+            StreamBufferReceive(e) => e.sequence,
+            StreamBufferSendFromIsr(e) => e.sequence,
+            StreamBufferReceiveFromIsr(e) => e.sequence,
+            StreamBufferReset(e) => e.sequence,
+            StreamBufferSendBlock(e) => e.sequence,
+            StreamBufferReceiveBlock(e) => e.sequence,
+            MessageBufferCreate(e) => e.sequence,
+            MessageBufferSend(e) => e.sequence,
+            MessageBufferReceive(e) => e.sequence,
+            MessageBufferSendFromIsr(e) => e.sequence,
+            MessageBufferReceiveFromIsr(e) => e.sequence,
+            MessageBufferReset(e) => e.sequence,
+            MessageBufferSendBlock(e) => e.sequence,
+            MessageBufferReceiveBlock(e) => e.sequence,
+            StateMachineCreate(e) => e.sequence,
+            StateMachineStateCreate(e) => e.sequence,
+            StateMachineStateChange(e) => e.sequence,
+            User(e) => e.sequence,
+            UnusedStack(e) => e.sequence,
+            Unknown(e) => e.sequence,
+            Dropped(e) => e.sequence,
+            Annotation(e) => e.sequence,
+            SessionBoundary(e) => e.sequence,
         }
     }
 
@@ -1002,6 +1707,8 @@ impl Event {
             TaskBegin(e) => e.timestamp,
             TaskResume(e) => e.timestamp,
             TaskActivate(e) => e.timestamp,
+            TaskCoreAffinitySet(e) => e.timestamp,
+            TaskMigrate(e) => e.timestamp,
             TaskNotify(e) => e.timestamp,
             TaskNotifyFromIsr(e) => e.timestamp,
             TaskNotifyWait(e) => e.timestamp,
@@ -1043,6 +1750,20 @@ impl Event {
             EventGroupSetBitsFromIsr(e) => e.timestamp,
             EventGroupSyncBlock(e) => e.timestamp,
             EventGroupWaitBitsBlock(e) => e.timestamp,
+            TimerCreate(e) => e.timestamp,
+            TimerStart(e) => e.timestamp,
+            TimerReset(e) => e.timestamp,
+            TimerStop(e) => e.timestamp,
+            TimerExpired(e) => e.timestamp,
+            ObjectDelete(e) => e.timestamp,
+            StreamBufferCreate(e) => e.timestamp,
+            StreamBufferSend(e) => e.timestamp,
+            StreamBufferReceive(e) => e.timestamp,
+            StreamBufferSendFromIsr(e) => e.timestamp,
+            StreamBufferReceiveFromIsr(e) => e.timestamp,
+            StreamBufferReset(e) => e.timestamp,
+            StreamBufferSendBlock(e) => e.timestamp,
+            StreamBufferReceiveBlock(e) => e.timestamp,
             MessageBufferCreate(e) => e.timestamp,
             MessageBufferSend(e) => e.timestamp,
             MessageBufferReceive(e) => e.timestamp,
@@ -1057,6 +1778,205 @@ impl Event {
             User(e) => e.timestamp,
             UnusedStack(e) => e.timestamp,
             Unknown(e) => e.timestamp,
+            Dropped(e) => e.around_timestamp,
+            Annotation(e) => e.timestamp,
+            SessionBoundary(e) => e.timestamp,
+        }
+    }
+
+    pub(crate) fn timestamp_mut(&mut self) -> &mut Timestamp {
+        use Event::*;
+        match self {
+            TraceStart(e) => &mut e.timestamp,
+            TsConfig(e) => &mut e.timestamp,
+            ObjectName(e) => &mut e.timestamp,
+            TaskPriority(e) => &mut e.timestamp,
+            TaskPriorityInherit(e) => &mut e.timestamp,
+            TaskPriorityDisinherit(e) => &mut e.timestamp,
+            IsrDefine(e) => &mut e.timestamp,
+            TaskCreate(e) => &mut e.timestamp,
+            QueueCreate(e) => &mut e.timestamp,
+            MutexCreate(e) => &mut e.timestamp,
+            SemaphoreBinaryCreate(e) => &mut e.timestamp,
+            SemaphoreCountingCreate(e) => &mut e.timestamp,
+            TaskReady(e) => &mut e.timestamp,
+            IsrBegin(e) => &mut e.timestamp,
+            IsrResume(e) => &mut e.timestamp,
+            TaskBegin(e) => &mut e.timestamp,
+            TaskResume(e) => &mut e.timestamp,
+            TaskActivate(e) => &mut e.timestamp,
+            TaskCoreAffinitySet(e) => &mut e.timestamp,
+            TaskMigrate(e) => &mut e.timestamp,
+            TaskNotify(e) => &mut e.timestamp,
+            TaskNotifyFromIsr(e) => &mut e.timestamp,
+            TaskNotifyWait(e) => &mut e.timestamp,
+            TaskNotifyWaitBlock(e) => &mut e.timestamp,
+            MemoryAlloc(e) => &mut e.timestamp,
+            MemoryFree(e) => &mut e.timestamp,
+            QueueSend(e) => &mut e.timestamp,
+            QueueSendBlock(e) => &mut e.timestamp,
+            QueueSendFromIsr(e) => &mut e.timestamp,
+            QueueReceive(e) => &mut e.timestamp,
+            QueueReceiveBlock(e) => &mut e.timestamp,
+            QueueReceiveFromIsr(e) => &mut e.timestamp,
+            QueuePeek(e) => &mut e.timestamp,
+            QueuePeekBlock(e) => &mut e.timestamp,
+            QueueSendFront(e) => &mut e.timestamp,
+            QueueSendFrontBlock(e) => &mut e.timestamp,
+            QueueSendFrontFromIsr(e) => &mut e.timestamp,
+            MutexGive(e) => &mut e.timestamp,
+            MutexGiveBlock(e) => &mut e.timestamp,
+            MutexGiveRecursive(e) => &mut e.timestamp,
+            MutexTake(e) => &mut e.timestamp,
+            MutexTakeBlock(e) => &mut e.timestamp,
+            MutexTakeRecursive(e) => &mut e.timestamp,
+            MutexTakeRecursiveBlock(e) => &mut e.timestamp,
+            SemaphoreGive(e) => &mut e.timestamp,
+            SemaphoreGiveBlock(e) => &mut e.timestamp,
+            SemaphoreGiveFromIsr(e) => &mut e.timestamp,
+            SemaphoreTake(e) => &mut e.timestamp,
+            SemaphoreTakeBlock(e) => &mut e.timestamp,
+            SemaphoreTakeFromIsr(e) => &mut e.timestamp,
+            SemaphorePeek(e) => &mut e.timestamp,
+            SemaphorePeekBlock(e) => &mut e.timestamp,
+            EventGroupCreate(e) => &mut e.timestamp,
+            EventGroupSync(e) => &mut e.timestamp,
+            EventGroupWaitBits(e) => &mut e.timestamp,
+            EventGroupClearBits(e) => &mut e.timestamp,
+            EventGroupClearBitsFromIsr(e) => &mut e.timestamp,
+            EventGroupSetBits(e) => &mut e.timestamp,
+            EventGroupSetBitsFromIsr(e) => &mut e.timestamp,
+            EventGroupSyncBlock(e) => &mut e.timestamp,
+            EventGroupWaitBitsBlock(e) => &mut e.timestamp,
+            TimerCreate(e) => &mut e.timestamp,
+            TimerStart(e) => &mut e.timestamp,
+            TimerReset(e) => &mut e.timestamp,
+            TimerStop(e) => &mut e.timestamp,
+            TimerExpired(e) => &mut e.timestamp,
+            ObjectDelete(e) => &mut e.timestamp,
+            StreamBufferCreate(e) => &mut e.timestamp,
+            StreamBufferSend(e) => &mut e.timestamp,
+            StreamBufferReceive(e) => &mut e.timestamp,
+            StreamBufferSendFromIsr(e) => &mut e.timestamp,
+            StreamBufferReceiveFromIsr(e) => &mut e.timestamp,
+            StreamBufferReset(e) => &mut e.timestamp,
+            StreamBufferSendBlock(e) => &mut e.timestamp,
+            StreamBufferReceiveBlock(e) => &mut e.timestamp,
+            MessageBufferCreate(e) => &mut e.timestamp,
+            MessageBufferSend(e) => &mut e.timestamp,
+            MessageBufferReceive(e) => &mut e.timestamp,
+            MessageBufferSendFromIsr(e) => &mut e.timestamp,
+            MessageBufferReceiveFromIsr(e) => &mut e.timestamp,
+            MessageBufferReset(e) => &mut e.timestamp,
+            MessageBufferSendBlock(e) => &mut e.timestamp,
+            MessageBufferReceiveBlock(e) => &mut e.timestamp,
+            StateMachineCreate(e) => &mut e.timestamp,
+            StateMachineStateCreate(e) => &mut e.timestamp,
+            StateMachineStateChange(e) => &mut e.timestamp,
+            User(e) => &mut e.timestamp,
+            UnusedStack(e) => &mut e.timestamp,
+            Unknown(e) => &mut e.timestamp,
+            Dropped(e) => &mut e.around_timestamp,
+            Annotation(e) => &mut e.timestamp,
+            SessionBoundary(e) => &mut e.timestamp,
+        }
+    }
+
+    pub(crate) fn sequence_mut(&mut self) -> &mut u64 {
+        use Event::*;
+        match self {
+            TraceStart(e) => &mut e.sequence,
+            TsConfig(e) => &mut e.sequence,
+            ObjectName(e) => &mut e.sequence,
+            TaskPriority(e) => &mut e.sequence,
+            TaskPriorityInherit(e) => &mut e.sequence,
+            TaskPriorityDisinherit(e) => &mut e.sequence,
+            IsrDefine(e) => &mut e.sequence,
+            TaskCreate(e) => &mut e.sequence,
+            QueueCreate(e) => &mut e.sequence,
+            MutexCreate(e) => &mut e.sequence,
+            SemaphoreBinaryCreate(e) => &mut e.sequence,
+            SemaphoreCountingCreate(e) => &mut e.sequence,
+            TaskReady(e) => &mut e.sequence,
+            IsrBegin(e) => &mut e.sequence,
+            IsrResume(e) => &mut e.sequence,
+            TaskBegin(e) => &mut e.sequence,
+            TaskResume(e) => &mut e.sequence,
+            TaskActivate(e) => &mut e.sequence,
+            TaskCoreAffinitySet(e) => &mut e.sequence,
+            TaskMigrate(e) => &mut e.sequence,
+            TaskNotify(e) => &mut e.sequence,
+            TaskNotifyFromIsr(e) => &mut e.sequence,
+            TaskNotifyWait(e) => &mut e.sequence,
+            TaskNotifyWaitBlock(e) => &mut e.sequence,
+            MemoryAlloc(e) => &mut e.sequence,
+            MemoryFree(e) => &mut e.sequence,
+            QueueSend(e) => &mut e.sequence,
+            QueueSendBlock(e) => &mut e.sequence,
+            QueueSendFromIsr(e) => &mut e.sequence,
+            QueueReceive(e) => &mut e.sequence,
+            QueueReceiveBlock(e) => &mut e.sequence,
+            QueueReceiveFromIsr(e) => &mut e.sequence,
+            QueuePeek(e) => &mut e.sequence,
+            QueuePeekBlock(e) => &mut e.sequence,
+            QueueSendFront(e) => &mut e.sequence,
+            QueueSendFrontBlock(e) => &mut e.sequence,
+            QueueSendFrontFromIsr(e) => &mut e.sequence,
+            MutexGive(e) => &mut e.sequence,
+            MutexGiveBlock(e) => &mut e.sequence,
+            MutexGiveRecursive(e) => &mut e.sequence,
+            MutexTake(e) => &mut e.sequence,
+            MutexTakeBlock(e) => &mut e.sequence,
+            MutexTakeRecursive(e) => &mut e.sequence,
+            MutexTakeRecursiveBlock(e) => &mut e.sequence,
+            SemaphoreGive(e) => &mut e.sequence,
+            SemaphoreGiveBlock(e) => &mut e.sequence,
+            SemaphoreGiveFromIsr(e) => &mut e.sequence,
+            SemaphoreTake(e) => &mut e.sequence,
+            SemaphoreTakeBlock(e) => &mut e.sequence,
+            SemaphoreTakeFromIsr(e) => &mut e.sequence,
+            SemaphorePeek(e) => &mut e.sequence,
+            SemaphorePeekBlock(e) => &mut e.sequence,
+            EventGroupCreate(e) => &mut e.sequence,
+            EventGroupSync(e) => &mut e.sequence,
+            EventGroupWaitBits(e) => &mut e.sequence,
+            EventGroupClearBits(e) => &mut e.sequence,
+            EventGroupClearBitsFromIsr(e) => &mut e.sequence,
+            EventGroupSetBits(e) => &mut e.sequence,
+            EventGroupSetBitsFromIsr(e) => &mut e.sequence,
+            EventGroupSyncBlock(e) => &mut e.sequence,
+            EventGroupWaitBitsBlock(e) => &mut e.sequence,
+            TimerCreate(e) => &mut e.sequence,
+            TimerStart(e) => &mut e.sequence,
+            TimerReset(e) => &mut e.sequence,
+            TimerStop(e) => &mut e.sequence,
+            TimerExpired(e) => &mut e.sequence,
+            ObjectDelete(e) => &mut e.sequence,
+            StreamBufferCreate(e) => &mut e.sequence,
+            StreamBufferSend(e) => &mut e.sequence,
+            StreamBufferReceive(e) => &mut e.sequence,
+            StreamBufferSendFromIsr(e) => &mut e.sequence,
+            StreamBufferReceiveFromIsr(e) => &mut e.sequence,
+            StreamBufferReset(e) => &mut e.sequence,
+            StreamBufferSendBlock(e) => &mut e.sequence,
+            StreamBufferReceiveBlock(e) => &mut e.sequence,
+            MessageBufferCreate(e) => &mut e.sequence,
+            MessageBufferSend(e) => &mut e.sequence,
+            MessageBufferReceive(e) => &mut e.sequence,
+            MessageBufferSendFromIsr(e) => &mut e.sequence,
+            MessageBufferReceiveFromIsr(e) => &mut e.sequence,
+            MessageBufferReset(e) => &mut e.sequence,
+            MessageBufferSendBlock(e) => &mut e.sequence,
+            MessageBufferReceiveBlock(e) => &mut e.sequence,
+            StateMachineCreate(e) => &mut e.sequence,
+            StateMachineStateCreate(e) => &mut e.sequence,
+            StateMachineStateChange(e) => &mut e.sequence,
+            User(e) => &mut e.sequence,
+            UnusedStack(e) => &mut e.sequence,
+            Unknown(e) => &mut e.sequence,
+            Dropped(e) => &mut e.sequence,
+            Annotation(e) => &mut e.sequence,
+            SessionBoundary(e) => &mut e.sequence,
         }
     }
 }
@@ -1112,6 +2032,23 @@ impl TrackingEventCounter {
     }
 }
 
+/// An unknown event with the given count and timestamp, the sequence is the count.
+/// Shared by the tests of the event iterator adapters.
+#[cfg(test)]
+pub(crate) fn test_event(count: u16, timestamp: u64) -> (EventCode, Event) {
+    let code = EventCode(0x0FFF);
+    (
+        code,
+        Event::Unknown(BaseEvent {
+            code,
+            event_count: EventCount(count),
+            sequence: count.into(),
+            timestamp: Timestamp(timestamp),
+            parameters: [0; 15],
+        }),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1132,7 +2069,7 @@ mod test {
 
         // Reset initial count works
         ec.set_initial_count(EventCount(u16::MAX));
-        assert_eq!(ec.count(), u16::MAX.into());
+        assert_eq!(ec.count(), u64::from(u16::MAX));
 
         // Non-rollover discontinuities
         ec.set_initial_count(EventCount(0));
@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}")]
 pub struct MutexCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -17,6 +18,7 @@ pub struct MutexCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}")]
 pub struct MutexEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -0,0 +1,18 @@
+use crate::streaming::event::EventCount;
+use crate::time::Timestamp;
+use crate::types::{ObjectClass, ObjectHandle, ObjectName};
+use derive_more::Display;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{class}:{handle}")]
+pub struct ObjectDeleteEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub class: ObjectClass,
+    pub name: Option<ObjectName>,
+    /// Object state at the time of deletion (i.e. task priority or messages waiting), if recorded
+    pub state: Option<u32>,
+}
@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}:'{name}'")]
 pub struct ObjectNameEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
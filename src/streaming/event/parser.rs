@@ -1,14 +1,30 @@
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::intern::InternPool;
+use crate::streaming::entry_table::{NameChange, PriorityChange, PriorityChangeKind};
 use crate::streaming::event::*;
-use crate::streaming::{EntryTable, Error, HeaderInfo};
-use crate::time::{Frequency, Ticks};
+use crate::streaming::{EntryTable, Error, HeaderInfo, WellKnownObjects};
+use crate::symbolizer::Symbolizer;
+use crate::time::{Frequency, Ticks, Timestamp};
 use crate::types::{
-    format_symbol_string, Endianness, FormatString, FormattedString, Heap, ObjectClass,
-    ObjectHandle, ObjectName, Priority, Protocol, SymbolString, TimerCounter, TrimmedString,
-    UserEventChannel,
+    Endianness, FormatCache, FormatString, FormattedString, Heap, ObjectClass, ObjectHandle,
+    ObjectName, Priority, Protocol, StringDecoder, StringSanitizer, SymbolString, TimerCounter,
+    TrimmedString, UserEventChannel, UNNAMED_OBJECT,
 };
 use byteordered::ByteOrdered;
+use std::collections::BTreeMap;
 use std::io::{self, Read};
-use tracing::error;
+use std::sync::Arc;
+
+/// What to do when an event references an object that has no name yet
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum ObjectLookupPolicy {
+    /// Return `Error::ObjectLookup`
+    #[default]
+    Error,
+    /// Use `UNNAMED_OBJECT` as the name and report a warning diagnostic,
+    /// i.e. for captures that start after the objects were named
+    Substitute,
+}
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct EventParser {
@@ -26,9 +42,65 @@ pub struct EventParser {
 
     /// Local scratch buffer for reading argument data
     arg_buf: Vec<u8>,
+
+    /// Whether the blocking send and give calls may carry a `ticks_to_wait` parameter,
+    /// see `HeaderInfo::send_ticks_to_wait`
+    send_ticks_to_wait: bool,
+
+    /// Whether to verify the object class of handles referenced by events
+    check_object_classes: bool,
+
+    /// What to do with events referencing unnamed objects
+    object_lookup_policy: ObjectLookupPolicy,
+
+    /// Classifies the entries named by events
+    well_known_objects: WellKnownObjects,
+
+    /// Resolves addresses to symbols, if provided
+    symbolizer: Option<Arc<Symbolizer>>,
+
+    /// Decodes the raw bytes of the strings read from the events
+    string_decoder: StringDecoder,
+
+    /// Cleans up the strings read from the events, if provided
+    string_sanitizer: Option<StringSanitizer>,
+
+    /// Interns the strings read from the events, if provided
+    intern_pool: Option<InternPool>,
+
+    /// Whether the entries present before the symbolizer was set have been symbolized,
+    /// afterwards only the inserted entries are
+    symbolized_all_entries: bool,
+
+    /// Recoverable issues found since the last call to `take_diagnostics`
+    diagnostics: Vec<Diagnostic>,
+
+    /// Symbol changes of entries that were already named
+    name_history: BTreeMap<ObjectHandle, Vec<NameChange>>,
+
+    /// Maximum number of symbol changes kept per entry
+    max_name_history_len: usize,
+
+    /// Priority changes of tasks, including inherited priorities
+    priority_history: BTreeMap<ObjectHandle, Vec<PriorityChange>>,
+
+    /// Maximum number of priority changes kept per task
+    max_priority_history_len: usize,
+
+    /// The task currently running, `None` in ISR context or before the first context switch
+    running_task: Option<ObjectHandle>,
+
+    /// User event format strings compiled so far
+    format_cache: FormatCache,
+
+    /// Extends the event counts to the events' sequence numbers
+    event_counter: Option<TrackingEventCounter>,
 }
 
 impl EventParser {
+    /// Default maximum number of changes kept per entry by the name and priority histories
+    pub const DEFAULT_MAX_HISTORY_LEN: usize = 32;
+
     pub fn new(endianness: Endianness, heap: Heap) -> Self {
         Self {
             endianness: byteordered::Endianness::from(endianness),
@@ -36,17 +108,280 @@ impl EventParser {
             custom_printf_event_id: None,
             buf: Vec::with_capacity(256),
             arg_buf: Vec::with_capacity(256),
+            send_ticks_to_wait: true,
+            check_object_classes: false,
+            object_lookup_policy: ObjectLookupPolicy::default(),
+            well_known_objects: WellKnownObjects::default(),
+            symbolizer: None,
+            string_decoder: StringDecoder::default(),
+            string_sanitizer: None,
+            intern_pool: None,
+            symbolized_all_entries: false,
+            diagnostics: Vec::new(),
+            name_history: BTreeMap::new(),
+            max_name_history_len: Self::DEFAULT_MAX_HISTORY_LEN,
+            priority_history: BTreeMap::new(),
+            max_priority_history_len: Self::DEFAULT_MAX_HISTORY_LEN,
+            running_task: None,
+            format_cache: FormatCache::default(),
+            event_counter: None,
+        }
+    }
+
+    /// A parser for the session following a trace restart, configured like this one.
+    /// The entries of the new session's table are classified by the well-known objects,
+    /// and their symbols sanitized and interned.
+    pub(crate) fn restarted(&self, header: &HeaderInfo, entry_table: &mut EntryTable) -> Self {
+        self.well_known_objects.classify(entry_table);
+        if let Some(sanitizer) = &self.string_sanitizer {
+            entry_table.sanitize_symbols(sanitizer);
         }
+        if let Some(pool) = &self.intern_pool {
+            entry_table.intern_symbols(pool);
+        }
+        let mut parser = Self {
+            custom_printf_event_id: self.custom_printf_event_id,
+            check_object_classes: self.check_object_classes,
+            object_lookup_policy: self.object_lookup_policy,
+            well_known_objects: self.well_known_objects.clone(),
+            symbolizer: self.symbolizer.clone(),
+            string_decoder: self.string_decoder,
+            string_sanitizer: self.string_sanitizer,
+            intern_pool: self.intern_pool.clone(),
+            max_name_history_len: self.max_name_history_len,
+            max_priority_history_len: self.max_priority_history_len,
+            ..Self::new(
+                header.endianness,
+                entry_table.system_heap().unwrap_or_default(),
+            )
+        };
+        parser.set_send_ticks_to_wait(header.send_ticks_to_wait());
+        parser
+    }
+
+    /// Accept the optional `ticks_to_wait` parameter of the blocking send and give calls,
+    /// enabled by default, see `HeaderInfo::send_ticks_to_wait`
+    pub fn set_send_ticks_to_wait(&mut self, enabled: bool) {
+        self.send_ticks_to_wait = enabled;
     }
 
     pub fn set_custom_printf_event_id(&mut self, custom_printf_event_id: EventId) {
         self.custom_printf_event_id = Some(custom_printf_event_id);
     }
 
+    /// Enable checking that events operate on objects of the expected class,
+    /// returning `Error::ObjectClassMismatch` when they don't.
+    /// Mismatches usually indicate the event ID table has drifted from the one used
+    /// by the recorder.
+    pub fn set_object_class_checking(&mut self, enabled: bool) {
+        self.check_object_classes = enabled;
+    }
+
+    pub fn set_object_lookup_policy(&mut self, policy: ObjectLookupPolicy) {
+        self.object_lookup_policy = policy;
+    }
+
+    /// Classify the entries without a class as they're named
+    pub fn set_well_known_objects(&mut self, objects: WellKnownObjects) {
+        self.well_known_objects = objects;
+    }
+
+    pub fn well_known_objects(&self) -> &WellKnownObjects {
+        &self.well_known_objects
+    }
+
+    /// Resolve memory addresses, ISR handles and entry table addresses
+    /// to symbols
+    pub fn set_symbolizer(&mut self, symbolizer: Arc<Symbolizer>) {
+        self.symbolizer = Some(symbolizer);
+        self.symbolized_all_entries = false;
+    }
+
+    /// Decode the object names and user event format strings read from the events
+    /// with `decoder` instead of lossy UTF-8
+    pub fn set_string_decoder(&mut self, decoder: StringDecoder) {
+        self.string_decoder = decoder;
+    }
+
+    pub(crate) fn string_decoder(&self) -> &StringDecoder {
+        &self.string_decoder
+    }
+
+    /// Sanitize the object names and user event format strings read from the events
+    pub fn set_string_sanitizer(&mut self, sanitizer: StringSanitizer) {
+        self.string_sanitizer = Some(sanitizer);
+    }
+
+    /// Intern the object names and user event format strings read from the events
+    pub fn set_intern_pool(&mut self, pool: InternPool) {
+        self.intern_pool = Some(pool);
+    }
+
     pub fn system_heap(&self) -> &Heap {
         &self.heap
     }
 
+    /// Take the recoverable issues found while parsing events so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    pub(crate) fn has_diagnostics(&self) -> bool {
+        !self.diagnostics.is_empty()
+    }
+
+    /// Keep at most the last `len` renames of each entry, 0 disables the name history.
+    /// Defaults to `DEFAULT_MAX_HISTORY_LEN`.
+    pub fn set_max_name_history_len(&mut self, len: usize) {
+        self.max_name_history_len = len;
+        truncate_histories(&mut self.name_history, len);
+    }
+
+    /// The renames of the entry with the given handle, oldest first,
+    /// see `set_max_name_history_len`
+    pub fn name_history(&self, handle: ObjectHandle) -> &[NameChange] {
+        self.name_history
+            .get(&handle)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The handles of all renamed entries, with their renames
+    pub fn renamed_entries(&self) -> &BTreeMap<ObjectHandle, Vec<NameChange>> {
+        &self.name_history
+    }
+
+    /// Keep at most the last `len` priority changes of each task, 0 disables the
+    /// priority history. Defaults to `DEFAULT_MAX_HISTORY_LEN`.
+    pub fn set_max_priority_history_len(&mut self, len: usize) {
+        self.max_priority_history_len = len;
+        truncate_histories(&mut self.priority_history, len);
+    }
+
+    /// The priority changes of the task with the given handle, oldest first,
+    /// see `set_max_priority_history_len`
+    pub fn priority_history(&self, handle: ObjectHandle) -> &[PriorityChange] {
+        self.priority_history
+            .get(&handle)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The handles of all tasks whose priority changed, with their changes
+    pub fn priority_changes(&self) -> &BTreeMap<ObjectHandle, Vec<PriorityChange>> {
+        &self.priority_history
+    }
+
+    /// The task currently running, `None` in ISR context or before the first context switch
+    pub fn running_task(&self) -> Option<ObjectHandle> {
+        self.running_task
+    }
+
+    /// Update the running task from a context switch event, `handle` is the
+    /// event's first parameter. Shared by `next_event` and `visit_event`.
+    fn track_running_task(&mut self, event_type: EventType, handle: Option<ObjectHandle>) {
+        match event_type {
+            EventType::TraceStart
+            | EventType::TaskSwitchTaskBegin
+            | EventType::TaskSwitchTaskResume
+            | EventType::TaskActivate => self.running_task = handle,
+            EventType::TaskSwitchIsrBegin | EventType::TaskSwitchIsrResume => {
+                self.running_task = None
+            }
+            _ => (),
+        }
+    }
+
+    /// The symbol of an object referenced by an event, see `ObjectLookupPolicy`
+    fn object_symbol(
+        &mut self,
+        symbol: Option<&SymbolString>,
+        handle: ObjectHandle,
+        event_count: EventCount,
+    ) -> Result<SymbolString, Error> {
+        match symbol {
+            Some(symbol) => Ok(symbol.clone()),
+            None => self
+                .missing_symbol(handle, event_count)
+                .map(|s| SymbolString(s.into())),
+        }
+    }
+
+    fn missing_symbol(
+        &mut self,
+        handle: ObjectHandle,
+        event_count: EventCount,
+    ) -> Result<&'static str, Error> {
+        match self.object_lookup_policy {
+            ObjectLookupPolicy::Error => Err(Error::ObjectLookup(handle)),
+            ObjectLookupPolicy::Substitute => {
+                let msg = format!("Object {handle} has no name, using '{UNNAMED_OBJECT}'");
+                warn!("{msg}");
+                self.diagnostics.push(
+                    Diagnostic::new(Severity::Warning, msg)
+                        .with_event_count(u16::from(event_count).into()),
+                );
+                Ok(UNNAMED_OBJECT)
+            }
+        }
+    }
+
+    /// Set an entry's symbol, recording renames and reporting names shared
+    /// with another object of the same class
+    fn name_entry(
+        &mut self,
+        entry_table: &mut EntryTable,
+        handle: ObjectHandle,
+        symbol: SymbolString,
+        event_count: EventCount,
+        timestamp: Timestamp,
+    ) {
+        if entry_table.class(handle).is_none() {
+            if let Some(class) = self.well_known_objects.class(handle, Some(&symbol.0)) {
+                entry_table.set_class(handle, class);
+            }
+        }
+        let previous = entry_table.symbol(handle).cloned();
+        let class = entry_table.set_symbol(handle, symbol.clone()).class;
+        if let Some(previous) = previous.filter(|p| *p != symbol) {
+            let msg = format!("Object {handle} renamed from '{previous}' to '{symbol}'");
+            warn!("{msg}");
+            self.diagnostics.push(
+                Diagnostic::new(Severity::Warning, msg)
+                    .with_event_count(u16::from(event_count).into()),
+            );
+            push_history(
+                &mut self.name_history,
+                handle,
+                NameChange {
+                    event_count,
+                    timestamp,
+                    previous,
+                    name: symbol.clone(),
+                },
+                self.max_name_history_len,
+            );
+        }
+
+        // Names aren't unique in general (i.e. user event strings), only
+        // objects of a known class are checked
+        let Some(class) = class else {
+            return;
+        };
+        let duplicate = entry_table.entries().iter().find_map(|(h, e)| {
+            (*h != handle && e.class == Some(class) && e.symbol.as_ref() == Some(&symbol))
+                .then_some(*h)
+        });
+        if let Some(other) = duplicate {
+            let msg = format!("{class} objects {other} and {handle} have the same name '{symbol}'");
+            warn!("{msg}");
+            self.diagnostics.push(
+                Diagnostic::new(Severity::Warning, msg)
+                    .with_event_count(u16::from(event_count).into()),
+            );
+        }
+    }
+
     pub fn next_event<R: Read>(
         &mut self,
         mut r: &mut R,
@@ -72,15 +407,82 @@ impl EventParser {
         let mut r = ByteOrdered::new(r, self.endianness);
 
         let event_code = match first_word_reader.read_u16() {
-            Ok(ec) => EventCode(ec),
+            Ok(ec) => EventCode(ec.into()),
             Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
             Err(e) => return Err(e.into()),
         };
+        let event_count = EventCount(first_word_reader.read_u16()?);
+        let timestamp = Timestamp(r.read_u32()?.into());
+
+        let params = StreamParams {
+            r: r.into_inner(),
+            endianness: self.endianness,
+        };
+        self.parse_event(params, event_code, event_count, timestamp, entry_table)
+    }
+
+    /// Like `next_event`, but parses the event at the start of `buf` in place.
+    ///
+    /// Returns the number of bytes of `buf` the event takes up along with the event,
+    /// `None` when `buf` is empty.
+    /// When `buf` ends part way through an event, `Error::TruncatedEvent` is returned
+    /// with the length of `buf`.
+    pub fn next_event_from_slice(
+        &mut self,
+        buf: &[u8],
+        entry_table: &mut EntryTable,
+    ) -> Result<(usize, Option<(EventCode, Event)>), Error> {
+        if buf.is_empty() {
+            return Ok((0, None));
+        }
+        let truncated = Error::TruncatedEvent(buf.len());
+        let Some((header, rest)) = buf.split_first_chunk::<8>() else {
+            // Restart words are checked before running out of header bytes
+            return match buf.first_chunk::<4>().copied().map(u32::from_le_bytes) {
+                Some(HeaderInfo::PSF_LITTLE_ENDIAN) => {
+                    Err(Error::TraceRestarted(Endianness::Little))
+                }
+                Some(HeaderInfo::PSF_BIG_ENDIAN) => Err(Error::TraceRestarted(Endianness::Big)),
+                _ => Err(truncated),
+            };
+        };
+        match u32::from_le_bytes([header[0], header[1], header[2], header[3]]) {
+            HeaderInfo::PSF_LITTLE_ENDIAN => return Err(Error::TraceRestarted(Endianness::Little)),
+            HeaderInfo::PSF_BIG_ENDIAN => return Err(Error::TraceRestarted(Endianness::Big)),
+            _ => (),
+        }
+
+        let mut params = SliceParams {
+            buf: rest,
+            endianness: self.endianness,
+        };
+        let event_code = EventCode(params.u16_from([header[0], header[1]]).into());
+        let event_count = EventCount(params.u16_from([header[2], header[3]]));
+        let timestamp = Timestamp(
+            params
+                .u32_from([header[4], header[5], header[6], header[7]])
+                .into(),
+        );
+        match self.parse_event(&mut params, event_code, event_count, timestamp, entry_table) {
+            Ok(event) => Ok((buf.len() - params.buf.len(), event)),
+            Err(Error::TruncatedEvent(_)) => Err(truncated),
+            Err(e) => Err(e),
+        }
+    }
 
+    /// Decode the event following its header, from the parameters of either
+    /// `next_event` or `next_event_from_slice`
+    fn parse_event<P: ParamReader>(
+        &mut self,
+        mut r: P,
+        event_code: EventCode,
+        event_count: EventCount,
+        timestamp: Timestamp,
+        entry_table: &mut EntryTable,
+    ) -> Result<Option<(EventCode, Event)>, Error> {
         let event_type = event_code.event_type();
         let event_id = event_code.event_id();
-        let event_count = EventCount(first_word_reader.read_u16()?);
-        let timestamp = Timestamp(r.read_u32()?.into());
+        let sequence = self.sequence(event_count);
         let num_params = event_code.parameter_count();
 
         if let Some(expected_parameter_count) = event_type.expected_parameter_count() {
@@ -93,17 +495,16 @@ impl EventParser {
             }
         }
 
-        Ok(match event_type {
+        let event = match event_type {
             EventType::TraceStart => {
                 let handle = object_handle(&mut r, event_id)?;
-                let sym = entry_table
-                    .symbol(handle)
-                    .ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry_table.symbol(handle), handle, event_count)?;
                 let event = TraceStartEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     current_task_handle: handle,
-                    current_task: sym.clone().into(),
+                    current_task: sym.into(),
                 };
                 Some((event_code, Event::TraceStart(event)))
             }
@@ -132,6 +533,7 @@ impl EventParser {
                 };
                 let event = TsConfigEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     frequency,
                     tick_rate_hz,
@@ -153,12 +555,12 @@ impl EventParser {
                     ));
                 }
                 let handle = object_handle(&mut r, event_id)?;
-                let symbol: SymbolString = self
-                    .read_string(&mut r, (usize::from(num_params) - 1) * 4)?
-                    .into();
-                entry_table.entry(handle).set_symbol(symbol.clone());
+                let symbol =
+                    SymbolString(self.read_string(&mut r, (usize::from(num_params) - 1) * 4)?);
+                self.name_entry(entry_table, handle, symbol.clone(), event_count, timestamp);
                 let event = ObjectNameEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: symbol,
@@ -171,14 +573,31 @@ impl EventParser {
             | EventType::TaskPriorityDisinherit => {
                 let handle = object_handle(&mut r, event_id)?;
                 let priority = Priority(r.read_u32()?);
-                let entry = entry_table.entry(handle);
-                entry.states.set_priority(priority);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let previous = entry_table.entry(handle).states.priority();
+                let entry = entry_table.set_priority(handle, priority);
+                push_history(
+                    &mut self.priority_history,
+                    handle,
+                    PriorityChange {
+                        event_count,
+                        timestamp,
+                        kind: match event_type {
+                            EventType::TaskPriority => PriorityChangeKind::Set,
+                            EventType::TaskPriorityInherit => PriorityChangeKind::Inherit,
+                            _ => PriorityChangeKind::Disinherit,
+                        },
+                        previous,
+                        priority,
+                    },
+                    self.max_priority_history_len,
+                );
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = TaskEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority,
                 };
                 Some((
@@ -202,19 +621,22 @@ impl EventParser {
                 }
                 let handle = object_handle(&mut r, event_id)?;
                 let priority = Priority(r.read_u32()?);
-                let symbol: SymbolString = self
-                    .read_string(&mut r, (usize::from(num_params) - 2) * 4)?
-                    .into();
-                let entry = entry_table.entry(handle);
-                entry.states.set_priority(priority);
-                entry.set_symbol(symbol.clone());
-                entry.set_class(ObjectClass::Isr);
+                let symbol =
+                    SymbolString(self.read_string(&mut r, (usize::from(num_params) - 2) * 4)?);
+                entry_table.set_class(handle, ObjectClass::Isr);
+                self.name_entry(entry_table, handle, symbol.clone(), event_count, timestamp);
+                let entry = entry_table.set_priority(handle, priority);
+                if let Some(symbolizer) = &self.symbolizer {
+                    entry.symbolized = symbolizer.symbolize(u32::from(handle).into());
+                }
                 let event = IsrEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: symbol.into(),
                     priority,
+                    symbolized: entry.symbolized.clone(),
                 };
                 Some((event_code, Event::IsrDefine(event)))
             }
@@ -222,15 +644,15 @@ impl EventParser {
             EventType::TaskCreate => {
                 let handle = object_handle(&mut r, event_id)?;
                 let priority = Priority(r.read_u32()?);
-                let entry = entry_table.entry(handle);
-                entry.states.set_priority(priority);
-                entry.set_class(ObjectClass::Task);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                entry_table.set_class(handle, ObjectClass::Task);
+                let entry = entry_table.set_priority(handle, priority);
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = TaskEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority,
                 };
                 Some((event_code, Event::TaskCreate(event)))
@@ -239,12 +661,13 @@ impl EventParser {
             EventType::TaskReady => {
                 let handle = object_handle(&mut r, event_id)?;
                 let entry = entry_table.entry(handle);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = TaskEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority: entry.states.priority(),
                 };
                 Some((event_code, Event::TaskReady(event)))
@@ -253,13 +676,15 @@ impl EventParser {
             EventType::TaskSwitchIsrBegin => {
                 let handle = object_handle(&mut r, event_id)?;
                 let entry = entry_table.entry(handle);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = IsrEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority: entry.states.priority(),
+                    symbolized: entry.symbolized.clone(),
                 };
                 Some((event_code, Event::IsrBegin(event)))
             }
@@ -267,13 +692,15 @@ impl EventParser {
             EventType::TaskSwitchIsrResume => {
                 let handle = object_handle(&mut r, event_id)?;
                 let entry = entry_table.entry(handle);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = IsrEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority: entry.states.priority(),
+                    symbolized: entry.symbolized.clone(),
                 };
                 Some((event_code, Event::IsrResume(event)))
             }
@@ -281,12 +708,13 @@ impl EventParser {
             EventType::TaskSwitchTaskResume => {
                 let handle = object_handle(&mut r, event_id)?;
                 let entry = entry_table.entry(handle);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = TaskEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority: entry.states.priority(),
                 };
                 Some((event_code, Event::TaskResume(event)))
@@ -301,33 +729,86 @@ impl EventParser {
                     ));
                 }
                 let handle = object_handle(&mut r, event_id)?;
-                let entry = entry_table.entry(handle);
-
                 if num_params.0 == 2 {
                     let priority = Priority(r.read_u32()?);
-                    entry.states.set_priority(priority);
+                    self.activate_priority(entry_table, handle, priority, event_count, timestamp);
                 }
+                let entry = entry_table.entry(handle);
 
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = TaskEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                     priority: entry.states.priority(),
                 };
                 Some((event_code, Event::TaskActivate(event)))
             }
 
+            EventType::TaskCoreAffinitySet => {
+                let handle = object_handle(&mut r, event_id)?;
+                let affinity_mask = r.read_u32()?;
+                let sym = self.object_symbol(entry_table.symbol(handle), handle, event_count)?;
+                let event = TaskCoreAffinityEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: sym.into(),
+                    affinity_mask,
+                };
+                Some((event_code, Event::TaskCoreAffinitySet(event)))
+            }
+
+            EventType::TaskMigrate => {
+                let handle = object_handle(&mut r, event_id)?;
+                let from_core = r.read_u32()?;
+                let to_core = r.read_u32()?;
+                let sym = self.object_symbol(entry_table.symbol(handle), handle, event_count)?;
+                let event = TaskMigrateEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: sym.into(),
+                    from_core,
+                    to_core,
+                };
+                Some((event_code, Event::TaskMigrate(event)))
+            }
+
             EventType::TaskNotify | EventType::TaskNotifyFromIsr => {
+                // handle[, index[, value]]
+                if !(1..=3).contains(&num_params.0) {
+                    return Err(Error::InvalidEventParameterCount(
+                        event_code.event_id(),
+                        1,
+                        num_params,
+                    ));
+                }
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                let index = if num_params.0 >= 2 {
+                    Some(r.read_u32()?)
+                } else {
+                    None
+                };
+                let value = if num_params.0 == 3 {
+                    Some(r.read_u32()?)
+                } else {
+                    None
+                };
                 let entry = entry_table.entry(handle);
                 let event = TaskNotifyEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     task_name: entry.symbol.clone().map(ObjectName::from),
                     ticks_to_wait: None,
+                    index,
+                    value,
                 };
                 Some((
                     event_code,
@@ -339,15 +820,31 @@ impl EventParser {
             }
 
             EventType::TaskNotifyWait | EventType::TaskNotifyWaitBlock => {
+                // handle, ticks_to_wait[, index]
+                if (num_params.0 != 2) && (num_params.0 != 3) {
+                    return Err(Error::InvalidEventParameterCount(
+                        event_code.event_id(),
+                        2,
+                        num_params,
+                    ));
+                }
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
                 let ticks_to_wait = Some(Ticks(r.read_u32()?));
+                let index = if num_params.0 == 3 {
+                    Some(r.read_u32()?)
+                } else {
+                    None
+                };
                 let entry = entry_table.entry(handle);
                 let event = TaskNotifyEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     task_name: entry.symbol.clone().map(ObjectName::from),
                     ticks_to_wait,
+                    index,
+                    value: None,
                 };
                 Some((
                     event_code,
@@ -368,10 +865,20 @@ impl EventParser {
                 }
                 let event = MemoryEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     address,
                     size,
                     heap: self.heap,
+                    symbolized: self
+                        .symbolizer
+                        .as_ref()
+                        .and_then(|s| s.symbolize(address.into())),
+                    task: self.running_task,
+                    task_name: self
+                        .running_task
+                        .and_then(|h| entry_table.symbol(h))
+                        .map(|s| s.clone().into()),
                 };
                 Some((
                     event_code,
@@ -386,10 +893,10 @@ impl EventParser {
             EventType::QueueCreate => {
                 let handle = object_handle(&mut r, event_id)?;
                 let queue_length = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Queue);
+                let entry = entry_table.set_class(handle, ObjectClass::Queue);
                 let event = QueueCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -400,19 +907,26 @@ impl EventParser {
 
             EventType::QueueSend
             | EventType::QueueSendBlock
-            | EventType::QueueSendFromIsr
-            | EventType::QueueReceiveFromIsr
             | EventType::QueueSendFront
-            | EventType::QueueSendFrontBlock
-            | EventType::QueueSendFrontFromIsr => {
+            | EventType::QueueSendFrontBlock => {
+                // handle[, ticks_to_wait], messages_waiting
+                let ticks_to_wait_present =
+                    self.send_ticks_to_wait_present(event_code, num_params, 2)?;
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let ticks_to_wait = if ticks_to_wait_present {
+                    Some(Ticks(r.read_u32()?))
+                } else {
+                    None
+                };
                 let messages_waiting = r.read_u32()?;
                 let event = QueueEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
-                    ticks_to_wait: None,
+                    ticks_to_wait,
                     messages_waiting,
                 };
                 Some((
@@ -420,10 +934,33 @@ impl EventParser {
                     match event_type {
                         EventType::QueueSend => Event::QueueSend(event),
                         EventType::QueueSendBlock => Event::QueueSendBlock(event),
+                        EventType::QueueSendFront => Event::QueueSendFront(event),
+                        _ /*EventType::QueueSendFrontBlock*/ => Event::QueueSendFrontBlock(event),
+                    },
+                ))
+            }
+
+            EventType::QueueSendFromIsr
+            | EventType::QueueReceiveFromIsr
+            | EventType::QueueSendFrontFromIsr => {
+                // ISRs can't block, there's no ticks_to_wait
+                let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let messages_waiting = r.read_u32()?;
+                let event = QueueEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry_table.symbol(handle).cloned().map(ObjectName::from),
+                    ticks_to_wait: None,
+                    messages_waiting,
+                };
+                Some((
+                    event_code,
+                    match event_type {
                         EventType::QueueSendFromIsr => Event::QueueSendFromIsr(event),
                         EventType::QueueReceiveFromIsr => Event::QueueReceiveFromIsr(event),
-                        EventType::QueueSendFront => Event::QueueSendFront(event),
-                        EventType::QueueSendFrontBlock => Event::QueueSendFrontBlock(event),
                         _ /*EventType::QueueSendFrontFromIsr*/ => Event::QueueSendFrontFromIsr(event),
                     },
                 ))
@@ -434,10 +971,12 @@ impl EventParser {
             | EventType::QueuePeek
             | EventType::QueuePeekBlock => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let ticks_to_wait = Some(Ticks(r.read_u32()?));
                 let messages_waiting = r.read_u32()?;
                 let event = QueueEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
@@ -458,10 +997,10 @@ impl EventParser {
             EventType::MutexCreate => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
                 let _unused = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Mutex);
+                let entry = entry_table.set_class(handle, ObjectClass::Mutex);
                 let event = MutexCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -470,15 +1009,24 @@ impl EventParser {
             }
 
             EventType::MutexGive | EventType::MutexGiveBlock | EventType::MutexGiveRecursive => {
+                // handle[, ticks_to_wait]
+                let ticks_to_wait_present =
+                    self.send_ticks_to_wait_present(event_code, num_params, 1)?;
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Mutex);
+                self.check_object_class(entry_table, handle, event_type)?;
+                let ticks_to_wait = if ticks_to_wait_present {
+                    Some(Ticks(r.read_u32()?))
+                } else {
+                    None
+                };
+                let entry = entry_table.set_class(handle, ObjectClass::Mutex);
                 let event = MutexEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
-                    ticks_to_wait: None,
+                    ticks_to_wait,
                 };
                 Some((
                     event_code,
@@ -495,11 +1043,12 @@ impl EventParser {
             | EventType::MutexTakeRecursive
             | EventType::MutexTakeRecursiveBlock => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let ticks_to_wait = Some(Ticks(r.read_u32()?));
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Mutex);
+                let entry = entry_table.set_class(handle, ObjectClass::Mutex);
                 let event = MutexEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -519,10 +1068,10 @@ impl EventParser {
             EventType::SemaphoreBinaryCreate => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
                 let _unused = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Semaphore);
+                let entry = entry_table.set_class(handle, ObjectClass::Semaphore);
                 let event = SemaphoreCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -534,10 +1083,10 @@ impl EventParser {
             EventType::SemaphoreCountingCreate => {
                 let handle = object_handle(&mut r, event_id)?;
                 let count = Some(r.read_u32()?);
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::Semaphore);
+                let entry = entry_table.set_class(handle, ObjectClass::Semaphore);
                 let event = SemaphoreCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -546,25 +1095,53 @@ impl EventParser {
                 Some((event_code, Event::SemaphoreCountingCreate(event)))
             }
 
-            EventType::SemaphoreGive
-            | EventType::SemaphoreGiveBlock
-            | EventType::SemaphoreGiveFromIsr
-            | EventType::SemaphoreTakeFromIsr => {
+            EventType::SemaphoreGive | EventType::SemaphoreGiveBlock => {
+                // handle[, ticks_to_wait], count
+                let ticks_to_wait_present =
+                    self.send_ticks_to_wait_present(event_code, num_params, 2)?;
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let ticks_to_wait = if ticks_to_wait_present {
+                    Some(Ticks(r.read_u32()?))
+                } else {
+                    None
+                };
                 let count = r.read_u32()?;
                 let event = SemaphoreEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
-                    ticks_to_wait: None,
+                    ticks_to_wait,
                     count,
                 };
                 Some((
                     event_code,
                     match event_type {
                         EventType::SemaphoreGive => Event::SemaphoreGive(event),
-                        EventType::SemaphoreGiveBlock => Event::SemaphoreGiveBlock(event),
+                        _ /*EventType::SemaphoreGiveBlock*/ => Event::SemaphoreGiveBlock(event),
+                    },
+                ))
+            }
+
+            EventType::SemaphoreGiveFromIsr | EventType::SemaphoreTakeFromIsr => {
+                // ISRs can't block, there's no ticks_to_wait
+                let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let count = r.read_u32()?;
+                let event = SemaphoreEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry_table.symbol(handle).cloned().map(ObjectName::from),
+                    ticks_to_wait: None,
+                    count,
+                };
+                Some((
+                    event_code,
+                    match event_type {
                         EventType::SemaphoreGiveFromIsr => Event::SemaphoreGiveFromIsr(event),
                         _ /*EventType::SemaphoreTakeFromIsr*/ => Event::SemaphoreTakeFromIsr(event),
                     },
@@ -576,10 +1153,12 @@ impl EventParser {
             | EventType::SemaphorePeek
             | EventType::SemaphorePeekBlock => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let ticks_to_wait = Some(Ticks(r.read_u32()?));
                 let count = r.read_u32()?;
                 let event = SemaphoreEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
@@ -597,13 +1176,62 @@ impl EventParser {
                 ))
             }
 
+            EventType::TimerCreate => {
+                let handle = object_handle(&mut r, event_id)?;
+                let period = Ticks(r.read_u32()?);
+                let entry = entry_table.set_class(handle, ObjectClass::Timer);
+                let event = TimerCreateEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry.symbol.clone().map(ObjectName::from),
+                    period,
+                };
+                Some((event_code, Event::TimerCreate(event)))
+            }
+
+            EventType::TimerStart | EventType::TimerReset | EventType::TimerStop => {
+                let handle = object_handle(&mut r, event_id)?;
+                let value = r.read_u32()?;
+                let event = TimerEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry_table.symbol(handle).cloned().map(ObjectName::from),
+                    value,
+                };
+                Some((
+                    event_code,
+                    match event_type {
+                        EventType::TimerStart => Event::TimerStart(event),
+                        EventType::TimerReset => Event::TimerReset(event),
+                        _ /*EventType::TimerStop*/ => Event::TimerStop(event),
+                    },
+                ))
+            }
+
+            EventType::TimerExpired => {
+                let callback = r.read_u32()?;
+                let timer_id = r.read_u32()?;
+                let event = TimerExpiredEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    callback,
+                    timer_id,
+                };
+                Some((event_code, Event::TimerExpired(event)))
+            }
+
             EventType::EventGroupCreate => {
                 let handle: ObjectHandle = object_handle(&mut r, event_id)?;
                 let event_bits = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::EventGroup);
+                let entry = entry_table.set_class(handle, ObjectClass::EventGroup);
                 let event = EventGroupCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry.symbol.clone().map(ObjectName::from),
@@ -621,9 +1249,11 @@ impl EventParser {
             | EventType::EventGroupSyncBlock
             | EventType::EventGroupWaitBitsBlock => {
                 let handle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let bits = r.read_u32()?;
                 let event = EventGroupEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
@@ -644,19 +1274,126 @@ impl EventParser {
                 ))
             }
 
-            EventType::MessageBufferCreate => {
-                let handle: ObjectHandle = object_handle(&mut r, event_id)?;
-                let buffer_size = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::MessageBuffer);
-                let event = MessageBufferCreateEvent {
-                    event_count,
-                    timestamp,
-                    handle,
-                    name: entry.symbol.clone().map(ObjectName::from),
-                    buffer_size,
-                };
-                Some((event_code, Event::MessageBufferCreate(event)))
+            EventType::TaskDelete
+            | EventType::QueueDelete
+            | EventType::SemaphoreDelete
+            | EventType::MutexDelete
+            | EventType::TimerDelete
+            | EventType::EventGroupDelete
+            | EventType::StreamBufferDelete
+            | EventType::MessageBufferDelete => {
+                if (num_params.0 != 1) && (num_params.0 != 2) {
+                    return Err(Error::InvalidEventParameterCount(
+                        event_code.event_id(),
+                        2,
+                        num_params,
+                    ));
+                }
+                let handle = object_handle(&mut r, event_id)?;
+                let state = if num_params.0 == 2 {
+                    Some(r.read_u32()?)
+                } else {
+                    None
+                };
+                let class = match event_type {
+                    EventType::TaskDelete => ObjectClass::Task,
+                    EventType::QueueDelete => ObjectClass::Queue,
+                    EventType::SemaphoreDelete => ObjectClass::Semaphore,
+                    EventType::MutexDelete => ObjectClass::Mutex,
+                    EventType::TimerDelete => ObjectClass::Timer,
+                    EventType::EventGroupDelete => ObjectClass::EventGroup,
+                    EventType::StreamBufferDelete => ObjectClass::StreamBuffer,
+                    _ /*EventType::MessageBufferDelete*/ => ObjectClass::MessageBuffer,
+                };
+                // The handle may be reused by a subsequently created object
+                let entry = entry_table.remove(handle);
+                let event = ObjectDeleteEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    class,
+                    name: entry.and_then(|e| e.symbol).map(ObjectName::from),
+                    state,
+                };
+                Some((event_code, Event::ObjectDelete(event)))
+            }
+
+            EventType::StreamBufferCreate => {
+                let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                let buffer_size = r.read_u32()?;
+                let entry = entry_table.set_class(handle, ObjectClass::StreamBuffer);
+                let event = StreamBufferCreateEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry.symbol.clone().map(ObjectName::from),
+                    buffer_size,
+                };
+                Some((event_code, Event::StreamBufferCreate(event)))
+            }
+
+            EventType::StreamBufferSend
+            | EventType::StreamBufferReceive
+            | EventType::StreamBufferSendFromIsr
+            | EventType::StreamBufferReceiveFromIsr
+            | EventType::StreamBufferReset => {
+                let handle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let bytes_in_buffer = r.read_u32()?;
+                let event = StreamBufferEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry_table.symbol(handle).cloned().map(ObjectName::from),
+                    bytes_in_buffer,
+                };
+                Some((
+                    event_code,
+                    match event_type {
+                        EventType::StreamBufferSend => Event::StreamBufferSend(event),
+                        EventType::StreamBufferReceive => Event::StreamBufferReceive(event),
+                        EventType::StreamBufferSendFromIsr => Event::StreamBufferSendFromIsr(event),
+                        EventType::StreamBufferReceiveFromIsr => Event::StreamBufferReceiveFromIsr(event),
+                        _ /*EventType::StreamBufferReset*/ => Event::StreamBufferReset(event),
+                    },
+                ))
+            }
+
+            EventType::StreamBufferSendBlock | EventType::StreamBufferReceiveBlock => {
+                let handle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                let event = StreamBufferBlockEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry_table.symbol(handle).cloned().map(ObjectName::from),
+                };
+                Some((
+                    event_code,
+                    match event_type {
+                        EventType::StreamBufferSendBlock => Event::StreamBufferSendBlock(event),
+                        _ /*EventType::StreamBufferReceiveBlock*/ => Event::StreamBufferReceiveBlock(event),
+                    },
+                ))
+            }
+
+            EventType::MessageBufferCreate => {
+                let handle: ObjectHandle = object_handle(&mut r, event_id)?;
+                let buffer_size = r.read_u32()?;
+                let entry = entry_table.set_class(handle, ObjectClass::MessageBuffer);
+                let event = MessageBufferCreateEvent {
+                    event_count,
+                    sequence,
+                    timestamp,
+                    handle,
+                    name: entry.symbol.clone().map(ObjectName::from),
+                    buffer_size,
+                };
+                Some((event_code, Event::MessageBufferCreate(event)))
             }
 
             EventType::MessageBufferSend
@@ -665,9 +1402,11 @@ impl EventParser {
             | EventType::MessageBufferReceiveFromIsr
             | EventType::MessageBufferReset => {
                 let handle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let bytes_in_buffer = r.read_u32()?;
                 let event = MessageBufferEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
@@ -687,8 +1426,10 @@ impl EventParser {
 
             EventType::MessageBufferSendBlock | EventType::MessageBufferReceiveBlock => {
                 let handle = object_handle(&mut r, event_id)?;
+                self.check_object_class(entry_table, handle, event_type)?;
                 let event = MessageBufferBlockEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
                     name: entry_table.symbol(handle).cloned().map(ObjectName::from),
@@ -705,14 +1446,14 @@ impl EventParser {
             EventType::StateMachineCreate => {
                 let handle = object_handle(&mut r, event_id)?;
                 let _unused = r.read_u32()?;
-                let entry = entry_table.entry(handle);
-                entry.set_class(ObjectClass::StateMachine);
-                let sym = entry.symbol.as_ref().ok_or(Error::ObjectLookup(handle))?;
+                let entry = entry_table.set_class(handle, ObjectClass::StateMachine);
+                let sym = self.object_symbol(entry.symbol.as_ref(), handle, event_count)?;
                 let event = StateMachineCreateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    name: sym.clone().into(),
+                    name: sym.into(),
                 };
                 Some((event_code, Event::StateMachineCreate(event)))
             }
@@ -720,22 +1461,24 @@ impl EventParser {
             EventType::StateMachineStateCreate => {
                 let state_handle = object_handle(&mut r, event_id)?;
                 let state_machine_handle = object_handle(&mut r, event_id)?;
-                let entry = entry_table.entry(state_handle);
-                entry.set_class(ObjectClass::StateMachine);
-                let state_machine_sym = entry_table
-                    .entry(state_machine_handle)
-                    .symbol
-                    .as_ref()
-                    .map(|s| ObjectName::from(s.clone()))
-                    .ok_or(Error::ObjectLookup(state_machine_handle))?;
-                let state_sym = entry_table
-                    .entry(state_handle)
-                    .symbol
-                    .as_ref()
-                    .map(|s| ObjectName::from(s.clone()))
-                    .ok_or(Error::ObjectLookup(state_handle))?;
+                entry_table.set_class(state_handle, ObjectClass::StateMachine);
+                let state_machine_sym: ObjectName = self
+                    .object_symbol(
+                        entry_table.entry(state_machine_handle).symbol.as_ref(),
+                        state_machine_handle,
+                        event_count,
+                    )?
+                    .into();
+                let state_sym: ObjectName = self
+                    .object_symbol(
+                        entry_table.entry(state_handle).symbol.as_ref(),
+                        state_handle,
+                        event_count,
+                    )?
+                    .into();
                 let event = StateMachineStateEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle: state_machine_handle,
                     name: state_machine_sym,
@@ -748,20 +1491,23 @@ impl EventParser {
             EventType::StateMachineStateChange => {
                 let state_machine_handle = object_handle(&mut r, event_id)?;
                 let state_handle = object_handle(&mut r, event_id)?;
-                let state_machine_sym = entry_table
-                    .entry(state_machine_handle)
-                    .symbol
-                    .as_ref()
-                    .map(|s| ObjectName::from(s.clone()))
-                    .ok_or(Error::ObjectLookup(state_machine_handle))?;
-                let state_sym = entry_table
-                    .entry(state_handle)
-                    .symbol
-                    .as_ref()
-                    .map(|s| ObjectName::from(s.clone()))
-                    .ok_or(Error::ObjectLookup(state_handle))?;
+                let state_machine_sym: ObjectName = self
+                    .object_symbol(
+                        entry_table.entry(state_machine_handle).symbol.as_ref(),
+                        state_machine_handle,
+                        event_count,
+                    )?
+                    .into();
+                let state_sym: ObjectName = self
+                    .object_symbol(
+                        entry_table.entry(state_handle).symbol.as_ref(),
+                        state_handle,
+                        event_count,
+                    )?
+                    .into();
                 let event = StateMachineStateChangeEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle: state_machine_handle,
                     name: state_machine_sym,
@@ -774,14 +1520,13 @@ impl EventParser {
             EventType::UnusedStack => {
                 let handle = object_handle(&mut r, event_id)?;
                 let low_mark = r.read_u32()?;
-                let sym = entry_table
-                    .symbol(handle)
-                    .ok_or(Error::ObjectLookup(handle))?;
+                let sym = self.object_symbol(entry_table.symbol(handle), handle, event_count)?;
                 let event = UnusedStackEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     handle,
-                    task: sym.clone().into(),
+                    task: sym.into(),
                     low_mark,
                 };
                 Some((event_code, Event::UnusedStack(event)))
@@ -826,7 +1571,7 @@ impl EventParser {
 
                 self.arg_buf.clear();
 
-                let format_string = if is_fixed {
+                let (fmt_string_handle, format_string) = if is_fixed {
                     let fmt_string_handle = object_handle(&mut r, event_id)?;
 
                     let num_arg_bytes = usize::from(arg_count.0) * 4;
@@ -837,10 +1582,10 @@ impl EventParser {
 
                     let res = entry_table
                         .symbol(fmt_string_handle)
-                        .map(|s| TrimmedString::from_str(s))
+                        .map(|s| s.0.clone())
                         .ok_or(Error::FixedUserEventFmtStringLookup(fmt_string_handle));
                     match res {
-                        Ok(fmt_string) => fmt_string,
+                        Ok(fmt_string) => (Some(fmt_string_handle), fmt_string),
                         Err(e) => {
                             // Need to read out the rest of the arg data so the parser can skip over the
                             // invalid data
@@ -848,10 +1593,7 @@ impl EventParser {
                             let remaining_param_words =
                                 num_params.0.saturating_sub(arg_count.0 + 2);
                             if remaining_param_words != 0 {
-                                let mut parameters = [0; EventParameterCount::MAX];
-                                r.read_u32_into(
-                                    &mut parameters[..usize::from(remaining_param_words)],
-                                )?;
+                                r.skip(usize::from(remaining_param_words) * 4)?;
                             }
                             return Err(e);
                         }
@@ -871,21 +1613,28 @@ impl EventParser {
 
                     let num_fmt_str_bytes =
                         (usize::from(num_params) - 1 - not_fmt_str_arg_count) * 4;
-                    self.read_string(&mut r, num_fmt_str_bytes)?
+                    (None, self.read_string(&mut r, num_fmt_str_bytes)?)
                 };
 
-                let (formatted_string, args) = match format_symbol_string(
-                    entry_table,
-                    Protocol::Streaming,
-                    self.endianness.into(),
-                    &format_string,
-                    &self.arg_buf,
-                ) {
+                let (formatted_string, args) = match self
+                    .format_cache
+                    .get(fmt_string_handle, &format_string)
+                    .render(
+                        entry_table,
+                        Protocol::Streaming,
+                        self.endianness.into(),
+                        &self.arg_buf,
+                    ) {
                     Ok((fs, args)) => (fs, args),
                     Err(e) => {
-                        error!("Failed to parse user event format string arguments, using the raw symbol instead. {e}");
+                        let msg = format!("Failed to parse user event format string arguments, using the raw symbol instead. {e}");
+                        error!("{msg}");
+                        self.diagnostics.push(
+                            Diagnostic::new(Severity::Error, msg)
+                                .with_event_count(u16::from(event_count).into()),
+                        );
                         (
-                            FormattedString(format_string.clone().into()),
+                            FormattedString(format_string.to_string()),
                             Default::default(),
                         )
                     }
@@ -893,9 +1642,10 @@ impl EventParser {
 
                 let event = UserEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     channel,
-                    format_string: FormatString(format_string.0),
+                    format_string: FormatString(format_string),
                     formatted_string,
                     args,
                 };
@@ -934,18 +1684,25 @@ impl EventParser {
 
                 let format_string = self.read_string(&mut r, fmt_len.into())?;
 
-                let (formatted_string, args) = match format_symbol_string(
-                    entry_table,
-                    Protocol::Streaming,
-                    self.endianness.into(),
-                    &format_string,
-                    &self.arg_buf,
-                ) {
+                let (formatted_string, args) = match self
+                    .format_cache
+                    .get(None, &format_string)
+                    .render(
+                        entry_table,
+                        Protocol::Streaming,
+                        self.endianness.into(),
+                        &self.arg_buf,
+                    ) {
                     Ok((fs, args)) => (fs, args),
                     Err(e) => {
-                        error!("Failed to parse custom printf event format string arguments, using the raw symbol instead. {e}");
+                        let msg = format!("Failed to parse custom printf event format string arguments, using the raw symbol instead. {e}");
+                        error!("{msg}");
+                        self.diagnostics.push(
+                            Diagnostic::new(Severity::Error, msg)
+                                .with_event_count(u16::from(event_count).into()),
+                        );
                         (
-                            FormattedString(format_string.clone().into()),
+                            FormattedString(format_string.to_string()),
                             Default::default(),
                         )
                     }
@@ -953,9 +1710,10 @@ impl EventParser {
 
                 let event = UserEvent {
                     event_count,
+                    sequence,
                     timestamp,
                     channel,
-                    format_string: FormatString(format_string.0),
+                    format_string: FormatString(format_string),
                     formatted_string,
                     args,
                 };
@@ -964,31 +1722,1240 @@ impl EventParser {
 
             // Return the base event type for everything else
             _ => {
-                let mut parameters = [0; EventParameterCount::MAX];
-                r.read_u32_into(&mut parameters[..usize::from(num_params)])?;
+                let parameters = r.read_parameters(num_params)?;
                 let event = BaseEvent {
                     code: event_code,
                     event_count,
+                    sequence,
                     timestamp,
                     parameters,
                 };
                 Some((event_code, Event::Unknown(event)))
             }
-        })
+        };
+
+        if let Some((_, ev)) = &event {
+            let handle = match ev {
+                Event::TraceStart(ev) => Some(ev.current_task_handle),
+                Event::TaskResume(ev) | Event::TaskActivate(ev) => Some(ev.handle),
+                // Task begin events are returned as unknown events
+                Event::Unknown(ev) => ObjectHandle::new(ev.parameters[0]),
+                _ => None,
+            };
+            self.track_running_task(event_type, handle);
+        }
+
+        self.symbolize_entries(entry_table);
+
+        Ok(event)
+    }
+
+    /// Decode the next event and hand it to `visitor` instead of returning an owned `Event`.
+    /// Returns false at the end of the stream.
+    ///
+    /// See `EventVisitor` for which events avoid constructing an `Event`.
+    pub fn visit_event<R: Read, V: EventVisitor + ?Sized>(
+        &mut self,
+        r: &mut R,
+        entry_table: &mut EntryTable,
+        visitor: &mut V,
+    ) -> Result<bool, Error> {
+        let mut header_bytes = [0_u8; 8];
+        match r.read_exact(&mut header_bytes[..4]) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        match u32::from_le_bytes([
+            header_bytes[0],
+            header_bytes[1],
+            header_bytes[2],
+            header_bytes[3],
+        ]) {
+            HeaderInfo::PSF_LITTLE_ENDIAN => return Err(Error::TraceRestarted(Endianness::Little)),
+            HeaderInfo::PSF_BIG_ENDIAN => return Err(Error::TraceRestarted(Endianness::Big)),
+            _ => (),
+        }
+        r.read_exact(&mut header_bytes[4..])?;
+
+        let mut hr = ByteOrdered::new(header_bytes.as_slice(), self.endianness);
+        let code = EventCode(hr.read_u16()?.into());
+        let event_count = EventCount(hr.read_u16()?);
+        let header = EventHeader {
+            code,
+            event_count,
+            sequence: self.sequence(event_count),
+            timestamp: Timestamp(hr.read_u32()?.into()),
+        };
+        let event_type = header.code.event_type();
+        let event_id = header.code.event_id();
+        let num_params = header.code.parameter_count();
+
+        let is_fast_path = matches!(
+            event_type,
+            EventType::TaskReady
+                | EventType::TaskSwitchTaskResume
+                | EventType::TaskActivate
+                | EventType::TaskSwitchIsrBegin
+                | EventType::TaskSwitchIsrResume
+                | EventType::MemoryAlloc
+                | EventType::MemoryFree
+        ) || event_type.object_class().is_some();
+
+        if !is_fast_path {
+            // Decode everything else with the regular parser, which may read past the parameters
+            // (i.e. custom printf events)
+            let mut chained = header_bytes.as_slice().chain(r);
+            return match self.next_event(&mut chained, entry_table)? {
+                Some((_, event)) => {
+                    visitor.visit_event(&header, &event);
+                    Ok(true)
+                }
+                None => Ok(false),
+            };
+        }
+
+        if let Some(expected_parameter_count) = event_type.expected_parameter_count() {
+            if usize::from(num_params) != expected_parameter_count {
+                return Err(Error::InvalidEventParameterCount(
+                    event_id,
+                    expected_parameter_count,
+                    num_params,
+                ));
+            }
+        } else if num_params.0 < 1 || (event_type == EventType::TaskActivate && num_params.0 > 2) {
+            return Err(Error::InvalidEventParameterCount(event_id, 1, num_params));
+        }
+
+        let params = read_parameters(r, self.endianness, num_params)?;
+        let params = &params[..usize::from(num_params)];
+
+        match event_type {
+            EventType::MemoryAlloc => {
+                self.heap.handle_alloc(params[1]);
+                visitor.visit_memory_alloc(&header, params[0], params[1], &self.heap);
+            }
+            EventType::MemoryFree => {
+                self.heap.handle_free(params[1]);
+                visitor.visit_memory_free(&header, params[0], params[1], &self.heap);
+            }
+            EventType::TaskReady
+            | EventType::TaskSwitchTaskResume
+            | EventType::TaskActivate
+            | EventType::TaskSwitchIsrBegin
+            | EventType::TaskSwitchIsrResume => {
+                let handle =
+                    ObjectHandle::new(params[0]).ok_or(Error::InvalidObjectHandle(event_id))?;
+                if let Some(priority) = params.get(1) {
+                    self.activate_priority(
+                        entry_table,
+                        handle,
+                        Priority(*priority),
+                        header.event_count,
+                        header.timestamp,
+                    );
+                }
+                let entry = entry_table.entry(handle);
+                let priority = entry.states.priority();
+                self.track_running_task(event_type, Some(handle));
+                let name = match entry.symbol.as_ref() {
+                    Some(symbol) => symbol.as_ref(),
+                    None => self.missing_symbol(handle, header.event_count)?,
+                };
+                match event_type {
+                    EventType::TaskReady => {
+                        visitor.visit_task_ready(&header, handle, name, priority)
+                    }
+                    EventType::TaskSwitchTaskResume => {
+                        visitor.visit_task_resume(&header, handle, name, priority)
+                    }
+                    EventType::TaskActivate => {
+                        visitor.visit_task_activate(&header, handle, name, priority)
+                    }
+                    EventType::TaskSwitchIsrBegin => {
+                        visitor.visit_isr_begin(&header, handle, name, priority)
+                    }
+                    _ /*EventType::TaskSwitchIsrResume*/ => {
+                        visitor.visit_isr_resume(&header, handle, name, priority)
+                    }
+                }
+            }
+            _ => {
+                let handle =
+                    ObjectHandle::new(params[0]).ok_or(Error::InvalidObjectHandle(event_id))?;
+                self.check_object_class(entry_table, handle, event_type)?;
+                if event_type.object_class() == Some(ObjectClass::Mutex) {
+                    entry_table.set_class(handle, ObjectClass::Mutex);
+                }
+                let name = entry_table.symbol(handle).map(|s| s.as_ref());
+                visitor.visit_object_operation(&header, handle, name, &params[1..]);
+            }
+        }
+
+        self.symbolize_entries(entry_table);
+
+        Ok(true)
+    }
+
+    /// Set the priority carried by a `TaskActivate` event, recording it in the
+    /// priority history when it differs from the known priority
+    fn activate_priority(
+        &mut self,
+        entry_table: &mut EntryTable,
+        handle: ObjectHandle,
+        priority: Priority,
+        event_count: EventCount,
+        timestamp: Timestamp,
+    ) {
+        let previous = entry_table.entry(handle).states.priority();
+        entry_table.set_priority(handle, priority);
+        if previous != priority {
+            push_history(
+                &mut self.priority_history,
+                handle,
+                PriorityChange {
+                    event_count,
+                    timestamp,
+                    kind: PriorityChangeKind::Activate,
+                    previous,
+                    priority,
+                },
+                self.max_priority_history_len,
+            );
+        }
+    }
+
+    /// Resolve the addresses of the entries inserted by the last event
+    fn symbolize_entries(&mut self, entry_table: &mut EntryTable) {
+        if let Some(symbolizer) = &self.symbolizer {
+            if self.symbolized_all_entries {
+                entry_table.symbolize_inserted(symbolizer);
+            } else {
+                entry_table.symbolize(symbolizer);
+                self.symbolized_all_entries = true;
+            }
+        }
+    }
+
+    /// When enabled, verify that the class of the object referenced by an event
+    /// matches the class the event type operates on
+    fn check_object_class(
+        &self,
+        entry_table: &EntryTable,
+        handle: ObjectHandle,
+        event_type: EventType,
+    ) -> Result<(), Error> {
+        if !self.check_object_classes {
+            return Ok(());
+        }
+        if let (Some(expected), Some(found)) =
+            (event_type.object_class(), entry_table.class(handle))
+        {
+            if expected != found {
+                return Err(Error::ObjectClassMismatch(
+                    EventId::from(event_type),
+                    handle,
+                    expected,
+                    found,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The sequence number of the event with the given count.
+    /// A truncated event is parsed again once the rest of it is available,
+    /// the count of the previous event doesn't advance the sequence.
+    fn sequence(&mut self, event_count: EventCount) -> u64 {
+        let counter = self.event_counter.get_or_insert_with(|| {
+            let mut counter = TrackingEventCounter::zero();
+            counter.set_initial_count(event_count);
+            counter
+        });
+        if counter.count() as u16 != u16::from(event_count) {
+            counter.update(event_count);
+        }
+        counter.count()
+    }
+
+    /// Read, decode and sanitize a string, interned when a pool is set
+    fn read_string<P: ParamReader>(
+        &mut self,
+        r: &mut P,
+        max_len: usize,
+    ) -> Result<Arc<str>, Error> {
+        let bytes = r.read_bytes(max_len, &mut self.buf)?;
+        let s = TrimmedString::from_raw_with(bytes, &self.string_decoder);
+        let s = match &self.string_sanitizer {
+            Some(sanitizer) => sanitizer.sanitize(&s),
+            None => s.0,
+        };
+        Ok(self.intern(s))
+    }
+
+    fn intern(&self, s: String) -> Arc<str> {
+        match &self.intern_pool {
+            Some(pool) => pool.intern(s),
+            None => s.into(),
+        }
+    }
+}
+
+/// Appends `change` to the history of `handle`, dropping the oldest changes past `max_len`
+fn push_history<T>(
+    histories: &mut BTreeMap<ObjectHandle, Vec<T>>,
+    handle: ObjectHandle,
+    change: T,
+    max_len: usize,
+) {
+    if max_len == 0 {
+        return;
+    }
+    let history = histories.entry(handle).or_default();
+    if history.len() >= max_len {
+        history.drain(..=history.len() - max_len);
+    }
+    history.push(change);
+}
+
+fn truncate_histories<T>(histories: &mut BTreeMap<ObjectHandle, Vec<T>>, max_len: usize) {
+    histories.retain(|_, history| {
+        history.drain(..history.len().saturating_sub(max_len));
+        !history.is_empty()
+    });
+}
+
+/// Reads the parameters of an event with a single read of their bytes,
+/// the parameters past `num_params` are zero
+fn read_parameters<R: Read>(
+    r: &mut R,
+    endianness: byteordered::Endianness,
+    num_params: EventParameterCount,
+) -> io::Result<[u32; EventParameterCount::MAX]> {
+    let mut bytes = [0_u8; EventParameterCount::MAX * 4];
+    let bytes = &mut bytes[..usize::from(num_params) * 4];
+    r.read_exact(bytes)?;
+    let mut parameters = [0; EventParameterCount::MAX];
+    for (param, b) in parameters.iter_mut().zip(bytes.chunks_exact(4)) {
+        let b = [b[0], b[1], b[2], b[3]];
+        *param = match endianness {
+            byteordered::Endianness::Little => u32::from_le_bytes(b),
+            byteordered::Endianness::Big => u32::from_be_bytes(b),
+        };
+    }
+    Ok(parameters)
+}
+
+/// Where the parameters of an event are decoded from, see `StreamParams` and `SliceParams`
+trait ParamReader {
+    /// The next `len` bytes, read into `scratch` unless they can be borrowed in place
+    fn read_bytes<'b>(
+        &'b mut self,
+        len: usize,
+        scratch: &'b mut Vec<u8>,
+    ) -> Result<&'b [u8], Error>;
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error>;
+
+    fn read_u16(&mut self) -> Result<u16, Error>;
+
+    fn read_u32(&mut self) -> Result<u32, Error>;
+
+    /// See `read_parameters`
+    fn read_parameters(
+        &mut self,
+        num_params: EventParameterCount,
+    ) -> Result<[u32; EventParameterCount::MAX], Error>;
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        let mut scratch = Vec::new();
+        self.read_bytes(len, &mut scratch)?;
+        Ok(())
+    }
+}
+
+impl<P: ParamReader + ?Sized> ParamReader for &mut P {
+    fn read_bytes<'b>(
+        &'b mut self,
+        len: usize,
+        scratch: &'b mut Vec<u8>,
+    ) -> Result<&'b [u8], Error> {
+        (**self).read_bytes(len, scratch)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        (**self).read_exact(buf)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        (**self).read_u16()
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        (**self).read_u32()
+    }
+
+    fn read_parameters(
+        &mut self,
+        num_params: EventParameterCount,
+    ) -> Result<[u32; EventParameterCount::MAX], Error> {
+        (**self).read_parameters(num_params)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        (**self).skip(len)
+    }
+}
+
+/// Parameters read from a stream, running out of data is an `io::ErrorKind::UnexpectedEof`
+/// error like the rest of the stream reading
+struct StreamParams<R> {
+    r: R,
+    endianness: byteordered::Endianness,
+}
+
+impl<R: Read> ParamReader for StreamParams<R> {
+    fn read_bytes<'b>(
+        &'b mut self,
+        len: usize,
+        scratch: &'b mut Vec<u8>,
+    ) -> Result<&'b [u8], Error> {
+        scratch.clear();
+        scratch.resize(len, 0);
+        self.r.read_exact(scratch)?;
+        Ok(scratch)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        Ok(self.r.read_exact(buf)?)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        Ok(ByteOrdered::new(&mut self.r, self.endianness).read_u16()?)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(ByteOrdered::new(&mut self.r, self.endianness).read_u32()?)
+    }
+
+    fn read_parameters(
+        &mut self,
+        num_params: EventParameterCount,
+    ) -> Result<[u32; EventParameterCount::MAX], Error> {
+        Ok(read_parameters(&mut self.r, self.endianness, num_params)?)
+    }
+}
+
+/// Parameters borrowed from a slice, running out of data is an `Error::TruncatedEvent`
+struct SliceParams<'a> {
+    buf: &'a [u8],
+    endianness: byteordered::Endianness,
+}
+
+impl<'a> SliceParams<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.buf.len() {
+            return Err(Error::TruncatedEvent(self.buf.len()));
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn u16_from(&self, b: [u8; 2]) -> u16 {
+        match self.endianness {
+            byteordered::Endianness::Little => u16::from_le_bytes(b),
+            byteordered::Endianness::Big => u16::from_be_bytes(b),
+        }
+    }
+
+    fn u32_from(&self, b: [u8; 4]) -> u32 {
+        match self.endianness {
+            byteordered::Endianness::Little => u32::from_le_bytes(b),
+            byteordered::Endianness::Big => u32::from_be_bytes(b),
+        }
+    }
+}
+
+impl ParamReader for SliceParams<'_> {
+    fn read_bytes<'b>(
+        &'b mut self,
+        len: usize,
+        _scratch: &'b mut Vec<u8>,
+    ) -> Result<&'b [u8], Error> {
+        self.take(len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        buf.copy_from_slice(self.take(buf.len())?);
+        Ok(())
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        let b = self.take(2)?;
+        Ok(self.u16_from([b[0], b[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let b = self.take(4)?;
+        Ok(self.u32_from([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn read_parameters(
+        &mut self,
+        num_params: EventParameterCount,
+    ) -> Result<[u32; EventParameterCount::MAX], Error> {
+        let bytes = self.take(usize::from(num_params) * 4)?;
+        let mut parameters = [0; EventParameterCount::MAX];
+        for (param, b) in parameters.iter_mut().zip(bytes.chunks_exact(4)) {
+            *param = self.u32_from([b[0], b[1], b[2], b[3]]);
+        }
+        Ok(parameters)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.take(len).map(|_| ())
+    }
+}
+
+impl EventParser {
+    /// Whether the `ticks_to_wait` of a blocking send or give call is present,
+    /// `base_count` is the parameter count without it
+    fn send_ticks_to_wait_present(
+        &self,
+        event_code: EventCode,
+        num_params: EventParameterCount,
+        base_count: usize,
+    ) -> Result<bool, Error> {
+        if self.send_ticks_to_wait {
+            optional_parameter_present(event_code, num_params, base_count)
+        } else if usize::from(num_params) == base_count {
+            Ok(false)
+        } else {
+            Err(Error::InvalidEventParameterCount(
+                event_code.event_id(),
+                base_count,
+                num_params,
+            ))
+        }
     }
+}
 
-    fn read_string<R: Read>(&mut self, r: &mut R, max_len: usize) -> Result<TrimmedString, Error> {
-        self.buf.clear();
-        self.buf.resize(max_len, 0);
-        r.read_exact(&mut self.buf)?;
-        Ok(TrimmedString::from_raw(&self.buf))
+/// Checks the parameter count of an event that has a single optional parameter,
+/// returning whether the optional parameter is present
+fn optional_parameter_present(
+    event_code: EventCode,
+    num_params: EventParameterCount,
+    base_count: usize,
+) -> Result<bool, Error> {
+    let n = usize::from(num_params);
+    if n == base_count {
+        Ok(false)
+    } else if n == base_count + 1 {
+        Ok(true)
+    } else {
+        Err(Error::InvalidEventParameterCount(
+            event_code.event_id(),
+            base_count,
+            num_params,
+        ))
     }
 }
 
-fn object_handle<T: byteordered::byteorder::ReadBytesExt, E: byteordered::Endian>(
-    r: &mut ByteOrdered<T, E>,
-    event_id: EventId,
-) -> Result<ObjectHandle, Error> {
+fn object_handle<P: ParamReader>(r: &mut P, event_id: EventId) -> Result<ObjectHandle, Error> {
     let oh = r.read_u32()?;
     ObjectHandle::new(oh).ok_or(Error::InvalidObjectHandle(event_id))
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    pub(crate) fn event_bytes(
+        id: u16,
+        event_count: u16,
+        timestamp: u32,
+        params: &[u32],
+    ) -> Vec<u8> {
+        let code = ((params.len() as u16) << 12) | id;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&code.to_le_bytes());
+        bytes.extend_from_slice(&event_count.to_le_bytes());
+        bytes.extend_from_slice(&timestamp.to_le_bytes());
+        for p in params {
+            bytes.extend_from_slice(&p.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub(crate) fn parser() -> EventParser {
+        EventParser::new(Endianness::Little, Heap::default())
+    }
+
+    #[test]
+    fn read_parameters_in_one_read() {
+        /// Counts the calls to `read`
+        struct CountingReader<'a>(&'a [u8], usize);
+
+        impl Read for CountingReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 += 1;
+                self.0.read(buf)
+            }
+        }
+
+        let bytes = [0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xFF];
+        let mut r = CountingReader(&bytes, 0);
+        let le = read_parameters(
+            &mut r,
+            byteordered::Endianness::Little,
+            EventParameterCount(2),
+        )
+        .unwrap();
+        assert_eq!(&le[..2], &[1, 0x0200_0000]);
+        assert!(le[2..].iter().all(|p| *p == 0));
+        assert_eq!(r.1, 1);
+        assert_eq!(r.0, &[0xFF]);
+
+        let be = read_parameters(
+            &mut bytes.as_slice(),
+            byteordered::Endianness::Big,
+            EventParameterCount(2),
+        )
+        .unwrap();
+        assert_eq!(&be[..3], &[0x0100_0000, 2, 0]);
+
+        let none = read_parameters(
+            &mut bytes.as_slice(),
+            byteordered::Endianness::Little,
+            EventParameterCount(0),
+        )
+        .unwrap();
+        assert_eq!(none, [0; EventParameterCount::MAX]);
+
+        let err = read_parameters(
+            &mut &bytes[..7],
+            byteordered::Endianness::Little,
+            EventParameterCount(2),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn next_event_from_slice() {
+        let h = ObjectHandle::new(0x2000_1000).unwrap();
+        let name = u32::from_le_bytes(*b"rx\0\0");
+        let mut bytes = event_bytes(0x03, 1, 10, &[h.into(), name]);
+        let first_len = bytes.len();
+        bytes.extend(event_bytes(0x30, 2, 20, &[h.into()]));
+
+        let mut stream_table = EntryTable::default();
+        let mut stream_parser = parser();
+        let mut r = bytes.as_slice();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+        let mut buf = bytes.as_slice();
+        for _ in 0..2 {
+            let expected = stream_parser.next_event(&mut r, &mut stream_table).unwrap();
+            let (consumed, event) = p.next_event_from_slice(buf, &mut entry_table).unwrap();
+            assert_eq!(event, expected);
+            buf = &buf[consumed..];
+        }
+        assert_eq!(
+            p.next_event_from_slice(buf, &mut entry_table).unwrap(),
+            (0, None)
+        );
+        assert_eq!(entry_table, stream_table);
+
+        for len in 1..first_len {
+            let err = parser()
+                .next_event_from_slice(&bytes[..len], &mut EntryTable::default())
+                .unwrap_err();
+            assert!(matches!(err, Error::TruncatedEvent(l) if l == len), "{err}");
+        }
+
+        let restart = HeaderInfo::PSF_LITTLE_ENDIAN.to_le_bytes();
+        let err = p
+            .next_event_from_slice(&restart, &mut entry_table)
+            .unwrap_err();
+        assert!(matches!(err, Error::TraceRestarted(Endianness::Little)));
+    }
+
+    #[test]
+    fn object_delete_removes_entry() {
+        let handle = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let entry = entry_table.entry(handle);
+        entry.set_symbol(SymbolString("q".into()));
+        entry.set_class(ObjectClass::Queue);
+
+        let mut p = parser();
+        let bytes = event_bytes(0x21, 10, 20, &[handle.into(), 3]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ev,
+            Event::ObjectDelete(ObjectDeleteEvent {
+                event_count: EventCount(10),
+                sequence: 10,
+                timestamp: Timestamp(20),
+                handle,
+                class: ObjectClass::Queue,
+                name: Some(ObjectName::from(SymbolString("q".into()))),
+                state: Some(3),
+            })
+        );
+        assert!(entry_table.symbol(handle).is_none());
+        assert!(entry_table.class(handle).is_none());
+    }
+
+    #[test]
+    fn bad_format_string_diagnostic() {
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        // A "%d" format string without any argument data
+        let fmt = u32::from_le_bytes(*b"%d\0\0");
+        let bytes = event_bytes(0x90, 7, 8, &[1, fmt]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(ev, Event::User(_)));
+
+        let diagnostics = p.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].event_count, Some(7));
+        assert!(p.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn object_lookup_policy() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        // Task created before its name was captured
+        let bytes = event_bytes(0x10, 1, 10, &[task.into(), 2]);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::ObjectLookup(h)) if h == task
+        ));
+
+        p.set_object_lookup_policy(ObjectLookupPolicy::Substitute);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskCreate(ev) = ev else {
+            panic!("Expected a TaskCreate event, got {ev:?}");
+        };
+        assert_eq!(ev.name.as_ref(), UNNAMED_OBJECT);
+        assert_eq!(ev.priority, Priority(2));
+        let diagnostics = p.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].event_count, Some(1));
+    }
+
+    #[test]
+    fn object_renames_and_shared_names() {
+        let h1 = ObjectHandle::new(0x2000_1000).unwrap();
+        let h2 = ObjectHandle::new(0x2000_2000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(h1, ObjectClass::Queue);
+        entry_table.set_class(h2, ObjectClass::Queue);
+        let mut p = parser();
+        let name = |s: &[u8; 4]| u32::from_le_bytes(*s);
+
+        for (count, handle, sym) in [(1, h1, b"rx\0\0"), (2, h1, b"rx\0\0"), (3, h1, b"tx\0\0")] {
+            let bytes = event_bytes(0x03, count, 10, &[handle.into(), name(sym)]);
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap();
+        }
+        let history = p.name_history(h1);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].event_count, EventCount(3));
+        assert_eq!(history[0].previous, SymbolString("rx".into()));
+        assert_eq!(history[0].name, SymbolString("tx".into()));
+        assert_eq!(p.take_diagnostics().len(), 1);
+
+        let bytes = event_bytes(0x03, 4, 10, &[h2.into(), name(b"tx\0\0")]);
+        p.next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        assert!(p.name_history(h2).is_empty());
+        let diagnostics = p.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].event_count, Some(4));
+    }
+
+    #[test]
+    fn names_interned_in_the_shared_pool() {
+        let h = ObjectHandle::new(0x2000_1000).unwrap();
+        let pool = InternPool::new();
+        let bytes = event_bytes(0x03, 1, 10, &[h.into(), u32::from_le_bytes(*b"rx\0\0")]);
+
+        let names = (0..2)
+            .map(|_| {
+                let mut entry_table = EntryTable::default();
+                let mut p = parser();
+                p.set_intern_pool(pool.clone());
+                let name = match p.next_event(&mut bytes.as_slice(), &mut entry_table) {
+                    Ok(Some((_, Event::ObjectName(ev)))) => ev.name,
+                    ev => panic!("unexpected event {ev:?}"),
+                };
+                assert!(Arc::ptr_eq(&name.0, &entry_table.symbol(h).unwrap().0));
+                name
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names[0].as_ref(), "rx");
+        assert!(Arc::ptr_eq(&names[0].0, &names[1].0));
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn name_history_is_capped() {
+        let h = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(h, ObjectClass::Queue);
+        let mut p = parser();
+        p.set_max_name_history_len(2);
+        let name = |s: &[u8; 4]| u32::from_le_bytes(*s);
+
+        for (count, sym) in [
+            (1, b"a\0\0\0"),
+            (2, b"b\0\0\0"),
+            (3, b"c\0\0\0"),
+            (4, b"d\0\0\0"),
+        ] {
+            let bytes = event_bytes(0x03, count, 10, &[h.into(), name(sym)]);
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap();
+        }
+        let counts: Vec<_> = p.name_history(h).iter().map(|c| c.event_count).collect();
+        assert_eq!(counts, vec![EventCount(3), EventCount(4)]);
+
+        p.set_max_name_history_len(1);
+        assert_eq!(p.name_history(h).len(), 1);
+        assert_eq!(p.name_history(h)[0].name, SymbolString("d".into()));
+
+        p.set_max_name_history_len(0);
+        assert!(p.renamed_entries().is_empty());
+    }
+
+    #[test]
+    fn task_priority_history() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(task, ObjectClass::Task);
+        entry_table.entry(task).set_symbol(SymbolString("t".into()));
+        entry_table.entry(task).states.set_priority(Priority(2));
+        let mut p = parser();
+
+        for (id, count, ts, prio) in [(0x04, 1, 10, 3), (0x05, 2, 20, 5), (0x06, 3, 30, 3)] {
+            let bytes = event_bytes(id, count, ts, &[task.into(), prio]);
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap();
+        }
+        let history = p.priority_history(task);
+        let changes: Vec<_> = history
+            .iter()
+            .map(|c| (c.kind, c.timestamp, c.previous.0, c.priority.0))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (PriorityChangeKind::Set, Timestamp(10), 2, 3),
+                (PriorityChangeKind::Inherit, Timestamp(20), 3, 5),
+                (PriorityChangeKind::Disinherit, Timestamp(30), 5, 3),
+            ]
+        );
+        assert_eq!(p.priority_changes().len(), 1);
+    }
+
+    #[test]
+    fn task_activate_priority_history() {
+        struct Noop;
+        impl EventVisitor for Noop {}
+
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let events = [
+            event_bytes(0x37, 1, 10, &[task.into(), 2]),
+            event_bytes(0x37, 2, 20, &[task.into(), 2]),
+            event_bytes(0x37, 3, 30, &[task.into()]),
+            event_bytes(0x37, 4, 40, &[task.into(), 4]),
+        ];
+        let expected = vec![
+            (PriorityChangeKind::Activate, Timestamp(10), 0, 2),
+            (PriorityChangeKind::Activate, Timestamp(40), 2, 4),
+        ];
+
+        for visit in [false, true] {
+            let mut entry_table = EntryTable::default();
+            entry_table.entry(task).set_symbol(SymbolString("t".into()));
+            let mut p = parser();
+            for bytes in events.iter() {
+                if visit {
+                    assert!(p
+                        .visit_event(&mut bytes.as_slice(), &mut entry_table, &mut Noop)
+                        .unwrap());
+                } else {
+                    p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                        .unwrap()
+                        .unwrap();
+                }
+            }
+            let changes: Vec<_> = p
+                .priority_history(task)
+                .iter()
+                .map(|c| (c.kind, c.timestamp, c.previous.0, c.priority.0))
+                .collect();
+            assert_eq!(changes, expected);
+        }
+    }
+
+    #[test]
+    fn priority_history_is_capped() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(task, ObjectClass::Task);
+        entry_table.entry(task).set_symbol(SymbolString("t".into()));
+        let mut p = parser();
+        p.set_max_priority_history_len(2);
+
+        for (count, prio) in [(1, 3), (2, 4), (3, 5), (4, 6)] {
+            let bytes = event_bytes(0x04, count, 10, &[task.into(), prio]);
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap();
+        }
+        let priorities: Vec<_> = p
+            .priority_history(task)
+            .iter()
+            .map(|c| c.priority.0)
+            .collect();
+        assert_eq!(priorities, vec![5, 6]);
+
+        p.set_max_priority_history_len(0);
+        assert!(p.priority_changes().is_empty());
+        let bytes = event_bytes(0x04, 5, 10, &[task.into(), 7]);
+        p.next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        assert!(p.priority_history(task).is_empty());
+    }
+
+    #[test]
+    fn memory_events_carry_the_running_task() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let isr = ObjectHandle::new(0x2000_2000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table
+            .entry(task)
+            .set_symbol(SymbolString("worker".into()));
+        entry_table
+            .entry(isr)
+            .set_symbol(SymbolString("irq".into()));
+        let mut p = parser();
+        let mut next = |id, count, params: &[u32]| {
+            let bytes = event_bytes(id, count, 10, params);
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap()
+                .1
+        };
+
+        let Event::MemoryAlloc(ev) = next(0x38, 1, &[0x2000_8000, 16]) else {
+            panic!("Expected a MemoryAlloc event");
+        };
+        assert_eq!(ev.task, None);
+
+        next(0x37, 2, &[task.into()]);
+        let Event::MemoryAlloc(ev) = next(0x38, 3, &[0x2000_8000, 16]) else {
+            panic!("Expected a MemoryAlloc event");
+        };
+        assert_eq!(ev.task, Some(task));
+        assert_eq!(ev.task_name.as_ref().map(|n| n.as_ref()), Some("worker"));
+
+        next(0x33, 4, &[isr.into()]);
+        let Event::MemoryFree(ev) = next(0x39, 5, &[0x2000_8000, 16]) else {
+            panic!("Expected a MemoryFree event");
+        };
+        assert_eq!(ev.task, None);
+        assert_eq!(ev.task_name, None);
+    }
+
+    #[test]
+    fn visitor_tracks_the_running_task_like_next_event() {
+        struct Noop;
+        impl EventVisitor for Noop {}
+
+        let task0 = ObjectHandle::new(0x2000_1000).unwrap();
+        let task1 = ObjectHandle::new(0x2000_1100).unwrap();
+        let isr = ObjectHandle::new(0x2000_2000).unwrap();
+        let mut entry_table = EntryTable::default();
+        for (handle, name) in [(task0, "t0"), (task1, "t1"), (isr, "irq")] {
+            entry_table
+                .entry(handle)
+                .set_symbol(SymbolString(name.into()));
+        }
+        let events = [
+            event_bytes(0x35, 1, 10, &[task0.into()]),
+            event_bytes(0x38, 2, 11, &[0x2000_8000, 16]),
+            event_bytes(0x33, 3, 12, &[isr.into()]),
+            event_bytes(0x39, 4, 13, &[0x2000_8000, 16]),
+            event_bytes(0x36, 5, 14, &[task1.into()]),
+            event_bytes(0x37, 6, 15, &[task0.into(), 2]),
+        ];
+
+        let mut p = parser();
+        let mut owned = Vec::new();
+        for bytes in events.iter() {
+            p.next_event(&mut bytes.as_slice(), &mut entry_table)
+                .unwrap()
+                .unwrap();
+            owned.push(p.running_task());
+        }
+        let mut p = parser();
+        let mut visited = Vec::new();
+        for bytes in events.iter() {
+            assert!(p
+                .visit_event(&mut bytes.as_slice(), &mut entry_table, &mut Noop)
+                .unwrap());
+            visited.push(p.running_task());
+        }
+        assert_eq!(
+            owned,
+            [
+                Some(task0),
+                Some(task0),
+                None,
+                None,
+                Some(task1),
+                Some(task0)
+            ]
+        );
+        assert_eq!(visited, owned);
+    }
+
+    #[test]
+    fn object_class_mismatch() {
+        let handle = ObjectHandle::new(0x2000_4000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(handle, ObjectClass::Mutex);
+        let mut p = parser();
+
+        // Disabled by default
+        let bytes = event_bytes(0x50, 1, 2, &[handle.into(), 1]);
+        assert!(p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .is_ok());
+
+        p.set_object_class_checking(true);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::ObjectClassMismatch(
+                _,
+                _,
+                ObjectClass::Queue,
+                ObjectClass::Mutex
+            ))
+        ));
+    }
+
+    #[test]
+    fn task_notify_index_and_value() {
+        let handle = ObjectHandle::new(0x2000_2000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        let bytes = event_bytes(0xC9, 1, 2, &[handle.into()]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskNotify(ev) = ev else {
+            panic!("Expected TaskNotify, got {ev}");
+        };
+        assert_eq!(ev.index, None);
+        assert_eq!(ev.value, None);
+
+        let bytes = event_bytes(0xCD, 2, 3, &[handle.into(), 1, 0xAB]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskNotifyFromIsr(ev) = ev else {
+            panic!("Expected TaskNotifyFromIsr, got {ev}");
+        };
+        assert_eq!(ev.index, Some(1));
+        assert_eq!(ev.value, Some(0xAB));
+
+        let bytes = event_bytes(0xCA, 3, 4, &[handle.into(), 100, 2]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskNotifyWait(ev) = ev else {
+            panic!("Expected TaskNotifyWait, got {ev}");
+        };
+        assert_eq!(ev.ticks_to_wait, Some(Ticks(100)));
+        assert_eq!(ev.index, Some(2));
+        assert_eq!(ev.value, None);
+    }
+
+    #[test]
+    fn sequence_across_rollovers() {
+        let handle = ObjectHandle::new(0x2000_2000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        // The event with count 0 is parsed twice, like a truncated event is
+        let sequences: Vec<_> = [0xFFFE, 0xFFFF, 0, 0, 1]
+            .into_iter()
+            .map(|count| {
+                let bytes = event_bytes(0xC9, count, 2, &[handle.into()]);
+                let (_, ev) = p
+                    .next_event(&mut bytes.as_slice(), &mut entry_table)
+                    .unwrap()
+                    .unwrap();
+                ev.sequence()
+            })
+            .collect();
+        assert_eq!(sequences, [0xFFFE, 0xFFFF, 0x1_0000, 0x1_0000, 0x1_0001]);
+    }
+
+    #[test]
+    fn optional_ticks_to_wait() {
+        let handle = ObjectHandle::new(0x2000_3000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        let bytes = event_bytes(0x56, 1, 2, &[handle.into(), 4]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::QueueSendBlock(ev) = ev else {
+            panic!("Expected QueueSendBlock, got {ev}");
+        };
+        assert_eq!(ev.ticks_to_wait, None);
+        assert_eq!(ev.messages_waiting, 4);
+
+        let bytes = event_bytes(0x56, 2, 3, &[handle.into(), 10, 4]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::QueueSendBlock(ev) = ev else {
+            panic!("Expected QueueSendBlock, got {ev}");
+        };
+        assert_eq!(ev.ticks_to_wait, Some(Ticks(10)));
+        assert_eq!(ev.messages_waiting, 4);
+
+        let bytes = event_bytes(0x56, 3, 4, &[handle.into(), 10, 4, 0]);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::InvalidEventParameterCount(_, 2, _))
+        ));
+    }
+
+    #[test]
+    fn task_core_affinity_and_migration() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_symbol(task, SymbolString("t".into()));
+        let mut p = parser();
+
+        for event_type in [EventType::TaskCoreAffinitySet, EventType::TaskMigrate] {
+            assert_eq!(EventType::from(EventId::from(event_type)), event_type);
+        }
+
+        let bytes = event_bytes(0xE9, 1, 10, &[task.into(), 0b10]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskCoreAffinitySet(ev) = ev else {
+            panic!("Expected TaskCoreAffinitySet, got {ev}");
+        };
+        assert_eq!(ev.handle, task);
+        assert_eq!(ev.name.to_string(), "t");
+        assert_eq!(ev.affinity_mask, 0b10);
+
+        let bytes = event_bytes(0xEA, 2, 20, &[task.into(), 0, 1]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::TaskMigrate(ev) = ev else {
+            panic!("Expected TaskMigrate, got {ev}");
+        };
+        assert_eq!(ev.handle, task);
+        assert_eq!((ev.from_core, ev.to_core), (0, 1));
+        assert_eq!(ev.to_string(), "[20]:'t':0->1");
+
+        let bytes = event_bytes(0xEA, 3, 30, &[task.into(), 0]);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::InvalidEventParameterCount(_, 3, _))
+        ));
+    }
+
+    #[test]
+    fn no_ticks_to_wait_from_isr() {
+        let handle = ObjectHandle::new(0x2000_3000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+
+        // QueueSendFromIsr
+        let bytes = event_bytes(0x59, 1, 2, &[handle.into(), 4]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::QueueSendFromIsr(ev) = ev else {
+            panic!("Expected QueueSendFromIsr, got {ev}");
+        };
+        assert_eq!(ev.ticks_to_wait, None);
+        assert_eq!(ev.messages_waiting, 4);
+
+        let bytes = event_bytes(0x59, 2, 3, &[handle.into(), 10, 4]);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::InvalidEventParameterCount(_, 2, _))
+        ));
+    }
+
+    #[test]
+    fn no_send_ticks_to_wait_when_disabled() {
+        let handle = ObjectHandle::new(0x2000_3000).unwrap();
+        let mut entry_table = EntryTable::default();
+        let mut p = parser();
+        p.set_send_ticks_to_wait(false);
+
+        let bytes = event_bytes(0x56, 1, 2, &[handle.into(), 4]);
+        let (_, ev) = p
+            .next_event(&mut bytes.as_slice(), &mut entry_table)
+            .unwrap()
+            .unwrap();
+        let Event::QueueSendBlock(ev) = ev else {
+            panic!("Expected QueueSendBlock, got {ev}");
+        };
+        assert_eq!(ev.ticks_to_wait, None);
+
+        let bytes = event_bytes(0x56, 2, 3, &[handle.into(), 10, 4]);
+        assert!(matches!(
+            p.next_event(&mut bytes.as_slice(), &mut entry_table),
+            Err(Error::InvalidEventParameterCount(_, 2, _))
+        ));
+    }
+}
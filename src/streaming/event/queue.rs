@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}:{queue_length}")]
 pub struct QueueCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -18,6 +19,7 @@ pub struct QueueCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}:{messages_waiting}")]
 pub struct QueueEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
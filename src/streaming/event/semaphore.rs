@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}")]
 pub struct SemaphoreCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -18,6 +19,7 @@ pub struct SemaphoreCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}:{count}")]
 pub struct SemaphoreEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -0,0 +1,19 @@
+use crate::streaming::event::EventCount;
+use crate::time::Timestamp;
+use derive_more::Display;
+
+/// A pseudo-event marking the start of a new session after the trace restarted,
+/// see `RecorderData::read_event_auto_restart`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{session}")]
+pub struct SessionBoundaryEvent {
+    /// Those of the new session's first event, which follows the boundary.
+    /// Zero when the stream ends before it, the event counter, sequence and
+    /// timestamps start over with the new session.
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    /// Number of the new session, the first session is 0
+    pub session: u32,
+}
@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}:{name}")]
 pub struct StateMachineCreateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -17,6 +18,7 @@ pub struct StateMachineCreateEvent {
 #[display(fmt = "[{timestamp}]:{handle}:{name}:{state}")]
 pub struct StateMachineStateEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
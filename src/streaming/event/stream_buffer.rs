@@ -0,0 +1,47 @@
+use crate::streaming::event::EventCount;
+use crate::time::Timestamp;
+use crate::types::{ObjectHandle, StreamBufferName};
+use derive_more::Display;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{handle}:{buffer_size}")]
+pub struct StreamBufferCreateEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: Option<StreamBufferName>,
+    pub buffer_size: u32,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{handle}:{bytes_in_buffer}")]
+pub struct StreamBufferEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: Option<StreamBufferName>,
+    pub bytes_in_buffer: u32,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{handle}")]
+pub struct StreamBufferBlockEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: Option<StreamBufferName>,
+}
+
+pub type StreamBufferSendEvent = StreamBufferEvent;
+pub type StreamBufferSendBlockEvent = StreamBufferBlockEvent;
+pub type StreamBufferSendFromIsrEvent = StreamBufferEvent;
+pub type StreamBufferReceiveEvent = StreamBufferEvent;
+pub type StreamBufferReceiveBlockEvent = StreamBufferBlockEvent;
+pub type StreamBufferReceiveFromIsrEvent = StreamBufferEvent;
+pub type StreamBufferResetEvent = StreamBufferEvent;
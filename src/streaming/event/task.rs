@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:'{name}':{priority}")]
 pub struct TaskEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub handle: ObjectHandle,
@@ -22,3 +23,31 @@ pub type TaskPriorityDisinheritEvent = TaskEvent;
 pub type TaskBeginEvent = TaskEvent;
 pub type TaskResumeEvent = TaskEvent;
 pub type TaskActivateEvent = TaskEvent;
+
+/// The core affinity of a task was set, see `EventType::TaskCoreAffinitySet`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:'{name}':{affinity_mask:#X}")]
+pub struct TaskCoreAffinityEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: TaskName,
+    /// Bit N set when the task may run on core N
+    pub affinity_mask: u32,
+}
+
+/// A task moved to another core, see `EventType::TaskMigrate`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:'{name}':{from_core}->{to_core}")]
+pub struct TaskMigrateEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: TaskName,
+    pub from_core: u32,
+    pub to_core: u32,
+}
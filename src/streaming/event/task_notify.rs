@@ -7,6 +7,7 @@ use derive_more::Display;
 #[display(fmt = "[{timestamp}]:{handle}")]
 pub struct TaskNotifyEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     /// Task-to-notify for TaskNotify and TaskNotifyFromIsr
@@ -14,6 +15,10 @@ pub struct TaskNotifyEvent {
     pub handle: ObjectHandle,
     pub task_name: Option<TaskName>,
     pub ticks_to_wait: Option<Ticks>,
+    /// Notification array index, present in newer kernels/trace versions (`uxIndexToNotify`, `uxIndexToWait`)
+    pub index: Option<u32>,
+    /// Notification value, present for TaskNotify and TaskNotifyFromIsr in newer trace versions
+    pub value: Option<u32>,
 }
 
 pub type TaskNotifyFromIsrEvent = TaskNotifyEvent;
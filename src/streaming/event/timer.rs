@@ -0,0 +1,48 @@
+use crate::streaming::event::EventCount;
+use crate::time::{Ticks, Timestamp};
+use crate::types::{ObjectHandle, TimerName};
+use derive_more::Display;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{handle}:{period}")]
+pub struct TimerCreateEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: Option<TimerName>,
+    pub period: Ticks,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{handle}:{value}")]
+pub struct TimerEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    pub handle: ObjectHandle,
+    pub name: Option<TimerName>,
+    /// The optional value of the timer command, the tick count the command was
+    /// issued at for start and reset commands
+    pub value: u32,
+}
+
+/// The recorder identifies the timer by its callback function and ID, not its handle
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{callback:X}:{timer_id:X}")]
+pub struct TimerExpiredEvent {
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+
+    /// Address of the callback function
+    pub callback: u32,
+    /// The timer ID (`pvTimerID`)
+    pub timer_id: u32,
+}
+
+pub type TimerStartEvent = TimerEvent;
+pub type TimerResetEvent = TimerEvent;
+pub type TimerStopEvent = TimerEvent;
@@ -9,6 +9,7 @@ use derive_more::Display;
 )]
 pub struct TsConfigEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub frequency: Frequency,
@@ -1,16 +1,26 @@
 use crate::streaming::event::EventCount;
 use crate::time::Timestamp;
-use crate::types::{Argument, FormatString, FormattedString, UserEventChannel};
+use crate::types::{
+    key_value_fields, Argument, Arguments, FormatString, FormattedString, UserEventChannel,
+};
 use derive_more::Display;
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 #[display(fmt = "[{timestamp}]:[{channel}]='{formatted_string}'")]
 pub struct UserEvent {
     pub event_count: EventCount,
+    pub sequence: u64,
     pub timestamp: Timestamp,
 
     pub channel: UserEventChannel,
     pub format_string: FormatString,
     pub formatted_string: FormattedString,
-    pub args: Vec<Argument>,
+    pub args: Arguments,
+}
+
+impl UserEvent {
+    /// The `key=value` fields of the event, see `key_value_fields`
+    pub fn key_values(&self) -> Vec<(String, Argument)> {
+        key_value_fields(&self.format_string, &self.args)
+    }
 }
@@ -0,0 +1,238 @@
+use crate::streaming::event::{Event, EventCode, EventCount, EventType};
+use crate::time::Timestamp;
+use crate::types::{Heap, ObjectHandle, Priority};
+
+/// The fields common to every event
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EventHeader {
+    pub code: EventCode,
+    pub event_count: EventCount,
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+}
+
+impl EventHeader {
+    pub fn event_type(&self) -> EventType {
+        self.code.event_type()
+    }
+}
+
+/// Consumes events without constructing the owned `Event` type, see `EventParser::visit_event`.
+///
+/// The high-frequency scheduling, memory and object operation events are handed
+/// to the visitor with names borrowed from the entry table. All other events are
+/// decoded as usual and handed to `visit_event`.
+///
+/// All methods default to doing nothing.
+#[allow(unused_variables)]
+pub trait EventVisitor {
+    fn visit_task_ready(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+    }
+
+    fn visit_task_resume(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+    }
+
+    fn visit_task_activate(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+    }
+
+    fn visit_isr_begin(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+    }
+
+    fn visit_isr_resume(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+    }
+
+    fn visit_memory_alloc(&mut self, header: &EventHeader, address: u32, size: u32, heap: &Heap) {}
+
+    fn visit_memory_free(&mut self, header: &EventHeader, address: u32, size: u32, heap: &Heap) {}
+
+    /// Queue, semaphore, mutex, event group, stream buffer and message buffer operations,
+    /// i.e. the event types where `EventType::object_class` is `Some`.
+    /// `params` are the raw parameters following the object handle.
+    fn visit_object_operation(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: Option<&str>,
+        params: &[u32],
+    ) {
+    }
+
+    /// All other events
+    fn visit_event(&mut self, header: &EventHeader, event: &Event) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::parser::test::{event_bytes, parser};
+    use crate::streaming::event::EventParameterCount;
+    use crate::streaming::{EntryTable, Error};
+    use crate::types::Endianness;
+    use crate::types::{ObjectClass, SymbolString};
+
+    /// Records the visits as `(method, event type)` pairs
+    #[derive(Default)]
+    struct Visits(Vec<(&'static str, EventType)>);
+
+    impl EventVisitor for Visits {
+        fn visit_isr_begin(&mut self, header: &EventHeader, _: ObjectHandle, _: &str, _: Priority) {
+            self.0.push(("isr_begin", header.event_type()));
+        }
+
+        fn visit_object_operation(
+            &mut self,
+            header: &EventHeader,
+            _: ObjectHandle,
+            name: Option<&str>,
+            params: &[u32],
+        ) {
+            assert_eq!(name, None);
+            assert_eq!(params, &[7]);
+            self.0.push(("object_operation", header.event_type()));
+        }
+
+        fn visit_event(&mut self, header: &EventHeader, _: &Event) {
+            self.0.push(("event", header.event_type()));
+        }
+    }
+
+    fn visit(
+        bytes: &[u8],
+        entry_table: &mut EntryTable,
+        visits: &mut Visits,
+    ) -> Result<bool, Error> {
+        parser().visit_event(&mut &bytes[..], entry_table, visits)
+    }
+
+    #[test]
+    fn dispatch() {
+        let isr = ObjectHandle::new(0x2000_2000).unwrap();
+        let queue = ObjectHandle::new(0x2000_3000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table
+            .entry(isr)
+            .set_symbol(SymbolString("irq".into()));
+        let mut v = Visits::default();
+
+        assert!(visit(
+            &event_bytes(0x33, 1, 10, &[isr.into()]),
+            &mut entry_table,
+            &mut v
+        )
+        .unwrap());
+        // Unnamed objects are fine for object operations
+        assert!(visit(
+            &event_bytes(0x59, 2, 11, &[queue.into(), 7]),
+            &mut entry_table,
+            &mut v
+        )
+        .unwrap());
+        assert!(visit(&event_bytes(0x7A, 3, 12, &[5]), &mut entry_table, &mut v).unwrap());
+        // Not overridden, defaults to doing nothing
+        assert!(visit(
+            &event_bytes(0x38, 4, 13, &[0x2000_8000, 16]),
+            &mut entry_table,
+            &mut v
+        )
+        .unwrap());
+
+        assert_eq!(
+            v.0,
+            vec![
+                ("isr_begin", EventType::TaskSwitchIsrBegin),
+                ("object_operation", EventType::QueueSendFromIsr),
+                ("event", EventType::TaskDelay),
+            ]
+        );
+        assert_eq!(entry_table.class(queue), None);
+    }
+
+    #[test]
+    fn end_of_stream_and_restart() {
+        let mut entry_table = EntryTable::default();
+        let mut v = Visits::default();
+        assert!(!visit(&[], &mut entry_table, &mut v).unwrap());
+        let psf = crate::streaming::HeaderInfo::PSF_LITTLE_ENDIAN.to_le_bytes();
+        assert!(matches!(
+            visit(&psf, &mut entry_table, &mut v),
+            Err(Error::TraceRestarted(Endianness::Little))
+        ));
+        // A partial first word is the end of the stream, the rest of the header isn't
+        assert!(!visit(&psf[..2], &mut entry_table, &mut v).unwrap());
+        let bytes = event_bytes(0x7A, 1, 10, &[5]);
+        assert!(visit(&bytes[..6], &mut entry_table, &mut v).is_err());
+        assert!(v.0.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let isr = ObjectHandle::new(0x2000_2000).unwrap();
+        let queue = ObjectHandle::new(0x2000_3000).unwrap();
+        let mut entry_table = EntryTable::default();
+        entry_table.set_class(queue, ObjectClass::Semaphore);
+        let mut v = Visits::default();
+
+        // No name for the ISR
+        assert!(matches!(
+            visit(&event_bytes(0x33, 1, 10, &[isr.into()]), &mut entry_table, &mut v),
+            Err(Error::ObjectLookup(h)) if h == isr
+        ));
+        assert!(matches!(
+            visit(&event_bytes(0x33, 1, 10, &[0]), &mut entry_table, &mut v),
+            Err(Error::InvalidObjectHandle(_))
+        ));
+        assert!(matches!(
+            visit(
+                &event_bytes(0x38, 1, 10, &[0x2000_8000]),
+                &mut entry_table,
+                &mut v
+            ),
+            Err(Error::InvalidEventParameterCount(
+                _,
+                2,
+                EventParameterCount(1)
+            ))
+        ));
+        let bytes = event_bytes(0x59, 1, 10, &[queue.into(), 7]);
+        let mut p = parser();
+        p.set_object_class_checking(true);
+        assert!(matches!(
+            p.visit_event(&mut bytes.as_slice(), &mut entry_table, &mut v),
+            Err(Error::ObjectClassMismatch(..))
+        ));
+        // Parameters missing from the input
+        let bytes = event_bytes(0x38, 1, 10, &[0x2000_8000, 16]);
+        assert!(visit(&bytes[..12], &mut entry_table, &mut v).is_err());
+        assert!(v.0.is_empty());
+    }
+}
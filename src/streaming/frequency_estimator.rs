@@ -0,0 +1,122 @@
+//! Timer frequency estimation for streams without a usable TsConfig.
+//!
+//! When the recorder includes OS tick events (`TRC_CFG_INCLUDE_OSTICK_EVENTS`),
+//! each one carries the new OS tick count, so with a known OS tick rate the
+//! timer frequency follows from the timer ticks elapsed between them.
+//! The result is only an estimate, it's off by the jitter of the tick
+//! interrupt and is never substituted for the recorded frequency implicitly.
+
+use crate::streaming::event::{Event, EventId};
+use crate::time::Frequency;
+use crate::time::StreamingClock;
+
+/// ID of the OS tick event (`PSF_EVENT_NEW_TIME`)
+pub const OS_TICK_EVENT_ID: u16 = 0x31;
+/// ID of the OS tick event recorded while the scheduler is suspended
+pub const OS_TICK_SCHEDULER_SUSPENDED_EVENT_ID: u16 = 0x32;
+
+/// A timer frequency estimated by a `FrequencyEstimator`, not read from the recorder
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EstimatedFrequency {
+    pub frequency: Frequency,
+    /// Number of OS ticks the estimate spans, more ticks give a better estimate
+    pub os_ticks: u32,
+}
+
+/// Estimates the timer frequency from the OS tick events of a stream
+#[derive(Clone, Debug)]
+pub struct FrequencyEstimator {
+    os_tick_rate: Frequency,
+    clock: StreamingClock,
+    /// Timer ticks and OS tick count of the first and latest OS tick events
+    first: Option<(u64, u32)>,
+    latest: Option<(u64, u32)>,
+}
+
+impl FrequencyEstimator {
+    /// `os_tick_rate` is the kernel's tick rate, i.e. `configTICK_RATE_HZ`
+    pub fn new(os_tick_rate: Frequency) -> Self {
+        Self {
+            os_tick_rate,
+            clock: StreamingClock::default(),
+            first: None,
+            latest: None,
+        }
+    }
+
+    /// Process every event of the stream, in order
+    pub fn process(&mut self, event: &Event) {
+        let t = self.clock.time(event.timestamp()).ticks();
+        let Event::Unknown(ev) = event else {
+            return;
+        };
+        let id = ev.code.event_id();
+        if id != EventId(OS_TICK_EVENT_ID) && id != EventId(OS_TICK_SCHEDULER_SUSPENDED_EVENT_ID) {
+            return;
+        }
+        let Some(os_tick_count) = ev.parameters().first().copied() else {
+            return;
+        };
+        let sample = (t, os_tick_count);
+        if self.first.is_none() {
+            self.first = Some(sample);
+        }
+        self.latest = Some(sample);
+    }
+
+    /// The estimated frequency, `None` until at least two OS ticks apart were
+    /// seen or when the OS tick rate is unknown
+    pub fn estimate(&self) -> Option<EstimatedFrequency> {
+        let ((t0, tick0), (t1, tick1)) = (self.first?, self.latest?);
+        let os_ticks = tick1.wrapping_sub(tick0);
+        if os_ticks == 0 || self.os_tick_rate.is_unitless() {
+            return None;
+        }
+        let frequency =
+            u128::from(t1 - t0) * u128::from(self.os_tick_rate.get_raw()) / u128::from(os_ticks);
+        Some(EstimatedFrequency {
+            frequency: Frequency(u32::try_from(frequency).ok()?),
+            os_ticks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCode, EventCount};
+    use crate::time::Timestamp;
+
+    fn os_tick(event_count: u16, timestamp: u64, os_tick_count: u32) -> Event {
+        let mut parameters = [0; 15];
+        parameters[0] = os_tick_count;
+        Event::Unknown(BaseEvent {
+            code: EventCode((0x1000 | OS_TICK_EVENT_ID).into()),
+            event_count: EventCount(event_count),
+            sequence: event_count.into(),
+            timestamp: Timestamp(timestamp),
+            parameters,
+        })
+    }
+
+    #[test]
+    fn estimates_from_os_ticks() {
+        let mut e = FrequencyEstimator::new(Frequency(1000));
+        e.process(&os_tick(0, 0xFFFF_F000, 10));
+        assert_eq!(e.estimate(), None);
+        // The timer wraps around
+        e.process(&os_tick(1, 0xFFFF_F000 + 72_000 - 0x1_0000_0000, 11));
+        e.process(&os_tick(
+            2,
+            0xFFFF_F000 + 3 * 72_000 + 10 - 0x1_0000_0000,
+            13,
+        ));
+        assert_eq!(
+            e.estimate(),
+            Some(EstimatedFrequency {
+                frequency: Frequency(72_003_333),
+                os_ticks: 3,
+            })
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::streaming::event::{DroppedEvent, Event, EventCode, EventCount, TrackingEventCounter};
+
+/// An iterator adapter that yields an `Event::Dropped` pseudo-event before each
+/// event following a gap in the event counter, see `EventIteratorExt::mark_gaps`
+#[derive(Clone, Debug)]
+pub struct MarkGaps<I> {
+    iter: I,
+    counter: Option<TrackingEventCounter>,
+    /// The event following a gap, yielded after its pseudo-event
+    pending: Option<(EventCode, Event)>,
+}
+
+impl<I> MarkGaps<I> {
+    pub(crate) fn new(iter: I) -> Self {
+        Self {
+            iter,
+            counter: None,
+            pending: None,
+        }
+    }
+}
+
+impl<I> Iterator for MarkGaps<I>
+where
+    I: Iterator<Item = (EventCode, Event)>,
+{
+    type Item = (EventCode, Event);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.pending.take() {
+            return Some(item);
+        }
+        let (code, event) = self.iter.next()?;
+        match event {
+            Event::Dropped(_) | Event::Annotation(_) => return Some((code, event)),
+            // The event counter starts over with the new session
+            Event::SessionBoundary(_) => {
+                self.counter = None;
+                return Some((code, event));
+            }
+            _ => (),
+        }
+
+        let event_count = event.event_count();
+        let Some(counter) = self.counter.as_mut() else {
+            let mut counter = TrackingEventCounter::zero();
+            counter.set_initial_count(event_count);
+            self.counter = Some(counter);
+            return Some((code, event));
+        };
+        let first_dropped = EventCount((counter.count() as u16).wrapping_add(1));
+        match counter.update(event_count) {
+            Some(count) if count != 0 => {
+                let dropped = DroppedEvent {
+                    event_count: first_dropped,
+                    sequence: event.sequence().saturating_sub(count),
+                    around_timestamp: event.timestamp(),
+                    count,
+                };
+                self.pending = Some((code, event));
+                Some((EventCode::DROPPED, Event::Dropped(dropped)))
+            }
+            _ => Some((code, event)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{test_event, EventParameterCount, EventType};
+    use crate::streaming::EventIteratorExt;
+    use crate::time::Timestamp;
+
+    #[test]
+    fn marks_gaps() {
+        let events = [
+            test_event(0xFFFE, 1),
+            test_event(0xFFFF, 2),
+            // Events 0 and 1 were dropped
+            test_event(2, 5),
+            test_event(3, 6),
+        ];
+        let marked: Vec<_> = events.into_iter().mark_gaps().collect();
+        assert_eq!(marked.len(), 5);
+        assert_eq!(marked[2].0, EventCode::DROPPED);
+        assert!(marked[2].0.is_pseudo());
+        assert_eq!(marked[2].0.event_type(), EventType::Dropped);
+        assert_eq!(marked[2].0.parameter_count(), EventParameterCount(0));
+        assert_eq!(
+            marked[2].1,
+            Event::Dropped(DroppedEvent {
+                event_count: EventCount(0),
+                sequence: 0,
+                around_timestamp: Timestamp(5),
+                count: 2,
+            })
+        );
+        assert_eq!(marked[3], test_event(2, 5));
+
+        let mut iter = marked.into_iter().stats();
+        iter.by_ref().for_each(drop);
+        let stats = iter.into_stats();
+        assert_eq!(stats.events, 4);
+        assert_eq!(stats.dropped_events, 2);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::streaming::event::Event;
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+
+/// Where an event is in the stream
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EventPosition {
+    /// Zero based index of the event among the events indexed
+    pub index: u64,
+    /// Byte offset of the event, relative to the first event like `Diagnostic::offset`
+    pub offset: u64,
+}
+
+/// Maps object handles to the positions of the events referencing them,
+/// see `RecorderData::enable_handle_index`.
+///
+/// Events are indexed by the object they refer to, see `Event::object_handle`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct HandleIndex {
+    events: u64,
+    positions: BTreeMap<ObjectHandle, Vec<EventPosition>>,
+}
+
+impl HandleIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index the next event, found at `offset`
+    pub fn record(&mut self, offset: u64, event: &Event) {
+        self.record_handle(offset, event.object_handle())
+    }
+
+    /// Like `record`, given the event's `Event::object_handle`
+    pub(crate) fn record_handle(&mut self, offset: u64, handle: Option<ObjectHandle>) {
+        let position = EventPosition {
+            index: self.events,
+            offset,
+        };
+        self.events += 1;
+        if let Some(handle) = handle {
+            self.positions.entry(handle).or_default().push(position);
+        }
+    }
+
+    /// The positions of the events referencing `handle`, in stream order
+    pub fn positions(&self, handle: ObjectHandle) -> &[EventPosition] {
+        self.positions
+            .get(&handle)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// The handles referenced by at least one event
+    pub fn handles(&self) -> impl Iterator<Item = ObjectHandle> + '_ {
+        self.positions.keys().copied()
+    }
+
+    /// Number of events indexed, including those not referencing any object
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::test_event;
+
+    #[test]
+    fn events_without_a_handle_are_counted() {
+        let h1 = ObjectHandle::new(1).unwrap();
+        let h2 = ObjectHandle::new(2).unwrap();
+        let mut index = HandleIndex::new();
+        assert_eq!(index.positions(h1), &[]);
+
+        index.record(0, &test_event(1, 0).1);
+        index.record_handle(8, Some(h2));
+        index.record_handle(16, None);
+        index.record_handle(24, Some(h2));
+
+        assert_eq!(index.events(), 4);
+        assert_eq!(index.handles().collect::<Vec<_>>(), vec![h2]);
+        assert_eq!(index.positions(h1), &[]);
+        assert_eq!(
+            index.positions(h2),
+            &[
+                EventPosition {
+                    index: 1,
+                    offset: 8
+                },
+                EventPosition {
+                    index: 3,
+                    offset: 24
+                },
+            ]
+        );
+    }
+}
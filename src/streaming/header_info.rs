@@ -1,11 +1,10 @@
-use crate::streaming::Error;
+use crate::streaming::{codec, Error};
 use crate::types::{
-    Endianness, KernelPortIdentity, KernelVersion, PlatformCfgVersion, TrimmedString,
+    Endianness, KernelPortIdentity, KernelVersion, PlatformCfgVersion, StringDecoder, TrimmedString,
 };
 use byteordered::ByteOrdered;
 use std::collections::VecDeque;
 use std::io::Read;
-use tracing::{debug, warn};
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct HeaderInfo {
@@ -26,6 +25,33 @@ impl HeaderInfo {
     pub const PSF_LITTLE_ENDIAN: u32 = 0x50_53_46_00;
     pub const PSF_BIG_ENDIAN: u32 = 0x00_46_53_50;
 
+    /// Warnings about the kernel port and format version not being officially supported
+    pub(crate) fn support_warnings(
+        kernel_port: KernelPortIdentity,
+        format_version: u16,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if kernel_port != KernelPortIdentity::FreeRtos {
+            warnings.push(format!(
+                "Kernel port {kernel_port} is not officially supported"
+            ));
+        }
+        if format_version != 10 && !(12..=14).contains(&format_version) {
+            warnings.push(format!(
+                "Version {format_version} is not officially supported"
+            ));
+        }
+        warnings
+    }
+
+    /// Whether the blocking send and give calls may carry a `ticks_to_wait` parameter.
+    /// The FreeRTOS port doesn't record it up to format v14, only the receive, take and
+    /// peek calls do, so the optional parameter is only accepted for the other
+    /// kernel ports and format versions.
+    pub fn send_ticks_to_wait(&self) -> bool {
+        !(self.kernel_port == KernelPortIdentity::FreeRtos && self.format_version <= 14)
+    }
+
     pub fn read_psf_word<R: Read>(r: &mut R) -> Result<Endianness, Error> {
         let mut r = ByteOrdered::le(r);
         let mut psf = [0; 4];
@@ -39,6 +65,13 @@ impl HeaderInfo {
     }
 
     pub fn find<R: Read>(r: &mut R) -> Result<Self, Error> {
+        Self::find_with_string_decoder(r, &StringDecoder::default())
+    }
+
+    pub(crate) fn find_with_string_decoder<R: Read>(
+        r: &mut R,
+        string_decoder: &StringDecoder,
+    ) -> Result<Self, Error> {
         debug!("Searching for PSF word");
         let mut offset = 0;
         let mut r = ByteOrdered::native(r);
@@ -49,7 +82,11 @@ impl HeaderInfo {
             match Self::read_psf_word(&mut psf_buf.clone()) {
                 Ok(endianness) => {
                     debug!(%endianness, offset, "Found PSF word");
-                    return Self::read_with_endianness(endianness, &mut r.into_inner());
+                    return Self::read_with_endianness_and_string_decoder(
+                        endianness,
+                        &mut r.into_inner(),
+                        string_decoder,
+                    );
                 }
                 Err(Error::PSFEndiannessIdentifier(_)) => {
                     offset += 1;
@@ -66,12 +103,27 @@ impl HeaderInfo {
     }
 
     pub fn read<R: Read>(r: &mut R) -> Result<Self, Error> {
+        Self::read_with_string_decoder(r, &StringDecoder::default())
+    }
+
+    pub(crate) fn read_with_string_decoder<R: Read>(
+        r: &mut R,
+        string_decoder: &StringDecoder,
+    ) -> Result<Self, Error> {
         let endianness = Self::read_psf_word(r)?;
-        Self::read_with_endianness(endianness, r)
+        Self::read_with_endianness_and_string_decoder(endianness, r, string_decoder)
     }
 
     /// Assumes the PSF word (u32) has already been read from the input
     pub fn read_with_endianness<R: Read>(endianness: Endianness, r: &mut R) -> Result<Self, Error> {
+        Self::read_with_endianness_and_string_decoder(endianness, r, &StringDecoder::default())
+    }
+
+    pub(crate) fn read_with_endianness_and_string_decoder<R: Read>(
+        endianness: Endianness,
+        r: &mut R,
+        string_decoder: &StringDecoder,
+    ) -> Result<Self, Error> {
         // The remaining fields after PSF word are endian-aware
         let mut r = ByteOrdered::new(r, byteordered::Endianness::from(endianness));
 
@@ -84,11 +136,8 @@ impl HeaderInfo {
             .map_err(|e| Error::KernelVersion(e.0))?;
         debug!(kernel_version = %kernel_version, kernel_port = %kernel_port, endianness = ?endianness, "Found kernel version");
 
-        if kernel_port != KernelPortIdentity::FreeRtos {
-            warn!("Kernel port {kernel_port} is not officially supported");
-        }
-        if format_version != 10 && !(12..=14).contains(&format_version) {
-            warn!("Version {format_version} is not officially supported");
+        for msg in Self::support_warnings(kernel_port, format_version) {
+            warn!("{msg}");
         }
 
         // Everything after platform is version specific
@@ -98,32 +147,10 @@ impl HeaderInfo {
         let num_cores = r.read_u32()? & 0xFF;
         let isr_tail_chaining_threshold = r.read_u32()?;
 
-        let platform_cfg_version_patch;
-        let platform_cfg_version_minor;
-        let platform_cfg_version_major;
-        let mut platform_cfg_bytes: [u8; 8] = [0; 8];
-
-        if format_version == 10 || format_version == 12 {
-            r.read_exact(&mut platform_cfg_bytes)?;
-
-            platform_cfg_version_patch = r.read_u16()?;
-            platform_cfg_version_minor = r.read_u8()?;
-            platform_cfg_version_major = r.read_u8()?;
-        } else {
-            // v13+
-            platform_cfg_version_patch = r.read_u16()?;
-            platform_cfg_version_minor = r.read_u8()?;
-            platform_cfg_version_major = r.read_u8()?;
-
-            r.read_exact(&mut platform_cfg_bytes)?;
-        }
-
-        let platform_cfg_version = PlatformCfgVersion {
-            major: platform_cfg_version_major,
-            minor: platform_cfg_version_minor,
-            patch: platform_cfg_version_patch,
-        };
-        let platform_cfg = TrimmedString::from_raw(&platform_cfg_bytes).into();
+        let cfg = codec::for_version(format_version)
+            .read_platform_cfg(&mut r.into_inner(), endianness)?;
+        let platform_cfg_version = cfg.version;
+        let platform_cfg = TrimmedString::from_raw_with(&cfg.name, string_decoder).into();
 
         Ok(Self {
             endianness,
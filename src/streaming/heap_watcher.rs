@@ -0,0 +1,167 @@
+use crate::streaming::event::{Event, EventCount};
+use crate::time::Timestamp;
+use crate::types::Heap;
+use derive_more::Display;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum HeapAlertKind {
+    /// `Heap::current` rose above the threshold
+    #[display(fmt = "CurrentAboveThreshold({_0})")]
+    CurrentAboveThreshold(u32),
+    /// `Heap::high_water_mark` reached the percentage of `Heap::max`
+    #[display(fmt = "HighWaterMarkAbovePercentage({_0}%)")]
+    HighWaterMarkAbovePercentage(u8),
+}
+
+/// Raised by a `HeapWatcher` when a memory event crosses one of its limits
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{kind}:{}/{}", "heap.current", "heap.max")]
+pub struct HeapAlert {
+    pub event_count: EventCount,
+    pub timestamp: Timestamp,
+    pub kind: HeapAlertKind,
+    /// The heap state after the event
+    pub heap: Heap,
+}
+
+/// Watches the system heap state carried by memory events.
+///
+/// The current-usage alert fires when `Heap::current` rises above the threshold
+/// and is re-armed once usage drops back to or below it.
+/// The high water mark only ever increases, so its alert fires at most once.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct HeapWatcher {
+    current_threshold: Option<u32>,
+    high_water_mark_percentage: Option<u8>,
+    above_current_threshold: bool,
+    high_water_mark_alerted: bool,
+    num_alerts: u64,
+}
+
+impl HeapWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Alert when `Heap::current` exceeds `threshold` bytes
+    pub fn with_current_threshold(mut self, threshold: u32) -> Self {
+        self.current_threshold = Some(threshold);
+        self
+    }
+
+    /// Alert when `Heap::high_water_mark` reaches `percentage` (clamped to 100) of `Heap::max`
+    pub fn with_high_water_mark_percentage(mut self, percentage: u8) -> Self {
+        self.high_water_mark_percentage = Some(percentage.min(100));
+        self
+    }
+
+    /// Number of alerts raised so far
+    pub fn num_alerts(&self) -> u64 {
+        self.num_alerts
+    }
+
+    /// Process the next event, returning any alerts raised by it
+    pub fn process(&mut self, event: &Event) -> Vec<HeapAlert> {
+        match event {
+            Event::MemoryAlloc(ev) | Event::MemoryFree(ev) => {
+                self.check(ev.event_count, ev.timestamp, ev.heap)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Like `process`, but hands each alert to `f`
+    pub fn process_with<F: FnMut(HeapAlert)>(&mut self, event: &Event, f: F) {
+        self.process(event).into_iter().for_each(f)
+    }
+
+    fn check(
+        &mut self,
+        event_count: EventCount,
+        timestamp: Timestamp,
+        heap: Heap,
+    ) -> Vec<HeapAlert> {
+        let mut alerts = Vec::new();
+        let mut alert = |kind| {
+            alerts.push(HeapAlert {
+                event_count,
+                timestamp,
+                kind,
+                heap,
+            })
+        };
+
+        if let Some(threshold) = self.current_threshold {
+            if heap.current > threshold {
+                if !self.above_current_threshold {
+                    self.above_current_threshold = true;
+                    alert(HeapAlertKind::CurrentAboveThreshold(threshold));
+                }
+            } else {
+                self.above_current_threshold = false;
+            }
+        }
+
+        if let Some(percentage) = self.high_water_mark_percentage {
+            // A zero max means the heap size isn't known
+            let limit = u64::from(heap.max) * u64::from(percentage);
+            if !self.high_water_mark_alerted
+                && heap.max != 0
+                && u64::from(heap.high_water_mark) * 100 >= limit
+            {
+                self.high_water_mark_alerted = true;
+                alert(HeapAlertKind::HighWaterMarkAbovePercentage(percentage));
+            }
+        }
+
+        self.num_alerts += alerts.len() as u64;
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::MemoryEvent;
+
+    fn alloc(current: u32, high_water_mark: u32) -> Event {
+        Event::MemoryAlloc(MemoryEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            address: 0x2000_0000,
+            size: 0,
+            heap: Heap {
+                current,
+                high_water_mark,
+                max: 1000,
+            },
+            symbolized: None,
+            task: None,
+            task_name: None,
+        })
+    }
+
+    #[test]
+    fn threshold_rearms_and_high_water_mark_fires_once() {
+        let mut w = HeapWatcher::new()
+            .with_current_threshold(500)
+            .with_high_water_mark_percentage(80);
+        assert!(w.process(&alloc(400, 400)).is_empty());
+        let alerts = w.process(&alloc(600, 600));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, HeapAlertKind::CurrentAboveThreshold(500));
+        assert!(w.process(&alloc(700, 700)).is_empty());
+        assert!(w.process(&alloc(300, 700)).is_empty());
+        let kinds: Vec<_> = w.process(&alloc(850, 850)).iter().map(|a| a.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                HeapAlertKind::CurrentAboveThreshold(500),
+                HeapAlertKind::HighWaterMarkAbovePercentage(80)
+            ]
+        );
+        assert!(w.process(&alloc(100, 900)).is_empty());
+        assert_eq!(w.num_alerts(), 3);
+    }
+}
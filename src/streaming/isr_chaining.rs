@@ -0,0 +1,158 @@
+use crate::streaming::event::{Event, EventCount, EventType};
+use crate::time::{Ticks, Timestamp};
+use crate::types::ObjectHandle;
+use derive_more::Display;
+
+/// An ISR that began (or resumed) within the ISR tail-chaining threshold
+/// of the previous ISR returning.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(fmt = "[{timestamp}]:{previous_isr}->{isr}:{gap}")]
+pub struct IsrTailChain {
+    pub event_count: EventCount,
+    pub timestamp: Timestamp,
+
+    /// The ISR that returned
+    pub previous_isr: ObjectHandle,
+    /// The ISR that began or resumed
+    pub isr: ObjectHandle,
+    /// Time between the previous ISR returning and this ISR starting
+    pub gap: Ticks,
+}
+
+/// Detects tail-chained ISR transitions in a stream of events.
+///
+/// The threshold is taken from the `TsConfig` event (`TRC_CFG_ISR_TAILCHAINING_THRESHOLD`)
+/// unless explicitly provided.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct IsrTailChainDetector {
+    threshold: Option<Ticks>,
+    threshold_is_fixed: bool,
+    active_isr: Option<ObjectHandle>,
+    last_isr_exit: Option<(Timestamp, ObjectHandle)>,
+    num_tail_chains: u64,
+}
+
+impl IsrTailChainDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given threshold instead of the one reported in the `TsConfig` event
+    pub fn with_threshold(threshold: Ticks) -> Self {
+        Self {
+            threshold: Some(threshold),
+            threshold_is_fixed: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn threshold(&self) -> Option<Ticks> {
+        self.threshold
+    }
+
+    /// Number of tail-chained transitions detected so far
+    pub fn num_tail_chains(&self) -> u64 {
+        self.num_tail_chains
+    }
+
+    /// Process the next event, returning a tail-chain if the event is an ISR
+    /// starting within the threshold of the previous ISR returning.
+    pub fn process(&mut self, event: &Event) -> Option<IsrTailChain> {
+        match event {
+            Event::TsConfig(ev) if !self.threshold_is_fixed => {
+                self.threshold = Some(Ticks(ev.isr_chaining_threshold));
+                None
+            }
+            Event::IsrBegin(ev) | Event::IsrResume(ev) => {
+                let chain = self.check_chained(ev.event_count, ev.timestamp, ev.handle);
+                self.active_isr = Some(ev.handle);
+                self.last_isr_exit = None;
+                chain
+            }
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.isr_exit(ev.timestamp);
+                None
+            }
+            // The streaming parser doesn't type task switch begin events
+            Event::Unknown(ev) if ev.code.event_type() == EventType::TaskSwitchTaskBegin => {
+                self.isr_exit(ev.timestamp);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn isr_exit(&mut self, timestamp: Timestamp) {
+        if let Some(isr) = self.active_isr.take() {
+            self.last_isr_exit = Some((timestamp, isr));
+        }
+    }
+
+    fn check_chained(
+        &mut self,
+        event_count: EventCount,
+        timestamp: Timestamp,
+        isr: ObjectHandle,
+    ) -> Option<IsrTailChain> {
+        let threshold = self.threshold?;
+        let (exit_timestamp, previous_isr) = self.last_isr_exit?;
+        // Streaming timestamps are 32-bit and can rollover
+        let gap = Ticks((timestamp.ticks() as u32).wrapping_sub(exit_timestamp.ticks() as u32));
+        if gap <= threshold {
+            self.num_tail_chains += 1;
+            Some(IsrTailChain {
+                event_count,
+                timestamp,
+                previous_isr,
+                isr,
+                gap,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{IsrEvent, TaskEvent};
+    use crate::types::{ObjectName, Priority};
+
+    fn isr_begin(handle: u32, ts: u64) -> Event {
+        Event::IsrBegin(IsrEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(ts),
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName("isr".into()),
+            priority: Priority(1),
+            symbolized: None,
+        })
+    }
+
+    fn task_resume(ts: u64) -> Event {
+        Event::TaskResume(TaskEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(ts),
+            handle: ObjectHandle::NO_TASK,
+            name: ObjectName("task".into()),
+            priority: Priority(1),
+        })
+    }
+
+    #[test]
+    fn tail_chain_within_threshold() {
+        let mut d = IsrTailChainDetector::with_threshold(Ticks(5));
+        assert_eq!(d.process(&isr_begin(10, 100)), None);
+        assert_eq!(d.process(&task_resume(110)), None);
+        let chain = d.process(&isr_begin(11, 114)).unwrap();
+        assert_eq!(chain.previous_isr, ObjectHandle::new(10).unwrap());
+        assert_eq!(chain.isr, ObjectHandle::new(11).unwrap());
+        assert_eq!(chain.gap, Ticks(4));
+        assert_eq!(d.process(&task_resume(120)), None);
+        assert_eq!(d.process(&isr_begin(10, 200)), None);
+        assert_eq!(d.num_tail_chains(), 1);
+    }
+}
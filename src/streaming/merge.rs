@@ -0,0 +1,196 @@
+use crate::streaming::event::{Event, EventCode, EventCount, TrackingEventCounter};
+use crate::time::Timestamp;
+use std::iter::Peekable;
+
+/// An iterator adapter merging two captures of the same device that overlap
+/// in time, see `EventIteratorExt::merge_capture`.
+///
+/// The events are merged in timestamp order, an event present in both captures
+/// (same event count and timestamp) is only yielded once. The timestamps are
+/// compared with wrapping arithmetic, so the captures may span timer rollovers
+/// as long as they're less than 2^31 ticks apart.
+///
+/// The sequence of the merged events is renumbered from their event counts, the
+/// sequences of the two captures started at different events and would collide.
+/// It's strictly increasing within a session, an event whose count the captures
+/// disagree on follows the previous one.
+///
+/// Pseudo-events aren't de-duplicated, a gap in one capture may well be filled
+/// by the other one, so mark the gaps after merging. They take the sequence of
+/// the event before them, like `EventIteratorExt::annotate` does.
+#[derive(Clone, Debug)]
+pub struct MergeCapture<A, B>
+where
+    A: Iterator<Item = (EventCode, Event)>,
+    B: Iterator<Item = (EventCode, Event)>,
+{
+    a: Peekable<A>,
+    b: Peekable<B>,
+    /// Timestamp of the last event yielded
+    timestamp: Option<Timestamp>,
+    /// Counts of the events yielded at `timestamp`, duplicates share the timestamp
+    counts: Vec<EventCount>,
+    duplicates: u64,
+    /// Extends the event counts of the merged events, reset at session boundaries
+    counter: Option<TrackingEventCounter>,
+    /// Sequence of the last event yielded
+    sequence: u64,
+}
+
+impl<A, B> MergeCapture<A, B>
+where
+    A: Iterator<Item = (EventCode, Event)>,
+    B: Iterator<Item = (EventCode, Event)>,
+{
+    pub(crate) fn new(a: A, b: B) -> Self {
+        Self {
+            a: a.peekable(),
+            b: b.peekable(),
+            timestamp: None,
+            counts: Vec::new(),
+            duplicates: 0,
+            counter: None,
+            sequence: 0,
+        }
+    }
+
+    /// Number of duplicate events skipped so far
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// The sequence of the next merged event, see the type docs
+    fn next_sequence(&mut self, event_count: EventCount) -> u64 {
+        let sequence = match &mut self.counter {
+            Some(counter) => {
+                if counter.count() as u16 != u16::from(event_count) {
+                    counter.update(event_count);
+                }
+                counter.count().max(self.sequence + 1)
+            }
+            None => {
+                let mut counter = TrackingEventCounter::zero();
+                counter.set_initial_count(event_count);
+                let sequence = counter.count();
+                self.counter = Some(counter);
+                sequence
+            }
+        };
+        self.sequence = sequence;
+        sequence
+    }
+}
+
+impl<A, B> Iterator for MergeCapture<A, B>
+where
+    A: Iterator<Item = (EventCode, Event)>,
+    B: Iterator<Item = (EventCode, Event)>,
+{
+    type Item = (EventCode, Event);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let take_a = match (self.a.peek(), self.b.peek()) {
+                (Some((_, a)), Some((_, b))) => !is_before(b, a),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let (code, mut event) = if take_a {
+                self.a.next()?
+            } else {
+                self.b.next()?
+            };
+            if matches!(
+                event,
+                Event::Dropped(_) | Event::Annotation(_) | Event::SessionBoundary(_)
+            ) {
+                if matches!(event, Event::SessionBoundary(_)) {
+                    self.counter = None;
+                    self.sequence = 0;
+                }
+                *event.sequence_mut() = self.sequence;
+                return Some((code, event));
+            }
+
+            let timestamp = event.timestamp();
+            let event_count = event.event_count();
+            if self.timestamp != Some(timestamp) {
+                self.timestamp = Some(timestamp);
+                self.counts.clear();
+            } else if self.counts.contains(&event_count) {
+                self.duplicates += 1;
+                continue;
+            }
+            self.counts.push(event_count);
+            *event.sequence_mut() = self.next_sequence(event_count);
+            return Some((code, event));
+        }
+    }
+}
+
+/// Whether `a` occurred before `b`, by timestamp then event count
+fn is_before(a: &Event, b: &Event) -> bool {
+    let dt = (b.timestamp().ticks() as u32).wrapping_sub(a.timestamp().ticks() as u32) as i32;
+    if dt != 0 {
+        return dt > 0;
+    }
+    let dc = u16::from(b.event_count()).wrapping_sub(u16::from(a.event_count())) as i16;
+    dc > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::test_event;
+    use crate::streaming::EventIteratorExt;
+
+    #[test]
+    fn overlapping_captures() {
+        let max = u64::from(u32::MAX);
+        // The host capture restarted, the second capture overlaps the first
+        // one's last two events and fills in a gap, across a timer rollover
+        let first = vec![
+            test_event(1, max - 30),
+            test_event(2, max - 20),
+            test_event(4, max - 10),
+            test_event(5, 2),
+        ];
+        let second = vec![
+            test_event(3, max - 15),
+            test_event(4, max - 10),
+            test_event(5, 2),
+            test_event(6, 2),
+            test_event(7, 20),
+        ];
+        // The second capture started later, its sequence started at its first event
+        let second = second.into_iter().map(|(code, mut ev)| {
+            *ev.sequence_mut() -= 3;
+            (code, ev)
+        });
+        let mut merged = first.into_iter().merge_capture(second);
+        let (counts, sequences): (Vec<_>, Vec<_>) = merged
+            .by_ref()
+            .map(|(_, ev)| (u16::from(ev.event_count()), ev.sequence()))
+            .unzip();
+        assert_eq!(counts, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(sequences, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(merged.duplicates(), 2);
+
+        // Captures disagreeing on an event count still get increasing sequences
+        let sequences: Vec<_> = vec![test_event(1, 10), test_event(2, 20)]
+            .into_iter()
+            .merge_capture(vec![test_event(2, 30), test_event(3, 40)])
+            .map(|(_, ev)| ev.sequence())
+            .collect();
+        assert_eq!(sequences, [1, 2, 3, 4]);
+
+        let gaps = vec![test_event(1, 10), test_event(4, 40)]
+            .into_iter()
+            .merge_capture(vec![test_event(2, 20), test_event(3, 30)])
+            .mark_gaps()
+            .filter(|(_, ev)| matches!(ev, Event::Dropped(_)))
+            .count();
+        assert_eq!(gaps, 0);
+    }
+}
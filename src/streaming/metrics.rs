@@ -0,0 +1,297 @@
+//! Live parse metrics served for Prometheus to scrape, see `LiveMetrics`
+
+use crate::streaming::event::{Event, TrackingEventCounter};
+use crate::streaming::RateMonitor;
+use crate::time::StreamingClock;
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+use prometheus::{
+    Encoder, Gauge, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MetricsError {
+    #[error("Failed to register the metrics")]
+    Prometheus(#[from] prometheus::Error),
+
+    #[error("Failed to start the metrics HTTP server: {0}")]
+    Http(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// Metrics of a live capture, updated with each event read.
+///
+/// All the metrics are prefixed with `trc_`, per-task metrics are labeled
+/// with the task name.
+pub struct LiveMetrics {
+    registry: Registry,
+    events: IntCounter,
+    bytes: IntCounter,
+    dropped_events: IntCounter,
+    events_per_sec: Gauge,
+    dropped_events_per_sec: Gauge,
+    heap_current: IntGauge,
+    heap_high_water_mark: IntGauge,
+    heap_max: IntGauge,
+    task_cpu_ticks: IntCounterVec,
+    rate_monitor: RateMonitor,
+    counter: Option<TrackingEventCounter>,
+    clock: StreamingClock,
+    task_names: BTreeMap<ObjectHandle, String>,
+    running_task: Option<(ObjectHandle, Timestamp)>,
+}
+
+impl LiveMetrics {
+    pub fn new() -> Result<Self, MetricsError> {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Register the metrics with an existing registry, i.e. one that
+    /// adds a constant label identifying the device
+    pub fn with_registry(registry: Registry) -> Result<Self, MetricsError> {
+        let events = IntCounter::new("trc_events_total", "Number of events read")?;
+        let bytes = IntCounter::new("trc_bytes_total", "Number of event bytes read")?;
+        let dropped_events = IntCounter::new(
+            "trc_dropped_events_total",
+            "Number of events dropped by the recorder",
+        )?;
+        let events_per_sec = Gauge::new(
+            "trc_events_per_second",
+            "Event rate over the trailing rate window",
+        )?;
+        let dropped_events_per_sec = Gauge::new(
+            "trc_dropped_events_per_second",
+            "Dropped event rate over the trailing rate window",
+        )?;
+        let heap_current = IntGauge::new("trc_heap_current_bytes", "Current system heap usage")?;
+        let heap_high_water_mark = IntGauge::new(
+            "trc_heap_high_water_mark_bytes",
+            "System heap high water mark",
+        )?;
+        let heap_max = IntGauge::new("trc_heap_max_bytes", "System heap size")?;
+        let task_cpu_ticks = IntCounterVec::new(
+            Opts::new(
+                "trc_task_cpu_ticks_total",
+                "Timer ticks spent running each task",
+            ),
+            &["task"],
+        )?;
+
+        registry.register(Box::new(events.clone()))?;
+        registry.register(Box::new(bytes.clone()))?;
+        registry.register(Box::new(dropped_events.clone()))?;
+        registry.register(Box::new(events_per_sec.clone()))?;
+        registry.register(Box::new(dropped_events_per_sec.clone()))?;
+        registry.register(Box::new(heap_current.clone()))?;
+        registry.register(Box::new(heap_high_water_mark.clone()))?;
+        registry.register(Box::new(heap_max.clone()))?;
+        registry.register(Box::new(task_cpu_ticks.clone()))?;
+
+        Ok(Self {
+            registry,
+            events,
+            bytes,
+            dropped_events,
+            events_per_sec,
+            dropped_events_per_sec,
+            heap_current,
+            heap_high_water_mark,
+            heap_max,
+            task_cpu_ticks,
+            rate_monitor: RateMonitor::default(),
+            counter: None,
+            clock: StreamingClock::default(),
+            task_names: BTreeMap::new(),
+            running_task: None,
+        })
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Update the metrics with an event that took `num_bytes` to read,
+    /// see `RateMonitor::record`
+    pub fn update(&mut self, event: &Event, num_bytes: u64) {
+        let event_count = event.event_count();
+        self.events.inc();
+        self.bytes.inc_by(num_bytes);
+        match self.counter.as_mut() {
+            Some(counter) => {
+                if let Some(dropped) = counter.update(event_count) {
+                    self.dropped_events.inc_by(dropped);
+                }
+            }
+            None => {
+                let mut counter = TrackingEventCounter::zero();
+                counter.set_initial_count(event_count);
+                self.counter = Some(counter);
+            }
+        }
+        self.rate_monitor.record(event_count, num_bytes);
+        let rates = self.rate_monitor.rates();
+        self.events_per_sec.set(rates.events_per_sec);
+        self.dropped_events_per_sec
+            .set(rates.dropped_events_per_sec);
+
+        let t = self.clock.time(event.timestamp());
+        match event {
+            Event::MemoryAlloc(ev) | Event::MemoryFree(ev) => {
+                self.heap_current.set(ev.heap.current.into());
+                self.heap_high_water_mark
+                    .set(ev.heap.high_water_mark.into());
+                self.heap_max.set(ev.heap.max.into());
+            }
+            Event::TraceStart(ev) => {
+                self.task_names
+                    .insert(ev.current_task_handle, ev.current_task.to_string());
+                self.switch(Some(ev.current_task_handle), t);
+            }
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.task_names.insert(ev.handle, ev.name.to_string());
+                self.switch(Some(ev.handle), t);
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.switch(None, t),
+            _ => (),
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn encode(&self) -> String {
+        encode(&self.registry)
+    }
+
+    /// Serve the metrics on `http://<addr>/metrics` from a background thread
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> Result<MetricsServer, MetricsError> {
+        MetricsServer::start(addr, self.registry.clone())
+    }
+
+    fn switch(&mut self, task: Option<ObjectHandle>, t: Timestamp) {
+        if let Some((prev, since)) = self.running_task.take() {
+            if let Some(name) = self.task_names.get(&prev) {
+                self.task_cpu_ticks
+                    .with_label_values(&[name])
+                    .inc_by(t.ticks().saturating_sub(since.ticks()));
+            }
+        }
+        self.running_task = task.map(|h| (h, t));
+    }
+}
+
+/// HTTP server thread started by `LiveMetrics::serve`, stopped when dropped
+pub struct MetricsServer {
+    server: Arc<tiny_http::Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    fn start<A: ToSocketAddrs>(addr: A, registry: Registry) -> Result<Self, MetricsError> {
+        let server = Arc::new(tiny_http::Server::http(addr).map_err(MetricsError::Http)?);
+        let thread_server = server.clone();
+        let thread = thread::Builder::new()
+            .name("trc-metrics".to_owned())
+            .spawn(move || {
+                for req in thread_server.incoming_requests() {
+                    let response = if req.url() == "/metrics" {
+                        let content_type = tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            TextEncoder::new().format_type().as_bytes(),
+                        )
+                        .expect("Invalid header");
+                        tiny_http::Response::from_string(encode(&registry))
+                            .with_header(content_type)
+                    } else {
+                        tiny_http::Response::from_string("Not found").with_status_code(404)
+                    };
+                    if let Err(e) = req.respond(response) {
+                        debug!("Failed to respond to a metrics request: {e}");
+                    }
+                }
+            })
+            .map_err(|e| MetricsError::Http(Box::new(e)))?;
+        Ok(Self {
+            server,
+            thread: Some(thread),
+        })
+    }
+
+    /// The address the server is listening on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn encode(registry: &Registry) -> String {
+    let mut buf = Vec::new();
+    // Encoding the text format only fails on invalid metric families, which
+    // the registry doesn't produce
+    let _ = TextEncoder::new().encode(&registry.gather(), &mut buf);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, IsrEvent, TaskEvent};
+    use crate::types::{ObjectName, Priority};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn task(count: u16, t: u64, handle: u32, name: &str) -> Event {
+        Event::TaskActivate(TaskEvent {
+            event_count: EventCount(count),
+            sequence: count.into(),
+            timestamp: Timestamp(t),
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName(name.into()),
+            priority: Priority(1),
+        })
+    }
+
+    #[test]
+    fn task_cpu_and_scrape() {
+        let mut m = LiveMetrics::new().unwrap();
+        m.update(&task(1, 100, 1, "a"), 12);
+        m.update(&task(2, 150, 2, "b"), 12);
+        m.update(
+            &Event::IsrBegin(IsrEvent {
+                event_count: EventCount(5),
+                sequence: 5,
+                timestamp: Timestamp(190),
+                handle: ObjectHandle::new(3).unwrap(),
+                name: ObjectName("isr".into()),
+                priority: Priority(2),
+                symbolized: None,
+            }),
+            12,
+        );
+        let text = m.encode();
+        assert!(text.contains("trc_events_total 3"));
+        assert!(text.contains("trc_bytes_total 36"));
+        assert!(text.contains("trc_dropped_events_total 2"));
+        assert!(text.contains("trc_task_cpu_ticks_total{task=\"a\"} 50"));
+        assert!(text.contains("trc_task_cpu_ticks_total{task=\"b\"} 40"));
+
+        let server = m.serve("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.0\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.0 200"));
+        assert!(response.contains("trc_events_total 3"));
+    }
+}
@@ -1,12 +1,67 @@
+pub use annotate::Annotate;
+pub use backfill::backfill_names;
 pub use entry_table::EntryTable;
+pub use entry_table_history::{EntryTableHistory, SnapshotPolicy};
 pub use error::Error;
+pub use frequency_estimator::{EstimatedFrequency, FrequencyEstimator};
+pub use gaps::MarkGaps;
+pub use handle_index::{EventPosition, HandleIndex};
 pub use header_info::HeaderInfo;
-pub use recorder_data::RecorderData;
+pub use heap_watcher::{HeapAlert, HeapAlertKind, HeapWatcher};
+pub use isr_chaining::{IsrTailChain, IsrTailChainDetector};
+pub use merge::MergeCapture;
+#[cfg(feature = "prometheus")]
+pub use metrics::{LiveMetrics, MetricsError, MetricsServer};
+pub use object_stats::{ObjectCounters, ObjectStats};
+pub use pipeline::{OverflowPolicy, Pipeline, PipelineStats};
+pub use query::{Query, QueryParseError};
+pub use rate_monitor::{RateMonitor, RateTotals, Rates};
+pub use recorder_data::{FinishedSession, RecorderData};
+pub use repair::{repair, RepairOptions, RepairStats};
+pub use sequence::{MatchedEvent, SequenceMatch, SequenceMatcher, SequencePattern};
+pub use shared_entry_table::SharedEntryTable;
+pub use slice::{TraceSlice, TraceSlicer};
+pub use stats::{EventIteratorExt, Stats, TaskStats, TraceStats};
+pub use symbol_map::{SymbolMap, SymbolMapEntry, SymbolMapError};
+pub use task_trace::{TaskTrace, TaskTraceSplitter};
 pub use timestamp_info::TimestampInfo;
+pub use timestamp_repair::TimestampRepair;
+pub use transport::{
+    ChunkedReader, ItmReader, PcapngPayloadReader, PcapngReader, PcapngWriter, RttSource, UdpSource,
+};
+pub use well_known::{WellKnownEntry, WellKnownObjects};
 
+pub mod annotate;
+pub mod backfill;
+pub(crate) mod codec;
+#[cfg(feature = "arrow")]
+pub mod columnar;
 pub mod entry_table;
+pub mod entry_table_history;
 pub mod error;
 pub mod event;
+pub mod frequency_estimator;
+pub mod gaps;
+pub mod handle_index;
 pub mod header_info;
+pub mod heap_watcher;
+pub mod isr_chaining;
+pub mod merge;
+#[cfg(feature = "prometheus")]
+pub mod metrics;
+pub mod object_stats;
+pub mod pipeline;
+pub mod query;
+pub mod rate_monitor;
 pub mod recorder_data;
+pub mod repair;
+pub mod sequence;
+pub mod shared_entry_table;
+pub mod slice;
+pub mod stats;
+pub mod symbol_map;
+pub mod task_trace;
 pub mod timestamp_info;
+pub mod timestamp_repair;
+pub mod transport;
+pub mod well_known;
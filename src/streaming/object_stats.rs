@@ -0,0 +1,240 @@
+use crate::streaming::event::{Event, EventCode, EventType};
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+
+/// Event counters of an object
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ObjectCounters {
+    /// All events referencing the object
+    pub events: u64,
+    /// Successful sends, gives and bit sets, including those from ISRs
+    pub sends: u64,
+    /// Successful receives, takes, peeks and waits, including those from ISRs
+    pub receives: u64,
+    /// Failed calls, i.e. a receive that timed out or a full queue
+    pub failures: u64,
+    /// Calls that blocked
+    pub blocks: u64,
+}
+
+/// Per-object counters of the events read, see `RecorderData::enable_object_stats`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ObjectStats {
+    objects: BTreeMap<ObjectHandle, ObjectCounters>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Outcome {
+    Send,
+    Receive,
+    Failure,
+    Block,
+    Other,
+}
+
+impl ObjectStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the next event
+    pub fn record(&mut self, event_code: EventCode, event: &Event) {
+        self.record_handle(event_code, Self::event_handle(event))
+    }
+
+    /// The handle of the object an event is counted against
+    pub(crate) fn event_handle(event: &Event) -> Option<ObjectHandle> {
+        // Failed calls are returned as unknown events, their first parameter
+        // is the handle
+        match event {
+            Event::Unknown(ev) => ev.parameters().first().and_then(|h| ObjectHandle::new(*h)),
+            _ => event.object_handle(),
+        }
+    }
+
+    /// Like `record`, given the handle from `event_handle`
+    pub(crate) fn record_handle(&mut self, event_code: EventCode, handle: Option<ObjectHandle>) {
+        let Some(handle) = handle else {
+            return;
+        };
+        let counters = self.objects.entry(handle).or_default();
+        counters.events += 1;
+        match outcome(event_code.event_type()) {
+            Outcome::Send => counters.sends += 1,
+            Outcome::Receive => counters.receives += 1,
+            Outcome::Failure => counters.failures += 1,
+            Outcome::Block => counters.blocks += 1,
+            Outcome::Other => (),
+        }
+    }
+
+    pub fn object(&self, handle: ObjectHandle) -> Option<&ObjectCounters> {
+        self.objects.get(&handle)
+    }
+
+    /// The counters of the objects referenced by at least one event
+    pub fn objects(&self) -> impl Iterator<Item = (ObjectHandle, &ObjectCounters)> {
+        self.objects.iter().map(|(h, c)| (*h, c))
+    }
+
+    /// The counters of all the objects added up
+    pub fn total(&self) -> ObjectCounters {
+        self.objects
+            .values()
+            .fold(ObjectCounters::default(), |mut total, c| {
+                total.events += c.events;
+                total.sends += c.sends;
+                total.receives += c.receives;
+                total.failures += c.failures;
+                total.blocks += c.blocks;
+                total
+            })
+    }
+}
+
+fn outcome(event_type: EventType) -> Outcome {
+    use EventType::*;
+    match event_type {
+        QueueSend
+        | QueueSendFromIsr
+        | QueueSendFront
+        | QueueSendFrontFromIsr
+        | SemaphoreGive
+        | SemaphoreGiveFromIsr
+        | MutexGive
+        | MutexGiveRecursive
+        | EventGroupSetBits
+        | EventGroupSetBitsFromIsr
+        | StreamBufferSend
+        | StreamBufferSendFromIsr
+        | MessageBufferSend
+        | MessageBufferSendFromIsr
+        | TaskNotify
+        | TaskNotifyFromIsr => Outcome::Send,
+
+        QueueReceive
+        | QueueReceiveFromIsr
+        | QueuePeek
+        | SemaphoreTake
+        | SemaphoreTakeFromIsr
+        | SemaphorePeek
+        | MutexTake
+        | MutexTakeRecursive
+        | EventGroupSync
+        | EventGroupWaitBits
+        | StreamBufferReceive
+        | StreamBufferReceiveFromIsr
+        | MessageBufferReceive
+        | MessageBufferReceiveFromIsr
+        | TaskNotifyWait => Outcome::Receive,
+
+        QueueSendFailed
+        | QueueSendFromIsrFailed
+        | QueueReceiveFailed
+        | QueueReceiveFromIsrFailed
+        | QueuePeekFailed
+        | MutexGiveFailed
+        | MutexTakeFailed
+        | SemaphoreGiveFailed
+        | SemaphoreGiveFromIsrFailed
+        | SemaphoreTakeFailed
+        | SemaphoreTakeFromIsrFailed
+        | SemaphorePeekFailed
+        | EventGroupSyncFailed
+        | EventGroupWaitBitsFailed
+        | StreamBufferSendFailed
+        | StreamBufferSendFromIsrFailed
+        | StreamBufferReceiveFailed
+        | StreamBufferReceiveFromIsrFailed
+        | MessageBufferSendFailed
+        | MessageBufferSendFromIsrFailed
+        | MessageBufferReceiveFailed
+        | MessageBufferReceiveFromIsrFailed
+        | TaskNotifyWaitFailed => Outcome::Failure,
+
+        QueueSendBlock
+        | QueueReceiveBlock
+        | QueuePeekBlock
+        | QueueSendFrontBlock
+        | MutexGiveBlock
+        | MutexTakeBlock
+        | MutexTakeRecursiveBlock
+        | SemaphoreGiveBlock
+        | SemaphoreTakeBlock
+        | SemaphorePeekBlock
+        | EventGroupSyncBlock
+        | EventGroupWaitBitsBlock
+        | StreamBufferSendBlock
+        | StreamBufferReceiveBlock
+        | MessageBufferSendBlock
+        | MessageBufferReceiveBlock
+        | TaskNotifyWaitBlock => Outcome::Block,
+
+        _ => Outcome::Other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCount, EventId, QueueEvent};
+    use crate::time::Timestamp;
+
+    fn code(et: EventType) -> EventCode {
+        EventCode(u16::from(EventId::from(et)).into())
+    }
+
+    fn queue_event(et: EventType, handle: ObjectHandle) -> (EventCode, Event) {
+        let ev = QueueEvent {
+            event_count: EventCount(1),
+            sequence: 1,
+            timestamp: Timestamp(1),
+            handle,
+            name: None,
+            ticks_to_wait: None,
+            messages_waiting: 0,
+        };
+        let event = match et {
+            EventType::QueueSend => Event::QueueSend(ev),
+            EventType::QueueReceive => Event::QueueReceive(ev),
+            EventType::QueueReceiveBlock => Event::QueueReceiveBlock(ev),
+            _ => unreachable!(),
+        };
+        (code(et), event)
+    }
+
+    #[test]
+    fn counters_per_object() {
+        let q0 = ObjectHandle::new(0x2000_1000).unwrap();
+        let q1 = ObjectHandle::new(0x2000_2000).unwrap();
+        // Handle and ticks to wait parameters
+        let failed_code = EventCode(u32::from(code(EventType::QueueReceiveFailed)) | (2 << 12));
+        let mut parameters = [0; 15];
+        parameters[..2].copy_from_slice(&[u32::from(q1), 100]);
+        let failed = Event::Unknown(BaseEvent {
+            code: failed_code,
+            event_count: EventCount(2),
+            sequence: 2,
+            timestamp: Timestamp(2),
+            parameters,
+        });
+
+        let mut stats = ObjectStats::new();
+        for (code, ev) in [
+            queue_event(EventType::QueueSend, q0),
+            queue_event(EventType::QueueSend, q0),
+            queue_event(EventType::QueueReceiveBlock, q1),
+            queue_event(EventType::QueueReceive, q0),
+            (failed_code, failed),
+        ] {
+            stats.record(code, &ev);
+        }
+
+        let c0 = stats.object(q0).unwrap();
+        assert_eq!((c0.events, c0.sends, c0.receives), (3, 2, 1));
+        let c1 = stats.object(q1).unwrap();
+        assert_eq!((c1.events, c1.blocks, c1.failures), (2, 1, 1));
+        assert_eq!(stats.total().events, 5);
+        assert_eq!(stats.objects().count(), 2);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::streaming::event::{Event, EventCode};
+use crate::streaming::{Error, RecorderData};
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// What the producer does when the queue is full
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum OverflowPolicy {
+    /// Wait for the consumer, applying backpressure to the input
+    #[default]
+    Block,
+    /// Discard the event, keeping up with the input at the cost of losing events
+    DropNewest,
+}
+
+/// A snapshot of a `Pipeline`'s metrics
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct PipelineStats {
+    /// Number of events waiting in the queue
+    pub queue_depth: usize,
+    pub max_queue_depth: usize,
+    /// Number of events the producer has parsed
+    pub events_parsed: u64,
+    /// Number of events discarded by the `DropNewest` policy
+    pub events_dropped: u64,
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    queue_depth: AtomicUsize,
+    max_queue_depth: AtomicUsize,
+    events_parsed: AtomicU64,
+    events_dropped: AtomicU64,
+}
+
+/// Parses events on a producer thread and hands them to the consumer through a bounded queue.
+///
+/// The producer stops at the end of the input, on the first error, or once the
+/// `Pipeline` is dropped or joined.
+///
+/// Consumers can resolve object names with `RecorderData::share_entry_table`,
+/// called before spawning the pipeline.
+pub struct Pipeline {
+    receiver: Receiver<(EventCode, Event)>,
+    metrics: Arc<Metrics>,
+    producer: JoinHandle<Result<RecorderData, Error>>,
+}
+
+impl Pipeline {
+    pub fn spawn<R>(
+        rd: RecorderData,
+        r: R,
+        capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let metrics = Arc::new(Metrics::default());
+        let producer_metrics = metrics.clone();
+        let producer = thread::Builder::new()
+            .name("trc-pipeline".to_owned())
+            .spawn(move || produce(rd, r, sender, capacity, overflow_policy, &producer_metrics))
+            .expect("Failed to spawn the pipeline producer thread");
+        Self {
+            receiver,
+            metrics,
+            producer,
+        }
+    }
+
+    /// Wait for the next event, returns `None` once the producer has stopped and
+    /// the queue is empty
+    pub fn recv(&self) -> Option<(EventCode, Event)> {
+        let ev = self.receiver.recv().ok()?;
+        self.metrics.queue_depth.fetch_sub(1, Ordering::AcqRel);
+        Some(ev)
+    }
+
+    /// Like `recv`, but gives up after `timeout`.
+    /// Returns `Ok(None)` once the producer has stopped and the queue is empty.
+    pub fn recv_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Option<(EventCode, Event)>, RecvTimeoutError> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(ev) => {
+                self.metrics.queue_depth.fetch_sub(1, Ordering::AcqRel);
+                Ok(Some(ev))
+            }
+            Err(RecvTimeoutError::Disconnected) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EventCode, Event)> + '_ {
+        std::iter::from_fn(move || self.recv())
+    }
+
+    pub fn stats(&self) -> PipelineStats {
+        PipelineStats {
+            queue_depth: self.metrics.queue_depth.load(Ordering::Acquire),
+            max_queue_depth: self.metrics.max_queue_depth.load(Ordering::Acquire),
+            events_parsed: self.metrics.events_parsed.load(Ordering::Acquire),
+            events_dropped: self.metrics.events_dropped.load(Ordering::Acquire),
+        }
+    }
+
+    /// Stop the producer and wait for it to finish.
+    ///
+    /// Returns the `RecorderData`, or the error that stopped the producer.
+    /// Events still in the queue are discarded.
+    pub fn join(self) -> Result<RecorderData, Error> {
+        drop(self.receiver);
+        match self.producer.join() {
+            Ok(res) => res,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+fn produce<R: Read>(
+    mut rd: RecorderData,
+    mut r: R,
+    sender: SyncSender<(EventCode, Event)>,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    metrics: &Metrics,
+) -> Result<RecorderData, Error> {
+    while let Some(ev) = rd.read_event(&mut r)? {
+        metrics.events_parsed.fetch_add(1, Ordering::AcqRel);
+
+        // Count the event as queued before sending so the consumer never
+        // observes a negative depth
+        metrics.queue_depth.fetch_add(1, Ordering::AcqRel);
+        let sent = match overflow_policy {
+            OverflowPolicy::Block => sender.send(ev).map_err(|_| ()),
+            OverflowPolicy::DropNewest => match sender.try_send(ev) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => {
+                    metrics.queue_depth.fetch_sub(1, Ordering::AcqRel);
+                    metrics.events_dropped.fetch_add(1, Ordering::AcqRel);
+                    continue;
+                }
+                Err(TrySendError::Disconnected(_)) => Err(()),
+            },
+        };
+        if sent.is_err() {
+            // The consumer is gone
+            debug!("Pipeline receiver dropped, stopping the producer");
+            break;
+        }
+        // The count briefly includes an event the consumer has received but not yet accounted for
+        let depth = metrics.queue_depth.load(Ordering::Acquire).min(capacity);
+        metrics.max_queue_depth.fetch_max(depth, Ordering::AcqRel);
+    }
+    Ok(rd)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::recorder_data::test::trace_v14;
+    use std::time::Instant;
+
+    /// Number of events preceding the malformed event in the fixture
+    fn valid_events() -> u64 {
+        let (mut rd, mut r) = trace_v14();
+        let mut events = 0;
+        while let Ok(Some(_)) = rd.read_event(&mut r) {
+            events += 1;
+        }
+        events
+    }
+
+    #[test]
+    fn stops_on_the_first_error() {
+        let (rd, r) = trace_v14();
+        let pipeline = Pipeline::spawn(rd, r, 4, OverflowPolicy::Block);
+        assert_eq!(pipeline.iter().count() as u64, valid_events());
+        assert_eq!(pipeline.recv(), None);
+        assert!(matches!(
+            pipeline.recv_timeout(Duration::from_millis(1)),
+            Ok(None)
+        ));
+        assert!(matches!(
+            pipeline.join(),
+            Err(Error::InvalidEventParameterCount(..))
+        ));
+    }
+
+    #[test]
+    fn drop_newest_without_a_consumer() {
+        let expected = valid_events();
+        let (rd, r) = trace_v14();
+        let pipeline = Pipeline::spawn(rd, r, 1, OverflowPolicy::DropNewest);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while pipeline.stats().events_parsed < expected {
+            assert!(Instant::now() < deadline, "{:?}", pipeline.stats());
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let stats = pipeline.stats();
+        assert_eq!(stats.events_dropped, expected - 1);
+        assert_eq!(stats.queue_depth, 1);
+        assert_eq!(stats.max_queue_depth, 1);
+        // Only the first event made it into the queue
+        let (mut rd, mut r) = trace_v14();
+        assert_eq!(pipeline.recv(), rd.read_event(&mut r).unwrap());
+        assert_eq!(pipeline.recv(), None);
+        assert_eq!(pipeline.stats().queue_depth, 0);
+    }
+
+    #[test]
+    fn joining_early_stops_the_producer() {
+        let (rd, r) = trace_v14();
+        let pipeline = Pipeline::spawn(rd, r, 1, OverflowPolicy::Block);
+        // The producer is blocked on the full queue until the receiver is dropped,
+        // it stops before reaching the malformed event
+        assert!(pipeline.join().is_ok());
+    }
+}
@@ -0,0 +1,494 @@
+//! A small query language for filtering streaming events.
+//!
+//! Queries are made up of comparisons combined with `&&`, `||`, `!` and parentheses:
+//! ```text
+//! type == QueueSend && object == "cmd_q" && ticks > 1000
+//! ```
+//!
+//! Supported fields:
+//! * `type`: the event type, i.e. `QueueSend` or `QUEUE_SEND` (case and underscore insensitive)
+//! * `id`: the event ID
+//! * `object`: the name of the object the event refers to
+//! * `handle`: the handle of the object the event refers to
+//! * `ticks` (or `timestamp`): the event timestamp
+//! * `count`: the event count
+//! * `channel`: the user event channel
+//! * `message`: the formatted user event string
+//!
+//! Supported operators: `==`, `!=`, `<`, `<=`, `>`, `>=` and `~=` (string contains).
+//! Numbers can be decimal or `0x` prefixed hex, strings can be quoted or bare words.
+//! A comparison against a field the event doesn't have is false.
+
+use crate::streaming::event::{Event, EventCode};
+use derive_more::Display;
+use std::str::FromStr;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Query(Expr);
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, Value),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Field {
+    #[display(fmt = "type")]
+    Type,
+    #[display(fmt = "id")]
+    Id,
+    #[display(fmt = "object")]
+    Object,
+    #[display(fmt = "handle")]
+    Handle,
+    #[display(fmt = "ticks")]
+    Ticks,
+    #[display(fmt = "count")]
+    Count,
+    #[display(fmt = "channel")]
+    Channel,
+    #[display(fmt = "message")]
+    Message,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Op {
+    #[display(fmt = "==")]
+    Eq,
+    #[display(fmt = "!=")]
+    Ne,
+    #[display(fmt = "<")]
+    Lt,
+    #[display(fmt = "<=")]
+    Le,
+    #[display(fmt = ">")]
+    Gt,
+    #[display(fmt = ">=")]
+    Ge,
+    #[display(fmt = "~=")]
+    Contains,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Value {
+    #[display(fmt = "{_0}")]
+    Number(u64),
+    #[display(fmt = "'{_0}'")]
+    String(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, thiserror::Error)]
+#[error("Invalid query at offset {offset}: {message}")]
+pub struct QueryParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Query {
+    pub fn new(expr: Expr) -> Self {
+        Self(expr)
+    }
+
+    pub fn parse(s: &str) -> Result<Self, QueryParseError> {
+        let tokens = tokenize(s)?;
+        let mut p = Parser { tokens, pos: 0 };
+        let expr = p.parse_or()?;
+        if let Some((offset, tok)) = p.tokens.get(p.pos) {
+            return Err(QueryParseError {
+                offset: *offset,
+                message: format!("unexpected token '{tok}'"),
+            });
+        }
+        Ok(Self(expr))
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.0
+    }
+
+    pub fn matches(&self, event_code: EventCode, event: &Event) -> bool {
+        self.0.matches(event_code, event)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl Expr {
+    pub fn compare<V: Into<Value>>(field: Field, op: Op, value: V) -> Self {
+        Expr::Compare(field, op, value.into())
+    }
+
+    pub fn and(self, other: Expr) -> Self {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Self {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Expr::Not(Box::new(self))
+    }
+
+    pub fn matches(&self, event_code: EventCode, event: &Event) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(event_code, event) && b.matches(event_code, event),
+            Expr::Or(a, b) => a.matches(event_code, event) || b.matches(event_code, event),
+            Expr::Not(e) => !e.matches(event_code, event),
+            Expr::Compare(field, op, value) => compare(*field, *op, value, event_code, event),
+        }
+    }
+}
+
+impl From<u64> for Value {
+    fn from(v: u64) -> Self {
+        Value::Number(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+enum FieldValue<'a> {
+    Number(u64),
+    Str(&'a str),
+    Owned(String),
+}
+
+fn normalize_type_name(s: &str) -> String {
+    s.chars()
+        .filter(|c| *c != '_')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn field_value(field: Field, event_code: EventCode, event: &Event) -> Option<FieldValue<'_>> {
+    Some(match field {
+        Field::Type => FieldValue::Owned(event_code.event_type().to_string()),
+        Field::Id => FieldValue::Number(u16::from(event_code.event_id()).into()),
+        Field::Object => FieldValue::Str(event.object_name()?),
+        Field::Handle => FieldValue::Number(u32::from(event.object_handle()?).into()),
+        Field::Ticks => FieldValue::Number(event.timestamp().ticks()),
+        Field::Count => FieldValue::Number(u16::from(event.event_count()).into()),
+        Field::Channel => match event {
+            Event::User(e) => FieldValue::Str(e.channel.as_str()),
+            _ => return None,
+        },
+        Field::Message => match event {
+            Event::User(e) => FieldValue::Str(e.formatted_string.as_ref()),
+            _ => return None,
+        },
+    })
+}
+
+fn compare(field: Field, op: Op, value: &Value, event_code: EventCode, event: &Event) -> bool {
+    let Some(fv) = field_value(field, event_code, event) else {
+        return false;
+    };
+    match (fv, value) {
+        (FieldValue::Number(a), Value::Number(b)) => match op {
+            Op::Eq => a == *b,
+            Op::Ne => a != *b,
+            Op::Lt => a < *b,
+            Op::Le => a <= *b,
+            Op::Gt => a > *b,
+            Op::Ge => a >= *b,
+            Op::Contains => false,
+        },
+        (FieldValue::Number(_), Value::String(_)) => false,
+        (FieldValue::Str(a), Value::String(b)) => compare_str(field, op, a, b),
+        (FieldValue::Owned(a), Value::String(b)) => compare_str(field, op, &a, b),
+        (FieldValue::Str(a), Value::Number(b)) => compare_str(field, op, a, &b.to_string()),
+        (FieldValue::Owned(a), Value::Number(b)) => compare_str(field, op, &a, &b.to_string()),
+    }
+}
+
+fn compare_str(field: Field, op: Op, a: &str, b: &str) -> bool {
+    let (a, b) = if field == Field::Type {
+        (normalize_type_name(a), normalize_type_name(b))
+    } else {
+        (a.to_owned(), b.to_owned())
+    };
+    match op {
+        Op::Eq => a == b,
+        Op::Ne => a != b,
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        Op::Contains => a.contains(&b),
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+enum Token {
+    #[display(fmt = "&&")]
+    And,
+    #[display(fmt = "||")]
+    Or,
+    #[display(fmt = "!")]
+    Not,
+    #[display(fmt = "(")]
+    LParen,
+    #[display(fmt = ")")]
+    RParen,
+    #[display(fmt = "{_0}")]
+    Op(Op),
+    #[display(fmt = "{_0}")]
+    Word(String),
+    #[display(fmt = "\"{_0}\"")]
+    Quoted(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, QueryParseError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let mut i = 0;
+    let peek = |i: usize| chars.get(i).map(|(_, c)| *c);
+    while let Some(&(offset, c)) = chars.get(i) {
+        let two = (c, peek(i + 1));
+        let (tok, len) = match two {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('~', Some('=')) => (Token::Op(Op::Contains), 2),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('"', _) | ('\'', _) => {
+                let quote = c;
+                let mut j = i + 1;
+                let mut v = String::new();
+                loop {
+                    match peek(j) {
+                        Some(ch) if ch == quote => break,
+                        Some(ch) => v.push(ch),
+                        None => {
+                            return Err(QueryParseError {
+                                offset,
+                                message: "unterminated string".to_owned(),
+                            })
+                        }
+                    }
+                    j += 1;
+                }
+                (Token::Quoted(v), j + 1 - i)
+            }
+            (c, _) if c.is_alphanumeric() || c == '_' => {
+                let mut j = i;
+                let mut v = String::new();
+                while let Some(ch) = peek(j) {
+                    if ch.is_alphanumeric() || ch == '_' || ch == '.' {
+                        v.push(ch);
+                        j += 1;
+                    } else {
+                        break;
+                    }
+                }
+                (Token::Word(v), j - i)
+            }
+            _ => {
+                return Err(QueryParseError {
+                    offset,
+                    message: format!("unexpected character '{c}'"),
+                })
+            }
+        };
+        tokens.push((offset, tok));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|(o, _)| *o)
+            .unwrap_or(0)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, QueryParseError> {
+        Err(QueryParseError {
+            offset: self.offset(),
+            message: message.to_owned(),
+        })
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        t
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryParseError> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            lhs = lhs.or(self.parse_and()?);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryParseError> {
+        let mut lhs = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            lhs = lhs.and(self.parse_unary()?);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryParseError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(self.parse_unary()?.not())
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let e = self.parse_or()?;
+                if self.next() != Some(Token::RParen) {
+                    self.pos -= 1;
+                    return self.error("expected ')'");
+                }
+                Ok(e)
+            }
+            _ => self.parse_compare(),
+        }
+    }
+
+    fn parse_compare(&mut self) -> Result<Expr, QueryParseError> {
+        let field = match self.next() {
+            Some(Token::Word(w)) => match w.to_lowercase().as_str() {
+                "type" => Field::Type,
+                "id" => Field::Id,
+                "object" | "name" => Field::Object,
+                "handle" => Field::Handle,
+                "ticks" | "timestamp" => Field::Ticks,
+                "count" => Field::Count,
+                "channel" => Field::Channel,
+                "message" => Field::Message,
+                _ => {
+                    self.pos -= 1;
+                    return self.error(&format!("unknown field '{w}'"));
+                }
+            },
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                return self.error("expected a field name");
+            }
+        };
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                return self.error("expected a comparison operator");
+            }
+        };
+        let value = match self.next() {
+            Some(Token::Quoted(s)) => Value::String(s),
+            Some(Token::Word(w)) => parse_number(&w)
+                .map(Value::Number)
+                .unwrap_or(Value::String(w)),
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                return self.error("expected a value");
+            }
+        };
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse::<u64>().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, QueueEvent};
+    use crate::time::Timestamp;
+    use crate::types::{ObjectHandle, ObjectName};
+
+    fn queue_send(ticks: u64) -> (EventCode, Event) {
+        (
+            EventCode(0x2050),
+            Event::QueueSend(QueueEvent {
+                event_count: EventCount(3),
+                sequence: 3,
+                timestamp: Timestamp(ticks),
+                handle: ObjectHandle::new(0x2000_0000).unwrap(),
+                name: Some(ObjectName("cmd_q".into())),
+                ticks_to_wait: None,
+                messages_waiting: 1,
+            }),
+        )
+    }
+
+    #[test]
+    fn parse_and_match() {
+        let q = Query::parse(r#"type == QueueSend && object == "cmd_q" && ticks > 1000"#).unwrap();
+        let (ec, ev) = queue_send(1001);
+        assert!(q.matches(ec, &ev));
+        let (ec, ev) = queue_send(1000);
+        assert!(!q.matches(ec, &ev));
+
+        let q =
+            Query::parse("(type == QUEUE_RECEIVE || handle == 0x20000000) && !count < 2").unwrap();
+        assert!(q.matches(ec, &ev));
+
+        let q = Query::parse("object ~= cmd && channel == foo").unwrap();
+        assert!(!q.matches(ec, &ev));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Query::parse("bogus == 1").unwrap_err().offset, 0);
+        assert_eq!(Query::parse("ticks >").unwrap_err().offset, 6);
+        assert!(Query::parse("(ticks > 1").is_err());
+        assert!(Query::parse("object == \"abc").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::streaming::event::{EventCount, TrackingEventCounter};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Rates over a `RateMonitor`'s window, all per second
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Default)]
+pub struct Rates {
+    pub events_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub dropped_events_per_sec: f64,
+    /// Fraction of the events produced by the recorder within the window that were dropped
+    pub drop_ratio: f64,
+}
+
+/// Totals since a `RateMonitor` was created
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct RateTotals {
+    pub events: u64,
+    pub bytes: u64,
+    pub dropped_events: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Sample {
+    at: Instant,
+    bytes: u64,
+    dropped_events: u64,
+}
+
+/// Rolling event rate, bandwidth and dropped event rate of a live capture.
+///
+/// Record each event as it's read, along with the number of bytes it took,
+/// i.e. the difference of `RecorderData::bytes_read` before and after reading it.
+/// Dropped events are detected from discontinuities in the event counter.
+#[derive(Clone, Debug)]
+pub struct RateMonitor {
+    window: Duration,
+    samples: VecDeque<Sample>,
+    counter: Option<TrackingEventCounter>,
+    totals: RateTotals,
+}
+
+impl RateMonitor {
+    pub const DEFAULT_WINDOW: Duration = Duration::from_secs(1);
+
+    /// Compute the rates over the trailing `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            counter: None,
+            totals: RateTotals::default(),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn totals(&self) -> RateTotals {
+        self.totals
+    }
+
+    /// Record an event read now
+    pub fn record(&mut self, event_count: EventCount, num_bytes: u64) {
+        self.record_at(Instant::now(), event_count, num_bytes)
+    }
+
+    /// Record an event read at the given instant, which must not precede
+    /// previously recorded instants
+    pub fn record_at(&mut self, at: Instant, event_count: EventCount, num_bytes: u64) {
+        let dropped_events = match self.counter.as_mut() {
+            Some(counter) => counter.update(event_count).unwrap_or(0),
+            None => {
+                let mut counter = TrackingEventCounter::zero();
+                counter.set_initial_count(event_count);
+                self.counter = Some(counter);
+                0
+            }
+        };
+        self.totals.events += 1;
+        self.totals.bytes += num_bytes;
+        self.totals.dropped_events += dropped_events;
+        self.samples.push_back(Sample {
+            at,
+            bytes: num_bytes,
+            dropped_events,
+        });
+        self.expire(at);
+    }
+
+    /// Rates over the window ending now
+    pub fn rates(&mut self) -> Rates {
+        self.rates_at(Instant::now())
+    }
+
+    /// Rates over the window ending at the given instant
+    pub fn rates_at(&mut self, at: Instant) -> Rates {
+        self.expire(at);
+        let secs = self.window.as_secs_f64();
+        if secs == 0.0 {
+            return Rates::default();
+        }
+        let events = self.samples.len() as u64;
+        let (bytes, dropped_events) = self
+            .samples
+            .iter()
+            .fold((0, 0), |(b, d), s| (b + s.bytes, d + s.dropped_events));
+        let produced = events + dropped_events;
+        Rates {
+            events_per_sec: events as f64 / secs,
+            bytes_per_sec: bytes as f64 / secs,
+            dropped_events_per_sec: dropped_events as f64 / secs,
+            drop_ratio: if produced == 0 {
+                0.0
+            } else {
+                dropped_events as f64 / produced as f64
+            },
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(s) = self.samples.front() {
+            if now.saturating_duration_since(s.at) > self.window {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for RateMonitor {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rolling_rates_with_drops() {
+        let start = Instant::now();
+        let mut m = RateMonitor::new(Duration::from_secs(2));
+        m.record_at(start, EventCount(10), 8);
+        m.record_at(start + Duration::from_millis(500), EventCount(11), 12);
+        // Events 12..=14 were dropped
+        m.record_at(start + Duration::from_secs(1), EventCount(15), 20);
+
+        let rates = m.rates_at(start + Duration::from_secs(1));
+        assert_eq!(rates.events_per_sec, 1.5);
+        assert_eq!(rates.bytes_per_sec, 20.0);
+        assert_eq!(rates.dropped_events_per_sec, 1.5);
+        assert_eq!(rates.drop_ratio, 0.5);
+
+        // Only the last event remains in the window
+        let rates = m.rates_at(start + Duration::from_millis(2800));
+        assert_eq!(rates.events_per_sec, 0.5);
+        assert_eq!(rates.bytes_per_sec, 10.0);
+
+        assert_eq!(
+            m.totals(),
+            RateTotals {
+                events: 3,
+                bytes: 40,
+                dropped_events: 3
+            }
+        );
+    }
+}
@@ -1,8 +1,21 @@
-use crate::streaming::event::{Event, EventCode, EventId, EventParser};
-use crate::streaming::{EntryTable, Error, HeaderInfo, TimestampInfo};
-use crate::types::{Endianness, Heap, Protocol};
-use std::io::Read;
-use tracing::debug;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::intern::InternPool;
+use crate::streaming::entry_table::{NameChange, PriorityChange};
+use crate::streaming::event::{
+    Event, EventCode, EventCount, EventHeader, EventId, EventParameterCount, EventParser,
+    EventVisitor, ObjectLookupPolicy, SessionBoundaryEvent,
+};
+use crate::streaming::{
+    codec, EntryTable, EntryTableHistory, Error, HandleIndex, HeaderInfo, ObjectStats,
+    SharedEntryTable, SnapshotPolicy, TimestampInfo, TimestampRepair, WellKnownObjects,
+};
+use crate::symbolizer::Symbolizer;
+use crate::time::Timestamp;
+use crate::types::{
+    Endianness, Heap, ObjectHandle, Priority, Protocol, StringDecoder, StringSanitizer,
+};
+use std::io::{self, Read};
+use std::sync::Arc;
 
 /// Encapsulates all of the startup data needed to materialize the events
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -12,42 +25,114 @@ pub struct RecorderData {
     pub timestamp_info: TimestampInfo,
     pub entry_table: EntryTable,
     parser: EventParser,
+    /// Number of bytes read since the first event, see `bytes_read`
+    offset: u64,
+    diagnostics: Vec<Diagnostic>,
+    entry_table_history: Option<EntryTableHistory>,
+    /// Bytes of a truncated event, replayed by the next read
+    partial_event: Vec<u8>,
+    /// Bytes of the event being read, reused between events.
+    /// Empty between reads, they're only kept when the event is truncated.
+    event_bytes: Vec<u8>,
+    timestamp_repair: Option<TimestampRepair>,
+    handle_index: Option<HandleIndex>,
+    object_stats: Option<ObjectStats>,
+    shared_entry_table: Option<SharedEntryTable>,
+    /// Number of the current session, incremented by `read_event_auto_restart`
+    session: u32,
+    /// See `finished_sessions`
+    finished_sessions: Vec<FinishedSession>,
+    /// A session boundary is returned before the next event, see `read_event_auto_restart`
+    boundary_pending: bool,
+    /// The first event of the new session, returned after its session boundary
+    pending_event: Option<(EventCode, Event)>,
+}
+
+/// The per-session records of a session that ended with a trace restart,
+/// see `RecorderData::finished_sessions`.
+///
+/// Handles are only unique within a session, a restarted trace may reuse them
+/// for other objects.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct FinishedSession {
+    pub session: u32,
+    pub entry_table_history: Option<EntryTableHistory>,
+    pub handle_index: Option<HandleIndex>,
+    pub object_stats: Option<ObjectStats>,
 }
 
 impl RecorderData {
     pub fn find<R: Read>(r: &mut R) -> Result<Self, Error> {
+        Self::find_with_string_decoder(r, StringDecoder::default())
+    }
+
+    /// Like `find`, but decodes the symbols and names in the trace data with `string_decoder`
+    pub fn find_with_string_decoder<R: Read>(
+        r: &mut R,
+        string_decoder: StringDecoder,
+    ) -> Result<Self, Error> {
         debug!("Finding header info");
-        let header = HeaderInfo::find(r)?;
+        let header = HeaderInfo::find_with_string_decoder(r, &string_decoder)?;
 
-        Self::read_common(header, r)
+        Self::read_common(header, r, string_decoder)
     }
 
     pub fn read<R: Read>(r: &mut R) -> Result<Self, Error> {
+        Self::read_with_string_decoder(r, StringDecoder::default())
+    }
+
+    /// Like `read`, but decodes the symbols and names in the trace data with `string_decoder`
+    pub fn read_with_string_decoder<R: Read>(
+        r: &mut R,
+        string_decoder: StringDecoder,
+    ) -> Result<Self, Error> {
         debug!("Reading header info");
-        let header = HeaderInfo::read(r)?;
+        let header = HeaderInfo::read_with_string_decoder(r, &string_decoder)?;
 
-        Self::read_common(header, r)
+        Self::read_common(header, r, string_decoder)
     }
 
     /// Assumes the PSF word (u32) has already been read from the input
     pub fn read_with_endianness<R: Read>(endianness: Endianness, r: &mut R) -> Result<Self, Error> {
+        Self::read_with_endianness_and_string_decoder(endianness, r, StringDecoder::default())
+    }
+
+    fn read_with_endianness_and_string_decoder<R: Read>(
+        endianness: Endianness,
+        r: &mut R,
+        string_decoder: StringDecoder,
+    ) -> Result<Self, Error> {
         debug!("Reading header info");
-        let header = HeaderInfo::read_with_endianness(endianness, r)?;
+        let header =
+            HeaderInfo::read_with_endianness_and_string_decoder(endianness, r, &string_decoder)?;
 
-        Self::read_common(header, r)
+        Self::read_common(header, r, string_decoder)
     }
 
-    fn read_common<R: Read>(header: HeaderInfo, r: &mut R) -> Result<Self, Error> {
+    fn read_common<R: Read>(
+        header: HeaderInfo,
+        r: &mut R,
+        string_decoder: StringDecoder,
+    ) -> Result<Self, Error> {
         debug!("Reading timestamp info");
-        let timestamp_info = TimestampInfo::read(r, header.endianness, header.format_version)?;
+        let codec = codec::for_version(header.format_version);
+        let timestamp_info = TimestampInfo::read(r, header.endianness, codec)?;
 
         debug!("Reading entry table");
-        let entry_table = EntryTable::read(r, header.endianness)?;
+        let mut entry_table = EntryTable::read(r, header.endianness, &string_decoder)?;
 
-        let parser = EventParser::new(
+        let mut parser = EventParser::new(
             header.endianness,
             entry_table.system_heap().unwrap_or_default(),
         );
+        parser.set_string_decoder(string_decoder);
+        parser.set_send_ticks_to_wait(header.send_ticks_to_wait());
+        parser.well_known_objects().classify(&mut entry_table);
+
+        let diagnostics = HeaderInfo::support_warnings(header.kernel_port, header.format_version)
+            .into_iter()
+            .map(|msg| Diagnostic::new(Severity::Warning, msg))
+            .collect();
 
         Ok(Self {
             protocol: Protocol::Streaming,
@@ -55,9 +140,24 @@ impl RecorderData {
             timestamp_info,
             entry_table,
             parser,
+            offset: 0,
+            diagnostics,
+            entry_table_history: None,
+            partial_event: Vec::new(),
+            event_bytes: Vec::new(),
+            timestamp_repair: None,
+            handle_index: None,
+            object_stats: None,
+            shared_entry_table: None,
+            session: 0,
+            finished_sessions: Vec::new(),
+            boundary_pending: false,
+            pending_event: None,
         })
     }
 
+    /// The heap tracked from the memory events of all cores, the recorder
+    /// reports a single system heap regardless of `HeaderInfo::num_cores`
     pub fn system_heap(&self) -> &Heap {
         self.parser.system_heap()
     }
@@ -67,7 +167,713 @@ impl RecorderData {
             .set_custom_printf_event_id(custom_printf_event_id);
     }
 
+    /// See `EventParser::set_object_class_checking`
+    pub fn set_object_class_checking(&mut self, enabled: bool) {
+        self.parser.set_object_class_checking(enabled);
+    }
+
+    /// See `ObjectLookupPolicy`
+    pub fn set_object_lookup_policy(&mut self, policy: ObjectLookupPolicy) {
+        self.parser.set_object_lookup_policy(policy);
+    }
+
+    /// Classify the well-known objects of the entry table, and those named later on.
+    ///
+    /// These replace the default well-known objects, before any event is read the
+    /// entries added and classes given by the defaults are undone first.
+    pub fn with_well_known_objects(mut self, objects: WellKnownObjects) -> Self {
+        if self.offset == 0 && self.session == 0 {
+            self.parser
+                .well_known_objects()
+                .declassify(&mut self.entry_table);
+        }
+        objects.classify(&mut self.entry_table);
+        self.parser.set_well_known_objects(objects);
+        self.publish_entry_table();
+        self
+    }
+
+    /// See `EventParser::set_symbolizer`
+    pub fn set_symbolizer(&mut self, symbolizer: Arc<Symbolizer>) {
+        self.entry_table.symbolize(&symbolizer);
+        self.parser.set_symbolizer(symbolizer);
+        self.publish_entry_table();
+    }
+
+    /// Sanitize the symbols of the entry table, and the object names and user
+    /// event strings read from here on
+    pub fn set_string_sanitizer(&mut self, sanitizer: StringSanitizer) {
+        self.entry_table.sanitize_symbols(&sanitizer);
+        self.parser.set_string_sanitizer(sanitizer);
+        self.publish_entry_table();
+    }
+
+    /// Intern the symbols of the entry table, and the object names and format
+    /// strings read from here on, in the given pool
+    pub fn set_intern_pool(&mut self, pool: InternPool) {
+        self.entry_table.intern_symbols(&pool);
+        self.parser.set_intern_pool(pool);
+    }
+
+    /// Number of bytes read so far since the first event, i.e. excluding the first
+    /// header and entry table. Those of restarted sessions are included, so offsets
+    /// remain positions in the stream.
+    pub fn bytes_read(&self) -> u64 {
+        self.offset
+    }
+
+    /// See `EventParser::set_max_name_history_len`
+    pub fn set_max_name_history_len(&mut self, len: usize) {
+        self.parser.set_max_name_history_len(len);
+    }
+
+    /// See `EventParser::name_history`
+    pub fn name_history(&self, handle: ObjectHandle) -> &[NameChange] {
+        self.parser.name_history(handle)
+    }
+
+    /// See `EventParser::set_max_priority_history_len`
+    pub fn set_max_priority_history_len(&mut self, len: usize) {
+        self.parser.set_max_priority_history_len(len);
+    }
+
+    /// See `EventParser::priority_history`
+    pub fn priority_history(&self, handle: ObjectHandle) -> &[PriorityChange] {
+        self.parser.priority_history(handle)
+    }
+
+    /// Start taking snapshots of the entry table after the events read with `read_event`
+    pub fn enable_entry_table_history(&mut self, policy: SnapshotPolicy) {
+        self.entry_table_history = Some(EntryTableHistory::new(policy));
+    }
+
+    pub fn entry_table_history(&self) -> Option<&EntryTableHistory> {
+        self.entry_table_history.as_ref()
+    }
+
+    /// Start indexing the events read with `read_event` by the object they refer to
+    pub fn enable_handle_index(&mut self) {
+        self.handle_index = Some(HandleIndex::new());
+    }
+
+    pub fn handle_index(&self) -> Option<&HandleIndex> {
+        self.handle_index.as_ref()
+    }
+
+    /// Take the index built so far, indexing carries on with an empty one
+    pub fn take_handle_index(&mut self) -> Option<HandleIndex> {
+        self.handle_index.as_mut().map(std::mem::take)
+    }
+
+    /// Start counting the events read with `read_event` per object, see `ObjectStats`
+    pub fn enable_object_stats(&mut self) {
+        self.object_stats = Some(ObjectStats::new());
+    }
+
+    pub fn object_stats(&self) -> Option<&ObjectStats> {
+        self.object_stats.as_ref()
+    }
+
+    /// Share the entry table with other threads, it's kept up to date by
+    /// `read_event` and `visit_event`.
+    ///
+    /// Calling it again returns another handle to the same table.
+    pub fn share_entry_table(&mut self) -> SharedEntryTable {
+        self.shared_entry_table
+            .get_or_insert_with(|| SharedEntryTable::new(self.entry_table.clone()))
+            .clone()
+    }
+
+    fn publish_entry_table(&self) {
+        if let Some(shared) = &self.shared_entry_table {
+            shared.publish(&self.entry_table);
+        }
+    }
+
+    /// Clamp non-monotonic timestamps of the events read with `read_event`,
+    /// each adjustment is reported as a diagnostic
+    pub fn enable_timestamp_repair(&mut self, repair: TimestampRepair) {
+        self.timestamp_repair = Some(repair);
+    }
+
+    pub fn timestamp_repair(&self) -> Option<&TimestampRepair> {
+        self.timestamp_repair.as_ref()
+    }
+
+    /// Recoverable issues found while reading the header and events so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Take the recoverable issues found so far, i.e. to avoid accumulating them
+    /// over a long running session
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Read the next event, returns `None` at the end of the stream.
+    ///
+    /// When the stream ends part way through an event, `Error::TruncatedEvent`
+    /// is returned and the partial event is kept, reading again once more
+    /// data is available resumes from the start of that event.
     pub fn read_event<R: Read>(&mut self, r: &mut R) -> Result<Option<(EventCode, Event)>, Error> {
-        self.parser.next_event(r, &mut self.entry_table)
+        let event_offset = self.offset;
+        self.start_event();
+        let mut r = EventReader::new(r, &mut self.event_bytes, &mut self.offset);
+        let res = self.parser.next_event(&mut r, &mut self.entry_table);
+        self.collect_diagnostics(event_offset);
+        let mut res = self.check_truncated(res, Ok(None))?;
+        if let Some((code, event)) = &mut res {
+            self.process_event(event_offset, *code, event);
+        }
+        Ok(res)
+    }
+
+    /// Repair the timestamp of an event returned by `read_event` or `read_event_from_slice`
+    /// and post-process it
+    fn process_event(&mut self, event_offset: u64, code: EventCode, event: &mut Event) {
+        let original_timestamp = self
+            .timestamp_repair
+            .as_mut()
+            .and_then(|repair| repair.repair(event));
+        let processed = ProcessedEvent {
+            code,
+            event_count: event.event_count(),
+            timestamp: event.timestamp(),
+            original_timestamp,
+            handle: event.object_handle(),
+            stats_handle: ObjectStats::event_handle(event),
+        };
+        self.post_process(event_offset, processed);
+    }
+
+    /// Record a decoded event in the enabled histories, indexes and stats,
+    /// shared by `read_event` and `visit_event`
+    fn post_process(&mut self, event_offset: u64, event: ProcessedEvent) {
+        if let Some(original) = event.original_timestamp {
+            let message = format!(
+                "Timestamp {original} is not monotonic, clamped to {}",
+                event.timestamp
+            );
+            warn!("{message}");
+            self.diagnostics.push(
+                Diagnostic::new(Severity::Warning, message)
+                    .with_offset(event_offset)
+                    .with_event_count(u16::from(event.event_count).into()),
+            );
+        }
+        if let Some(history) = &mut self.entry_table_history {
+            history.record(event.event_count, &self.entry_table);
+        }
+        if let Some(index) = &mut self.handle_index {
+            index.record_handle(event_offset, event.handle);
+        }
+        if let Some(stats) = &mut self.object_stats {
+            stats.record_handle(event.code, event.stats_handle);
+        }
+        self.publish_entry_table();
+    }
+
+    /// Like `read_event`, but handles the trace restarting instead of returning
+    /// `Error::TraceRestarted`.
+    ///
+    /// The header and entry table of the new session are read, replacing the
+    /// current ones, and an `Event::SessionBoundary` pseudo-event is returned,
+    /// carrying the event count and timestamp of the new session's first event.
+    /// That event is read ahead and returned next.
+    /// The configuration (i.e. the custom printf event ID, symbolizer and
+    /// enabled features) carries over to the new session, the entry table history,
+    /// handle index and object stats start over, see `finished_sessions`.
+    pub fn read_event_auto_restart<R: Read>(
+        &mut self,
+        r: &mut R,
+    ) -> Result<Option<(EventCode, Event)>, Error> {
+        if let Some(event) = self.pending_event.take() {
+            return Ok(Some(event));
+        }
+        // A session boundary waiting on the rest of a truncated first event is
+        // returned once it's read
+        let first = loop {
+            match self.read_event(r) {
+                Err(Error::TraceRestarted(endianness)) => {
+                    self.restart(endianness, r)?;
+                    self.boundary_pending = true;
+                }
+                res if self.boundary_pending => break res?,
+                res => return res,
+            }
+        };
+        self.boundary_pending = false;
+        let boundary = match &first {
+            Some((_, event)) => SessionBoundaryEvent {
+                event_count: event.event_count(),
+                sequence: event.sequence(),
+                timestamp: event.timestamp(),
+                session: self.session,
+            },
+            None => SessionBoundaryEvent {
+                event_count: EventCount(0),
+                sequence: 0,
+                timestamp: Timestamp::zero(),
+                session: self.session,
+            },
+        };
+        self.pending_event = first;
+        Ok(Some((
+            EventCode::SESSION_BOUNDARY,
+            Event::SessionBoundary(boundary),
+        )))
+    }
+
+    /// Number of the current session, the first session is 0.
+    /// Only `read_event_auto_restart` moves on to the next session.
+    pub fn session(&self) -> u32 {
+        self.session
+    }
+
+    /// The entry table histories, handle indexes and object stats of the sessions
+    /// that ended with a trace restart, the current session's are kept as usual
+    pub fn finished_sessions(&self) -> &[FinishedSession] {
+        &self.finished_sessions
+    }
+
+    /// Take the records of the finished sessions, i.e. to avoid accumulating them
+    /// over a long running capture
+    pub fn take_finished_sessions(&mut self) -> Vec<FinishedSession> {
+        std::mem::take(&mut self.finished_sessions)
+    }
+
+    /// Read the header and entry table following the PSF word of a restarted trace
+    fn restart<R: Read>(&mut self, endianness: Endianness, r: &mut R) -> Result<(), Error> {
+        debug!("Restarting the trace");
+        let string_decoder = *self.parser.string_decoder();
+        let mut r = CountingReader {
+            inner: r,
+            count: &mut self.offset,
+        };
+        let Self {
+            protocol,
+            header,
+            timestamp_info,
+            mut entry_table,
+            diagnostics,
+            ..
+        } = Self::read_with_endianness_and_string_decoder(endianness, &mut r, string_decoder)?;
+        self.parser = self.parser.restarted(&header, &mut entry_table);
+        self.protocol = protocol;
+        self.header = header;
+        self.timestamp_info = timestamp_info;
+        self.entry_table = entry_table;
+        self.diagnostics.extend(diagnostics);
+        if let Some(repair) = &mut self.timestamp_repair {
+            repair.restart();
+        }
+        self.finished_sessions.push(FinishedSession {
+            session: self.session,
+            entry_table_history: self
+                .entry_table_history
+                .as_mut()
+                .map(|h| std::mem::replace(h, EntryTableHistory::new(h.policy()))),
+            handle_index: self.handle_index.as_mut().map(std::mem::take),
+            object_stats: self.object_stats.as_mut().map(std::mem::take),
+        });
+        self.session += 1;
+        self.publish_entry_table();
+        Ok(())
+    }
+
+    /// Like `read_event`, but parses the next event in place from the start of `buf`,
+    /// e.g. a memory mapped capture or a DMA buffer.
+    ///
+    /// Returns the number of bytes of `buf` consumed along with the event.
+    /// When `buf` ends part way through an event, all of it is consumed, no event
+    /// is returned and the partial event is kept like `read_event` does,
+    /// parsing resumes with the next slice. Only the bytes of such an event are copied.
+    /// An empty `buf` is the end of the stream.
+    pub fn read_event_from_slice(
+        &mut self,
+        buf: &[u8],
+    ) -> Result<(usize, Option<(EventCode, Event)>), Error> {
+        if buf.is_empty() {
+            return Ok((0, None));
+        }
+        let event_offset = self.offset;
+        let kept = self.partial_event.len();
+        let res = if kept == 0 {
+            match self
+                .parser
+                .next_event_from_slice(buf, &mut self.entry_table)
+            {
+                Err(Error::TruncatedEvent(_)) => {
+                    self.partial_event.extend_from_slice(buf);
+                    Ok((buf.len(), None))
+                }
+                res => res,
+            }
+        } else {
+            self.complete_partial_event(buf)
+        };
+        self.collect_diagnostics(event_offset);
+        let (consumed, mut res) = res?;
+        if let Some((code, event)) = &mut res {
+            self.offset += (kept + consumed) as u64;
+            self.process_event(event_offset, *code, event);
+        }
+        Ok((consumed, res))
+    }
+
+    /// Parse the event kept from the end of the previous slice, completed with the start
+    /// of `buf`. Returns the number of bytes of `buf` consumed.
+    fn complete_partial_event(
+        &mut self,
+        buf: &[u8],
+    ) -> Result<(usize, Option<(EventCode, Event)>), Error> {
+        // Events are made of a header and their parameters, except for custom printf
+        // events whose format string follows them, so only take more when needed
+        const MAX_EVENT_LEN: usize = 8 + EventParameterCount::MAX * 4;
+        let kept = self.partial_event.len();
+        let mut bytes = std::mem::take(&mut self.partial_event);
+        let mut taken = buf.len().min(MAX_EVENT_LEN);
+        let res = loop {
+            bytes.truncate(kept);
+            bytes.extend_from_slice(&buf[..taken]);
+            match self
+                .parser
+                .next_event_from_slice(&bytes, &mut self.entry_table)
+            {
+                Err(Error::TruncatedEvent(_)) if taken < buf.len() => taken = buf.len(),
+                Err(Error::TruncatedEvent(_)) => {
+                    self.partial_event = bytes;
+                    return Ok((buf.len(), None));
+                }
+                res => break res,
+            }
+        };
+        bytes.clear();
+        self.partial_event = bytes;
+        res.map(|(len, event)| (len - kept, event))
+    }
+
+    /// See `EventParser::visit_event`, truncated events are handled like `read_event`.
+    ///
+    /// Events go through the same timestamp repair, entry table history, handle index
+    /// and object stats as with `read_event`. A repaired timestamp is only seen in the
+    /// `EventHeader` handed to the visitor, except for `EventVisitor::visit_event`
+    /// where the event is copied to carry it too.
+    pub fn visit_event<R: Read, V: EventVisitor + ?Sized>(
+        &mut self,
+        r: &mut R,
+        visitor: &mut V,
+    ) -> Result<bool, Error> {
+        let event_offset = self.offset;
+        self.start_event();
+        let mut r = EventReader::new(r, &mut self.event_bytes, &mut self.offset);
+        let mut visitor = PostProcessVisitor {
+            visitor,
+            timestamp_repair: self.timestamp_repair.as_mut(),
+            processed: None,
+        };
+        let res = self
+            .parser
+            .visit_event(&mut r, &mut self.entry_table, &mut visitor);
+        let processed = visitor.processed;
+        self.collect_diagnostics(event_offset);
+        let res = self.check_truncated(res, Ok(false))?;
+        if let Some(processed) = processed.filter(|_| res) {
+            self.post_process(event_offset, processed);
+        }
+        Ok(res)
+    }
+
+    /// Number of bytes of the truncated event kept for the next read
+    pub fn partial_event_len(&self) -> usize {
+        self.partial_event.len()
+    }
+
+    /// Read up to `max_events` events, returns an empty `Vec` at the end of the stream
+    pub fn read_events<R: Read>(
+        &mut self,
+        r: &mut R,
+        max_events: usize,
+    ) -> Result<Vec<(EventCode, Event)>, Error> {
+        let mut events = Vec::with_capacity(max_events);
+        self.read_events_into(r, max_events, &mut events)?;
+        Ok(events)
+    }
+
+    /// Like `read_events`, but reuses the allocation of `events`, which is cleared first.
+    ///
+    /// Returns the number of events read, zero at the end of the stream.
+    /// On error, `events` contains the events read prior to the error.
+    pub fn read_events_into<R: Read>(
+        &mut self,
+        r: &mut R,
+        max_events: usize,
+        events: &mut Vec<(EventCode, Event)>,
+    ) -> Result<usize, Error> {
+        events.clear();
+        while events.len() < max_events {
+            match self.read_event(r)? {
+                Some(ev) => events.push(ev),
+                None => break,
+            }
+        }
+        Ok(events.len())
+    }
+
+    /// Move the bytes of a truncated event, if any, into the event buffer to be replayed.
+    /// Both buffers keep their allocation.
+    fn start_event(&mut self) {
+        self.event_bytes.clear();
+        std::mem::swap(&mut self.event_bytes, &mut self.partial_event);
+    }
+
+    /// Turn an end of stream part way through an event into `Error::TruncatedEvent`,
+    /// keeping the event's bytes for the next read
+    fn check_truncated<T: PartialEq>(
+        &mut self,
+        res: Result<T, Error>,
+        end_of_stream: Result<T, Error>,
+    ) -> Result<T, Error> {
+        let truncated = match &res {
+            _ if self.event_bytes.is_empty() => false,
+            Err(Error::Io(e)) => e.kind() == io::ErrorKind::UnexpectedEof,
+            Ok(v) => end_of_stream.as_ref().is_ok_and(|eos| eos == v),
+            Err(_) => false,
+        };
+        if truncated {
+            let len = self.event_bytes.len();
+            debug!(len, "Stream ended part way through an event");
+            self.offset -= len as u64;
+            std::mem::swap(&mut self.partial_event, &mut self.event_bytes);
+            Err(Error::TruncatedEvent(len))
+        } else {
+            self.event_bytes.clear();
+            res
+        }
+    }
+
+    fn collect_diagnostics(&mut self, event_offset: u64) {
+        if self.parser.has_diagnostics() {
+            self.diagnostics.extend(
+                self.parser
+                    .take_diagnostics()
+                    .into_iter()
+                    .map(|d| d.with_offset(event_offset)),
+            );
+        }
+    }
+}
+
+/// Replays the bytes of a previously truncated event before reading the input,
+/// tracking the number of bytes read and recording the current event's bytes
+struct EventReader<'a, R> {
+    inner: &'a mut R,
+    event_bytes: &'a mut Vec<u8>,
+    /// Position in `event_bytes` of the replayed bytes
+    replay_pos: usize,
+    count: &'a mut u64,
+}
+
+impl<'a, R: Read> EventReader<'a, R> {
+    fn new(inner: &'a mut R, event_bytes: &'a mut Vec<u8>, count: &'a mut u64) -> Self {
+        Self {
+            inner,
+            event_bytes,
+            replay_pos: 0,
+            count,
+        }
+    }
+}
+
+impl<R: Read> Read for EventReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = if self.replay_pos < self.event_bytes.len() {
+            let replay = &self.event_bytes[self.replay_pos..];
+            let n = replay.len().min(buf.len());
+            buf[..n].copy_from_slice(&replay[..n]);
+            self.replay_pos += n;
+            n
+        } else {
+            let n = self.inner.read(buf)?;
+            self.event_bytes.extend_from_slice(&buf[..n]);
+            self.replay_pos += n;
+            n
+        };
+        *self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// Counts the bytes read from the input, i.e. the header and entry table of a restarted trace
+struct CountingReader<'a, R> {
+    inner: &'a mut R,
+    count: &'a mut u64,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        *self.count += n as u64;
+        Ok(n)
+    }
+}
+
+/// What `RecorderData::post_process` needs to know about a decoded event
+#[derive(Copy, Clone, Debug)]
+struct ProcessedEvent {
+    code: EventCode,
+    event_count: EventCount,
+    timestamp: Timestamp,
+    /// The timestamp before it was repaired
+    original_timestamp: Option<Timestamp>,
+    /// See `Event::object_handle`
+    handle: Option<ObjectHandle>,
+    /// See `ObjectStats::event_handle`
+    stats_handle: Option<ObjectHandle>,
+}
+
+/// Repairs the timestamps of the events handed to `visitor` and keeps what
+/// `RecorderData::post_process` needs
+struct PostProcessVisitor<'a, V: ?Sized> {
+    visitor: &'a mut V,
+    timestamp_repair: Option<&'a mut TimestampRepair>,
+    processed: Option<ProcessedEvent>,
+}
+
+impl<V: ?Sized> PostProcessVisitor<'_, V> {
+    fn process(&mut self, header: &EventHeader, handle: Option<ObjectHandle>) -> EventHeader {
+        let mut header = *header;
+        let original_timestamp = self
+            .timestamp_repair
+            .as_mut()
+            .and_then(|repair| repair.repair_timestamp(&mut header.timestamp));
+        self.processed = Some(ProcessedEvent {
+            code: header.code,
+            event_count: header.event_count,
+            timestamp: header.timestamp,
+            original_timestamp,
+            handle,
+            stats_handle: handle,
+        });
+        header
+    }
+}
+
+impl<V: EventVisitor + ?Sized> EventVisitor for PostProcessVisitor<'_, V> {
+    fn visit_task_ready(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_task_ready(&header, handle, name, priority)
+    }
+
+    fn visit_task_resume(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_task_resume(&header, handle, name, priority)
+    }
+
+    fn visit_task_activate(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_task_activate(&header, handle, name, priority)
+    }
+
+    fn visit_isr_begin(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_isr_begin(&header, handle, name, priority)
+    }
+
+    fn visit_isr_resume(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: &str,
+        priority: Priority,
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_isr_resume(&header, handle, name, priority)
+    }
+
+    fn visit_memory_alloc(&mut self, header: &EventHeader, address: u32, size: u32, heap: &Heap) {
+        let header = self.process(header, None);
+        self.visitor
+            .visit_memory_alloc(&header, address, size, heap)
+    }
+
+    fn visit_memory_free(&mut self, header: &EventHeader, address: u32, size: u32, heap: &Heap) {
+        let header = self.process(header, None);
+        self.visitor.visit_memory_free(&header, address, size, heap)
+    }
+
+    fn visit_object_operation(
+        &mut self,
+        header: &EventHeader,
+        handle: ObjectHandle,
+        name: Option<&str>,
+        params: &[u32],
+    ) {
+        let header = self.process(header, Some(handle));
+        self.visitor
+            .visit_object_operation(&header, handle, name, params)
+    }
+
+    fn visit_event(&mut self, header: &EventHeader, event: &Event) {
+        let header = self.process(header, event.object_handle());
+        if let Some(processed) = &mut self.processed {
+            processed.stats_handle = ObjectStats::event_handle(event);
+        }
+        if header.timestamp == event.timestamp() {
+            self.visitor.visit_event(&header, event)
+        } else {
+            let mut event = event.clone();
+            *event.timestamp_mut() = header.timestamp;
+            self.visitor.visit_event(&header, &event)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// Two sessions of v14 data, the second one following a restart
+    pub(crate) const TRACE_V14: &[u8] =
+        include_bytes!("../../test_resources/fixtures/streaming/v14/trace.psf");
+
+    /// Length of the first session of `TRACE_V14`, up to the restart
+    pub(crate) const TRACE_V14_FIRST_SESSION_LEN: usize = 1456;
+
+    /// The recorder data of `TRACE_V14` and the data following its header
+    pub(crate) fn trace_v14() -> (RecorderData, &'static [u8]) {
+        let mut r = TRACE_V14;
+        let rd = RecorderData::find(&mut r).unwrap();
+        (rd, r)
     }
 }
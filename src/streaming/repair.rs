@@ -0,0 +1,234 @@
+//! Repair of streaming data files: copy the data up to the last event that
+//! could be decoded, dropping whatever follows, i.e. the garbage left by an
+//! interrupted capture.
+//!
+//! Events that decode but reference unknown objects are kept, only data that
+//! can't be decoded ends the copy. Anything preceding the first PSF word is
+//! dropped as well.
+
+use crate::streaming::event::EventId;
+use crate::streaming::{Error, HeaderInfo, RecorderData};
+use std::io::{self, Read, Write};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct RepairOptions {
+    /// Stop at the first trace restart, keeping only the first session
+    pub strip_restarts: bool,
+    /// See `RecorderData::set_custom_printf_event_id`, custom printf events
+    /// can't be decoded without it
+    pub custom_printf_event_id: Option<EventId>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RepairStats {
+    /// Events copied to the output
+    pub events: u64,
+    /// Sessions copied to the output
+    pub sessions: u64,
+    /// Whether sessions following a restart were dropped, see `RepairOptions::strip_restarts`
+    pub stripped_restarts: bool,
+    pub bytes_written: u64,
+    /// Input bytes that weren't copied, leading bytes before the first PSF
+    /// word included
+    pub bytes_dropped: u64,
+    /// Why the copy ended early, `None` when the whole input was valid
+    pub stop_reason: Option<String>,
+}
+
+/// Copy the valid part of the streaming data in `r` to `w`
+pub fn repair<R: Read, W: Write>(
+    r: R,
+    mut w: W,
+    options: RepairOptions,
+) -> Result<RepairStats, Error> {
+    let mut stats = RepairStats::default();
+    let mut r = TeeReader {
+        inner: r,
+        pending: Vec::new(),
+        total: 0,
+    };
+
+    let configure = |rd: &mut RecorderData| {
+        if let Some(id) = options.custom_printf_event_id {
+            rd.set_custom_printf_event_id(id);
+        }
+    };
+
+    let mut rd = RecorderData::find(&mut r)?;
+    configure(&mut rd);
+    // Skip the bytes searched before the PSF word
+    let psf_pos = find_psf_word(&r.pending).unwrap_or(0);
+    r.pending.drain(..psf_pos);
+    stats.sessions = 1;
+    commit(&mut r, &mut w, &mut stats)?;
+
+    loop {
+        match rd.read_event(&mut r) {
+            Ok(Some(_)) => {
+                stats.events += 1;
+                commit(&mut r, &mut w, &mut stats)?;
+            }
+            Ok(None) => break,
+            Err(Error::TraceRestarted(endianness)) => {
+                if options.strip_restarts {
+                    stats.stripped_restarts = true;
+                    stats.stop_reason = Some("Trace restarted".to_owned());
+                    break;
+                }
+                match RecorderData::read_with_endianness(endianness, &mut r) {
+                    Ok(new_rd) => {
+                        rd = new_rd;
+                        configure(&mut rd);
+                        stats.sessions += 1;
+                        commit(&mut r, &mut w, &mut stats)?;
+                    }
+                    Err(e) => {
+                        stats.stop_reason = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+            Err(e) if is_undecodable(&e) => {
+                stats.stop_reason = Some(e.to_string());
+                break;
+            }
+            // The event's bytes were all read, it's kept
+            Err(_) => {
+                stats.events += 1;
+                commit(&mut r, &mut w, &mut stats)?;
+            }
+        }
+    }
+
+    let remaining = io::copy(&mut r.inner, &mut io::sink())?;
+    stats.bytes_dropped = r.total + remaining - stats.bytes_written;
+    w.flush()?;
+    Ok(stats)
+}
+
+fn is_undecodable(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::Io(_)
+            | Error::TruncatedEvent(_)
+            | Error::InvalidEventParameterCount(..)
+            | Error::InvalidObjectHandle(_)
+            | Error::InvalidTimerCounter(_)
+            | Error::PSFEndiannessIdentifier(_)
+            | Error::KernelVersion(_)
+            | Error::InvalidEntryTableSymbolSize
+            | Error::InvalidEntryTableStateCount
+    )
+}
+
+fn find_psf_word(data: &[u8]) -> Option<usize> {
+    let le = HeaderInfo::PSF_LITTLE_ENDIAN.to_le_bytes();
+    let be = HeaderInfo::PSF_BIG_ENDIAN.to_le_bytes();
+    data.windows(4).position(|w| w == le || w == be)
+}
+
+fn commit<R, W: Write>(
+    r: &mut TeeReader<R>,
+    w: &mut W,
+    stats: &mut RepairStats,
+) -> Result<(), Error> {
+    w.write_all(&r.pending)?;
+    stats.bytes_written += r.pending.len() as u64;
+    r.pending.clear();
+    Ok(())
+}
+
+/// Keeps the bytes read since the last commit
+struct TeeReader<R> {
+    inner: R,
+    pending: Vec<u8>,
+    total: u64,
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pending.extend_from_slice(&buf[..n]);
+        self.total += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::recorder_data::test::{TRACE_V14, TRACE_V14_FIRST_SESSION_LEN};
+
+    fn options() -> RepairOptions {
+        RepairOptions {
+            strip_restarts: false,
+            custom_printf_event_id: Some(0x0FA0.into()),
+        }
+    }
+
+    #[test]
+    fn valid_input_is_copied_as_is() {
+        let session = &TRACE_V14[..TRACE_V14_FIRST_SESSION_LEN];
+        let mut out = Vec::new();
+        let stats = repair(session, &mut out, options()).unwrap();
+        assert_eq!(out, session);
+        assert_eq!(stats.sessions, 1);
+        assert!(stats.events > 0);
+        assert_eq!(stats.bytes_written, session.len() as u64);
+        assert_eq!(stats.bytes_dropped, 0);
+        assert_eq!(stats.stop_reason, None);
+        assert!(!stats.stripped_restarts);
+    }
+
+    #[test]
+    fn truncated_event_is_dropped() {
+        let session = &TRACE_V14[..TRACE_V14_FIRST_SESSION_LEN];
+        let valid = repair(session, io::sink(), options()).unwrap();
+
+        // Leading junk, the first session and half of an event header
+        let mut damaged = vec![0x00; 5];
+        damaged.extend_from_slice(session);
+        damaged.extend_from_slice(&[0x01, 0x10, 0x00]);
+        let mut out = Vec::new();
+        let stats = repair(damaged.as_slice(), &mut out, options()).unwrap();
+        assert_eq!(out, session);
+        assert_eq!(stats.events, valid.events);
+        assert_eq!(stats.bytes_dropped, 8);
+        assert!(stats.stop_reason.is_some());
+    }
+
+    #[test]
+    fn restart_with_a_truncated_header() {
+        // The second session's header ends early
+        let damaged = &TRACE_V14[..TRACE_V14_FIRST_SESSION_LEN + 8];
+        let mut out = Vec::new();
+        let stats = repair(damaged, &mut out, options()).unwrap();
+        assert_eq!(out, &TRACE_V14[..TRACE_V14_FIRST_SESSION_LEN]);
+        assert_eq!(stats.sessions, 1);
+        assert!(!stats.stripped_restarts);
+        assert_eq!(stats.bytes_dropped, 8);
+        assert!(stats.stop_reason.is_some());
+    }
+
+    #[test]
+    fn errors() {
+        // No PSF word
+        assert!(repair([0xAB; 64].as_slice(), io::sink(), options()).is_err());
+        // Truncated header
+        assert!(repair(&TRACE_V14[..32], io::sink(), options()).is_err());
+
+        struct FailingWriter;
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(matches!(
+            repair(TRACE_V14, FailingWriter, options()),
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+//! Matching of event sequences, e.g. a task blocking on a mutex followed by a
+//! priority inheritance within 100 ticks.
+//!
+//! A pattern is a list of steps, each a `Query` the next event of the sequence
+//! must match. Steps can bound the time since the previous step and require
+//! the event to refer to the same object as the first step's event. Other
+//! events may occur between the steps.
+//!
+//! ```
+//! use trace_recorder_parser::streaming::{Query, SequencePattern};
+//!
+//! let pattern = SequencePattern::new("inheritance", Query::parse("type == MutexTakeBlock").unwrap())
+//!     .followed_by(Query::parse("type == MutexGive").unwrap())
+//!     .same_object()
+//!     .within(100);
+//! ```
+
+use crate::streaming::event::{Event, EventCode, EventCount};
+use crate::streaming::Query;
+use crate::time::StreamingClock;
+use crate::time::Timestamp;
+use crate::types::ObjectHandle;
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Step {
+    query: Query,
+    /// Maximum ticks since the previous step
+    within: Option<u64>,
+    same_object: bool,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SequencePattern {
+    name: String,
+    steps: Vec<Step>,
+}
+
+impl SequencePattern {
+    pub fn new<S: Into<String>>(name: S, first: Query) -> Self {
+        Self {
+            name: name.into(),
+            steps: vec![Step {
+                query: first,
+                within: None,
+                same_object: false,
+            }],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add a step
+    pub fn followed_by(mut self, query: Query) -> Self {
+        self.steps.push(Step {
+            query,
+            within: None,
+            same_object: false,
+        });
+        self
+    }
+
+    /// The last step must occur at most `ticks` after the previous one
+    pub fn within(mut self, ticks: u64) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.within = Some(ticks);
+        }
+        self
+    }
+
+    /// The last step's event must refer to the same object as the first step's event
+    pub fn same_object(mut self) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.same_object = true;
+        }
+        self
+    }
+}
+
+/// An event of a matched sequence
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct MatchedEvent {
+    pub event_count: EventCount,
+    /// Ticks since the first event processed by the matcher
+    pub timestamp: Timestamp,
+    pub object_handle: Option<ObjectHandle>,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SequenceMatch {
+    pub pattern: String,
+    /// One event per step
+    pub events: Vec<MatchedEvent>,
+}
+
+impl SequenceMatch {
+    /// Ticks between the first and the last event of the sequence
+    pub fn duration(&self) -> u64 {
+        match (self.events.first(), self.events.last()) {
+            (Some(first), Some(last)) => last.timestamp.ticks() - first.timestamp.ticks(),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Partial {
+    pattern: usize,
+    events: Vec<MatchedEvent>,
+}
+
+/// Finds the occurrences of a set of patterns in a stream of events
+#[derive(Clone, Debug)]
+pub struct SequenceMatcher {
+    patterns: Vec<SequencePattern>,
+    partials: Vec<Partial>,
+    max_partials: usize,
+    clock: StreamingClock,
+}
+
+impl SequenceMatcher {
+    /// Sequences started but not yet matched are limited to this many, the
+    /// oldest are abandoned first
+    pub const DEFAULT_MAX_PARTIALS: usize = 1024;
+
+    pub fn new() -> Self {
+        Self {
+            patterns: Vec::new(),
+            partials: Vec::new(),
+            max_partials: Self::DEFAULT_MAX_PARTIALS,
+            clock: StreamingClock::default(),
+        }
+    }
+
+    pub fn with_pattern(mut self, pattern: SequencePattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    pub fn with_max_partials(mut self, max_partials: usize) -> Self {
+        self.max_partials = max_partials;
+        self
+    }
+
+    /// Number of sequences started but not yet matched
+    pub fn partials(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Process every event of the stream, in order, returns the sequences
+    /// the event completes
+    pub fn process(&mut self, event_code: EventCode, event: &Event) -> Vec<SequenceMatch> {
+        let matched = MatchedEvent {
+            event_count: event.event_count(),
+            timestamp: self.clock.time(event.timestamp()),
+            object_handle: event.object_handle(),
+        };
+        let now = matched.timestamp.ticks();
+        let mut completed = Vec::new();
+
+        // Advance the started sequences, dropping those that timed out
+        let patterns = &self.patterns;
+        self.partials.retain_mut(|partial| {
+            let steps = &patterns[partial.pattern].steps;
+            let step = &steps[partial.events.len()];
+            let previous = partial.events[partial.events.len() - 1];
+            let elapsed = now - previous.timestamp.ticks();
+            if step.within.is_some_and(|within| elapsed > within) {
+                return false;
+            }
+            let same_object =
+                !step.same_object || matched.object_handle == partial.events[0].object_handle;
+            if same_object && step.query.matches(event_code, event) {
+                partial.events.push(matched);
+                if partial.events.len() == steps.len() {
+                    completed.push(SequenceMatch {
+                        pattern: patterns[partial.pattern].name.clone(),
+                        events: std::mem::take(&mut partial.events),
+                    });
+                    return false;
+                }
+            }
+            true
+        });
+
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            if !pattern.steps[0].query.matches(event_code, event) {
+                continue;
+            }
+            if pattern.steps.len() == 1 {
+                completed.push(SequenceMatch {
+                    pattern: pattern.name.clone(),
+                    events: vec![matched],
+                });
+            } else {
+                self.partials.push(Partial {
+                    pattern: i,
+                    events: vec![matched],
+                });
+            }
+        }
+        if self.partials.len() > self.max_partials {
+            let excess = self.partials.len() - self.max_partials;
+            self.partials.drain(..excess);
+        }
+
+        completed
+    }
+}
+
+impl Default for SequenceMatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventType, MutexEvent, TaskEvent};
+    use crate::types::{ObjectName, Priority};
+
+    fn code(et: EventType, params: u16) -> EventCode {
+        EventCode((u16::from(crate::streaming::event::EventId::from(et)) | (params << 12)).into())
+    }
+
+    fn mutex_take_block(count: u16, t: u64, handle: u32) -> (EventCode, Event) {
+        (
+            code(EventType::MutexTakeBlock, 2),
+            Event::MutexTakeBlock(MutexEvent {
+                event_count: EventCount(count),
+                sequence: count.into(),
+                timestamp: Timestamp(t),
+                handle: ObjectHandle::new(handle).unwrap(),
+                name: None,
+                ticks_to_wait: None,
+            }),
+        )
+    }
+
+    fn inherit(count: u16, t: u64) -> (EventCode, Event) {
+        (
+            code(EventType::TaskPriorityInherit, 2),
+            Event::TaskPriorityInherit(TaskEvent {
+                event_count: EventCount(count),
+                sequence: count.into(),
+                timestamp: Timestamp(t),
+                handle: ObjectHandle::new(0x100).unwrap(),
+                name: ObjectName("low".into()),
+                priority: Priority(5),
+            }),
+        )
+    }
+
+    #[test]
+    fn sequences_within_deadline() {
+        let mut m = SequenceMatcher::new().with_pattern(
+            SequencePattern::new(
+                "inheritance",
+                Query::parse("type == MutexTakeBlock").unwrap(),
+            )
+            .followed_by(Query::parse("type == TaskPriorityInherit").unwrap())
+            .within(100),
+        );
+        let events = [
+            mutex_take_block(0, 0, 0x10),
+            inherit(1, 50),
+            mutex_take_block(2, 100, 0x10),
+            // Too late
+            inherit(3, 300),
+        ];
+        let matches: Vec<_> = events
+            .iter()
+            .flat_map(|(code, ev)| m.process(*code, ev))
+            .collect();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, "inheritance");
+        assert_eq!(matches[0].duration(), 50);
+        assert_eq!(matches[0].events[1].event_count, EventCount(1));
+        assert_eq!(m.partials(), 0);
+    }
+
+    #[test]
+    fn same_object_steps() {
+        let mut m = SequenceMatcher::new().with_pattern(
+            SequencePattern::new("retake", Query::parse("type == MutexTakeBlock").unwrap())
+                .followed_by(Query::parse("type == MutexTakeBlock").unwrap())
+                .same_object(),
+        );
+        let events = [
+            mutex_take_block(0, 0, 0x10),
+            mutex_take_block(1, 10, 0x20),
+            mutex_take_block(2, 20, 0x10),
+        ];
+        let matches: Vec<_> = events
+            .iter()
+            .flat_map(|(code, ev)| m.process(*code, ev))
+            .collect();
+        assert_eq!(matches.len(), 1);
+        let counts: Vec<_> = matches[0].events.iter().map(|e| e.event_count).collect();
+        assert_eq!(counts, vec![EventCount(0), EventCount(2)]);
+    }
+}
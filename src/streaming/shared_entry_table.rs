@@ -0,0 +1,115 @@
+use crate::streaming::EntryTable;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The entry table of a `RecorderData`, shared with other threads, see
+/// `RecorderData::share_entry_table`.
+///
+/// Event decoding stays sequential, events update the table as they're parsed,
+/// the table is published again after each event that changed it.
+/// Readers take immutable snapshots and never hold the lock while using them,
+/// so workers can resolve names and classes while the parser carries on.
+///
+/// Comparisons use the latest published tables.
+#[derive(Clone, Debug, Default)]
+pub struct SharedEntryTable(Arc<RwLock<Arc<EntryTable>>>);
+
+impl SharedEntryTable {
+    pub fn new(table: EntryTable) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(table))))
+    }
+
+    /// The latest published table
+    pub fn snapshot(&self) -> Arc<EntryTable> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Publish the table, unless it's unchanged since the last time.
+    /// Changes are found from `EntryTable::generation`, the tables aren't compared.
+    pub(crate) fn publish(&self, table: &EntryTable) {
+        let published = self
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .generation();
+        if published != table.generation() {
+            *self.0.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(table.clone());
+        }
+    }
+}
+
+impl PartialEq for SharedEntryTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.snapshot() == other.snapshot()
+    }
+}
+
+impl Eq for SharedEntryTable {}
+
+impl PartialOrd for SharedEntryTable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SharedEntryTable {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.snapshot().cmp(&other.snapshot())
+    }
+}
+
+impl Hash for SharedEntryTable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.snapshot().hash(state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{ObjectClass, ObjectHandle};
+
+    #[test]
+    fn publish_changed_tables_only() {
+        let handle = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut table = EntryTable::default();
+        let shared = SharedEntryTable::new(table.clone());
+        let other = shared.clone();
+
+        let before = other.snapshot();
+        shared.publish(&table);
+        assert!(Arc::ptr_eq(&before, &other.snapshot()));
+
+        table.set_class(handle, ObjectClass::Queue);
+        shared.publish(&table);
+        // Snapshots taken before are left as they were
+        assert_eq!(before.class(handle), None);
+        assert_eq!(other.snapshot().class(handle), Some(ObjectClass::Queue));
+        assert_eq!(shared, other);
+
+        // Setting the same class again doesn't change the generation
+        let published = other.snapshot();
+        table.set_class(handle, ObjectClass::Queue);
+        shared.publish(&table);
+        assert!(Arc::ptr_eq(&published, &other.snapshot()));
+    }
+
+    #[test]
+    fn snapshot_from_another_thread() {
+        let handle = ObjectHandle::new(0x2000_1000).unwrap();
+        let mut table = EntryTable::default();
+        let shared = SharedEntryTable::new(table.clone());
+        table.set_class(handle, ObjectClass::Task);
+        shared.publish(&table);
+
+        let reader = shared.clone();
+        let class = std::thread::spawn(move || reader.snapshot().class(handle))
+            .join()
+            .unwrap();
+        assert_eq!(class, Some(ObjectClass::Task));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::streaming::event::{Event, EventCode};
+use crate::streaming::query::{Expr, Field, Op, Query};
+use crate::streaming::EntryTable;
+
+/// A sub-trace of the events between a start and stop trigger
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct TraceSlice {
+    /// Zero based index of the slice within the trace
+    pub index: usize,
+    /// The entry table as it was when the slice started, needed to
+    /// decode the objects referenced by the slice's events
+    pub entry_table: EntryTable,
+    pub events: Vec<(EventCode, Event)>,
+    /// False when the trace ended before the stop trigger was seen
+    pub complete: bool,
+}
+
+/// Slices a stream of events into sub-traces delimited by start and stop triggers.
+///
+/// ```
+/// use trace_recorder_parser::streaming::TraceSlicer;
+///
+/// // User events on the "test" channel containing "START" or "STOP"
+/// let slicer = TraceSlicer::user_event_markers("test", "START", "STOP");
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TraceSlicer {
+    start: Query,
+    stop: Query,
+    include_markers: bool,
+    num_slices: usize,
+    current: Option<TraceSlice>,
+}
+
+impl TraceSlicer {
+    pub fn new(start: Query, stop: Query) -> Self {
+        Self {
+            start,
+            stop,
+            include_markers: true,
+            num_slices: 0,
+            current: None,
+        }
+    }
+
+    /// Triggers on user events on `channel` whose formatted string contains
+    /// `start_text` or `stop_text`
+    pub fn user_event_markers(channel: &str, start_text: &str, stop_text: &str) -> Self {
+        let marker = |text: &str| {
+            Query::new(
+                Expr::compare(Field::Channel, Op::Eq, channel).and(Expr::compare(
+                    Field::Message,
+                    Op::Contains,
+                    text,
+                )),
+            )
+        };
+        Self::new(marker(start_text), marker(stop_text))
+    }
+
+    /// Whether the start and stop marker events are included in the slices, defaults to true
+    pub fn include_markers(mut self, include: bool) -> Self {
+        self.include_markers = include;
+        self
+    }
+
+    /// Whether a slice is currently being collected
+    pub fn is_active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Process the next event, returning a slice when the stop trigger is seen.
+    /// `entry_table` is the table used to parse the event.
+    pub fn process(
+        &mut self,
+        event_code: EventCode,
+        event: &Event,
+        entry_table: &EntryTable,
+    ) -> Option<TraceSlice> {
+        match self.current.as_mut() {
+            None => {
+                if self.start.matches(event_code, event) {
+                    let mut slice = TraceSlice {
+                        index: self.num_slices,
+                        entry_table: entry_table.clone(),
+                        events: Vec::new(),
+                        complete: false,
+                    };
+                    if self.include_markers {
+                        slice.events.push((event_code, event.clone()));
+                    }
+                    self.num_slices += 1;
+                    self.current = Some(slice);
+                }
+                None
+            }
+            Some(slice) => {
+                if self.stop.matches(event_code, event) {
+                    if self.include_markers {
+                        slice.events.push((event_code, event.clone()));
+                    }
+                    let mut slice = self.current.take()?;
+                    slice.complete = true;
+                    Some(slice)
+                } else {
+                    slice.events.push((event_code, event.clone()));
+                    None
+                }
+            }
+        }
+    }
+
+    /// Take the partially collected slice, if any, i.e. at the end of the trace
+    pub fn finish(&mut self) -> Option<TraceSlice> {
+        self.current.take()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::test_event;
+
+    fn count_eq(count: u64) -> Query {
+        Query::new(Expr::compare(Field::Count, Op::Eq, count))
+    }
+
+    fn counts(slice: &TraceSlice) -> Vec<u16> {
+        slice
+            .events
+            .iter()
+            .map(|(_, ev)| ev.event_count().into())
+            .collect()
+    }
+
+    fn run(slicer: &mut TraceSlicer, events: std::ops::Range<u16>) -> Vec<TraceSlice> {
+        let entry_table = EntryTable::default();
+        events
+            .filter_map(|count| {
+                let (code, ev) = test_event(count % 8, count.into());
+                slicer.process(code, &ev, &entry_table)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn slices_between_triggers() {
+        let mut slicer = TraceSlicer::new(count_eq(2), count_eq(4));
+        // Stop before start is ignored, the second slice is left open
+        let slices = run(&mut slicer, 3..20);
+        assert_eq!(slices.len(), 1);
+        assert_eq!(slices[0].index, 0);
+        assert!(slices[0].complete);
+        assert_eq!(counts(&slices[0]), vec![2, 3, 4]);
+
+        assert!(slicer.is_active());
+        let partial = slicer.finish().unwrap();
+        assert_eq!(partial.index, 1);
+        assert!(!partial.complete);
+        assert_eq!(counts(&partial), vec![2, 3]);
+        assert!(!slicer.is_active());
+        assert!(slicer.finish().is_none());
+    }
+
+    #[test]
+    fn excluded_markers() {
+        let mut slicer = TraceSlicer::new(count_eq(1), count_eq(3)).include_markers(false);
+        let slices = run(&mut slicer, 0..4);
+        assert_eq!(counts(&slices[0]), vec![2]);
+
+        // Back to back triggers make an empty slice
+        let mut slicer = TraceSlicer::new(count_eq(1), count_eq(2)).include_markers(false);
+        let slices = run(&mut slicer, 0..4);
+        assert!(slices[0].events.is_empty());
+        assert!(slices[0].complete);
+    }
+
+    #[test]
+    fn start_trigger_within_a_slice_is_an_ordinary_event() {
+        let start = Query::new(Expr::compare(Field::Count, Op::Lt, 2u64));
+        let mut slicer = TraceSlicer::new(start, count_eq(3));
+        let slices = run(&mut slicer, 0..4);
+        assert_eq!(slices.len(), 1);
+        assert_eq!(counts(&slices[0]), vec![0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::streaming::annotate::Annotate;
+use crate::streaming::event::{AnnotationEvent, Event, EventCode, EventType, TrackingEventCounter};
+use crate::streaming::gaps::MarkGaps;
+use crate::streaming::merge::MergeCapture;
+use crate::time::{Frequency, StreamingInstant, Timestamp};
+use crate::types::{ObjectHandle, TaskName};
+use std::collections::BTreeMap;
+
+/// Per-task counters of a `TraceStats`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct TaskStats {
+    pub name: Option<TaskName>,
+    /// Number of events that occurred while the task was running
+    pub events: u64,
+    /// Number of times the task was switched in
+    pub switched_in: u64,
+    /// Bytes allocated while the task was running
+    pub bytes_allocated: u64,
+    /// Bytes freed while the task was running
+    pub bytes_freed: u64,
+}
+
+/// Aggregate statistics of a stream of events, updated one event at a time.
+///
+/// See `EventIteratorExt::stats` for sampling them while iterating.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct TraceStats {
+    pub events: u64,
+    /// Events detected as dropped from discontinuities in the event counter
+    pub dropped_events: u64,
+    pub events_by_type: BTreeMap<EventType, u64>,
+    pub first_timestamp: Option<Timestamp>,
+    /// Time of the latest event relative to the first, with timer rollovers accounted for
+    pub elapsed: Timestamp,
+    /// Smallest `Heap::current` carried by a memory event
+    pub min_heap_current: Option<u32>,
+    /// Largest `Heap::current` carried by a memory event
+    pub max_heap_current: Option<u32>,
+    pub tasks: BTreeMap<ObjectHandle, TaskStats>,
+
+    counter: Option<TrackingEventCounter>,
+    instant: StreamingInstant,
+    running_task: Option<ObjectHandle>,
+}
+
+impl TraceStats {
+    pub fn new() -> Self {
+        Self {
+            events: 0,
+            dropped_events: 0,
+            events_by_type: BTreeMap::new(),
+            first_timestamp: None,
+            elapsed: Timestamp::zero(),
+            min_heap_current: None,
+            max_heap_current: None,
+            tasks: BTreeMap::new(),
+            counter: None,
+            instant: StreamingInstant::zero(),
+            running_task: None,
+        }
+    }
+
+    /// Update with the next event, pseudo-events such as `Event::Dropped` are ignored
+    pub fn update(&mut self, event_code: EventCode, event: &Event) {
+        if matches!(
+            event,
+            Event::Dropped(_) | Event::Annotation(_) | Event::SessionBoundary(_)
+        ) {
+            return;
+        }
+        self.events += 1;
+        *self
+            .events_by_type
+            .entry(event_code.event_type())
+            .or_default() += 1;
+
+        let event_count = event.event_count();
+        match self.counter.as_mut() {
+            Some(counter) => self.dropped_events += counter.update(event_count).unwrap_or(0),
+            None => {
+                let mut counter = TrackingEventCounter::zero();
+                counter.set_initial_count(event_count);
+                self.counter = Some(counter);
+            }
+        }
+
+        let timestamp = event.timestamp();
+        match self.first_timestamp {
+            Some(first) => {
+                self.elapsed = self.instant.elapsed(timestamp) - first;
+            }
+            None => {
+                self.first_timestamp = Some(timestamp);
+                self.instant.elapsed(timestamp);
+            }
+        }
+
+        match event {
+            Event::TraceStart(ev) => {
+                self.running_task = Some(ev.current_task_handle);
+                self.task(ev.current_task_handle).name = Some(ev.current_task.clone());
+            }
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.running_task = Some(ev.handle);
+                let task = self.task(ev.handle);
+                task.name = Some(ev.name.clone());
+                task.switched_in += 1;
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.running_task = None,
+            Event::MemoryAlloc(ev) | Event::MemoryFree(ev) => {
+                let current = ev.heap.current;
+                self.min_heap_current =
+                    Some(self.min_heap_current.map_or(current, |m| m.min(current)));
+                self.max_heap_current =
+                    Some(self.max_heap_current.map_or(current, |m| m.max(current)));
+            }
+            _ => (),
+        }
+
+        if let Some(handle) = self.running_task {
+            let task = self.task(handle);
+            task.events += 1;
+            match event {
+                Event::MemoryAlloc(ev) => task.bytes_allocated += u64::from(ev.size),
+                Event::MemoryFree(ev) => task.bytes_freed += u64::from(ev.size),
+                _ => (),
+            }
+        }
+    }
+
+    /// Average number of events per second, `None` until the events span some time
+    /// or when the timer frequency is unknown
+    pub fn events_per_sec(&self, frequency: Frequency) -> Option<f64> {
+        self.per_sec(self.events, frequency)
+    }
+
+    /// Average number of dropped events per second, see `events_per_sec`
+    pub fn dropped_events_per_sec(&self, frequency: Frequency) -> Option<f64> {
+        self.per_sec(self.dropped_events, frequency)
+    }
+
+    fn per_sec(&self, n: u64, frequency: Frequency) -> Option<f64> {
+        let secs = self.elapsed.to_time(frequency).duration()?.as_secs_f64();
+        (secs > 0.0).then(|| n as f64 / secs)
+    }
+
+    fn task(&mut self, handle: ObjectHandle) -> &mut TaskStats {
+        self.tasks.entry(handle).or_default()
+    }
+}
+
+impl Default for TraceStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An iterator adapter that updates a `TraceStats` with each event it yields,
+/// see `EventIteratorExt::stats`
+#[derive(Clone, Debug)]
+pub struct Stats<I> {
+    iter: I,
+    stats: TraceStats,
+}
+
+impl<I> Stats<I> {
+    /// The statistics of the events yielded so far
+    pub fn trace_stats(&self) -> &TraceStats {
+        &self.stats
+    }
+
+    pub fn into_stats(self) -> TraceStats {
+        self.stats
+    }
+}
+
+impl<I> Iterator for Stats<I>
+where
+    I: Iterator<Item = (EventCode, Event)>,
+{
+    type Item = (EventCode, Event);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (code, event) = self.iter.next()?;
+        self.stats.update(code, &event);
+        Some((code, event))
+    }
+}
+
+pub trait EventIteratorExt: Iterator<Item = (EventCode, Event)> + Sized {
+    /// Collect `TraceStats` while iterating, without a second pass over the events
+    fn stats(self) -> Stats<Self> {
+        Stats {
+            iter: self,
+            stats: TraceStats::new(),
+        }
+    }
+
+    /// Insert an `Event::Dropped` pseudo-event wherever the event counter
+    /// shows that the recorder dropped events
+    fn mark_gaps(self) -> MarkGaps<Self> {
+        MarkGaps::new(self)
+    }
+
+    /// Insert the annotations as `Event::Annotation` pseudo-events, each one
+    /// before the first event that isn't earlier than it
+    fn annotate<A: IntoIterator<Item = AnnotationEvent>>(self, annotations: A) -> Annotate<Self> {
+        Annotate::new(self, annotations)
+    }
+
+    /// Merge with the events of another capture of the same device, i.e. after
+    /// the host restarted its capture, skipping the events present in both
+    fn merge_capture<B>(self, other: B) -> MergeCapture<Self, B::IntoIter>
+    where
+        B: IntoIterator<Item = (EventCode, Event)>,
+    {
+        MergeCapture::new(self, other.into_iter())
+    }
+}
+
+impl<I: Iterator<Item = (EventCode, Event)>> EventIteratorExt for I {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, EventId, MemoryEvent, TaskEvent};
+    use crate::types::{Heap, ObjectName, Priority};
+
+    fn code(event_type: EventType) -> EventCode {
+        EventCode(EventId::from(event_type).0.into())
+    }
+
+    fn activate(count: u16, timestamp: u64, handle: ObjectHandle) -> (EventCode, Event) {
+        (
+            code(EventType::TaskActivate),
+            Event::TaskActivate(TaskEvent {
+                event_count: EventCount(count),
+                sequence: count.into(),
+                timestamp: Timestamp(timestamp),
+                handle,
+                name: ObjectName("t".into()),
+                priority: Priority(1),
+            }),
+        )
+    }
+
+    fn alloc(count: u16, timestamp: u64, size: u32, current: u32) -> (EventCode, Event) {
+        (
+            code(EventType::MemoryAlloc),
+            Event::MemoryAlloc(MemoryEvent {
+                event_count: EventCount(count),
+                sequence: count.into(),
+                timestamp: Timestamp(timestamp),
+                address: 0x2000_0000,
+                size,
+                heap: Heap {
+                    current,
+                    high_water_mark: current,
+                    max: 0,
+                },
+                symbolized: None,
+                task: None,
+                task_name: None,
+            }),
+        )
+    }
+
+    #[test]
+    fn stats_sampled_mid_stream() {
+        let task = ObjectHandle::new(0x2000_1000).unwrap();
+        let events = vec![
+            alloc(1, 0xFFFF_FF00, 32, 32),
+            activate(2, 0xFFFF_FFF0, task),
+            // Events 3 and 4 were dropped, the timer rolled over
+            alloc(5, 0x0000_0100, 16, 48),
+        ];
+        let mut iter = events.into_iter().stats();
+        iter.next().unwrap();
+        assert_eq!(iter.trace_stats().events, 1);
+        assert_eq!(iter.trace_stats().min_heap_current, Some(32));
+
+        assert_eq!(iter.by_ref().count(), 2);
+        let stats = iter.into_stats();
+        assert_eq!(stats.events, 3);
+        assert_eq!(stats.dropped_events, 2);
+        assert_eq!(stats.elapsed, Timestamp(0x200));
+        assert_eq!(stats.events_by_type[&EventType::MemoryAlloc], 2);
+        assert_eq!(stats.max_heap_current, Some(48));
+        assert_eq!(stats.events_per_sec(Frequency(0x200)), Some(3.0));
+        assert_eq!(stats.events_per_sec(Frequency(0)), None);
+
+        let task_stats = &stats.tasks[&task];
+        assert_eq!(task_stats.switched_in, 1);
+        assert_eq!(task_stats.events, 2);
+        assert_eq!(task_stats.bytes_allocated, 16);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::streaming::EntryTable;
+use crate::types::{ObjectClass, ObjectHandle, SymbolString};
+use std::collections::BTreeMap;
+
+/// An externally provided object handle to name/class map.
+///
+/// Streaming captures started mid-run won't contain the ObjectName events
+/// for objects created before the capture started. A symbol map, either loaded
+/// from a JSON/YAML file or taken from a previous capture's entry table,
+/// can be used to pre-populate the entry table with `EntryTable::seed`.
+///
+/// The JSON/YAML format is a map of handles (decimal or `0x` prefixed hex) to either
+/// a name or a name and class:
+/// ```json
+/// {
+///   "0x20001000": "MyTask",
+///   "0x20002000": { "name": "MyQueue", "class": "Queue" }
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct SymbolMap(BTreeMap<ObjectHandle, SymbolMapEntry>);
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SymbolMapEntry {
+    pub symbol: SymbolString,
+    pub class: Option<ObjectClass>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SymbolMapError {
+    #[error("Invalid object handle '{0}' in symbol map")]
+    InvalidHandle(String),
+
+    #[error("Invalid object class '{0}' in symbol map")]
+    InvalidClass(String),
+
+    #[cfg(feature = "json")]
+    #[error("Failed to parse JSON symbol map")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("Failed to parse YAML symbol map")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+impl SymbolMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a map from the named entries of an existing entry table,
+    /// i.e. from a previous capture of the same system
+    pub fn from_entry_table(table: &EntryTable) -> Self {
+        Self(
+            table
+                .entries()
+                .iter()
+                .filter_map(|(handle, entry)| {
+                    entry.symbol.as_ref().map(|sym| {
+                        (
+                            *handle,
+                            SymbolMapEntry {
+                                symbol: sym.clone(),
+                                class: entry.class,
+                            },
+                        )
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn insert<S: Into<String>>(
+        &mut self,
+        handle: ObjectHandle,
+        name: S,
+        class: Option<ObjectClass>,
+    ) {
+        self.0.insert(
+            handle,
+            SymbolMapEntry {
+                symbol: SymbolString(name.into().into()),
+                class,
+            },
+        );
+    }
+
+    pub fn get(&self, handle: ObjectHandle) -> Option<&SymbolMapEntry> {
+        self.0.get(&handle)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ObjectHandle, &SymbolMapEntry)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json_str(s: &str) -> Result<Self, SymbolMapError> {
+        let raw: BTreeMap<String, serde_impl::RawEntry> = serde_json::from_str(s)?;
+        serde_impl::from_raw(raw)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json_reader<R: std::io::Read>(r: R) -> Result<Self, SymbolMapError> {
+        let raw: BTreeMap<String, serde_impl::RawEntry> = serde_json::from_reader(r)?;
+        serde_impl::from_raw(raw)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(s: &str) -> Result<Self, SymbolMapError> {
+        let raw: BTreeMap<String, serde_impl::RawEntry> = serde_yaml::from_str(s)?;
+        serde_impl::from_raw(raw)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_reader<R: std::io::Read>(r: R) -> Result<Self, SymbolMapError> {
+        let raw: BTreeMap<String, serde_impl::RawEntry> = serde_yaml::from_reader(r)?;
+        serde_impl::from_raw(raw)
+    }
+}
+
+#[cfg(any(feature = "json", feature = "yaml"))]
+mod serde_impl {
+    use super::{SymbolMap, SymbolMapError};
+    use crate::types::{ObjectClass, ObjectHandle};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum RawEntry {
+        Name(String),
+        Full { name: String, class: Option<String> },
+    }
+
+    pub(super) fn from_raw(raw: BTreeMap<String, RawEntry>) -> Result<SymbolMap, SymbolMapError> {
+        let mut map = SymbolMap::new();
+        for (handle_str, entry) in raw.into_iter() {
+            let trimmed = handle_str.trim();
+            let handle = if let Some(hex) = trimmed
+                .strip_prefix("0x")
+                .or_else(|| trimmed.strip_prefix("0X"))
+            {
+                u32::from_str_radix(hex, 16).ok()
+            } else {
+                trimmed.parse::<u32>().ok()
+            }
+            .and_then(ObjectHandle::new)
+            .ok_or_else(|| SymbolMapError::InvalidHandle(handle_str.clone()))?;
+            let (name, class) = match entry {
+                RawEntry::Name(name) => (name, None),
+                RawEntry::Full { name, class } => (name, class),
+            };
+            let class = class
+                .map(|c| ObjectClass::from_str(&c).map_err(|_| SymbolMapError::InvalidClass(c)))
+                .transpose()?;
+            map.insert(handle, name, class);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json_symbol_map() {
+        let map = SymbolMap::from_json_str(
+            r#"{ "0x20001000": "MyTask", "536879104": { "name": "MyQueue", "class": "queue" } }"#,
+        )
+        .unwrap();
+        assert_eq!(map.len(), 2);
+        let e = map.get(ObjectHandle::new(0x2000_1000).unwrap()).unwrap();
+        assert_eq!(e.symbol.as_ref(), "MyTask");
+        assert_eq!(e.class, None);
+        let e = map.get(ObjectHandle::new(0x2000_2000).unwrap()).unwrap();
+        assert_eq!(e.symbol.as_ref(), "MyQueue");
+        assert_eq!(e.class, Some(ObjectClass::Queue));
+
+        let mut table = EntryTable::default();
+        table.seed(&map);
+        assert_eq!(
+            table.symbol_handle("MyQueue", Some(ObjectClass::Queue)),
+            ObjectHandle::new(0x2000_2000)
+        );
+
+        assert!(matches!(
+            SymbolMap::from_json_str(r#"{ "0x0": "Bad" }"#),
+            Err(SymbolMapError::InvalidHandle(_))
+        ));
+    }
+}
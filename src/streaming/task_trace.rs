@@ -0,0 +1,219 @@
+use crate::streaming::event::{Event, EventCode};
+use crate::types::{ObjectClass, ObjectHandle, TaskName};
+use std::collections::BTreeMap;
+
+/// The events of a single task
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct TaskTrace {
+    pub handle: ObjectHandle,
+    pub name: Option<TaskName>,
+    /// The events that occurred while the task was running, plus the
+    /// scheduling events (ready, begin/resume, priority changes, notifications, etc)
+    /// that target the task
+    pub events: Vec<(EventCode, Event)>,
+}
+
+/// Splits a stream of events into per-task sub-traces.
+///
+/// Events are attributed to the task that was running when they occurred,
+/// events that occur in ISR context are only attributed to the task they target.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct TaskTraceSplitter {
+    /// Only collect these tasks, all tasks when empty
+    filter: Vec<ObjectHandle>,
+    running_task: Option<ObjectHandle>,
+    traces: BTreeMap<ObjectHandle, TaskTrace>,
+}
+
+impl TaskTraceSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only collect the sub-traces of the given tasks
+    pub fn with_tasks<I: IntoIterator<Item = ObjectHandle>>(tasks: I) -> Self {
+        Self {
+            filter: tasks.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    /// The task currently running, `None` when in ISR context or before the
+    /// first context switch
+    pub fn running_task(&self) -> Option<ObjectHandle> {
+        self.running_task
+    }
+
+    pub fn process(&mut self, event_code: EventCode, event: &Event) {
+        let target = Self::scheduling_target(event);
+
+        // Update the running context before attributing the event so that a
+        // context switch is part of the trace of the task being switched in
+        match event {
+            Event::TraceStart(ev) => {
+                self.running_task = Some(ev.current_task_handle);
+                self.set_name(ev.current_task_handle, &ev.current_task);
+            }
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.running_task = Some(ev.handle);
+            }
+            Event::IsrBegin(_) | Event::IsrResume(_) => self.running_task = None,
+            _ => (),
+        }
+
+        match event {
+            Event::TaskCreate(ev)
+            | Event::TaskReady(ev)
+            | Event::TaskPriority(ev)
+            | Event::TaskPriorityInherit(ev)
+            | Event::TaskPriorityDisinherit(ev)
+            | Event::TaskBegin(ev)
+            | Event::TaskResume(ev)
+            | Event::TaskActivate(ev) => self.set_name(ev.handle, &ev.name),
+            Event::TaskNotify(ev)
+            | Event::TaskNotifyFromIsr(ev)
+            | Event::TaskNotifyWait(ev)
+            | Event::TaskNotifyWaitBlock(ev) => {
+                if let Some(name) = ev.task_name.as_ref() {
+                    self.set_name(ev.handle, name);
+                }
+            }
+            _ => (),
+        }
+
+        if let Some(task) = self.running_task {
+            self.push(task, event_code, event);
+        }
+        if let Some(task) = target {
+            if Some(task) != self.running_task {
+                self.push(task, event_code, event);
+            }
+        }
+    }
+
+    pub fn task_trace(&self, handle: ObjectHandle) -> Option<&TaskTrace> {
+        self.traces.get(&handle)
+    }
+
+    pub fn task_traces(&self) -> impl Iterator<Item = &TaskTrace> {
+        self.traces.values()
+    }
+
+    pub fn into_task_traces(self) -> BTreeMap<ObjectHandle, TaskTrace> {
+        self.traces
+    }
+
+    /// The task a scheduling related event targets, if any
+    fn scheduling_target(event: &Event) -> Option<ObjectHandle> {
+        Some(match event {
+            Event::TaskCreate(ev)
+            | Event::TaskReady(ev)
+            | Event::TaskPriority(ev)
+            | Event::TaskPriorityInherit(ev)
+            | Event::TaskPriorityDisinherit(ev)
+            | Event::TaskBegin(ev)
+            | Event::TaskResume(ev)
+            | Event::TaskActivate(ev) => ev.handle,
+            Event::TaskNotify(ev) | Event::TaskNotifyFromIsr(ev) => ev.handle,
+            Event::ObjectDelete(ev) if ev.class == ObjectClass::Task => ev.handle,
+            _ => return None,
+        })
+    }
+
+    fn is_collected(&self, handle: ObjectHandle) -> bool {
+        self.filter.is_empty() || self.filter.contains(&handle)
+    }
+
+    fn set_name(&mut self, handle: ObjectHandle, name: &TaskName) {
+        if self.is_collected(handle) {
+            self.trace_mut(handle).name = Some(name.clone());
+        }
+    }
+
+    fn push(&mut self, handle: ObjectHandle, event_code: EventCode, event: &Event) {
+        if self.is_collected(handle) {
+            self.trace_mut(handle)
+                .events
+                .push((event_code, event.clone()));
+        }
+    }
+
+    fn trace_mut(&mut self, handle: ObjectHandle) -> &mut TaskTrace {
+        self.traces.entry(handle).or_insert_with(|| TaskTrace {
+            handle,
+            name: None,
+            events: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{EventCount, EventId, EventType, IsrEvent, TaskEvent};
+    use crate::time::Timestamp;
+    use crate::types::{ObjectName, Priority};
+
+    fn task_event(handle: u32, name: &str) -> TaskEvent {
+        TaskEvent {
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(0),
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName(name.into()),
+            priority: Priority(1),
+        }
+    }
+
+    fn code(event_type: EventType) -> EventCode {
+        EventCode(u16::from(EventId::from(event_type)).into())
+    }
+
+    #[test]
+    fn split_by_task() {
+        let t1 = ObjectHandle::new(1).unwrap();
+        let t2 = ObjectHandle::new(2).unwrap();
+        let mut s = TaskTraceSplitter::new();
+
+        s.process(
+            code(EventType::TaskActivate),
+            &Event::TaskActivate(task_event(1, "t1")),
+        );
+        // t1 readies t2
+        s.process(
+            code(EventType::TaskReady),
+            &Event::TaskReady(task_event(2, "t2")),
+        );
+        s.process(
+            code(EventType::TaskSwitchIsrBegin),
+            &Event::IsrBegin(IsrEvent {
+                event_count: EventCount(0),
+                sequence: 0,
+                timestamp: Timestamp(0),
+                handle: ObjectHandle::new(3).unwrap(),
+                name: ObjectName("isr".into()),
+                priority: Priority(1),
+                symbolized: None,
+            }),
+        );
+        assert_eq!(s.running_task(), None);
+        s.process(
+            code(EventType::TaskActivate),
+            &Event::TaskActivate(task_event(2, "t2")),
+        );
+
+        let t1_trace = s.task_trace(t1).unwrap();
+        assert_eq!(t1_trace.name.as_ref().map(|n| n.as_ref()), Some("t1"));
+        assert_eq!(t1_trace.events.len(), 2);
+        let t2_trace = s.task_trace(t2).unwrap();
+        assert_eq!(t2_trace.events.len(), 2);
+        assert!(matches!(t2_trace.events[0].1, Event::TaskReady(_)));
+
+        let mut s = TaskTraceSplitter::with_tasks([t2]);
+        s.process(
+            code(EventType::TaskActivate),
+            &Event::TaskActivate(task_event(1, "t1")),
+        );
+        assert!(s.task_trace(t1).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::streaming::codec::Codec;
 use crate::streaming::Error;
 use crate::time::{Frequency, Timestamp};
 use crate::types::{Endianness, TimerCounter};
@@ -19,7 +20,7 @@ impl TimestampInfo {
     pub(crate) fn read<R: Read>(
         r: &mut R,
         endianness: Endianness,
-        format_version: u16,
+        codec: &dyn Codec,
     ) -> Result<Self, Error> {
         let mut r = ByteOrdered::new(r, byteordered::Endianness::from(endianness));
 
@@ -27,19 +28,9 @@ impl TimestampInfo {
         let timer_type =
             TimerCounter::from_hwtc_type(hwtc_type).ok_or(Error::InvalidTimerCounter(hwtc_type))?;
 
-        let timer_frequency;
-        let timer_period;
-
-        if format_version == 10 || format_version == 12 {
-            // NOTE: we assume TRC_BASE_TYPE and TRC_UNSIGNED_BASE_TYPE are 32-bit
-            timer_frequency = Frequency(r.read_u32()?);
-            timer_period = r.read_u32()?;
-        } else {
-            // v13+
-            timer_period = r.read_u32()?;
-            // NOTE: we assume TRC_BASE_TYPE and TRC_UNSIGNED_BASE_TYPE are 32-bit
-            timer_frequency = Frequency(r.read_u32()?);
-        }
+        let timer_cfg = codec.read_timer_cfg(r.inner_mut(), endianness)?;
+        let timer_frequency = timer_cfg.frequency;
+        let timer_period = timer_cfg.period;
 
         let timer_wraparounds = r.read_u32()?;
         let os_tick_rate_hz = Frequency(r.read_u32()?);
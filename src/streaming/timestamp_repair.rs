@@ -0,0 +1,103 @@
+//! Opt-in repair of non-monotonic event timestamps, see
+//! `RecorderData::enable_timestamp_repair`.
+//!
+//! Streaming timestamps are the raw 32-bit timer value, so a timestamp lower
+//! than the previous one is normally a timer wraparound. A corrupt timestamp
+//! shows up as a step forward larger than any real gap between two events,
+//! that is the backwards step seen modulo the timer range. Such timestamps are
+//! clamped to the previous one so the event time never goes backwards.
+
+use crate::streaming::event::Event;
+use crate::time::Timestamp;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct TimestampRepair {
+    max_forward_step: u32,
+    previous: Option<u32>,
+    adjustments: u64,
+}
+
+impl TimestampRepair {
+    /// Steps beyond half the timer range are taken as going backwards
+    pub const DEFAULT_MAX_FORWARD_STEP: u32 = u32::MAX / 2;
+
+    /// `max_forward_step` is the largest number of ticks expected between two
+    /// consecutive events, including timer wraparounds
+    pub fn new(max_forward_step: u32) -> Self {
+        Self {
+            max_forward_step,
+            previous: None,
+            adjustments: 0,
+        }
+    }
+
+    /// Number of timestamps adjusted so far
+    pub fn adjustments(&self) -> u64 {
+        self.adjustments
+    }
+
+    /// Forget the previous timestamp, the timestamps start over after a trace restart
+    pub(crate) fn restart(&mut self) {
+        self.previous = None;
+    }
+
+    /// Clamp the event's timestamp when it's not monotonic, returns the
+    /// original timestamp when it was adjusted
+    pub(crate) fn repair(&mut self, event: &mut Event) -> Option<Timestamp> {
+        self.repair_timestamp(event.timestamp_mut())
+    }
+
+    /// Like `repair`, for a timestamp not yet part of an `Event`
+    pub(crate) fn repair_timestamp(&mut self, timestamp: &mut Timestamp) -> Option<Timestamp> {
+        // Streaming timestamps are the 32-bit timer value
+        let raw = timestamp.ticks() as u32;
+        let Some(previous) = self.previous else {
+            self.previous = Some(raw);
+            return None;
+        };
+        if raw.wrapping_sub(previous) <= self.max_forward_step {
+            self.previous = Some(raw);
+            return None;
+        }
+        self.adjustments += 1;
+        let original = *timestamp;
+        *timestamp = Timestamp(previous.into());
+        Some(original)
+    }
+}
+
+impl Default for TimestampRepair {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_FORWARD_STEP)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::streaming::event::{BaseEvent, EventCode, EventCount};
+
+    fn event(timestamp: u32) -> Event {
+        Event::Unknown(BaseEvent {
+            code: EventCode(0x0FFF),
+            event_count: EventCount(0),
+            sequence: 0,
+            timestamp: Timestamp(timestamp.into()),
+            parameters: [0; 15],
+        })
+    }
+
+    #[test]
+    fn clamps_backwards_steps() {
+        let mut repair = TimestampRepair::new(0x1000);
+        let mut repaired = Vec::new();
+        for t in [0xFFFF_FF00, 0xFFFF_FFF0, 0x10, 0x08, 0x2000, 0x20] {
+            let mut ev = event(t);
+            repair.repair(&mut ev);
+            repaired.push(ev.timestamp().ticks());
+        }
+        // The wraparound is kept, the step back and the excessive step are clamped
+        assert_eq!(repaired, [0xFFFF_FF00, 0xFFFF_FFF0, 0x10, 0x10, 0x10, 0x20]);
+        assert_eq!(repair.adjustments(), 2);
+    }
+}
@@ -0,0 +1,268 @@
+//! Capture files that wrap the PSF data in length-prefixed chunks, like the
+//! raw captures saved by SEGGER J-Link tools.
+//!
+//! The container layouts vary between tools and versions, so the layout is
+//! described by a `ChunkLayout` rather than fixed: an optional file header,
+//! then chunks made of a header holding the payload length, the payload and
+//! an optional trailer. `ChunkLayout::jlink_raw` is the layout of the J-Link
+//! RAW captures.
+
+use crate::types::Endianness;
+use std::io::{self, Read};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum LengthField {
+    U8,
+    U16,
+    U32,
+}
+
+impl LengthField {
+    fn size(self) -> usize {
+        match self {
+            LengthField::U8 => 1,
+            LengthField::U16 => 2,
+            LengthField::U32 => 4,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChunkLayoutError {
+    #[error(
+        "The {field_len} byte length field at offset {offset} doesn't fit in the {header_len} byte chunk header"
+    )]
+    LengthFieldOutOfBounds {
+        offset: usize,
+        field_len: usize,
+        header_len: usize,
+    },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ChunkLayout {
+    /// Bytes skipped at the start of the file
+    pub file_header_len: usize,
+    pub chunk_header_len: usize,
+    /// Offset of the payload length field in the chunk header
+    pub length_offset: usize,
+    pub length_field: LengthField,
+    pub endianness: Endianness,
+    /// Bytes following each chunk's payload, i.e. a checksum
+    pub chunk_trailer_len: usize,
+}
+
+impl ChunkLayout {
+    /// Chunks with a header made only of a little-endian length field
+    pub fn new(length_field: LengthField) -> Self {
+        Self {
+            file_header_len: 0,
+            chunk_header_len: length_field.size(),
+            length_offset: 0,
+            length_field,
+            endianness: Endianness::Little,
+            chunk_trailer_len: 0,
+        }
+    }
+
+    /// J-Link RAW captures: no file header, each chunk is a little-endian u32
+    /// payload length followed by the payload
+    pub fn jlink_raw() -> Self {
+        Self::new(LengthField::U32)
+    }
+
+    pub fn with_file_header_len(mut self, len: usize) -> Self {
+        self.file_header_len = len;
+        self
+    }
+
+    /// The length field at `length_offset` within a header of `len` bytes
+    pub fn with_chunk_header(mut self, len: usize, length_offset: usize) -> Self {
+        self.chunk_header_len = len;
+        self.length_offset = length_offset;
+        self
+    }
+
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    pub fn with_chunk_trailer_len(mut self, len: usize) -> Self {
+        self.chunk_trailer_len = len;
+        self
+    }
+
+    /// Check that the length field is within the chunk header
+    pub fn validate(&self) -> Result<(), ChunkLayoutError> {
+        let field_len = self.length_field.size();
+        if self.length_offset + field_len > self.chunk_header_len {
+            return Err(ChunkLayoutError::LengthFieldOutOfBounds {
+                offset: self.length_offset,
+                field_len,
+                header_len: self.chunk_header_len,
+            });
+        }
+        Ok(())
+    }
+
+    fn payload_len(&self, header: &[u8]) -> usize {
+        let field = &header[self.length_offset..self.length_offset + self.length_field.size()];
+        let mut bytes = [0; 4];
+        match self.endianness {
+            Endianness::Little => {
+                bytes[..field.len()].copy_from_slice(field);
+                u32::from_le_bytes(bytes) as usize
+            }
+            Endianness::Big => {
+                bytes[4 - field.len()..].copy_from_slice(field);
+                u32::from_be_bytes(bytes) as usize
+            }
+        }
+    }
+}
+
+/// A `Read` source of the payload of a chunked capture file
+#[derive(Debug)]
+pub struct ChunkedReader<R: Read> {
+    r: R,
+    layout: ChunkLayout,
+    header: Vec<u8>,
+    file_header_read: bool,
+    /// Payload bytes left in the current chunk
+    remaining: usize,
+    chunks: u64,
+}
+
+impl<R: Read> ChunkedReader<R> {
+    pub fn new(r: R, layout: ChunkLayout) -> Result<Self, ChunkLayoutError> {
+        layout.validate()?;
+        Ok(Self {
+            r,
+            layout,
+            header: vec![0; layout.chunk_header_len],
+            file_header_read: false,
+            remaining: 0,
+            chunks: 0,
+        })
+    }
+
+    /// Number of chunks read so far
+    pub fn chunks(&self) -> u64 {
+        self.chunks
+    }
+
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+
+    /// Read the next chunk's header, returns false at the end of the file
+    fn next_chunk(&mut self) -> io::Result<bool> {
+        if !self.file_header_read {
+            self.file_header_read = true;
+            skip(&mut self.r, self.layout.file_header_len)?;
+        }
+        loop {
+            match self.r.read_exact(&mut self.header) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+                Err(e) => return Err(e),
+            }
+            self.chunks += 1;
+            self.remaining = self.layout.payload_len(&self.header);
+            if self.remaining != 0 {
+                return Ok(true);
+            }
+            skip(&mut self.r, self.layout.chunk_trailer_len)?;
+        }
+    }
+}
+
+impl<R: Read> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 && !self.next_chunk()? {
+            return Ok(0);
+        }
+        let max = buf.len().min(self.remaining);
+        let n = self.r.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n;
+        if self.remaining == 0 {
+            skip(&mut self.r, self.layout.chunk_trailer_len)?;
+        }
+        Ok(n)
+    }
+}
+
+fn skip<R: Read>(r: &mut R, len: usize) -> io::Result<()> {
+    let skipped = io::copy(&mut r.take(len as u64), &mut io::sink())?;
+    if skipped != len as u64 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn length_prefixed_chunks() {
+        let file = [3, 0, b'a', b'b', b'c', 0, 0, 1, 0, b'd'];
+        let mut r =
+            ChunkedReader::new(file.as_slice(), ChunkLayout::new(LengthField::U16)).unwrap();
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"abcd");
+        assert_eq!(r.chunks(), 3);
+    }
+
+    #[test]
+    fn headers_and_trailers() {
+        let layout = ChunkLayout::new(LengthField::U32)
+            .with_file_header_len(4)
+            .with_chunk_header(6, 2)
+            .with_endianness(Endianness::Big)
+            .with_chunk_trailer_len(2);
+        let file = [
+            b'R', b'A', b'W', 0, // File header
+            0xAA, 0xBB, 0, 0, 0, 2, b'a', b'b', 0xCC, 0xCC, // Chunk
+            0xAA, 0xBB, 0, 0, 0, 1, b'c', 0xCC, 0xCC, // Chunk
+        ];
+        let mut out = Vec::new();
+        ChunkedReader::new(file.as_slice(), layout)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"abc");
+    }
+
+    #[test]
+    fn truncated_chunk() {
+        let file = [4, b'a', b'b'];
+        let mut out = Vec::new();
+        let err = ChunkedReader::new(file.as_slice(), ChunkLayout::new(LengthField::U8))
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn length_field_outside_the_header() {
+        let layout = ChunkLayout::new(LengthField::U32).with_chunk_header(4, 2);
+        assert!(matches!(
+            ChunkedReader::new([].as_slice(), layout),
+            Err(ChunkLayoutError::LengthFieldOutOfBounds {
+                offset: 2,
+                field_len: 4,
+                header_len: 4
+            })
+        ));
+    }
+}
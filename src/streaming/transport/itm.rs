@@ -0,0 +1,238 @@
+//! PSF data written to an ITM stimulus port, as captured from the SWO pin
+//! by a debug probe or a UART, with the ITM packet framing removed.
+//!
+//! The input must be the raw ITM packet stream, TPIU formatted captures
+//! need to be deformatted first.
+
+use std::io::{self, Read};
+
+/// Stimulus port used by the TraceRecorder ITM stream port, unless
+/// `TRC_CFG_STREAM_PORT_ITM_PORT` is changed
+pub const DEFAULT_STIMULUS_PORT: u8 = 1;
+
+const OVERFLOW: u8 = 0x70;
+/// Number of zero bytes preceding the final byte of a synchronization packet
+const SYNC_ZEROS: usize = 5;
+
+/// Packet statistics of an `ItmDecoder`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ItmStats {
+    /// Instrumentation packets from the selected stimulus port
+    pub port_packets: u64,
+    /// Instrumentation packets from the other stimulus ports
+    pub other_port_packets: u64,
+    /// Hardware source, timestamp and extension packets
+    pub other_packets: u64,
+    pub sync_packets: u64,
+    /// Overflow packets, the target dropped packets because the SWO
+    /// output couldn't keep up
+    pub overflows: u64,
+    /// Reserved header bytes, i.e. from a stream that lost synchronization
+    pub invalid_headers: u64,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum State {
+    Header,
+    /// Source packet payload, the data is kept when it's from the selected port
+    Payload {
+        keep: bool,
+        remaining: usize,
+    },
+    /// Continuation bytes of timestamp and extension packets
+    Continuation,
+}
+
+/// Extracts the payload of a stimulus port's instrumentation packets from an
+/// ITM packet stream
+#[derive(Clone, Debug)]
+pub struct ItmDecoder {
+    port: u8,
+    state: State,
+    zeros: usize,
+    stats: ItmStats,
+}
+
+impl ItmDecoder {
+    pub fn new(port: u8) -> Self {
+        Self {
+            port,
+            state: State::Header,
+            zeros: 0,
+            stats: ItmStats::default(),
+        }
+    }
+
+    pub fn port(&self) -> u8 {
+        self.port
+    }
+
+    pub fn stats(&self) -> ItmStats {
+        self.stats
+    }
+
+    /// Decode `data`, appending the selected port's payload to `out`.
+    /// Packets can span calls.
+    pub fn decode(&mut self, data: &[u8], out: &mut Vec<u8>) {
+        for b in data.iter().copied() {
+            match self.state {
+                State::Payload { keep, remaining } => {
+                    if keep {
+                        out.push(b);
+                    }
+                    self.state = match remaining - 1 {
+                        0 => State::Header,
+                        remaining => State::Payload { keep, remaining },
+                    };
+                }
+                State::Continuation => {
+                    if b & 0x80 == 0 {
+                        self.state = State::Header;
+                    }
+                }
+                State::Header => self.header(b),
+            }
+        }
+    }
+
+    fn header(&mut self, b: u8) {
+        if b == 0 {
+            self.zeros += 1;
+            return;
+        }
+        let zeros = std::mem::take(&mut self.zeros);
+        if b == 0x80 && zeros >= SYNC_ZEROS {
+            self.stats.sync_packets += 1;
+        } else if b == OVERFLOW {
+            self.stats.overflows += 1;
+        } else if b & 0x03 != 0 {
+            // Source packet, bit 2 distinguishes hardware sources from
+            // instrumentation, bits 3..7 are the port or source ID
+            let size = match b & 0x03 {
+                1 => 1,
+                2 => 2,
+                _ => 4,
+            };
+            let instrumentation = b & 0x04 == 0;
+            let keep = instrumentation && (b >> 3) == self.port;
+            match (instrumentation, keep) {
+                (_, true) => self.stats.port_packets += 1,
+                (true, false) => self.stats.other_port_packets += 1,
+                (false, _) => self.stats.other_packets += 1,
+            }
+            self.state = State::Payload {
+                keep,
+                remaining: size,
+            };
+        } else if b & 0x0F == 0 || b & 0x0B == 0x08 || b & 0xDF == 0x94 {
+            // Local timestamp, extension or global timestamp, the payload
+            // continues while bit 7 is set
+            self.stats.other_packets += 1;
+            if b & 0x80 != 0 {
+                self.state = State::Continuation;
+            }
+        } else {
+            self.stats.invalid_headers += 1;
+        }
+    }
+}
+
+/// A `Read` source of the PSF data in an ITM packet stream
+#[derive(Debug)]
+pub struct ItmReader<R: Read> {
+    r: R,
+    decoder: ItmDecoder,
+    buf: Vec<u8>,
+    out: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> ItmReader<R> {
+    const READ_SIZE: usize = 4096;
+
+    /// Read the payload of `DEFAULT_STIMULUS_PORT`
+    pub fn new(r: R) -> Self {
+        Self::with_port(r, DEFAULT_STIMULUS_PORT)
+    }
+
+    pub fn with_port(r: R, port: u8) -> Self {
+        Self {
+            r,
+            decoder: ItmDecoder::new(port),
+            buf: vec![0; Self::READ_SIZE],
+            out: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn stats(&self) -> ItmStats {
+        self.decoder.stats()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+}
+
+impl<R: Read> Read for ItmReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.out.len() {
+            self.out.clear();
+            self.pos = 0;
+            let n = self.r.read(&mut self.buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.decoder.decode(&self.buf[..n], &mut self.out);
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn removes_framing() {
+        let stream = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x80, // Synchronization
+            0x0B, 1, 2, 3, 4, // Port 1, 4 bytes
+            0x01, 0xFF, // Port 0, 1 byte
+            0xC0, 0x81, 0x01, // Local timestamp with 2 continuation bytes
+            0x70, // Overflow
+            0x0A, 5, 6, // Port 1, 2 bytes
+            0x17, 0xAA, 0xBB, 0xCC, 0xDD, // DWT hardware source (PC sample), 4 bytes
+            0x09, 7, // Port 1, 1 byte
+        ];
+        let mut r = ItmReader::new(stream.as_slice());
+        let mut out = Vec::new();
+        r.read_to_end(&mut out).unwrap();
+        assert_eq!(out, vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(
+            r.stats(),
+            ItmStats {
+                port_packets: 3,
+                other_port_packets: 1,
+                other_packets: 2,
+                sync_packets: 1,
+                overflows: 1,
+                invalid_headers: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn packets_span_chunks() {
+        let mut d = ItmDecoder::new(2);
+        let mut out = Vec::new();
+        d.decode(&[0x13, 1, 2], &mut out);
+        d.decode(&[3, 4, 0x12], &mut out);
+        d.decode(&[5, 6], &mut out);
+        assert_eq!(out, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(d.stats().port_packets, 2);
+    }
+}
@@ -0,0 +1,14 @@
+//! Containers and transports that carry PSF data, unwrapped into the plain
+//! byte stream read by `RecorderData`
+
+pub use chunked::{ChunkLayout, ChunkLayoutError, ChunkedReader, LengthField};
+pub use itm::{ItmDecoder, ItmReader, ItmStats};
+pub use pcapng::{Packet, PcapngError, PcapngPayloadReader, PcapngReader, PcapngWriter};
+pub use rtt::{RttError, RttSource, TargetMemory};
+pub use udp::{GapStats, ReorderBuffer, UdpSource};
+
+pub mod chunked;
+pub mod itm;
+pub mod pcapng;
+pub mod rtt;
+pub mod udp;
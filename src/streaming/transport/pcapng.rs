@@ -0,0 +1,463 @@
+//! PSF bytes encapsulated in [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html)
+//! packets, one packet per chunk received from the target, stamped with the
+//! host receive time.
+//!
+//! Packets are written with the `LINKTYPE_USER0` link type by default so
+//! Wireshark shows the raw bytes, a different link type can be used when the
+//! packets include the transport headers.
+
+use byteordered::{ByteOrdered, Endianness};
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The first of the link types reserved for private use
+pub const LINKTYPE_USER0: u16 = 147;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const SIMPLE_PACKET_BLOCK: u32 = 0x0000_0003;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_IF_TSRESOL: u16 = 9;
+/// Blocks larger than this are considered corrupt rather than allocated
+const MAX_BLOCK_LEN: u32 = 16 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PcapngError {
+    #[error("Invalid pcapng section header byte-order magic {0:#X}")]
+    ByteOrderMagic(u32),
+
+    #[error("Expected a pcapng section header block, found block type {0:#X}")]
+    MissingSectionHeader(u32),
+
+    #[error("Invalid pcapng block length {0}")]
+    BlockLength(u32),
+
+    #[error("Found a packet on interface {0} which wasn't described")]
+    InterfaceLookup(u32),
+
+    #[error(
+        "Encountered and IO error while reading the pcapng stream ({})",
+        .0.kind()
+    )]
+    Io(#[from] io::Error),
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Packet {
+    pub interface: u32,
+    pub link_type: u16,
+    /// Host receive time, simple packet blocks don't have one
+    pub timestamp: Option<SystemTime>,
+    /// Length of the packet on the wire, `data` is shorter when it was truncated
+    pub original_len: u32,
+    pub data: Vec<u8>,
+}
+
+/// Writes a single section with a single interface, in little endian
+#[derive(Debug)]
+pub struct PcapngWriter<W: Write> {
+    w: W,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    pub fn new(w: W) -> io::Result<Self> {
+        Self::with_link_type(w, LINKTYPE_USER0)
+    }
+
+    /// Write the section header and the interface description
+    pub fn with_link_type(mut w: W, link_type: u16) -> io::Result<Self> {
+        let mut shb = Vec::with_capacity(16);
+        shb.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        shb.extend_from_slice(&1_u16.to_le_bytes());
+        shb.extend_from_slice(&0_u16.to_le_bytes());
+        // Section length unspecified
+        shb.extend_from_slice(&(-1_i64).to_le_bytes());
+        write_block(&mut w, SECTION_HEADER_BLOCK, &shb)?;
+
+        let mut idb = Vec::with_capacity(20);
+        idb.extend_from_slice(&link_type.to_le_bytes());
+        idb.extend_from_slice(&0_u16.to_le_bytes());
+        // No snap length limit
+        idb.extend_from_slice(&0_u32.to_le_bytes());
+        // Nanosecond timestamps
+        idb.extend_from_slice(&OPT_IF_TSRESOL.to_le_bytes());
+        idb.extend_from_slice(&1_u16.to_le_bytes());
+        idb.extend_from_slice(&[9, 0, 0, 0]);
+        idb.extend_from_slice(&OPT_ENDOFOPT.to_le_bytes());
+        idb.extend_from_slice(&0_u16.to_le_bytes());
+        write_block(&mut w, INTERFACE_DESCRIPTION_BLOCK, &idb)?;
+
+        Ok(Self { w })
+    }
+
+    /// Write a packet received now
+    pub fn write_packet(&mut self, data: &[u8]) -> io::Result<()> {
+        self.write_packet_at(SystemTime::now(), data)
+    }
+
+    pub fn write_packet_at(&mut self, timestamp: SystemTime, data: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(data.len())
+            .ok()
+            .filter(|len| *len <= MAX_BLOCK_LEN)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Packet too large"))?;
+        let ns = timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let mut epb = Vec::with_capacity(20 + data.len() + 3);
+        epb.extend_from_slice(&0_u32.to_le_bytes());
+        epb.extend_from_slice(&((ns >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(ns as u32).to_le_bytes());
+        epb.extend_from_slice(&len.to_le_bytes());
+        epb.extend_from_slice(&len.to_le_bytes());
+        epb.extend_from_slice(data);
+        epb.resize(padded(epb.len()), 0);
+        write_block(&mut self.w, ENHANCED_PACKET_BLOCK, &epb)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Interface {
+    link_type: u16,
+    /// `if_tsresol` option value
+    ts_resolution: u8,
+}
+
+/// Reads the packets of a pcapng stream, any number of sections of either
+/// endianness are supported. Blocks other than packets are skipped.
+#[derive(Debug)]
+pub struct PcapngReader<R: Read> {
+    r: R,
+    endianness: Endianness,
+    interfaces: Vec<Interface>,
+}
+
+impl<R: Read> PcapngReader<R> {
+    /// Read the first section header
+    pub fn new(mut r: R) -> Result<Self, PcapngError> {
+        let mut block_type = [0; 4];
+        r.read_exact(&mut block_type)?;
+        let block_type = u32::from_le_bytes(block_type);
+        if block_type != SECTION_HEADER_BLOCK {
+            return Err(PcapngError::MissingSectionHeader(block_type));
+        }
+        let mut reader = Self {
+            r,
+            endianness: Endianness::Little,
+            interfaces: Vec::new(),
+        };
+        reader.read_section_header()?;
+        Ok(reader)
+    }
+
+    /// Read the next packet, returns `None` at the end of the stream
+    pub fn next_packet(&mut self) -> Result<Option<Packet>, PcapngError> {
+        loop {
+            let mut block_type = [0; 4];
+            match self.r.read_exact(&mut block_type) {
+                Ok(()) => (),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+            let block_type = match self.endianness {
+                Endianness::Little => u32::from_le_bytes(block_type),
+                Endianness::Big => u32::from_be_bytes(block_type),
+            };
+
+            if block_type == SECTION_HEADER_BLOCK {
+                self.read_section_header()?;
+                continue;
+            }
+
+            let body = self.read_block_body()?;
+            let mut b = ByteOrdered::runtime(body.as_slice(), self.endianness);
+            match block_type {
+                INTERFACE_DESCRIPTION_BLOCK => {
+                    let link_type = b.read_u16()?;
+                    let _reserved = b.read_u16()?;
+                    let _snap_len = b.read_u32()?;
+                    let mut ts_resolution = 6;
+                    while let Some((code, value)) = read_option(&mut b)? {
+                        if code == OPT_IF_TSRESOL && !value.is_empty() {
+                            ts_resolution = value[0];
+                        }
+                    }
+                    self.interfaces.push(Interface {
+                        link_type,
+                        ts_resolution,
+                    });
+                }
+                ENHANCED_PACKET_BLOCK => {
+                    let interface = b.read_u32()?;
+                    let ts_high = b.read_u32()?;
+                    let ts_low = b.read_u32()?;
+                    let captured_len = b.read_u32()?;
+                    let original_len = b.read_u32()?;
+                    let desc = self.interface(interface)?;
+                    let data = packet_data(&body, 20, captured_len)?;
+                    let ts = (u64::from(ts_high) << 32) | u64::from(ts_low);
+                    return Ok(Some(Packet {
+                        interface,
+                        link_type: desc.link_type,
+                        timestamp: Some(UNIX_EPOCH + duration(ts, desc.ts_resolution)),
+                        original_len,
+                        data,
+                    }));
+                }
+                SIMPLE_PACKET_BLOCK => {
+                    let original_len = b.read_u32()?;
+                    let desc = self.interface(0)?;
+                    let captured_len = original_len.min(body.len().saturating_sub(4) as u32);
+                    let data = packet_data(&body, 4, captured_len)?;
+                    return Ok(Some(Packet {
+                        interface: 0,
+                        link_type: desc.link_type,
+                        timestamp: None,
+                        original_len,
+                        data,
+                    }));
+                }
+                _ => debug!("Skipping pcapng block type {block_type:#X}"),
+            }
+        }
+    }
+
+    /// Read the packets' data as a contiguous byte stream
+    pub fn into_payload_reader(self) -> PcapngPayloadReader<R> {
+        PcapngPayloadReader {
+            reader: self,
+            packet: Vec::new(),
+            pos: 0,
+            timestamp: None,
+        }
+    }
+
+    fn interface(&self, id: u32) -> Result<Interface, PcapngError> {
+        self.interfaces
+            .get(id as usize)
+            .copied()
+            .ok_or(PcapngError::InterfaceLookup(id))
+    }
+
+    /// Read the rest of a section header block, after its type
+    fn read_section_header(&mut self) -> Result<(), PcapngError> {
+        let mut len_and_magic = [0; 8];
+        self.r.read_exact(&mut len_and_magic)?;
+        let magic = u32::from_le_bytes([
+            len_and_magic[4],
+            len_and_magic[5],
+            len_and_magic[6],
+            len_and_magic[7],
+        ]);
+        self.endianness = if magic == BYTE_ORDER_MAGIC {
+            Endianness::Little
+        } else if magic.swap_bytes() == BYTE_ORDER_MAGIC {
+            Endianness::Big
+        } else {
+            return Err(PcapngError::ByteOrderMagic(magic));
+        };
+        let len = ByteOrdered::runtime(&len_and_magic[..4], self.endianness).read_u32()?;
+        if len < 28 || len % 4 != 0 || len > MAX_BLOCK_LEN {
+            return Err(PcapngError::BlockLength(len));
+        }
+        // Version, section length, options and the trailing length
+        io::copy(
+            &mut (&mut self.r).take(u64::from(len) - 12),
+            &mut io::sink(),
+        )?;
+        self.interfaces.clear();
+        Ok(())
+    }
+
+    /// Read a block's length, body and trailing length, returns the body
+    fn read_block_body(&mut self) -> Result<Vec<u8>, PcapngError> {
+        let len = ByteOrdered::runtime(&mut self.r, self.endianness).read_u32()?;
+        if len < 12 || len % 4 != 0 || len > MAX_BLOCK_LEN {
+            return Err(PcapngError::BlockLength(len));
+        }
+        let mut body = vec![0; len as usize - 12];
+        self.r.read_exact(&mut body)?;
+        let trailing_len = ByteOrdered::runtime(&mut self.r, self.endianness).read_u32()?;
+        if trailing_len != len {
+            return Err(PcapngError::BlockLength(trailing_len));
+        }
+        Ok(body)
+    }
+}
+
+impl<R: Read> Iterator for PcapngReader<R> {
+    type Item = Result<Packet, PcapngError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+/// Reads the data of a pcapng stream's packets, i.e. for `RecorderData`
+#[derive(Debug)]
+pub struct PcapngPayloadReader<R: Read> {
+    reader: PcapngReader<R>,
+    packet: Vec<u8>,
+    pos: usize,
+    timestamp: Option<SystemTime>,
+}
+
+impl<R: Read> PcapngPayloadReader<R> {
+    /// Host receive time of the packet the last byte read came from
+    pub fn packet_timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+}
+
+impl<R: Read> Read for PcapngPayloadReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.packet.len() {
+            match self.reader.next_packet() {
+                Ok(Some(p)) => {
+                    self.packet = p.data;
+                    self.pos = 0;
+                    self.timestamp = p.timestamp;
+                }
+                Ok(None) => return Ok(0),
+                Err(PcapngError::Io(e)) => return Err(e),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+        }
+        let n = buf.len().min(self.packet.len() - self.pos);
+        buf[..n].copy_from_slice(&self.packet[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+fn write_block<W: Write>(w: &mut W, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let len = (body.len() + 12) as u32;
+    w.write_all(&block_type.to_le_bytes())?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(body)?;
+    w.write_all(&len.to_le_bytes())
+}
+
+fn read_option<R: Read>(
+    b: &mut ByteOrdered<R, Endianness>,
+) -> Result<Option<(u16, Vec<u8>)>, PcapngError> {
+    let code = match b.read_u16() {
+        Ok(code) => code,
+        // Options are optional, including the end of options marker
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let len = b.read_u16()?;
+    if code == OPT_ENDOFOPT {
+        return Ok(None);
+    }
+    let mut value = vec![0; padded(len.into())];
+    b.read_exact(&mut value)?;
+    value.truncate(len.into());
+    Ok(Some((code, value)))
+}
+
+fn packet_data(body: &[u8], offset: usize, captured_len: u32) -> Result<Vec<u8>, PcapngError> {
+    body.get(offset..offset + captured_len as usize)
+        .map(<[u8]>::to_vec)
+        .ok_or(PcapngError::BlockLength(captured_len))
+}
+
+/// Convert a timestamp in units of the `if_tsresol` resolution
+fn duration(ts: u64, ts_resolution: u8) -> Duration {
+    let exp = u32::from(ts_resolution & 0x7F);
+    let nanos = if ts_resolution & 0x80 != 0 {
+        (u128::from(ts) * 1_000_000_000) >> exp.min(127)
+    } else if exp <= 9 {
+        u128::from(ts) * 10_u128.pow(9 - exp)
+    } else {
+        u128::from(ts) / 10_u128.pow(exp.min(38) - 9)
+    };
+    Duration::from_nanos(nanos.min(u64::MAX.into()) as u64)
+}
+
+fn padded(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timestamp_resolutions() {
+        assert_eq!(duration(1_500_000, 6), Duration::from_millis(1500));
+        assert_eq!(duration(1_500, 9), Duration::from_nanos(1500));
+        assert_eq!(duration(3, 0x81), Duration::from_millis(1500));
+        assert_eq!(duration(15_000, 13), Duration::from_nanos(1));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let t = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let mut w = PcapngWriter::new(Vec::new()).unwrap();
+        w.write_packet_at(t, &[1, 2, 3]).unwrap();
+        w.write_packet_at(t + Duration::from_millis(1), &[4, 5, 6, 7, 8])
+            .unwrap();
+        let bytes = w.into_inner();
+        assert_eq!(bytes.len() % 4, 0);
+
+        let packets: Vec<Packet> = PcapngReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].link_type, LINKTYPE_USER0);
+        assert_eq!(packets[0].timestamp, Some(t));
+        assert_eq!(packets[0].data, vec![1, 2, 3]);
+        assert_eq!(packets[1].original_len, 5);
+
+        let mut payload = Vec::new();
+        let mut r = PcapngReader::new(bytes.as_slice())
+            .unwrap()
+            .into_payload_reader();
+        r.read_to_end(&mut payload).unwrap();
+        assert_eq!(payload, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(r.packet_timestamp(), Some(t + Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn big_endian_section() {
+        let mut bytes = Vec::new();
+        // Section header
+        bytes.extend_from_slice(&SECTION_HEADER_BLOCK.to_be_bytes());
+        bytes.extend_from_slice(&28_u32.to_be_bytes());
+        bytes.extend_from_slice(&BYTE_ORDER_MAGIC.to_be_bytes());
+        bytes.extend_from_slice(&[0, 1, 0, 0]);
+        bytes.extend_from_slice(&(-1_i64).to_be_bytes());
+        bytes.extend_from_slice(&28_u32.to_be_bytes());
+        // Interface description without options
+        bytes.extend_from_slice(&INTERFACE_DESCRIPTION_BLOCK.to_be_bytes());
+        bytes.extend_from_slice(&20_u32.to_be_bytes());
+        bytes.extend_from_slice(&LINKTYPE_USER0.to_be_bytes());
+        bytes.extend_from_slice(&[0; 6]);
+        bytes.extend_from_slice(&20_u32.to_be_bytes());
+        // Simple packet
+        bytes.extend_from_slice(&SIMPLE_PACKET_BLOCK.to_be_bytes());
+        bytes.extend_from_slice(&20_u32.to_be_bytes());
+        bytes.extend_from_slice(&2_u32.to_be_bytes());
+        bytes.extend_from_slice(&[0xAA, 0xBB, 0, 0]);
+        bytes.extend_from_slice(&20_u32.to_be_bytes());
+
+        let mut r = PcapngReader::new(bytes.as_slice()).unwrap();
+        let p = r.next_packet().unwrap().unwrap();
+        assert_eq!(p.data, vec![0xAA, 0xBB]);
+        assert_eq!(p.timestamp, None);
+        assert!(r.next_packet().unwrap().is_none());
+    }
+}
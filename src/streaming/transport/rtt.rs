@@ -0,0 +1,358 @@
+//! SEGGER RTT up-channel source, reading the TraceRecorder RTT stream port's
+//! buffer straight out of target memory through a debug probe.
+//!
+//! The probe is abstracted by `TargetMemory`, implement it for the memory
+//! interface of a probe library's core to capture live without any target-side
+//! networking. With the `probe-rs` feature, it's implemented for probe-rs's `Core`,
+//! i.e. `RttSource::attach(session.core(0)?, control_block, DEFAULT_UP_CHANNEL)`.
+
+use std::io::{self, Read};
+use std::ops::Range;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const CONTROL_BLOCK_ID: &[u8; 16] = b"SEGGER RTT\0\0\0\0\0\0";
+/// Up channel used by the TraceRecorder RTT stream port, unless
+/// `TRC_CFG_STREAM_PORT_RTT_UP_BUFFER_INDEX` is changed
+pub const DEFAULT_UP_CHANNEL: usize = 1;
+
+const CONTROL_BLOCK_HEADER_SIZE: u64 = 24;
+const DESCRIPTOR_SIZE: u64 = 24;
+const MAX_NAME_LEN: usize = 32;
+
+/// Read/write access to a 32-bit little-endian target's memory
+pub trait TargetMemory {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    fn write(&mut self, address: u64, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Byte-wide accesses, the RTT structures and buffers aren't necessarily aligned
+#[cfg(feature = "probe-rs")]
+impl TargetMemory for probe_rs::Core<'_> {
+    type Error = probe_rs::Error;
+
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+        probe_rs::MemoryInterface::read_8(self, address, buf)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> Result<(), Self::Error> {
+        probe_rs::MemoryInterface::write_8(self, address, data)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RttError {
+    #[error("RTT control block not found")]
+    ControlBlockNotFound,
+
+    #[error("RTT up channel {0} doesn't exist")]
+    ChannelLookup(usize),
+
+    #[error("No RTT up channel named '{0}'")]
+    ChannelNameLookup(String),
+
+    #[error("RTT up channel {0} has an invalid buffer descriptor")]
+    InvalidChannel(usize),
+
+    #[error("Failed to access the target memory")]
+    Memory(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// Reads an RTT up channel, see `RttSource::attach`
+#[derive(Debug)]
+pub struct RttSource<M: TargetMemory> {
+    memory: M,
+    channel: usize,
+    /// Address of the channel's buffer descriptor
+    descriptor: u64,
+    buffer: u64,
+    size: u32,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+}
+
+impl<M: TargetMemory> RttSource<M> {
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Search the given memory ranges for the RTT control block, returns its address
+    pub fn find_control_block(memory: &mut M, ranges: &[Range<u64>]) -> Result<u64, RttError> {
+        let id = CONTROL_BLOCK_ID;
+        for range in ranges {
+            let mut mem = vec![0; (range.end - range.start) as usize];
+            memory.read(range.start, &mut mem).map_err(memory_error)?;
+            // The control block is 4-byte aligned
+            if let Some(offset) = mem
+                .windows(id.len())
+                .enumerate()
+                .step_by(4)
+                .find_map(|(offset, w)| (w == id).then_some(offset))
+            {
+                return Ok(range.start + offset as u64);
+            }
+        }
+        Err(RttError::ControlBlockNotFound)
+    }
+
+    /// Attach to an up channel of the control block at `control_block`
+    pub fn attach(mut memory: M, control_block: u64, channel: usize) -> Result<Self, RttError> {
+        let mut header = [0; CONTROL_BLOCK_HEADER_SIZE as usize];
+        memory
+            .read(control_block, &mut header)
+            .map_err(memory_error)?;
+        if &header[..16] != CONTROL_BLOCK_ID {
+            return Err(RttError::ControlBlockNotFound);
+        }
+        let num_up = u32_at(&header, 16) as usize;
+        if channel >= num_up {
+            return Err(RttError::ChannelLookup(channel));
+        }
+
+        let descriptor =
+            control_block + CONTROL_BLOCK_HEADER_SIZE + channel as u64 * DESCRIPTOR_SIZE;
+        let mut desc = [0; DESCRIPTOR_SIZE as usize];
+        memory.read(descriptor, &mut desc).map_err(memory_error)?;
+        let buffer = u32_at(&desc, 4);
+        let size = u32_at(&desc, 8);
+        if buffer == 0 || size == 0 {
+            return Err(RttError::InvalidChannel(channel));
+        }
+        debug!(channel, buffer, size, "Attached to RTT up channel");
+
+        Ok(Self {
+            memory,
+            channel,
+            descriptor,
+            buffer: buffer.into(),
+            size,
+            poll_interval: Self::DEFAULT_POLL_INTERVAL,
+            idle_timeout: None,
+        })
+    }
+
+    /// Attach to the up channel named `name`, i.e. "TzData"
+    pub fn attach_by_name(mut memory: M, control_block: u64, name: &str) -> Result<Self, RttError> {
+        let mut header = [0; CONTROL_BLOCK_HEADER_SIZE as usize];
+        memory
+            .read(control_block, &mut header)
+            .map_err(memory_error)?;
+        if &header[..16] != CONTROL_BLOCK_ID {
+            return Err(RttError::ControlBlockNotFound);
+        }
+        let num_up = u32_at(&header, 16) as usize;
+        for channel in 0..num_up {
+            let descriptor =
+                control_block + CONTROL_BLOCK_HEADER_SIZE + channel as u64 * DESCRIPTOR_SIZE;
+            let mut name_ptr = [0; 4];
+            memory
+                .read(descriptor, &mut name_ptr)
+                .map_err(memory_error)?;
+            let name_ptr = u32::from_le_bytes(name_ptr);
+            if name_ptr == 0 {
+                continue;
+            }
+            let mut channel_name = [0; MAX_NAME_LEN];
+            memory
+                .read(name_ptr.into(), &mut channel_name)
+                .map_err(memory_error)?;
+            let len = channel_name
+                .iter()
+                .position(|b| *b == 0)
+                .unwrap_or(channel_name.len());
+            if &channel_name[..len] == name.as_bytes() {
+                return Self::attach(memory, control_block, channel);
+            }
+        }
+        Err(RttError::ChannelNameLookup(name.to_owned()))
+    }
+
+    /// How long `read` sleeps when the channel is empty
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Make `read` return end of stream once the channel was empty for
+    /// `idle_timeout`, by default it waits for data indefinitely
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Read the data available in the channel without waiting, returns the
+    /// number of bytes read
+    pub fn poll(&mut self, buf: &mut [u8]) -> Result<usize, RttError> {
+        let mut offsets = [0; 8];
+        self.memory
+            .read(self.descriptor + 12, &mut offsets)
+            .map_err(memory_error)?;
+        let write_offset = u32_at(&offsets, 0);
+        let read_offset = u32_at(&offsets, 4);
+        if write_offset >= self.size || read_offset >= self.size {
+            return Err(RttError::InvalidChannel(self.channel));
+        }
+
+        // When the data wraps around the end of the buffer, only the part up to
+        // the end is read, the rest is read by the next poll
+        let available = if write_offset >= read_offset {
+            write_offset - read_offset
+        } else {
+            self.size - read_offset
+        };
+        let n = (available as usize).min(buf.len());
+        if n == 0 {
+            return Ok(0);
+        }
+        self.memory
+            .read(self.buffer + u64::from(read_offset), &mut buf[..n])
+            .map_err(memory_error)?;
+        let read_offset = (read_offset + n as u32) % self.size;
+        self.memory
+            .write(self.descriptor + 16, &read_offset.to_le_bytes())
+            .map_err(memory_error)?;
+        Ok(n)
+    }
+
+    pub fn into_inner(self) -> M {
+        self.memory
+    }
+}
+
+impl<M: TargetMemory> Read for RttSource<M> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let started = Instant::now();
+        loop {
+            match self.poll(buf) {
+                Ok(0) => {
+                    if self
+                        .idle_timeout
+                        .is_some_and(|timeout| started.elapsed() >= timeout)
+                    {
+                        return Ok(0);
+                    }
+                    thread::sleep(self.poll_interval);
+                }
+                Ok(n) => return Ok(n),
+                Err(e) => return Err(io::Error::other(e)),
+            }
+        }
+    }
+}
+
+fn memory_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> RttError {
+    RttError::Memory(Box::new(e))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BASE: u64 = 0x2000_0000;
+    const CB: u64 = BASE + 0x100;
+    const NAME: u64 = BASE + 0x80;
+    const BUF: u64 = BASE + 0x200;
+    const BUF_SIZE: u32 = 16;
+
+    struct Ram(Vec<u8>);
+
+    impl TargetMemory for Ram {
+        type Error = io::Error;
+
+        fn read(&mut self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+            let start = (address - BASE) as usize;
+            buf.copy_from_slice(&self.0[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, address: u64, data: &[u8]) -> Result<(), Self::Error> {
+            let start = (address - BASE) as usize;
+            self.0[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    impl Ram {
+        fn put_u32(&mut self, address: u64, v: u32) {
+            self.write(address, &v.to_le_bytes()).unwrap();
+        }
+
+        /// Emulate the target writing to up channel 1
+        fn target_write(&mut self, data: &[u8]) {
+            let desc = CB + 24 + 24;
+            let mut wr = [0; 4];
+            self.read(desc + 12, &mut wr).unwrap();
+            let mut wr = u32::from_le_bytes(wr);
+            for b in data {
+                self.0[(BUF - BASE) as usize + wr as usize] = *b;
+                wr = (wr + 1) % BUF_SIZE;
+            }
+            self.put_u32(desc + 12, wr);
+        }
+    }
+
+    fn ram() -> Ram {
+        let mut ram = Ram(vec![0; 0x400]);
+        ram.write(NAME, b"TzData\0").unwrap();
+        ram.write(CB, CONTROL_BLOCK_ID).unwrap();
+        ram.put_u32(CB + 16, 2);
+        ram.put_u32(CB + 20, 0);
+        // Channel 0 is the terminal
+        ram.put_u32(CB + 24 + 4, (BUF + 0x100) as u32);
+        ram.put_u32(CB + 24 + 8, 8);
+        // Channel 1
+        let desc = CB + 24 + 24;
+        ram.put_u32(desc, NAME as u32);
+        ram.put_u32(desc + 4, BUF as u32);
+        ram.put_u32(desc + 8, BUF_SIZE);
+        ram
+    }
+
+    #[test]
+    fn find_and_attach() {
+        let mut ram = ram();
+        let ranges = [BASE..BASE + 0x40, BASE + 0x40..BASE + 0x400];
+        let cb = RttSource::find_control_block(&mut ram, &ranges).unwrap();
+        assert_eq!(cb, CB);
+        let src = RttSource::attach_by_name(ram, cb, "TzData").unwrap();
+        assert_eq!(src.channel(), DEFAULT_UP_CHANNEL);
+        assert!(matches!(
+            RttSource::attach(src.into_inner(), CB, 2),
+            Err(RttError::ChannelLookup(2))
+        ));
+    }
+
+    #[test]
+    fn reads_wrapping_data() {
+        let mut src = RttSource::attach(ram(), CB, 1)
+            .unwrap()
+            .with_poll_interval(Duration::from_millis(1))
+            .with_idle_timeout(Duration::from_millis(5));
+        let mut buf = [0; 32];
+        assert_eq!(src.poll(&mut buf).unwrap(), 0);
+
+        src.memory.target_write(&[1; 12]);
+        assert_eq!(src.read(&mut buf).unwrap(), 12);
+        src.memory.target_write(&[2, 3, 4, 5, 6, 7]);
+        let mut out = Vec::new();
+        src.read_to_end(&mut out).unwrap();
+        assert_eq!(out, vec![2, 3, 4, 5, 6, 7]);
+    }
+}
@@ -0,0 +1,428 @@
+//! PSF data received over UDP, where datagrams can be reordered or lost.
+//!
+//! The PSF header is read as it arrives, the datagrams carrying events are then
+//! put back in order using the event counter of their first and last events.
+//! Each datagram is expected to hold whole events, as sent by the TraceRecorder
+//! UDP stream port.
+
+use crate::streaming::event::{EventCode, EventCount, EventId};
+use crate::streaming::{Error, RecorderData};
+use crate::types::Endianness;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// Datagram ordering statistics of a `ReorderBuffer`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct GapStats {
+    pub datagrams: u64,
+    /// Datagrams that arrived ahead of one or more of their predecessors
+    pub out_of_order: u64,
+    /// Datagrams discarded because they arrived after their place in the
+    /// stream was skipped, or were duplicates
+    pub late: u64,
+    /// Datagrams discarded because they didn't contain a whole event
+    pub invalid: u64,
+    /// Number of gaps skipped in the event counter sequence
+    pub gaps: u64,
+    /// Number of events in the skipped gaps, these are either lost datagrams
+    /// or events dropped by the recorder
+    pub missing_events: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Pending {
+    last: u64,
+    data: Vec<u8>,
+}
+
+/// Puts datagrams of events back in event counter order.
+///
+/// Datagrams are held back until their predecessors arrive, or until more than
+/// `window` datagrams are waiting, in which case the gap is skipped.
+#[derive(Clone, Debug)]
+pub struct ReorderBuffer {
+    endianness: Endianness,
+    custom_printf_event_id: Option<EventId>,
+    window: usize,
+    /// Event count expected next, extended to 64 bits
+    next: Option<u64>,
+    origin: Option<u64>,
+    pending: BTreeMap<u64, Pending>,
+    stats: GapStats,
+}
+
+impl ReorderBuffer {
+    pub const DEFAULT_WINDOW: usize = 32;
+
+    pub fn new(endianness: Endianness) -> Self {
+        Self {
+            endianness,
+            custom_printf_event_id: None,
+            window: Self::DEFAULT_WINDOW,
+            next: None,
+            origin: None,
+            pending: BTreeMap::new(),
+            stats: GapStats::default(),
+        }
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Custom printf events have a variable length, their ID is needed to find
+    /// the events in a datagram, see `RecorderData::set_custom_printf_event_id`
+    pub fn with_custom_printf_event_id(mut self, custom_printf_event_id: EventId) -> Self {
+        self.custom_printf_event_id = Some(custom_printf_event_id);
+        self
+    }
+
+    /// Set the event count expected next. By default the sequence starts with
+    /// the earliest of the first `window` datagrams, or the earliest waiting
+    /// when flushed.
+    pub fn set_next_event_count(&mut self, event_count: EventCount) {
+        self.next = Some(event_count.0.into());
+    }
+
+    pub fn stats(&self) -> GapStats {
+        self.stats
+    }
+
+    /// Number of datagrams waiting for their predecessors
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Add a datagram, appending the data that's now contiguous to `out`
+    pub fn push(&mut self, datagram: Vec<u8>, out: &mut Vec<u8>) {
+        self.stats.datagrams += 1;
+        let Some((first, last)) =
+            event_count_range(&datagram, self.endianness, self.custom_printf_event_id)
+        else {
+            self.stats.invalid += 1;
+            return;
+        };
+        // Until the sequence starts, counts are extended relative to the first
+        // datagram, leaving room for predecessors arriving after it
+        let reference = match self.next {
+            Some(next) => next,
+            None => *self
+                .origin
+                .get_or_insert(u64::from(first) + (1 << u16::BITS)),
+        };
+        let delta = i64::from(first.wrapping_sub(reference as u16) as i16);
+        let first = reference.wrapping_add_signed(delta);
+        if (self.next.is_some() && delta < 0) || self.pending.contains_key(&first) {
+            self.stats.late += 1;
+            return;
+        }
+        if self.next.is_some() && delta > 0 {
+            self.stats.out_of_order += 1;
+        }
+        let last = first + u64::from(last.wrapping_sub(first as u16));
+        self.pending.insert(
+            first,
+            Pending {
+                last,
+                data: datagram,
+            },
+        );
+
+        self.release(out);
+        while self.pending.len() > self.window {
+            self.skip_gap(out);
+        }
+    }
+
+    /// Append all the waiting datagrams to `out`, skipping the gaps, i.e.
+    /// when the input stalled or ended
+    pub fn flush(&mut self, out: &mut Vec<u8>) {
+        while !self.pending.is_empty() {
+            self.skip_gap(out);
+        }
+    }
+
+    fn release(&mut self, out: &mut Vec<u8>) {
+        while let Some(next) = self.next {
+            let Some(entry) = self.pending.first_entry() else {
+                break;
+            };
+            let first = *entry.key();
+            if first > next {
+                break;
+            }
+            let p = entry.remove();
+            if first < next {
+                // Overlaps the data already released
+                self.stats.late += 1;
+            } else {
+                out.extend_from_slice(&p.data);
+                self.next = Some(p.last + 1);
+            }
+        }
+    }
+
+    fn skip_gap(&mut self, out: &mut Vec<u8>) {
+        let Some(first) = self.pending.keys().next().copied() else {
+            return;
+        };
+        if let Some(next) = self.next {
+            debug!(
+                "Skipping {} missing events before event count {}",
+                first - next,
+                first as u16
+            );
+            self.stats.gaps += 1;
+            self.stats.missing_events += first - next;
+        }
+        self.next = Some(first);
+        self.release(out);
+    }
+}
+
+/// A `Read` source of PSF data received on a UDP socket
+#[derive(Debug)]
+pub struct UdpSource {
+    socket: UdpSocket,
+    window: usize,
+    custom_printf_event_id: Option<EventId>,
+    buf: Vec<u8>,
+    out: Vec<u8>,
+    pos: usize,
+    reorder: Option<ReorderBuffer>,
+}
+
+impl UdpSource {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        UdpSocket::bind(addr).map(Self::new)
+    }
+
+    /// Use an existing socket. When the socket has a read timeout, waiting
+    /// datagrams are released when it expires, skipping the gaps.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            window: ReorderBuffer::DEFAULT_WINDOW,
+            custom_printf_event_id: None,
+            buf: vec![0; u16::MAX.into()],
+            out: Vec::new(),
+            pos: 0,
+            reorder: None,
+        }
+    }
+
+    /// See `ReorderBuffer::with_window`
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// See `ReorderBuffer::with_custom_printf_event_id`, it's also set on the
+    /// recorder data returned by `find_recorder_data`
+    pub fn with_custom_printf_event_id(mut self, custom_printf_event_id: EventId) -> Self {
+        self.custom_printf_event_id = Some(custom_printf_event_id);
+        self
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Read the recorder data, then start reordering the event datagrams
+    pub fn find_recorder_data(&mut self) -> Result<RecorderData, Error> {
+        let mut rd = RecorderData::find(self)?;
+        if let Some(id) = self.custom_printf_event_id {
+            rd.set_custom_printf_event_id(id);
+        }
+        self.start_reordering(rd.header.endianness);
+        Ok(rd)
+    }
+
+    /// Reorder the datagrams received from now on, the data read so far must
+    /// end on an event boundary, i.e. after reading the recorder data
+    pub fn start_reordering(&mut self, endianness: Endianness) {
+        let mut reorder = ReorderBuffer::new(endianness).with_window(self.window);
+        if let Some(id) = self.custom_printf_event_id {
+            reorder = reorder.with_custom_printf_event_id(id);
+        }
+        let leftover = &self.out[self.pos..];
+        if let Some((_, last)) =
+            event_count_range(leftover, endianness, self.custom_printf_event_id)
+        {
+            reorder.set_next_event_count(EventCount(last.wrapping_add(1)));
+        }
+        self.reorder = Some(reorder);
+    }
+
+    pub fn gap_stats(&self) -> GapStats {
+        self.reorder
+            .as_ref()
+            .map(ReorderBuffer::stats)
+            .unwrap_or_default()
+    }
+}
+
+impl Read for UdpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.out.len() {
+            self.out.clear();
+            self.pos = 0;
+            match self.socket.recv(&mut self.buf) {
+                Ok(n) => match self.reorder.as_mut() {
+                    Some(reorder) => reorder.push(self.buf[..n].to_vec(), &mut self.out),
+                    None => self.out.extend_from_slice(&self.buf[..n]),
+                },
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) && self.reorder.as_ref().is_some_and(|r| r.pending() != 0) =>
+                {
+                    if let Some(reorder) = self.reorder.as_mut() {
+                        reorder.flush(&mut self.out);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Event counts of the first and last whole events in `data`
+pub(crate) fn event_count_range(
+    data: &[u8],
+    endianness: Endianness,
+    custom_printf_event_id: Option<EventId>,
+) -> Option<(u16, u16)> {
+    let u16_at = |offset: usize| {
+        let bytes = [data[offset], data[offset + 1]];
+        match endianness {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        }
+    };
+    let mut range = None;
+    let mut offset = 0;
+    // Event code, event count, timestamp and the parameters
+    while offset + 8 <= data.len() {
+        let code = EventCode(u16_at(offset).into());
+        let len = if Some(code.event_id()) == custom_printf_event_id {
+            // Channel handle, argument and format string lengths, then the
+            // arguments and format string
+            if offset + 16 > data.len() {
+                break;
+            }
+            16 + 4 * usize::from(u16_at(offset + 12)) + usize::from(u16_at(offset + 14))
+        } else {
+            8 + 4 * usize::from(code.parameter_count())
+        };
+        if offset + len > data.len() {
+            break;
+        }
+        let count = u16_at(offset + 2);
+        range = Some((range.map_or(count, |(first, _)| first), count));
+        offset += len;
+    }
+    range
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A datagram of parameterless events
+    fn datagram(counts: std::ops::RangeInclusive<u16>) -> Vec<u8> {
+        counts
+            .flat_map(|c| {
+                let mut ev = vec![0x01, 0x00];
+                ev.extend_from_slice(&c.to_le_bytes());
+                ev.extend_from_slice(&[0; 4]);
+                ev
+            })
+            .collect()
+    }
+
+    #[test]
+    fn event_counts() {
+        let mut data = datagram(1..=3);
+        assert_eq!(
+            event_count_range(&data, Endianness::Little, None),
+            Some((1, 3))
+        );
+        // Trailing partial event
+        data.extend_from_slice(&[0x00, 0x10, 0x04, 0x00]);
+        assert_eq!(
+            event_count_range(&data, Endianness::Little, None),
+            Some((1, 3))
+        );
+        assert_eq!(event_count_range(&[0; 7], Endianness::Little, None), None);
+    }
+
+    #[test]
+    fn reorders_and_skips_gaps() {
+        let mut r = ReorderBuffer::new(Endianness::Little).with_window(2);
+        r.set_next_event_count(EventCount(1));
+        let mut out = Vec::new();
+        r.push(datagram(1..=2), &mut out);
+        r.push(datagram(5..=6), &mut out);
+        r.push(datagram(3..=4), &mut out);
+        assert_eq!(out, datagram(1..=6));
+        assert_eq!(r.pending(), 0);
+
+        // Late duplicate
+        r.push(datagram(3..=4), &mut out);
+        // 7..=8 is lost, the window fills up
+        out.clear();
+        r.push(datagram(9..=9), &mut out);
+        r.push(datagram(10..=10), &mut out);
+        assert!(out.is_empty());
+        r.push(datagram(11..=11), &mut out);
+        assert_eq!(out, datagram(9..=11));
+        // Arriving after being skipped
+        r.push(datagram(7..=8), &mut out);
+
+        assert_eq!(
+            r.stats(),
+            GapStats {
+                datagrams: 8,
+                out_of_order: 4,
+                late: 2,
+                invalid: 0,
+                gaps: 1,
+                missing_events: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn sequence_starts_with_the_earliest_datagram() {
+        let mut r = ReorderBuffer::new(Endianness::Little);
+        let mut out = Vec::new();
+        r.push(datagram(3..=4), &mut out);
+        r.push(datagram(1..=2), &mut out);
+        assert!(out.is_empty());
+        r.flush(&mut out);
+        assert_eq!(out, datagram(1..=4));
+        assert_eq!(r.stats().gaps, 0);
+        assert_eq!(r.stats().late, 0);
+    }
+
+    #[test]
+    fn event_count_rollover() {
+        let mut r = ReorderBuffer::new(Endianness::Little);
+        r.set_next_event_count(EventCount(u16::MAX - 1));
+        let mut out = Vec::new();
+        r.push(datagram(0..=1), &mut out);
+        r.push(datagram(u16::MAX - 1..=u16::MAX), &mut out);
+        let mut expected = datagram(u16::MAX - 1..=u16::MAX);
+        expected.extend(datagram(0..=1));
+        assert_eq!(out, expected);
+        assert_eq!(r.stats().gaps, 0);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::streaming::EntryTable;
+use crate::types::{
+    ObjectClass, ObjectHandle, Priority, SymbolString, STARTUP_TASK_NAME, TZ_CTRL_TASK_NAME,
+};
+use std::collections::BTreeMap;
+
+/// Objects classified by their symbol or handle alone, before (or without)
+/// any event revealing their class, see `RecorderData::with_well_known_objects`.
+///
+/// The default knows the recorder's `TzCtrl` task and adds the startup task,
+/// which the recorder doesn't list, to the entry table.
+/// Vendor service tasks and the like can be added to it.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct WellKnownObjects {
+    symbols: BTreeMap<String, ObjectClass>,
+    handles: BTreeMap<ObjectHandle, ObjectClass>,
+    entries: BTreeMap<ObjectHandle, WellKnownEntry>,
+}
+
+/// An object added to the entry tables that don't have it, see `WellKnownObjects::with_entry`
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct WellKnownEntry {
+    pub symbol: String,
+    pub class: ObjectClass,
+    pub priority: Priority,
+}
+
+impl Default for WellKnownObjects {
+    fn default() -> Self {
+        Self::empty()
+            .with_symbol(TZ_CTRL_TASK_NAME, ObjectClass::Task)
+            .with_entry(
+                ObjectHandle::NO_TASK,
+                WellKnownEntry {
+                    symbol: STARTUP_TASK_NAME.to_owned(),
+                    class: ObjectClass::Task,
+                    priority: Priority(1),
+                },
+            )
+    }
+}
+
+impl WellKnownObjects {
+    /// No well-known objects, not even `TzCtrl` or the startup task
+    pub fn empty() -> Self {
+        Self {
+            symbols: BTreeMap::new(),
+            handles: BTreeMap::new(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Objects named `symbol` are of the given class
+    pub fn with_symbol<S: Into<String>>(mut self, symbol: S, class: ObjectClass) -> Self {
+        self.symbols.insert(symbol.into(), class);
+        self
+    }
+
+    /// The object with the given handle is of the given class
+    pub fn with_handle(mut self, handle: ObjectHandle, class: ObjectClass) -> Self {
+        self.handles.insert(handle, class);
+        self
+    }
+
+    /// The object with the given handle is added to the entry table when the
+    /// table doesn't have it, i.e. objects that exist before the recorder starts
+    pub fn with_entry(mut self, handle: ObjectHandle, entry: WellKnownEntry) -> Self {
+        self.entries.insert(handle, entry);
+        self
+    }
+
+    /// The class of the object, handles take precedence over symbols
+    pub fn class(&self, handle: ObjectHandle, symbol: Option<&str>) -> Option<ObjectClass> {
+        self.handles
+            .get(&handle)
+            .or_else(|| self.entries.get(&handle).map(|e| &e.class))
+            .or_else(|| symbol.and_then(|s| self.symbols.get(s)))
+            .copied()
+    }
+
+    /// Add the missing well-known entries and classify the entries without a class,
+    /// returns the number of entries added or classified
+    pub fn classify(&self, table: &mut EntryTable) -> usize {
+        let mut classified = 0;
+        for (handle, entry) in self.entries.iter() {
+            if !table.entries().contains_key(handle) {
+                table.set_symbol(*handle, SymbolString(entry.symbol.as_str().into()));
+                table.set_priority(*handle, entry.priority);
+            }
+        }
+        for handle in table.entries().keys().copied().collect::<Vec<_>>() {
+            if table.class(handle).is_none() {
+                if let Some(class) = self.class(handle, table.symbol(handle).map(|s| s.as_ref())) {
+                    table.set_class(handle, class);
+                    classified += 1;
+                }
+            }
+        }
+        classified
+    }
+
+    /// Undo `classify` on a table no events have been read into, removing the
+    /// well-known entries it added and the classes it gave
+    pub(crate) fn declassify(&self, table: &mut EntryTable) {
+        for (handle, entry) in self.entries.iter() {
+            let added = table.entries().get(handle).is_some_and(|e| {
+                e.symbol.as_ref().map(|s| s.as_ref()) == Some(entry.symbol.as_str())
+                    && e.class == Some(entry.class)
+                    && e.symbolized.is_none()
+            });
+            if added {
+                table.remove(*handle);
+            }
+        }
+        for handle in table.entries().keys().copied().collect::<Vec<_>>() {
+            let symbol = table.symbol(handle).map(|s| s.as_ref());
+            if table.class(handle).is_some() && table.class(handle) == self.class(handle, symbol) {
+                table.clear_class(handle);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classify_entries() {
+        let mut table = EntryTable::default();
+        let [tz_ctrl, wifi, q, unnamed] =
+            [0x10, 0x20, 0x30, 0x40].map(|h| ObjectHandle::new(h).unwrap());
+        for (handle, sym) in [
+            (tz_ctrl, TZ_CTRL_TASK_NAME),
+            (wifi, "wifi_svc"),
+            (q, "wifi_q"),
+        ] {
+            table.entry(handle).set_symbol(SymbolString(sym.into()));
+        }
+        table.entry(q).set_class(ObjectClass::Queue);
+        table.entry(unnamed);
+
+        let objects = WellKnownObjects::default()
+            .with_symbol("wifi_svc", ObjectClass::Task)
+            .with_symbol("wifi_q", ObjectClass::Task)
+            .with_handle(unnamed, ObjectClass::Isr);
+        assert_eq!(objects.classify(&mut table), 4);
+        assert_eq!(
+            table.symbol(ObjectHandle::NO_TASK).unwrap().as_ref(),
+            STARTUP_TASK_NAME
+        );
+        assert_eq!(table.class(ObjectHandle::NO_TASK), Some(ObjectClass::Task));
+        assert_eq!(
+            table.entries()[&ObjectHandle::NO_TASK].states.priority(),
+            Priority(1)
+        );
+        assert_eq!(table.class(tz_ctrl), Some(ObjectClass::Task));
+        assert_eq!(table.class(wifi), Some(ObjectClass::Task));
+        assert_eq!(table.class(q), Some(ObjectClass::Queue));
+        assert_eq!(table.class(unnamed), Some(ObjectClass::Isr));
+
+        let mut table = EntryTable::default();
+        table
+            .entry(tz_ctrl)
+            .set_symbol(SymbolString(TZ_CTRL_TASK_NAME.into()));
+        assert_eq!(WellKnownObjects::empty().classify(&mut table), 0);
+        assert_eq!(table.class(tz_ctrl), None);
+        assert!(table.symbol(ObjectHandle::NO_TASK).is_none());
+    }
+
+    #[test]
+    fn declassify_undoes_classify() {
+        let mut table = EntryTable::default();
+        let [tz_ctrl, q] = [0x10, 0x20].map(|h| ObjectHandle::new(h).unwrap());
+        table.set_symbol(tz_ctrl, SymbolString(TZ_CTRL_TASK_NAME.into()));
+        table.set_class(q, ObjectClass::Queue);
+        let unclassified = table.clone();
+
+        let objects = WellKnownObjects::default();
+        objects.classify(&mut table);
+        assert_ne!(table, unclassified);
+        objects.declassify(&mut table);
+        assert_eq!(table, unclassified);
+    }
+}
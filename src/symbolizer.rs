@@ -0,0 +1,150 @@
+//! Address to symbol name resolution
+//!
+//! Raw addresses found in the trace data (memory allocations, ISR handles,
+//! object handles) can be resolved to the symbols of the firmware image.
+//! Symbols can be provided directly or, with the `elf` feature enabled,
+//! loaded from an ELF file.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Symbol {
+    pub name: String,
+    /// Size of the symbol in bytes, zero if unknown
+    pub size: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Symbolizer {
+    symbols: BTreeMap<u64, Symbol>,
+}
+
+impl Symbolizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_symbols<I, S>(symbols: I) -> Self
+    where
+        I: IntoIterator<Item = (u64, S, u64)>,
+        S: Into<String>,
+    {
+        let mut s = Self::new();
+        for (address, name, size) in symbols.into_iter() {
+            s.insert(address, name, size);
+        }
+        s
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, address: u64, name: S, size: u64) {
+        self.symbols.insert(
+            address,
+            Symbol {
+                name: name.into(),
+                size,
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Resolve an address to the symbol that starts at, or contains, the address
+    pub fn resolve(&self, address: u64) -> Option<&Symbol> {
+        let (start, sym) = self.symbols.range(..=address).next_back()?;
+        if *start == address || address < start.saturating_add(sym.size) {
+            Some(sym)
+        } else {
+            None
+        }
+    }
+
+    /// Resolve an address to a symbol name, including the offset into the
+    /// symbol when the address isn't the start of it (i.e. `my_buffer+0x10`)
+    pub fn symbolize(&self, address: u64) -> Option<String> {
+        let (start, sym) = self.symbols.range(..=address).next_back()?;
+        if *start == address {
+            Some(sym.name.clone())
+        } else if address < start.saturating_add(sym.size) {
+            Some(format!("{}+0x{:X}", sym.name, address - start))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "elf")]
+mod elf {
+    use super::Symbolizer;
+    use object::{Object, ObjectSymbol, SymbolKind};
+    use std::{fs, io, path::Path};
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum ElfError {
+        #[error("Failed to read the ELF file")]
+        Io(#[from] io::Error),
+
+        #[error("Failed to parse the ELF file")]
+        Object(#[from] object::Error),
+    }
+
+    impl Symbolizer {
+        /// Load the data and text symbols from an ELF file
+        pub fn from_elf_file<P: AsRef<Path>>(path: P) -> Result<Self, ElfError> {
+            let data = fs::read(path)?;
+            Self::from_elf_data(&data)
+        }
+
+        /// Load the data and text symbols from the contents of an ELF file
+        pub fn from_elf_data(data: &[u8]) -> Result<Self, ElfError> {
+            let file = object::File::parse(data)?;
+            let mut s = Self::new();
+            for sym in file.symbols() {
+                if !matches!(sym.kind(), SymbolKind::Text | SymbolKind::Data) || sym.is_undefined()
+                {
+                    continue;
+                }
+                if let Ok(name) = sym.name() {
+                    if name.is_empty() {
+                        continue;
+                    }
+                    // Clear the thumb bit on function addresses
+                    let address = if sym.kind() == SymbolKind::Text {
+                        sym.address() & !1
+                    } else {
+                        sym.address()
+                    };
+                    s.insert(address, name, sym.size());
+                }
+            }
+            Ok(s)
+        }
+    }
+}
+
+#[cfg(feature = "elf")]
+pub use elf::ElfError;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_containing_symbol() {
+        let s = Symbolizer::from_symbols([
+            (0x2000_0000, "ucHeap", 0x100),
+            (0x0800_0100, "SysTick_Handler", 0),
+        ]);
+        assert_eq!(s.symbolize(0x2000_0000).as_deref(), Some("ucHeap"));
+        assert_eq!(s.symbolize(0x2000_0010).as_deref(), Some("ucHeap+0x10"));
+        assert_eq!(s.symbolize(0x2000_0100), None);
+        assert_eq!(s.symbolize(0x0800_0100).as_deref(), Some("SysTick_Handler"));
+        assert_eq!(s.symbolize(0x0800_0102), None);
+        assert_eq!(s.symbolize(0x10), None);
+    }
+}
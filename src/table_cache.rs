@@ -0,0 +1,219 @@
+//! A compact binary file format for caching object metadata tables
+//! (`EntryTable`, `SymbolTable` and `ObjectPropertyTable`) across capture
+//! sessions of the same firmware.
+//!
+//! All values are little-endian. Each file starts with a magic number,
+//! a format version and the kind of table it contains.
+
+use crate::types::{ObjectClass, ObjectHandle};
+use byteordered::{ByteOrdered, Endianness};
+use std::io::{self, Read, Write};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TableCacheError {
+    #[error("Invalid table cache magic number {0:X?}")]
+    InvalidMagic([u8; 4]),
+
+    #[error("Unsupported table cache format version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Expected a {0} table cache but found a {1}")]
+    TableKindMismatch(TableKind, TableKind),
+
+    #[error("Invalid table kind {0}")]
+    InvalidTableKind(u8),
+
+    #[error("Invalid zero value object handle in table cache")]
+    InvalidObjectHandle,
+
+    #[error("Invalid object class {0} in table cache")]
+    InvalidObjectClass(u8),
+
+    #[error("Invalid table cache string data")]
+    InvalidString(#[from] std::string::FromUtf8Error),
+
+    #[error("Encountered an IO error while reading or writing the table cache ({})", .0.kind())]
+    Io(#[from] io::Error),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, derive_more::Display)]
+pub enum TableKind {
+    #[display(fmt = "EntryTable")]
+    EntryTable = 1,
+    #[display(fmt = "SymbolTable")]
+    SymbolTable = 2,
+    #[display(fmt = "ObjectPropertyTable")]
+    ObjectPropertyTable = 3,
+}
+
+impl TableKind {
+    fn from_u8(v: u8) -> Option<Self> {
+        Some(match v {
+            1 => TableKind::EntryTable,
+            2 => TableKind::SymbolTable,
+            3 => TableKind::ObjectPropertyTable,
+            _ => return None,
+        })
+    }
+}
+
+const MAGIC: [u8; 4] = *b"TRTC";
+const VERSION: u8 = 1;
+const NO_CLASS: u8 = 0xFF;
+
+pub(crate) type CacheWriter<W> = ByteOrdered<W, Endianness>;
+pub(crate) type CacheReader<R> = ByteOrdered<R, Endianness>;
+
+pub(crate) fn write_header<W: Write>(
+    w: W,
+    kind: TableKind,
+) -> Result<CacheWriter<W>, TableCacheError> {
+    let mut w = ByteOrdered::runtime(w, Endianness::Little);
+    w.write_all(&MAGIC)?;
+    w.write_u8(VERSION)?;
+    w.write_u8(kind as u8)?;
+    Ok(w)
+}
+
+pub(crate) fn read_header<R: Read>(
+    r: R,
+    kind: TableKind,
+) -> Result<CacheReader<R>, TableCacheError> {
+    let mut r = ByteOrdered::runtime(r, Endianness::Little);
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(TableCacheError::InvalidMagic(magic));
+    }
+    let version = r.read_u8()?;
+    if version != VERSION {
+        return Err(TableCacheError::UnsupportedVersion(version));
+    }
+    let raw_kind = r.read_u8()?;
+    let found = TableKind::from_u8(raw_kind).ok_or(TableCacheError::InvalidTableKind(raw_kind))?;
+    if found != kind {
+        return Err(TableCacheError::TableKindMismatch(kind, found));
+    }
+    Ok(r)
+}
+
+pub(crate) fn write_len<W: Write>(w: &mut CacheWriter<W>, len: usize) -> io::Result<()> {
+    w.write_u32(u32::try_from(len).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?)
+}
+
+pub(crate) fn read_len<R: Read>(r: &mut CacheReader<R>) -> io::Result<usize> {
+    Ok(r.read_u32()? as usize)
+}
+
+pub(crate) fn write_str<W: Write>(w: &mut CacheWriter<W>, s: &str) -> io::Result<()> {
+    write_len(w, s.len())?;
+    w.write_all(s.as_bytes())
+}
+
+pub(crate) fn read_string<R: Read>(r: &mut CacheReader<R>) -> Result<String, TableCacheError> {
+    let len = read_len(r)?;
+    let mut buf = Vec::new();
+    r.by_ref().take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(String::from_utf8(buf)?)
+}
+
+pub(crate) fn write_opt_str<W: Write>(w: &mut CacheWriter<W>, s: Option<&str>) -> io::Result<()> {
+    match s {
+        Some(s) => {
+            w.write_u8(1)?;
+            write_str(w, s)
+        }
+        None => w.write_u8(0),
+    }
+}
+
+pub(crate) fn read_opt_string<R: Read>(
+    r: &mut CacheReader<R>,
+) -> Result<Option<String>, TableCacheError> {
+    if r.read_u8()? != 0 {
+        Ok(Some(read_string(r)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub(crate) fn read_handle<R: Read>(
+    r: &mut CacheReader<R>,
+) -> Result<ObjectHandle, TableCacheError> {
+    ObjectHandle::new(r.read_u32()?).ok_or(TableCacheError::InvalidObjectHandle)
+}
+
+pub(crate) fn write_opt_class<W: Write>(
+    w: &mut CacheWriter<W>,
+    class: Option<ObjectClass>,
+) -> io::Result<()> {
+    w.write_u8(class.map(|c| c.into_usize() as u8).unwrap_or(NO_CLASS))
+}
+
+pub(crate) fn read_opt_class<R: Read>(
+    r: &mut CacheReader<R>,
+) -> Result<Option<ObjectClass>, TableCacheError> {
+    let raw = r.read_u8()?;
+    if raw == NO_CLASS {
+        Ok(None)
+    } else {
+        ObjectClass::enumerate()
+            .get(usize::from(raw))
+            .copied()
+            .map(Some)
+            .ok_or(TableCacheError::InvalidObjectClass(raw))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::symbol_table::{SymbolCrc6, SymbolTable};
+    use crate::streaming::EntryTable;
+    use crate::types::SymbolString;
+
+    #[test]
+    fn entry_table_roundtrip() {
+        let mut table = EntryTable::default();
+        let entry = table.entry(ObjectHandle::new(0x2000_0010).unwrap());
+        entry.set_symbol(SymbolString("q".into()));
+        entry.set_class(ObjectClass::Queue);
+        table.entry(ObjectHandle::new(0x2000_0020).unwrap());
+
+        let mut buf = Vec::new();
+        table.save(&mut buf).unwrap();
+        assert_eq!(EntryTable::load(buf.as_slice()).unwrap(), table);
+
+        assert!(matches!(
+            SymbolTable::load(buf.as_slice()),
+            Err(TableCacheError::TableKindMismatch(
+                TableKind::SymbolTable,
+                TableKind::EntryTable
+            ))
+        ));
+    }
+
+    #[test]
+    fn symbol_table_roundtrip() {
+        let mut table = SymbolTable::default();
+        table.insert(
+            ObjectHandle::new(4).unwrap(),
+            None,
+            SymbolCrc6::new(b"chan"),
+            SymbolString("chan".into()),
+        );
+        table.insert(
+            ObjectHandle::new(10).unwrap(),
+            ObjectHandle::new(4),
+            SymbolCrc6::new(b"fmt %d"),
+            SymbolString("fmt %d".into()),
+        );
+
+        let mut buf = Vec::new();
+        table.save(&mut buf).unwrap();
+        assert_eq!(SymbolTable::load(buf.as_slice()).unwrap(), table);
+    }
+}
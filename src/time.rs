@@ -2,6 +2,7 @@ use derive_more::{
     Add, AddAssign, Binary, Deref, Display, Into, LowerHex, MulAssign, Octal, Sub, Sum, UpperHex,
 };
 use std::ops;
+use std::time::Duration;
 
 /// Frequency of the clock/timer/counter used as time base
 #[derive(
@@ -118,6 +119,47 @@ impl Timestamp {
     pub const fn ticks(&self) -> u64 {
         self.get_raw()
     }
+
+    /// Convert the ticks to time using the timer `frequency`, only the ticks
+    /// are kept when the frequency is unitless
+    pub fn to_time(&self, frequency: Frequency) -> ConvertedTime {
+        if frequency.is_unitless() {
+            return ConvertedTime::TickOnly(self.ticks());
+        }
+        let nanos = u128::from(self.ticks()) * 1_000_000_000 / u128::from(frequency.get_raw());
+        ConvertedTime::Duration(Duration::from_nanos(
+            u64::try_from(nanos).unwrap_or(u64::MAX),
+        ))
+    }
+}
+
+/// A `Timestamp` converted to time, see `Timestamp::to_time`
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ConvertedTime {
+    Duration(Duration),
+    /// The timer frequency is unknown, the time is only known in ticks
+    TickOnly(u64),
+}
+
+impl ConvertedTime {
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            ConvertedTime::Duration(d) => Some(*d),
+            ConvertedTime::TickOnly(_) => None,
+        }
+    }
+
+    pub fn is_tick_only(&self) -> bool {
+        matches!(self, ConvertedTime::TickOnly(_))
+    }
+
+    /// Nanoseconds, or ticks for `TickOnly`, for outputs with a single time column
+    pub fn nanos_or_ticks(&self) -> u64 {
+        match self {
+            ConvertedTime::Duration(d) => u64::try_from(d.as_nanos()).unwrap_or(u64::MAX),
+            ConvertedTime::TickOnly(ticks) => *ticks,
+        }
+    }
 }
 
 impl From<Ticks> for Timestamp {
@@ -305,10 +347,47 @@ impl StreamingInstant {
     }
 }
 
+/// Extends streaming timestamps to 64 bits, relative to the first one seen
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct StreamingClock(Option<(Timestamp, StreamingInstant)>);
+
+impl StreamingClock {
+    pub(crate) fn time(&mut self, raw: Timestamp) -> Timestamp {
+        match self.0.as_mut() {
+            Some((first, instant)) => {
+                Timestamp(instant.elapsed(raw).ticks().saturating_sub(first.ticks()))
+            }
+            None => {
+                let mut instant = StreamingInstant::zero();
+                let first = instant.elapsed(raw);
+                self.0 = Some((first, instant));
+                Timestamp::zero()
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn time_conversion() {
+        let t = Timestamp(1_500);
+        assert_eq!(
+            t.to_time(Frequency(1_000_000)),
+            ConvertedTime::Duration(Duration::from_micros(1_500))
+        );
+        let tick_only = t.to_time(Frequency(0));
+        assert_eq!(tick_only, ConvertedTime::TickOnly(1_500));
+        assert_eq!(tick_only.duration(), None);
+        assert_eq!(tick_only.nanos_or_ticks(), 1_500);
+        assert_eq!(
+            Timestamp(u64::MAX).to_time(Frequency(1)).nanos_or_ticks(),
+            u64::MAX
+        );
+    }
+
     #[test]
     fn differential_time_xts16() {
         let mut accumulated_time = Timestamp::zero();
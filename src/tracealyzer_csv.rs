@@ -0,0 +1,191 @@
+//! CSV export in the column layout of Tracealyzer's own trace export
+//!
+//! Each row holds the actor (the task or ISR running when the event occurred),
+//! the event text and the timestamp in seconds since the first event.
+//! Events that occur before the first context switch are attributed to
+//! `STARTUP_TASK_NAME`.
+
+use crate::snapshot;
+use crate::streaming;
+use crate::time::StreamingClock;
+use crate::time::{ConvertedTime, Frequency, Timestamp};
+use crate::types::STARTUP_TASK_NAME;
+use std::io::{self, Write};
+
+pub const HEADER: [&str; 3] = ["Actor", "Event", "Timestamp"];
+
+pub struct TracealyzerCsvWriter<W: Write> {
+    w: W,
+    frequency: Frequency,
+    actor: String,
+    clock: StreamingClock,
+    header_written: bool,
+}
+
+impl<W: Write> TracealyzerCsvWriter<W> {
+    /// Timestamps are converted to seconds using the timer `frequency`, they're
+    /// written in ticks when the frequency is unknown
+    pub fn new(w: W, frequency: Frequency) -> Self {
+        Self {
+            w,
+            frequency,
+            actor: STARTUP_TASK_NAME.to_owned(),
+            clock: StreamingClock::default(),
+            header_written: false,
+        }
+    }
+
+    pub fn write_streaming_event(
+        &mut self,
+        event_code: streaming::event::EventCode,
+        event: &streaming::event::Event,
+    ) -> io::Result<()> {
+        use streaming::event::Event;
+
+        match event {
+            Event::TraceStart(ev) => self.actor = ev.current_task.to_string(),
+            Event::TaskBegin(ev) | Event::TaskResume(ev) | Event::TaskActivate(ev) => {
+                self.actor = ev.name.to_string()
+            }
+            Event::IsrBegin(ev) | Event::IsrResume(ev) => self.actor = ev.name.to_string(),
+            _ => (),
+        }
+
+        let text = match event {
+            Event::User(ev) => format!("[{}] {}", ev.channel, ev.formatted_string),
+            Event::Dropped(ev) => format!("{} events dropped", ev.count),
+            Event::Annotation(ev) => match &ev.note {
+                Some(note) => format!("[annotation] {}: {note}", ev.label),
+                None => format!("[annotation] {}", ev.label),
+            },
+            Event::SessionBoundary(ev) => format!("Session {} started", ev.session),
+            _ => event_text(event_code.event_type(), event.object_name()),
+        };
+        let t = self.clock.time(event.timestamp());
+        self.write_row(&text, t)
+    }
+
+    pub fn write_snapshot_event(
+        &mut self,
+        event_type: snapshot::event::EventType,
+        event: &snapshot::event::Event,
+    ) -> io::Result<()> {
+        use snapshot::event::Event;
+
+        let object_name = match event {
+            Event::TaskBegin(ev)
+            | Event::TaskReady(ev)
+            | Event::TaskResume(ev)
+            | Event::TaskCreate(ev) => Some(ev.name.as_ref()),
+            Event::IsrBegin(ev) | Event::IsrResume(ev) => Some(ev.name.as_ref()),
+            Event::TaskInstanceFinishedNextKse(ev) | Event::TaskInstanceFinishedDirect(ev) => {
+                Some(ev.name.as_ref())
+            }
+            _ => None,
+        };
+        match event {
+            Event::TaskBegin(ev) | Event::TaskResume(ev) => self.actor = ev.name.to_string(),
+            Event::IsrBegin(ev) | Event::IsrResume(ev) => self.actor = ev.name.to_string(),
+            _ => (),
+        }
+
+        let text = match event {
+            Event::User(ev) => format!("[{}] {}", ev.channel, ev.formatted_string),
+            _ => event_text(event_type, object_name),
+        };
+        self.write_row(&text, event.timestamp())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    fn write_row(&mut self, text: &str, t: Timestamp) -> io::Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            writeln!(self.w, "{}", HEADER.join(","))?;
+        }
+        let time = match t.to_time(self.frequency) {
+            ConvertedTime::Duration(d) => format!("{:.9}", d.as_secs_f64()),
+            ConvertedTime::TickOnly(ticks) => ticks.to_string(),
+        };
+        writeln!(self.w, "{},{},{time}", quoted(&self.actor), quoted(text))
+    }
+}
+
+fn event_text<T: std::fmt::Display>(event_type: T, object_name: Option<&str>) -> String {
+    match object_name {
+        Some(name) => format!("{event_type}({name})"),
+        None => event_type.to_string(),
+    }
+}
+
+/// Quote a field containing a separator, quote or line break
+fn quoted(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{
+        Event, EventType, IsrEvent, TaskEvent, UserEvent as SnapshotUserEvent,
+    };
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{
+        Arguments, FormatString, FormattedString, ObjectHandle, ObjectName, Priority,
+        UserEventArgRecordCount, UserEventChannel,
+    };
+
+    #[test]
+    fn snapshot_rows() {
+        let mut w = TracealyzerCsvWriter::new(Vec::new(), Frequency(1000));
+        let task = TaskEvent {
+            handle: ObjectHandle::new(1).unwrap(),
+            name: ObjectName("worker".into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(1500),
+        };
+        let user = SnapshotUserEvent {
+            channel: UserEventChannel::Custom("log".to_owned()),
+            format_string: FormatString("".into()),
+            formatted_string: FormattedString("a, \"b\"".to_owned()),
+            args: Arguments::new(),
+            timestamp: Timestamp(1750),
+        };
+        let isr = IsrEvent {
+            handle: ObjectHandle::new(2).unwrap(),
+            name: ObjectName("uart".into()),
+            priority: Priority(3),
+            timestamp: Timestamp(2000),
+        };
+        w.write_snapshot_event(EventType::TaskReady, &Event::TaskReady(task.clone()))
+            .unwrap();
+        w.write_snapshot_event(EventType::TaskSwitchTaskBegin, &Event::TaskBegin(task))
+            .unwrap();
+        w.write_snapshot_event(
+            EventType::UserEvent(UserEventArgRecordCount(0)),
+            &Event::User(user),
+        )
+        .unwrap();
+        w.write_snapshot_event(EventType::TaskSwitchIsrBegin, &Event::IsrBegin(isr))
+            .unwrap();
+
+        let csv = String::from_utf8(w.into_inner()).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                "Actor,Event,Timestamp",
+                "(startup),TASK_READY(worker),1.500000000",
+                "worker,TS_TASK_BEGIN(worker),1.500000000",
+                "worker,\"[log] a, \"\"b\"\"\",1.750000000",
+                "uart,TS_ISR_BEGIN(uart),2.000000000",
+            ]
+        );
+    }
+}
@@ -4,12 +4,14 @@ use byteordered::ByteOrdered;
 use derive_more::{Binary, Deref, Display, From, Into, LowerHex, Octal, UpperHex};
 use enum_iterator::Sequence;
 use ordered_float::OrderedFloat;
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
 use std::fmt::Write as _;
 use std::io;
 use std::num::NonZeroU32;
 use std::str::FromStr;
+use std::sync::Arc;
 use thiserror::Error;
-use tracing::warn;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
 pub enum Protocol {
@@ -285,13 +287,21 @@ pub(crate) trait SymbolTableExt {
     fn symbol(&self, handle: ObjectHandle) -> Option<&SymbolString>;
 }
 
+/// A symbol of the trace data, shared rather than copied by the names taken from it
+/// and interned when an `InternPool` is set
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Into, Display)]
 #[display(fmt = "{_0}")]
-pub struct SymbolString(pub(crate) String);
+pub struct SymbolString(pub(crate) Arc<str>);
 
 impl From<TrimmedString> for SymbolString {
     fn from(s: TrimmedString) -> Self {
-        Self(s.0)
+        Self(s.0.into())
+    }
+}
+
+impl From<SymbolString> for String {
+    fn from(s: SymbolString) -> Self {
+        s.0.as_ref().to_owned()
     }
 }
 
@@ -309,19 +319,77 @@ impl std::ops::Deref for SymbolString {
     }
 }
 
+/// How the raw bytes of symbols and names in the trace data are decoded, see
+/// `streaming::RecorderData::find_with_string_decoder` and
+/// `snapshot::RecorderData::locate_and_parse_with_string_decoder`
+#[derive(Copy, Clone, Debug, Default)]
+pub enum StringDecoder {
+    /// UTF-8, replacing invalid sequences with `U+FFFD`
+    #[default]
+    Utf8Lossy,
+    /// ISO 8859-1, each byte is the Unicode code point
+    Latin1,
+    /// A user provided decoder, i.e. for Shift-JIS
+    Custom(fn(&[u8]) -> String),
+}
+
+impl StringDecoder {
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            StringDecoder::Utf8Lossy => String::from_utf8_lossy(bytes).into_owned(),
+            StringDecoder::Latin1 => bytes.iter().map(|b| char::from(*b)).collect(),
+            StringDecoder::Custom(f) => f(bytes),
+        }
+    }
+
+    /// Custom decoders are compared by address, they're only equal to copies of themselves
+    fn key(&self) -> (u8, usize) {
+        match self {
+            StringDecoder::Utf8Lossy => (0, 0),
+            StringDecoder::Latin1 => (1, 0),
+            StringDecoder::Custom(f) => (2, *f as usize),
+        }
+    }
+}
+
+impl PartialEq for StringDecoder {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for StringDecoder {}
+
+impl PartialOrd for StringDecoder {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for StringDecoder {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl std::hash::Hash for StringDecoder {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Into, Display)]
 #[display(fmt = "{_0}")]
 pub(crate) struct TrimmedString(pub(crate) String);
 
 impl TrimmedString {
-    pub(crate) fn from_raw(s: &[u8]) -> Self {
-        let s = String::from_utf8_lossy(s);
-        let substr = if let Some(idx) = s.find(char::from(0)) {
-            &s[..idx]
-        } else {
-            &s
+    pub(crate) fn from_raw_with(s: &[u8], decoder: &StringDecoder) -> Self {
+        // Strings are nul terminated, or fill the whole buffer
+        let s = match s.iter().position(|b| *b == 0) {
+            Some(idx) => &s[..idx],
+            None => s,
         };
-        Self::from_str(substr.trim_end_matches(char::from(0)))
+        Self::from_str(&decoder.decode(s))
     }
 
     pub(crate) fn from_str(s: &str) -> Self {
@@ -344,19 +412,111 @@ impl std::ops::Deref for TrimmedString {
     }
 }
 
+/// Cleans up symbols and strings read from corrupted memory, see
+/// `streaming::RecorderData::set_string_sanitizer`.
+///
+/// By default, non-printable characters are replaced with `U+FFFD`, leading and
+/// trailing Unicode whitespace is trimmed and the length isn't limited.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct StringSanitizer {
+    /// `None` removes the non-printable characters
+    replacement: Option<char>,
+    /// Maximum length in characters
+    max_len: Option<usize>,
+    trim: bool,
+}
+
+impl Default for StringSanitizer {
+    fn default() -> Self {
+        Self {
+            replacement: Some(char::REPLACEMENT_CHARACTER),
+            max_len: None,
+            trim: true,
+        }
+    }
+}
+
+impl StringSanitizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the non-printable characters with `replacement`
+    pub fn with_replacement(mut self, replacement: char) -> Self {
+        self.replacement = Some(replacement);
+        self
+    }
+
+    /// Remove the non-printable characters instead of replacing them
+    pub fn removing_non_printables(mut self) -> Self {
+        self.replacement = None;
+        self
+    }
+
+    /// Truncate to at most `max_len` characters
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Keep leading and trailing whitespace
+    pub fn without_trimming(mut self) -> Self {
+        self.trim = false;
+        self
+    }
+
+    /// Control characters (C0, C1 and DEL) are the non-printable characters
+    pub fn sanitize(&self, s: &str) -> String {
+        let s = if self.trim { s.trim() } else { s };
+        let mut sanitized: String = s
+            .chars()
+            .filter_map(|c| {
+                if c.is_control() {
+                    self.replacement
+                } else {
+                    Some(c)
+                }
+            })
+            .take(self.max_len.unwrap_or(usize::MAX))
+            .collect();
+        if self.trim {
+            // Truncating or removing characters can leave whitespace at the end
+            sanitized.truncate(sanitized.trim_end().len());
+        }
+        sanitized
+    }
+}
+
 pub const STARTUP_TASK_NAME: &str = "(startup)";
 pub const TZ_CTRL_TASK_NAME: &str = "TzCtrl";
+/// FreeRTOS `configIDLE_TASK_NAME` default
+pub const IDLE_TASK_NAME: &str = "IDLE";
 
+/// The name of an object, shares the allocation of the entry table symbol it was taken from
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, From, Into, Display)]
 #[display(fmt = "{_0}")]
-pub struct ObjectName(pub(crate) String);
+pub struct ObjectName(pub(crate) Arc<str>);
+
+impl From<String> for ObjectName {
+    fn from(s: String) -> Self {
+        Self(s.into())
+    }
+}
+
+impl From<ObjectName> for String {
+    fn from(s: ObjectName) -> Self {
+        s.0.as_ref().to_owned()
+    }
+}
 
 pub type TaskName = ObjectName;
 pub type IsrName = ObjectName;
 pub type QueueName = ObjectName;
 pub type SemaphoreName = ObjectName;
 pub type MutexName = ObjectName;
+pub type TimerName = ObjectName;
 pub type EventGroupName = ObjectName;
+pub type StreamBufferName = ObjectName;
 pub type MessageBufferName = ObjectName;
 pub type StateMachineName = ObjectName;
 pub type StateMachineStateName = ObjectName;
@@ -439,8 +599,7 @@ impl From<UserEventArgRecordCount> for usize {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
-#[display(fmt = "{}")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Argument {
     Char(char),
     I8(i8),
@@ -454,7 +613,133 @@ pub enum Argument {
     String(String),
 }
 
+impl std::fmt::Display for Argument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Argument::Char(v) => v.fmt(f),
+            Argument::I8(v) => v.fmt(f),
+            Argument::U8(v) => v.fmt(f),
+            Argument::I16(v) => v.fmt(f),
+            Argument::U16(v) => v.fmt(f),
+            Argument::I32(v) => v.fmt(f),
+            Argument::U32(v) => v.fmt(f),
+            Argument::F32(v) => v.fmt(f),
+            Argument::F64(v) => v.fmt(f),
+            Argument::String(v) => v.fmt(f),
+        }
+    }
+}
+
+/// The arguments of a user event, most have no more than a couple of arguments,
+/// those are stored inline without allocating
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Arguments(SmallVec<[Argument; 2]>);
+
+impl Arguments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_slice(&self) -> &[Argument] {
+        self.0.as_slice()
+    }
+
+    pub(crate) fn push(&mut self, arg: Argument) {
+        self.0.push(arg)
+    }
+
+    /// Whether the arguments no longer fit inline
+    #[cfg(test)]
+    pub(crate) fn spilled(&self) -> bool {
+        self.0.spilled()
+    }
+}
+
+impl std::ops::Deref for Arguments {
+    type Target = [Argument];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl From<Vec<Argument>> for Arguments {
+    fn from(args: Vec<Argument>) -> Self {
+        Self(SmallVec::from_vec(args))
+    }
+}
+
+impl FromIterator<Argument> for Arguments {
+    fn from_iter<I: IntoIterator<Item = Argument>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Arguments {
+    type Item = &'a Argument;
+    type IntoIter = std::slice::Iter<'a, Argument>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// The type of an `Argument`, without its value
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum ArgumentKind {
+    #[display(fmt = "char")]
+    Char,
+    #[display(fmt = "i8")]
+    I8,
+    #[display(fmt = "u8")]
+    U8,
+    #[display(fmt = "i16")]
+    I16,
+    #[display(fmt = "u16")]
+    U16,
+    #[display(fmt = "i32")]
+    I32,
+    #[display(fmt = "u32")]
+    U32,
+    #[display(fmt = "f32")]
+    F32,
+    #[display(fmt = "f64")]
+    F64,
+    #[display(fmt = "string")]
+    String,
+}
+
 impl Argument {
+    pub fn kind(&self) -> ArgumentKind {
+        match self {
+            Argument::Char(_) => ArgumentKind::Char,
+            Argument::I8(_) => ArgumentKind::I8,
+            Argument::U8(_) => ArgumentKind::U8,
+            Argument::I16(_) => ArgumentKind::I16,
+            Argument::U16(_) => ArgumentKind::U16,
+            Argument::I32(_) => ArgumentKind::I32,
+            Argument::U32(_) => ArgumentKind::U32,
+            Argument::F32(_) => ArgumentKind::F32,
+            Argument::F64(_) => ArgumentKind::F64,
+            Argument::String(_) => ArgumentKind::String,
+        }
+    }
+
+    /// The value of numeric arguments
+    pub fn as_f64(&self) -> Option<f64> {
+        Some(match self {
+            Argument::Char(_) | Argument::String(_) => return None,
+            Argument::I8(v) => (*v).into(),
+            Argument::U8(v) => (*v).into(),
+            Argument::I16(v) => (*v).into(),
+            Argument::U16(v) => (*v).into(),
+            Argument::I32(v) => (*v).into(),
+            Argument::U32(v) => (*v).into(),
+            Argument::F32(v) => v.0.into(),
+            Argument::F64(v) => v.0,
+        })
+    }
+
     fn as_i64(&self) -> Option<i64> {
         Some(match self {
             Argument::I8(v) => (*v).into(),
@@ -468,9 +753,30 @@ impl Argument {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Into, Deref, Display)]
+/// A user event format string, shares the allocation of the symbol it was taken from
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Into, Display)]
 #[display(fmt = "{_0}")]
-pub struct FormatString(pub(crate) String);
+pub struct FormatString(pub(crate) Arc<str>);
+
+impl FormatString {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for FormatString {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<FormatString> for String {
+    fn from(s: FormatString) -> Self {
+        s.0.as_ref().to_owned()
+    }
+}
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Into, Deref, Display)]
 #[display(fmt = "{_0}")]
@@ -501,53 +807,118 @@ enum HexDisplay {
     Lowercase,
 }
 
-// TODO - float & float endianness support, warn if not supported and found
-// TODO - tests for all this, like '%%' == "%"
-// NOTE Assumes UTF8
-pub(crate) fn format_symbol_string<S: SymbolTableExt>(
+#[cfg(test)]
+fn format_symbol_string<S: SymbolTableExt>(
     symbol_table: &S,
     protocol: Protocol,
     endianness: Endianness,
     format_string: &str,
     arg_data: &[u8],
-) -> Result<(FormattedString, Vec<Argument>), FormattedStringError> {
-    let mut r = ByteOrdered::runtime(arg_data, byteordered::Endianness::from(endianness));
-    let mut formatted_string = String::new();
-    let mut args = Vec::new();
-    let mut found_format_specifier = false;
-    let mut found_subspec = SubSpecifier::None;
-    let mut is_hex: Option<HexDisplay> = None;
-
-    for in_c in format_string.chars() {
-        let is_width_or_padding = in_c.is_numeric() || in_c == '#' || in_c == '.';
-        if in_c == '%' {
-            if found_format_specifier {
+) -> Result<(FormattedString, Arguments), FormattedStringError> {
+    CompiledFormat::new(format_string).render(symbol_table, protocol, endianness, arg_data)
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+enum FormatToken {
+    Literal(String),
+    Conversion {
+        specifier: char,
+        subspec: SubSpecifier,
+    },
+}
+
+/// A user event format string parsed into literal text and conversion specifiers,
+/// so it can be rendered against the arguments of many events
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct CompiledFormat {
+    source: String,
+    tokens: Vec<FormatToken>,
+}
+
+impl CompiledFormat {
+    pub(crate) fn new(format_string: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut literal = String::new();
+        let mut found_format_specifier = false;
+        let mut found_subspec = SubSpecifier::None;
+
+        for in_c in format_string.chars() {
+            let is_width_or_padding = in_c.is_numeric() || in_c == '#' || in_c == '.';
+            if in_c == '%' {
+                if found_format_specifier {
+                    found_format_specifier = false;
+                    literal.push(in_c);
+                } else {
+                    found_format_specifier = true;
+                    found_subspec = SubSpecifier::None;
+                }
+            } else if found_format_specifier && is_width_or_padding {
+                // TODO - support width and padding, skip it for now
+            } else if found_format_specifier && in_c == 'l' {
+                found_subspec = SubSpecifier::Long;
+            } else if found_format_specifier && in_c == 'h' {
+                found_subspec = SubSpecifier::Short;
+            } else if found_format_specifier && in_c == 'b' {
+                found_subspec = SubSpecifier::Octet;
+            } else if found_format_specifier {
+                if !literal.is_empty() {
+                    tokens.push(FormatToken::Literal(std::mem::take(&mut literal)));
+                }
+                tokens.push(FormatToken::Conversion {
+                    specifier: in_c,
+                    subspec: found_subspec,
+                });
                 found_format_specifier = false;
-                formatted_string.push(in_c);
-            } else {
-                found_format_specifier = true;
                 found_subspec = SubSpecifier::None;
+            } else {
+                literal.push(in_c);
             }
-        } else if found_format_specifier && is_width_or_padding {
-            // TODO - support width and padding, skip it for now
-        } else if found_format_specifier && !is_width_or_padding && in_c == 'l' {
-            found_subspec = SubSpecifier::Long;
-        } else if found_format_specifier && !is_width_or_padding && in_c == 'h' {
-            found_subspec = SubSpecifier::Short;
-        } else if found_format_specifier && !is_width_or_padding && in_c == 'b' {
-            found_subspec = SubSpecifier::Octet;
-        } else if found_format_specifier && !is_width_or_padding {
+        }
+        if !literal.is_empty() {
+            tokens.push(FormatToken::Literal(literal));
+        }
+
+        Self {
+            source: format_string.to_owned(),
+            tokens,
+        }
+    }
+
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    // TODO - float & float endianness support, warn if not supported and found
+    // NOTE Assumes UTF8
+    pub(crate) fn render<S: SymbolTableExt>(
+        &self,
+        symbol_table: &S,
+        protocol: Protocol,
+        endianness: Endianness,
+        arg_data: &[u8],
+    ) -> Result<(FormattedString, Arguments), FormattedStringError> {
+        let format_string = self.source.as_str();
+        let mut r = ByteOrdered::runtime(arg_data, byteordered::Endianness::from(endianness));
+        let mut formatted_string = String::with_capacity(format_string.len());
+        let mut args = Arguments::new();
+
+        for token in self.tokens.iter() {
+            let (in_c, found_subspec) = match token {
+                FormatToken::Literal(s) => {
+                    formatted_string.push_str(s);
+                    continue;
+                }
+                FormatToken::Conversion { specifier, subspec } => (*specifier, *subspec),
+            };
+            let is_hex = match in_c {
+                'x' => Some(HexDisplay::Lowercase),
+                'X' => Some(HexDisplay::Uppercase),
+                _ => None,
+            };
             let arg = match in_c {
                 'd' if matches!(found_subspec, SubSpecifier::None) => Argument::I32(r.read_i32()?),
                 'u' if matches!(found_subspec, SubSpecifier::None) => Argument::U32(r.read_u32()?),
-                'x' => {
-                    is_hex = Some(HexDisplay::Lowercase);
-                    Argument::U32(r.read_u32()?)
-                }
-                'X' => {
-                    is_hex = Some(HexDisplay::Uppercase);
-                    Argument::U32(r.read_u32()?)
-                }
+                'x' | 'X' => Argument::U32(r.read_u32()?),
                 's' => {
                     let arg_index = ObjectHandle::new(match protocol {
                         Protocol::Snapshot => r.read_u16()?.into(),
@@ -623,16 +994,108 @@ pub(crate) fn format_symbol_string<S: SymbolTableExt>(
             };
 
             args.push(arg);
+        }
 
-            found_format_specifier = false;
-            found_subspec = SubSpecifier::None;
-            is_hex = None;
-        } else {
-            formatted_string.push(in_c);
+        Ok((FormattedString(formatted_string), args))
+    }
+
+    /// Number of conversion specifiers, `%%` excluded
+    pub(crate) fn conversions(&self) -> usize {
+        self.tokens
+            .iter()
+            .filter(|t| matches!(t, FormatToken::Conversion { .. }))
+            .count()
+    }
+
+    /// The `key=value` pairs of the format string, see `key_value_fields`
+    pub(crate) fn key_values(&self, args: &[Argument]) -> Vec<(String, Argument)> {
+        // Events that couldn't be rendered have no arguments
+        let args_match = self.conversions() == args.len();
+        let mut fields = Vec::new();
+        let mut pending_key = None;
+        let mut arg_index = 0;
+
+        for token in self.tokens.iter() {
+            match token {
+                FormatToken::Literal(s) => {
+                    pending_key = None;
+                    let mut words = s.split(|c: char| c.is_whitespace() || c == ',' || c == ';');
+                    let last = words.next_back();
+                    for word in words {
+                        if let Some((key, value)) = word.split_once('=') {
+                            if !key.is_empty() && !value.is_empty() {
+                                fields.push((key.to_owned(), Argument::String(value.to_owned())));
+                            }
+                        }
+                    }
+                    match last.and_then(|w| w.split_once('=')) {
+                        Some((key, "")) if !key.is_empty() => pending_key = Some(key),
+                        Some((key, value)) if !key.is_empty() => {
+                            fields.push((key.to_owned(), Argument::String(value.to_owned())))
+                        }
+                        _ => (),
+                    }
+                }
+                FormatToken::Conversion { .. } => {
+                    if let (Some(key), true) = (pending_key.take(), args_match) {
+                        fields.push((key.to_owned(), args[arg_index].clone()));
+                    }
+                    arg_index += 1;
+                }
+            }
         }
+        fields
     }
+}
 
-    Ok((FormattedString(formatted_string), args))
+/// Compiled user event format strings, compiled on first use.
+///
+/// Format strings referenced by a symbol handle are keyed by the handle, the
+/// symbol is compared on lookup since handles can be reused for other strings.
+/// Format strings carried in the events are keyed by the string itself.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) struct FormatCache {
+    by_handle: BTreeMap<ObjectHandle, CompiledFormat>,
+    by_string: BTreeMap<String, CompiledFormat>,
+}
+
+impl FormatCache {
+    pub(crate) fn get(
+        &mut self,
+        handle: Option<ObjectHandle>,
+        format_string: &str,
+    ) -> &CompiledFormat {
+        match handle {
+            Some(handle) => {
+                let compiled = self
+                    .by_handle
+                    .entry(handle)
+                    .or_insert_with(|| CompiledFormat::new(format_string));
+                if compiled.source() != format_string {
+                    *compiled = CompiledFormat::new(format_string);
+                }
+                compiled
+            }
+            None => {
+                if !self.by_string.contains_key(format_string) {
+                    self.by_string
+                        .insert(format_string.to_owned(), CompiledFormat::new(format_string));
+                }
+                &self.by_string[format_string]
+            }
+        }
+    }
+}
+
+/// Split a user event into the `key=value` pairs of its format string, as in
+/// `"temp=%d rpm=%u mode=%s"`.
+///
+/// Keys are the words directly followed by `=`, with words separated by
+/// whitespace, `,` or `;`. A value that's a conversion specifier yields the
+/// event's argument, a literal value yields a string argument. Text that
+/// isn't a `key=value` pair and conversions without a key are skipped.
+pub fn key_value_fields(format_string: &str, args: &[Argument]) -> Vec<(String, Argument)> {
+    CompiledFormat::new(format_string).key_values(args)
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -671,6 +1134,18 @@ impl TimerCounter {
             _ => return None,
         })
     }
+
+    pub(crate) fn hwtc_type(&self) -> u32 {
+        use TimerCounter::*;
+        match self {
+            FreeRunning32Incr => 1,
+            FreeRunning32Decr => 2,
+            OsIncr => 3,
+            OsDecr => 4,
+            CustomIncr => 5,
+            CustomDecr => 6,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
@@ -739,14 +1214,76 @@ mod test {
 
     #[test]
     fn trimmed_string() {
-        assert_eq!(TrimmedString::from_raw(b"foo bar").0.as_str(), "foo bar");
         assert_eq!(
-            TrimmedString::from_raw(b"foo bar\0\0\0").0.as_str(),
+            TrimmedString::from_raw_with(b"foo bar", &StringDecoder::default())
+                .0
+                .as_str(),
             "foo bar"
         );
-        assert_eq!(TrimmedString::from_raw(b"foo\0\0\0bar").0.as_str(), "foo");
-        assert_eq!(TrimmedString::from_raw(b"\0foo\0\0\0bar").0.as_str(), "");
-        assert_eq!(TrimmedString::from_raw(b"").0.as_str(), "");
+        assert_eq!(
+            TrimmedString::from_raw_with(b"foo bar\0\0\0", &StringDecoder::default())
+                .0
+                .as_str(),
+            "foo bar"
+        );
+        assert_eq!(
+            TrimmedString::from_raw_with(b"foo\0\0\0bar", &StringDecoder::default())
+                .0
+                .as_str(),
+            "foo"
+        );
+        assert_eq!(
+            TrimmedString::from_raw_with(b"\0foo\0\0\0bar", &StringDecoder::default())
+                .0
+                .as_str(),
+            ""
+        );
+        assert_eq!(
+            TrimmedString::from_raw_with(b"", &StringDecoder::default())
+                .0
+                .as_str(),
+            ""
+        );
+    }
+
+    #[test]
+    fn trimmed_string_decoders() {
+        let raw = b"caf\xE9\n\0\0";
+        assert_eq!(
+            TrimmedString::from_raw_with(raw, &StringDecoder::Utf8Lossy).0,
+            "caf\u{FFFD}"
+        );
+        assert_eq!(
+            TrimmedString::from_raw_with(raw, &StringDecoder::Latin1).0,
+            "caf\u{E9}"
+        );
+        let upper = StringDecoder::Custom(|b| String::from_utf8_lossy(b).to_uppercase());
+        assert_eq!(TrimmedString::from_raw_with(b"idle\0", &upper).0, "IDLE");
+    }
+
+    #[test]
+    fn string_sanitizer() {
+        let raw = " \u{3000}t\x01ask\x7F \u{3000}";
+        assert_eq!(StringSanitizer::new().sanitize(raw), "t\u{FFFD}ask\u{FFFD}");
+        assert_eq!(
+            StringSanitizer::new()
+                .removing_non_printables()
+                .sanitize(raw),
+            "task"
+        );
+        assert_eq!(
+            StringSanitizer::new()
+                .with_replacement('?')
+                .without_trimming()
+                .sanitize(raw),
+            " \u{3000}t?ask? \u{3000}"
+        );
+        assert_eq!(
+            StringSanitizer::new()
+                .with_max_len(4)
+                .sanitize("\u{E9}t\u{E9} long"),
+            "\u{E9}t\u{E9}"
+        );
     }
 
     #[test]
@@ -757,24 +1294,24 @@ mod test {
         let fmt = "literal";
         assert_eq!(
             format_symbol_string(&sn_st, Protocol::Snapshot, Endianness::Little, fmt, &[]).unwrap(),
-            (FormattedString(fmt.to_string()), vec![])
+            (FormattedString(fmt.to_string()), Arguments::new())
         );
         assert_eq!(
             format_symbol_string(&sr_st, Protocol::Streaming, Endianness::Little, fmt, &[])
                 .unwrap(),
-            (FormattedString(fmt.to_string()), vec![])
+            (FormattedString(fmt.to_string()), Arguments::new())
         );
 
         let fmt = "foo bar biz %%";
         let out = "foo bar biz %";
         assert_eq!(
             format_symbol_string(&sn_st, Protocol::Snapshot, Endianness::Little, fmt, &[]).unwrap(),
-            (FormattedString(out.to_string()), vec![])
+            (FormattedString(out.to_string()), Arguments::new())
         );
         assert_eq!(
             format_symbol_string(&sr_st, Protocol::Streaming, Endianness::Little, fmt, &[])
                 .unwrap(),
-            (FormattedString(out.to_string()), vec![])
+            (FormattedString(out.to_string()), Arguments::new())
         );
 
         let fmt = "my int %d = %02u";
@@ -794,7 +1331,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::I32(-1), Argument::U32(23)]
+                Arguments::from(vec![Argument::I32(-1), Argument::U32(23)])
             )
         );
         assert_eq!(
@@ -808,7 +1345,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::I32(-1), Argument::U32(23)]
+                Arguments::from(vec![Argument::I32(-1), Argument::U32(23)])
             )
         );
 
@@ -826,7 +1363,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::F32(OrderedFloat::from(-1.1_f32))]
+                Arguments::from(vec![Argument::F32(OrderedFloat::from(-1.1_f32))])
             )
         );
         assert_eq!(
@@ -840,7 +1377,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::F32(OrderedFloat::from(-1.1_f32))]
+                Arguments::from(vec![Argument::F32(OrderedFloat::from(-1.1_f32))])
             )
         );
 
@@ -861,7 +1398,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::I8(-4), Argument::I16(-25)]
+                Arguments::from(vec![Argument::I8(-4), Argument::I16(-25)])
             )
         );
         let arg_bytes: Vec<u8> = i32::to_le_bytes(-4_i8 as i32)
@@ -879,7 +1416,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::I8(-4), Argument::I16(-25)]
+                Arguments::from(vec![Argument::I8(-4), Argument::I16(-25)])
             )
         );
 
@@ -887,7 +1424,8 @@ mod test {
         let out = "my string = 'foo'";
         let str_arg = b"foo\0";
         let handle = ObjectHandle::new(1).unwrap();
-        let symbol: SymbolString = TrimmedString::from_raw(str_arg).into();
+        let symbol: SymbolString =
+            TrimmedString::from_raw_with(str_arg, &StringDecoder::default()).into();
         sn_st.insert(
             handle,
             None,
@@ -907,7 +1445,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::String(symbol.0.clone())]
+                Arguments::from(vec![Argument::String(symbol.to_string())])
             )
         );
         assert_eq!(
@@ -921,7 +1459,7 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![Argument::String(symbol.0)]
+                Arguments::from(vec![Argument::String(symbol.to_string())])
             )
         );
 
@@ -945,14 +1483,87 @@ mod test {
             .unwrap(),
             (
                 FormattedString(out.to_string()),
-                vec![
+                Arguments::from(vec![
                     Argument::U32(1),
                     Argument::U32(2),
                     Argument::U32(3),
                     Argument::U32(0xFE),
                     Argument::U32(0xFF),
-                ]
+                ])
             )
         );
     }
+
+    #[test]
+    fn arguments_past_inline_capacity() {
+        let st = crate::streaming::EntryTable::default();
+        let arg_bytes: Vec<u8> = [(-7_i32) as u32, 0xFF, 3]
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect();
+        let (formatted, args) = format_symbol_string(
+            &st,
+            Protocol::Streaming,
+            Endianness::Little,
+            "%d %x %u",
+            &arg_bytes,
+        )
+        .unwrap();
+        assert_eq!(formatted.as_str(), "-7 ff 3");
+        assert!(args.spilled());
+        assert_eq!(
+            args.as_slice(),
+            &[Argument::I32(-7), Argument::U32(0xFF), Argument::U32(3)]
+        );
+        assert_eq!(args.iter().cloned().collect::<Arguments>(), args);
+        assert_eq!(args.iter().filter_map(Argument::as_f64).sum::<f64>(), 251.0);
+    }
+
+    #[test]
+    fn format_cache_recompiles_reused_handles() {
+        let st = crate::streaming::EntryTable::default();
+        let mut cache = FormatCache::default();
+        let handle = ObjectHandle::new(4).unwrap();
+        let arg = 10_u32.to_le_bytes();
+        let render = |c: &CompiledFormat| {
+            c.render(&st, Protocol::Streaming, Endianness::Little, &arg)
+                .unwrap()
+                .0
+                 .0
+        };
+        assert_eq!(render(cache.get(Some(handle), "a=%d")), "a=10");
+        assert_eq!(render(cache.get(Some(handle), "a=%d")), "a=10");
+        assert_eq!(render(cache.get(Some(handle), "b=%x %%")), "b=a %");
+        assert_eq!(render(cache.get(None, "c=%u")), "c=10");
+        assert_eq!(cache.by_handle.len(), 1);
+        assert_eq!(cache.by_string.len(), 1);
+    }
+
+    #[test]
+    fn key_value_pairs() {
+        let fields = key_value_fields(
+            "temp=%d rpm=%u, mode=fast; %u free, err=%s",
+            &[
+                Argument::I32(-4),
+                Argument::U32(1200),
+                Argument::U32(7),
+                Argument::String("none".to_owned()),
+            ],
+        );
+        assert_eq!(
+            fields,
+            vec![
+                ("temp".to_owned(), Argument::I32(-4)),
+                ("rpm".to_owned(), Argument::U32(1200)),
+                ("mode".to_owned(), Argument::String("fast".to_owned())),
+                ("err".to_owned(), Argument::String("none".to_owned())),
+            ]
+        );
+
+        // Unrendered events have no arguments
+        assert_eq!(
+            key_value_fields("a=%q b=c", &[]),
+            vec![("b".to_owned(), Argument::String("c".to_owned()))]
+        );
+    }
 }
@@ -0,0 +1,367 @@
+//! Value change dump (VCD) export of task states, ISRs and user event channels
+//! for waveform viewers like GTKWave
+//!
+//! * Each task is a 2-bit signal: 0 blocked (or not seen yet), 1 ready, 2 running.
+//!   A task preempted by an ISR is shown as ready.
+//! * Each ISR is a 1-bit signal, high while it or an ISR nested on top of it runs.
+//! * Each user event channel is a real signal holding the first numeric argument
+//!   of its events, so channels used like GPIOs or counters show up as waveforms.
+//!
+//! Signals must be declared before any value changes, so the changes are
+//! buffered until `write` is called.
+
+use crate::analysis::sched::{ContextStack, SchedEvent};
+use crate::analysis::StreamingClock;
+use crate::session::SessionInfo;
+use crate::snapshot;
+use crate::streaming;
+use crate::time::{Frequency, Timestamp};
+use crate::types::ObjectHandle;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+const BLOCKED: u8 = 0;
+const READY: u8 = 1;
+const RUNNING: u8 = 2;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum SignalKind {
+    Task,
+    Isr,
+    Channel,
+}
+
+#[derive(Clone, Debug)]
+struct Signal {
+    kind: SignalKind,
+    name: String,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Value {
+    Bits(u8),
+    Real(f64),
+}
+
+#[derive(Clone, Debug)]
+pub struct VcdExporter {
+    frequency: Frequency,
+    clock: StreamingClock,
+    signals: Vec<Signal>,
+    tasks: BTreeMap<ObjectHandle, usize>,
+    isrs: BTreeMap<ObjectHandle, usize>,
+    channels: BTreeMap<String, usize>,
+    task_states: BTreeMap<ObjectHandle, u8>,
+    stack: ContextStack,
+    blocking: bool,
+    changes: Vec<(Timestamp, usize, Value)>,
+    session: Option<SessionInfo>,
+}
+
+impl VcdExporter {
+    /// Timestamps are converted to nanoseconds using the timer `frequency`,
+    /// they're written in ticks when the frequency is unknown
+    pub fn new(frequency: Frequency) -> Self {
+        Self {
+            frequency,
+            clock: StreamingClock::default(),
+            signals: Vec::new(),
+            tasks: BTreeMap::new(),
+            isrs: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            task_states: BTreeMap::new(),
+            stack: ContextStack::default(),
+            blocking: false,
+            changes: Vec::new(),
+            session: None,
+        }
+    }
+
+    /// Write the session metadata as comments in the header
+    pub fn with_session_info(mut self, session: SessionInfo) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    pub fn process_streaming_event(&mut self, event: &streaming::event::Event) {
+        let t = self.clock.time(event.timestamp());
+        if let Some(ev) = SchedEvent::from_streaming(event) {
+            self.process(t, &ev);
+        }
+    }
+
+    pub fn process_snapshot_event(&mut self, event: &snapshot::event::Event) {
+        if let Some(ev) = SchedEvent::from_snapshot(event) {
+            self.process(event.timestamp(), &ev);
+        }
+    }
+
+    /// Write the header, signal definitions and value changes
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "$version {} {} $end",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        if self.frequency.is_unitless() {
+            writeln!(
+                w,
+                "$comment Timer frequency unknown, times are in ticks $end"
+            )?;
+        }
+        if let Some(session) = &self.session {
+            for line in session.to_string().lines() {
+                writeln!(w, "$comment {line} $end")?;
+            }
+        }
+        writeln!(w, "$timescale 1 ns $end")?;
+        for (kind, scope) in [
+            (SignalKind::Task, "tasks"),
+            (SignalKind::Isr, "isrs"),
+            (SignalKind::Channel, "user_events"),
+        ] {
+            writeln!(w, "$scope module {scope} $end")?;
+            for (idx, s) in self
+                .signals
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.kind == kind)
+            {
+                let (var, width) = match kind {
+                    SignalKind::Task => ("reg", 2),
+                    SignalKind::Isr => ("wire", 1),
+                    SignalKind::Channel => ("real", 64),
+                };
+                writeln!(
+                    w,
+                    "$var {var} {width} {} {} $end",
+                    identifier(idx),
+                    reference(&s.name)
+                )?;
+            }
+            writeln!(w, "$upscope $end")?;
+        }
+        writeln!(w, "$enddefinitions $end")?;
+
+        writeln!(w, "$dumpvars")?;
+        for (idx, s) in self.signals.iter().enumerate() {
+            match s.kind {
+                SignalKind::Task => writeln!(w, "b{BLOCKED:b} {}", identifier(idx))?,
+                SignalKind::Isr => writeln!(w, "0{}", identifier(idx))?,
+                SignalKind::Channel => writeln!(w, "r0 {}", identifier(idx))?,
+            }
+        }
+        writeln!(w, "$end")?;
+
+        let mut time = None;
+        for (t, idx, value) in self.changes.iter() {
+            let t = self.nanoseconds(*t);
+            if time != Some(t) {
+                time = Some(t);
+                writeln!(w, "#{t}")?;
+            }
+            match (self.signals[*idx].kind, value) {
+                (SignalKind::Isr, Value::Bits(b)) => writeln!(w, "{b}{}", identifier(*idx))?,
+                (_, Value::Bits(b)) => writeln!(w, "b{b:b} {}", identifier(*idx))?,
+                (_, Value::Real(r)) => writeln!(w, "r{r} {}", identifier(*idx))?,
+            }
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, t: Timestamp, event: &SchedEvent<'_>) {
+        match event {
+            SchedEvent::TaskSwitch(handle, name) => {
+                if let Some(prev) = self.stack.task.filter(|prev| prev != handle) {
+                    if self.task_states.get(&prev) == Some(&RUNNING) {
+                        let state = if self.blocking { BLOCKED } else { READY };
+                        self.set_task_state(t, prev, None, state);
+                    }
+                }
+                self.blocking = false;
+                self.set_task_state(t, *handle, *name, RUNNING);
+            }
+            SchedEvent::IsrBegin(handle, name) | SchedEvent::IsrResume(handle, name) => {
+                if let Some(task) = self.stack.task {
+                    if self.task_states.get(&task) == Some(&RUNNING) {
+                        self.set_task_state(t, task, None, READY);
+                    }
+                }
+                let idx = signal(
+                    &mut self.signals,
+                    &mut self.isrs,
+                    *handle,
+                    SignalKind::Isr,
+                    name,
+                );
+                // Resuming an ISR that began before the trace did also raises it
+                if matches!(event, SchedEvent::IsrBegin(..)) || !self.stack.isrs.contains(handle) {
+                    self.changes.push((t, idx, Value::Bits(1)));
+                }
+            }
+            SchedEvent::TaskReady(handle, name) => {
+                if self.task_states.get(handle) != Some(&RUNNING) {
+                    self.set_task_state(t, *handle, Some(name), READY);
+                }
+            }
+            SchedEvent::Block => self.blocking = true,
+            SchedEvent::User(channel, value) => {
+                if let Some(value) = value {
+                    let idx = match self.channels.get(channel) {
+                        Some(idx) => *idx,
+                        None => {
+                            self.signals.push(Signal {
+                                kind: SignalKind::Channel,
+                                name: channel.clone(),
+                            });
+                            self.channels
+                                .insert(channel.clone(), self.signals.len() - 1);
+                            self.signals.len() - 1
+                        }
+                    };
+                    self.changes.push((t, idx, Value::Real(*value)));
+                }
+            }
+        }
+
+        for isr in self.stack.apply(event) {
+            if let Some(idx) = self.isrs.get(&isr) {
+                self.changes.push((t, *idx, Value::Bits(0)));
+            }
+        }
+    }
+
+    fn set_task_state(
+        &mut self,
+        t: Timestamp,
+        handle: ObjectHandle,
+        name: Option<&str>,
+        state: u8,
+    ) {
+        let name = name
+            .map(str::to_owned)
+            .unwrap_or_else(|| handle.to_string());
+        let idx = signal(
+            &mut self.signals,
+            &mut self.tasks,
+            handle,
+            SignalKind::Task,
+            &name,
+        );
+        if self.task_states.insert(handle, state) != Some(state) {
+            self.changes.push((t, idx, Value::Bits(state)));
+        }
+    }
+
+    fn nanoseconds(&self, t: Timestamp) -> u64 {
+        t.to_time(self.frequency).nanos_or_ticks()
+    }
+}
+
+fn signal(
+    signals: &mut Vec<Signal>,
+    map: &mut BTreeMap<ObjectHandle, usize>,
+    handle: ObjectHandle,
+    kind: SignalKind,
+    name: &str,
+) -> usize {
+    *map.entry(handle).or_insert_with(|| {
+        signals.push(Signal {
+            kind,
+            name: name.to_owned(),
+        });
+        signals.len() - 1
+    })
+}
+
+/// Short identifier made of printable ASCII characters
+fn identifier(mut idx: usize) -> String {
+    const FIRST: u8 = b'!';
+    const NUM: usize = (b'~' - b'!' + 1) as usize;
+    let mut id = String::new();
+    loop {
+        id.push(char::from(FIRST + (idx % NUM) as u8));
+        idx /= NUM;
+        if idx == 0 {
+            break id;
+        }
+        idx -= 1;
+    }
+}
+
+/// Signal names can't contain whitespace
+fn reference(name: &str) -> String {
+    match name.trim() {
+        "" => "_".to_owned(),
+        n => n.replace(char::is_whitespace, "_"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::snapshot::event::{Event, IsrEvent, TaskEvent, UserEvent};
+    use crate::snapshot::object_properties::TaskState;
+    use crate::types::{
+        Argument, FormatString, FormattedString, ObjectName, Priority, UserEventChannel,
+    };
+
+    fn task(handle: u32, name: &str, t: u64) -> TaskEvent {
+        TaskEvent {
+            handle: ObjectHandle::new(handle).unwrap(),
+            name: ObjectName(name.into()),
+            state: TaskState::Active,
+            priority: Priority(1),
+            timestamp: Timestamp(t),
+        }
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier(0), "!");
+        assert_eq!(identifier(93), "~");
+        assert_eq!(identifier(94), "!!");
+        assert_eq!(reference(" Tmr Svc"), "Tmr_Svc");
+    }
+
+    #[test]
+    fn snapshot_task_isr_and_channel_signals() {
+        let events = [
+            Event::TaskBegin(task(1, "a", 0)),
+            Event::TaskReady(task(2, "b", 10)),
+            Event::IsrBegin(IsrEvent {
+                handle: ObjectHandle::new(3).unwrap(),
+                name: ObjectName("tick".into()),
+                priority: Priority(5),
+                timestamp: Timestamp(20),
+            }),
+            Event::TaskResume(task(2, "b", 30)),
+            Event::User(UserEvent {
+                timestamp: Timestamp(40),
+                channel: UserEventChannel::Custom("gpio".to_owned()),
+                format_string: FormatString("%d".into()),
+                formatted_string: FormattedString("1".to_owned()),
+                args: vec![Argument::U8(1)].into(),
+            }),
+        ];
+        let mut vcd = VcdExporter::new(Frequency(1_000_000));
+        events.iter().for_each(|ev| vcd.process_snapshot_event(ev));
+        let mut out = Vec::new();
+        vcd.write(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("$var reg 2 ! a $end"));
+        assert!(out.contains("$var reg 2 \" b $end"));
+        assert!(out.contains("$var wire 1 # tick $end"));
+        assert!(out.contains("$var real 64 $ gpio $end"));
+        let changes = &out[out.find("#0").unwrap()..];
+        assert_eq!(
+            changes.lines().collect::<Vec<_>>(),
+            vec![
+                "#0", "b10 !", "#10000", "b1 \"", "#20000", "b1 !", "1#", "#30000", "b10 \"", "0#",
+                "#40000", "r1 $"
+            ]
+        );
+    }
+}
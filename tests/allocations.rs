@@ -0,0 +1,119 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use trace_recorder_parser::{streaming::event::*, streaming::*, types::*};
+
+const TRACE_V14: &str = "test_resources/fixtures/streaming/v14/trace.psf";
+
+/// Counts the allocations made by the current thread, the tests run in parallel
+struct CountingAllocator;
+
+thread_local! {
+    static NUM_ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = NUM_ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = NUM_ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn num_allocs() -> usize {
+    NUM_ALLOCS.with(|n| n.get())
+}
+
+#[test]
+fn streaming_v14_visitor_fast_path_does_not_allocate() {
+    /// Whether the last event was handed to one of the borrowed fast path methods
+    #[derive(Default)]
+    struct FastPath(bool);
+
+    impl EventVisitor for FastPath {
+        fn visit_task_activate(
+            &mut self,
+            _header: &EventHeader,
+            _handle: ObjectHandle,
+            _name: &str,
+            _priority: Priority,
+        ) {
+            self.0 = true;
+        }
+
+        fn visit_object_operation(
+            &mut self,
+            _header: &EventHeader,
+            _handle: ObjectHandle,
+            _name: Option<&str>,
+            _params: &[u32],
+        ) {
+            self.0 = true;
+        }
+
+        fn visit_memory_alloc(
+            &mut self,
+            _header: &EventHeader,
+            _address: u32,
+            _size: u32,
+            _heap: &Heap,
+        ) {
+            self.0 = true;
+        }
+
+        fn visit_memory_free(
+            &mut self,
+            _header: &EventHeader,
+            _address: u32,
+            _size: u32,
+            _heap: &Heap,
+        ) {
+            self.0 = true;
+        }
+    }
+
+    let mut bytes = Vec::new();
+    File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join(TRACE_V14))
+        .unwrap()
+        .read_to_end(&mut bytes)
+        .unwrap();
+    let mut r = bytes.as_slice();
+    let mut rd = RecorderData::find(&mut r).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut v = FastPath::default();
+
+    // Warm up the buffers kept between events
+    for _ in 0..8 {
+        assert!(rd.visit_event(&mut r, &mut v).unwrap());
+    }
+
+    let mut num_fast_path = 0;
+    loop {
+        v.0 = false;
+        let before = num_allocs();
+        match rd.visit_event(&mut r, &mut v) {
+            Ok(true) => (),
+            // The fixture ends with a restart
+            Ok(false) | Err(Error::TraceRestarted(_)) => break,
+            Err(e) => panic!("{e}"),
+        }
+        if v.0 {
+            assert_eq!(num_allocs(), before, "offset {}", rd.bytes_read());
+            num_fast_path += 1;
+        }
+    }
+    assert!(num_fast_path > 0);
+}
@@ -8,6 +8,10 @@ const TRACE_V10: &str = "test_resources/fixtures/streaming/v10/trace.psf";
 const TRACE_V12: &str = "test_resources/fixtures/streaming/v12/trace.psf";
 const TRACE_V13: &str = "test_resources/fixtures/streaming/v13/trace.psf";
 const TRACE_V14: &str = "test_resources/fixtures/streaming/v14/trace.psf";
+/// The v14 header and entry table followed by stream buffer events
+const TRACE_V14_STREAM_BUFFER: &str = "test_resources/fixtures/streaming/v14/stream_buffer.psf";
+/// The v14 trace in 256 byte J-Link RAW chunks
+const TRACE_V14_JLINK_RAW: &str = "test_resources/fixtures/streaming/v14/trace.jlink.raw";
 
 fn open_trace_file(trace_path: &str) -> File {
     let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(trace_path);
@@ -158,6 +162,121 @@ fn streaming_v14_garbage_with_trace_restart() {
     }
 }
 
+#[test]
+fn streaming_v14_read_event_auto_restart() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+
+    for _ in 0..64 {
+        let (code, _) = rd.read_event_auto_restart(&mut f).unwrap().unwrap();
+        assert!(!code.is_pseudo());
+    }
+    let (code, ev) = rd.read_event_auto_restart(&mut f).unwrap().unwrap();
+    assert_eq!(code, EventCode::SESSION_BOUNDARY);
+    assert_eq!(code.event_type(), EventType::SessionBoundary);
+    assert_ne!(code, EventCode::DROPPED);
+    assert_ne!(code, EventCode::ANNOTATION);
+    assert_ne!(code.event_type(), EventType::Null);
+    assert!(matches!(
+        ev,
+        Event::SessionBoundary(SessionBoundaryEvent { session: 1, .. })
+    ));
+    assert_eq!(rd.session(), 1);
+    assert_eq!(rd.header.format_version, 14);
+
+    let (code, ev) = rd.read_event_auto_restart(&mut f).unwrap().unwrap();
+    assert_eq!(code.event_type(), EventType::TraceStart);
+    assert_eq!(ev.timestamp(), Timestamp::from(Ticks::new(64)));
+}
+
+#[test]
+fn streaming_v14_restart_reusing_handles() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    let mut r = bytes.as_slice();
+    RecorderData::find(&mut r).unwrap();
+    let events_start = bytes.len() - r.len();
+    let psf = HeaderInfo::PSF_LITTLE_ENDIAN.to_le_bytes();
+    let header_start = bytes.windows(4).position(|w| w == psf).unwrap();
+    let header = &bytes[header_start..events_start];
+
+    let h = 0x2000_1000_u32;
+    let object_name = |count: u16, timestamp: u32, name: &[u8; 4]| {
+        let mut ev = Vec::new();
+        ev.extend_from_slice(&(0x2003_u16).to_le_bytes());
+        ev.extend_from_slice(&count.to_le_bytes());
+        ev.extend_from_slice(&timestamp.to_le_bytes());
+        ev.extend_from_slice(&h.to_le_bytes());
+        ev.extend_from_slice(name);
+        ev
+    };
+    // The restarted trace reuses the handle for another object
+    let mut data = header.to_vec();
+    data.extend(object_name(10, 100, b"rx\0\0"));
+    data.extend(object_name(11, 110, b"rx\0\0"));
+    data.extend_from_slice(header);
+    data.extend(object_name(7, 50, b"tx\0\0"));
+
+    let mut r = data.as_slice();
+    let mut rd = RecorderData::find(&mut r).unwrap();
+    rd.enable_handle_index();
+    rd.enable_object_stats();
+    rd.enable_entry_table_history(SnapshotPolicy::OnChange);
+    let mut events = Vec::new();
+    while let Some((_, ev)) = rd.read_event_auto_restart(&mut r).unwrap() {
+        events.push(ev);
+    }
+    assert_eq!(events.len(), 4);
+    let Event::SessionBoundary(boundary) = &events[2] else {
+        panic!("expected a session boundary, got {:?}", events[2]);
+    };
+    assert_eq!(boundary.session, 1);
+    assert_eq!(u16::from(boundary.event_count), 7);
+    assert_eq!(boundary.sequence, 7);
+    assert_eq!(boundary.timestamp, Timestamp::from(Ticks::new(50)));
+    assert_eq!(events[3].event_count(), boundary.event_count);
+    assert_eq!(rd.bytes_read() as usize, data.len() - header.len());
+
+    let h = events[3].object_handle().unwrap();
+    assert_eq!(events[0].object_handle(), Some(h));
+    assert_eq!(rd.entry_table.symbol(h).unwrap().as_ref(), "tx");
+    assert!(rd.name_history(h).is_empty());
+    assert!(rd
+        .diagnostics()
+        .iter()
+        .all(|d| !d.message.contains("renamed")));
+
+    let [finished] = rd.finished_sessions() else {
+        panic!("expected one finished session");
+    };
+    assert_eq!(finished.session, 0);
+    let index = finished.handle_index.as_ref().unwrap();
+    assert_eq!(index.positions(h).len(), 2);
+    assert_eq!(
+        finished
+            .object_stats
+            .as_ref()
+            .unwrap()
+            .object(h)
+            .unwrap()
+            .events,
+        2
+    );
+    let history = finished.entry_table_history.as_ref().unwrap();
+    assert_eq!(history.at(100).unwrap().symbol(h).unwrap().as_ref(), "rx");
+
+    let index = rd.handle_index().unwrap();
+    assert_eq!(index.events(), 1);
+    assert_eq!(
+        index.positions(h)[0].offset as usize,
+        data.len() - header.len() - 16
+    );
+    assert_eq!(rd.object_stats().unwrap().object(h).unwrap().events, 1);
+    let history = rd.entry_table_history().unwrap();
+    assert_eq!(history.at(100).unwrap().symbol(h).unwrap().as_ref(), "tx");
+}
+
 struct CommonTestConfig {
     trace_path: &'static str,
     expected_trace_format_version: u16,
@@ -318,3 +437,646 @@ fn common_tests(cfg: CommonTestConfig) {
         trd.check_event(UnusedStack);
     }
 }
+
+#[test]
+fn streaming_v14_user_event_slices() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut slicer = TraceSlicer::user_event_markers("ch1", "1 arg", "3 args");
+
+    let mut slices = Vec::new();
+    // The fixture has trailing data after the last complete event
+    while let Ok(Some((code, event))) = rd.read_event(&mut f) {
+        if let Some(slice) = slicer.process(code, &event, &rd.entry_table) {
+            slices.push(slice);
+        }
+    }
+    assert!(slicer.finish().is_none());
+
+    assert_eq!(slices.len(), 1);
+    let slice = &slices[0];
+    assert!(slice.complete);
+    assert_eq!(slice.index, 0);
+    let counts: Vec<u16> = slice
+        .events
+        .iter()
+        .map(|(_, ev)| u16::from(ev.event_count()))
+        .collect();
+    assert_eq!(counts, (59..=63).collect::<Vec<u16>>());
+}
+
+#[test]
+fn streaming_v14_batched_reads() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut expected = Vec::new();
+    for _ in 0..40 {
+        expected.push(rd.read_event(&mut f).unwrap().unwrap());
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut events = rd.read_events(&mut f, 16).unwrap();
+    assert_eq!(events.len(), 16);
+    let mut batch = Vec::new();
+    for _ in 0..2 {
+        assert_eq!(rd.read_events_into(&mut f, 12, &mut batch).unwrap(), 12);
+        events.extend_from_slice(&batch);
+    }
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn streaming_v14_visitor() {
+    #[derive(Default)]
+    struct Counts {
+        event_counts: Vec<u16>,
+        task_names: Vec<String>,
+        num_owned: usize,
+    }
+
+    impl EventVisitor for Counts {
+        fn visit_task_activate(
+            &mut self,
+            header: &EventHeader,
+            _handle: ObjectHandle,
+            name: &str,
+            _priority: Priority,
+        ) {
+            self.event_counts.push(header.event_count.into());
+            self.task_names.push(name.to_owned());
+        }
+
+        fn visit_object_operation(
+            &mut self,
+            header: &EventHeader,
+            _handle: ObjectHandle,
+            _name: Option<&str>,
+            _params: &[u32],
+        ) {
+            self.event_counts.push(header.event_count.into());
+        }
+
+        fn visit_event(&mut self, header: &EventHeader, event: &Event) {
+            assert_eq!(header.event_count, event.event_count());
+            self.event_counts.push(header.event_count.into());
+            self.num_owned += 1;
+        }
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut expected_counts = Vec::new();
+    let mut expected_task_names = Vec::new();
+    for _ in 0..64 {
+        let (_, ev) = rd.read_event(&mut f).unwrap().unwrap();
+        match &ev {
+            Event::TaskActivate(e) => expected_task_names.push(e.name.to_string()),
+            Event::TaskReady(_)
+            | Event::TaskResume(_)
+            | Event::IsrBegin(_)
+            | Event::IsrResume(_)
+            | Event::MemoryAlloc(_)
+            | Event::MemoryFree(_) => continue,
+            _ => (),
+        }
+        expected_counts.push(u16::from(ev.event_count()));
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut v = Counts::default();
+    for _ in 0..64 {
+        assert!(rd.visit_event(&mut f, &mut v).unwrap());
+    }
+    assert_eq!(v.event_counts, expected_counts);
+    assert_eq!(v.task_names, expected_task_names);
+    assert!(!v.task_names.is_empty());
+    assert!(v.num_owned > 0);
+}
+
+#[test]
+fn streaming_v14_visitor_post_processing_matches_read_event() {
+    struct Noop;
+    impl EventVisitor for Noop {}
+
+    fn recorder_data(f: &mut File) -> RecorderData {
+        let mut rd = RecorderData::find(f).unwrap();
+        rd.set_custom_printf_event_id(0x0FA0.into());
+        rd.enable_handle_index();
+        rd.enable_object_stats();
+        rd.enable_entry_table_history(SnapshotPolicy::OnChange);
+        rd.enable_timestamp_repair(TimestampRepair::default());
+        rd
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut read = recorder_data(&mut f);
+    while let Ok(Some(_)) = read.read_event(&mut f) {}
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut visited = recorder_data(&mut f);
+    while let Ok(true) = visited.visit_event(&mut f, &mut Noop) {}
+
+    assert!(read.handle_index().unwrap().events() > 0);
+    assert_eq!(visited.handle_index(), read.handle_index());
+    assert_eq!(visited.object_stats(), read.object_stats());
+    assert_eq!(
+        visited.entry_table_history().unwrap().snapshots(),
+        read.entry_table_history().unwrap().snapshots()
+    );
+    assert_eq!(visited.timestamp_repair(), read.timestamp_repair());
+}
+
+#[test]
+fn streaming_v14_isr_tail_chain_after_task_switch() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    // Take the handles from the fixture's events up to its ISR
+    let mut task = None;
+    let (isr, next_count) = loop {
+        match rd.read_event(&mut f).unwrap().unwrap().1 {
+            Event::TaskActivate(ev) => task = Some(u32::from(ev.handle)),
+            Event::IsrBegin(ev) => break (u32::from(ev.handle), u16::from(ev.event_count) + 1),
+            _ => (),
+        }
+    };
+    let task = task.unwrap();
+
+    // ISR, switch to a task, the ISR again 2 ticks later, then 100 ticks later
+    let mut events = Vec::new();
+    for (count, (et, handle, ts)) in [
+        (EventType::TaskSwitchIsrBegin, isr, 1000_u32),
+        (EventType::TaskSwitchTaskBegin, task, 1010),
+        (EventType::TaskSwitchIsrBegin, isr, 1012),
+        (EventType::TaskSwitchTaskBegin, task, 1020),
+        (EventType::TaskSwitchIsrBegin, isr, 1120),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        // A single parameter, the handle
+        events.extend((0x1000 | u16::from(EventId::from(et))).to_le_bytes());
+        events.extend((next_count + count as u16).to_le_bytes());
+        events.extend(ts.to_le_bytes());
+        events.extend(handle.to_le_bytes());
+    }
+
+    let mut r = events.as_slice();
+    let mut d = IsrTailChainDetector::with_threshold(Ticks::new(5));
+    let mut chains = Vec::new();
+    while let Some((_, ev)) = rd.read_event(&mut r).unwrap() {
+        chains.extend(d.process(&ev));
+    }
+    assert_eq!(chains.len(), 1);
+    assert_eq!(chains[0].timestamp.ticks(), 1012);
+    assert_eq!(chains[0].gap, Ticks::new(2));
+    assert_eq!(u32::from(chains[0].isr), isr);
+}
+
+#[test]
+fn streaming_v14_pipeline() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut expected = Vec::new();
+    while let Ok(Some(ev)) = rd.read_event(&mut f) {
+        expected.push(ev);
+    }
+    let expected_table = rd.entry_table;
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let table = rd.share_entry_table();
+    let pipeline = Pipeline::spawn(rd, f, 8, OverflowPolicy::Block);
+    let events: Vec<_> = pipeline.iter().collect();
+    assert_eq!(events, expected);
+
+    let stats = pipeline.stats();
+    assert_eq!(stats.queue_depth, 0);
+    assert!(stats.max_queue_depth <= 8);
+    assert_eq!(stats.events_parsed, expected.len() as u64);
+    assert_eq!(stats.events_dropped, 0);
+    // The fixture ends with a malformed event
+    assert!(pipeline.join().is_err());
+    assert_eq!(table.snapshot().as_ref(), &expected_table);
+}
+
+#[test]
+fn streaming_v14_entry_table_history() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.enable_entry_table_history(SnapshotPolicy::OnChange);
+    let mut num_events = 0;
+    while let Ok(Some(_)) = rd.read_event(&mut f) {
+        num_events += 1;
+    }
+    let history = rd.entry_table_history().unwrap();
+    let snapshots = history.snapshots();
+    assert!(!snapshots.is_empty() && snapshots.len() <= num_events);
+    let (last_count, last) = snapshots.last().unwrap();
+    assert_eq!(last.as_ref(), &rd.entry_table);
+    assert_eq!(history.at(*last_count + 100), Some(last));
+    // Snapshots are only taken when the table changed
+    assert!(snapshots.windows(2).all(|w| w[0].1 != w[1].1));
+}
+
+#[test]
+fn streaming_v14_handle_index() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    rd.enable_handle_index();
+    let mut events = Vec::new();
+    while let Ok(Some((_, event))) = rd.read_event(&mut f) {
+        events.push(event);
+    }
+    let index = rd.handle_index().unwrap();
+    assert_eq!(index.events(), events.len() as u64);
+    let busiest = index
+        .handles()
+        .max_by_key(|h| index.positions(*h).len())
+        .unwrap();
+    let positions = index.positions(busiest);
+    assert!(positions.len() > 1);
+    assert!(positions.windows(2).all(|w| w[0].offset < w[1].offset));
+    for p in positions {
+        assert_eq!(events[p.index as usize].object_handle(), Some(busiest));
+    }
+    let indexed: usize = index.handles().map(|h| index.positions(h).len()).sum();
+    let expected = events.iter().filter(|ev| ev.object_handle().is_some());
+    assert_eq!(indexed, expected.count());
+}
+
+#[test]
+fn streaming_v14_pcapng_roundtrip() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    let mut w = PcapngWriter::new(Vec::new()).unwrap();
+    for chunk in bytes.chunks(61) {
+        w.write_packet(chunk).unwrap();
+    }
+    let pcapng = w.into_inner();
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut r = PcapngReader::new(pcapng.as_slice())
+        .unwrap()
+        .into_payload_reader();
+    let mut pcapng_rd = RecorderData::find(&mut r).unwrap();
+    assert_eq!(pcapng_rd.header, rd.header);
+    let mut num_events = 0;
+    while let Ok(Some(expected)) = rd.read_event(&mut f) {
+        assert_eq!(pcapng_rd.read_event(&mut r).unwrap(), Some(expected));
+        num_events += 1;
+    }
+    assert!(num_events > 0);
+    assert!(r.packet_timestamp().is_some());
+}
+
+#[test]
+fn streaming_v14_jlink_raw() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut r = ChunkedReader::new(
+        open_trace_file(TRACE_V14_JLINK_RAW),
+        transport::ChunkLayout::jlink_raw(),
+    )
+    .unwrap();
+    let mut raw_rd = RecorderData::find(&mut r).unwrap();
+    assert_eq!(raw_rd.header, rd.header);
+    let mut num_events = 0;
+    while let Ok(Some(expected)) = rd.read_event(&mut f) {
+        assert_eq!(raw_rd.read_event(&mut r).unwrap(), Some(expected));
+        num_events += 1;
+    }
+    assert!(num_events > 0);
+    assert!(r.chunks() > 1);
+}
+
+#[test]
+fn streaming_v14_stream_buffer_events() {
+    let mut f = open_trace_file(TRACE_V14_STREAM_BUFFER);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let mut events = Vec::new();
+    while let Some((code, ev)) = rd.read_event(&mut f).unwrap() {
+        events.push((code.event_type(), ev));
+    }
+    assert_eq!(events.len(), 9);
+
+    let handle = match &events[0] {
+        (EventType::StreamBufferCreate, Event::StreamBufferCreate(ev)) => {
+            assert_eq!(u32::from(ev.handle), 0x2000_A000);
+            assert_eq!(ev.buffer_size, 64);
+            ev.handle
+        }
+        e => panic!("{e:?}"),
+    };
+    let name = Some("sbuf".to_owned());
+    assert_eq!(events[1].0, EventType::ObjectName);
+
+    let bytes_in_buffer: Vec<_> = events[2..]
+        .iter()
+        .map(|(typ, ev)| match ev {
+            Event::StreamBufferSend(ev)
+            | Event::StreamBufferSendFromIsr(ev)
+            | Event::StreamBufferReceive(ev)
+            | Event::StreamBufferReceiveFromIsr(ev)
+            | Event::StreamBufferReset(ev) => {
+                assert_eq!(ev.handle, handle);
+                assert_eq!(ev.name.as_ref().map(ToString::to_string), name);
+                (*typ, Some(ev.bytes_in_buffer))
+            }
+            Event::StreamBufferSendBlock(ev) | Event::StreamBufferReceiveBlock(ev) => {
+                assert_eq!(ev.handle, handle);
+                assert_eq!(ev.name.as_ref().map(ToString::to_string), name);
+                (*typ, None)
+            }
+            e => panic!("{e:?}"),
+        })
+        .collect();
+    assert_eq!(
+        bytes_in_buffer,
+        vec![
+            (EventType::StreamBufferSend, Some(16)),
+            (EventType::StreamBufferSendBlock, None),
+            (EventType::StreamBufferSendFromIsr, Some(24)),
+            (EventType::StreamBufferReceive, Some(8)),
+            (EventType::StreamBufferReceiveFromIsr, Some(0)),
+            (EventType::StreamBufferReceiveBlock, None),
+            (EventType::StreamBufferReset, Some(0)),
+        ]
+    );
+    assert_eq!(
+        rd.entry_table.class(handle),
+        Some(ObjectClass::StreamBuffer)
+    );
+}
+
+#[test]
+fn streaming_v14_udp_reordered_datagrams() {
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    // Split the events into datagrams of 3 events
+    let mut cursor = std::io::Cursor::new(bytes.as_slice());
+    let mut rd = RecorderData::find(&mut cursor).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut boundaries = vec![cursor.position() as usize];
+    while let Ok(Some(_)) = rd.read_event(&mut cursor) {
+        boundaries.push(cursor.position() as usize);
+    }
+    let header = &bytes[..boundaries[0]];
+    let mut datagrams: Vec<&[u8]> = boundaries
+        .iter()
+        .step_by(3)
+        .zip(boundaries.iter().skip(3).step_by(3))
+        .map(|(start, end)| &bytes[*start..*end])
+        .collect();
+    datagrams.chunks_mut(2).for_each(|pair| pair.reverse());
+
+    let mut src = UdpSource::bind("127.0.0.1:0")
+        .unwrap()
+        .with_window(4)
+        .with_custom_printf_event_id(0x0FA0.into());
+    src.socket()
+        .set_read_timeout(Some(Duration::from_millis(200)))
+        .unwrap();
+    let tx = UdpSocket::bind("127.0.0.1:0").unwrap();
+    tx.connect(src.socket().local_addr().unwrap()).unwrap();
+    tx.send(header).unwrap();
+    for datagram in datagrams.iter() {
+        tx.send(datagram).unwrap();
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut udp_rd = src.find_recorder_data().unwrap();
+    assert_eq!(udp_rd.entry_table, rd.entry_table);
+    for _ in 0..datagrams.len() * 3 {
+        let expected = rd.read_event(&mut f).unwrap();
+        assert_eq!(udp_rd.read_event(&mut src).unwrap(), expected);
+    }
+    let stats = src.gap_stats();
+    assert_eq!(stats.gaps, 0);
+    assert_eq!(stats.late, 0);
+    assert!(stats.out_of_order > 0);
+}
+
+#[test]
+fn streaming_v14_itm_stimulus_port() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    // Word writes to port 1, interleaved with port 0 writes and local timestamps
+    let mut itm = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x80];
+    let words = bytes.chunks_exact(4);
+    assert!(words.remainder().is_empty());
+    for word in words {
+        itm.push((1 << 3) | 0x03);
+        itm.extend_from_slice(word);
+        itm.extend_from_slice(&[0x01, b'x', 0xC0, 0x01]);
+    }
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut r = transport::ItmReader::new(itm.as_slice());
+    let mut itm_rd = RecorderData::find(&mut r).unwrap();
+    itm_rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut num_events = 0;
+    while let Ok(Some(expected)) = rd.read_event(&mut f) {
+        assert_eq!(itm_rd.read_event(&mut r).unwrap(), Some(expected));
+        num_events += 1;
+    }
+    assert!(num_events > 0);
+    assert_eq!(r.stats().overflows, 0);
+    assert_eq!(r.stats().invalid_headers, 0);
+}
+
+#[test]
+fn streaming_v14_truncated_final_event() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    let mut cursor = std::io::Cursor::new(bytes.as_slice());
+    let mut rd = RecorderData::find(&mut cursor).unwrap();
+    let first = rd.read_event(&mut cursor).unwrap().unwrap();
+    let second_start = cursor.position() as usize;
+    let second = rd.read_event(&mut cursor).unwrap().unwrap();
+
+    // Cut the capture 6 bytes into the second event
+    let mut partial = bytes[..second_start + 6].as_ref();
+    let mut rd = RecorderData::find(&mut partial).unwrap();
+    assert_eq!(rd.read_event(&mut partial).unwrap(), Some(first));
+    assert!(matches!(
+        rd.read_event(&mut partial),
+        Err(Error::TruncatedEvent(6))
+    ));
+    assert_eq!(rd.partial_event_len(), 6);
+
+    // The rest of the capture arrives
+    let mut rest = bytes[second_start + 6..].as_ref();
+    assert_eq!(rd.read_event(&mut rest).unwrap(), Some(second));
+    assert_eq!(rd.partial_event_len(), 0);
+
+    // A clean end of stream is still just the end
+    let mut empty = [].as_slice();
+    assert_eq!(rd.read_event(&mut empty).unwrap(), None);
+}
+
+#[test]
+fn streaming_v14_read_event_from_slice() {
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    let expected = rd.read_events(&mut f, 60).unwrap();
+    let events_len = rd.bytes_read() as usize;
+
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    let mut header = bytes.as_slice();
+    let mut rd = RecorderData::find(&mut header).unwrap();
+    let events_start = bytes.len() - header.len();
+
+    // Buffers of 50 bytes, events straddle their boundaries
+    let mut events = Vec::new();
+    for chunk in bytes[events_start..events_start + events_len].chunks(50) {
+        let mut buf = chunk;
+        while !buf.is_empty() {
+            let (consumed, event) = rd.read_event_from_slice(buf).unwrap();
+            assert!(consumed > 0);
+            buf = &buf[consumed..];
+            events.extend(event);
+        }
+    }
+    assert_eq!(rd.read_event_from_slice(&[]).unwrap(), (0, None));
+    assert_eq!(rd.partial_event_len(), 0);
+    assert_eq!(events, expected);
+    assert_eq!(rd.bytes_read() as usize, events_len);
+}
+
+#[test]
+fn streaming_v14_well_known_objects() {
+    let rd = RecorderData::find(&mut open_trace_file(TRACE_V14)).unwrap();
+    let tz_ctrl = rd
+        .entry_table
+        .symbol_handle(TZ_CTRL_TASK_NAME, Some(ObjectClass::Task))
+        .unwrap();
+    assert!(rd.entry_table.symbol(ObjectHandle::NO_TASK).is_some());
+
+    let rd = rd.with_well_known_objects(WellKnownObjects::empty());
+    assert_eq!(rd.entry_table.class(tz_ctrl), None);
+    assert!(rd.entry_table.symbol(ObjectHandle::NO_TASK).is_none());
+
+    let rd = RecorderData::find(&mut open_trace_file(TRACE_V14))
+        .unwrap()
+        .with_well_known_objects(WellKnownObjects::empty().with_handle(tz_ctrl, ObjectClass::Isr));
+    assert_eq!(rd.entry_table.class(tz_ctrl), Some(ObjectClass::Isr));
+}
+
+#[test]
+fn streaming_v14_session_info() {
+    use trace_recorder_parser::session::SessionInfo;
+
+    let mut f = open_trace_file(TRACE_V14);
+    let mut rd = RecorderData::find(&mut f).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut session = SessionInfo::from_streaming(&rd);
+    let mut timestamps = Vec::new();
+    while let Ok(Some((_, event))) = rd.read_event(&mut f) {
+        session.process_streaming_event(&event);
+        timestamps.push(event.timestamp().ticks());
+    }
+    assert_eq!(session.platform_cfg.as_deref(), Some("FreeRTOS"));
+    assert_eq!(session.kernel_port, KernelPortIdentity::FreeRtos);
+    assert_eq!(session.timer_frequency.get_raw(), 1_000_000);
+    assert_eq!(session.os_tick_rate.map(|f| f.get_raw()), Some(1000));
+    assert_eq!(session.num_cores, 1);
+    assert!(session.start_task.is_some());
+    assert_eq!(
+        session.duration.ticks(),
+        timestamps.last().unwrap() - timestamps.first().unwrap()
+    );
+    let header = session.to_string();
+    assert!(header.starts_with("protocol: streaming\nplatform_cfg: FreeRTOS\n"));
+    assert!(header.lines().last().unwrap().starts_with("duration: "));
+}
+
+#[test]
+fn streaming_v14_timestamp_repair() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+    let mut cursor = std::io::Cursor::new(bytes.as_slice());
+    let mut rd = RecorderData::find(&mut cursor).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut starts = vec![cursor.position() as usize];
+    let mut timestamps = Vec::new();
+    while let Ok(Some((_, event))) = rd.read_event(&mut cursor) {
+        starts.push(cursor.position() as usize);
+        timestamps.push(event.timestamp());
+    }
+    // Send the timestamp of the 10th event back in time
+    let corrupt = starts[9] + 4;
+    bytes[corrupt..corrupt + 4].copy_from_slice(&0_u32.to_le_bytes());
+
+    let mut r = bytes.as_slice();
+    let mut rd = RecorderData::find(&mut r).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    rd.enable_timestamp_repair(TimestampRepair::default());
+    let mut repaired = Vec::new();
+    while let Ok(Some((_, event))) = rd.read_event(&mut r) {
+        repaired.push(event.timestamp());
+    }
+    assert_eq!(repaired.len(), timestamps.len());
+    assert_eq!(repaired[9], timestamps[8]);
+    assert_eq!(repaired[..9], timestamps[..9]);
+    assert_eq!(repaired[10..], timestamps[10..]);
+    assert_eq!(rd.timestamp_repair().unwrap().adjustments(), 1);
+    let diagnostic = rd.diagnostics().last().unwrap();
+    assert_eq!(diagnostic.offset, Some((starts[9] - starts[0]) as u64));
+    assert!(diagnostic.message.contains("not monotonic"));
+}
+
+#[test]
+fn streaming_v14_repair() {
+    let mut bytes = Vec::new();
+    open_trace_file(TRACE_V14).read_to_end(&mut bytes).unwrap();
+
+    // Leading junk and a trailing partial event
+    let mut damaged = vec![0xAB; 3];
+    damaged.extend_from_slice(&bytes);
+    damaged.extend_from_slice(&[0x01, 0x10, 0x00]);
+
+    let mut options = RepairOptions {
+        strip_restarts: false,
+        custom_printf_event_id: Some(0x0FA0.into()),
+    };
+    let mut out = Vec::new();
+    let stats = repair(damaged.as_slice(), &mut out, options).unwrap();
+    assert_eq!(out, bytes);
+    assert_eq!(stats.sessions, 2);
+    assert_eq!(stats.bytes_written, bytes.len() as u64);
+    assert_eq!(stats.bytes_dropped, 6);
+    assert!(stats.stop_reason.is_some());
+
+    // Only the first session, up to the restart
+    let mut out = Vec::new();
+    options.strip_restarts = true;
+    let stripped = repair(damaged.as_slice(), &mut out, options).unwrap();
+    assert_eq!(out, &bytes[..1456]);
+    assert!(stripped.stripped_restarts);
+    assert_eq!(stripped.sessions, 1);
+    assert!(stripped.events < stats.events);
+
+    // The repaired file reads to the end without errors
+    let mut r = out.as_slice();
+    let mut rd = RecorderData::find(&mut r).unwrap();
+    rd.set_custom_printf_event_id(0x0FA0.into());
+    let mut events = 0;
+    while rd.read_event(&mut r).unwrap().is_some() {
+        events += 1;
+    }
+    assert_eq!(events, stripped.events);
+}